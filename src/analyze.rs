@@ -0,0 +1,169 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::projection::project;
+use crate::schedule::{parse_schedule, translate};
+
+/// The seconds a hotspot is spread over per job that takes part in it.
+const SPREAD_PER_JOB_SECS: u64 = 10;
+
+/// The smallest jitter ever suggested for a job taking part in a hotspot.
+const MIN_SUGGESTED_JITTER_SECS: u64 = 30;
+
+/// `AnalyzedJob` is a structure that represents a job whose schedule takes part in a hotspot analysis.
+///
+/// # Fields
+///
+/// * `job` - A string that identifies the job, either its id or its position in the job file.
+/// * `cron` - A string that represents the cron schedule of the job.
+/// * `jitter` - An `Option<Duration>` that represents the jitter of the job. Jobs with a jitter are already spread out and never take part in hotspots.
+/// * `estimated_duration` - An `Option<Duration>` that represents the estimated duration of the runs of the job, if it has run.
+#[derive(Debug, Clone)]
+pub struct AnalyzedJob {
+    pub job: String,
+    pub cron: String,
+    pub jitter: Option<Duration>,
+    pub estimated_duration: Option<Duration>,
+}
+
+/// `Suggestion` is a structure that represents a way to move a job out of a hotspot.
+///
+/// # Fields
+///
+/// * `job` - A string that identifies the job the suggestion applies to.
+/// * `jitter` - A `Duration` that represents a jitter that spreads the executions of the job.
+/// * `staggered_cron` - An `Option<String>` that represents an equivalent cron expression shifted by a few seconds, if the expression can be shifted.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Suggestion {
+    pub job: String,
+    #[serde(with = "humantime_serde")]
+    pub jitter: Duration,
+    pub staggered_cron: Option<String>,
+}
+
+/// `Hotspot` is a structure that represents a time of day where many jobs fire simultaneously.
+///
+/// # Fields
+///
+/// * `time_of_day` - A string that represents the local time of day of the hotspot, formatted as `HH:MM:SS`.
+/// * `fires` - A `usize` that represents the number of executions starting at this time of day within the analyzed window.
+/// * `jobs` - A vector of strings that identify the jobs firing at this time of day.
+/// * `estimated_duration` - An `Option<Duration>` that represents how long the hotspot lasts, i.e. the longest estimated duration of the
+///   jobs firing. It is `None` if none of them has run yet.
+/// * `suggestions` - A vector of `Suggestion` instances that spread the hotspot, one for every job but the first.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Hotspot {
+    pub time_of_day: String,
+    pub fires: usize,
+    pub jobs: Vec<String>,
+    #[serde(with = "humantime_serde")]
+    pub estimated_duration: Option<Duration>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Detects the times of day where many jobs fire simultaneously.
+///
+/// The schedules of the jobs are projected over the given window, starting now.
+/// Every time of day where at least `threshold` distinct jobs fire is reported as a hotspot, most crowded first.
+/// Jobs with an invalid cron expression are ignored, `lint` reports them.
+///
+/// # Arguments
+///
+/// * `jobs` - A slice of `AnalyzedJob` instances that represent the jobs to be analyzed.
+/// * `window` - A `Duration` that represents how far ahead the schedules are projected.
+/// * `threshold` - A `usize` that represents the number of distinct jobs firing together that makes a hotspot.
+///
+/// # Returns
+///
+/// * `Vec<Hotspot>` - Returns the detected hotspots.
+pub fn analyze_hotspots(jobs: &[AnalyzedJob], window: Duration, threshold: usize) -> Vec<Hotspot> {
+    let from = Local::now();
+    let until =
+        from + chrono::Duration::from_std(window).unwrap_or_else(|_| chrono::Duration::days(365));
+    let mut times: BTreeMap<NaiveTime, (BTreeSet<usize>, usize)> = BTreeMap::new();
+    for (i, job) in jobs.iter().enumerate() {
+        if job.jitter.is_some_and(|jitter| !jitter.is_zero()) {
+            continue;
+        }
+        let Ok(schedule) = parse_schedule(&job.cron) else {
+            continue;
+        };
+        for tick in project(&schedule, from, until) {
+            let (firing, fires) = times.entry(tick.time()).or_default();
+            firing.insert(i);
+            *fires += 1;
+        }
+    }
+
+    let mut hotspots: Vec<_> = times
+        .into_iter()
+        .filter(|(_, (firing, _))| firing.len() >= threshold.max(2))
+        .map(|(time, (firing, fires))| {
+            let firing: Vec<_> = firing.into_iter().map(|i| &jobs[i]).collect();
+            Hotspot {
+                time_of_day: time.format("%H:%M:%S").to_string(),
+                fires,
+                jobs: firing.iter().map(|job| job.job.clone()).collect(),
+                estimated_duration: firing.iter().filter_map(|job| job.estimated_duration).max(),
+                suggestions: suggest(&firing),
+            }
+        })
+        .collect();
+    hotspots.sort_by_key(|hotspot| Reverse(hotspot.jobs.len()));
+    hotspots
+}
+
+/// Suggests how to spread the jobs firing at the same time of day.
+///
+/// The first job keeps its schedule, the others are shifted by evenly spaced offsets within the minute.
+///
+/// # Arguments
+///
+/// * `firing` - A slice of the jobs firing at the same time of day.
+///
+/// # Returns
+///
+/// * `Vec<Suggestion>` - Returns a suggestion for every job but the first.
+fn suggest(firing: &[&AnalyzedJob]) -> Vec<Suggestion> {
+    let count = firing.len() as u64;
+    let step = (60 / count).max(1);
+    let jitter = Duration::from_secs((count * SPREAD_PER_JOB_SECS).max(MIN_SUGGESTED_JITTER_SECS));
+    firing
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, job)| Suggestion {
+            job: job.job.clone(),
+            jitter,
+            staggered_cron: stagger_expression(&job.cron, i as u64 * step),
+        })
+        .collect()
+}
+
+/// Shifts a cron expression by a number of seconds within the minute.
+///
+/// Only expressions whose seconds field is a single number can be shifted, and only as long as they stay within the minute. Crontab
+/// expressions and macros are shifted from their second 0, so the shifted expression has a seconds field.
+///
+/// # Arguments
+///
+/// * `cron` - A string that represents the cron expression.
+/// * `offset` - A `u64` that represents the number of seconds to shift the expression by.
+///
+/// # Returns
+///
+/// * `Option<String>` - Returns the shifted expression, or `None` if the expression cannot be shifted.
+fn stagger_expression(cron: &str, offset: u64) -> Option<String> {
+    let cron = translate(cron).ok()?;
+    let mut fields: Vec<String> = cron.split_whitespace().map(String::from).collect();
+    let seconds = fields.first()?.parse::<u64>().ok()? + offset;
+    if seconds >= 60 {
+        return None;
+    }
+    fields[0] = seconds.to_string();
+    Some(fields.join(" "))
+}
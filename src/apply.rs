@@ -0,0 +1,275 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::chain::{find_cycle, ChainLink};
+use crate::job::{Job, JobInfo, JobOptions, Trigger};
+use crate::manifest::JobSpec;
+use crate::CronusResult;
+
+/// `PlanAction` is an enumeration that represents what `apply` does to a job to converge the live jobs to a job file.
+///
+/// # Variants
+///
+/// * `Add` - Represents adding a declared job that is not live yet.
+/// * `Update` - Represents replacing a live job whose declaration has changed, keeping its id and run history.
+/// * `Remove` - Represents deleting a live job that is no longer declared.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    Add,
+    Update,
+    Remove,
+}
+
+/// `FieldChange` is a structure that represents a field of a job declaration changed by an update.
+///
+/// # Fields
+///
+/// * `field` - A string that represents the name of the field, e.g. `cron`, `job` or `options.jitter`.
+/// * `from` - A `Value` that represents the live value of the field. It is `null` if the field is not set.
+/// * `to` - A `Value` that represents the declared value of the field. It is `null` if the field is not set.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+/// `PlanStep` is a structure that represents a single change of an apply plan.
+///
+/// # Fields
+///
+/// * `action` - A `PlanAction` that represents what is done to the job.
+/// * `key` - A string that represents the stable key of the job.
+/// * `id` - An `Option<String>` that represents the id of the live job. It is `None` for a job that has not been added yet.
+/// * `changes` - A vector of `FieldChange` instances that represent the fields changed by an update. It is empty for additions and removals.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct PlanStep {
+    pub action: PlanAction,
+    pub key: String,
+    pub id: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+/// `PlanSummary` is a structure that represents the number of changes of an apply plan by action.
+///
+/// # Fields
+///
+/// * `add` - A `usize` that represents the number of jobs to be added.
+/// * `update` - A `usize` that represents the number of jobs to be updated.
+/// * `remove` - A `usize` that represents the number of jobs to be removed.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct PlanSummary {
+    pub add: usize,
+    pub update: usize,
+    pub remove: usize,
+}
+
+/// `Plan` is a structure that represents the changes that converge the live jobs to a job file.
+///
+/// # Fields
+///
+/// * `summary` - A `PlanSummary` that represents the number of changes by action, so pipelines can gate on e.g. unexpected removals.
+/// * `steps` - A vector of `PlanStep` instances that represent the changes, in the order they are carried out.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Plan {
+    pub summary: PlanSummary,
+    pub steps: Vec<PlanStep>,
+}
+
+/// Plans the changes that converge the live jobs to the jobs declared in a job file.
+///
+/// Declared jobs and live jobs are matched by their stable key. Live jobs without a key are not managed by `apply` and are left alone.
+/// Removals come first, so the names they free can be taken by updated and added jobs, followed by updates and then additions.
+/// Jobs whose declaration has not changed do not appear in the plan.
+///
+/// # Arguments
+///
+/// * `declared` - A slice of `JobSpec` instances that represent the jobs declared in the job file.
+/// * `live` - A slice of `JobInfo` instances that represent the jobs registered on the service.
+///
+/// # Returns
+///
+/// * `CronusResult<Plan>` - Returns a `CronusResult` that contains the plan, or an error if a declared job has no key, a key is declared twice, a cron expression, a timezone a template, a resource limit, a user, a group or an idempotency key is invalid, or the declared jobs form a dependency cycle.
+pub fn plan_apply(declared: &[JobSpec], live: &[JobInfo]) -> CronusResult<Plan> {
+    let mut keys = HashSet::new();
+    for (i, spec) in declared.iter().enumerate() {
+        let key = spec.key.as_deref().ok_or(format!("Job #{i} has no key"))?;
+        if !keys.insert(key) {
+            return Err(format!("Job key `{key}` is declared more than once").into());
+        }
+        spec.validate().map_err(|e| format!("Job `{key}`: {e}"))?;
+    }
+    let managed: HashMap<_, _> = live
+        .iter()
+        .filter_map(|job| Some((job.options.key.as_deref()?, job)))
+        .collect();
+    check_cycles(declared, live, &managed)?;
+
+    let mut removals = Vec::new();
+    for job in live {
+        if let Some(key) = job.options.key.as_deref().filter(|key| !keys.contains(key)) {
+            removals.push(PlanStep {
+                action: PlanAction::Remove,
+                key: key.to_string(),
+                id: Some(job.id.clone()),
+                changes: Vec::new(),
+            });
+        }
+    }
+    let mut updates = Vec::new();
+    let mut additions = Vec::new();
+    for spec in declared {
+        let key = spec.key.clone().unwrap_or_default();
+        match managed.get(key.as_str()) {
+            Some(job) => {
+                // A job declaring no namespace is in the namespace it is applied in, like the live job.
+                let mut options = spec.options();
+                options.namespace = options.namespace.or_else(|| job.options.namespace.clone());
+                let changes = diff_fields(
+                    &declared_fields(&job.trigger, &job.job, &job.options),
+                    &declared_fields(&spec.trigger, &spec.job, &options),
+                );
+                if !changes.is_empty() {
+                    updates.push(PlanStep {
+                        action: PlanAction::Update,
+                        key,
+                        id: Some(job.id.clone()),
+                        changes,
+                    });
+                }
+            }
+            None => additions.push(PlanStep {
+                action: PlanAction::Add,
+                key,
+                id: None,
+                changes: Vec::new(),
+            }),
+        }
+    }
+    removals.sort_by(|a, b| a.key.cmp(&b.key));
+    let summary = PlanSummary {
+        add: additions.len(),
+        update: updates.len(),
+        remove: removals.len(),
+    };
+    Ok(Plan {
+        summary,
+        steps: removals
+            .into_iter()
+            .chain(updates)
+            .chain(additions)
+            .collect(),
+    })
+}
+
+/// Flattens a job declaration into its fields.
+///
+/// The trigger is a `cron`, an `after`, an `event`, an `rrule` or a `sun` field, and every execution setting is an `options.<name>` field.
+///
+/// # Arguments
+///
+/// * `trigger` - A reference to the `Trigger` of the job.
+/// * `job` - A reference to the `Job` itself.
+/// * `options` - A reference to the `JobOptions` of the job.
+///
+/// # Returns
+///
+/// * `BTreeMap<String, Value>` - Returns the values of the fields by name.
+fn declared_fields(trigger: &Trigger, job: &Job, options: &JobOptions) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    if let Ok(Value::Object(trigger)) = serde_json::to_value(trigger) {
+        fields.extend(trigger);
+    }
+    fields.insert(
+        "job".to_string(),
+        serde_json::to_value(job).unwrap_or_default(),
+    );
+    if let Ok(Value::Object(options)) = serde_json::to_value(options) {
+        fields.extend(
+            options
+                .into_iter()
+                .map(|(name, value)| (format!("options.{name}"), value)),
+        );
+    }
+    fields
+}
+
+/// Compares the fields of two job declarations.
+///
+/// # Arguments
+///
+/// * `live` - A reference to the fields of the live job.
+/// * `declared` - A reference to the fields of the declared job.
+///
+/// # Returns
+///
+/// * `Vec<FieldChange>` - Returns the fields whose values differ, sorted by name.
+fn diff_fields(
+    live: &BTreeMap<String, Value>,
+    declared: &BTreeMap<String, Value>,
+) -> Vec<FieldChange> {
+    let names: BTreeSet<_> = live.keys().chain(declared.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let from = live.get(name).cloned().unwrap_or_default();
+            let to = declared.get(name).cloned().unwrap_or_default();
+            (from != to).then(|| FieldChange {
+                field: name.clone(),
+                from,
+                to,
+            })
+        })
+        .collect()
+}
+
+/// Checks that the jobs declared in a job file do not form a dependency cycle once applied.
+///
+/// # Arguments
+///
+/// * `declared` - A slice of `JobSpec` instances that represent the jobs declared in the job file.
+/// * `live` - A slice of `JobInfo` instances that represent the jobs registered on the service.
+/// * `managed` - A reference to the live jobs managed by `apply`, by key.
+///
+/// # Returns
+///
+/// * `CronusResult<()>` - Returns `Ok(())` if there is no cycle, or an error describing the cycle.
+fn check_cycles(
+    declared: &[JobSpec],
+    live: &[JobInfo],
+    managed: &HashMap<&str, &JobInfo>,
+) -> CronusResult<()> {
+    let ids: Vec<_> = declared
+        .iter()
+        .map(|spec| {
+            let key = spec.key.as_deref().unwrap_or_default();
+            managed.get(key).map_or(key, |job| job.id.as_str())
+        })
+        .collect();
+    let links: Vec<_> = live
+        .iter()
+        .filter(|job| job.options.key.is_none())
+        .map(|job| ChainLink {
+            id: &job.id,
+            name: job.options.name.as_deref(),
+            after: job.trigger.after(),
+        })
+        .chain(declared.iter().zip(&ids).map(|(spec, id)| ChainLink {
+            id,
+            name: spec.options.name.as_deref(),
+            after: spec.trigger.after(),
+        }))
+        .collect();
+    for (spec, id) in declared.iter().zip(&ids) {
+        if spec.trigger.after().is_none() {
+            continue;
+        }
+        if let Some(cycle) = find_cycle(&links, id) {
+            return Err(format!("Dependency cycle: {}", cycle.join(" -> ")).into());
+        }
+    }
+    Ok(())
+}
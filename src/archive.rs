@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::follower::ServiceSnapshot;
+use crate::store::{parse_jobs, SCHEMA_VERSION};
+use crate::CronusResult;
+
+/// The version of the format of the archives written by this version of cronus, recorded in their manifest.
+pub const ARCHIVE_VERSION: u64 = 1;
+
+/// The size of a block of a tar archive: every header and every padded file content is a whole number of blocks.
+const BLOCK: usize = 512;
+
+/// Writes a snapshot of a service to a tar archive, replacing the file if it exists.
+///
+/// The archive holds a `manifest.json` with the version of its format, the time it was taken at and the position of the last event of
+/// the service, the jobs in `jobs.json`, written like a job store in clear so it can also be passed to `--store`, the recorded runs of
+/// every job in `runs.json` and the state of every job in `state.json`. It is a plain ustar archive, so it can be listed and extracted
+/// with `tar`.
+///
+/// # Arguments
+///
+/// * `file` - A path to the archive.
+/// * `snapshot` - A reference to the `ServiceSnapshot` to be archived.
+///
+/// # Returns
+///
+/// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if the archive is on disk, or an error if not.
+pub fn write(file: &Path, snapshot: &ServiceSnapshot) -> CronusResult<()> {
+    let taken_at = Utc::now().timestamp() as u64;
+    let manifest = json!({ "version": ARCHIVE_VERSION, "taken_at": taken_at, "seq": snapshot.seq });
+    let jobs = json!({ "schema": SCHEMA_VERSION, "jobs": snapshot.jobs });
+    let mut archive = Vec::new();
+    for (name, content) in [
+        ("manifest.json", manifest),
+        ("jobs.json", jobs),
+        ("runs.json", json!(snapshot.runs)),
+        ("state.json", json!(snapshot.state)),
+    ] {
+        let content = serde_json::to_vec_pretty(&content)?;
+        archive.extend_from_slice(&header(name, content.len(), taken_at));
+        archive.extend_from_slice(&content);
+        archive.resize(archive.len().next_multiple_of(BLOCK), 0);
+    }
+    // An archive ends with two empty blocks.
+    archive.resize(archive.len() + 2 * BLOCK, 0);
+    let mut written = File::create(file)?;
+    written.write_all(&archive)?;
+    written.sync_all()?;
+    Ok(())
+}
+
+/// Reads a snapshot of a service from a tar archive written by `write`.
+///
+/// # Arguments
+///
+/// * `file` - A path to the archive.
+///
+/// # Returns
+///
+/// * `CronusResult<ServiceSnapshot>` - Returns a `CronusResult` that contains the snapshot on success, or an error if the archive cannot
+///   be read, misses its manifest or its jobs, or is written in a newer format. Jobs written in an older schema of the job store are
+///   migrated like the jobs of a store.
+pub fn read(file: &Path) -> CronusResult<ServiceSnapshot> {
+    let mut archive = Vec::new();
+    File::open(file)?.read_to_end(&mut archive)?;
+    let mut files = entries(&archive)?;
+    let mut take = |name: &str| -> CronusResult<Option<Value>> {
+        files
+            .remove(name)
+            .map(|content| serde_json::from_slice(&content))
+            .transpose()
+            .map_err(|e| format!("Cannot read `{name}` of the archive: {e}").into())
+    };
+    let manifest = take("manifest.json")?.ok_or("The archive misses its `manifest.json`")?;
+    let version = manifest["version"].as_u64().unwrap_or_default();
+    if version > ARCHIVE_VERSION {
+        return Err(format!(
+            "The archive is written in version {version}, this version of cronus reads up to version {ARCHIVE_VERSION}"
+        )
+        .into());
+    }
+    let jobs = take("jobs.json")?.ok_or("The archive misses its `jobs.json`")?;
+    Ok(ServiceSnapshot {
+        seq: manifest["seq"].as_u64().unwrap_or_default(),
+        jobs: parse_jobs(jobs, None, "jobs.json")?,
+        runs: take("runs.json")?
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default(),
+        state: take("state.json")?
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default(),
+    })
+}
+
+/// Builds the ustar header of a regular file.
+///
+/// # Arguments
+///
+/// * `name` - A string that represents the name of the file, shorter than 100 bytes.
+/// * `size` - A `usize` that represents the size of the content of the file.
+/// * `mtime` - A `u64` that represents the time the file was last modified in Unix timestamp.
+///
+/// # Returns
+///
+/// * `[u8; BLOCK]` - Returns the header, with its checksum.
+fn header(name: &str, size: usize, mtime: u64) -> [u8; BLOCK] {
+    let mut header = [0; BLOCK];
+    let mut put = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    put(0, name.as_bytes());
+    put(100, b"0000600\0");
+    put(108, b"0000000\0");
+    put(116, b"0000000\0");
+    put(124, format!("{size:011o}\0").as_bytes());
+    put(136, format!("{mtime:011o}\0").as_bytes());
+    // The checksum is computed with its own field filled with spaces.
+    put(148, b"        ");
+    put(156, b"0");
+    put(257, b"ustar\0");
+    put(263, b"00");
+    let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+    header
+}
+
+/// Reads the regular files of a tar archive, skipping its directories, links and extended headers.
+///
+/// # Arguments
+///
+/// * `archive` - A byte slice that represents the archive.
+///
+/// # Returns
+///
+/// * `CronusResult<BTreeMap<String, Vec<u8>>>` - Returns a `CronusResult` that contains the content of every file by name, without a
+///   leading `./`, on success, or an error if the archive is truncated or malformed.
+fn entries(archive: &[u8]) -> CronusResult<BTreeMap<String, Vec<u8>>> {
+    let mut files = BTreeMap::new();
+    let mut offset = 0;
+    while let Some(header) = archive.get(offset..offset + BLOCK) {
+        if header.iter().all(|&byte| byte == 0) {
+            break;
+        }
+        let field = |range: std::ops::Range<usize>| {
+            let field = &header[range];
+            let end = field
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(field.len());
+            String::from_utf8_lossy(&field[..end]).into_owned()
+        };
+        let size = usize::from_str_radix(field(124..136).trim(), 8)
+            .map_err(|_| "The archive is not a tar archive")?;
+        let start = offset + BLOCK;
+        let content = archive
+            .get(start..start + size)
+            .ok_or("The archive is truncated")?;
+        if matches!(header[156], b'0' | 0) {
+            let name = field(0..100);
+            let name = name.strip_prefix("./").unwrap_or(&name);
+            files.insert(name.to_string(), content.to_vec());
+        }
+        offset = start + size.next_multiple_of(BLOCK);
+    }
+    Ok(files)
+}
@@ -0,0 +1,250 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+use nng::options::transport::ipc::PeerPid;
+use nng::options::Options;
+use nng::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::credentials::user_name;
+use crate::crypto::StoreKey;
+
+/// What the entries of an encrypted audit log are bound to, so they cannot be passed off as an entry of the journal of the store.
+const AUDIT_CONTEXT: &str = "cronus-audit";
+
+/// `Caller` is a structure that represents the process a command was received from, as reported by the command socket.
+///
+/// # Fields
+///
+/// * `pid` - An `Option<u64>` that represents the process id of the caller. It is `None` if the platform does not report it.
+/// * `uid` - An `Option<u64>` that represents the user id of the caller. It is `None` if the platform does not report it.
+/// * `user` - An `Option<String>` that represents the name of the user of the caller. It is `None` if the user id is unknown.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Caller {
+    pub pid: Option<u64>,
+    pub uid: Option<u64>,
+    pub user: Option<String>,
+}
+
+impl Caller {
+    /// Identifies the caller a command was received from.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - A mutable reference to the `Message` the command was received in.
+    ///
+    /// # Returns
+    ///
+    /// * `Caller` - Returns the caller, with the ids the command socket reports.
+    pub fn of(msg: &mut Message) -> Self {
+        let Some(pid) = msg.pipe().and_then(|pipe| pipe.get_opt::<PeerPid>().ok()) else {
+            return Self::default();
+        };
+        // The pipes of nng cannot read the user id of their peer, but the process directory of the peer belongs to its user.
+        let uid = std::fs::metadata(format!("/proc/{pid}"))
+            .ok()
+            .map(|metadata| u64::from(metadata.uid()));
+        Self {
+            pid: Some(pid),
+            uid,
+            user: uid
+                .and_then(|uid| u32::try_from(uid).ok())
+                .and_then(user_name),
+        }
+    }
+}
+
+/// `AuditEntry` is a structure that represents a management command received by the service, as recorded in its audit log.
+///
+/// # Fields
+///
+/// * `at` - A `u64` that represents the time the command was answered in Unix timestamp.
+/// * `caller` - A `Caller` that represents the process the command was received from.
+/// * `job_id` - An `Option<String>` that represents the id of the job the command is about, including the job it added. It is `None` if
+///   the command is about the service.
+/// * `command` - A `Value` that represents the command, as it was received.
+/// * `response` - A `Value` that represents the response of the service, as the command line prints it.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub at: u64,
+    #[serde(flatten)]
+    pub caller: Caller,
+    pub job_id: Option<String>,
+    pub command: Value,
+    pub response: Value,
+}
+
+/// `AuditLog` is a structure that represents the append-only audit log of a service, a file with one `AuditEntry` per line.
+///
+/// With a key, every line is encrypted, as the commands adding, updating or restoring jobs hold their definitions, along with the
+/// tokens and connection strings they often hold.
+///
+/// # Fields
+///
+/// * `file` - A `PathBuf` that represents the path of the audit file, created on the first entry if it does not exist.
+/// * `key` - An `Option<StoreKey>` that represents the key the entries are encrypted with, the key of the job store. It is `None` if
+///   they are written in clear.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    file: PathBuf,
+    key: Option<StoreKey>,
+}
+
+impl AuditLog {
+    /// Creates the audit log kept in a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - A `PathBuf` that represents the path of the audit file.
+    /// * `key` - An `Option<StoreKey>` that represents the key the entries are encrypted with. It is `None` if they are written in clear.
+    ///
+    /// # Returns
+    ///
+    /// * `AuditLog` - Returns the audit log.
+    pub fn new(file: PathBuf, key: Option<StoreKey>) -> Self {
+        Self { file, key }
+    }
+
+    /// Appends an entry to the audit log, encrypted if the log has a key, and flushed to disk before it returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - A reference to the `AuditEntry` to be appended.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<()>` - Returns `Ok(())` if the entry was appended, or an error if the audit file cannot be written.
+    pub fn append(&self, entry: &AuditEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        if let Some(key) = &self.key {
+            line = key
+                .seal(AUDIT_CONTEXT, line.as_bytes())
+                .map_err(io::Error::other)?;
+        }
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file)?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()
+    }
+
+    /// Reads the most recent entries of the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `accept` - A function that tells the entries read, e.g. the entries about one job.
+    /// * `limit` - A `usize` that represents the maximum number of entries read.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<Vec<AuditEntry>>` - Returns the most recent entries, oldest first, or an error if the audit file cannot be read. An
+    ///   audit log with no file yet has no entries, and a line that cannot be parsed, e.g. one cut short by a crash, is skipped, as is a
+    ///   line that cannot be decrypted with the key of the log, e.g. written in clear before the log had a key.
+    pub fn read(
+        &self,
+        accept: impl Fn(&AuditEntry) -> bool,
+        limit: usize,
+    ) -> io::Result<Vec<AuditEntry>> {
+        let file = match File::open(&self.file) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let parsed = match &self.key {
+                Some(key) => key
+                    .open(AUDIT_CONTEXT, &line)
+                    .ok()
+                    .and_then(|line| serde_json::from_slice::<AuditEntry>(&line).ok()),
+                None => serde_json::from_str::<AuditEntry>(&line).ok(),
+            };
+            let Some(entry) = parsed else {
+                continue;
+            };
+            if accept(&entry) {
+                entries.push(entry);
+            }
+        }
+        let skipped = entries.len().saturating_sub(limit);
+        entries.drain(..skipped);
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cronus-{}-{name}", Uuid::new_v4()))
+    }
+
+    fn key() -> StoreKey {
+        let file = temp_file("key");
+        std::fs::write(&file, "11".repeat(32)).unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let key = StoreKey::load(&file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        key
+    }
+
+    fn entry(job_id: &str) -> AuditEntry {
+        AuditEntry {
+            at: 1,
+            caller: Caller::default(),
+            job_id: Some(job_id.to_string()),
+            command: json!({ "command": "add_job", "token": "s3cr3t" }),
+            response: json!({ "created": true }),
+        }
+    }
+
+    #[test]
+    fn entries_are_sealed_with_the_key() {
+        let file = temp_file("audit");
+        let audit = AuditLog::new(file.clone(), Some(key()));
+        audit.append(&entry("a")).unwrap();
+        audit.append(&entry("b")).unwrap();
+        let written = std::fs::read_to_string(&file).unwrap();
+        assert!(!written.contains("s3cr3t"));
+        assert_eq!(
+            audit.read(|_| true, 10).unwrap(),
+            vec![entry("a"), entry("b")]
+        );
+        assert_eq!(
+            audit
+                .read(|entry| entry.job_id.as_deref() == Some("b"), 10)
+                .unwrap(),
+            vec![entry("b")]
+        );
+        assert!(AuditLog::new(file.clone(), None)
+            .read(|_| true, 10)
+            .unwrap()
+            .is_empty());
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn clear_entries_are_not_listed_with_a_key() {
+        let file = temp_file("audit");
+        AuditLog::new(file.clone(), None)
+            .append(&entry("a"))
+            .unwrap();
+        let audit = AuditLog::new(file.clone(), Some(key()));
+        audit.append(&entry("b")).unwrap();
+        assert_eq!(audit.read(|_| true, 10).unwrap(), vec![entry("b")]);
+        assert_eq!(audit.read(|_| true, 0).unwrap(), vec![]);
+        std::fs::remove_file(file).unwrap();
+    }
+}
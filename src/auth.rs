@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::namespace::{validate_namespace, Scope};
+
+/// The environment variable holding the token a client presents to the service.
+pub const TOKEN_VAR: &str = "CRONUS_TOKEN";
+
+/// `Role` is an enumeration that represents what the holder of a token may do on the service.
+///
+/// # Variants
+///
+/// * `Viewer` - Represents a role that may only read the state of the service, e.g. list jobs or their history.
+/// * `Operator` - Represents a role that may also change and run jobs, see `Command::mutates_jobs`.
+/// * `Admin` - Represents a role that may also restore a snapshot into the service, promote it, reload its configuration or stop it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    /// Checks whether the role may send a command.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd` - A reference to the `Command` sent.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the role may send the command, and `false` if not.
+    pub fn permits(self, cmd: &Command) -> bool {
+        match self {
+            Self::Admin => true,
+            Self::Operator => !matches!(
+                cmd,
+                Command::Restore { .. }
+                    | Command::Promote
+                    | Command::ReloadConfig
+                    | Command::StopService
+            ),
+            Self::Viewer => !cmd.is_audited(),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Viewer => write!(f, "viewer"),
+            Self::Operator => write!(f, "operator"),
+            Self::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// `AuthToken` is a structure that represents a token accepted by the service, declared as a `[tokens.<name>]` table.
+///
+/// # Fields
+///
+/// * `token` - A string that represents the secret the client presents.
+/// * `role` - A `Role` that represents what the holder of the token may do.
+/// * `namespace` - An `Option<String>` that represents the namespace the holder works in when it names none. A viewer or an operator
+///   is confined to it, while an admin may name any namespace. It is `None` for the `default` namespace.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct AuthToken {
+    pub token: String,
+    pub role: Role,
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+/// Checks that the tokens of a configuration can be told apart.
+///
+/// # Arguments
+///
+/// * `tokens` - A reference to the tokens accepted by the service, by name.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if every token is set and unique and names a valid namespace, if any, or an error message
+///   if not.
+pub fn validate_tokens(tokens: &BTreeMap<String, AuthToken>) -> Result<(), String> {
+    for (name, auth) in tokens {
+        if auth.token.is_empty() {
+            return Err(format!("Token `{name}` is empty"));
+        }
+        if let Some(namespace) = &auth.namespace {
+            validate_namespace(namespace).map_err(|e| format!("Token `{name}`: {e}"))?;
+        }
+        if let Some((other, _)) = tokens
+            .iter()
+            .find(|(other, other_auth)| *other < name && other_auth.token == auth.token)
+        {
+            return Err(format!("Tokens `{other}` and `{name}` are the same"));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that a client may send a command to the service.
+///
+/// A service with no tokens accepts every command, as before tokens existed. Otherwise, the command must come with a token of the
+/// service whose role permits it.
+///
+/// # Arguments
+///
+/// * `tokens` - A reference to the tokens accepted by the service, by name.
+/// * `token` - An `Option<&str>` that represents the token the client presented. It is `None` if the client presented none.
+/// * `cmd` - A reference to the `Command` sent.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the command is permitted, or an error message describing why it is forbidden.
+pub fn authorize(
+    tokens: &BTreeMap<String, AuthToken>,
+    token: Option<&str>,
+    cmd: &Command,
+) -> Result<(), String> {
+    if tokens.is_empty() {
+        return Ok(());
+    }
+    let (name, auth) = find_token(tokens, token)?;
+    if auth.role.permits(cmd) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Token `{name}` has the {} role, which does not permit this command",
+            auth.role
+        ))
+    }
+}
+
+/// Resolves the namespaces a client works on, see `Scope`.
+///
+/// A service with no tokens trusts the namespace every client names, or else puts it in the `default` namespace. Otherwise, a client
+/// naming none works in the namespace of its token, and a viewer or an operator may only name the namespace of its token, while an
+/// admin may name any namespace, or `*` for every namespace.
+///
+/// # Arguments
+///
+/// * `tokens` - A reference to the tokens accepted by the service, by name.
+/// * `token` - An `Option<&str>` that represents the token the client presented. It is `None` if the client presented none.
+/// * `namespace` - An `Option<&str>` that represents the namespace the client named. It is `None` if it named none.
+///
+/// # Returns
+///
+/// * `Result<Scope, String>` - Returns the scope of the client, or an error message describing why it may not work in the namespace it
+///   named.
+pub fn scope(
+    tokens: &BTreeMap<String, AuthToken>,
+    token: Option<&str>,
+    namespace: Option<&str>,
+) -> Result<Scope, String> {
+    let named = namespace.map(Scope::named).transpose()?;
+    if tokens.is_empty() {
+        return Ok(named.unwrap_or_default());
+    }
+    let (name, auth) = find_token(tokens, token)?;
+    let home = auth
+        .namespace
+        .as_deref()
+        .map_or_else(Scope::default, |namespace| {
+            Scope::Namespace(namespace.to_string())
+        });
+    match named {
+        Some(named) if named != home && auth.role != Role::Admin => Err(format!(
+            "Token `{name}` has the {} role, which is confined to namespace `{home}`",
+            auth.role
+        )),
+        named => Ok(named.unwrap_or(home)),
+    }
+}
+
+/// Finds the token a client presented among the tokens of the service.
+///
+/// # Arguments
+///
+/// * `tokens` - A reference to the tokens accepted by the service, by name.
+/// * `token` - An `Option<&str>` that represents the token the client presented. It is `None` if the client presented none.
+///
+/// # Returns
+///
+/// * `Result<(&String, &AuthToken), String>` - Returns the name and the declaration of the token, or an error message if the client
+///   presented none, or one the service does not accept.
+fn find_token<'a>(
+    tokens: &'a BTreeMap<String, AuthToken>,
+    token: Option<&str>,
+) -> Result<(&'a String, &'a AuthToken), String> {
+    let token = token.ok_or_else(|| format!("A token is required, set {TOKEN_VAR}"))?;
+    tokens
+        .iter()
+        .find(|(_, auth)| same_secret(&auth.token, token))
+        .ok_or_else(|| "Unknown token".to_string())
+}
+
+/// Compares two secrets in a time that does not depend on where they differ, so a client cannot guess a token byte by byte.
+///
+/// # Arguments
+///
+/// * `expected` - A string that represents the secret of the service.
+/// * `given` - A string that represents the secret presented by the client.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` if the secrets are the same.
+fn same_secret(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
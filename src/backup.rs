@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::StoreKey;
+use crate::hooks::Hook;
+use crate::store::{write_jobs, StoredJob};
+
+/// The number of snapshots kept by default.
+const DEFAULT_KEEP: usize = 7;
+
+/// `BackupConfig` is a structure that represents how the service backs up its own state, declared as a `[backup]` table.
+///
+/// # Fields
+///
+/// * `dir` - A `PathBuf` that represents the directory the snapshots are written to, e.g. a mounted network share.
+/// * `interval` - A `Duration` that represents how often a snapshot is taken, e.g. `1h`.
+/// * `keep` - A `usize` that represents the number of most recent snapshots kept in the directory, older ones being deleted. It is 7 by default.
+/// * `upload` - An `Option<Hook>` that represents a job run after every snapshot, e.g. to sync the directory to a remote target. It is `None` if the snapshots are only kept in the directory.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct BackupConfig {
+    pub dir: PathBuf,
+    #[serde(with = "humantime_serde")]
+    pub interval: Duration,
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+    pub upload: Option<Hook>,
+}
+
+/// Returns the number of snapshots kept by default.
+///
+/// # Returns
+///
+/// * `usize` - Returns `DEFAULT_KEEP`.
+fn default_keep() -> usize {
+    DEFAULT_KEEP
+}
+
+/// Takes a snapshot of the jobs of a service, then deletes the oldest snapshots of the service beyond the retention.
+///
+/// A snapshot is written like a job store, to `<name>-<timestamp>.json` in the directory, e.g. `cronus-20240102T030405Z.json`, so it can
+/// be restored by starting the service with `--store` pointing to a copy of it, and encrypted like the job store if it has a key.
+///
+/// # Arguments
+///
+/// * `config` - A reference to the `BackupConfig` of the service.
+/// * `name` - A string that represents the name of the service, prefixed to its snapshots so services can share a directory.
+/// * `jobs` - A slice of `StoredJob` instances that represent the jobs of the service.
+/// * `key` - An `Option<&StoreKey>` that represents the key the snapshot is encrypted with. It is `None` if it is written in clear.
+///
+/// # Returns
+///
+/// * `Result<PathBuf, String>` - Returns the path of the snapshot, or an error message if it cannot be written or the old snapshots cannot be deleted.
+pub fn snapshot(
+    config: &BackupConfig,
+    name: &str,
+    jobs: &[StoredJob],
+    key: Option<&StoreKey>,
+) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(&config.dir)
+        .map_err(|e| format!("Cannot create {}: {e}", config.dir.display()))?;
+    let file = config.dir.join(format!(
+        "{name}-{}.json",
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    write_jobs(&file, jobs, key).map_err(|e| format!("Cannot write {}: {e}", file.display()))?;
+    prune(&config.dir, name, config.keep.max(1))
+        .map_err(|e| format!("Cannot delete old snapshots: {e}"))?;
+    Ok(file)
+}
+
+/// Deletes the oldest snapshots of a service beyond the retention.
+///
+/// # Arguments
+///
+/// * `dir` - A path to the directory of the snapshots.
+/// * `name` - A string that represents the name of the service.
+/// * `keep` - A `usize` that represents the number of most recent snapshots kept.
+///
+/// # Returns
+///
+/// * `std::io::Result<()>` - Returns `Ok(())` if the snapshots beyond the retention were deleted, or an error if not.
+fn prune(dir: &Path, name: &str, keep: usize) -> std::io::Result<()> {
+    let prefix = format!("{name}-");
+    let mut snapshots = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let file_name = entry?.file_name();
+        let file_name = file_name.to_string_lossy();
+        let is_snapshot = file_name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".json"))
+            .is_some_and(|timestamp| {
+                chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%dT%H%M%SZ").is_ok()
+            });
+        if is_snapshot {
+            snapshots.push(file_name.into_owned());
+        }
+    }
+    // Timestamps sort chronologically, so the oldest snapshots come first.
+    snapshots.sort();
+    for snapshot in &snapshots[..snapshots.len().saturating_sub(keep)] {
+        std::fs::remove_file(dir.join(snapshot))?;
+    }
+    Ok(())
+}
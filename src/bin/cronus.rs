@@ -1,15 +1,94 @@
+use std::net::SocketAddr;
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use fork::{daemon, Fork};
 use serde_json::json;
+use structopt::clap::{AppSettings, Shell};
 use structopt::StructOpt;
 use uuid::Uuid;
 
+use cronus::analyze::{analyze_hotspots, AnalyzedJob};
+use cronus::apply::{plan_apply, PlanAction};
+use cronus::archive;
+use cronus::calendar::HolidayPolicy;
+use cronus::chain::{find_cycle, ChainLink};
+use cronus::chaos::Chaos;
 use cronus::command::{CommandClient, CommandResponse};
-use cronus::job::Job;
+use cronus::completions::{self, COMPLETE_JOBS};
+use cronus::config::ServiceConfig;
+use cronus::context::Contexts;
+use cronus::controller::{self, ControllerConfig};
+use cronus::crypto::StoreKey;
+use cronus::doctor::{self, Environment};
+use cronus::dst::DstPolicy;
+use cronus::error_code::ErrorCode;
+use cronus::hooks::SYSTEM_RUNS;
+use cronus::http_client;
+use cronus::job::{
+    CommandStdin, ExpiryAction, Job, JobOptions, OverlapPolicy, Priority, RunId, Trigger,
+};
+use cronus::label::parse_label;
+use cronus::limits::parse_size;
+use cronus::lint::lint_job;
+use cronus::listing::{JobFilter, JobSort};
+use cronus::man;
+use cronus::manifest::{load_job_specs, JobSpec};
+use cronus::notify::NotifyEvent;
+use cronus::pipeline::JobStep;
+use cronus::recurrence::parse_rrule;
+use cronus::sandbox::Sandbox;
+use cronus::schedule::{self, Shorthand, TimeOfDay, Weekdays};
 use cronus::scheduler::CronusScheduler;
+use cronus::secret::{parse_secret, SecretSource};
+use cronus::solar::{SunSchedule, SunTime};
+use cronus::store;
+use cronus::supervisor::{self, SupervisorConfig};
+use cronus::table::{render, OutputFormat};
+use cronus::template;
+use cronus::timeline::render_svg;
+use cronus::top;
+use cronus::tz::{list_zones, parse_timezone};
+use cronus::workflow::load_workflow;
 use cronus::CronusResult;
 
+/// The name the CLI is run as, in its completion scripts and its man page.
+const BIN_NAME: &str = "cronus";
+
+/// How often `tail --follow` polls the Cronus service for new lines of output.
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The `Cli` struct.
+///
+/// This struct represents the command line of the Cronus task execution manager: the command, along with the options of every command.
+///
+/// # Fields
+///
+/// * `context` - The context of the contexts file the command is sent to, instead of the service of its name and path, if any.
+/// * `namespace` - The namespace the command works in, instead of the namespace of the context, of `CRONUS_NAMESPACE` or of the token, if any.
+/// * `command` - The command.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "Cronus", about = "Scheduled task execution manager")]
+struct Cli {
+    #[structopt(
+        long,
+        long_help = "Context of the contexts file, ~/.config/cronus/contexts.toml or the file named by CRONUS_CONTEXTS, the command is sent to, e.g. prod for [contexts.prod], instead of the service of its name and path: a service or a controller, on this host or reached over TCP"
+    )]
+    context: Option<String>,
+
+    #[structopt(
+        long,
+        long_help = "Namespace the command works in, e.g. data: only the jobs of the namespace are listed, changed and added to, instead of the namespace of the context, of CRONUS_NAMESPACE or of the token. An admin token may also name any namespace, or * for every namespace"
+    )]
+    namespace: Option<String>,
+
+    #[structopt(subcommand)]
+    command: Command,
+}
+
 /// The `Command` enum.
 ///
 /// This enum represents the different commands that the Cronus task execution manager can handle.
@@ -19,13 +98,45 @@ use cronus::CronusResult;
 ///
 /// * `Start` - Starts the Cronus service.
 /// * `Stop` - Stops the Cronus service.
+/// * `Reload` - Reloads the configuration file of the Cronus service.
+/// * `Promote` - Promotes a Cronus service following another one, so it runs the mirrored cron jobs.
+/// * `Snapshot` - Archives the jobs, the run history and the state of the jobs of the Cronus service.
+/// * `Restore` - Restores an archive of `Snapshot` into a Cronus service without jobs.
 /// * `Add` - Adds a cron job to the Cronus service.
 /// * `Delete` - Deletes a cron job from the Cronus service.
 /// * `List` - Lists the cron jobs on the Cronus service.
+/// * `Get` - Describes a cron job on the Cronus service, with the result of its last run.
+/// * `History` - Lists the recorded runs of a cron job on the Cronus service.
+/// * `Lint` - Lints the cron jobs on the Cronus service, or the jobs declared in a job file.
+/// * `Analyze` - Detects times of day where many cron jobs fire simultaneously.
+/// * `Timeline` - Lays out the past and projected executions of the cron jobs on the Cronus service as a Gantt chart.
+/// * `Ack` - Acknowledges the last failure of a cron job on the Cronus service.
+/// * `Events` - Lists the most recent events of the Cronus service.
+/// * `Audit` - Lists the most recent management commands received by the Cronus service, from its audit log.
+/// * `Top` - Shows a live dashboard of the cron jobs on the Cronus service in the terminal.
+/// * `Trigger` - Runs a cron job on the Cronus service right away.
+/// * `Exec` - Runs a command on the Cronus service right away without scheduling it, printing its output and its run.
+/// * `Pause` - Pauses a cron job on the Cronus service.
+/// * `Resume` - Resumes a paused cron job on the Cronus service.
+/// * `Kill` - Kills the runs of a cron job in flight on the Cronus service.
+/// * `Tail` - Prints the output of the run of a cron job on the Cronus service started last, following it while it is in flight.
+/// * `Emit` - Emits an event firing the cron jobs listening on its topic.
+/// * `Stats` - Reports the machine time consumed by cron jobs on the Cronus service by day and by month.
+/// * `Digest` - Reports the cron jobs consuming the most machine time on the Cronus service today and this month.
+/// * `Tz` - Inspects the timezone database cron jobs are scheduled with.
+/// * `Apply` - Converges the cron jobs on the Cronus service to the jobs declared in a job file.
+/// * `Batch` - Carries out the commands of a batch file on the Cronus service, all of them or none of them.
 /// * `Run` - Runs the Cronus service.
+/// * `Supervise` - Runs several Cronus services in one process.
+/// * `Controller` - Runs a controller managing the cron jobs of several Cronus services at once.
 /// * `Ping` - Pings the Cronus service.
+/// * `EncryptStore` - Encrypts the job store of a stopped Cronus service written in clear, once.
+/// * `Doctor` - Diagnoses the environment of the Cronus service, from the socket and the clock to the job store.
+/// * `Completions` - Generates the completion script of the CLI for a shell.
+/// * `Man` - Generates the man page of the CLI.
+/// * `CompleteJobs` - Lists the ids or the names of the cron jobs on the Cronus service, one per line, for the completion scripts.
 #[derive(StructOpt, Debug)]
-#[structopt(name = "Cronus", about = "Scheduled task execution manager")]
+#[allow(clippy::large_enum_variant)]
 enum Command {
     #[structopt(about = "Start cronus service")]
     Start {
@@ -44,6 +155,67 @@ enum Command {
             long_help = "Cronus service command acceptance path"
         )]
         path: PathBuf,
+
+        #[structopt(
+            long,
+            long_help = "Address the HTTP API of cronus service listens on, e.g. 127.0.0.1:8080"
+        )]
+        http_addr: Option<SocketAddr>,
+        #[structopt(
+            long,
+            long_help = "Address the command socket of cronus service also listens on over TCP, e.g. 0.0.0.0:7070, so it is managed from other hosts, e.g. as an agent of a controller. Declare tokens in its configuration file, as anyone reaching the address may send commands"
+        )]
+        tcp_addr: Option<SocketAddr>,
+        #[structopt(
+            long,
+            long_help = "Address the health probes of cronus service are served on over HTTP, e.g. 0.0.0.0:8086: GET /healthz answers 200 while the service is alive and GET /readyz while it takes commands and its job store can be written, or else 503, along with the state of the service as JSON"
+        )]
+        health_addr: Option<SocketAddr>,
+        #[structopt(
+            long,
+            long_help = "Number of workers running jobs, i.e. maximum number of runs of all jobs in flight at once, beyond which runs are queued by priority or skipped according to the overlap policy of their job [default: 64]"
+        )]
+        max_parallel: Option<NonZeroUsize>,
+        #[structopt(
+            long,
+            long_help = "Configuration file of cronus service, a TOML file or else a JSON file, declaring the hooks run when it starts, stops and reloads"
+        )]
+        config: Option<PathBuf>,
+        #[structopt(
+            long,
+            long_help = "Job store of cronus service, a JSON file its jobs are kept in across restarts, migrated on startup if written by an older release [default: jobs are kept in memory]"
+        )]
+        store: Option<PathBuf>,
+        #[structopt(
+            long,
+            long_help = "Key file the job store and the backups of cronus service are encrypted with, holding 32 bytes or their 64 hex digits, e.g. written by openssl rand -hex 32, and readable by its owner only [default: they are written in clear]"
+        )]
+        store_key: Option<PathBuf>,
+        #[structopt(
+            long,
+            requires = "store",
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "Window of the lease on the job store shared with another cronus service, e.g. 10s: only the service holding the lease runs the jobs, while the other one waits, and takes over once the lease has not been renewed for a whole window [default: the job store is not shared]"
+        )]
+        lease_window: Option<Duration>,
+        #[structopt(
+            long,
+            conflicts_with = "follow",
+            long_help = "Job file, like the one given to apply --file, the jobs with a key of cronus service are kept converged to: it is read again every 5 seconds, and the jobs it declares are added, updated and those it no longer declares removed, each emitting a job_reconciled event [default: jobs are only changed by commands]"
+        )]
+        reconcile: Option<PathBuf>,
+        #[structopt(
+            long,
+            conflicts_with = "store",
+            long_help = "Name of the cronus service to follow, with the same command acceptance path: its jobs and run history are mirrored read-only, without running the jobs, until the service is promoted with promote"
+        )]
+        follow: Option<String>,
+        #[structopt(
+            long,
+            hidden = true,
+            long_help = "Developer mode injecting failures at the given rates, e.g. delay=0.2,max-delay=10s,fail=0.1,drop=0.05: ticks delayed up to max-delay, runs failed instead of being carried out, and commands dropped without being answered"
+        )]
+        chaos: Option<Chaos>,
     },
     #[structopt(about = "Stop cronus service")]
     Stop {
@@ -63,8 +235,8 @@ enum Command {
         )]
         path: PathBuf,
     },
-    #[structopt(about = "Add a cron job to cronus service")]
-    Add {
+    #[structopt(about = "Reload the configuration file of cronus service")]
+    Reload {
         #[structopt(
             short,
             long,
@@ -80,19 +252,29 @@ enum Command {
             long_help = "Cronus service command acceptance path"
         )]
         path: PathBuf,
-
+    },
+    #[structopt(
+        about = "Promote cronus service following another one, so it runs the mirrored cron jobs"
+    )]
+    Promote {
         #[structopt(
             short,
             long,
-            long_help = "Corn expression for the job to be added to cronus service"
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
         )]
-        corn: String,
+        name: String,
 
-        #[structopt(subcommand)]
-        sub_cmd: AddSubCommand,
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
     },
-    #[structopt(about = "Delete cron job from cronus service")]
-    Delete {
+    #[structopt(about = "Archive the cron jobs, their runs and their state of cronus service")]
+    Snapshot {
         #[structopt(
             short,
             long,
@@ -110,14 +292,16 @@ enum Command {
         path: PathBuf,
 
         #[structopt(
-            short,
             long,
-            long_help = "Corn job id to be deleted from cronus service"
+            parse(from_os_str),
+            long_help = "Tar archive the snapshot is written to, e.g. state.tar"
         )]
-        id: String,
+        out: PathBuf,
     },
-    #[structopt(about = "List cron job on cronus service")]
-    List {
+    #[structopt(
+        about = "Restore an archive of cronus snapshot into cronus service without cron jobs"
+    )]
+    Restore {
         #[structopt(
             short,
             long,
@@ -133,9 +317,16 @@ enum Command {
             long_help = "Cronus service command acceptance path"
         )]
         path: PathBuf,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            long_help = "Tar archive written by cronus snapshot"
+        )]
+        from: PathBuf,
     },
-    #[structopt(about = "Run cronus service")]
-    Run {
+    #[structopt(about = "Add a cron job to cronus service")]
+    Add {
         #[structopt(
             short,
             long,
@@ -151,185 +342,2688 @@ enum Command {
             long_help = "Cronus service command acceptance path"
         )]
         path: PathBuf,
-    },
-    #[structopt(about = "Ping cronus service")]
-    Ping {
+
         #[structopt(
             short,
             long,
-            default_value = "cronus",
-            long_help = "Cronus service command acceptance name"
+            required_unless_one = &["after", "on-event", "rrule", "at-sun", "daily-at", "weekly-on", "monthly-on"],
+            long_help = "Corn expression for the job to be added to cronus service, or one of the macros @hourly, @daily, @weekly, @monthly and @yearly, or @reboot to run the job once every time cronus service starts"
         )]
-        name: String,
+        corn: Option<String>,
 
         #[structopt(
-            short,
             long,
-            default_value = "/tmp",
-            long_help = "Cronus service command acceptance path"
+            conflicts_with_all = &["corn", "after", "on-event", "rrule", "at-sun", "weekly-on", "monthly-on"],
+            long_help = "Time of the day the job runs at every day, instead of a corn expression, as HH:MM, e.g. 03:30"
         )]
-        path: PathBuf,
-    },
-}
+        daily_at: Option<TimeOfDay>,
 
-/// The `AddSubCommand` enum.
-///
-/// This enum represents the different subcommands that can be used with the `Add` command of the Cronus task execution manager.
-/// Each variant of the enum corresponds to a different subcommand, and contains the arguments for that subcommand.
-///
-/// # Variants
-///
-/// * `CmdJob` - Represents a command job. It contains the path to the command and the arguments for the command.
-/// * `RhaiJob` - Represents a Rhai job. It contains the Rhai script code.
-/// * `RhaiFileJob` - Represents a Rhai file job. It contains the path to the Rhai script file.
-#[derive(StructOpt, Debug)]
-enum AddSubCommand {
-    #[structopt(about = "Command Job")]
-    CmdJob {
-        #[structopt(short, long, parse(from_os_str), long_help = "Command path")]
-        cmd: PathBuf,
+        #[structopt(
+            long,
+            conflicts_with_all = &["corn", "after", "on-event", "rrule", "at-sun", "monthly-on"],
+            long_help = "Days of the week the job runs on every week, instead of a corn expression, separated by commas, e.g. mon or mon,wed,fri. It runs at midnight, or at the time given with --at"
+        )]
+        weekly_on: Option<Weekdays>,
 
-        #[structopt(short, long, long_help = "Command args")]
-        args: Vec<String>,
-    },
-    #[structopt(about = "Rhai Job")]
-    RhaiJob {
-        #[structopt(short, long, long_help = "Rhai script code")]
-        script: String,
-    },
-    #[structopt(about = "Rhai file Job")]
-    RhaiFileJob {
-        #[structopt(short, long, parse(from_os_str), long_help = "Rhai script file path")]
-        script_file: PathBuf,
-    },
-}
+        #[structopt(
+            long,
+            conflicts_with_all = &["corn", "after", "on-event", "rrule", "at-sun"],
+            parse(try_from_str = schedule::parse_day_of_month),
+            long_help = "Day of the month the job runs on every month, instead of a corn expression, from 1 to 31, skipping the months without that day. It runs at midnight, or at the time given with --at"
+        )]
+        monthly_on: Option<u32>,
 
-impl AddSubCommand {
-    /// Converts the `AddSubCommand` enum into a `Job`.
-    ///
-    /// This method is used to convert the different subcommands that can be used with the `Add` command of the Cronus task execution manager into a `Job`.
-    /// Each variant of the `AddSubCommand` enum corresponds to a different type of job, and this method will create a `Job` of the appropriate type based on the variant.
-    ///
-    /// # Returns
-    ///
-    /// * `Job` - The `Job` that corresponds to the `AddSubCommand`.
-    fn into_job(self) -> Job {
-        match self {
-            AddSubCommand::CmdJob { cmd, args } => Job::new_command(cmd, args),
-            AddSubCommand::RhaiJob { script } => Job::new_rhai_script(script),
-            AddSubCommand::RhaiFileJob { script_file } => Job::new_rhai_script_file(script_file),
-        }
-    }
-}
+        #[structopt(
+            long,
+            long_help = "Time of the day the job given with --weekly-on or --monthly-on runs at, as HH:MM, e.g. 07:00"
+        )]
+        at: Option<TimeOfDay>,
 
-/// Asynchronously runs the Cronus task execution manager.
-///
-/// This function matches the command line arguments to the corresponding command variant and executes the command.
-/// It handles all the commands that the Cronus task execution manager can process, including starting and stopping the service, adding and deleting jobs, listing jobs, and running the service.
-///
-/// # Returns
-///
-/// * `CronusResult<String>` - The result of running the command. If the command is executed successfully, it returns a `CronusResult::Ok(String)` where the `String` is a JSON string that represents the result of the command. If there is an error executing the command, it returns a `CronusResult::Err(CronusError)` where the `CronusError` represents the error that occurred.
-async fn run() -> CronusResult<String> {
-    let response = match Command::from_args() {
-        Command::Start { name, path } => {
-            if !check_service_running(name.clone(), path.clone())? {
-                run_new_service(name, path)?;
-            }
-            CommandResponse::ServiceRunning
-        }
-        Command::Stop { name, path } => {
-            let cc = CommandClient::new(name, path)?;
-            cc.stop_service()?
-        }
-        Command::Add {
-            name,
-            path,
-            corn,
-            sub_cmd,
-        } => {
-            let cc = CommandClient::new(name, path)?;
-            cc.add_job(corn, sub_cmd.into_job())?
-        }
-        Command::Delete { name, path, id } => {
-            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
-            let cc = CommandClient::new(name, path)?;
-            cc.delete_job(id)?
-        }
-        Command::List { name, path } => {
-            let cc = CommandClient::new(name, path)?;
-            cc.list_jobs()?
-        }
-        Command::Run { name, path } => {
-            let scheduler = CronusScheduler::new(name, path).await?;
-            scheduler.run().await?
-        }
-        Command::Ping { name, path } => {
-            let cc = CommandClient::new(name, path)?;
-            cc.ping_service()?
-        }
-    };
-    Ok(response.to_json_msg())
-}
+        #[structopt(
+            long,
+            requires = "corn",
+            long_help = "Declares that the corn expression starts with a seconds field, e.g. `*/30 * * * * *`, for a precision of a second. Without it, the expression has the 5 fields of crontab and fires at second 0"
+        )]
+        seconds: bool,
 
-/// Checks if the Cronus service is running.
-///
-/// This function sends a ping to the Cronus service and checks the response to determine if the service is running.
-///
-/// # Arguments
-///
-/// * `name` - The name of the Cronus service.
-/// * `path` - The path where the Cronus service is located.
-///
-/// # Returns
-///
-/// * `CronusResult<bool>` - Returns `Ok(true)` if the service is running, `Ok(false)` if the service is not running, and `Err(CronusError)` if there was an error checking the service status.
-fn check_service_running(name: String, path: PathBuf) -> CronusResult<bool> {
-    if let Ok(cc) = CommandClient::new(name, path) {
-        if let Ok(res) = cc.ping_service() {
-            if res == CommandResponse::ServiceRunning {
-                return Ok(true);
-            }
-        }
-    }
-    Ok(false)
-}
+        #[structopt(
+            long,
+            conflicts_with = "corn",
+            long_help = "Id or name of the job after whose successful runs the job runs, instead of on a corn expression"
+        )]
+        after: Option<String>,
 
-/// Starts a new Cronus service.
-///
-/// This function starts a new instance of the Cronus service in a new process. It uses the `daemon` function to create a new child process, and then starts the Cronus service in the child process.
-///
-/// # Arguments
-///
-/// * `name` - The name of the Cronus service.
-/// * `path` - The path where the Cronus service is located.
-///
-/// # Returns
-///
-/// * `CronusResult<()>` - Returns `Ok(())` if the service is started successfully, and `Err(CronusError)` if there was an error starting the service.
-fn run_new_service(name: String, path: PathBuf) -> CronusResult<()> {
-    let cronus = std::env::current_exe()?;
-    match daemon(false, false) {
-        Ok(Fork::Child) => {
-            std::process::Command::new(cronus)
-                .arg("run")
-                .arg("--name")
-                .arg(name)
-                .arg("--path")
-                .arg(path)
-                .spawn()?;
-            std::process::exit(0);
-        }
-        _ => std::process::exit(0),
-    }
-}
+        #[structopt(
+            long,
+            conflicts_with_all = &["corn", "after"],
+            long_help = "Topic of the events emitted with emit that run the job, instead of a corn expression"
+        )]
+        on_event: Option<String>,
 
-#[tokio::main]
-async fn main() -> CronusResult<()> {
-    let json_result = run().await.unwrap_or_else(|e| {
-        let e = e.to_string();
-        json!({"error": e}).to_string()
-    });
-    println!("{json_result}");
+        #[structopt(
+            long,
+            conflicts_with_all = &["corn", "after", "on-event"],
+            long_help = "RFC 5545 recurrence the job runs on, instead of a corn expression, as a DTSTART followed by RRULE, RDATE and EXDATE lines separated by newlines or spaces, e.g. 'DTSTART;TZID=Europe/Paris:20260101T090000 RRULE:FREQ=MONTHLY;BYDAY=2TU'"
+        )]
+        rrule: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with_all = &["corn", "after", "on-event", "rrule"],
+            requires_all = &["lat", "lon"],
+            long_help = "Time of the sun the job runs at every day, instead of a corn expression: dawn, sunrise, noon, sunset or dusk, optionally followed by an offset, e.g. sunset+30m or sunrise-1h. Computed for the place given with --lat and --lon"
+        )]
+        at_sun: Option<SunTime>,
+
+        #[structopt(
+            long,
+            requires = "at-sun",
+            allow_hyphen_values = true,
+            long_help = "Latitude of the place of --at-sun, in degrees north, e.g. 48.85"
+        )]
+        lat: Option<f64>,
+
+        #[structopt(
+            long,
+            requires = "at-sun",
+            allow_hyphen_values = true,
+            long_help = "Longitude of the place of --at-sun, in degrees east, e.g. 2.35 or -73.98"
+        )]
+        lon: Option<f64>,
+
+        #[structopt(
+            long,
+            long_help = "Unique name other jobs can refer to the job by with --after"
+        )]
+        job_name: Option<String>,
+
+        #[structopt(
+            long,
+            long_help = "Unique key of the job, e.g. my-backup, so adding a job with a key already taken updates that job in place and prints its id with created false, instead of adding a duplicate"
+        )]
+        key: Option<String>,
+
+        #[structopt(
+            long,
+            long_help = "What the job is for, e.g. \"Purges the uploads older than 30 days\", shown by get and list --output wide"
+        )]
+        description: Option<String>,
+
+        #[structopt(
+            long,
+            number_of_values = 1,
+            parse(try_from_str = parse_label),
+            long_help = "Free-form metadata of the job as key=value, e.g. team=data or ticket=OPS-1234, shown by get and list --output wide. May be given several times"
+        )]
+        label: Vec<(String, String)>,
+
+        #[structopt(
+            long,
+            long_help = "Refuse the job if it has the same schedule and payload as a job already on cronus service, instead of adding it with a warning"
+        )]
+        no_duplicates: bool,
+
+        #[structopt(
+            long,
+            long_help = "Register the job paused, so it does not run until it is resumed with resume, e.g. to review it with get, list or timeline first"
+        )]
+        paused: bool,
+
+        #[structopt(
+            long,
+            long_help = "Only check the job on cronus service, as adding it would, and print what would be added, with its upcoming runs and the problems found with it, e.g. a command missing on the host of the service, without adding it"
+        )]
+        dry_run: bool,
+
+        #[structopt(
+            long,
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "Maximum random delay applied before each execution of the job, e.g. 120s"
+        )]
+        jitter: Option<Duration>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_datetime),
+            long_help = "Time the schedule of the job becomes active, e.g. 2024-07-01T09:00:00Z or 2024-07-01 09:00 in local time"
+        )]
+        not_before: Option<DateTime<Utc>>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_datetime),
+            long_help = "Time the schedule of the job expires, e.g. 2024-07-31T18:00:00Z or 2024-07-31 18:00 in local time"
+        )]
+        not_after: Option<DateTime<Utc>>,
+
+        #[structopt(
+            long,
+            default_value = "remove",
+            long_help = "What happens to the job once its schedule has expired: remove or pause"
+        )]
+        on_expiry: ExpiryAction,
+
+        #[structopt(
+            long,
+            long_help = "Skip the job while the failure of its previous run has not been acknowledged with ack"
+        )]
+        skip_if_failed: bool,
+
+        #[structopt(
+            long,
+            long_help = "Cost center the runs of the job are charged to, reported by stats --cost-centers"
+        )]
+        cost_center: Option<String>,
+
+        #[structopt(
+            long,
+            long_help = "IANA timezone the corn expression is evaluated in, e.g. Europe/Paris, instead of local time"
+        )]
+        timezone: Option<String>,
+
+        #[structopt(
+            long,
+            default_value = "skip",
+            long_help = "What happens to a tick of the corn expression that falls into an hour a daylight-saving transition removes or repeats: skip it, run it once at its first occurrence in a repeated hour, or also shift it past a missing hour, e.g. 02:30 to 03:30"
+        )]
+        on_dst: DstPolicy,
+
+        #[structopt(
+            long,
+            long_help = "Only run the job on business days, from Monday to Friday in the timezone of the job"
+        )]
+        business_days: bool,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            long_help = "Holiday calendar file the job does not run on, one YYYY-MM-DD date per line, optionally followed by the name of the holiday. It is read at every run"
+        )]
+        holidays: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            default_value = "skip",
+            long_help = "What happens to a run due on a day that is not a business day: skip, or shift to the same time on the next business day"
+        )]
+        on_holiday: HolidayPolicy,
+
+        #[structopt(
+            long,
+            long_help = "Maximum number of runs of the job in flight at once, beyond which runs are queued or skipped according to --on-overlap"
+        )]
+        max_concurrent: Option<NonZeroU32>,
+
+        #[structopt(
+            long,
+            default_value = "queue",
+            long_help = "What happens to a run beyond the concurrency limits of the job or of the service: queue or skip"
+        )]
+        on_overlap: OverlapPolicy,
+
+        #[structopt(
+            long,
+            default_value = "normal",
+            long_help = "How urgently the runs of the job are picked up by the workers of cronus service under load: high, normal or low"
+        )]
+        priority: Priority,
+
+        #[structopt(
+            long,
+            allow_hyphen_values = true,
+            long_help = "Niceness the processes of the job run with, from -20 (most favorable) to 19 (least favorable)"
+        )]
+        nice: Option<i32>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_size),
+            long_help = "Maximum address space of the processes of the job, e.g. 512M or 2G"
+        )]
+        max_mem: Option<u64>,
+
+        #[structopt(
+            long,
+            long_help = "Maximum number of CPUs the processes of the job may use, e.g. 0.5, enforced with a cgroup v2 on Linux"
+        )]
+        cpu_quota: Option<f64>,
+
+        #[structopt(
+            long,
+            long_help = "Name or uid of the user the processes of the job run as, which requires cronus service to run as root"
+        )]
+        user: Option<String>,
+
+        #[structopt(
+            long,
+            long_help = "Name or gid of the group the processes of the job run with, instead of the primary group of the user"
+        )]
+        group: Option<String>,
+
+        #[structopt(
+            long,
+            long_help = "Template the idempotency key of a run is rendered from, e.g. 'etl-{{scheduled_time | %Y%m%d}}', so a run whose key already succeeded is skipped, even across restarts of cronus service"
+        )]
+        idempotency_key: Option<String>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "Window a run of the job is expected to succeed within, e.g. 25h, beyond which cronus service emits a run_overdue event, catching schedules that never fire"
+        )]
+        expect_within: Option<Duration>,
+
+        #[structopt(
+            long,
+            long_help = "Number of consecutive failed runs after which the job is paused, emitting a job_disabled event, until it is resumed with resume"
+        )]
+        disable_after: Option<NonZeroU32>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "How long a run of the job may take, e.g. 30s or 2h, before it is killed and recorded as timed out in its history"
+        )]
+        timeout: Option<Duration>,
+
+        #[structopt(
+            long,
+            long_help = "Maximum number of operations a Rhai or Lua script, or a WebAssembly module, of the job may carry out, e.g. 1000000, before it is aborted"
+        )]
+        max_operations: Option<NonZeroU64>,
+
+        #[structopt(
+            long,
+            default_value = "standard",
+            long_help = "What a Rhai or Lua script, or a WebAssembly module, of the job is allowed to do: strict confines it to small values without modules, standard to large values, and full lifts the limits and lets it access files and run processes with read_file, write_file and exec"
+        )]
+        sandbox: Sandbox,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_hook),
+            long_help = "Job run before each run of the job, given like in a job file, in JSON or YAML, e.g. '{type: command, cmd: curl, args: [-fsS, https://hc.example.com/backup/start]}'"
+        )]
+        on_start: Option<Job>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_hook),
+            long_help = "Job run after each successful run of the job, given like --on-start"
+        )]
+        on_success: Option<Job>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = parse_hook),
+            long_help = "Job run after each failed run of the job, given like --on-start"
+        )]
+        on_failure: Option<Job>,
+
+        #[structopt(
+            long,
+            long_help = "healthchecks.io style ping URL of the job, e.g. https://hc-ping.com/<uuid>, pinged at <url>/start when a run starts, and at <url> or <url>/fail when it succeeds or fails"
+        )]
+        healthcheck_url: Option<String>,
+
+        #[structopt(
+            long,
+            long_help = "Regular expression a line of output of a run must match for the run to succeed, e.g. 'OK$', for tools that always exit with 0"
+        )]
+        success_regex: Option<String>,
+
+        #[structopt(
+            long,
+            long_help = "Regular expression no line of output of a run may match for the run to succeed, e.g. ERROR, for tools that always exit with 0"
+        )]
+        failure_regex: Option<String>,
+
+        #[structopt(
+            long,
+            number_of_values = 1,
+            long_help = "Name of a notifier declared in the configuration file of cronus service, e.g. ops for [notifiers.ops], alerted about the runs of the job. May be given several times"
+        )]
+        notify: Vec<String>,
+
+        #[structopt(
+            long,
+            number_of_values = 1,
+            long_help = "Outcome of the runs of the job the notifiers are alerted about: failure or success. May be given several times, and defaults to failure"
+        )]
+        notify_on: Vec<NotifyEvent>,
+
+        #[structopt(
+            long,
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "How long a run of the job may be in flight, e.g. 2h, before the notifiers are alerted that it runs long"
+        )]
+        notify_after: Option<Duration>,
+
+        #[structopt(
+            long,
+            number_of_values = 1,
+            parse(try_from_str = parse_secret),
+            long_help = "Secret given to the job as an environment variable, read from a file as NAME=@file or from the keyring of the user of cronus service as NAME=keyring:entry at every run, and masked in its output. Only the source is kept with the job. May be given several times"
+        )]
+        secret: Vec<(String, SecretSource)>,
+
+        #[structopt(subcommand)]
+        sub_cmd: AddSubCommand,
+    },
+    #[structopt(about = "Delete cron job from cronus service")]
+    Delete {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Corn job id to be deleted from cronus service"
+        )]
+        id: String,
+    },
+    #[structopt(about = "List cron job on cronus service")]
+    List {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            long,
+            default_value = "0",
+            long_help = "Number of jobs skipped, e.g. the next_offset of the previous page"
+        )]
+        offset: usize,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Maximum number of jobs listed, printing a page with the total number of jobs and the next_offset of the next page"
+        )]
+        limit: Option<usize>,
+
+        #[structopt(long, long_help = "Only list the jobs whose name contains this text")]
+        job_name: Option<String>,
+
+        #[structopt(
+            long = "type",
+            long_help = "Only list the jobs of this type, e.g. command, rhai_script or workflow"
+        )]
+        job_type: Option<String>,
+
+        #[structopt(
+            long,
+            default_value = "id",
+            long_help = "Order the jobs are listed in: id, name or next_run"
+        )]
+        sort: JobSort,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "json",
+            long_help = "Output format: json, table, wide with more columns, or quiet with only the ids"
+        )]
+        output: OutputFormat,
+    },
+    #[structopt(about = "Describe a cron job on cronus service, with the result of its last run")]
+    Get {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(short, long, long_help = "Corn job id to be described")]
+        id: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "json",
+            long_help = "Output format: json, table, wide with more columns, or quiet with only the ids"
+        )]
+        output: OutputFormat,
+    },
+    #[structopt(about = "List recorded runs of a cron job on cronus service")]
+    History {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Corn job id whose runs are listed, or `system` for the runs of the hooks of cronus service"
+        )]
+        id: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "json",
+            long_help = "Output format: json, table, wide with more columns, or quiet with only the ids"
+        )]
+        output: OutputFormat,
+    },
+    #[structopt(about = "Lint cron jobs on cronus service or in a job file")]
+    Lint {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            long_help = "Job file to lint instead of the jobs registered on cronus service"
+        )]
+        file: Option<PathBuf>,
+    },
+    #[structopt(
+        about = "Detect schedule hotspots of cron jobs on cronus service or in a job file"
+    )]
+    Analyze {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            long_help = "Job file to analyze instead of the jobs registered on cronus service"
+        )]
+        file: Option<PathBuf>,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "24h",
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "How far ahead the schedules are projected"
+        )]
+        window: Duration,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "3",
+            long_help = "Number of distinct jobs firing at the same time that makes a hotspot"
+        )]
+        threshold: usize,
+    },
+    #[structopt(about = "Export the past and projected executions of cron jobs on cronus service")]
+    Timeline {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "24h",
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "How far back past executions and how far ahead projected executions are laid out"
+        )]
+        window: Duration,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            long_help = "File the timeline is written to, as an SVG chart if it has an .svg extension and as JSON otherwise"
+        )]
+        output: Option<PathBuf>,
+    },
+    #[structopt(about = "Acknowledge the last failure of a cron job on cronus service")]
+    Ack {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(short, long, long_help = "Corn job id whose failure is acknowledged")]
+        id: String,
+    },
+    #[structopt(about = "List recent events of cronus service")]
+    Events {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+    },
+    #[structopt(about = "List the management commands received by cronus service")]
+    Audit {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Corn job id whose commands are listed, every command is listed if not given"
+        )]
+        id: Option<String>,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "100",
+            long_help = "Maximum number of commands listed, the most recent ones being kept"
+        )]
+        limit: usize,
+    },
+    #[structopt(about = "Show a live dashboard of cron jobs on cronus service")]
+    Top {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+    },
+    #[structopt(about = "Converge cron jobs on cronus service to a job file")]
+    Apply {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            required_unless = "workflow",
+            long_help = "Job file declaring the jobs managed by apply, in TOML (jobs.toml) or JSON"
+        )]
+        file: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with = "file",
+            long_help = "YAML workflow file declaring a single workflow to add or update, leaving the other jobs alone"
+        )]
+        workflow: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            long_help = "Print the plan without changing the jobs on cronus service"
+        )]
+        dry_run: bool,
+    },
+    #[structopt(
+        about = "Carry out a batch of commands on cronus service, all of them or none of them"
+    )]
+    Batch {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            long_help = "JSON file holding an array of commands adding, updating, deleting, pausing or resuming jobs, carried out in order"
+        )]
+        file: PathBuf,
+    },
+    #[structopt(about = "Run a cron job on cronus service right away")]
+    Trigger {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(short, long, long_help = "Corn job id to be run")]
+        id: String,
+    },
+    #[structopt(
+        about = "Run a failed run of a cron job again right away, for the same scheduled time and window"
+    )]
+    Retry {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            long,
+            long_help = "Id of the failed run to be retried, as recorded in the history"
+        )]
+        run: RunId,
+    },
+    #[structopt(
+        about = "Run a command on cronus service right away, with its timeout, output, history and notifications, without scheduling it"
+    )]
+    Exec {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            long,
+            long_help = "Name the command is registered under as a manual job, e.g. backup, so every exec of the same name shares its history"
+        )]
+        job_name: String,
+
+        #[structopt(
+            long,
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "How long the run may take, e.g. 30s or 2h, before it is killed and recorded as timed out in its history"
+        )]
+        timeout: Option<Duration>,
+
+        #[structopt(
+            long,
+            long_help = "Cost center the run is charged to, reported by stats --cost-centers"
+        )]
+        cost_center: Option<String>,
+
+        #[structopt(
+            long,
+            number_of_values = 1,
+            long_help = "Name of a notifier declared in the configuration file of cronus service, e.g. ops for [notifiers.ops], alerted about the run. May be given several times"
+        )]
+        notify: Vec<String>,
+
+        #[structopt(
+            long,
+            number_of_values = 1,
+            long_help = "Outcome of the run the notifiers are alerted about: failure or success. May be given several times, and defaults to failure"
+        )]
+        notify_on: Vec<NotifyEvent>,
+
+        #[structopt(
+            long,
+            long_help = "Regular expression a line of output of the run must match for the run to succeed, e.g. 'OK$'"
+        )]
+        success_regex: Option<String>,
+
+        #[structopt(
+            long,
+            long_help = "Regular expression no line of output of the run may match for the run to succeed, e.g. ERROR"
+        )]
+        failure_regex: Option<String>,
+
+        #[structopt(
+            required = true,
+            last = true,
+            long_help = "Command to run and its args, after --, e.g. -- /usr/bin/backup.sh --full"
+        )]
+        cmd: Vec<String>,
+    },
+    #[structopt(about = "Pause a cron job on cronus service")]
+    Pause {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(short, long, long_help = "Corn job id to be paused")]
+        id: String,
+    },
+    #[structopt(about = "Resume a paused cron job on cronus service")]
+    Resume {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(short, long, long_help = "Corn job id to be resumed")]
+        id: String,
+    },
+    #[structopt(about = "Kill the runs of a cron job in flight on cronus service")]
+    Kill {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(short, long, long_help = "Corn job id whose runs are killed")]
+        id: String,
+
+        #[structopt(
+            long,
+            long_help = "Id of the run to be killed, as printed by trigger or recorded in the history, instead of every run of the job"
+        )]
+        run: Option<RunId>,
+    },
+    #[structopt(about = "Print the output of the last run of a cron job, like tail")]
+    Tail {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(short, long, long_help = "Corn job id whose output is printed")]
+        id: String,
+
+        #[structopt(
+            long,
+            long_help = "Id of the run whose output is printed, in flight or among the last 5 runs of the job, instead of the last run of the job"
+        )]
+        run: Option<RunId>,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Follow the output while the run is in flight, waiting for the job to run if it has not run yet, like tail -f"
+        )]
+        follow: bool,
+    },
+    #[structopt(about = "Emit an event firing the cron jobs listening on its topic")]
+    Emit {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Topic the event is emitted on, e.g. deploy.finished"
+        )]
+        topic: String,
+
+        #[structopt(
+            long,
+            long_help = "Payload of the event, recorded in the events of the fired jobs"
+        )]
+        payload: Option<String>,
+    },
+    #[structopt(about = "Report machine time consumed by cron jobs on cronus service")]
+    Stats {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Corn job id to report on, instead of every job"
+        )]
+        id: Option<String>,
+
+        #[structopt(
+            long,
+            conflicts_with = "id",
+            long_help = "Report the machine time charged to every cost center instead"
+        )]
+        cost_centers: bool,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "json",
+            long_help = "Output format: json, table, wide with more columns, or quiet with only the ids"
+        )]
+        output: OutputFormat,
+    },
+    #[structopt(about = "Report cron jobs consuming the most machine time on cronus service")]
+    Digest {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "json",
+            long_help = "Output format: json, table, wide with more columns, or quiet with only the ids"
+        )]
+        output: OutputFormat,
+    },
+    #[structopt(about = "Inspect the timezone database cron jobs are scheduled with")]
+    Tz {
+        #[structopt(subcommand)]
+        sub_cmd: TzSubCommand,
+    },
+    #[structopt(about = "Run cronus service")]
+    Run {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            long,
+            long_help = "Address the HTTP API of cronus service listens on, e.g. 127.0.0.1:8080"
+        )]
+        http_addr: Option<SocketAddr>,
+        #[structopt(
+            long,
+            long_help = "Address the command socket of cronus service also listens on over TCP, e.g. 0.0.0.0:7070, so it is managed from other hosts, e.g. as an agent of a controller. Declare tokens in its configuration file, as anyone reaching the address may send commands"
+        )]
+        tcp_addr: Option<SocketAddr>,
+        #[structopt(
+            long,
+            long_help = "Address the health probes of cronus service are served on over HTTP, e.g. 0.0.0.0:8086: GET /healthz answers 200 while the service is alive and GET /readyz while it takes commands and its job store can be written, or else 503, along with the state of the service as JSON"
+        )]
+        health_addr: Option<SocketAddr>,
+        #[structopt(
+            long,
+            long_help = "Number of workers running jobs, i.e. maximum number of runs of all jobs in flight at once, beyond which runs are queued by priority or skipped according to the overlap policy of their job [default: 64]"
+        )]
+        max_parallel: Option<NonZeroUsize>,
+        #[structopt(
+            long,
+            long_help = "Configuration file of cronus service, a TOML file or else a JSON file, declaring the hooks run when it starts, stops and reloads"
+        )]
+        config: Option<PathBuf>,
+        #[structopt(
+            long,
+            long_help = "Job store of cronus service, a JSON file its jobs are kept in across restarts, migrated on startup if written by an older release [default: jobs are kept in memory]"
+        )]
+        store: Option<PathBuf>,
+        #[structopt(
+            long,
+            long_help = "Key file the job store and the backups of cronus service are encrypted with, holding 32 bytes or their 64 hex digits, e.g. written by openssl rand -hex 32, and readable by its owner only [default: they are written in clear]"
+        )]
+        store_key: Option<PathBuf>,
+        #[structopt(
+            long,
+            requires = "store",
+            parse(try_from_str = humantime::parse_duration),
+            long_help = "Window of the lease on the job store shared with another cronus service, e.g. 10s: only the service holding the lease runs the jobs, while the other one waits, and takes over once the lease has not been renewed for a whole window [default: the job store is not shared]"
+        )]
+        lease_window: Option<Duration>,
+        #[structopt(
+            long,
+            conflicts_with = "follow",
+            long_help = "Job file, like the one given to apply --file, the jobs with a key of cronus service are kept converged to: it is read again every 5 seconds, and the jobs it declares are added, updated and those it no longer declares removed, each emitting a job_reconciled event [default: jobs are only changed by commands]"
+        )]
+        reconcile: Option<PathBuf>,
+        #[structopt(
+            long,
+            conflicts_with = "store",
+            long_help = "Name of the cronus service to follow, with the same command acceptance path: its jobs and run history are mirrored read-only, without running the jobs, until the service is promoted with promote"
+        )]
+        follow: Option<String>,
+        #[structopt(
+            long,
+            hidden = true,
+            long_help = "Developer mode injecting failures at the given rates, e.g. delay=0.2,max-delay=10s,fail=0.1,drop=0.05: ticks delayed up to max-delay, runs failed instead of being carried out, and commands dropped without being answered"
+        )]
+        chaos: Option<Chaos>,
+    },
+    #[structopt(about = "Run several cronus services in one process")]
+    Supervise {
+        #[structopt(
+            long,
+            long_help = "Configuration file of the supervisor, a TOML file or else a JSON file, declaring every service under [services.<name>] with the options of run, e.g. store, config and http_addr"
+        )]
+        config: PathBuf,
+    },
+    #[structopt(
+        about = "Run a controller managing the cron jobs of several cronus services at once"
+    )]
+    Controller {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Controller command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Controller command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            long,
+            long_help = "Address the command socket of the controller also listens on over TCP, e.g. 0.0.0.0:7070, for the contexts of other hosts"
+        )]
+        tcp_addr: Option<SocketAddr>,
+
+        #[structopt(
+            long,
+            long_help = "Configuration file of the controller, a TOML file or else a JSON file, declaring every agent under [agents.<name>] with the addr its command socket listens on over TCP, e.g. web1:7070, or the name and path of a service on this host"
+        )]
+        config: PathBuf,
+    },
+    #[structopt(about = "Ping cronus service")]
+    Ping {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+    },
+    #[structopt(about = "Encrypt the job store of a stopped cronus service written in clear")]
+    EncryptStore {
+        #[structopt(
+            long,
+            parse(from_os_str),
+            long_help = "Job store of cronus service to encrypt, along with its journal, while the service is stopped. The copies of the store backed up before a migration, which hold its jobs in clear, are removed"
+        )]
+        store: PathBuf,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            long_help = "Key file the job store is encrypted with from then on, to be passed to cronus service with --store-key"
+        )]
+        store_key: PathBuf,
+    },
+    #[structopt(about = "Diagnose the environment of cronus service")]
+    Doctor {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            long_help = "Configuration file of cronus service to check"
+        )]
+        config: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            long_help = "Job store of cronus service to check"
+        )]
+        store: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            long_help = "Key file the job store of cronus service is encrypted with"
+        )]
+        store_key: Option<PathBuf>,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "json",
+            long_help = "Output format: json, table, wide with more columns, or quiet with only the failed checks"
+        )]
+        output: OutputFormat,
+    },
+    #[structopt(about = "Generate the completion script of cronus for a shell")]
+    Completions {
+        #[structopt(
+            possible_values = &Shell::variants(),
+            case_insensitive = true,
+            long_help = "Shell the completion script is sourced by, completing job ids and names in bash, fish and zsh"
+        )]
+        shell: Shell,
+    },
+    #[structopt(about = "Generate the man page of cronus")]
+    Man,
+    #[structopt(
+        name = COMPLETE_JOBS,
+        setting = AppSettings::Hidden,
+        about = "List the jobs of cronus service for completion scripts"
+    )]
+    CompleteJobs {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+
+        #[structopt(long, long_help = "List the names of the jobs instead of their ids")]
+        names: bool,
+    },
+}
+
+/// The `TzSubCommand` enum.
+///
+/// This enum represents the different subcommands that can be used with the `Tz` command of the Cronus task execution manager.
+///
+/// # Variants
+///
+/// * `List` - Lists the timezones of the embedded timezone database, with their current offset.
+/// * `Check` - Checks the timezones of the cron jobs on the Cronus service, and whether the system timezone database is newer than the embedded one.
+#[derive(StructOpt, Debug)]
+enum TzSubCommand {
+    #[structopt(about = "List the timezones cron jobs can be scheduled in")]
+    List,
+    #[structopt(
+        about = "Check the timezones of cron jobs on cronus service against its timezone database"
+    )]
+    Check {
+        #[structopt(
+            short,
+            long,
+            default_value = "cronus",
+            long_help = "Cronus service command acceptance name"
+        )]
+        name: String,
+
+        #[structopt(
+            short,
+            long,
+            default_value = "/tmp",
+            long_help = "Cronus service command acceptance path"
+        )]
+        path: PathBuf,
+    },
+}
+
+/// The `AddSubCommand` enum.
+///
+/// This enum represents the different subcommands that can be used with the `Add` command of the Cronus task execution manager.
+/// Each variant of the enum corresponds to a different subcommand, and contains the arguments for that subcommand.
+///
+/// # Variants
+///
+/// * `CmdJob` - Represents a command job. It contains the path to the command and the arguments for the command.
+/// * `RhaiJob` - Represents a Rhai job. It contains the Rhai script code.
+/// * `RhaiFileJob` - Represents a Rhai file job. It contains the path to the Rhai script file.
+/// * `ContainerJob` - Represents a container job. It contains the image of the container, its command, environment variables and volumes.
+/// * `PipelineJob` - Represents a pipeline job. It contains the jobs of its steps, in order, and whether the steps after a failed step still run.
+/// * `LuaJob` - Represents a Lua job, when cronus is built with the `lua` feature. It contains the Lua script code.
+/// * `LuaFileJob` - Represents a Lua file job, when cronus is built with the `lua` feature. It contains the path to the Lua script file.
+/// * `SqlJob` - Represents an SQL job, when cronus is built with the `sql` feature. It contains the URL of the database and the statements to execute.
+/// * `SshJob` - Represents a remote command job, when cronus is built with the `ssh` feature. It contains the remote host and user, the private key of the user and the command.
+/// * `WasmJob` - Represents a WebAssembly job, when cronus is built with the `wasm` feature. It contains the path to the module, the function to be called and its arguments.
+#[derive(StructOpt, Debug)]
+#[allow(clippy::enum_variant_names)]
+enum AddSubCommand {
+    #[structopt(about = "Command Job")]
+    CmdJob {
+        #[structopt(short, long, parse(from_os_str), long_help = "Command path")]
+        cmd: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Command args, which may contain placeholders like `{{scheduled_time - 1d | %Y%m%d}}`, `{{date:%Y-%m-%d}}`, `{{scheduled_ts}}`, `{{job_name}}` or `{{run_id}}`"
+        )]
+        args: Vec<String>,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            long_help = "File whose content is written to the standard input of the command at every run, e.g. a query for psql"
+        )]
+        stdin_file: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            conflicts_with = "stdin-file",
+            long_help = "String written to the standard input of the command at every run"
+        )]
+        stdin_string: Option<String>,
+    },
+    #[structopt(about = "Rhai Job")]
+    RhaiJob {
+        #[structopt(short, long, long_help = "Rhai script code")]
+        script: String,
+    },
+    #[structopt(about = "Rhai file Job")]
+    RhaiFileJob {
+        #[structopt(short, long, parse(from_os_str), long_help = "Rhai script file path")]
+        script_file: PathBuf,
+    },
+    #[structopt(about = "Container Job, run through the Docker or Podman engine")]
+    ContainerJob {
+        #[structopt(
+            short,
+            long,
+            long_help = "Container image, e.g. alpine:3.20, pulled if the engine does not have it"
+        )]
+        image: String,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Container command, which may contain time placeholders like `{{scheduled_time | %Y%m%d}}`, instead of the command of the image"
+        )]
+        cmd: Vec<String>,
+
+        #[structopt(
+            short,
+            long,
+            parse(try_from_str = parse_env_var),
+            long_help = "Container environment variable, e.g. LEVEL=debug, whose value may contain placeholders like `{{run_id}}`"
+        )]
+        env: Vec<(String, String)>,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Volume mounted into the container, e.g. /srv/backups:/backups:ro"
+        )]
+        volumes: Vec<String>,
+    },
+    #[structopt(about = "Pipeline Job, running its steps one after the other")]
+    PipelineJob {
+        #[structopt(
+            short,
+            long,
+            required = true,
+            number_of_values = 1,
+            parse(try_from_str = parse_step),
+            long_help = "Step of the pipeline, a job written like in a job file, in JSON or YAML, e.g. '{type: command, cmd: /usr/bin/make, args: [build]}', named by its position; repeat it for every step, in order"
+        )]
+        step: Vec<Job>,
+
+        #[structopt(
+            long,
+            long_help = "Run the steps after a failed step all the same, the run of the pipeline still fails"
+        )]
+        keep_going: bool,
+    },
+    #[cfg(feature = "lua")]
+    #[structopt(about = "Lua Job")]
+    LuaJob {
+        #[structopt(short, long, long_help = "Lua script code")]
+        script: String,
+    },
+    #[cfg(feature = "lua")]
+    #[structopt(about = "Lua file Job")]
+    LuaFileJob {
+        #[structopt(short, long, parse(from_os_str), long_help = "Lua script file path")]
+        script_file: PathBuf,
+    },
+    #[cfg(feature = "sql")]
+    #[structopt(about = "SQL Job, run against a Postgres, MySQL or SQLite database")]
+    SqlJob {
+        #[structopt(
+            short,
+            long,
+            long_help = "Database URL, e.g. postgres://cronus@db1/app, mysql://cronus@db1/app or sqlite:///var/lib/app.db"
+        )]
+        url: String,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Statements to execute, separated by semicolons, which may contain time placeholders like `{{scheduled_time - 30d | %Y-%m-%d}}`"
+        )]
+        statement: String,
+    },
+    #[cfg(feature = "ssh")]
+    #[structopt(about = "Remote command Job, run over SSH")]
+    SshJob {
+        #[structopt(
+            short = "H",
+            long,
+            long_help = "Remote host, with an optional port, e.g. db1.internal:2222, which must be listed in ~/.ssh/known_hosts"
+        )]
+        host: String,
+
+        #[structopt(short, long, long_help = "Remote user")]
+        user: String,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            long_help = "Private key file of the remote user, instead of the SSH agent"
+        )]
+        key_path: Option<PathBuf>,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Remote command, which may contain time placeholders like `{{scheduled_time - 1d | %Y%m%d}}`"
+        )]
+        command: String,
+    },
+    #[cfg(feature = "wasm")]
+    #[structopt(about = "WebAssembly Job")]
+    WasmJob {
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            long_help = "WebAssembly module path, either a binary .wasm or a text .wat file"
+        )]
+        module: PathBuf,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Name of the function exported by the module to be called"
+        )]
+        func: String,
+
+        #[structopt(
+            short,
+            long,
+            long_help = "Function args, i32, i64, f32 or f64 values which may contain time placeholders like `{{scheduled_time | %s}}`"
+        )]
+        args: Vec<String>,
+    },
+}
+
+impl AddSubCommand {
+    /// Converts the `AddSubCommand` enum into a `Job`.
+    ///
+    /// This method is used to convert the different subcommands that can be used with the `Add` command of the Cronus task execution manager into a `Job`.
+    /// Each variant of the `AddSubCommand` enum corresponds to a different type of job, and this method will create a `Job` of the appropriate type based on the variant.
+    ///
+    /// # Returns
+    ///
+    /// * `Job` - The `Job` that corresponds to the `AddSubCommand`.
+    fn into_job(self) -> Job {
+        match self {
+            AddSubCommand::CmdJob {
+                cmd,
+                args,
+                stdin_file,
+                stdin_string,
+            } => {
+                let stdin = stdin_string
+                    .map(CommandStdin::Text)
+                    .or(stdin_file.map(CommandStdin::File));
+                Job::new_command(cmd, args, stdin)
+            }
+            AddSubCommand::RhaiJob { script } => Job::new_rhai_script(script),
+            AddSubCommand::RhaiFileJob { script_file } => Job::new_rhai_script_file(script_file),
+            AddSubCommand::ContainerJob {
+                image,
+                cmd,
+                env,
+                volumes,
+            } => Job::new_container(image, cmd, env.into_iter().collect(), volumes),
+            AddSubCommand::PipelineJob { step, keep_going } => Job::new_pipeline(
+                step.into_iter()
+                    .enumerate()
+                    .map(|(i, job)| JobStep {
+                        name: (i + 1).to_string(),
+                        job,
+                        continue_on_failure: keep_going,
+                    })
+                    .collect(),
+            ),
+            #[cfg(feature = "lua")]
+            AddSubCommand::LuaJob { script } => Job::new_lua(script),
+            #[cfg(feature = "lua")]
+            AddSubCommand::LuaFileJob { script_file } => Job::new_lua_file(script_file),
+            #[cfg(feature = "sql")]
+            AddSubCommand::SqlJob { url, statement } => Job::new_sql(url, statement),
+            #[cfg(feature = "ssh")]
+            AddSubCommand::SshJob {
+                host,
+                user,
+                key_path,
+                command,
+            } => Job::new_ssh(host, user, key_path, command),
+            #[cfg(feature = "wasm")]
+            AddSubCommand::WasmJob { module, func, args } => Job::new_wasm(module, func, args),
+        }
+    }
+}
+
+/// Asynchronously runs the Cronus task execution manager.
+///
+/// This function matches the command line arguments to the corresponding command variant and executes the command.
+/// It handles all the commands that the Cronus task execution manager can process, including starting and stopping the service, adding and deleting jobs, listing jobs, and running the service.
+///
+/// # Returns
+///
+/// * `CronusResult<(String, Option<ErrorCode>)>` - The result of running the command. If the command is executed successfully, it returns a `CronusResult::Ok((String, Option<ErrorCode>))` where the `String` is the response of the service in the output format of the command, JSON by default, along with the code of the failure it reports, if any. If there is an error executing the command, it returns a `CronusResult::Err(CronusError)` where the `CronusError` represents the error that occurred.
+async fn run() -> CronusResult<(String, Option<ErrorCode>)> {
+    let mut format = OutputFormat::Json;
+    let cli = Cli::from_args();
+    // The commands are sent to the service or the controller of the context, if any, instead of the service of their name and path.
+    let context = cli
+        .context
+        .map(|context| Contexts::load()?.take(&context))
+        .transpose()?;
+    let namespace = cli.namespace;
+    let client = |name: String, path: PathBuf| -> CronusResult<CommandClient> {
+        let mut cc = match &context {
+            Some(context) => context.connect(),
+            None => CommandClient::new(name, path),
+        }?;
+        if namespace.is_some() {
+            cc.set_namespace(namespace.clone());
+        }
+        Ok(cc)
+    };
+    let response = match cli.command {
+        Command::Start {
+            name,
+            path,
+            http_addr,
+            tcp_addr,
+            health_addr,
+            max_parallel,
+            config,
+            store,
+            store_key,
+            lease_window,
+            reconcile,
+            follow,
+            chaos,
+        } => {
+            // The service runs from `/`, and is started only if its configuration and its store key are valid.
+            let config = config.map(std::fs::canonicalize).transpose()?;
+            let reconcile = reconcile.map(std::fs::canonicalize).transpose()?;
+            if let Some(config) = &config {
+                ServiceConfig::load(config)?;
+            }
+            let store = store.map(std::path::absolute).transpose()?;
+            let store_key = store_key.map(std::fs::canonicalize).transpose()?;
+            if let Some(store_key) = &store_key {
+                StoreKey::load(store_key)?;
+            }
+            if !check_service_running(name.clone(), path.clone())? {
+                run_new_service(
+                    name,
+                    path,
+                    http_addr,
+                    tcp_addr,
+                    health_addr,
+                    max_parallel,
+                    config,
+                    store,
+                    store_key,
+                    lease_window,
+                    reconcile,
+                    follow,
+                    chaos,
+                )?;
+            }
+            CommandResponse::ServiceRunning
+        }
+        Command::Stop { name, path } => {
+            let cc = client(name, path)?;
+            cc.stop_service()?
+        }
+        Command::Reload { name, path } => {
+            let cc = client(name, path)?;
+            cc.reload_config()?
+        }
+        Command::Promote { name, path } => {
+            let cc = client(name, path)?;
+            cc.promote()?
+        }
+        Command::Snapshot { name, path, out } => {
+            let cc = client(name, path)?;
+            match cc.snapshot()? {
+                CommandResponse::Snapshot(snapshot) => {
+                    archive::write(&out, &snapshot)?;
+                    CommandResponse::SnapshotExported(out, snapshot.jobs.len())
+                }
+                response => response,
+            }
+        }
+        Command::Restore { name, path, from } => {
+            let snapshot = archive::read(&from)
+                .map_err(|e| format!("Cannot read `{}`: {e}", from.display()))?;
+            let cc = client(name, path)?;
+            cc.restore(snapshot)?
+        }
+        Command::Add {
+            name,
+            path,
+            corn,
+            daily_at,
+            weekly_on,
+            monthly_on,
+            at,
+            seconds,
+            after,
+            on_event,
+            rrule,
+            at_sun,
+            lat,
+            lon,
+            job_name,
+            key,
+            description,
+            label,
+            no_duplicates,
+            paused,
+            dry_run,
+            jitter,
+            not_before,
+            not_after,
+            on_expiry,
+            skip_if_failed,
+            cost_center,
+            timezone,
+            on_dst,
+            business_days,
+            holidays,
+            on_holiday,
+            max_concurrent,
+            on_overlap,
+            priority,
+            nice,
+            max_mem,
+            cpu_quota,
+            user,
+            group,
+            idempotency_key,
+            expect_within,
+            disable_after,
+            timeout,
+            max_operations,
+            sandbox,
+            on_start,
+            on_success,
+            on_failure,
+            healthcheck_url,
+            success_regex,
+            failure_regex,
+            notify,
+            notify_on,
+            notify_after,
+            secret,
+            sub_cmd,
+        } => {
+            if let Some(zone) = &timezone {
+                parse_timezone(zone)?;
+            }
+            if let Some(not_after) = not_after {
+                if not_after <= Utc::now() {
+                    return Err("Not-after time is in the past".into());
+                }
+                if not_before.is_some_and(|not_before| not_before >= not_after) {
+                    return Err("Not-before time must be earlier than not-after time".into());
+                }
+            }
+            if expect_within.is_some_and(|window| window.as_secs() == 0) {
+                return Err("The expect-within window must be at least 1s".into());
+            }
+            if timeout.is_some_and(|timeout| timeout.is_zero()) {
+                return Err("The timeout must not be zero".into());
+            }
+            if notify.is_empty() && (!notify_on.is_empty() || notify_after.is_some()) {
+                return Err(
+                    "--notify-on and --notify-after require a notifier, given with --notify".into(),
+                );
+            }
+            if on_holiday == HolidayPolicy::Shift && !business_days && holidays.is_none() {
+                return Err("--on-holiday requires --business-days or --holidays".into());
+            }
+            let shorthand = match (daily_at, weekly_on, monthly_on) {
+                (Some(at), _, _) => Some(Shorthand::Daily(at)),
+                (None, Some(days), _) => Some(Shorthand::Weekly(days, at.unwrap_or_default())),
+                (None, None, Some(day)) => Some(Shorthand::Monthly(day, at.unwrap_or_default())),
+                (None, None, None) => None,
+            };
+            if at.is_some()
+                && !matches!(
+                    shorthand,
+                    Some(Shorthand::Weekly(..) | Shorthand::Monthly(..))
+                )
+            {
+                return Err("--at requires --weekly-on or --monthly-on".into());
+            }
+            let corn = corn.or_else(|| shorthand.as_ref().map(Shorthand::to_cron));
+            if let Some(corn) = &corn {
+                schedule::validate_precision(corn, seconds)?;
+                schedule::validate(corn)?;
+            }
+            if let Some(rule) = &rrule {
+                parse_rrule(rule)?;
+            }
+            let sun = at_sun
+                .zip(lat.zip(lon))
+                .map(|(at, (latitude, longitude))| SunSchedule {
+                    at,
+                    latitude,
+                    longitude,
+                });
+            if let Some(sun) = &sun {
+                sun.validate()?;
+            }
+            let trigger = match (corn, after, on_event, rrule, sun) {
+                (Some(corn), _, _, _, _) => Trigger::Cron(corn),
+                (None, Some(after), _, _, _) => Trigger::After(after),
+                (None, None, Some(topic), _, _) => Trigger::Event(topic),
+                (None, None, None, Some(rule), _) => Trigger::Rrule(rule),
+                (None, None, None, None, Some(sun)) => Trigger::Sun(sun),
+                (None, None, None, None, None) => {
+                    return Err(
+                        "Either a corn expression, --daily-at, --weekly-on, --monthly-on, --after, --on-event, --rrule or --at-sun is required"
+                            .into(),
+                    )
+                }
+            };
+            let options = JobOptions {
+                name: job_name,
+                key,
+                namespace: None,
+                description,
+                labels: label.into_iter().collect(),
+                jitter,
+                not_before,
+                not_after,
+                on_expiry,
+                skip_if_failed,
+                cost_center,
+                timezone,
+                on_dst,
+                business_days,
+                holidays,
+                on_holiday,
+                max_concurrent,
+                on_overlap,
+                priority,
+                nice,
+                max_mem,
+                cpu_quota,
+                user,
+                group,
+                idempotency_key,
+                expect_within,
+                disable_after,
+                timeout,
+                max_operations,
+                sandbox,
+                on_start,
+                on_success,
+                on_failure,
+                healthcheck_url,
+                success_regex,
+                failure_regex,
+                notify,
+                notify_on,
+                notify_after,
+                secrets: secret.into_iter().collect(),
+            };
+            options.limits().validate()?;
+            options.run_as().validate()?;
+            options.calendar()?;
+            options.output_patterns()?;
+            if let Some(key) = &options.idempotency_key {
+                template::validate(key).map_err(|e| format!("Invalid idempotency key: {e}"))?;
+            }
+            let job = sub_cmd.into_job();
+            job.validate_templates()?;
+            options.validate_hooks()?;
+            if let Some(url) = &options.healthcheck_url {
+                http_client::validate_url(url)?;
+            }
+            let cc = client(name, path)?;
+            if options.name.is_some() || trigger.after().is_some() {
+                check_chain(&cc, &trigger, &options)?;
+            }
+            cc.add_job(trigger, job, options, no_duplicates, paused, dry_run)?
+        }
+        Command::Delete { name, path, id } => {
+            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
+            let cc = client(name, path)?;
+            cc.delete_job(id)?
+        }
+        Command::List {
+            name,
+            path,
+            offset,
+            limit,
+            job_name,
+            job_type,
+            sort,
+            output,
+        } => {
+            let filter = JobFilter {
+                name: job_name,
+                job_type,
+                namespace: None,
+            };
+            let cc = client(name, path)?;
+            format = output;
+            cc.query_jobs(offset, limit, filter, sort)?
+        }
+        Command::Get {
+            name,
+            path,
+            id,
+            output,
+        } => {
+            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
+            let cc = client(name, path)?;
+            format = output;
+            cc.get_job(id)?
+        }
+        Command::History {
+            name,
+            path,
+            id,
+            output,
+        } => {
+            let id = match id.as_str() {
+                "system" => SYSTEM_RUNS.to_string(),
+                _ => Uuid::parse_str(&id)
+                    .map_err(|_| "Invalid job id")?
+                    .to_string(),
+            };
+            let cc = client(name, path)?;
+            format = output;
+            cc.job_history(id)?
+        }
+        Command::Lint { name, path, file } => match file {
+            Some(file) => {
+                let findings = load_job_specs(&file)?
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, spec)| {
+                        lint_job(
+                            &format!("#{i}"),
+                            &spec.trigger,
+                            &spec.job,
+                            &spec.options,
+                            None,
+                        )
+                    })
+                    .collect();
+                CommandResponse::LintReport(findings)
+            }
+            None => {
+                let cc = client(name, path)?;
+                cc.lint_jobs()?
+            }
+        },
+        Command::Analyze {
+            name,
+            path,
+            file,
+            window,
+            threshold,
+        } => match file {
+            Some(file) => {
+                let analyzed: Vec<_> = load_job_specs(&file)?
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, spec)| {
+                        Some(AnalyzedJob {
+                            job: format!("#{i}"),
+                            cron: spec.trigger.cron()?.to_string(),
+                            jitter: spec.options.jitter,
+                            estimated_duration: None,
+                        })
+                    })
+                    .collect();
+                CommandResponse::Hotspots(analyze_hotspots(&analyzed, window, threshold))
+            }
+            None => {
+                let cc = client(name, path)?;
+                cc.analyze_schedules(window, threshold)?
+            }
+        },
+        Command::Timeline {
+            name,
+            path,
+            window,
+            output,
+        } => {
+            let cc = client(name, path)?;
+            match (cc.timeline(window)?, output) {
+                (CommandResponse::Timeline(timeline), Some(output)) => {
+                    let content = match output.extension() {
+                        Some(ext) if ext.eq_ignore_ascii_case("svg") => render_svg(&timeline),
+                        _ => serde_json::to_string_pretty(&timeline)?,
+                    };
+                    std::fs::write(&output, content)?;
+                    CommandResponse::TimelineExported(output)
+                }
+                (response, _) => response,
+            }
+        }
+        Command::Ack { name, path, id } => {
+            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
+            let cc = client(name, path)?;
+            cc.ack_failure(id)?
+        }
+        Command::Events { name, path } => {
+            let cc = client(name, path)?;
+            cc.list_events()?
+        }
+        Command::Audit {
+            name,
+            path,
+            id,
+            limit,
+        } => {
+            if let Some(id) = &id {
+                Uuid::parse_str(id).map_err(|_| "Invalid job id")?;
+            }
+            let cc = client(name, path)?;
+            cc.list_audit(id, limit)?
+        }
+        Command::Top { name, path } => {
+            let cc = client(name.clone(), path)?;
+            top::run(&cc, &name)?;
+            CommandResponse::ServiceRunning
+        }
+        Command::Apply {
+            name,
+            path,
+            file,
+            workflow,
+            dry_run,
+        } => {
+            let (specs, prune) = match (file, workflow) {
+                (_, Some(workflow)) => (vec![load_workflow(&workflow)?.into_spec()], false),
+                (Some(file), None) => (load_job_specs(&file)?, true),
+                (None, None) => return Err("Either a job file or --workflow is required".into()),
+            };
+            let cc = client(name, path)?;
+            apply_job_specs(&cc, specs, prune, dry_run)?
+        }
+        Command::Batch { name, path, file } => {
+            let text = std::fs::read_to_string(&file)
+                .map_err(|e| format!("Cannot read the batch file {}: {e}", file.display()))?;
+            let commands = serde_json::from_str(&text)
+                .map_err(|e| format!("Invalid batch file {}: {e}", file.display()))?;
+            let cc = client(name, path)?;
+            cc.batch(commands)?
+        }
+        Command::Trigger { name, path, id } => {
+            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
+            let cc = client(name, path)?;
+            cc.trigger_job(id)?
+        }
+        Command::Retry { name, path, run } => {
+            let cc = client(name, path)?;
+            cc.retry_run(run)?
+        }
+        Command::Exec {
+            name,
+            path,
+            job_name,
+            timeout,
+            cost_center,
+            notify,
+            notify_on,
+            success_regex,
+            failure_regex,
+            cmd,
+        } => {
+            let mut cmd = cmd.into_iter();
+            let program = cmd.next().ok_or("A command is required after --")?;
+            let options = JobOptions {
+                key: Some(format!("exec:{job_name}")),
+                name: Some(job_name),
+                timeout,
+                cost_center,
+                notify,
+                notify_on,
+                success_regex,
+                failure_regex,
+                ..JobOptions::default()
+            };
+            options.output_patterns()?;
+            let cc = client(name, path)?;
+            exec_job(
+                &cc,
+                Job::new_command(PathBuf::from(program), cmd.collect(), None),
+                options,
+            )?
+        }
+        Command::Pause { name, path, id } => {
+            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
+            let cc = client(name, path)?;
+            cc.pause_job(id)?
+        }
+        Command::Resume { name, path, id } => {
+            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
+            let cc = client(name, path)?;
+            cc.resume_job(id)?
+        }
+        Command::Kill {
+            name,
+            path,
+            id,
+            run,
+        } => {
+            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
+            let cc = client(name, path)?;
+            cc.kill_run(id, run)?
+        }
+        Command::Tail {
+            name,
+            path,
+            id,
+            run,
+            follow,
+        } => {
+            Uuid::parse_str(&id).map_err(|_| "Invalid job id")?;
+            let cc = client(name, path)?;
+            let mut from = 0;
+            let mut run_id = run;
+            let mut started = false;
+            loop {
+                match cc.tail_output(id.clone(), from, run_id)? {
+                    CommandResponse::RunOutput(Some(chunk)) => {
+                        for line in &chunk.lines {
+                            println!("{line}");
+                        }
+                        if chunk.finished || !follow {
+                            break CommandResponse::OutputTailed(chunk.finished);
+                        }
+                        from = chunk.next;
+                        // Once started, the run is followed by its id, so a run started after it does not cut it short.
+                        if chunk.run_id.is_some() {
+                            run_id = chunk.run_id;
+                            started = true;
+                        }
+                    }
+                    // The followed run is neither in flight nor the last run anymore, so it is over.
+                    CommandResponse::RunOutput(None) if started => {
+                        break CommandResponse::OutputTailed(true)
+                    }
+                    CommandResponse::RunOutput(None) if follow => {}
+                    response => break response,
+                }
+                thread::sleep(TAIL_POLL_INTERVAL);
+            }
+        }
+        Command::Tz { sub_cmd } => match sub_cmd {
+            TzSubCommand::List => CommandResponse::ZoneList(list_zones(Utc::now())),
+            TzSubCommand::Check { name, path } => {
+                let cc = client(name, path)?;
+                cc.check_timezones()?
+            }
+        },
+        Command::Emit {
+            name,
+            path,
+            topic,
+            payload,
+        } => {
+            let cc = client(name, path)?;
+            cc.emit_event(topic, payload)?
+        }
+        Command::Stats {
+            name,
+            path,
+            id,
+            cost_centers,
+            output,
+        } => {
+            if let Some(id) = &id {
+                Uuid::parse_str(id).map_err(|_| "Invalid job id")?;
+            }
+            let cc = client(name, path)?;
+            format = output;
+            if cost_centers {
+                cc.cost_center_stats()?
+            } else {
+                cc.job_stats(id)?
+            }
+        }
+        Command::Digest { name, path, output } => {
+            let cc = client(name, path)?;
+            format = output;
+            cc.usage_digest()?
+        }
+        Command::Run {
+            name,
+            path,
+            http_addr,
+            tcp_addr,
+            health_addr,
+            max_parallel,
+            config,
+            store,
+            store_key,
+            lease_window,
+            reconcile,
+            follow,
+            chaos,
+        } => {
+            let scheduler = CronusScheduler::new(
+                name,
+                path,
+                http_addr,
+                tcp_addr,
+                health_addr,
+                max_parallel,
+                config,
+                store,
+                store_key,
+                lease_window,
+                reconcile,
+                follow,
+                chaos,
+            )
+            .await?;
+            scheduler.run().await?
+        }
+        Command::Supervise { config } => {
+            supervisor::supervise(SupervisorConfig::load(&config)?).await?
+        }
+        Command::Controller {
+            name,
+            path,
+            tcp_addr,
+            config,
+        } => {
+            let config = ControllerConfig::load(&config)?;
+            tokio::task::spawn_blocking(move || {
+                controller::control(name, path, tcp_addr, config).map_err(|e| e.to_string())
+            })
+            .await??
+        }
+        Command::Ping { name, path } => {
+            let cc = client(name, path)?;
+            cc.ping_service()?
+        }
+        Command::EncryptStore { store, store_key } => {
+            let key = StoreKey::load(&store_key)?;
+            let jobs = store::encrypt(&store, &key)?;
+            CommandResponse::StoreEncrypted(store, jobs)
+        }
+        Command::Doctor {
+            name,
+            path,
+            config,
+            store,
+            store_key,
+            output,
+        } => {
+            format = output;
+            CommandResponse::DoctorReport(doctor::diagnose(&Environment {
+                name,
+                path,
+                config,
+                store,
+                store_key,
+            }))
+        }
+        Command::Completions { shell } => {
+            return Ok((completions::generate(Cli::clap(), BIN_NAME, shell), None));
+        }
+        Command::Man => return Ok((man::render(Cli::clap(), BIN_NAME), None)),
+        Command::CompleteJobs { name, path, names } => {
+            // Completion must not print errors among the candidates, so a service that cannot be reached has no jobs.
+            let jobs = match client(name, path).and_then(|cc| cc.list_jobs()) {
+                Ok(CommandResponse::JobList(jobs)) => jobs,
+                _ => Vec::new(),
+            };
+            let lines: Vec<_> = jobs
+                .into_iter()
+                .filter_map(|job| {
+                    if names {
+                        job.options.name
+                    } else {
+                        Some(job.id)
+                    }
+                })
+                .collect();
+            return Ok((lines.join("\n"), None));
+        }
+    };
+    Ok((render(&response, format, Utc::now()), response.error_code()))
+}
+
+/// Runs a command on the Cronus service right away, registered as a manual job keyed by its name, and waits for the run to finish.
+///
+/// The output of the run is printed as it is read, and the run is looked up in the history of the job once it is over.
+///
+/// # Arguments
+///
+/// * `cc` - The `CommandClient` connected to the Cronus service.
+/// * `job` - The `Job` of the command to be run.
+/// * `options` - The `JobOptions` of the command, with its name and its key.
+///
+/// # Returns
+///
+/// * `CronusResult<CommandResponse>` - Returns a `CommandResponse::RunFinished` with the run, the response of the service if the job cannot be added or triggered, or an error if the service cannot be reached.
+fn exec_job(cc: &CommandClient, job: Job, options: JobOptions) -> CronusResult<CommandResponse> {
+    let id = match cc
+        .add_job(Trigger::Manual, job, options, false, false, false)?
+        .without_warnings()
+    {
+        CommandResponse::JobAdded(id)
+        | CommandResponse::JobDuplicated(id, _)
+        | CommandResponse::JobReplaced(id) => id,
+        response => return Ok(response),
+    };
+    let run_id = match cc.trigger_job(id.clone())? {
+        CommandResponse::JobTriggered(Some(run_id)) => run_id,
+        response => return Ok(response),
+    };
+    let mut from = 0;
+    loop {
+        match cc.tail_output(id.clone(), from, Some(run_id))? {
+            CommandResponse::RunOutput(Some(chunk)) => {
+                for line in &chunk.lines {
+                    println!("{line}");
+                }
+                if chunk.finished {
+                    break;
+                }
+                from = chunk.next;
+            }
+            // The run is not in flight until the service has picked it up.
+            CommandResponse::RunOutput(None) => {}
+            response => return Ok(response),
+        }
+        thread::sleep(TAIL_POLL_INTERVAL);
+    }
+    // The run is recorded in the history right after its output is over.
+    loop {
+        if let CommandResponse::RunList(runs) = cc.job_history(id.clone())? {
+            if let Some(run) = runs.into_iter().find(|run| run.run_id == Some(run_id)) {
+                return Ok(CommandResponse::RunFinished(run));
+            }
+        }
+        thread::sleep(TAIL_POLL_INTERVAL);
+    }
+}
+
+/// Checks that a job can join the dependency chains of the jobs registered on the Cronus service.
+///
+/// The name of the job must not be taken yet, and a job running after another job must not close a dependency cycle.
+///
+/// # Arguments
+///
+/// * `cc` - The `CommandClient` connected to the Cronus service.
+/// * `trigger` - The `Trigger` of the job to be added.
+/// * `options` - The `JobOptions` of the job to be added.
+///
+/// # Returns
+///
+/// * `CronusResult<()>` - Returns `Ok(())` if the job can be added, and an error describing the conflict if not.
+fn check_chain(cc: &CommandClient, trigger: &Trigger, options: &JobOptions) -> CronusResult<()> {
+    let CommandResponse::JobList(jobs) = cc.list_jobs()? else {
+        return Err("Unexpected response to list jobs".into());
+    };
+    if let Some(name) = &options.name {
+        if jobs
+            .iter()
+            .any(|job| job.options.name.as_ref() == Some(name))
+        {
+            return Err(format!("Job name `{name}` is already taken").into());
+        }
+    }
+    let mut links: Vec<_> = jobs
+        .iter()
+        .map(|job| ChainLink {
+            id: &job.id,
+            name: job.options.name.as_deref(),
+            after: job.trigger.after(),
+        })
+        .collect();
+    links.push(ChainLink {
+        id: "",
+        name: options.name.as_deref(),
+        after: trigger.after(),
+    });
+    if let Some(cycle) = find_cycle(&links, "") {
+        return Err(format!("Dependency cycle: {}", cycle.join(" -> ")).into());
+    }
+    Ok(())
+}
+
+/// Converges the cron jobs on the Cronus service to the jobs declared in a job file.
+///
+/// The plan is computed against the live jobs, then carried out step by step unless it is a dry run.
+///
+/// # Arguments
+///
+/// * `cc` - The `CommandClient` connected to the Cronus service.
+/// * `specs` - The `JobSpec` instances declared in the job file.
+/// * `prune` - Whether the live jobs with a key that is not declared are removed. Without pruning, only the declared jobs are added or updated.
+/// * `dry_run` - Whether the plan is only computed.
+///
+/// # Returns
+///
+/// * `CronusResult<CommandResponse>` - Returns a `CommandResponse::ApplyPlan` with the plan, carrying the ids of the added jobs unless it is a dry run.
+fn apply_job_specs(
+    cc: &CommandClient,
+    specs: Vec<JobSpec>,
+    prune: bool,
+    dry_run: bool,
+) -> CronusResult<CommandResponse> {
+    let CommandResponse::JobList(mut live) = cc.list_jobs()? else {
+        return Err("Unexpected response to list jobs".into());
+    };
+    if !prune {
+        live.retain(|job| {
+            job.options.key.is_none() || specs.iter().any(|spec| spec.key == job.options.key)
+        });
+    }
+    let mut plan = plan_apply(&specs, &live)?;
+    if dry_run {
+        return Ok(CommandResponse::ApplyPlan(plan));
+    }
+    for step in &mut plan.steps {
+        let spec = specs
+            .iter()
+            .find(|spec| spec.key.as_ref() == Some(&step.key));
+        match (step.action, spec, step.id.clone()) {
+            (PlanAction::Remove, _, Some(id)) => {
+                cc.delete_job(id)?;
+            }
+            (PlanAction::Update, Some(spec), Some(id)) => {
+                cc.update_job(id, spec.trigger.clone(), spec.job.clone(), spec.options())?;
+            }
+            (PlanAction::Add, Some(spec), None) => {
+                if let CommandResponse::JobAdded(id)
+                | CommandResponse::JobDuplicated(id, _)
+                | CommandResponse::JobReplaced(id) = cc
+                    .add_job(
+                        spec.trigger.clone(),
+                        spec.job.clone(),
+                        spec.options(),
+                        false,
+                        false,
+                        false,
+                    )?
+                    .without_warnings()
+                {
+                    step.id = Some(id);
+                }
+            }
+            _ => return Err(format!("Inconsistent plan step for job `{}`", step.key).into()),
+        }
+    }
+    Ok(CommandResponse::ApplyPlan(plan))
+}
+
+/// Parses a date and time given on the command line.
+///
+/// RFC 3339 timestamps are used as is, while `YYYY-MM-DD HH:MM[:SS]` and `YYYY-MM-DD` are interpreted in local time.
+///
+/// # Arguments
+///
+/// * `s` - The string to be parsed.
+///
+/// # Returns
+///
+/// * `Result<DateTime<Utc>, String>` - Returns the parsed date and time, or an error message if the string is not a valid date and time.
+fn parse_datetime(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| format!("Invalid date and time `{s}`"))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .ok_or_else(|| format!("Date and time `{s}` does not exist in local time"))
+}
+
+/// Parses an environment variable given on the command line, written like `NAME=value`.
+///
+/// # Arguments
+///
+/// * `s` - The string to be parsed.
+///
+/// # Returns
+///
+/// * `Result<(String, String), String>` - Returns the name and the value of the variable, or an error message if the string has no name.
+fn parse_env_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!(
+            "Invalid environment variable `{s}`, expected `NAME=value`"
+        )),
+    }
+}
+
+/// Parses a hook given on the command line, a job written like in a job file, in JSON or YAML.
+///
+/// # Arguments
+///
+/// * `s` - The string to be parsed.
+///
+/// # Returns
+///
+/// * `Result<Job, String>` - Returns the job of the hook, or an error message if the string is not a job.
+fn parse_hook(s: &str) -> Result<Job, String> {
+    serde_yaml::from_str(s).map_err(|e| format!("Invalid hook `{s}`: {e}"))
+}
+
+/// Parses a step of a pipeline given on the command line, a job written like in a job file, in JSON or YAML.
+///
+/// # Arguments
+///
+/// * `s` - The string to be parsed.
+///
+/// # Returns
+///
+/// * `Result<Job, String>` - Returns the job of the step, or an error message if the string is not a job.
+fn parse_step(s: &str) -> Result<Job, String> {
+    serde_yaml::from_str(s).map_err(|e| format!("Invalid step `{s}`: {e}"))
+}
+
+/// Checks if the Cronus service is running.
+///
+/// This function sends a ping to the Cronus service and checks the response to determine if the service is running.
+///
+/// # Arguments
+///
+/// * `name` - The name of the Cronus service.
+/// * `path` - The path where the Cronus service is located.
+///
+/// # Returns
+///
+/// * `CronusResult<bool>` - Returns `Ok(true)` if the service is running, `Ok(false)` if the service is not running, and `Err(CronusError)` if there was an error checking the service status.
+fn check_service_running(name: String, path: PathBuf) -> CronusResult<bool> {
+    if let Ok(cc) = CommandClient::new(name, path) {
+        if let Ok(res) = cc.ping_service() {
+            if res == CommandResponse::ServiceRunning {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Starts a new Cronus service.
+///
+/// This function starts a new instance of the Cronus service in a new process. It uses the `daemon` function to create a new child process, and then starts the Cronus service in the child process.
+///
+/// # Arguments
+///
+/// * `name` - The name of the Cronus service.
+/// * `path` - The path where the Cronus service is located.
+/// * `http_addr` - The address the HTTP API of the Cronus service listens on, if any.
+/// * `tcp_addr` - The address the command socket of the Cronus service also listens on over TCP, if any.
+/// * `health_addr` - The address the health probes of the Cronus service are served on, if any.
+/// * `max_parallel` - The maximum number of runs of all jobs the Cronus service keeps in flight at once, if any.
+/// * `config` - The absolute path of the configuration file of the Cronus service, if any.
+/// * `store` - The absolute path of the job store of the Cronus service, if any.
+/// * `store_key` - The absolute path of the key file the job store and the backups of the Cronus service are encrypted with, if any.
+/// * `lease_window` - The window of the lease on the job store the Cronus service shares with another one, if any.
+/// * `reconcile` - The absolute path of the job file the jobs of the Cronus service are converged to, if any.
+/// * `follow` - The name of the Cronus service the new one follows, if any.
+/// * `chaos` - The failures the Cronus service injects in chaos mode, if any.
+///
+/// # Returns
+///
+/// * `CronusResult<()>` - Returns `Ok(())` if the service is started successfully, and `Err(CronusError)` if there was an error starting the service.
+#[allow(clippy::too_many_arguments)]
+fn run_new_service(
+    name: String,
+    path: PathBuf,
+    http_addr: Option<SocketAddr>,
+    tcp_addr: Option<SocketAddr>,
+    health_addr: Option<SocketAddr>,
+    max_parallel: Option<NonZeroUsize>,
+    config: Option<PathBuf>,
+    store: Option<PathBuf>,
+    store_key: Option<PathBuf>,
+    lease_window: Option<Duration>,
+    reconcile: Option<PathBuf>,
+    follow: Option<String>,
+    chaos: Option<Chaos>,
+) -> CronusResult<()> {
+    let cronus = std::env::current_exe()?;
+    match daemon(false, false) {
+        Ok(Fork::Child) => {
+            let mut cmd = std::process::Command::new(cronus);
+            cmd.arg("run")
+                .arg("--name")
+                .arg(name)
+                .arg("--path")
+                .arg(path);
+            if let Some(http_addr) = http_addr {
+                cmd.arg("--http-addr").arg(http_addr.to_string());
+            }
+            if let Some(tcp_addr) = tcp_addr {
+                cmd.arg("--tcp-addr").arg(tcp_addr.to_string());
+            }
+            if let Some(health_addr) = health_addr {
+                cmd.arg("--health-addr").arg(health_addr.to_string());
+            }
+            if let Some(max_parallel) = max_parallel {
+                cmd.arg("--max-parallel").arg(max_parallel.to_string());
+            }
+            if let Some(config) = config {
+                cmd.arg("--config").arg(config);
+            }
+            if let Some(store) = store {
+                cmd.arg("--store").arg(store);
+            }
+            if let Some(store_key) = store_key {
+                cmd.arg("--store-key").arg(store_key);
+            }
+            if let Some(lease_window) = lease_window {
+                cmd.arg("--lease-window")
+                    .arg(humantime::format_duration(lease_window).to_string());
+            }
+            if let Some(reconcile) = reconcile {
+                cmd.arg("--reconcile").arg(reconcile);
+            }
+            if let Some(follow) = follow {
+                cmd.arg("--follow").arg(follow);
+            }
+            if let Some(chaos) = chaos {
+                cmd.arg("--chaos").arg(chaos.to_string());
+            }
+            cmd.spawn()?;
+            std::process::exit(0);
+        }
+        _ => std::process::exit(0),
+    }
+}
+
+#[tokio::main]
+async fn main() -> CronusResult<()> {
+    let (json_result, code) = run().await.unwrap_or_else(|e| {
+        let code = ErrorCode::of_error(e.as_ref());
+        let e = e.to_string();
+        (json!({"error": e, "code": code}).to_string(), Some(code))
+    });
+    println!("{json_result}");
+    if let Some(code) = code {
+        std::process::exit(code.exit_code());
+    }
     Ok(())
 }
@@ -0,0 +1,192 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::tz::parse_timezone;
+
+/// The number of days searched for the next business day, beyond which a calendar is deemed to have none.
+const MAX_SHIFT_DAYS: usize = 366;
+
+/// `HolidayPolicy` is an enumeration that represents what happens to a run of a job due on a day that is not a business day.
+///
+/// # Variants
+///
+/// * `Skip` - Represents skipping the run, emitting a `RunSkipped` event.
+/// * `Shift` - Represents running the job at the same time on the next business day instead, for the tick it was due for.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum HolidayPolicy {
+    #[default]
+    Skip,
+    Shift,
+}
+
+impl FromStr for HolidayPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "shift" => Ok(Self::Shift),
+            _ => Err(format!(
+                "Invalid holiday policy `{s}`, expected `skip` or `shift`"
+            )),
+        }
+    }
+}
+
+/// `BusinessCalendar` is a structure that represents the days a job runs on.
+///
+/// # Fields
+///
+/// * `weekdays_only` - A `bool` that represents whether Saturdays and Sundays are not business days.
+/// * `holidays` - A `BTreeSet<NaiveDate>` that represents the dates that are not business days, read from a holiday calendar file.
+/// * `timezone` - An `Option<Tz>` that represents the timezone the days are counted in. It is `None` if they are counted in local time.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct BusinessCalendar {
+    pub weekdays_only: bool,
+    pub holidays: BTreeSet<NaiveDate>,
+    pub timezone: Option<Tz>,
+}
+
+impl BusinessCalendar {
+    /// Loads the calendar of a job.
+    ///
+    /// # Arguments
+    ///
+    /// * `weekdays_only` - A `bool` that represents whether the job only runs from Monday to Friday.
+    /// * `holidays` - An `Option<&Path>` that represents the holiday calendar file of the job, see `parse_holidays`. It is `None` if the
+    ///   job has no holidays.
+    /// * `timezone` - An `Option<&str>` that represents the IANA name of the timezone of the job. It is `None` for local time.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BusinessCalendar, String>` - Returns the calendar, or an error message if the holiday calendar file cannot be read or
+    ///   is malformed, or the timezone is unknown.
+    pub fn load(
+        weekdays_only: bool,
+        holidays: Option<&Path>,
+        timezone: Option<&str>,
+    ) -> Result<Self, String> {
+        let holidays = match holidays {
+            Some(file) => {
+                let text = std::fs::read_to_string(file).map_err(|e| {
+                    format!("Cannot read the holiday calendar {}: {e}", file.display())
+                })?;
+                parse_holidays(&text)
+                    .map_err(|e| format!("Invalid holiday calendar {}: {e}", file.display()))?
+            }
+            None => BTreeSet::new(),
+        };
+        Ok(Self {
+            weekdays_only,
+            holidays,
+            timezone: timezone.map(parse_timezone).transpose()?,
+        })
+    }
+
+    /// Checks whether a date is a business day of the calendar.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - A `NaiveDate` that represents the date.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the date is not a holiday, nor a Saturday or a Sunday when the calendar only has weekdays.
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !(self.weekdays_only && matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            || self.holidays.contains(&date))
+    }
+
+    /// Returns the date of a time in the timezone of the calendar.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - A `DateTime<Utc>` that represents the time.
+    ///
+    /// # Returns
+    ///
+    /// * `NaiveDate` - Returns the date of the time.
+    pub fn date_of(&self, at: DateTime<Utc>) -> NaiveDate {
+        match &self.timezone {
+            Some(zone) => at.with_timezone(zone).date_naive(),
+            None => at.with_timezone(&Local).date_naive(),
+        }
+    }
+
+    /// Finds the time a run due on a day that is not a business day is shifted to.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - A `DateTime<Utc>` that represents the time the run is due.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<DateTime<Utc>>` - Returns the same time of day on the first business day after the one of `at`, or `None` if there
+    ///   is none within a year.
+    pub fn next_business_time(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let date = self
+            .date_of(at)
+            .iter_days()
+            .skip(1)
+            .take(MAX_SHIFT_DAYS)
+            .find(|date| self.is_business_day(*date))?;
+        match &self.timezone {
+            Some(zone) => same_time_on(at, date, zone),
+            None => same_time_on(at, date, &Local),
+        }
+    }
+}
+
+/// Parses a holiday calendar, one date per line, written `YYYY-MM-DD` and optionally followed by the name of the holiday, e.g.
+/// `2026-12-25 Christmas Day`. Blank lines, and anything following a `#`, are ignored.
+///
+/// # Arguments
+///
+/// * `text` - A string that represents the holiday calendar.
+///
+/// # Returns
+///
+/// * `Result<BTreeSet<NaiveDate>, String>` - Returns the dates of the holidays, or an error message describing the first malformed
+///   line.
+pub fn parse_holidays(text: &str) -> Result<BTreeSet<NaiveDate>, String> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(number, line)| {
+            let line = line.split('#').next().unwrap_or_default();
+            Some((number + 1, line.split_whitespace().next()?))
+        })
+        .map(|(number, date)| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| format!("line {number}: invalid date `{date}`, expected YYYY-MM-DD"))
+        })
+        .collect()
+}
+
+/// Moves a time to another date, keeping its time of day in a timezone.
+///
+/// # Arguments
+///
+/// * `at` - A `DateTime<Utc>` that represents the time.
+/// * `date` - A `NaiveDate` that represents the date to move the time to.
+/// * `zone` - A reference to the timezone the time of day is kept in.
+///
+/// # Returns
+///
+/// * `Option<DateTime<Utc>>` - Returns the time on the date, the earliest one if it occurs twice, or `None` if it is skipped by a
+///   DST change on that date.
+fn same_time_on<Z: TimeZone>(
+    at: DateTime<Utc>,
+    date: NaiveDate,
+    zone: &Z,
+) -> Option<DateTime<Utc>> {
+    let time = at.with_timezone(zone).time();
+    zone.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|shifted| shifted.with_timezone(&Utc))
+}
@@ -0,0 +1,72 @@
+/// `ChainLink` is a structure that represents a job as a link of a dependency chain.
+///
+/// # Fields
+///
+/// * `id` - A string that represents the unique identifier of the job.
+/// * `name` - An `Option<&str>` that represents the name of the job, if any.
+/// * `after` - An `Option<&str>` that represents the id or the name of the job this job runs after, if any.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainLink<'a> {
+    pub id: &'a str,
+    pub name: Option<&'a str>,
+    pub after: Option<&'a str>,
+}
+
+impl ChainLink<'_> {
+    /// Checks whether a reference designates the job of the link.
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - A string that represents the id or the name of a job.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the reference is the id or the name of the job.
+    pub fn is_referred_by(&self, reference: &str) -> bool {
+        self.id == reference || self.name == Some(reference)
+    }
+
+    /// Returns the label of the job in a dependency chain.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - Returns the name of the job, or its id if it has no name.
+    fn label(&self) -> &str {
+        self.name.unwrap_or(self.id)
+    }
+}
+
+/// Finds a dependency cycle going through a job.
+///
+/// Every job runs after at most one other job, so the chain is followed from the given job until it either ends or comes back.
+/// A job waiting for a job that is not registered ends the chain.
+///
+/// # Arguments
+///
+/// * `links` - A slice of `ChainLink` instances that represent all the jobs, including the given one.
+/// * `start` - A string that represents the id of the job the cycle must go through.
+///
+/// # Returns
+///
+/// * `Option<Vec<String>>` - Returns the labels of the jobs forming the cycle, starting and ending with the given job, or `None` if there is no cycle.
+pub fn find_cycle(links: &[ChainLink], start: &str) -> Option<Vec<String>> {
+    let mut current = links.iter().find(|link| link.id == start)?;
+    let mut visited = vec![current];
+    while let Some(after) = current.after {
+        current = links.iter().find(|link| link.is_referred_by(after))?;
+        if current.id == start {
+            visited.push(current);
+            return Some(
+                visited
+                    .iter()
+                    .map(|link| link.label().to_string())
+                    .collect(),
+            );
+        }
+        if visited.iter().any(|link| link.id == current.id) {
+            return None;
+        }
+        visited.push(current);
+    }
+    None
+}
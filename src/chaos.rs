@@ -0,0 +1,159 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use rand::Rng;
+
+/// The longest delay injected into a tick by default.
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// The failures injected into the process, set once when the service starts in chaos mode.
+static CHAOS: OnceLock<Chaos> = OnceLock::new();
+
+/// `Chaos` is a structure that represents the failures a service injects in chaos mode, a developer mode for validating the error
+/// handling of tools built on top of cronus.
+///
+/// It is written like `delay=0.2,max-delay=10s,fail=0.1,drop=0.05`, every key being optional.
+///
+/// # Fields
+///
+/// * `delay` - A `f64` that represents the rate of the ticks delayed, from 0 to 1.
+/// * `max_delay` - A `Duration` that represents the longest delay injected into a tick. It is 5 seconds by default.
+/// * `fail` - A `f64` that represents the rate of the runs failed instead of being carried out, from 0 to 1.
+/// * `drop` - A `f64` that represents the rate of the commands dropped without being handled or answered, from 0 to 1.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Chaos {
+    pub delay: f64,
+    pub max_delay: Duration,
+    pub fail: f64,
+    pub drop: f64,
+}
+
+impl Default for Chaos {
+    fn default() -> Self {
+        Self {
+            delay: 0.0,
+            max_delay: DEFAULT_MAX_DELAY,
+            fail: 0.0,
+            drop: 0.0,
+        }
+    }
+}
+
+impl FromStr for Chaos {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chaos = Self::default();
+        for setting in s.split(',').filter(|setting| !setting.is_empty()) {
+            let (key, value) = setting.split_once('=').ok_or_else(|| {
+                format!("Invalid chaos setting `{setting}`, expected `key=value`")
+            })?;
+            match key {
+                "delay" => chaos.delay = parse_rate(key, value)?,
+                "max-delay" => {
+                    chaos.max_delay = humantime::parse_duration(value)
+                        .map_err(|e| format!("Invalid chaos max-delay `{value}`: {e}"))?
+                }
+                "fail" => chaos.fail = parse_rate(key, value)?,
+                "drop" => chaos.drop = parse_rate(key, value)?,
+                _ => {
+                    return Err(format!(
+                        "Unknown chaos setting `{key}`, expected `delay`, `max-delay`, `fail` or `drop`"
+                    ))
+                }
+            }
+        }
+        Ok(chaos)
+    }
+}
+
+impl Display for Chaos {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "delay={},max-delay={},fail={},drop={}",
+            self.delay,
+            humantime::format_duration(self.max_delay),
+            self.fail,
+            self.drop
+        )
+    }
+}
+
+/// Parses the rate of a chaos setting.
+///
+/// # Arguments
+///
+/// * `key` - A string that represents the name of the setting.
+/// * `value` - A string that represents the rate.
+///
+/// # Returns
+///
+/// * `Result<f64, String>` - Returns the rate, or an error message if it is not a number from 0 to 1.
+fn parse_rate(key: &str, value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|rate| (0.0..=1.0).contains(rate))
+        .ok_or_else(|| format!("Invalid chaos {key} rate `{value}`, expected a number from 0 to 1"))
+}
+
+/// Turns chaos mode on for the process.
+///
+/// Chaos mode can only be turned on once: the failures of later calls are ignored.
+///
+/// # Arguments
+///
+/// * `chaos` - A `Chaos` that represents the failures to be injected.
+pub fn enable(chaos: Chaos) {
+    _ = CHAOS.set(chaos);
+}
+
+/// Returns the delay injected into a tick.
+///
+/// # Returns
+///
+/// * `Duration` - Returns a random delay up to `max_delay` for `delay` of the ticks, or `Duration::ZERO` if the tick is not delayed or
+///   chaos mode is off.
+pub fn tick_delay() -> Duration {
+    match CHAOS.get() {
+        Some(chaos) if strikes(chaos.delay) => {
+            let millis = rand::thread_rng().gen_range(0..=chaos.max_delay.as_millis() as u64);
+            Duration::from_millis(millis)
+        }
+        _ => Duration::ZERO,
+    }
+}
+
+/// Checks whether a run is failed instead of being carried out.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` for `fail` of the runs, or `false` if chaos mode is off.
+pub fn fails_run() -> bool {
+    CHAOS.get().is_some_and(|chaos| strikes(chaos.fail))
+}
+
+/// Checks whether a command is dropped without being handled or answered.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` for `drop` of the commands, or `false` if chaos mode is off.
+pub fn drops_command() -> bool {
+    CHAOS.get().is_some_and(|chaos| strikes(chaos.drop))
+}
+
+/// Draws whether an injected failure strikes.
+///
+/// # Arguments
+///
+/// * `rate` - A `f64` that represents the rate of the failure, from 0 to 1.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` with a probability of `rate`.
+fn strikes(rate: f64) -> bool {
+    rate > 0.0 && rand::thread_rng().gen_bool(rate)
+}
@@ -1,28 +1,176 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 
-use crate::CronusResult;
-use crate::job::{Job, JobInfo};
+use crate::analyze::Hotspot;
+use crate::apply::Plan;
+use crate::audit::AuditEntry;
+use crate::auth::TOKEN_VAR;
+use crate::doctor::{CheckStatus, Finding};
+use crate::error_code::ErrorCode;
+use crate::events::{Event, EventFeed};
+use crate::follower::ServiceSnapshot;
+use crate::format::FORMAT_VERSION;
+use crate::history::{RunRecord, RunStatus};
+use crate::job::{Job, JobInfo, JobOptions, RunId, Trigger};
+use crate::lint::LintFinding;
+use crate::listing::{JobFilter, JobPage, JobSort};
+use crate::namespace::NAMESPACE_VAR;
 use crate::nng_socket::NngIpcSocket;
+use crate::output::OutputChunk;
+use crate::preview::JobPreview;
+use crate::stats::{CostCenterStats, Digest, JobStats};
+use crate::timeline::Timeline;
+use crate::tz::{TzReport, ZoneInfo};
+use crate::CronusResult;
 
 /// `Command` is an enumeration that represents the different types of commands that can be issued.
 ///
 /// # Variants
 ///
-/// * `AddJob` - Represents a command to add a job. It contains a cron string and a `Job` instance.
-/// * `ListJobs` - Represents a command to list all jobs.
+/// * `AddJob` - Represents a command to add a job. It contains the `Trigger` of the job, a `Job` instance, the `JobOptions` of the job and whether the job is refused if it duplicates a registered job, see `StoredJob::duplicates`, whether it is registered paused, and whether the job is only checked, see `JobPreview`.
+/// * `ListJobs` - Represents a command to list the jobs. It contains the number of jobs skipped, the maximum number of jobs listed, or `None` for every job, the `JobFilter` the jobs listed match and the `JobSort` they are listed in.
+/// * `GetJob` - Represents a command to describe a single job, with the result of its last run. It contains the id of the job.
 /// * `DeleteJob` - Represents a command to delete a job. It contains the id of the job to be deleted.
+/// * `UpdateJob` - Represents a command to replace the declaration of a job, keeping its id and run history. It contains the id of the job, its new `Trigger`, `Job` instance and `JobOptions`.
+/// * `JobHistory` - Represents a command to list the recorded runs of a job. It contains the id of the job.
+/// * `LintJobs` - Represents a command to lint all registered jobs.
+/// * `AnalyzeSchedules` - Represents a command to detect hotspots in the schedules of all registered jobs. It contains the window the schedules are projected over and the number of jobs firing together that makes a hotspot.
+/// * `Timeline` - Represents a command to lay out the past and projected executions of all registered jobs. It contains how far back and ahead the executions are laid out.
+/// * `AckFailure` - Represents a command to acknowledge the last failure of a job. It contains the id of the job.
+/// * `TriggerJob` - Represents a command to run a job right away, regardless of its schedule. It contains the id of the job.
+/// * `RetryRun` - Represents a command to run the job of a failed run again right away, for the same tick as the failed run. It contains the id of the failed run.
+/// * `PauseJob` - Represents a command to suspend the executions of a job. It contains the id of the job.
+/// * `ResumeJob` - Represents a command to resume the executions of a paused job. It contains the id of the job.
+/// * `KillRun` - Represents a command to kill the runs of a job in flight. It contains the id of the job, and the id of the run to be killed, or `None` to kill every run of the job.
+/// * `TailOutput` - Represents a command to read the output of a run of a job. It contains the id of the job, the position of the first line to be read, and the id of the run, or `None` for the run started last.
+/// * `EmitEvent` - Represents a command to emit an event, firing the jobs listening on its topic. It contains the topic and the payload of the event, if any.
+/// * `JobStats` - Represents a command to report the machine time consumed by jobs by day and by month. It contains the id of the job to report on, or `None` for every job.
+/// * `CostCenterStats` - Represents a command to report the machine time charged to every cost center by day and by month.
+/// * `UsageDigest` - Represents a command to report the jobs consuming the most machine time today and this month.
+/// * `CheckTimezones` - Represents a command to check the timezones the jobs are scheduled in against the timezone database of the service.
+/// * `ListEvents` - Represents a command to list the most recent events of the service.
+/// * `ListAudit` - Represents a command to list the most recent management commands received by the service, from its audit log. It contains the id of the job whose commands are listed, or `None` for every command, and the maximum number of commands listed.
+/// * `FollowEvents` - Represents a command to read the events of the service emitted after a position, sent by its followers. It contains the `seq` of the last event already read.
+/// * `Snapshot` - Represents a command to read the jobs, the run history and the state of the jobs of the service, sent by its followers and by `cronus snapshot`.
+/// * `Restore` - Represents a command to register the jobs of a snapshot, with their ids, run history and state, in a service that has no jobs yet. It contains the `ServiceSnapshot`.
+/// * `Promote` - Represents a command to stop following the primary of the service, and schedule the jobs mirrored from it.
+/// * `ReloadConfig` - Represents a command to read the configuration file of the service again, and run its reload hooks.
+/// * `Batch` - Represents a command to carry out several commands adding, updating, deleting, pausing or resuming jobs at once, all of
+///   them or none of them. It contains the commands, carried out in order.
 /// * `StopService` - Represents a command to stop the service.
 /// * `PingService` - Represents a command to ping the service.
+/// * `Unsupported` - Represents a command this version of the service does not know, sent by a newer client.
+///
+/// Commands are serialized as JSON objects tagged with their `command` in snake case, next to the `version` of the format they are written in.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
 pub enum Command {
-    AddJob { cron: String, job: Job },
-    ListJobs,
-    DeleteJob { id: String },
+    AddJob {
+        trigger: Trigger,
+        job: Job,
+        options: JobOptions,
+        #[serde(default)]
+        no_duplicates: bool,
+        #[serde(default)]
+        paused: bool,
+        #[serde(default)]
+        dry_run: bool,
+    },
+    ListJobs {
+        #[serde(default)]
+        offset: usize,
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        filter: JobFilter,
+        #[serde(default)]
+        sort: JobSort,
+    },
+    GetJob {
+        id: String,
+    },
+    DeleteJob {
+        id: String,
+    },
+    UpdateJob {
+        id: String,
+        trigger: Trigger,
+        job: Job,
+        options: JobOptions,
+    },
+    JobHistory {
+        id: String,
+    },
+    LintJobs,
+    AnalyzeSchedules {
+        #[serde(with = "humantime_serde")]
+        window: Duration,
+        threshold: usize,
+    },
+    Timeline {
+        #[serde(with = "humantime_serde")]
+        window: Duration,
+    },
+    AckFailure {
+        id: String,
+    },
+    TriggerJob {
+        id: String,
+    },
+    RetryRun {
+        run_id: RunId,
+    },
+    PauseJob {
+        id: String,
+    },
+    ResumeJob {
+        id: String,
+    },
+    KillRun {
+        id: String,
+        #[serde(default)]
+        run_id: Option<RunId>,
+    },
+    TailOutput {
+        id: String,
+        from: u64,
+        #[serde(default)]
+        run_id: Option<RunId>,
+    },
+    EmitEvent {
+        topic: String,
+        payload: Option<String>,
+    },
+    JobStats {
+        id: Option<String>,
+    },
+    CostCenterStats,
+    UsageDigest,
+    CheckTimezones,
+    ListEvents,
+    ListAudit {
+        id: Option<String>,
+        limit: usize,
+    },
+    FollowEvents {
+        after: u64,
+    },
+    Snapshot,
+    Restore {
+        snapshot: ServiceSnapshot,
+    },
+    Promote,
+    ReloadConfig,
+    Batch {
+        commands: Vec<Command>,
+    },
     StopService,
     PingService,
+    #[serde(other)]
+    Unsupported,
 }
 
 impl Command {
@@ -30,23 +178,58 @@ impl Command {
     ///
     /// # Arguments
     ///
-    /// * `cron` - A cron string that represents the schedule of the job.
+    /// * `trigger` - A `Trigger` instance that represents what makes the job run.
     /// * `job` - A `Job` instance that represents the job to be added.
+    /// * `options` - A `JobOptions` instance that represents the execution settings of the job.
+    /// * `no_duplicates` - A `bool` that represents whether the job is refused if it has the schedule and the payload of a registered job, instead of being added with a warning.
+    /// * `paused` - A `bool` that represents whether the job is registered paused, so it does not run until it is resumed.
+    /// * `dry_run` - A `bool` that represents whether the job is only checked, reporting what adding it would do without registering it.
     ///
     /// # Returns
     ///
     /// * `Command` - Returns a `Command::AddJob` variant.
-    pub fn new_add_job(cron: String, job: Job) -> Self {
-        Self::AddJob { cron, job }
+    pub fn new_add_job(
+        trigger: Trigger,
+        job: Job,
+        options: JobOptions,
+        no_duplicates: bool,
+        paused: bool,
+        dry_run: bool,
+    ) -> Self {
+        Self::AddJob {
+            trigger,
+            job,
+            options,
+            no_duplicates,
+            paused,
+            dry_run,
+        }
     }
 
     /// Creates a new `ListJobs` command.
     ///
+    /// # Arguments
+    ///
+    /// * `offset` - A `usize` that represents the number of jobs skipped.
+    /// * `limit` - An `Option<usize>` that represents the maximum number of jobs listed, or `None` for every job.
+    /// * `filter` - A `JobFilter` that represents which jobs are listed.
+    /// * `sort` - A `JobSort` that represents the order the jobs are listed in.
+    ///
     /// # Returns
     ///
     /// * `Command` - Returns a `Command::ListJobs` variant.
-    pub fn new_list_jobs() -> Self {
-        Self::ListJobs
+    pub fn new_list_jobs(
+        offset: usize,
+        limit: Option<usize>,
+        filter: JobFilter,
+        sort: JobSort,
+    ) -> Self {
+        Self::ListJobs {
+            offset,
+            limit,
+            filter,
+            sort,
+        }
     }
 
     /// Creates a new `DeleteJob` command.
@@ -62,6 +245,328 @@ impl Command {
         Self::DeleteJob { id }
     }
 
+    /// Creates a new `UpdateJob` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job to be updated.
+    /// * `trigger` - A `Trigger` instance that represents what makes the job run.
+    /// * `job` - A `Job` instance that represents the job itself.
+    /// * `options` - A `JobOptions` instance that represents the execution settings of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::UpdateJob` variant.
+    pub fn new_update_job(id: String, trigger: Trigger, job: Job, options: JobOptions) -> Self {
+        Self::UpdateJob {
+            id,
+            trigger,
+            job,
+            options,
+        }
+    }
+
+    /// Creates a new `GetJob` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job described.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::GetJob` variant.
+    pub fn new_get_job(id: String) -> Self {
+        Self::GetJob { id }
+    }
+
+    /// Creates a new `JobHistory` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job whose runs are listed.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::JobHistory` variant.
+    pub fn new_job_history(id: String) -> Self {
+        Self::JobHistory { id }
+    }
+
+    /// Creates a new `LintJobs` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::LintJobs` variant.
+    pub fn new_lint_jobs() -> Self {
+        Self::LintJobs
+    }
+
+    /// Creates a new `AnalyzeSchedules` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - A `Duration` that represents how far ahead the schedules are projected.
+    /// * `threshold` - A `usize` that represents the number of distinct jobs firing together that makes a hotspot.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::AnalyzeSchedules` variant.
+    pub fn new_analyze_schedules(window: Duration, threshold: usize) -> Self {
+        Self::AnalyzeSchedules { window, threshold }
+    }
+
+    /// Creates a new `Timeline` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - A `Duration` that represents how far back and ahead the executions are laid out.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::Timeline` variant.
+    pub fn new_timeline(window: Duration) -> Self {
+        Self::Timeline { window }
+    }
+
+    /// Creates a new `AckFailure` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job whose failure is acknowledged.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::AckFailure` variant.
+    pub fn new_ack_failure(id: String) -> Self {
+        Self::AckFailure { id }
+    }
+
+    /// Creates a new `TriggerJob` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job to be run.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::TriggerJob` variant.
+    pub fn new_trigger_job(id: String) -> Self {
+        Self::TriggerJob { id }
+    }
+
+    /// Creates a new `RetryRun` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - A `RunId` that represents the id of the failed run to be retried.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::RetryRun` variant.
+    pub fn new_retry_run(run_id: RunId) -> Self {
+        Self::RetryRun { run_id }
+    }
+
+    /// Creates a new `PauseJob` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job to be paused.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::PauseJob` variant.
+    pub fn new_pause_job(id: String) -> Self {
+        Self::PauseJob { id }
+    }
+
+    /// Creates a new `ResumeJob` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job to be resumed.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::ResumeJob` variant.
+    pub fn new_resume_job(id: String) -> Self {
+        Self::ResumeJob { id }
+    }
+
+    /// Creates a new `KillRun` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job whose runs are killed.
+    /// * `run_id` - An `Option<RunId>` that represents the id of the run to be killed. It is `None` to kill every run of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::KillRun` variant.
+    pub fn new_kill_run(id: String, run_id: Option<RunId>) -> Self {
+        Self::KillRun { id, run_id }
+    }
+
+    /// Creates a new `TailOutput` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job whose output is read.
+    /// * `from` - A `u64` that represents the position of the first line to be read, `0` for the first line of the run.
+    /// * `run_id` - An `Option<RunId>` that represents the id of the run whose output is read. It is `None` for the run started last.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::TailOutput` variant.
+    pub fn new_tail_output(id: String, from: u64, run_id: Option<RunId>) -> Self {
+        Self::TailOutput { id, from, run_id }
+    }
+
+    /// Creates a new `EmitEvent` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - A string that represents the topic the event is emitted on.
+    /// * `payload` - An `Option<String>` that represents the payload of the event, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::EmitEvent` variant.
+    pub fn new_emit_event(topic: String, payload: Option<String>) -> Self {
+        Self::EmitEvent { topic, payload }
+    }
+
+    /// Creates a new `JobStats` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - An `Option<String>` that represents the id of the job to report on, or `None` for every job.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::JobStats` variant.
+    pub fn new_job_stats(id: Option<String>) -> Self {
+        Self::JobStats { id }
+    }
+
+    /// Creates a new `CostCenterStats` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::CostCenterStats` variant.
+    pub fn new_cost_center_stats() -> Self {
+        Self::CostCenterStats
+    }
+
+    /// Creates a new `UsageDigest` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::UsageDigest` variant.
+    pub fn new_usage_digest() -> Self {
+        Self::UsageDigest
+    }
+
+    /// Creates a new `CheckTimezones` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::CheckTimezones` variant.
+    pub fn new_check_timezones() -> Self {
+        Self::CheckTimezones
+    }
+
+    /// Creates a new `ListEvents` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::ListEvents` variant.
+    pub fn new_list_events() -> Self {
+        Self::ListEvents
+    }
+
+    /// Creates a new `FollowEvents` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - A `u64` that represents the `seq` of the last event already read, `0` for none.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::FollowEvents` variant.
+    pub fn new_follow_events(after: u64) -> Self {
+        Self::FollowEvents { after }
+    }
+
+    /// Creates a new `Snapshot` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::Snapshot` variant.
+    pub fn new_snapshot() -> Self {
+        Self::Snapshot
+    }
+
+    /// Creates a new `Restore` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - A `ServiceSnapshot` that represents the jobs, the run history and the state of the jobs to be restored.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::Restore` variant.
+    pub fn new_restore(snapshot: ServiceSnapshot) -> Self {
+        Self::Restore { snapshot }
+    }
+
+    /// Creates a new `Promote` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::Promote` variant.
+    pub fn new_promote() -> Self {
+        Self::Promote
+    }
+
+    /// Creates a new `ReloadConfig` command.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::ReloadConfig` variant.
+    pub fn new_reload_config() -> Self {
+        Self::ReloadConfig
+    }
+
+    /// Creates a new `Batch` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - A vector of `Command` instances that represent the commands carried out, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::Batch` variant.
+    pub fn new_batch(commands: Vec<Command>) -> Self {
+        Self::Batch { commands }
+    }
+
+    /// Checks whether the command may be carried out as part of a `Batch` command.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the command adds, updates, deletes, pauses or resumes a job, which a batch can undo, and `false` if not.
+    pub fn is_batchable(&self) -> bool {
+        matches!(
+            self,
+            Self::AddJob { .. }
+                | Self::UpdateJob { .. }
+                | Self::DeleteJob { .. }
+                | Self::PauseJob { .. }
+                | Self::ResumeJob { .. }
+        )
+    }
+
     /// Creates a new `StopService` command.
     ///
     /// # Returns
@@ -80,16 +585,131 @@ impl Command {
         Self::PingService
     }
 
+    /// Creates a new `ListAudit` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - An `Option<String>` that represents the id of the job whose commands are listed, or `None` for every command.
+    /// * `limit` - A `usize` that represents the maximum number of commands listed.
+    ///
+    /// # Returns
+    ///
+    /// * `Command` - Returns a `Command::ListAudit` variant.
+    pub fn new_list_audit(id: Option<String>, limit: usize) -> Self {
+        Self::ListAudit { id, limit }
+    }
+
+    /// Checks whether the command manages the service, which is recorded in its audit log.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the command changes or runs a job, see `mutates_jobs`, promotes the service, reloads its configuration
+    ///   or stops it, and `false` if it only reads the state of the service.
+    pub fn is_audited(&self) -> bool {
+        self.mutates_jobs()
+            || matches!(self, Self::Promote | Self::ReloadConfig | Self::StopService)
+    }
+
+    /// Returns the id of the job the command targets.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - Returns the id of the job the command deletes, updates, acknowledges, triggers, pauses, resumes or kills, or
+    ///   `None` if the command targets no existing job.
+    pub fn job_id(&self) -> Option<&str> {
+        match self {
+            Self::DeleteJob { id }
+            | Self::UpdateJob { id, .. }
+            | Self::AckFailure { id }
+            | Self::TriggerJob { id }
+            | Self::PauseJob { id }
+            | Self::ResumeJob { id }
+            | Self::KillRun { id, .. } => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the command changes the jobs of the service or runs them, which a follower refuses.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the command adds, deletes, updates, acknowledges, triggers, pauses, resumes or kills a job, retries
+    ///   a run, emits an event, restores a snapshot, or is a batch.
+    pub fn mutates_jobs(&self) -> bool {
+        matches!(
+            self,
+            Self::AddJob { .. }
+                | Self::DeleteJob { .. }
+                | Self::UpdateJob { .. }
+                | Self::AckFailure { .. }
+                | Self::TriggerJob { .. }
+                | Self::RetryRun { .. }
+                | Self::PauseJob { .. }
+                | Self::ResumeJob { .. }
+                | Self::KillRun { .. }
+                | Self::EmitEvent { .. }
+                | Self::Restore { .. }
+                | Self::Batch { .. }
+        )
+    }
+
+    /// Checks whether the command only reads the state of the service, which the service answers on a task of its own.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the command lists, gets, lints, analyzes, tails, summarizes or follows jobs, runs, events or audit
+    ///   entries, or takes a snapshot.
+    pub fn is_query(&self) -> bool {
+        matches!(
+            self,
+            Self::ListJobs { .. }
+                | Self::GetJob { .. }
+                | Self::JobHistory { .. }
+                | Self::LintJobs
+                | Self::AnalyzeSchedules { .. }
+                | Self::Timeline { .. }
+                | Self::TailOutput { .. }
+                | Self::JobStats { .. }
+                | Self::CostCenterStats
+                | Self::UsageDigest
+                | Self::CheckTimezones
+                | Self::ListEvents
+                | Self::ListAudit { .. }
+                | Self::FollowEvents { .. }
+                | Self::Snapshot
+        )
+    }
+
     /// Converts the `Command` instance into a byte vector.
     ///
+    /// The command is written in the current version of the format, `FORMAT_VERSION`, with the token and the namespace of the client,
+    /// if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `envelope` - A reference to the `Envelope` that represents what the client presents to the service along with the command.
+    ///
     /// # Returns
     ///
     /// * `CronusResult<Vec<u8>>` - Returns a `CronusResult` that contains a byte vector on success or an error.
-    pub fn to_bytes(&self) -> CronusResult<Vec<u8>> {
-        serde_json::to_vec(self).map_err(Into::into)
+    pub fn to_bytes(&self, envelope: &Envelope) -> CronusResult<Vec<u8>> {
+        let mut cmd = serde_json::to_value(self)?;
+        if let Some(cmd) = cmd.as_object_mut() {
+            cmd.insert("version".to_string(), json!(FORMAT_VERSION));
+            if let Some(token) = &envelope.token {
+                cmd.insert("token".to_string(), json!(token));
+            }
+            if let Some(namespace) = &envelope.namespace {
+                cmd.insert("namespace".to_string(), json!(namespace));
+            }
+        }
+        serde_json::to_vec(&cmd).map_err(Into::into)
     }
 
-    /// Creates a `Command` instance from a byte slice.
+    /// Creates a `Command` instance from a byte slice, with the token and the namespace the client presented.
+    ///
+    /// A command of an unknown type, or written in a newer version of the format, is read as a `Command::Unsupported`. A command without
+    /// a `version` is read as written in the current version.
     ///
     /// # Arguments
     ///
@@ -97,9 +717,51 @@ impl Command {
     ///
     /// # Returns
     ///
-    /// * `CronusResult<Command>` - Returns a `CronusResult` that contains a `Command` instance on success or an error.
-    pub fn from_bytes(cmd: &[u8]) -> CronusResult<Self> {
-        serde_json::from_slice::<Self>(cmd).map_err(Into::into)
+    /// * `CronusResult<(Command, Envelope)>` - Returns a `CronusResult` that contains a `Command` instance and the `Envelope` of the
+    ///   client on success or an error.
+    pub fn from_bytes(cmd: &[u8]) -> CronusResult<(Self, Envelope)> {
+        let cmd: Value = serde_json::from_slice(cmd)?;
+        let string = |field| cmd.get(field).and_then(Value::as_str).map(str::to_string);
+        let envelope = Envelope {
+            token: string("token"),
+            namespace: string("namespace"),
+        };
+        if cmd
+            .get("version")
+            .and_then(Value::as_u64)
+            .is_some_and(|version| version > FORMAT_VERSION)
+        {
+            return Ok((Self::Unsupported, envelope));
+        }
+        Ok((serde_json::from_value::<Self>(cmd)?, envelope))
+    }
+}
+
+/// `Envelope` is a structure that represents what a client presents to the service along with each of its commands.
+///
+/// # Fields
+///
+/// * `token` - An `Option<String>` that represents the token the client presents. It is `None` if it presents none.
+/// * `namespace` - An `Option<String>` that represents the namespace the client works in, see `Scope`. It is `None` if it names none,
+///   in which case it works in the namespace of its token, or else in the `default` namespace.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Envelope {
+    pub token: Option<String>,
+    pub namespace: Option<String>,
+}
+
+impl Envelope {
+    /// Creates the `Envelope` of a client from its environment: the token of the `CRONUS_TOKEN` environment variable and the namespace
+    /// of the `CRONUS_NAMESPACE` environment variable, if set.
+    ///
+    /// # Returns
+    ///
+    /// * `Envelope` - Returns the envelope of the client.
+    pub fn from_env() -> Self {
+        Self {
+            token: std::env::var(TOKEN_VAR).ok(),
+            namespace: std::env::var(NAMESPACE_VAR).ok(),
+        }
     }
 }
 
@@ -108,15 +770,117 @@ impl Command {
 /// # Variants
 ///
 /// * `JobAdded(String)` - Represents a response for a successful `AddJob` command. It contains a string that represents the id of the added job.
-/// * `JobList(Vec<JobInfo>)` - Represents a response for a `ListJobs` command. It contains a vector of `JobInfo` instances that represent the list of jobs.
+/// * `JobDuplicated(String, String)` - Represents a response for a successful `AddJob` command whose job has the schedule and the payload of a registered job, added nonetheless. It contains the id of the added job and the id of the registered job.
+/// * `DuplicateJob(String)` - Represents a response for an `AddJob` command refusing duplicates whose job has the schedule and the payload of a registered job, in which case it is not added. It contains the id of the registered job.
+/// * `JobReplaced(String)` - Represents a response for a successful `AddJob` command whose key is the key of a registered job, which is updated in place instead of adding another job. It contains a string that represents the id of the existing job.
+/// * `JobPreview(JobPreview)` - Represents a response for an `AddJob` command that is a dry run. It contains a `JobPreview` instance that represents what adding the job would do.
+/// * `JobList(Vec<JobInfo>)` - Represents a response for a `ListJobs` command without a limit. It contains a vector of `JobInfo` instances that represent the list of jobs.
+/// * `JobPage(JobPage)` - Represents a response for a `ListJobs` command with a limit. It contains a `JobPage` instance that represents the jobs of the page, with the offset of the next one.
+/// * `JobDetail(Option<JobInfo>)` - Represents a response for a `GetJob` command. It contains a `JobInfo` instance that represents the job, or `None` if no job has the id.
 /// * `JobDeleted` - Represents a response for a successful `DeleteJob` command.
+/// * `JobUpdated` - Represents a response for a successful `UpdateJob` command.
+/// * `ApplyPlan(Plan)` - Represents the result of an apply. It contains a `Plan` instance that represents the changes planned, or carried out unless it was a dry run.
+/// * `RunList(Vec<RunRecord>)` - Represents a response for a `JobHistory` command. It contains a vector of `RunRecord` instances that represent the recorded runs of the job.
+/// * `LintReport(Vec<LintFinding>)` - Represents a response for a `LintJobs` command. It contains a vector of `LintFinding` instances that represent the smells detected in the registered jobs.
+/// * `Hotspots(Vec<Hotspot>)` - Represents a response for an `AnalyzeSchedules` command. It contains a vector of `Hotspot` instances that represent the times of day where many jobs fire simultaneously.
+/// * `Timeline(Timeline)` - Represents a response for a `Timeline` command. It contains a `Timeline` instance that represents the past and projected executions of the jobs.
+/// * `TimelineExported(PathBuf)` - Represents a timeline written to a file. It contains the path of the file.
+/// * `FailureAcknowledged` - Represents a response for a successful `AckFailure` command.
+/// * `JobTriggered(Option<RunId>)` - Represents a response for a successful `TriggerJob` command. It contains the id of the run started, or `None` if the job is not registered.
+/// * `RunRetried(String, RunId)` - Represents a response for a successful `RetryRun` command. It contains the id of the job of the run and the id of the run started.
+/// * `JobPaused` - Represents a response for a successful `PauseJob` command.
+/// * `JobResumed` - Represents a response for a successful `ResumeJob` command.
+/// * `RunKilled(bool)` - Represents a response for a successful `KillRun` command. It contains a `bool` that represents whether a run of the job was in flight and has been killed.
+/// * `RunOutput(Option<OutputChunk>)` - Represents a response for a `TailOutput` command. It contains an `OutputChunk` instance that represents the lines of output read, or `None` if the job has not run since the service started, or the run is neither in flight nor the one started last.
+/// * `OutputTailed(bool)` - Represents the end of a tail of the output of a run. It contains a `bool` that represents whether the run is over.
+/// * `RunFinished(RunRecord)` - Represents the end of a run of `cronus exec`. It contains a `RunRecord` instance that represents the run, as recorded in the history of its job.
+/// * `EventEmitted(Vec<String>)` - Represents a response for a successful `EmitEvent` command. It contains a vector of strings that represent the ids of the jobs fired by the event.
+/// * `Stats(Vec<JobStats>)` - Represents a response for a `JobStats` command. It contains a vector of `JobStats` instances that represent the machine time consumed by the jobs.
+/// * `CostCenterStats(Vec<CostCenterStats>)` - Represents a response for a `CostCenterStats` command. It contains a vector of `CostCenterStats` instances that represent the machine time charged to the cost centers.
+/// * `Digest(Digest)` - Represents a response for a `UsageDigest` command. It contains a `Digest` instance that represents the jobs consuming the most machine time.
+/// * `TimezoneReport(TzReport)` - Represents a response for a `CheckTimezones` command. It contains a `TzReport` instance that represents the state of the timezone database the schedules rely on.
+/// * `ZoneList(Vec<ZoneInfo>)` - Represents the timezones of the timezone database of the service. It contains a vector of `ZoneInfo` instances that represent the timezones.
+/// * `EventList(Vec<Event>)` - Represents a response for a `ListEvents` command. It contains a vector of `Event` instances that represent the most recent events of the service.
+/// * `AuditLog(Vec<AuditEntry>)` - Represents a response for a `ListAudit` command. It contains a vector of `AuditEntry` instances that represent the most recent management commands received by the service, oldest first.
+/// * `EventFeed(EventFeed)` - Represents a response for a `FollowEvents` command. It contains an `EventFeed` instance that represents the events emitted after the position.
+/// * `Snapshot(ServiceSnapshot)` - Represents a response for a `Snapshot` command. It contains a `ServiceSnapshot` instance that represents the jobs, the run history and the state of the jobs of the service.
+/// * `SnapshotExported(PathBuf, usize)` - Represents a snapshot written to an archive. It contains the path of the archive and the number of jobs archived.
+/// * `StoreEncrypted(PathBuf, usize)` - Represents a job store written in clear that was encrypted. It contains the path of the store and the number of jobs it holds.
+/// * `Restored(Vec<String>)` - Represents a response for a successful `Restore` command. It contains a vector of strings that represent the ids of the restored jobs.
+/// * `RestoreRejected(String)` - Represents a response for a `Restore` command sent to a service that has jobs already, in which case nothing is restored. It contains a message that describes why the snapshot was rejected.
+/// * `Promoted(Option<String>)` - Represents a response for a `Promote` command. It contains the name of the primary the service stopped following, or `None` if the service was not following any.
+/// * `ReadOnly(String)` - Represents a response for a command a follower refuses, as it changes or runs jobs. It contains the name of the primary the service follows.
+/// * `Forbidden(String)` - Represents a response for a command the token of the client does not permit, or adding or updating a job the policy of the service denies. It contains a message that describes why the command is forbidden.
+/// * `ConfigReloaded(Vec<RunRecord>)` - Represents a response for a successful `ReloadConfig` command. It contains a vector of `RunRecord` instances that represent the runs of the reload hooks.
+/// * `ConfigRejected(String)` - Represents a response for a `ReloadConfig` command whose configuration file is invalid, in which case the service keeps its configuration. It contains a message that describes why the file was rejected.
+/// * `Batch(Vec<CommandResponse>)` - Represents a response for a successful `Batch` command. It contains a vector of `CommandResponse` instances that represent the responses of its commands, in order.
+/// * `BatchAborted(usize, String)` - Represents a response for a `Batch` command one of whose commands failed, in which case the changes of the commands before it are undone. It contains the position of the failed command, from 0, and a message that describes why it failed.
+/// * `DoctorReport(Vec<Finding>)` - Represents the diagnosis of the environment of a service by `cronus doctor`. It contains a vector of `Finding` instances that represent the outcome of every check.
+/// * `Unsupported(String)` - Represents a response for a command, or a job, this version of the service does not support. It contains a message that describes what is not supported.
+/// * `AgentUnavailable(String, String)` - Represents a response of a controller for a command one of its agents did not answer. It contains the name of the agent and the error it failed with.
+/// * `Error { code, message }` - Represents a response for a request the service failed to read or to carry out, e.g. a request that is not a valid command. It contains the `ErrorCode` of the failure and a message that describes it.
+/// * `Throttled(String)` - Represents a response for a request refused without being handled, as it is too large or its client sent too many requests, see `ThrottleConfig`. It contains a message that describes why the request was refused.
+/// * `QuotaExceeded(String)` - Represents a response for adding or updating a job beyond the quota of the service, see `JobQuota`. It contains a message that describes which limit the job exceeds.
+/// * `Warned(Box<CommandResponse>, Vec<String>)` - Represents a response that succeeded with non-fatal problems, e.g. a job added with a
+///   schedule firing every second. It contains the response, and the messages that describe the problems, printed in a `warnings` array.
 /// * `ServiceRunning` - Represents a response for a successful `PingService` command.
 /// * `ServiceStopped` - Represents a response for a successful `StopService` command.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum CommandResponse {
     JobAdded(String),
+    JobDuplicated(String, String),
+    DuplicateJob(String),
+    JobReplaced(String),
+    JobPreview(JobPreview),
     JobList(Vec<JobInfo>),
+    JobPage(JobPage),
+    JobDetail(Option<JobInfo>),
     JobDeleted,
+    JobUpdated,
+    ApplyPlan(Plan),
+    RunList(Vec<RunRecord>),
+    LintReport(Vec<LintFinding>),
+    Hotspots(Vec<Hotspot>),
+    Timeline(Timeline),
+    TimelineExported(PathBuf),
+    FailureAcknowledged,
+    JobTriggered(Option<RunId>),
+    RunRetried(String, RunId),
+    JobPaused,
+    JobResumed,
+    RunKilled(bool),
+    RunOutput(Option<OutputChunk>),
+    OutputTailed(bool),
+    RunFinished(RunRecord),
+    EventEmitted(Vec<String>),
+    Stats(Vec<JobStats>),
+    CostCenterStats(Vec<CostCenterStats>),
+    Digest(Digest),
+    TimezoneReport(TzReport),
+    ZoneList(Vec<ZoneInfo>),
+    EventList(Vec<Event>),
+    AuditLog(Vec<AuditEntry>),
+    EventFeed(EventFeed),
+    Snapshot(ServiceSnapshot),
+    SnapshotExported(PathBuf, usize),
+    StoreEncrypted(PathBuf, usize),
+    Restored(Vec<String>),
+    RestoreRejected(String),
+    Promoted(Option<String>),
+    ReadOnly(String),
+    Forbidden(String),
+    ConfigReloaded(Vec<RunRecord>),
+    ConfigRejected(String),
+    Batch(Vec<CommandResponse>),
+    BatchAborted(usize, String),
+    DoctorReport(Vec<Finding>),
+    Unsupported(String),
+    AgentUnavailable(String, String),
+    Throttled(String),
+    Error { code: ErrorCode, message: String },
+    QuotaExceeded(String),
+    Warned(Box<CommandResponse>, Vec<String>),
     ServiceRunning,
     ServiceStopped,
 }
@@ -146,68 +910,354 @@ impl CommandResponse {
 
     /// Converts the `CommandResponse` instance into a JSON message.
     ///
-    /// This function serializes the `CommandResponse` instance into a JSON string. The structure of the JSON string depends on the variant of the `CommandResponse` instance.
+    /// This function serializes the `CommandResponse` instance into a JSON string, see `to_json`.
     ///
     /// # Returns
     ///
     /// * `String` - Returns a JSON string that represents the `CommandResponse` instance.
     pub fn to_json_msg(&self) -> String {
-        let json_msg = match self {
-            Self::JobAdded(id) => json!({"job_id": id}),
-            Self::JobList(jobs) => json!(jobs),
-            Self::JobDeleted => json!({"message": "Job deleted"}),
-            Self::ServiceRunning => json!({"message": "Service running"}),
-            Self::ServiceStopped => json!({"message": "Service stopped"}),
-        };
-        json_msg.to_string()
+        self.to_json().to_string()
     }
-}
-
-/// `CommandClient` is a struct that wraps an `NngIpcSocket` instance.
-///
-/// It provides methods to send different types of `Command` instances to the socket and receive `CommandResponse` instances.
-///
-/// # Fields
-///
-/// * `NngIpcSocket` - An instance of `NngIpcSocket` that is used to send and receive commands.
-pub struct CommandClient(NngIpcSocket);
 
-impl CommandClient {
-    /// Creates a new `CommandProxy` instance.
+    /// Attaches warnings to the `CommandResponse` instance.
     ///
     /// # Arguments
     ///
-    /// * `name` - A string that represents the name of the socket.
-    /// * `path` - A `PathBuf` that represents the path of the socket.
+    /// * `warnings` - A vector of strings that represent the non-fatal problems of the command.
     ///
     /// # Returns
     ///
-    /// * `CronusResult<CommandClient>` - Returns a `CronusResult` that contains a `CommandClient` instance on success or an error.
-    pub fn new(name: String, path: PathBuf) -> CronusResult<Self> {
-        Ok(Self(NngIpcSocket::new_dial(path.join(name))?))
+    /// * `CommandResponse` - Returns a `CommandResponse::Warned` with the warnings, or the response itself if there are none.
+    pub fn with_warnings(self, warnings: Vec<String>) -> Self {
+        match (self, warnings.is_empty()) {
+            (response, true) => response,
+            (Self::Warned(response, mut previous), false) => {
+                previous.extend(warnings);
+                Self::Warned(response, previous)
+            }
+            (response, false) => Self::Warned(Box::new(response), warnings),
+        }
     }
 
-    /// Sends an `AddJob` command to the socket.
+    /// Detaches the warnings of the `CommandResponse` instance, for a client only interested in its outcome.
     ///
-    /// # Arguments
+    /// # Returns
+    ///
+    /// * `CommandResponse` - Returns the response without its warnings.
+    pub fn without_warnings(self) -> Self {
+        match self {
+            Self::Warned(response, _) => *response,
+            response => response,
+        }
+    }
+
+    /// Returns the kind of failure the `CommandResponse` instance reports, if it reports one.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<ErrorCode>` - Returns the code of the failure, or `None` if the command succeeded.
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::DuplicateJob(_) | Self::RestoreRejected(_) => Some(ErrorCode::Conflict),
+            Self::JobDetail(None) | Self::RunOutput(None) => Some(ErrorCode::NotFound),
+            Self::ReadOnly(_) => Some(ErrorCode::ReadOnly),
+            Self::Forbidden(_) => Some(ErrorCode::Forbidden),
+            Self::ConfigRejected(_) | Self::BatchAborted(..) => Some(ErrorCode::Rejected),
+            Self::JobPreview(preview) if preview.rejected() => Some(ErrorCode::Rejected),
+            Self::RunFinished(run) if run.status == RunStatus::Failed => Some(ErrorCode::Failed),
+            Self::Unsupported(_) => Some(ErrorCode::Unsupported),
+            Self::AgentUnavailable(..) => Some(ErrorCode::ServiceUnavailable),
+            Self::Throttled(_) => Some(ErrorCode::Throttled),
+            Self::Error { code, .. } => Some(*code),
+            Self::QuotaExceeded(_) => Some(ErrorCode::QuotaExceeded),
+            Self::Warned(response, _) => response.error_code(),
+            Self::DoctorReport(findings)
+                if findings
+                    .iter()
+                    .any(|finding| finding.status == CheckStatus::Error) =>
+            {
+                Some(ErrorCode::Failed)
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts the `CommandResponse` instance into a JSON value.
+    ///
+    /// The structure of the JSON value depends on the variant of the `CommandResponse` instance. The JSON object of a failure also
+    /// holds its `code`, see `error_code`.
+    ///
+    /// # Returns
+    ///
+    /// * `Value` - Returns a JSON value that represents the `CommandResponse` instance.
+    pub fn to_json(&self) -> Value {
+        let mut json = self.to_json_body();
+        if let (Some(code), Some(object)) = (self.error_code(), json.as_object_mut()) {
+            object.insert("code".to_string(), json!(code));
+        }
+        json
+    }
+
+    /// Converts the `CommandResponse` instance into a JSON value, without the code of its failure.
+    ///
+    /// # Returns
+    ///
+    /// * `Value` - Returns a JSON value that represents the `CommandResponse` instance.
+    fn to_json_body(&self) -> Value {
+        match self {
+            Self::JobAdded(id) => json!({"job_id": id, "created": true}),
+            Self::JobDuplicated(id, duplicated) => json!({
+                "job_id": id,
+                "created": true,
+                "warning": format!("Job has the same schedule and payload as job `{duplicated}`")
+            }),
+            Self::DuplicateJob(duplicated) => json!({
+                "message": "Duplicate job",
+                "error": format!("Job has the same schedule and payload as job `{duplicated}`")
+            }),
+            Self::JobReplaced(id) => json!({"job_id": id, "created": false}),
+            Self::JobPreview(preview) => {
+                let mut json = json!(preview);
+                json["dry_run"] = json!(true);
+                json
+            }
+            Self::JobList(jobs) => json!(jobs),
+            Self::JobPage(page) => json!(page),
+            Self::JobDetail(Some(job)) => json!(job),
+            Self::JobDetail(None) => json!({"message": "No job"}),
+            Self::JobDeleted => json!({"message": "Job deleted"}),
+            Self::JobUpdated => json!({"message": "Job updated"}),
+            Self::ApplyPlan(plan) => json!(plan),
+            Self::RunList(runs) => json!(runs),
+            Self::LintReport(findings) => json!(findings),
+            Self::Hotspots(hotspots) => json!(hotspots),
+            Self::Timeline(timeline) => json!(timeline),
+            Self::TimelineExported(file) => json!({"exported": file}),
+            Self::FailureAcknowledged => json!({"message": "Failure acknowledged"}),
+            Self::JobTriggered(run_id) => json!({"message": "Job triggered", "run_id": run_id}),
+            Self::RunRetried(id, run_id) => {
+                json!({"message": "Run retried", "job_id": id, "run_id": run_id})
+            }
+            Self::JobPaused => json!({"message": "Job paused"}),
+            Self::JobResumed => json!({"message": "Job resumed"}),
+            Self::RunKilled(killed) => json!({"killed": killed}),
+            Self::RunOutput(Some(chunk)) => json!(chunk),
+            Self::RunOutput(None) => json!({"message": "No run"}),
+            Self::OutputTailed(true) => json!({"message": "Run finished"}),
+            Self::OutputTailed(false) => json!({"message": "Run in flight"}),
+            Self::RunFinished(run) => json!(run),
+            Self::EventEmitted(ids) => json!({"fired": ids}),
+            Self::Stats(stats) => json!(stats),
+            Self::CostCenterStats(stats) => json!(stats),
+            Self::Digest(digest) => json!(digest),
+            Self::TimezoneReport(report) => json!(report),
+            Self::ZoneList(zones) => json!(zones),
+            Self::EventList(events) => json!(events),
+            Self::AuditLog(entries) => json!(entries),
+            Self::EventFeed(feed) => json!(feed),
+            Self::Snapshot(snapshot) => json!(snapshot),
+            Self::SnapshotExported(file, jobs) => json!({"exported": file, "jobs": jobs}),
+            Self::StoreEncrypted(file, jobs) => json!({"encrypted": file, "jobs": jobs}),
+            Self::Restored(ids) => json!({"message": "Snapshot restored", "restored": ids}),
+            Self::RestoreRejected(error) => {
+                json!({"message": "Restore rejected", "error": error})
+            }
+            Self::Promoted(Some(primary)) => {
+                json!({"message": "Service promoted", "primary": primary})
+            }
+            Self::Promoted(None) => json!({"message": "Service not following"}),
+            Self::ReadOnly(primary) => json!({
+                "message": "Read-only",
+                "error": format!("This service follows `{primary}`, promote it to change its jobs")
+            }),
+            Self::Forbidden(error) => json!({"message": "Forbidden", "error": error}),
+            Self::ConfigReloaded(hooks) => json!({"message": "Config reloaded", "hooks": hooks}),
+            Self::ConfigRejected(error) => json!({"message": "Config rejected", "error": error}),
+            Self::Batch(responses) => json!(responses
+                .iter()
+                .map(CommandResponse::to_json)
+                .collect::<Vec<_>>()),
+            Self::BatchAborted(index, error) => {
+                json!({"message": "Batch aborted", "index": index, "error": error})
+            }
+            Self::DoctorReport(findings) => json!(findings),
+            Self::Unsupported(error) => json!({"message": "Unsupported", "error": error}),
+            Self::AgentUnavailable(agent, error) => {
+                json!({"message": "Agent unavailable", "agent": agent, "error": error})
+            }
+            Self::Throttled(error) => json!({"message": "Throttled", "error": error}),
+            Self::Error { message, .. } => json!({"error": message}),
+            Self::QuotaExceeded(error) => json!({"message": "Quota exceeded", "error": error}),
+            Self::Warned(response, warnings) => match response.to_json_body() {
+                Value::Object(mut object) => {
+                    object.insert("warnings".to_string(), json!(warnings));
+                    Value::Object(object)
+                }
+                json => json!({"result": json, "warnings": warnings}),
+            },
+            Self::ServiceRunning => json!({"message": "Service running"}),
+            Self::ServiceStopped => json!({"message": "Service stopped"}),
+        }
+    }
+}
+
+/// `CommandClient` is a struct that wraps an `NngIpcSocket` instance.
+///
+/// It provides methods to send different types of `Command` instances to the socket and receive `CommandResponse` instances.
+///
+/// # Fields
+///
+/// * `socket` - An instance of `NngIpcSocket` that is used to send and receive commands.
+/// * `envelope` - An `Envelope` that represents the token and the namespace presented with every command.
+pub struct CommandClient {
+    socket: NngIpcSocket,
+    envelope: Envelope,
+}
+
+impl CommandClient {
+    /// Creates a new `CommandProxy` instance, presenting the token of the `CRONUS_TOKEN` environment variable and the namespace of the
+    /// `CRONUS_NAMESPACE` environment variable, if set.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string that represents the name of the socket.
+    /// * `path` - A `PathBuf` that represents the path of the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandClient>` - Returns a `CronusResult` that contains a `CommandClient` instance on success or an error.
+    pub fn new(name: String, path: PathBuf) -> CronusResult<Self> {
+        Ok(Self {
+            socket: NngIpcSocket::new_dial(path.join(name))?,
+            envelope: Envelope::from_env(),
+        })
+    }
+
+    /// Creates a new `CommandClient` instance connected to the command socket of a service at a URL, e.g. `tcp://web1:7070` for a
+    /// service listening on TCP with `--tcp-addr`, presenting the token of the `CRONUS_TOKEN` environment variable and the namespace of
+    /// the `CRONUS_NAMESPACE` environment variable, if set.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - A string that represents the address of the socket, with its transport.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandClient>` - Returns a `CronusResult` that contains a `CommandClient` instance on success or an error.
+    pub fn connect(url: &str) -> CronusResult<Self> {
+        Ok(Self {
+            socket: NngIpcSocket::new_dial_url(url)?,
+            envelope: Envelope::from_env(),
+        })
+    }
+
+    /// Replaces the token presented with every command, e.g. by the HTTP API with the token of each request.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - An `Option<String>` that represents the token presented. It is `None` if no token is presented.
+    pub fn set_token(&mut self, token: Option<String>) {
+        self.envelope.token = token;
+    }
+
+    /// Replaces the namespace every command is sent in, e.g. by the CLI with `--namespace`.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - An `Option<String>` that represents the namespace. It is `None` to work in the namespace of the token, if any, or
+    ///   else in the `default` namespace.
+    pub fn set_namespace(&mut self, namespace: Option<String>) {
+        self.envelope.namespace = namespace;
+    }
+
+    /// Bounds how long a command may wait for the response of the service, e.g. so an interactive client notices a service that stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - A `Duration` after which a command fails with a timeout error.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` on success or an error.
+    pub fn set_timeout(&self, timeout: Duration) -> CronusResult<()> {
+        self.socket.set_timeout(timeout)
+    }
+
+    /// Sends any command to the socket, e.g. a command relayed by a controller to one of its agents.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd` - A `Command` instance that represents the command sent.
     ///
-    /// * `corn` - A cron string that represents the schedule of the job.
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn send_command(&self, cmd: Command) -> CronusResult<CommandResponse> {
+        self.cmd_request(cmd)
+    }
+
+    /// Sends an `AddJob` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `trigger` - A `Trigger` instance that represents what makes the job run.
     /// * `job` - A `Job` instance that represents the job to be added.
+    /// * `options` - A `JobOptions` instance that represents the execution settings of the job.
+    /// * `no_duplicates` - A `bool` that represents whether the job is refused if it has the schedule and the payload of a registered job.
+    /// * `paused` - A `bool` that represents whether the job is registered paused.
+    /// * `dry_run` - A `bool` that represents whether the job is only checked, without being registered.
     ///
     /// # Returns
     ///
     /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
-    pub fn add_job(&self, corn: String, job: Job) -> CronusResult<CommandResponse> {
-        self.cmd_request(Command::new_add_job(corn, job))
+    pub fn add_job(
+        &self,
+        trigger: Trigger,
+        job: Job,
+        options: JobOptions,
+        no_duplicates: bool,
+        paused: bool,
+        dry_run: bool,
+    ) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_add_job(
+            trigger,
+            job,
+            options,
+            no_duplicates,
+            paused,
+            dry_run,
+        ))
     }
 
-    /// Sends a `ListJobs` command to the socket.
+    /// Sends a `ListJobs` command to the socket, listing every job by id.
     ///
     /// # Returns
     ///
     /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
     pub fn list_jobs(&self) -> CronusResult<CommandResponse> {
-        self.cmd_request(Command::new_list_jobs())
+        self.query_jobs(0, None, JobFilter::default(), JobSort::default())
+    }
+
+    /// Sends a `ListJobs` command to the socket, listing a page of the jobs matching a filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - A `usize` that represents the number of jobs skipped.
+    /// * `limit` - An `Option<usize>` that represents the maximum number of jobs listed, or `None` for every job.
+    /// * `filter` - A `JobFilter` that represents which jobs are listed.
+    /// * `sort` - A `JobSort` that represents the order the jobs are listed in.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn query_jobs(
+        &self,
+        offset: usize,
+        limit: Option<usize>,
+        filter: JobFilter,
+        sort: JobSort,
+    ) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_list_jobs(offset, limit, filter, sort))
     }
 
     /// Sends a `DeleteJob` command to the socket.
@@ -223,6 +1273,340 @@ impl CommandClient {
         self.cmd_request(Command::new_delete_job(id))
     }
 
+    /// Sends an `UpdateJob` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job to be updated.
+    /// * `trigger` - A `Trigger` instance that represents what makes the job run.
+    /// * `job` - A `Job` instance that represents the job itself.
+    /// * `options` - A `JobOptions` instance that represents the execution settings of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn update_job(
+        &self,
+        id: String,
+        trigger: Trigger,
+        job: Job,
+        options: JobOptions,
+    ) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_update_job(id, trigger, job, options))
+    }
+
+    /// Sends a `GetJob` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job described.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn get_job(&self, id: String) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_get_job(id))
+    }
+
+    /// Sends a `JobHistory` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job whose runs are listed.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn job_history(&self, id: String) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_job_history(id))
+    }
+
+    /// Sends a `LintJobs` command to the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn lint_jobs(&self) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_lint_jobs())
+    }
+
+    /// Sends an `AnalyzeSchedules` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - A `Duration` that represents how far ahead the schedules are projected.
+    /// * `threshold` - A `usize` that represents the number of distinct jobs firing together that makes a hotspot.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn analyze_schedules(
+        &self,
+        window: Duration,
+        threshold: usize,
+    ) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_analyze_schedules(window, threshold))
+    }
+
+    /// Sends a `Timeline` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - A `Duration` that represents how far back and ahead the executions are laid out.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn timeline(&self, window: Duration) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_timeline(window))
+    }
+
+    /// Sends an `AckFailure` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job whose failure is acknowledged.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn ack_failure(&self, id: String) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_ack_failure(id))
+    }
+
+    /// Sends a `TriggerJob` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job to be run.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn trigger_job(&self, id: String) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_trigger_job(id))
+    }
+
+    /// Sends a `RetryRun` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - A `RunId` that represents the id of the failed run to be retried.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn retry_run(&self, run_id: RunId) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_retry_run(run_id))
+    }
+
+    /// Sends a `PauseJob` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job to be paused.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn pause_job(&self, id: String) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_pause_job(id))
+    }
+
+    /// Sends a `ResumeJob` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job to be resumed.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn resume_job(&self, id: String) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_resume_job(id))
+    }
+
+    /// Sends a `KillRun` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job whose runs are killed.
+    /// * `run_id` - An `Option<RunId>` that represents the id of the run to be killed. It is `None` to kill every run of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn kill_run(&self, id: String, run_id: Option<RunId>) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_kill_run(id, run_id))
+    }
+
+    /// Sends a `TailOutput` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string that represents the id of the job whose output is read.
+    /// * `from` - A `u64` that represents the position of the first line to be read.
+    /// * `run_id` - An `Option<RunId>` that represents the id of the run whose output is read. It is `None` for the run started last.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn tail_output(
+        &self,
+        id: String,
+        from: u64,
+        run_id: Option<RunId>,
+    ) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_tail_output(id, from, run_id))
+    }
+
+    /// Sends an `EmitEvent` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - A string that represents the topic the event is emitted on.
+    /// * `payload` - An `Option<String>` that represents the payload of the event, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn emit_event(
+        &self,
+        topic: String,
+        payload: Option<String>,
+    ) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_emit_event(topic, payload))
+    }
+
+    /// Sends a `JobStats` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - An `Option<String>` that represents the id of the job to report on, or `None` for every job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn job_stats(&self, id: Option<String>) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_job_stats(id))
+    }
+
+    /// Sends a `CostCenterStats` command to the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn cost_center_stats(&self) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_cost_center_stats())
+    }
+
+    /// Sends a `UsageDigest` command to the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn usage_digest(&self) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_usage_digest())
+    }
+
+    /// Sends a `CheckTimezones` command to the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn check_timezones(&self) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_check_timezones())
+    }
+
+    /// Sends a `ListEvents` command to the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn list_events(&self) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_list_events())
+    }
+
+    /// Sends a `ListAudit` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - An `Option<String>` that represents the id of the job whose commands are listed, or `None` for every command.
+    /// * `limit` - A `usize` that represents the maximum number of commands listed.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn list_audit(&self, id: Option<String>, limit: usize) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_list_audit(id, limit))
+    }
+
+    /// Sends a `FollowEvents` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - A `u64` that represents the `seq` of the last event already read.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn follow_events(&self, after: u64) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_follow_events(after))
+    }
+
+    /// Sends a `Snapshot` command to the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn snapshot(&self) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_snapshot())
+    }
+
+    /// Sends a `Restore` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - A `ServiceSnapshot` that represents the jobs, the run history and the state of the jobs to be restored.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn restore(&self, snapshot: ServiceSnapshot) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_restore(snapshot))
+    }
+
+    /// Sends a `Promote` command to the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn promote(&self) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_promote())
+    }
+
+    /// Sends a `ReloadConfig` command to the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn reload_config(&self) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_reload_config())
+    }
+
+    /// Sends a `Batch` command to the socket.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - A vector of `Command` instances that represent the commands carried out, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
+    pub fn batch(&self, commands: Vec<Command>) -> CronusResult<CommandResponse> {
+        self.cmd_request(Command::new_batch(commands))
+    }
+
     /// Sends a `StopService` command to the socket.
     ///
     /// # Returns
@@ -251,8 +1635,8 @@ impl CommandClient {
     ///
     /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse` instance on success or an error.
     fn cmd_request(&self, cmd: Command) -> CronusResult<CommandResponse> {
-        self.0.send(&cmd.to_bytes()?)?;
-        let msg = self.0.recv()?;
+        self.socket.send(&cmd.to_bytes(&self.envelope)?)?;
+        let msg = self.socket.recv()?;
         CommandResponse::from_bytes(&msg[..])
     }
 }
@@ -0,0 +1,124 @@
+use structopt::clap::{App, Shell};
+
+/// The name of the hidden subcommand the completion scripts list the jobs of the Cronus service with.
+pub const COMPLETE_JOBS: &str = "complete-jobs";
+
+/// The Bash function completing job ids and names, wrapping the generated `_cronus` function. The `-n` and `-p` options already typed
+/// are passed on, so the jobs of the Cronus service the command line is about are completed.
+const BASH_JOBS: &str = r#"
+_cronus_jobs() {
+    local service=() i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${COMP_WORDS[i]}" in
+            -n|--name|-p|--path) service+=("${COMP_WORDS[i]}" "${COMP_WORDS[i+1]}") ;;
+        esac
+    done
+    cronus complete-jobs "$@" "${service[@]}" 2>/dev/null
+}
+
+_cronus_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "${prev}" in
+        -i|--id)
+            COMPREPLY=($(compgen -W "$(_cronus_jobs)" -- "${cur}"))
+            return 0
+            ;;
+        --job-name)
+            COMPREPLY=($(compgen -W "$(_cronus_jobs --names)" -- "${cur}"))
+            return 0
+            ;;
+    esac
+    _cronus "$@"
+}
+
+complete -F _cronus_dynamic -o bashdefault -o default cronus
+"#;
+
+/// The fish functions completing job ids and names, next to the generated completions.
+const FISH_JOBS: &str = r#"
+function __cronus_jobs
+    set -l words (commandline -opc)
+    set -l service
+    for i in (seq (count $words))
+        switch $words[$i]
+            case -n --name -p --path
+                set -a service $words[$i] $words[(math $i + 1)]
+        end
+    end
+    cronus complete-jobs $argv $service 2>/dev/null
+end
+
+complete -c cronus -s i -l id -f -a '(__cronus_jobs)'
+complete -c cronus -l job-name -f -a '(__cronus_jobs --names)'
+"#;
+
+/// The zsh functions completing job ids and names, called by the generated `_cronus` function, following its `#compdef` line.
+const ZSH_JOBS: &str = r#"
+_cronus_jobs() {
+    local -a service jobs
+    local i
+    for ((i = 2; i < CURRENT; i++)); do
+        case "${words[i]}" in
+            -n|--name|-p|--path) service+=("${words[i]}" "${words[i+1]}") ;;
+        esac
+    done
+    jobs=(${(f)"$(cronus complete-jobs "$@" "${service[@]}" 2>/dev/null)"})
+    compadd -a jobs
+}
+"#;
+
+/// Generates the completion script of the CLI for a shell.
+///
+/// On top of the subcommands and options, the Bash, fish and zsh scripts complete the ids of the jobs given to `-i` and the names of
+/// the jobs given to `--job-name`, listed by the running Cronus service with `COMPLETE_JOBS` as they are typed. The PowerShell and
+/// Elvish scripts only complete the subcommands and options.
+///
+/// # Arguments
+///
+/// * `app` - An `App` that represents the command line of the CLI.
+/// * `bin_name` - A string that represents the name the CLI is run as.
+/// * `shell` - A `Shell` that represents the shell the script is sourced by.
+///
+/// # Returns
+///
+/// * `String` - Returns the completion script.
+pub fn generate(mut app: App, bin_name: &str, shell: Shell) -> String {
+    let mut script = Vec::new();
+    app.gen_completions_to(bin_name, shell, &mut script);
+    let script = String::from_utf8_lossy(&script);
+    match shell {
+        Shell::Bash => format!("{script}{BASH_JOBS}"),
+        Shell::Fish => format!("{script}{FISH_JOBS}"),
+        Shell::Zsh => {
+            let mut lines = script.lines().map(complete_zsh_jobs);
+            let compdef = lines.next().unwrap_or_default();
+            let script = lines.collect::<Vec<_>>().join("\n");
+            format!("{compdef}\n{ZSH_JOBS}\n{script}\n")
+        }
+        _ => script.into_owned(),
+    }
+}
+
+/// Completes the jobs in a line of the generated zsh script, if it declares the `-i` or the `--job-name` option.
+///
+/// # Arguments
+///
+/// * `line` - A string that represents the line, e.g. `'-i+[Corn job id to be described]' \`.
+///
+/// # Returns
+///
+/// * `String` - Returns the line, with the action completing the jobs appended to the description of the option.
+fn complete_zsh_jobs(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let action = if trimmed.starts_with("'-i+[") || trimmed.starts_with("'--id=[") {
+        ": :_cronus_jobs"
+    } else if trimmed.starts_with("'--job-name=[") {
+        ": :_cronus_jobs --names"
+    } else {
+        return line.to_string();
+    };
+    match line.rfind("]'") {
+        Some(end) => format!("{}]{action}'{}", &line[..end], &line[end + 2..]),
+        None => line.to_string(),
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{validate_tokens, AuthToken};
+use crate::backup::BackupConfig;
+use crate::hooks::{Hook, HookStage};
+use crate::notify::Notifier;
+#[cfg(feature = "otel")]
+use crate::otel::OtelConfig;
+use crate::policy::JobPolicy;
+use crate::quota::JobQuota;
+use crate::throttle::ThrottleConfig;
+use crate::CronusResult;
+
+/// `ServiceConfig` is a structure that represents the configuration file of the Cronus service.
+///
+/// # Fields
+///
+/// * `on_start` - A vector of `Hook` instances that represent the hooks run when the service starts, declared as `[[on_start]]` tables.
+/// * `on_stop` - A vector of `Hook` instances that represent the hooks run before the service shuts down, declared as `[[on_stop]]` tables.
+/// * `on_reload` - A vector of `Hook` instances that represent the hooks run when the configuration is reloaded, declared as `[[on_reload]]` tables.
+/// * `backup` - An `Option<BackupConfig>` that represents how the service backs up its own state, declared as a `[backup]` table. It is `None` if the state is not backed up.
+/// * `notifiers` - A `BTreeMap<String, Notifier>` that represents the backends the alerts about jobs are sent to by name, declared as `[notifiers.<name>]` tables.
+/// * `otel` - An `Option<OtelConfig>` that represents how the service exports its telemetry, declared as an `[otel]` table, with the `otel` feature. It is `None` if the telemetry is not exported.
+/// * `policy` - A `JobPolicy` that represents which jobs the service accepts, declared as a `[policy]` table. Every job is accepted by default.
+/// * `tokens` - A `BTreeMap<String, AuthToken>` that represents the tokens the clients of the service present by name, declared as `[tokens.<name>]` tables. Every client is trusted if it is empty.
+/// * `throttle` - A `ThrottleConfig` that represents how much every client may send over the command socket, declared as a `[throttle]` table. The clients are not throttled by default.
+/// * `quota` - A `JobQuota` that represents how many jobs the service accepts, declared as a `[quota]` table. The jobs are not counted by default.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ServiceConfig {
+    #[serde(default)]
+    pub on_start: Vec<Hook>,
+    #[serde(default)]
+    pub on_stop: Vec<Hook>,
+    #[serde(default)]
+    pub on_reload: Vec<Hook>,
+    #[serde(default)]
+    pub backup: Option<BackupConfig>,
+    #[serde(default)]
+    pub notifiers: BTreeMap<String, Notifier>,
+    #[cfg(feature = "otel")]
+    #[serde(default)]
+    pub otel: Option<OtelConfig>,
+    #[serde(default)]
+    pub policy: JobPolicy,
+    #[serde(default)]
+    pub tokens: BTreeMap<String, AuthToken>,
+    #[serde(default)]
+    pub throttle: ThrottleConfig,
+    #[serde(default)]
+    pub quota: JobQuota,
+}
+
+impl ServiceConfig {
+    /// Loads the configuration of the service from a file.
+    ///
+    /// A configuration file with a `.toml` extension is a TOML document, any other configuration file is a JSON object.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path to the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<ServiceConfig>` - Returns a `CronusResult` that contains the configuration on success, or an error if the file
+    ///   cannot be read or parsed, declares a hook with invalid templates or an invalid notifier or an invalid telemetry exporter or tokens that are empty or the same, or backs up the state without an interval.
+    pub fn load(path: &Path) -> CronusResult<Self> {
+        let config: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            serde_json::from_slice(&std::fs::read(path)?)?
+        };
+        for stage in [HookStage::Start, HookStage::Stop, HookStage::Reload] {
+            for hook in config.hooks(stage) {
+                hook.job
+                    .validate_templates()
+                    .map_err(|e| format!("Hook `{stage}:{}`: {e}", hook.name))?;
+            }
+        }
+        if let Some(backup) = &config.backup {
+            if backup.interval.is_zero() {
+                return Err("The backup interval must not be zero".into());
+            }
+            if let Some(hook) = &backup.upload {
+                hook.job
+                    .validate_templates()
+                    .map_err(|e| format!("Hook `backup:{}`: {e}", hook.name))?;
+            }
+        }
+        for (name, notifier) in &config.notifiers {
+            notifier
+                .validate()
+                .map_err(|e| format!("Notifier `{name}`: {e}"))?;
+        }
+        #[cfg(feature = "otel")]
+        if let Some(otel) = &config.otel {
+            otel.validate()
+                .map_err(|e| format!("Telemetry exporter: {e}"))?;
+        }
+        validate_tokens(&config.tokens)?;
+        Ok(config)
+    }
+
+    /// Returns the hooks run at a stage of the lifecycle of the service.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - A `HookStage` that represents the stage.
+    ///
+    /// # Returns
+    ///
+    /// * `&[Hook]` - Returns the hooks of the stage, in the order they are run.
+    pub fn hooks(&self, stage: HookStage) -> &[Hook] {
+        match stage {
+            HookStage::Start => &self.on_start,
+            HookStage::Stop => &self.on_stop,
+            HookStage::Reload => &self.on_reload,
+        }
+    }
+}
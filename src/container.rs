@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::credentials::RunAs;
+use crate::http_client;
+use crate::job::{JobIdentity, KillSwitch, Tick, RUN_KILLED};
+use crate::limits::ResourceLimits;
+use crate::output::RunOutput;
+use crate::template;
+
+/// The sockets of the container engines looked for, in order, when `DOCKER_HOST` is not set.
+const ENGINE_SOCKETS: [&str; 2] = ["/var/run/docker.sock", "/run/podman/podman.sock"];
+
+/// How long a request to the container engine may wait for data, except for pulling an image which may take any time.
+const ENGINE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a running container is checked for having exited, or its run for having been killed.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The number of the last lines of the logs of a container recorded in the result of its run.
+const RESULT_LOG_LINES: usize = 50;
+
+/// `Container` is a structure that represents a container created for a run, removed along with its logs once it is dropped.
+///
+/// # Fields
+///
+/// * `socket` - A `PathBuf` that represents the socket of the container engine.
+/// * `id` - A string that represents the id of the container.
+struct Container {
+    socket: PathBuf,
+    id: String,
+}
+
+impl Drop for Container {
+    fn drop(&mut self) {
+        let target = format!("/containers/{}?force=true", self.id);
+        _ = http_client::unix_request(&self.socket, "DELETE", &target, None, Some(ENGINE_TIMEOUT));
+    }
+}
+
+/// Finds the socket of the container engine.
+///
+/// # Returns
+///
+/// * `Result<PathBuf, String>` - Returns the socket named by `DOCKER_HOST`, or else the first Docker or Podman socket found, including
+///   the rootless Podman socket of the user of the service, or an error message if there is none.
+pub fn engine_socket() -> Result<PathBuf, String> {
+    if let Ok(host) = std::env::var("DOCKER_HOST") {
+        return host
+            .strip_prefix("unix://")
+            .map(PathBuf::from)
+            .ok_or_else(|| format!("Unsupported DOCKER_HOST `{host}`, expected a unix:// socket"));
+    }
+    let rootless = std::env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .map(|dir| Path::new(&dir).join("podman/podman.sock"));
+    ENGINE_SOCKETS
+        .iter()
+        .map(PathBuf::from)
+        .chain(rootless)
+        .find(|socket| socket.exists())
+        .ok_or_else(|| {
+            "No Docker or Podman socket found, set DOCKER_HOST to the socket of the container engine"
+                .to_string()
+        })
+}
+
+/// Runs a container for a run of a job, through the API of the Docker or Podman engine, and waits for it to exit.
+///
+/// The image is pulled if the engine does not have it yet. The command and the values of the environment variables are templates rendered
+/// for the tick of the run, see `template::render`, and the container is given the tick and the job as the `CRONUS_*` environment variables of command jobs, along
+/// with its own environment variables and the secrets of the job, masked in the output of the run. The container runs with the memory and CPU limits of the job, and as its user and group. Once the
+/// container has exited, its logs are written to the output of the run, and the result of the run records its exit code and the last
+/// lines of its logs. The container is removed once the run is over, and killed first if the run is.
+///
+/// # Arguments
+///
+/// * `image` - A string that represents the image of the container, e.g. `alpine:3.20`.
+/// * `cmd` - A slice of strings that represent the command templates of the container. The command of the image is run if it is empty.
+/// * `env` - A reference to the `BTreeMap<String, String>` of the environment variables of the container.
+/// * `volumes` - A slice of strings that represent the volumes mounted into the container, e.g. `/srv/backups:/backups:ro`.
+/// * `tick` - A `Tick` that represents the tick of the run.
+/// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+/// * `kill_switch` - A reference to the `KillSwitch` of the run.
+/// * `output` - A reference to the `RunOutput` of the run.
+/// * `limits` - A `ResourceLimits` that represents the limits applied to the container.
+/// * `run_as` - A reference to the `RunAs` that represents the user and group the container runs as.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the container exited with status 0, or an error message describing why the run failed.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    image: &str,
+    cmd: &[String],
+    env: &BTreeMap<String, String>,
+    volumes: &[String],
+    tick: Tick,
+    identity: &JobIdentity,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+    limits: ResourceLimits,
+    run_as: &RunAs,
+) -> Result<(), String> {
+    let socket = engine_socket()?;
+    let cmd = cmd
+        .iter()
+        .map(|arg| template::render(arg, tick, identity))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut variables: Vec<_> = tick
+        .variables()
+        .into_iter()
+        .map(|(name, value)| format!("CRONUS_{}={value}", name.to_uppercase()))
+        .collect();
+    variables.push(format!("CRONUS_JOB_ID={}", identity.id));
+    if let Some(name) = &identity.name {
+        variables.push(format!("CRONUS_JOB_NAME={name}"));
+    }
+    for (name, value) in env {
+        variables.push(format!(
+            "{name}={}",
+            template::render(value, tick, identity)?
+        ));
+    }
+    variables.extend(
+        identity
+            .resolve_secrets(output)?
+            .into_iter()
+            .map(|(name, value)| format!("{name}={value}")),
+    );
+    let mut spec = json!({
+        "Image": image,
+        "Env": variables,
+        "Labels": {"cronus.job_id": identity.id},
+        "HostConfig": {
+            "Binds": volumes,
+            "Memory": limits.max_mem.unwrap_or_default(),
+            "NanoCpus": limits.cpu_quota.map_or(0, |cpus| (cpus * 1e9) as u64),
+        },
+    });
+    if !cmd.is_empty() {
+        spec["Cmd"] = json!(cmd);
+    }
+    if let Some(user) = &run_as.user {
+        spec["User"] = match &run_as.group {
+            Some(group) => json!(format!("{user}:{group}")),
+            None => json!(user),
+        };
+    }
+    let spec = spec.to_string();
+    let create = || send(&socket, "POST", "/containers/create", Some(&spec));
+    let (mut status, mut response) = create()?;
+    if status == 404 {
+        pull(&socket, image)?;
+        (status, response) = create()?;
+    }
+    let created = check(status, &response, "create the container")?;
+    let id = created["Id"]
+        .as_str()
+        .ok_or("The container engine did not return the id of the container")?
+        .to_string();
+    let container = Container { socket, id };
+    let target = |action: &str| format!("/containers/{}/{action}", container.id);
+    call(
+        &container.socket,
+        "POST",
+        &target("start"),
+        None,
+        "start the container",
+    )?;
+    let exit_code = loop {
+        if kill_switch.is_killed() {
+            _ = call(
+                &container.socket,
+                "POST",
+                &target("kill"),
+                None,
+                "kill the container",
+            );
+            return Err(RUN_KILLED.to_string());
+        }
+        let state = call(
+            &container.socket,
+            "GET",
+            &target("json"),
+            None,
+            "inspect the container",
+        )?;
+        if !state["State"]["Running"].as_bool().unwrap_or_default() {
+            break state["State"]["ExitCode"].as_i64().unwrap_or(-1);
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    let logs: Vec<_> = logs(&container)?
+        .iter()
+        .map(|line| output.redact(line))
+        .collect();
+    for line in &logs {
+        output.push(line.clone());
+    }
+    let tail = &logs[logs.len().saturating_sub(RESULT_LOG_LINES)..];
+    output.set_exit_code(exit_code as i32);
+    output.set_result(json!({"exit_code": exit_code, "logs": tail}));
+    if exit_code != 0 {
+        return Err(format!("Container exited with status {exit_code}"));
+    }
+    Ok(())
+}
+
+/// Pulls an image, waiting for the pull to be over.
+///
+/// # Arguments
+///
+/// * `socket` - A reference to the path of the socket of the container engine.
+/// * `image` - A string that represents the image, e.g. `alpine:3.20`. The `latest` tag is pulled if the image has no tag.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the image was pulled, or an error message if not.
+fn pull(socket: &Path, image: &str) -> Result<(), String> {
+    let (image, tag) = match image.rsplit_once(':').filter(|(_, tag)| !tag.contains('/')) {
+        Some((image, tag)) => (image, tag),
+        None => (image, "latest"),
+    };
+    let target = format!("/images/create?fromImage={image}&tag={tag}");
+    let (status, body) = http_client::unix_request(socket, "POST", &target, None, None)?;
+    let body = String::from_utf8_lossy(&body);
+    // The progress of the pull is streamed as JSON lines, a failure along the way being reported by a line holding an `error`.
+    let failure = body
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find_map(|line| line["error"].as_str().map(str::to_string));
+    match failure {
+        _ if !(200..300).contains(&status) => Err(format!(
+            "Container engine failed to pull {image}:{tag}: {}",
+            engine_message(&body)
+        )),
+        Some(failure) => Err(format!(
+            "Container engine failed to pull {image}:{tag}: {failure}"
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Reads the logs of a container that has exited.
+///
+/// # Arguments
+///
+/// * `container` - A reference to the `Container`.
+///
+/// # Returns
+///
+/// * `Result<Vec<String>, String>` - Returns the lines the container wrote to its standard output and standard error, or an error message
+///   if they cannot be read.
+fn logs(container: &Container) -> Result<Vec<String>, String> {
+    let target = format!("/containers/{}/logs?stdout=true&stderr=true", container.id);
+    let (status, body) = http_client::unix_request(
+        &container.socket,
+        "GET",
+        &target,
+        None,
+        Some(ENGINE_TIMEOUT),
+    )?;
+    if !(200..300).contains(&status) {
+        return Err(format!(
+            "Container engine failed to read the logs of the container: {}",
+            engine_message(&String::from_utf8_lossy(&body))
+        ));
+    }
+    let text = demultiplex(&body).unwrap_or_else(|| body.clone());
+    Ok(String::from_utf8_lossy(&text)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Demultiplexes the logs of a container without a TTY, whose lines are sent in frames headed by their stream and their length.
+///
+/// # Arguments
+///
+/// * `body` - A slice of bytes that represents the multiplexed logs.
+///
+/// # Returns
+///
+/// * `Option<Vec<u8>>` - Returns the logs without their frame headers, or `None` if they are not multiplexed.
+fn demultiplex(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut text = Vec::with_capacity(body.len());
+    while !body.is_empty() {
+        if body.len() < 8 || body[0] > 2 || body[1..4] != [0, 0, 0] {
+            return None;
+        }
+        let length = u32::from_be_bytes([body[4], body[5], body[6], body[7]]) as usize;
+        let frame = body.get(8..8 + length)?;
+        text.extend_from_slice(frame);
+        body = &body[8 + length..];
+    }
+    Some(text)
+}
+
+/// Calls the API of the container engine and reads its JSON response, failing unless its status is 2xx or 304, i.e. nothing to do.
+///
+/// # Arguments
+///
+/// * `socket` - A reference to the path of the socket of the container engine.
+/// * `method` - A string that represents the method of the request.
+/// * `target` - A string that represents the target of the request, e.g. `/containers/create`.
+/// * `body` - An `Option<&str>` that represents the JSON body of the request. It is `None` if the request has no body.
+/// * `action` - A string that describes the call in error messages, e.g. `start the container`.
+///
+/// # Returns
+///
+/// * `Result<Value, String>` - Returns the response, `null` if it is empty, or an error message if the call failed.
+fn call(
+    socket: &Path,
+    method: &str,
+    target: &str,
+    body: Option<&str>,
+    action: &str,
+) -> Result<Value, String> {
+    let (status, response) = send(socket, method, target, body)?;
+    check(status, &response, action)
+}
+
+/// Sends a request to the API of the container engine.
+///
+/// # Arguments
+///
+/// * `socket` - A reference to the path of the socket of the container engine.
+/// * `method` - A string that represents the method of the request.
+/// * `target` - A string that represents the target of the request.
+/// * `body` - An `Option<&str>` that represents the JSON body of the request. It is `None` if the request has no body.
+///
+/// # Returns
+///
+/// * `Result<(u16, String), String>` - Returns the status and the body of the response, or an error message if the request failed.
+fn send(
+    socket: &Path,
+    method: &str,
+    target: &str,
+    body: Option<&str>,
+) -> Result<(u16, String), String> {
+    let (status, response) =
+        http_client::unix_request(socket, method, target, body, Some(ENGINE_TIMEOUT))?;
+    Ok((status, String::from_utf8_lossy(&response).into_owned()))
+}
+
+/// Reads the JSON response of the container engine.
+///
+/// # Arguments
+///
+/// * `status` - A `u16` that represents the status of the response.
+/// * `response` - A string that represents the body of the response.
+/// * `action` - A string that describes the call in error messages.
+///
+/// # Returns
+///
+/// * `Result<Value, String>` - Returns the response, `null` if it is empty, or an error message unless its status is 2xx or 304.
+fn check(status: u16, response: &str, action: &str) -> Result<Value, String> {
+    if !(200..300).contains(&status) && status != 304 {
+        return Err(format!(
+            "Container engine failed to {action}: {}",
+            engine_message(response)
+        ));
+    }
+    Ok(serde_json::from_str(response).unwrap_or_default())
+}
+
+/// Reads the message of an error response of the container engine.
+///
+/// # Arguments
+///
+/// * `response` - A string that represents the body of the response.
+///
+/// # Returns
+///
+/// * `String` - Returns the `message` of the response, or the whole response if it has none.
+fn engine_message(response: &str) -> String {
+    serde_json::from_str::<Value>(response)
+        .ok()
+        .and_then(|error| error["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| response.trim().to_string())
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::command::CommandClient;
+use crate::CronusResult;
+
+/// The environment variable naming the contexts file read by the CLI, instead of `~/.config/cronus/contexts.toml`.
+pub const CONTEXTS_VAR: &str = "CRONUS_CONTEXTS";
+
+/// `Endpoint` is a structure that represents the command socket of a service, on this host by its name and path like `-n` and `-p`, or
+/// on another host by the address it listens on over TCP, see `--tcp-addr`.
+///
+/// # Fields
+///
+/// * `addr` - An `Option<String>` that represents the TCP address of the service, e.g. `web1:7070`. It is `None` if the service is
+///   reached on this host by its name and path.
+/// * `name` - A `String` that represents the command acceptance name of the service, `cronus` by default.
+/// * `path` - A `PathBuf` that represents the command acceptance path of the service, `/tmp` by default.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Endpoint {
+    #[serde(default)]
+    pub addr: Option<String>,
+    #[serde(default = "default_name")]
+    pub name: String,
+    #[serde(default = "default_path")]
+    pub path: PathBuf,
+}
+
+/// Returns the command acceptance name of an endpoint that declares none, the default of the CLI.
+fn default_name() -> String {
+    "cronus".to_string()
+}
+
+/// Returns the command acceptance path of an endpoint that declares none, the default of the CLI.
+fn default_path() -> PathBuf {
+    PathBuf::from("/tmp")
+}
+
+impl Endpoint {
+    /// Returns the URL the command socket of the service is dialed at.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns a `tcp://` URL if the endpoint has a TCP address, or else the `ipc://` URL of its name and path.
+    pub fn url(&self) -> String {
+        match &self.addr {
+            Some(addr) => format!("tcp://{addr}"),
+            None => format!("ipc://{}", self.path.join(&self.name).display()),
+        }
+    }
+}
+
+/// `ClientContext` is a structure that represents a context of the contexts file: the service, or the controller, the commands of the
+/// CLI are sent to with `--context <name>`, along with the token they present.
+///
+/// # Fields
+///
+/// * `endpoint` - An `Endpoint` that represents the command socket the commands are sent to.
+/// * `token` - An `Option<String>` that represents the token presented with the commands. It is `None` to present the token of the
+///   `CRONUS_TOKEN` environment variable, if set.
+/// * `namespace` - An `Option<String>` that represents the namespace the commands work in. It is `None` to work in the namespace of the
+///   `CRONUS_NAMESPACE` environment variable, if set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientContext {
+    #[serde(flatten)]
+    pub endpoint: Endpoint,
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+impl ClientContext {
+    /// Connects to the command socket of the context.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandClient>` - Returns a `CronusResult` that contains a `CommandClient` presenting the token and the namespace
+    ///   of the context on success, or an error if the command socket cannot be dialed.
+    pub fn connect(&self) -> CronusResult<CommandClient> {
+        let mut cc = CommandClient::connect(&self.endpoint.url())?;
+        if self.token.is_some() {
+            cc.set_token(self.token.clone());
+        }
+        if self.namespace.is_some() {
+            cc.set_namespace(self.namespace.clone());
+        }
+        Ok(cc)
+    }
+}
+
+/// `Contexts` is a structure that represents the contexts file of the CLI, e.g. `~/.config/cronus/contexts.toml`:
+///
+/// ```toml
+/// [contexts.prod]
+/// addr = "controller.example.com:7070"
+/// token = "..."
+///
+/// [contexts.local]
+/// name = "cronus"
+/// ```
+///
+/// # Fields
+///
+/// * `contexts` - A `BTreeMap<String, ClientContext>` that represents every context by its name.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Contexts {
+    pub contexts: BTreeMap<String, ClientContext>,
+}
+
+impl Contexts {
+    /// Loads the contexts file of the CLI, from the file named by the `CRONUS_CONTEXTS` environment variable, if set, or else from
+    /// `~/.config/cronus/contexts.toml`.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<Contexts>` - Returns a `CronusResult` that contains the contexts on success, or an error if the file cannot be
+    ///   read or parsed.
+    pub fn load() -> CronusResult<Self> {
+        let file = match std::env::var_os(CONTEXTS_VAR) {
+            Some(file) => PathBuf::from(file),
+            None => Path::new(&std::env::var_os("HOME").ok_or("HOME is not set")?)
+                .join(".config/cronus/contexts.toml"),
+        };
+        Self::load_from(&file).map_err(|e| format!("Cannot read `{}`: {e}", file.display()).into())
+    }
+
+    /// Loads contexts from a file.
+    ///
+    /// A file with a `.toml` extension is a TOML document, any other file is a JSON object, like the configuration file of a service.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - A path to the contexts file.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<Contexts>` - Returns a `CronusResult` that contains the contexts on success, or an error if the file cannot be
+    ///   read or parsed.
+    pub fn load_from(file: &Path) -> CronusResult<Self> {
+        if file
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            Ok(toml::from_str(&std::fs::read_to_string(file)?)?)
+        } else {
+            Ok(serde_json::from_slice(&std::fs::read(file)?)?)
+        }
+    }
+
+    /// Takes a context out of the contexts.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string that represents the name of the context.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<ClientContext>` - Returns a `CronusResult` that contains the context on success, or an error naming the known
+    ///   contexts if there is none by that name.
+    pub fn take(mut self, name: &str) -> CronusResult<ClientContext> {
+        self.contexts.remove(name).ok_or_else(|| {
+            let known: Vec<_> = self.contexts.keys().map(String::as_str).collect();
+            format!("No context `{name}`, expected one of: {}", known.join(", ")).into()
+        })
+    }
+}
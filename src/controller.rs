@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::command::{Command, CommandClient, CommandResponse, Envelope};
+use crate::context::Endpoint;
+use crate::job::JobInfo;
+use crate::listing::{page_jobs, sort_jobs, JobFilter, JobSort};
+use crate::nng_socket::NngIpcSocket;
+use crate::CronusResult;
+
+/// How long the controller waits for an agent to answer a command before it reports the agent as unavailable.
+const AGENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `Relayed` is the outcome of relaying a command to the agents: their merged answer, or the answer an agent refused the command with,
+/// boxed as it is as large as an answer.
+type Relayed<T> = Result<T, Box<CommandResponse>>;
+
+/// `ControllerConfig` is a structure that represents the agents of a controller, read from its configuration file, e.g.
+/// `fleet.toml`:
+///
+/// ```toml
+/// [agents.web1]
+/// addr = "web1.example.com:7070"
+///
+/// [agents.web2]
+/// addr = "web2.example.com:7070"
+///
+/// [agents.local]
+/// name = "cronus"
+/// ```
+///
+/// # Fields
+///
+/// * `agents` - A `BTreeMap<String, Endpoint>` that represents the command socket of every agent by its name, reached over TCP by its
+///   `addr`, or on this host by its `name` and `path`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ControllerConfig {
+    pub agents: BTreeMap<String, Endpoint>,
+}
+
+impl ControllerConfig {
+    /// Loads the agents of a controller from a file.
+    ///
+    /// A configuration file with a `.toml` extension is a TOML document, any other configuration file is a JSON object, like the
+    /// configuration file of a service.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path to the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<ControllerConfig>` - Returns a `CronusResult` that contains the configuration on success, or an error if the
+    ///   file cannot be read or parsed, or declares no agent.
+    pub fn load(path: &Path) -> CronusResult<Self> {
+        let config: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            serde_json::from_slice(&std::fs::read(path)?)?
+        };
+        if config.agents.is_empty() {
+            return Err("The controller declares no agent".into());
+        }
+        Ok(config)
+    }
+}
+
+/// Runs a controller relaying the commands it receives to its agents, until it is stopped.
+///
+/// The controller answers the commands of the CLI on a command socket of its own, like a service, so `cronus -n <name> list`, or
+/// `cronus --context <context> list` for a context naming it, manages the jobs of every agent at once:
+///
+/// * `ListJobs` lists the jobs of every agent, each with the `agent` it runs on, sorted and paged across agents.
+/// * The commands about a job, e.g. `GetJob`, `DeleteJob`, `TriggerJob` or `TailOutput`, are relayed to the agent the job runs on.
+/// * `AddJob` is relayed to the agent holding a job with the key of the job, if any, or else to the agent with the fewest jobs.
+/// * `EmitEvent` is relayed to every agent, firing the jobs listening on its topic on all of them.
+/// * `ListEvents`, `JobStats` and `LintJobs` merge the answers of every agent.
+///
+/// Every command presents the token and the namespace of its client to the agents, never a token of the controller, so the agents
+/// authorize and scope it as if it was sent to them directly. The controller answers `PingService` and `StopService` itself, and any other command, e.g. a
+/// `Batch` that cannot be carried out atomically across agents, with `Unsupported`. An agent that cannot be reached is reported
+/// with `AgentUnavailable`, as the jobs listed without it would be incomplete.
+///
+/// # Arguments
+///
+/// * `name` - A string that represents the command acceptance name of the controller.
+/// * `path` - A `PathBuf` that represents the command acceptance path of the controller.
+/// * `tcp_addr` - An `Option<SocketAddr>` that represents the address the controller also listens on over TCP. It is `None` if the
+///   controller is only reached on its path.
+/// * `config` - A `ControllerConfig` that represents the agents of the controller.
+///
+/// # Returns
+///
+/// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::ServiceStopped` once the controller
+///   is stopped, or an error if its command socket fails.
+pub fn control(
+    name: String,
+    path: PathBuf,
+    tcp_addr: Option<SocketAddr>,
+    config: ControllerConfig,
+) -> CronusResult<CommandResponse> {
+    let server = NngIpcSocket::new_listen(path.join(name))?;
+    if let Some(tcp_addr) = tcp_addr {
+        server.listen_on(&format!("tcp://{tcp_addr}"))?;
+    }
+    loop {
+        let msg = server.recv()?;
+        let response = match Command::from_bytes(&msg[..]) {
+            Ok((Command::StopService, _)) => CommandResponse::ServiceStopped,
+            Ok((cmd, envelope)) => relay(&config.agents, cmd, envelope).unwrap_or_else(|res| *res),
+            Err(e) => CommandResponse::Unsupported(format!("Invalid command: {e}")),
+        };
+        server.send(&response.to_bytes()?[..])?;
+        if response == CommandResponse::ServiceStopped {
+            return Ok(response);
+        }
+    }
+}
+
+/// Relays a command to the agents it is about, see `control`, and merges their answers.
+///
+/// # Arguments
+///
+/// * `agents` - A reference to the `BTreeMap<String, Endpoint>` of the agents of the controller.
+/// * `cmd` - A `Command` that represents the command received by the controller.
+/// * `envelope` - An `Envelope` that represents the token and the namespace presented by the client, presented to the agents in turn.
+///
+/// # Returns
+///
+/// * `Relayed<CommandResponse>` - Returns the merged answer of the agents, or the first answer of an agent that refused
+///   the command, e.g. `Forbidden`, or an `AgentUnavailable` for an agent that cannot be reached.
+fn relay(
+    agents: &BTreeMap<String, Endpoint>,
+    cmd: Command,
+    envelope: Envelope,
+) -> Relayed<CommandResponse> {
+    let job_id = match &cmd {
+        Command::GetJob { id }
+        | Command::JobHistory { id }
+        | Command::TailOutput { id, .. }
+        | Command::JobStats { id: Some(id) } => Some(id.clone()),
+        cmd => cmd.job_id().map(str::to_string),
+    };
+    if let Some(id) = job_id {
+        let (agent, job) = locate(agents, &id, &envelope)?;
+        if let Command::GetJob { .. } = cmd {
+            return Ok(CommandResponse::JobDetail(Some(job)));
+        }
+        return send(agent, &agents[agent], &envelope, |cc| cc.send_command(cmd));
+    }
+    match cmd {
+        Command::ListJobs {
+            offset,
+            limit,
+            filter,
+            sort,
+        } => {
+            let mut jobs = list(agents, &envelope, filter, sort)?;
+            sort_jobs(&mut jobs, sort);
+            Ok(match limit {
+                Some(limit) => CommandResponse::JobPage(page_jobs(jobs, offset, limit)),
+                None => CommandResponse::JobList(jobs.into_iter().skip(offset).collect()),
+            })
+        }
+        Command::AddJob {
+            trigger,
+            job,
+            options,
+            no_duplicates,
+            paused,
+            dry_run,
+        } => {
+            let jobs = list(agents, &envelope, JobFilter::default(), JobSort::default())?;
+            let keyed = options.key.as_ref().and_then(|key| {
+                jobs.iter()
+                    .find(|job| job.options.key.as_ref() == Some(key))
+                    .and_then(|job| job.agent.clone())
+            });
+            let agent = keyed.unwrap_or_else(|| {
+                // Every agent is counted, including those without jobs, the first one by name winning ties.
+                let mut counts: BTreeMap<&String, usize> =
+                    agents.keys().map(|name| (name, 0)).collect();
+                for job in &jobs {
+                    if let Some(count) = job.agent.as_ref().and_then(|a| counts.get_mut(a)) {
+                        *count += 1;
+                    }
+                }
+                let fewest = counts.values().min().copied().unwrap_or_default();
+                counts
+                    .into_iter()
+                    .find(|(_, count)| *count == fewest)
+                    .map(|(name, _)| name.clone())
+                    .unwrap_or_default()
+            });
+            send(&agent, &agents[&agent], &envelope, |cc| {
+                cc.add_job(trigger, job, options, no_duplicates, paused, dry_run)
+            })
+        }
+        Command::EmitEvent { topic, payload } => {
+            let mut fired = Vec::new();
+            for (_, response) in fan_out(agents, &envelope, |cc| {
+                cc.emit_event(topic.clone(), payload.clone())
+            })? {
+                match response {
+                    CommandResponse::EventEmitted(ids) => fired.extend(ids),
+                    response => return Err(Box::new(response)),
+                }
+            }
+            Ok(CommandResponse::EventEmitted(fired))
+        }
+        Command::ListEvents => {
+            let mut events = Vec::new();
+            for (_, response) in fan_out(agents, &envelope, CommandClient::list_events)? {
+                match response {
+                    CommandResponse::EventList(list) => events.extend(list),
+                    response => return Err(Box::new(response)),
+                }
+            }
+            events.sort_by_key(|event| event.at);
+            Ok(CommandResponse::EventList(events))
+        }
+        Command::JobStats { id: None } => {
+            let mut stats = Vec::new();
+            for (_, response) in fan_out(agents, &envelope, |cc| cc.job_stats(None))? {
+                match response {
+                    CommandResponse::Stats(list) => stats.extend(list),
+                    response => return Err(Box::new(response)),
+                }
+            }
+            Ok(CommandResponse::Stats(stats))
+        }
+        Command::LintJobs => {
+            let mut findings = Vec::new();
+            for (_, response) in fan_out(agents, &envelope, CommandClient::lint_jobs)? {
+                match response {
+                    CommandResponse::LintReport(list) => findings.extend(list),
+                    response => return Err(Box::new(response)),
+                }
+            }
+            Ok(CommandResponse::LintReport(findings))
+        }
+        Command::PingService => Ok(CommandResponse::ServiceRunning),
+        cmd => {
+            let command = serde_json::to_value(&cmd).unwrap_or_default();
+            Err(Box::new(CommandResponse::Unsupported(format!(
+                "A controller does not relay `{}` commands",
+                command["command"].as_str().unwrap_or_default()
+            ))))
+        }
+    }
+}
+
+/// Lists the jobs of every agent, each with the `agent` it runs on.
+///
+/// # Arguments
+///
+/// * `agents` - A reference to the `BTreeMap<String, Endpoint>` of the agents of the controller.
+/// * `envelope` - A reference to the `Envelope` that represents the token and the namespace presented to the agents.
+/// * `filter` - A `JobFilter` that represents which jobs are listed.
+/// * `sort` - A `JobSort` that represents the order the agents list their jobs in.
+///
+/// # Returns
+///
+/// * `Relayed<Vec<JobInfo>>` - Returns the jobs of every agent, agent after agent, or the answer of an agent that did
+///   not list its jobs.
+fn list(
+    agents: &BTreeMap<String, Endpoint>,
+    envelope: &Envelope,
+    filter: JobFilter,
+    sort: JobSort,
+) -> Relayed<Vec<JobInfo>> {
+    let mut jobs = Vec::new();
+    for (agent, response) in fan_out(agents, envelope, |cc| {
+        cc.query_jobs(0, None, filter.clone(), sort)
+    })? {
+        match response {
+            CommandResponse::JobList(list) => jobs.extend(list.into_iter().map(|mut job| {
+                job.agent = Some(agent.to_string());
+                job
+            })),
+            response => return Err(Box::new(response)),
+        }
+    }
+    Ok(jobs)
+}
+
+/// Finds the agent a job runs on, asking every agent for the job.
+///
+/// # Arguments
+///
+/// * `agents` - A reference to the `BTreeMap<String, Endpoint>` of the agents of the controller.
+/// * `id` - A string that represents the id of the job.
+/// * `envelope` - A reference to the `Envelope` that represents the token and the namespace presented to the agents.
+///
+/// # Returns
+///
+/// * `Relayed<(&'a str, JobInfo)>` - Returns the name of the agent and the job, with its `agent`, or `JobDetail(None)`
+///   if no agent has the job, or the answer of an agent that did not describe its job.
+fn locate<'a>(
+    agents: &'a BTreeMap<String, Endpoint>,
+    id: &str,
+    envelope: &Envelope,
+) -> Relayed<(&'a str, JobInfo)> {
+    for (agent, response) in fan_out(agents, envelope, |cc| cc.get_job(id.to_string()))? {
+        match response {
+            CommandResponse::JobDetail(Some(mut job)) => {
+                job.agent = Some(agent.to_string());
+                return Ok((agent, job));
+            }
+            CommandResponse::JobDetail(None) => {}
+            response => return Err(Box::new(response)),
+        }
+    }
+    Err(Box::new(CommandResponse::JobDetail(None)))
+}
+
+/// Sends a command to every agent at once, each on a thread of its own.
+///
+/// # Arguments
+///
+/// * `agents` - A reference to the `BTreeMap<String, Endpoint>` of the agents of the controller.
+/// * `envelope` - A reference to the `Envelope` that represents the token and the namespace presented to the agents.
+/// * `call` - A function sending the command with the client of an agent.
+///
+/// # Returns
+///
+/// * `Relayed<Vec<(&'a str, CommandResponse)>>` - Returns the answer of every agent, by name, or an `AgentUnavailable`
+///   for the first agent that cannot be reached.
+fn fan_out<'a>(
+    agents: &'a BTreeMap<String, Endpoint>,
+    envelope: &Envelope,
+    call: impl Fn(&CommandClient) -> CronusResult<CommandResponse> + Sync,
+) -> Relayed<Vec<(&'a str, CommandResponse)>> {
+    std::thread::scope(|scope| {
+        let calls: Vec<_> = agents
+            .iter()
+            .map(|(agent, endpoint)| {
+                let call = &call;
+                let answer = scope.spawn(move || call_agent(endpoint, envelope, call));
+                (agent.as_str(), answer)
+            })
+            .collect();
+        calls
+            .into_iter()
+            .map(|(agent, answer)| {
+                answer
+                    .join()
+                    .unwrap_or_else(|_| Err("The call to the agent panicked".to_string()))
+                    .map(|response| (agent, response))
+                    .map_err(|e| Box::new(CommandResponse::AgentUnavailable(agent.to_string(), e)))
+            })
+            .collect()
+    })
+}
+
+/// Sends a command to an agent.
+///
+/// # Arguments
+///
+/// * `agent` - A string that represents the name of the agent.
+/// * `endpoint` - A reference to the `Endpoint` of the agent.
+/// * `envelope` - A reference to the `Envelope` that represents the token and the namespace presented to the agent.
+/// * `call` - A function sending the command with the client of the agent.
+///
+/// # Returns
+///
+/// * `Relayed<CommandResponse>` - Returns the answer of the agent, or an `AgentUnavailable` if it cannot be reached.
+fn send(
+    agent: &str,
+    endpoint: &Endpoint,
+    envelope: &Envelope,
+    call: impl FnOnce(&CommandClient) -> CronusResult<CommandResponse>,
+) -> Relayed<CommandResponse> {
+    call_agent(endpoint, envelope, call)
+        .map_err(|e| Box::new(CommandResponse::AgentUnavailable(agent.to_string(), e)))
+}
+
+/// Connects to an agent, presenting the token and the namespace of the client of the controller, and sends it a command.
+///
+/// # Arguments
+///
+/// * `endpoint` - A reference to the `Endpoint` of the agent.
+/// * `envelope` - A reference to the `Envelope` that represents the token and the namespace presented to the agent.
+/// * `call` - A function sending the command with the client of the agent.
+///
+/// # Returns
+///
+/// * `Result<CommandResponse, String>` - Returns the answer of the agent, or an error message if it cannot be reached, or does not
+///   answer within `AGENT_TIMEOUT`.
+fn call_agent(
+    endpoint: &Endpoint,
+    envelope: &Envelope,
+    call: impl FnOnce(&CommandClient) -> CronusResult<CommandResponse>,
+) -> Result<CommandResponse, String> {
+    CommandClient::connect(&endpoint.url())
+        .and_then(|mut cc| {
+            cc.set_token(envelope.token.clone());
+            cc.set_namespace(envelope.namespace.clone());
+            cc.set_timeout(AGENT_TIMEOUT)?;
+            call(&cc)
+        })
+        .map_err(|e| e.to_string())
+}
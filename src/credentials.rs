@@ -0,0 +1,258 @@
+use std::ffi::{CStr, CString};
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::ptr;
+
+use serde::{Deserialize, Serialize};
+
+/// The size of the buffer the entries of the user and group databases are read into.
+const ENTRY_BUFFER_SIZE: usize = 16 * 1024;
+
+/// `RunAs` is a structure that represents the user and group the processes spawned by a command job run as.
+///
+/// # Fields
+///
+/// * `user` - An `Option<String>` that represents the name or the uid of the user. It is `None` if the processes run as the user of the service.
+/// * `group` - An `Option<String>` that represents the name or the gid of the group. It is `None` if the processes run with the primary group of
+///   the user, or with the group of the service if no user is given either.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RunAs {
+    pub user: Option<String>,
+    pub group: Option<String>,
+}
+
+/// `Account` is a structure that represents an entry of the user database.
+///
+/// # Fields
+///
+/// * `name` - A string that represents the name of the user.
+/// * `uid` - A `u32` that represents the uid of the user.
+/// * `gid` - A `u32` that represents the gid of the primary group of the user.
+/// * `home` - A `PathBuf` that represents the home directory of the user.
+struct Account {
+    name: String,
+    uid: u32,
+    gid: u32,
+    home: PathBuf,
+}
+
+impl RunAs {
+    /// Sets up a command so the process it spawns drops its privileges to the user and group.
+    ///
+    /// Like the system crontab, the process gets the supplementary groups of the user, and its `HOME`, `USER` and `LOGNAME` environment
+    /// variables. The groups, gid and uid are switched in the process before it executes the command, after any other setup registered
+    /// earlier, so resource limits requiring privileges are applied first. Switching requires the service to run as root.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd` - A mutable reference to the `Command` to be set up.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<()>` - Returns `Ok(())` if the command was set up, or an error if the user or the group cannot be found.
+    pub fn apply(&self, cmd: &mut Command) -> io::Result<()> {
+        if self.user.is_none() && self.group.is_none() {
+            return Ok(());
+        }
+        let account = self.user.as_deref().map(lookup_user).transpose()?;
+        let gid = match (&self.group, &account) {
+            (Some(group), _) => Some(lookup_group(group)?),
+            (None, Some(account)) => Some(account.gid),
+            (None, None) => None,
+        };
+        let groups = match (&account, gid) {
+            (Some(account), Some(gid)) => group_list(&account.name, gid)?,
+            (_, gid) => gid.into_iter().collect(),
+        };
+        let uid = account.as_ref().map(|account| account.uid);
+        if let Some(account) = &account {
+            cmd.env("HOME", &account.home)
+                .env("USER", &account.name)
+                .env("LOGNAME", &account.name);
+        }
+        // SAFETY: the closure only calls async-signal-safe functions, as required between `fork` and `exec`.
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::setgroups(groups.len(), groups.as_ptr()) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if let Some(gid) = gid {
+                    if libc::setgid(gid) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                if let Some(uid) = uid {
+                    if libc::setuid(uid) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the user and the group exist.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the user and the group exist, or an error message describing the first one that does not.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(user) = &self.user {
+            lookup_user(user).map_err(|e| e.to_string())?;
+        }
+        if let Some(group) = &self.group {
+            lookup_group(group).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// Looks up the name of a user in the user database.
+///
+/// # Arguments
+///
+/// * `uid` - A `u32` that represents the uid of the user.
+///
+/// # Returns
+///
+/// * `Option<String>` - Returns the name of the user, or `None` if the user does not exist.
+pub fn user_name(uid: u32) -> Option<String> {
+    lookup_user(&uid.to_string())
+        .ok()
+        .map(|account| account.name)
+}
+
+/// Looks up a user in the user database.
+///
+/// # Arguments
+///
+/// * `user` - A string that represents the name or the uid of the user.
+///
+/// # Returns
+///
+/// * `io::Result<Account>` - Returns the entry of the user, or an error if the user does not exist.
+fn lookup_user(user: &str) -> io::Result<Account> {
+    let mut buf = vec![0; ENTRY_BUFFER_SIZE];
+    let mut entry = MaybeUninit::<libc::passwd>::uninit();
+    let mut found = ptr::null_mut();
+    // SAFETY: the entry, the buffer and the result pointer outlive the call, and the buffer length is the one of the buffer.
+    let code = unsafe {
+        match user.parse::<u32>() {
+            Ok(uid) => libc::getpwuid_r(
+                uid,
+                entry.as_mut_ptr(),
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut found,
+            ),
+            Err(_) => {
+                let name = CString::new(user)?;
+                libc::getpwnam_r(
+                    name.as_ptr(),
+                    entry.as_mut_ptr(),
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                    &mut found,
+                )
+            }
+        }
+    };
+    if code != 0 {
+        return Err(io::Error::from_raw_os_error(code));
+    }
+    if found.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Unknown user `{user}`"),
+        ));
+    }
+    // SAFETY: the entry was filled in, and its strings point into the buffer, which is still alive.
+    unsafe {
+        let entry = entry.assume_init();
+        Ok(Account {
+            name: CStr::from_ptr(entry.pw_name).to_string_lossy().into_owned(),
+            uid: entry.pw_uid,
+            gid: entry.pw_gid,
+            home: PathBuf::from(CStr::from_ptr(entry.pw_dir).to_string_lossy().into_owned()),
+        })
+    }
+}
+
+/// Looks up a group in the group database.
+///
+/// # Arguments
+///
+/// * `group` - A string that represents the name or the gid of the group.
+///
+/// # Returns
+///
+/// * `io::Result<u32>` - Returns the gid of the group, or an error if the group does not exist.
+fn lookup_group(group: &str) -> io::Result<u32> {
+    let mut buf = vec![0; ENTRY_BUFFER_SIZE];
+    let mut entry = MaybeUninit::<libc::group>::uninit();
+    let mut found = ptr::null_mut();
+    // SAFETY: the entry, the buffer and the result pointer outlive the call, and the buffer length is the one of the buffer.
+    let code = unsafe {
+        match group.parse::<u32>() {
+            Ok(gid) => libc::getgrgid_r(
+                gid,
+                entry.as_mut_ptr(),
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut found,
+            ),
+            Err(_) => {
+                let name = CString::new(group)?;
+                libc::getgrnam_r(
+                    name.as_ptr(),
+                    entry.as_mut_ptr(),
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                    &mut found,
+                )
+            }
+        }
+    };
+    if code != 0 {
+        return Err(io::Error::from_raw_os_error(code));
+    }
+    if found.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Unknown group `{group}`"),
+        ));
+    }
+    // SAFETY: the entry was filled in.
+    Ok(unsafe { entry.assume_init() }.gr_gid)
+}
+
+/// Lists the groups a user belongs to.
+///
+/// # Arguments
+///
+/// * `user` - A string that represents the name of the user.
+/// * `gid` - A `u32` that represents the gid of the group the process runs with, always part of the list.
+///
+/// # Returns
+///
+/// * `io::Result<Vec<u32>>` - Returns the gids of the groups, or an error if the name of the user is invalid.
+fn group_list(user: &str, gid: u32) -> io::Result<Vec<u32>> {
+    let name = CString::new(user)?;
+    let mut groups = vec![0; 32];
+    loop {
+        let mut count = groups.len() as libc::c_int;
+        // SAFETY: the list holds `count` gids, and `getgrouplist` writes at most that many.
+        let found =
+            unsafe { libc::getgrouplist(name.as_ptr(), gid, groups.as_mut_ptr(), &mut count) };
+        if found >= 0 {
+            groups.truncate(count as usize);
+            return Ok(groups);
+        }
+        // The list was too small, `count` now holds the number of groups of the user.
+        groups.resize((count as usize).max(groups.len() * 2), 0);
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use rand::Rng;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::digest::{digest, SHA256};
+
+use crate::CronusResult;
+
+/// The length in bytes of the key of a job store.
+pub const KEY_LEN: usize = 32;
+
+/// `StoreKey` is a structure that represents the key the job store and its snapshots are encrypted with, using ChaCha20-Poly1305.
+///
+/// Every sealed value is written as the hex digits of a random nonce, followed by the ciphertext and its authentication tag, so a
+/// value that was tampered with, or sealed with another key, is never opened.
+///
+/// # Fields
+///
+/// * `key` - A `LessSafeKey` that represents the key, bound to the ChaCha20-Poly1305 algorithm.
+#[derive(Clone)]
+pub struct StoreKey {
+    key: LessSafeKey,
+}
+
+impl fmt::Debug for StoreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StoreKey(..)")
+    }
+}
+
+impl StoreKey {
+    /// Loads a key from a key file, holding either `KEY_LEN` raw bytes or their hex digits, e.g. written by `openssl rand -hex 32`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - A path to the key file.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<StoreKey>` - Returns a `CronusResult` that contains the key on success, or an error if the file cannot be read,
+    ///   can be read by other users than its owner, or does not hold a key.
+    pub fn load(file: &Path) -> CronusResult<Self> {
+        if std::fs::metadata(file)?.permissions().mode() & 0o077 != 0 {
+            return Err(format!(
+                "The key file `{}` can be read by other users, restrict it with chmod 600",
+                file.display()
+            )
+            .into());
+        }
+        let content = std::fs::read(file)?;
+        let bytes = match std::str::from_utf8(&content) {
+            Ok(text) if text.trim().len() == KEY_LEN * 2 => from_hex(text.trim()),
+            _ => Some(content),
+        }
+        .filter(|bytes| bytes.len() == KEY_LEN)
+        .ok_or_else(|| {
+            format!(
+                "The key file `{}` must hold {KEY_LEN} bytes, or their {} hex digits",
+                file.display(),
+                KEY_LEN * 2
+            )
+        })?;
+        let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes)
+            .map_err(|_| format!("Invalid key in `{}`", file.display()))?;
+        Ok(Self {
+            key: LessSafeKey::new(key),
+        })
+    }
+
+    /// Encrypts a value, bound to a context so it cannot be passed off as a value of another kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - A string that represents what the value is, e.g. `store` or `journal`.
+    /// * `plaintext` - A byte slice that represents the value.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, String>` - Returns the sealed value, as hex digits, or an error message if the value is too large to be sealed.
+    pub fn seal(&self, context: &str, plaintext: &[u8]) -> Result<String, String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill(&mut nonce[..]);
+        let mut sealed = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(context),
+                &mut sealed,
+            )
+            .map_err(|_| "The value is too large to be encrypted")?;
+        Ok(format!("{}{}", to_hex(&nonce), to_hex(&sealed)))
+    }
+
+    /// Decrypts a value sealed with `seal`.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - A string that represents what the value is, as it was sealed.
+    /// * `sealed` - A string that represents the sealed value.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<u8>, String>` - Returns the value, or an error message if it is malformed, was tampered with, or was sealed with
+    ///   another key or for another context.
+    pub fn open(&self, context: &str, sealed: &str) -> Result<Vec<u8>, String> {
+        let mut bytes = from_hex(sealed).ok_or("The encrypted value is malformed")?;
+        if bytes.len() < NONCE_LEN {
+            return Err("The encrypted value is malformed".to_string());
+        }
+        let mut ciphertext = bytes.split_off(NONCE_LEN);
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(&bytes);
+        let plaintext = self
+            .key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(context),
+                &mut ciphertext,
+            )
+            .map_err(|_| "Cannot decrypt the value, the key is wrong or the value was altered")?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// Fingerprints bytes with SHA-256, e.g. the declaration of a job, so a change of them is told apart without keeping them.
+///
+/// # Arguments
+///
+/// * `bytes` - A byte slice that represents the bytes.
+///
+/// # Returns
+///
+/// * `String` - Returns the hex digits of the SHA-256 digest of the bytes.
+pub fn fingerprint(bytes: &[u8]) -> String {
+    to_hex(digest(&SHA256, bytes).as_ref())
+}
+
+/// Writes bytes as lowercase hex digits.
+///
+/// # Arguments
+///
+/// * `bytes` - A byte slice that represents the bytes.
+///
+/// # Returns
+///
+/// * `String` - Returns two hex digits per byte.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Reads bytes written as hex digits.
+///
+/// # Arguments
+///
+/// * `hex` - A string that represents the hex digits.
+///
+/// # Returns
+///
+/// * `Option<Vec<u8>>` - Returns the bytes, or `None` if the string is not an even number of hex digits.
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> StoreKey {
+        StoreKey {
+            key: LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &[byte; KEY_LEN]).unwrap()),
+        }
+    }
+
+    #[test]
+    fn sealed_value_opens_with_its_key_and_context() {
+        let sealed = key(1).seal("store", b"jobs").unwrap();
+        assert!(!sealed.contains("jobs"));
+        assert_eq!(key(1).open("store", &sealed).unwrap(), b"jobs");
+        assert_ne!(key(1).seal("store", b"jobs").unwrap(), sealed);
+    }
+
+    #[test]
+    fn sealed_value_is_refused_with_another_key_or_context() {
+        let sealed = key(1).seal("store", b"jobs").unwrap();
+        assert!(key(2).open("store", &sealed).is_err());
+        assert!(key(1).open("journal", &sealed).is_err());
+    }
+
+    #[test]
+    fn altered_or_clear_value_is_refused() {
+        let mut sealed = key(1).seal("store", b"jobs").unwrap();
+        let last = if sealed.ends_with('0') { "1" } else { "0" };
+        sealed.replace_range(sealed.len() - 1.., last);
+        assert!(key(1).open("store", &sealed).is_err());
+        assert!(key(1).open("store", "{\"jobs\":[]}").is_err());
+        assert!(key(1).open("store", "00").is_err());
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(from_hex(&to_hex(&[0, 15, 255])), Some(vec![0, 15, 255]));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
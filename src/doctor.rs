@@ -0,0 +1,443 @@
+use std::fs::{self, OpenOptions};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::TOKEN_VAR;
+use crate::command::{CommandClient, CommandResponse};
+use crate::config::ServiceConfig;
+use crate::crypto::StoreKey;
+use crate::error_code::ErrorCode;
+use crate::store;
+use crate::tz::parse_timezone;
+
+/// How long the doctor waits for the Cronus service to answer, before deeming it hung.
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The earliest date the clock of a machine is deemed set at. A clock behind it most likely was never set, e.g. on a board without a
+/// real-time clock, and jobs would fire at the wrong times.
+const EARLIEST_CLOCK: (i32, u32, u32) = (2024, 1, 1);
+
+/// The difference between the clock of the machine and the clock of the file system of the command path beyond which they are deemed
+/// out of sync, e.g. on a network file system, which skews the times of the files of the service.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5);
+
+/// `CheckStatus` is an enumeration that represents the outcome of a check of the doctor.
+///
+/// # Variants
+///
+/// * `Ok` - Represents a check that passed.
+/// * `Warning` - Represents a check that found something that may get in the way, e.g. a service that is not running.
+/// * `Error` - Represents a check that found something keeping the service from working, e.g. a store that cannot be read.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// `Finding` is a structure that represents the outcome of a check of the doctor.
+///
+/// # Fields
+///
+/// * `check` - A string that represents the name of the check, e.g. `socket` or `store`.
+/// * `status` - A `CheckStatus` that represents the outcome of the check.
+/// * `message` - A string that represents a human readable description of the outcome.
+/// * `hint` - An `Option<String>` that represents what to do about it. It is `None` if there is nothing to do.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Finding {
+    pub check: String,
+    pub status: CheckStatus,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl Finding {
+    /// Creates a new `Finding` for a check that passed.
+    ///
+    /// # Arguments
+    ///
+    /// * `check` - A string that represents the name of the check.
+    /// * `message` - A string that represents what was checked.
+    ///
+    /// # Returns
+    ///
+    /// * `Finding` - Returns a new `Finding` instance.
+    fn ok(check: &str, message: impl Into<String>) -> Self {
+        Self {
+            check: check.to_string(),
+            status: CheckStatus::Ok,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    /// Creates a new `Finding` for a check that found a problem.
+    ///
+    /// # Arguments
+    ///
+    /// * `check` - A string that represents the name of the check.
+    /// * `status` - A `CheckStatus` that represents how serious the problem is.
+    /// * `message` - A string that represents the problem.
+    /// * `hint` - A string that represents what to do about it.
+    ///
+    /// # Returns
+    ///
+    /// * `Finding` - Returns a new `Finding` instance.
+    fn problem(
+        check: &str,
+        status: CheckStatus,
+        message: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            check: check.to_string(),
+            status,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// `Environment` is a structure that represents what a Cronus service runs with, as given to `cronus start`.
+///
+/// # Fields
+///
+/// * `name` - A string that represents the name of the service.
+/// * `path` - A `PathBuf` that represents the command acceptance path of the service.
+/// * `config` - An `Option<PathBuf>` that represents the configuration file of the service. It is `None` if it has none.
+/// * `store` - An `Option<PathBuf>` that represents the job store of the service. It is `None` if its jobs are kept in memory.
+/// * `store_key` - An `Option<PathBuf>` that represents the key file the job store is encrypted with. It is `None` if it is in clear.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    pub name: String,
+    pub path: PathBuf,
+    pub config: Option<PathBuf>,
+    pub store: Option<PathBuf>,
+    pub store_key: Option<PathBuf>,
+}
+
+/// Diagnoses the environment of a Cronus service, so what keeps it from starting or from being reached is found before it bites.
+///
+/// The doctor checks the command acceptance path can be written to, the socket of the service is not left over by a service that exited
+/// and the service answers, the clock of the machine is set and in sync with the file system, the timezone of the machine is known, and
+/// that the configuration file, the key file and the job store given can be read, without changing any of them.
+///
+/// # Arguments
+///
+/// * `env` - A reference to the `Environment` of the service.
+///
+/// # Returns
+///
+/// * `Vec<Finding>` - Returns the outcome of every check, in the order they are made.
+pub fn diagnose(env: &Environment) -> Vec<Finding> {
+    let mut findings = vec![check_command_path(&env.path)];
+    findings.push(check_socket(env));
+    findings.extend(check_clock(&env.path));
+    findings.push(check_timezone());
+    if let Some(config) = &env.config {
+        findings.push(match ServiceConfig::load(config) {
+            Ok(_) => Finding::ok("config", format!("{} is valid", config.display())),
+            Err(e) => Finding::problem(
+                "config",
+                CheckStatus::Error,
+                format!("{} cannot be loaded: {e}", config.display()),
+                "Fix the configuration file, the service does not start with it",
+            ),
+        });
+    }
+    let key = match &env.store_key {
+        Some(file) => match StoreKey::load(file) {
+            Ok(key) => {
+                findings.push(Finding::ok(
+                    "store_key",
+                    format!("{} holds a key", file.display()),
+                ));
+                Some(key)
+            }
+            Err(e) => {
+                findings.push(Finding::problem(
+                    "store_key",
+                    CheckStatus::Error,
+                    e.to_string(),
+                    "Give a key file readable by its owner only, e.g. written by openssl rand -hex 32",
+                ));
+                return findings;
+            }
+        },
+        None => None,
+    };
+    if let Some(file) = &env.store {
+        findings.extend(check_store(file, key.as_ref()));
+    }
+    findings
+}
+
+/// Checks that the command acceptance path is a directory files can be created in, as the service creates its socket, its run state and
+/// its audit log there.
+///
+/// # Arguments
+///
+/// * `path` - A path to the command acceptance path.
+///
+/// # Returns
+///
+/// * `Finding` - Returns the outcome of the check.
+fn check_command_path(path: &Path) -> Finding {
+    const CHECK: &str = "command_path";
+    if !path.is_dir() {
+        return Finding::problem(
+            CHECK,
+            CheckStatus::Error,
+            format!("{} is not a directory", path.display()),
+            format!(
+                "Create it with mkdir -p {}, or pass another one with -p",
+                path.display()
+            ),
+        );
+    }
+    match probe(path) {
+        Ok(_) => Finding::ok(CHECK, format!("{} is writable", path.display())),
+        Err(e) => Finding::problem(
+            CHECK,
+            CheckStatus::Error,
+            format!("{} is not writable: {e}", path.display()),
+            "Run cronus as a user that can write to it, or pass another path with -p",
+        ),
+    }
+}
+
+/// Checks the socket of the service, and whether the service answers on it.
+///
+/// # Arguments
+///
+/// * `env` - A reference to the `Environment` of the service.
+///
+/// # Returns
+///
+/// * `Finding` - Returns the outcome of the check.
+fn check_socket(env: &Environment) -> Finding {
+    const CHECK: &str = "socket";
+    let socket = env.path.join(&env.name);
+    let file_type = match fs::symlink_metadata(&socket) {
+        Ok(metadata) => metadata.file_type(),
+        Err(_) => {
+            return Finding::problem(
+                CHECK,
+                CheckStatus::Warning,
+                format!("Service `{}` is not running", env.name),
+                format!(
+                    "Start it with cronus start -n {} -p {}",
+                    env.name,
+                    env.path.display()
+                ),
+            )
+        }
+    };
+    if !file_type.is_socket() {
+        return Finding::problem(
+            CHECK,
+            CheckStatus::Error,
+            format!("{} is not a socket", socket.display()),
+            "Move the file away, or name the service otherwise with -n",
+        );
+    }
+    let response = CommandClient::new(env.name.clone(), env.path.clone()).and_then(|cc| {
+        cc.set_timeout(PING_TIMEOUT)?;
+        cc.ping_service()
+    });
+    match response {
+        Ok(CommandResponse::ServiceRunning) => {
+            Finding::ok(CHECK, format!("Service `{}` answers", env.name))
+        }
+        Ok(response) => Finding::problem(
+            CHECK,
+            CheckStatus::Error,
+            format!("Service `{}` answers {}", env.name, response.to_json_msg()),
+            format!("Check the token of the client in {TOKEN_VAR}"),
+        ),
+        Err(e) if matches!(e.downcast_ref(), Some(nng::Error::TimedOut)) => Finding::problem(
+            CHECK,
+            CheckStatus::Error,
+            format!(
+                "Service `{}` does not answer within {}",
+                env.name,
+                humantime::format_duration(PING_TIMEOUT)
+            ),
+            "The service is hung, kill its process and start it again",
+        ),
+        Err(e) if ErrorCode::of_error(e.as_ref()) == ErrorCode::ServiceUnavailable => {
+            Finding::problem(
+                CHECK,
+                CheckStatus::Warning,
+                format!(
+                    "{} is left over by a service that exited: {e}",
+                    socket.display()
+                ),
+                format!(
+                    "Start the service with cronus start -n {} -p {}, which replaces the socket",
+                    env.name,
+                    env.path.display()
+                ),
+            )
+        }
+        Err(e) => Finding::problem(
+            CHECK,
+            CheckStatus::Error,
+            format!("Service `{}` cannot be reached: {e}", env.name),
+            "Check that the socket is readable and writable by the user running cronus",
+        ),
+    }
+}
+
+/// Checks that the clock of the machine is set, and in sync with the clock of the file system of the command path.
+///
+/// # Arguments
+///
+/// * `path` - A path to the command acceptance path.
+///
+/// # Returns
+///
+/// * `Vec<Finding>` - Returns the outcome of the check, and of the check of the file system if the path is writable.
+fn check_clock(path: &Path) -> Vec<Finding> {
+    const CHECK: &str = "clock";
+    let now = Utc::now();
+    let shown = now.format("%Y-%m-%d %H:%M:%S UTC");
+    let (year, month, day) = EARLIEST_CLOCK;
+    let earliest = NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|at| at.and_utc())
+        .unwrap_or_default();
+    let mut findings = vec![if now < earliest {
+        Finding::problem(
+            CHECK,
+            CheckStatus::Error,
+            format!("The clock of the machine is at {shown}, it is most likely not set"),
+            "Set the clock, e.g. by synchronizing it with NTP, jobs fire at the wrong times until then",
+        )
+    } else {
+        Finding::ok(CHECK, format!("The clock of the machine is at {shown}"))
+    }];
+    let Ok(modified) = probe(path) else {
+        return findings;
+    };
+    let skew = match modified.duration_since(SystemTime::now()) {
+        Ok(ahead) => ahead,
+        Err(behind) => behind.duration(),
+    };
+    if skew > MAX_CLOCK_SKEW {
+        findings.push(Finding::problem(
+            "file_clock",
+            CheckStatus::Warning,
+            format!(
+                "The file system of {} is {} off the clock of the machine, at {}",
+                path.display(),
+                humantime::format_duration(Duration::from_secs(skew.as_secs())),
+                DateTime::<Utc>::from(modified)
+            ),
+            "Synchronize the clock of the file server, or use a local command path",
+        ));
+    }
+    findings
+}
+
+/// Checks that the timezone of the machine is known, if it is set with the `TZ` environment variable.
+///
+/// # Returns
+///
+/// * `Finding` - Returns the outcome of the check.
+fn check_timezone() -> Finding {
+    const CHECK: &str = "timezone";
+    match std::env::var("TZ") {
+        Ok(zone) => match parse_timezone(zone.trim_start_matches(':')) {
+            Ok(_) => Finding::ok(CHECK, format!("TZ `{zone}` is a known timezone")),
+            Err(e) => Finding::problem(
+                CHECK,
+                CheckStatus::Warning,
+                e,
+                "Set TZ to an IANA timezone, e.g. Europe/Paris, jobs without a timezone run in UTC until then",
+            ),
+        },
+        Err(_) => Finding::ok(CHECK, "Jobs without a timezone run in the local time of the machine"),
+    }
+}
+
+/// Checks that a job store can be opened, and is not readable by other users when it is in clear.
+///
+/// # Arguments
+///
+/// * `file` - A path to the store file.
+/// * `key` - An `Option<&StoreKey>` that represents the key the store is encrypted with. It is `None` if it is in clear.
+///
+/// # Returns
+///
+/// * `Vec<Finding>` - Returns the outcome of the check, with a finding for a temporary file left over by an interrupted write, if any.
+fn check_store(file: &Path, key: Option<&StoreKey>) -> Vec<Finding> {
+    const CHECK: &str = "store";
+    let mut findings = vec![match store::check(file, key) {
+        Ok(1) => Finding::ok(CHECK, format!("{} holds 1 job", file.display())),
+        Ok(jobs) => Finding::ok(CHECK, format!("{} holds {jobs} jobs", file.display())),
+        Err(e) => Finding::problem(
+            CHECK,
+            CheckStatus::Error,
+            format!("{} cannot be opened: {e}", file.display()),
+            "Restore the store from a backup, the service does not start with it",
+        ),
+    }];
+    let shared =
+        fs::metadata(file).is_ok_and(|metadata| metadata.permissions().mode() & 0o077 != 0);
+    if key.is_none() && shared {
+        findings.push(Finding::problem(
+            "store_permissions",
+            CheckStatus::Warning,
+            format!("{} can be read by other users", file.display()),
+            format!(
+                "Restrict it with chmod 600 {}, or encrypt it with --store-key",
+                file.display()
+            ),
+        ));
+    }
+    let mut temp = file.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    if temp.exists() {
+        findings.push(Finding::problem(
+            "store_temp",
+            CheckStatus::Warning,
+            format!(
+                "{} is left over by a write of the store that was interrupted",
+                temp.display()
+            ),
+            format!(
+                "Remove it with rm {}, the store holds the jobs as of the last complete write",
+                temp.display()
+            ),
+        ));
+    }
+    findings
+}
+
+/// Creates, and removes, a file in a directory, to find out whether it is writable.
+///
+/// # Arguments
+///
+/// * `dir` - A path to the directory.
+///
+/// # Returns
+///
+/// * `std::io::Result<SystemTime>` - Returns the time the file system recorded the file was modified at, or the error creating it.
+fn probe(dir: &Path) -> std::io::Result<SystemTime> {
+    let file = dir.join(format!(".cronus-doctor-{}", std::process::id()));
+    let modified = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&file)
+        .and_then(|created| created.metadata())
+        .and_then(|metadata| metadata.modified());
+    _ = fs::remove_file(&file);
+    modified
+}
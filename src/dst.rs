@@ -0,0 +1,333 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDateTime, Offset, TimeZone, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+
+use crate::projection;
+
+/// How many days ahead the next change of the offset of a timezone is looked for.
+const TRANSITION_SEARCH_DAYS: i64 = 366;
+
+/// How far ahead of the time they are run from the ticks moved by DST transitions are looked for, 28 years, after which the days of the
+/// week fall on the same dates again, so a schedule that has no tick in a transition by then has none at all.
+const RUNS_HORIZON: chrono::Duration = chrono::Duration::days(28 * 365 + 7);
+
+/// How far back the runs moved by a DST transition are looked for when computing the period of a run, longer than any transition.
+const DISPLACED_LOOKBACK: chrono::Duration = chrono::Duration::days(2);
+
+/// `DstPolicy` is an enumeration that represents what happens to a tick of a cron schedule evaluated in local time, or in a timezone,
+/// that falls into an hour a daylight-saving transition removes, e.g. 02:30 when clocks jump from 02:00 to 03:00, or repeats, e.g.
+/// 02:30 when clocks fall back from 03:00 to 02:00.
+///
+/// # Variants
+///
+/// * `Skip` - Represents not running the tick at all, neither in a missing hour nor in a repeated hour.
+/// * `Once` - Represents running a tick of a repeated hour once, at its first occurrence, and not running a tick of a missing hour.
+/// * `Shift` - Represents also running a tick of a missing hour at the shifted time, moved forward by the length of the transition,
+///   e.g. 02:30 at 03:30.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DstPolicy {
+    #[default]
+    Skip,
+    Once,
+    Shift,
+}
+
+impl FromStr for DstPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "once" => Ok(Self::Once),
+            "shift" => Ok(Self::Shift),
+            _ => Err(format!(
+                "Invalid DST policy `{s}`, expected `skip`, `once` or `shift`"
+            )),
+        }
+    }
+}
+
+impl DstPolicy {
+    /// Checks whether a tick moved by a DST transition runs under the policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `change` - A `DstChange` that represents the transition the tick falls into.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the tick runs.
+    pub fn runs(self, change: DstChange) -> bool {
+        match change {
+            DstChange::Gap => self == Self::Shift,
+            DstChange::Overlap => self != Self::Skip,
+        }
+    }
+}
+
+/// `DstChange` is an enumeration that represents the kind of a daylight-saving transition.
+///
+/// # Variants
+///
+/// * `Gap` - Represents clocks jumping forward, so the local times of the transition do not exist.
+/// * `Overlap` - Represents clocks falling back, so the local times of the transition occur twice.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DstChange {
+    Gap,
+    Overlap,
+}
+
+/// `DisplacedTick` is a structure that represents a tick of a cron schedule the job scheduler does not fire, as its local time falls
+/// into a daylight-saving transition.
+///
+/// # Fields
+///
+/// * `local` - A `NaiveDateTime` that represents the local time of the tick.
+/// * `at` - A `DateTime<Utc>` that represents the time the tick runs at, if its policy runs it: at the shifted time in a gap, at the
+///   first occurrence in an overlap.
+/// * `change` - A `DstChange` that represents the transition the tick falls into.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DisplacedTick {
+    pub local: NaiveDateTime,
+    pub at: DateTime<Utc>,
+    pub change: DstChange,
+}
+
+/// `Transition` is a structure that represents a change of the offset of a timezone.
+///
+/// # Fields
+///
+/// * `at` - A `DateTime<Utc>` that represents the time the new offset applies from.
+/// * `before` - An `i64` that represents the offset before the change, in seconds east of UTC.
+/// * `after` - An `i64` that represents the offset after the change, in seconds east of UTC.
+struct Transition {
+    at: DateTime<Utc>,
+    before: i64,
+    after: i64,
+}
+
+/// Returns the offset of a timezone at a time.
+///
+/// # Arguments
+///
+/// * `zone` - A reference to the timezone.
+/// * `at` - A `DateTime<Utc>` that represents the time.
+///
+/// # Returns
+///
+/// * `i64` - Returns the offset, in seconds east of UTC.
+fn offset_at<Z: TimeZone>(zone: &Z, at: DateTime<Utc>) -> i64 {
+    zone.offset_from_utc_datetime(&at.naive_utc())
+        .fix()
+        .local_minus_utc() as i64
+}
+
+/// Finds the next change of the offset of a timezone, looking a day at a time and then narrowing it down to the second.
+///
+/// # Arguments
+///
+/// * `zone` - A reference to the timezone.
+/// * `after` - A `DateTime<Utc>` that represents the time to look from, exclusive.
+///
+/// # Returns
+///
+/// * `Option<Transition>` - Returns the change, or `None` if the offset does not change within `TRANSITION_SEARCH_DAYS`.
+fn next_transition<Z: TimeZone>(zone: &Z, after: DateTime<Utc>) -> Option<Transition> {
+    let before = offset_at(zone, after);
+    let mut high = (1..=TRANSITION_SEARCH_DAYS)
+        .map(|days| after + chrono::Duration::days(days))
+        .find(|at| offset_at(zone, *at) != before)?;
+    let mut low = high - chrono::Duration::days(1);
+    while high - low > chrono::Duration::seconds(1) {
+        let middle = low + (high - low) / 2;
+        if offset_at(zone, middle) == before {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    Some(Transition {
+        at: high,
+        before,
+        after: offset_at(zone, high),
+    })
+}
+
+/// Finds the ticks of a schedule falling into a transition.
+///
+/// The schedule is walked on a clock without transitions, so every local time the transition removes or repeats is matched against
+/// its fields.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule`.
+/// * `transition` - A reference to the `Transition`.
+///
+/// # Returns
+///
+/// * `Vec<DisplacedTick>` - Returns the ticks of the local times of the transition, in chronological order.
+fn ticks_in(schedule: &Schedule, transition: &Transition) -> Vec<DisplacedTick> {
+    let change = if transition.after > transition.before {
+        DstChange::Gap
+    } else {
+        DstChange::Overlap
+    };
+    let local = |offset: i64| (transition.at + chrono::Duration::seconds(offset)).naive_utc();
+    let start = local(transition.before.min(transition.after));
+    let end = local(transition.before.max(transition.after));
+    schedule
+        .after(&Utc.from_utc_datetime(&(start - chrono::Duration::seconds(1))))
+        .map(|tick| tick.naive_utc())
+        .take_while(|tick| *tick < end)
+        .map(|local| DisplacedTick {
+            local,
+            at: Utc.from_utc_datetime(&local) - chrono::Duration::seconds(transition.before),
+            change,
+        })
+        .collect()
+}
+
+/// Finds the ticks of a schedule the job scheduler does not fire within a time window, as they fall into a DST transition.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule`.
+/// * `zone` - A reference to the timezone the schedule is evaluated in.
+/// * `from` - A `DateTime<Utc>` that represents the start of the window, exclusive.
+/// * `until` - A `DateTime<Utc>` that represents the end of the window, inclusive.
+///
+/// # Returns
+///
+/// * `Vec<DisplacedTick>` - Returns the ticks whose running time lies in the window, in chronological order.
+pub fn displaced_ticks<Z: TimeZone>(
+    schedule: &Schedule,
+    zone: &Z,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<DisplacedTick> {
+    // A tick of an overlap runs before its transition, and a tick of a gap after it, so the transitions of the days around the window
+    // are looked at.
+    let mut cursor = from - chrono::Duration::hours(24);
+    let mut ticks = Vec::new();
+    while let Some(transition) =
+        next_transition(zone, cursor).filter(|t| t.at <= until + chrono::Duration::hours(24))
+    {
+        ticks.extend(
+            ticks_in(schedule, &transition)
+                .into_iter()
+                .filter(|tick| tick.at > from && tick.at <= until),
+        );
+        cursor = transition.at;
+    }
+    ticks
+}
+
+/// Finds the period of a schedule a time falls in, counting the ticks a DST policy runs although the job scheduler does not fire them.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule`.
+/// * `zone` - A reference to the timezone the schedule is evaluated in.
+/// * `policy` - A `DstPolicy` that represents which ticks of DST transitions run.
+/// * `at` - A `DateTime<Utc>` that represents the time to find the period of.
+///
+/// # Returns
+///
+/// * `Option<(DateTime<Utc>, DateTime<Utc>)>` - Returns the last two ticks run up to `at`, oldest first, or `None` if the schedule has
+///   not fired twice by then.
+pub fn period_at<Z: TimeZone>(
+    schedule: &Schedule,
+    zone: &Z,
+    policy: DstPolicy,
+    at: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let period = projection::period_at(schedule, zone, at);
+    if policy == DstPolicy::Skip {
+        return period;
+    }
+    let mut ticks: Vec<_> = displaced_ticks(schedule, zone, at - DISPLACED_LOOKBACK, at)
+        .into_iter()
+        .filter(|tick| policy.runs(tick.change))
+        .map(|tick| tick.at)
+        .chain(period.into_iter().flat_map(|(start, end)| [start, end]))
+        .collect();
+    ticks.sort();
+    ticks.dedup();
+    match ticks[..] {
+        [.., start, end] => Some((start, end)),
+        _ => None,
+    }
+}
+
+/// `DstRuns` is a structure that represents the ticks of a schedule a DST policy runs although the job scheduler does not fire them,
+/// as an iterator computing them one transition at a time.
+///
+/// # Fields
+///
+/// * `schedule` - A `Schedule` that represents the cron schedule of the job.
+/// * `zone` - A `Z` that represents the timezone the schedule is evaluated in.
+/// * `policy` - A `DstPolicy` that represents which ticks of DST transitions run.
+/// * `after` - A `DateTime<Utc>` that represents the time the ticks are run from, exclusive.
+/// * `cursor` - A `DateTime<Utc>` that represents the time the next transition is looked for from.
+/// * `pending` - A `VecDeque<DateTime<Utc>>` that represents the times of the ticks of the last transition not walked yet.
+///
+/// The iterator ends once no transition within `RUNS_HORIZON` of `after` has a tick left, e.g. at once in a timezone without DST.
+pub struct DstRuns<Z> {
+    schedule: Schedule,
+    zone: Z,
+    policy: DstPolicy,
+    after: DateTime<Utc>,
+    cursor: DateTime<Utc>,
+    pending: VecDeque<DateTime<Utc>>,
+}
+
+impl<Z: TimeZone> DstRuns<Z> {
+    /// Constructs the runs of a schedule moved by DST transitions.
+    ///
+    /// # Arguments
+    ///
+    /// * `schedule` - A `Schedule` that represents the cron schedule of the job.
+    /// * `zone` - A `Z` that represents the timezone the schedule is evaluated in.
+    /// * `policy` - A `DstPolicy` that represents which ticks of DST transitions run.
+    /// * `after` - A `DateTime<Utc>` that represents the time the ticks are run from, exclusive.
+    ///
+    /// # Returns
+    ///
+    /// * `DstRuns<Z>` - Returns the iterator of the runs.
+    pub fn new(schedule: Schedule, zone: Z, policy: DstPolicy, after: DateTime<Utc>) -> Self {
+        Self {
+            schedule,
+            zone,
+            policy,
+            after,
+            cursor: after,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<Z: TimeZone> Iterator for DstRuns<Z> {
+    type Item = DateTime<Utc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(at) = self.pending.pop_front() {
+                return Some(at);
+            }
+            let transition = next_transition(&self.zone, self.cursor)
+                .filter(|transition| transition.at <= self.after + RUNS_HORIZON)?;
+            self.cursor = transition.at;
+            self.pending.extend(
+                ticks_in(&self.schedule, &transition)
+                    .into_iter()
+                    .filter(|tick| self.policy.runs(tick.change) && tick.at > self.after)
+                    .map(|tick| tick.at),
+            );
+        }
+    }
+}
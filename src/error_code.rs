@@ -0,0 +1,82 @@
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+/// `ErrorCode` is an enumeration that represents the kind of a failure of a command, printed as the `code` of its error and told by
+/// the exit code of the CLI, so scripts can branch on it.
+///
+/// # Variants
+///
+/// * `Failed` - Represents any other failure, e.g. an invalid argument, the failure of the service to run the command, or a failed run of
+///   `cronus exec`. Exits with 1.
+/// * `ServiceUnavailable` - Represents the Cronus service not running, or not answering in time. Exits with 2.
+/// * `NotFound` - Represents the job, or the run, the command is about not existing. Exits with 3.
+/// * `Forbidden` - Represents the token of the client, or the policy of the service, not permitting the command. Exits with 4.
+/// * `ReadOnly` - Represents a follower refusing a command changing or running jobs, to be sent to its primary. Exits with 5.
+/// * `Rejected` - Represents the service rejecting the content of the command, e.g. an invalid configuration file, or a batch one
+///   of whose commands failed. Exits with 6.
+/// * `Conflict` - Represents the command clashing with a registered job, e.g. adding a duplicate of a job. Exits with 7.
+/// * `Unsupported` - Represents a command, or a job, this version of the service does not support. Exits with 8.
+/// * `Throttled` - Represents the service refusing a request too large, or sent by a client sending too many, to be retried later. Exits
+///   with 9.
+/// * `QuotaExceeded` - Represents the service refusing a job beyond its quota, e.g. one more job of a cost center at its limit. Exits
+///   with 10.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Failed,
+    ServiceUnavailable,
+    NotFound,
+    Forbidden,
+    ReadOnly,
+    Rejected,
+    Conflict,
+    Unsupported,
+    Throttled,
+    QuotaExceeded,
+}
+
+impl ErrorCode {
+    /// Returns the exit code of the CLI for the failure.
+    ///
+    /// # Returns
+    ///
+    /// * `i32` - Returns the exit code, from 1 to 10, see the variants of `ErrorCode`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Failed => 1,
+            Self::ServiceUnavailable => 2,
+            Self::NotFound => 3,
+            Self::Forbidden => 4,
+            Self::ReadOnly => 5,
+            Self::Rejected => 6,
+            Self::Conflict => 7,
+            Self::Unsupported => 8,
+            Self::Throttled => 9,
+            Self::QuotaExceeded => 10,
+        }
+    }
+
+    /// Classifies an error of the CLI.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - A reference to the error.
+    ///
+    /// # Returns
+    ///
+    /// * `ErrorCode` - Returns `ServiceUnavailable` if the service cannot be reached, and `Failed` otherwise.
+    pub fn of_error(error: &(dyn Error + 'static)) -> Self {
+        match error.downcast_ref::<nng::Error>() {
+            Some(
+                nng::Error::ConnectionRefused
+                | nng::Error::ConnectionReset
+                | nng::Error::ConnectionAborted
+                | nng::Error::TimedOut
+                | nng::Error::Closed
+                | nng::Error::EntryNotFound,
+            ) => Self::ServiceUnavailable,
+            _ => Self::Failed,
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::apply::{FieldChange, PlanAction};
+use crate::history::RunRecord;
+use crate::job::{ExpiryAction, RunId};
+use crate::store::StoredJob;
+
+/// The maximum number of events kept for listing.
+const MAX_RECENT_EVENTS: usize = 1000;
+
+/// The capacity of the channel events are broadcast on.
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// `EventKind` is an enumeration that represents the different things that can happen to a job.
+///
+/// # Variants
+///
+/// * `RunSucceeded` - Represents a run of the job that finished successfully.
+/// * `RunFailed` - Represents a run of the job that failed. It contains a message that describes the failure.
+/// * `RunSkipped` - Represents a tick of the job that was not executed. It contains the reason the run was skipped.
+/// * `FailureAcknowledged` - Represents the acknowledgement of the last failure of the job.
+/// * `EventReceived` - Represents an event emitted on the topic the job listens on, which fires the job. It contains the topic and the payload of the event, if any.
+/// * `RunOverdue` - Represents a job that has not succeeded within the window it is expected to. It contains the window and the time the last successful run of the job finished in Unix timestamp, if any.
+/// * `JobDisabled` - Represents a job paused after failing too many times in a row. It contains the number of consecutive failed runs.
+/// * `JobExpired` - Represents a job whose schedule expired. It contains what happened to the job, which is announced afterwards
+///   with a `JobChanged` or a `JobRemoved` event.
+/// * `JobChanged` - Represents a job added, updated, paused or resumed. It contains the job as it is now, as kept in the job store.
+/// * `JobRemoved` - Represents a job deleted, or removed once its schedule expired.
+/// * `RunRecorded` - Represents a run of the job recorded in the run history. It contains the record of the run.
+/// * `RunLongRunning` - Represents a run of the job still in flight once it has run for the `notify_after` threshold of the job. It contains the threshold.
+/// * `JobReconciled` - Represents a job added, updated or removed to converge the jobs of the service to its manifest, see `--reconcile`. It contains what was done to the job and the fields an update changed.
+///
+/// The `JobChanged`, `JobRemoved` and `RunRecorded` events carry the state of the service, so a follower can mirror it from the events.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum EventKind {
+    RunSucceeded,
+    RunFailed {
+        message: String,
+    },
+    RunSkipped {
+        reason: String,
+    },
+    FailureAcknowledged,
+    EventReceived {
+        topic: String,
+        payload: Option<String>,
+    },
+    RunOverdue {
+        #[serde(with = "humantime_serde")]
+        expect_within: Duration,
+        last_success: Option<u64>,
+    },
+    JobDisabled {
+        failures: u32,
+    },
+    JobExpired {
+        action: ExpiryAction,
+    },
+    JobChanged {
+        job: StoredJob,
+    },
+    JobRemoved,
+    RunRecorded {
+        record: RunRecord,
+    },
+    RunLongRunning {
+        #[serde(with = "humantime_serde")]
+        running_for: Duration,
+    },
+    JobReconciled {
+        action: PlanAction,
+        changes: Vec<FieldChange>,
+    },
+}
+
+/// `Event` is a structure that represents something that happened to a job.
+///
+/// # Fields
+///
+/// * `seq` - A `u64` that represents the position of the event among the events of the service, from `1` on.
+/// * `at` - A `u64` that represents the time the event happened in Unix timestamp.
+/// * `job_id` - A string that represents the unique identifier of the job.
+/// * `run_id` - An `Option<RunId>` that represents the id of the run the event is about. It is `None` if the event is about the job itself.
+/// * `kind` - An `EventKind` that represents what happened.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Event {
+    #[serde(default)]
+    pub seq: u64,
+    pub at: u64,
+    pub job_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<RunId>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+/// `EventFeed` is a structure that represents the events of a service emitted after a position, read by its followers.
+///
+/// # Fields
+///
+/// * `events` - A vector of `Event` instances that represent the events kept after the position, oldest first.
+/// * `missed` - A `bool` that represents whether events after the position were dropped, or the position is ahead of the service, e.g.
+///   because it restarted, in which case the events cannot be caught up with.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct EventFeed {
+    pub events: Vec<Event>,
+    pub missed: bool,
+}
+
+/// `EventBus` is a structure that distributes the events of the scheduler.
+///
+/// Every emitted event is broadcast to the subscribers of the bus, and the most recent events are kept for listing.
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+    recent: Mutex<VecDeque<Event>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            sender,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl EventBus {
+    /// Emits an event for a job.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    /// * `kind` - An `EventKind` that represents what happened.
+    pub fn emit(&self, id: Uuid, kind: EventKind) {
+        self.publish(Event {
+            seq: 0,
+            at: Utc::now().timestamp() as u64,
+            job_id: id.to_string(),
+            run_id: None,
+            kind,
+        });
+    }
+
+    /// Emits an event for a run of a job.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    /// * `run_id` - A `RunId` that represents the ID of the run.
+    /// * `kind` - An `EventKind` that represents what happened.
+    pub fn emit_run(&self, id: Uuid, run_id: RunId, kind: EventKind) {
+        self.publish(Event {
+            seq: 0,
+            at: Utc::now().timestamp() as u64,
+            job_id: id.to_string(),
+            run_id: Some(run_id),
+            kind,
+        });
+    }
+
+    /// Publishes an event as it is, e.g. an event mirrored from another service, in its turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - An `Event` that represents what happened, whose `seq` is replaced by the position of the event on the bus.
+    pub fn publish(&self, mut event: Event) {
+        let mut recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        event.seq = recent.back().map_or(1, |last| last.seq + 1);
+        if recent.len() >= MAX_RECENT_EVENTS {
+            recent.pop_front();
+        }
+        recent.push_back(event.clone());
+        _ = self.sender.send(event);
+    }
+
+    /// Subscribes to the events emitted from now on.
+    ///
+    /// # Returns
+    ///
+    /// * `broadcast::Receiver<Event>` - Returns a receiver that yields every event emitted after the subscription.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Returns the most recent events, oldest first.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Event>` - Returns the most recent events.
+    pub fn recent(&self) -> Vec<Event> {
+        self.recent
+            .lock()
+            .map(|recent| recent.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the position of the last event emitted.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - Returns the `seq` of the last event, or `0` if no event has been emitted yet.
+    pub fn last_seq(&self) -> u64 {
+        let recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        recent.back().map_or(0, |last| last.seq)
+    }
+
+    /// Returns the events emitted after a position, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - A `u64` that represents the `seq` of the last event already read, `0` for none.
+    ///
+    /// # Returns
+    ///
+    /// * `EventFeed` - Returns the events kept after the position, telling whether some of them were already dropped.
+    pub fn since(&self, after: u64) -> EventFeed {
+        let recent = self.recent.lock().unwrap_or_else(PoisonError::into_inner);
+        let last = recent.back().map_or(0, |last| last.seq);
+        let first = recent.front().map_or(last + 1, |first| first.seq);
+        EventFeed {
+            events: recent
+                .iter()
+                .filter(|event| event.seq > after)
+                .cloned()
+                .collect(),
+            missed: after > last || first > after + 1,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::history::RunRecord;
+use crate::store::StoredJob;
+
+/// How often a follower reads the events its primary emitted since it last read them.
+pub const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `ServiceSnapshot` is a structure that represents the state of a service, read by a follower before it follows the events of the
+/// service, and whenever it cannot catch up with them, or archived by `cronus snapshot` to be restored into another service.
+///
+/// # Fields
+///
+/// * `seq` - A `u64` that represents the position of the last event emitted before the snapshot was taken, to follow the events from.
+/// * `jobs` - A vector of `StoredJob` instances that represent the jobs of the service, as kept in the job store.
+/// * `runs` - A `BTreeMap<Uuid, Vec<RunRecord>>` that represents the recorded runs of every job, oldest first.
+/// * `state` - A `BTreeMap<String, BTreeMap<String, Value>>` that represents the key/value state of every job by its id, empty if the
+///   snapshot was taken by an older service.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ServiceSnapshot {
+    pub seq: u64,
+    pub jobs: Vec<StoredJob>,
+    pub runs: BTreeMap<Uuid, Vec<RunRecord>>,
+    #[serde(default)]
+    pub state: BTreeMap<String, BTreeMap<String, Value>>,
+}
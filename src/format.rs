@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_yaml::value::{Tag, TaggedValue};
+use serde_yaml::{Mapping, Value};
+
+use crate::job::{CommandStdin, Job};
+use crate::pipeline::JobStep;
+use crate::workflow::WorkflowStep;
+
+/// The version of the serialized format of jobs and commands, bumped whenever a change cannot be read by older versions of cronus.
+pub const FORMAT_VERSION: u64 = 1;
+
+/// `JobPayload` is an enumeration that represents a job in its serialized format, whose variant is written as the `type` of the job.
+///
+/// The payload is externally tagged rather than internally tagged, so deserializing it does not buffer the jobs it nests, whose legacy
+/// YAML tags would be lost.
+///
+/// # Variants
+///
+/// * `Command` - Represents a command job, see `Job::Command`.
+/// * `RhaiScript` - Represents a Rhai script job, see `Job::RhaiScript`.
+/// * `RhaiScriptFile` - Represents a Rhai script file job, see `Job::RhaiScriptFile`.
+/// * `Container` - Represents a container job, see `Job::Container`.
+/// * `Lua` - Represents a Lua script job, see `Job::Lua`.
+/// * `LuaFile` - Represents a Lua script file job, see `Job::LuaFile`.
+/// * `Wasm` - Represents a WebAssembly job, see `Job::Wasm`.
+/// * `Sql` - Represents an SQL job, see `Job::Sql`.
+/// * `Ssh` - Represents a remote command job, see `Job::Ssh`.
+/// * `Workflow` - Represents a workflow job, see `Job::Workflow`.
+/// * `Pipeline` - Represents a pipeline job, see `Job::Pipeline`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JobPayload {
+    Command {
+        cmd: PathBuf,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stdin: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stdin_file: Option<PathBuf>,
+    },
+    RhaiScript {
+        script: String,
+    },
+    RhaiScriptFile {
+        file: PathBuf,
+    },
+    Container {
+        image: String,
+        #[serde(default)]
+        cmd: Vec<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+        #[serde(default)]
+        volumes: Vec<String>,
+    },
+    #[cfg(feature = "lua")]
+    Lua {
+        script: String,
+    },
+    #[cfg(feature = "lua")]
+    LuaFile {
+        file: PathBuf,
+    },
+    #[cfg(feature = "wasm")]
+    Wasm {
+        module_path: PathBuf,
+        func: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    #[cfg(feature = "sql")]
+    Sql {
+        url: String,
+        statement: String,
+    },
+    #[cfg(feature = "ssh")]
+    Ssh {
+        host: String,
+        user: String,
+        #[serde(default)]
+        key_path: Option<PathBuf>,
+        command: String,
+    },
+    Workflow {
+        steps: Vec<WorkflowStep>,
+    },
+    Pipeline {
+        steps: Vec<JobStep>,
+    },
+}
+
+/// `JobType` is an enumeration that represents the types of jobs this version of cronus supports, as named by the `type` of a payload.
+///
+/// Lua jobs are only supported when cronus is built with the `lua` feature, WebAssembly jobs with the `wasm` feature, SQL jobs with the
+/// `sql` feature and SSH jobs with the `ssh` feature. They are read as `Job::Unsupported` otherwise.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JobType {
+    Command,
+    RhaiScript,
+    RhaiScriptFile,
+    Container,
+    #[cfg(feature = "lua")]
+    Lua,
+    #[cfg(feature = "lua")]
+    LuaFile,
+    #[cfg(feature = "wasm")]
+    Wasm,
+    #[cfg(feature = "sql")]
+    Sql,
+    #[cfg(feature = "ssh")]
+    Ssh,
+    Workflow,
+    Pipeline,
+}
+
+/// `LegacyJob` is an enumeration that represents a job written before the format was versioned, externally tagged
+/// like `{"Command": ["/usr/local/bin/backup", ["--full"]]}`, or like `!Command [/usr/local/bin/backup, [--full]]` in YAML.
+#[derive(Deserialize)]
+enum LegacyJob {
+    Command(PathBuf, Vec<String>),
+    RhaiScript(String),
+    RhaiScriptFile(PathBuf),
+    Workflow(Vec<WorkflowStep>),
+}
+
+impl Serialize for Job {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let payload = match self.clone() {
+            Job::Command(cmd, args, stdin) => {
+                let (stdin, stdin_file) = match stdin {
+                    Some(CommandStdin::Text(text)) => (Some(text), None),
+                    Some(CommandStdin::File(file)) => (None, Some(file)),
+                    None => (None, None),
+                };
+                JobPayload::Command {
+                    cmd,
+                    args,
+                    stdin,
+                    stdin_file,
+                }
+            }
+            Job::RhaiScript(script) => JobPayload::RhaiScript { script },
+            Job::RhaiScriptFile(file) => JobPayload::RhaiScriptFile { file },
+            Job::Container {
+                image,
+                cmd,
+                env,
+                volumes,
+            } => JobPayload::Container {
+                image,
+                cmd,
+                env,
+                volumes,
+            },
+            #[cfg(feature = "lua")]
+            Job::Lua(script) => JobPayload::Lua { script },
+            #[cfg(feature = "lua")]
+            Job::LuaFile(file) => JobPayload::LuaFile { file },
+            #[cfg(feature = "wasm")]
+            Job::Wasm {
+                module_path,
+                func,
+                args,
+            } => JobPayload::Wasm {
+                module_path,
+                func,
+                args,
+            },
+            #[cfg(feature = "sql")]
+            Job::Sql { url, statement } => JobPayload::Sql { url, statement },
+            #[cfg(feature = "ssh")]
+            Job::Ssh {
+                host,
+                user,
+                key_path,
+                command,
+            } => JobPayload::Ssh {
+                host,
+                user,
+                key_path,
+                command,
+            },
+            Job::Workflow(steps) => JobPayload::Workflow { steps },
+            Job::Pipeline(steps) => JobPayload::Pipeline { steps },
+            Job::Unsupported { kind, version } => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("version", &version)?;
+                map.serialize_entry("type", &kind)?;
+                return map.end();
+            }
+        };
+        let Value::Tagged(payload) = serde_yaml::to_value(payload).map_err(S::Error::custom)?
+        else {
+            return Err(S::Error::custom(
+                "A job payload must be tagged with its type",
+            ));
+        };
+        let fields = match payload.value {
+            Value::Mapping(fields) => fields,
+            _ => Mapping::new(),
+        };
+        let mut map = serializer.serialize_map(Some(fields.len() + 2))?;
+        map.serialize_entry("version", &FORMAT_VERSION)?;
+        map.serialize_entry("type", &payload.tag.to_string().trim_start_matches('!'))?;
+        for (name, value) in &fields {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Job {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        job_from_value(Value::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// Reads a job from its serialized format.
+///
+/// A job whose `type` is unknown, or written in a newer version of the format, is read as a `Job::Unsupported`, so it can be rejected
+/// with a helpful message rather than failing the whole document it belongs to. A job without a `type` is read in the legacy format.
+/// The job is buffered as a YAML value, which keeps the tags of legacy YAML jobs and can be read from any other format as well.
+///
+/// # Arguments
+///
+/// * `value` - A `Value` that represents the serialized job.
+///
+/// # Returns
+///
+/// * `Result<Job, String>` - Returns the job, or an error message if the job is malformed.
+fn job_from_value(value: Value) -> Result<Job, String> {
+    let Value::Mapping(mut fields) = value else {
+        return legacy_job_from_value(value);
+    };
+    let Some(kind) = fields.remove("type") else {
+        return legacy_job_from_value(Value::Mapping(fields));
+    };
+    let Value::String(kind) = kind else {
+        return Err("The type of a job must be a string".to_string());
+    };
+    let version = match fields.remove("version") {
+        Some(version) => version
+            .as_u64()
+            .ok_or("The version of a job must be a positive integer")?,
+        None => FORMAT_VERSION,
+    };
+    if version > FORMAT_VERSION
+        || serde_yaml::from_value::<JobType>(Value::String(kind.clone())).is_err()
+    {
+        return Ok(Job::Unsupported { kind, version });
+    }
+    let payload = serde_yaml::from_value(Value::Tagged(Box::new(TaggedValue {
+        tag: Tag::new(kind),
+        value: Value::Mapping(fields),
+    })))
+    .map_err(|e| e.to_string())?;
+    Ok(match payload {
+        JobPayload::Command {
+            cmd,
+            args,
+            stdin,
+            stdin_file,
+        } => {
+            let stdin = match (stdin, stdin_file) {
+                (Some(_), Some(_)) => {
+                    return Err(
+                        "A command job takes either `stdin` or `stdin_file`, not both".to_string(),
+                    )
+                }
+                (Some(text), None) => Some(CommandStdin::Text(text)),
+                (None, file) => file.map(CommandStdin::File),
+            };
+            Job::Command(cmd, args, stdin)
+        }
+        JobPayload::RhaiScript { script } => Job::RhaiScript(script),
+        JobPayload::RhaiScriptFile { file } => Job::RhaiScriptFile(file),
+        JobPayload::Container {
+            image,
+            cmd,
+            env,
+            volumes,
+        } => Job::Container {
+            image,
+            cmd,
+            env,
+            volumes,
+        },
+        #[cfg(feature = "lua")]
+        JobPayload::Lua { script } => Job::Lua(script),
+        #[cfg(feature = "lua")]
+        JobPayload::LuaFile { file } => Job::LuaFile(file),
+        #[cfg(feature = "wasm")]
+        JobPayload::Wasm {
+            module_path,
+            func,
+            args,
+        } => Job::Wasm {
+            module_path,
+            func,
+            args,
+        },
+        #[cfg(feature = "sql")]
+        JobPayload::Sql { url, statement } => Job::Sql { url, statement },
+        #[cfg(feature = "ssh")]
+        JobPayload::Ssh {
+            host,
+            user,
+            key_path,
+            command,
+        } => Job::Ssh {
+            host,
+            user,
+            key_path,
+            command,
+        },
+        JobPayload::Workflow { steps } => Job::Workflow(steps),
+        JobPayload::Pipeline { steps } => Job::Pipeline(steps),
+    })
+}
+
+/// Reads a job written in the legacy format.
+///
+/// # Arguments
+///
+/// * `value` - A `Value` that represents the serialized job, either a YAML tagged value or a map with the variant as its single key.
+///
+/// # Returns
+///
+/// * `Result<Job, String>` - Returns the job, or an error message if the job is malformed.
+fn legacy_job_from_value(value: Value) -> Result<Job, String> {
+    let value = match value {
+        Value::Mapping(mapping) if mapping.len() == 1 => {
+            let Some((Value::String(tag), value)) = mapping.into_iter().next() else {
+                return Err("A job must be tagged with its type".to_string());
+            };
+            Value::Tagged(Box::new(TaggedValue {
+                tag: Tag::new(tag),
+                value,
+            }))
+        }
+        value => value,
+    };
+    serde_yaml::from_value(value)
+        .map(|legacy| match legacy {
+            LegacyJob::Command(cmd, args) => Job::Command(cmd, args, None),
+            LegacyJob::RhaiScript(script) => Job::RhaiScript(script),
+            LegacyJob::RhaiScriptFile(file) => Job::RhaiScriptFile(file),
+            LegacyJob::Workflow(steps) => Job::Workflow(steps),
+        })
+        .map_err(|e| e.to_string())
+}
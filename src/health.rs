@@ -0,0 +1,216 @@
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::task::spawn_blocking;
+
+use crate::command::CommandClient;
+use crate::store::journal_file;
+
+/// How long a probe waits for the command loop of the service to answer a ping.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `ServiceState` is an enumeration that represents what a service is doing, as reported by its health probes.
+///
+/// # Variants
+///
+/// * `Starting` - Represents a service restoring its jobs and running its start hooks, not taking commands yet.
+/// * `Standby` - Represents a service waiting for the lease on the job store it shares with another service, see `Lease`.
+/// * `Primary` - Represents a service scheduling its jobs and taking commands.
+/// * `Follower` - Represents a service mirroring its primary, taking the commands only reading its state.
+/// * `Stopping` - Represents a service stopping, once it was sent a `StopService` command.
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceState {
+    Starting,
+    Standby,
+    Primary,
+    Follower,
+    Stopping,
+}
+
+/// `Check` is a structure that represents the outcome of a check of a health probe.
+///
+/// # Fields
+///
+/// * `ok` - A `bool` that represents whether the check passed.
+/// * `error` - An `Option<String>` that represents why the check failed. It is `None` if it passed.
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct Check {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    /// Creates a new `Check` from the result of the check.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - A `Result<(), String>` that represents the result of the check.
+    ///
+    /// # Returns
+    ///
+    /// * `Check` - Returns the check, failed with the error of the result if any.
+    fn of(result: Result<(), String>) -> Self {
+        Self {
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+}
+
+/// `HealthReport` is a structure that represents the answer of a health probe, served as the JSON body of `/healthz` and `/readyz`.
+///
+/// # Fields
+///
+/// * `healthy` - A `bool` that represents whether the probe passed, answered with `200 OK`, or else with `503 Service Unavailable`.
+/// * `state` - A `ServiceState` that represents what the service is doing.
+/// * `uptime` - A `Duration` that represents how long ago the service was started.
+/// * `command_loop` - An `Option<Check>` that represents whether the command loop of the service answered a ping over its command
+///   socket in time. It is `None` while the service is starting or on standby, as it takes no commands yet.
+/// * `store` - An `Option<Check>` that represents whether the journal of the job store of the service can be written, as every change
+///   of a job is appended to it before it is acknowledged. It is `None` if the jobs of the service are only kept in memory.
+#[derive(Debug, PartialEq, Serialize, Clone)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub state: ServiceState,
+    #[serde(with = "humantime_serde")]
+    pub uptime: Duration,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_loop: Option<Check>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store: Option<Check>,
+}
+
+/// `Health` is a structure that represents the health of a service, shared by the service, which reports its state, and its health
+/// probes, see `serve_health`.
+///
+/// # Fields
+///
+/// * `name` - A string that represents the name of the command socket of the service, pinged by the probes.
+/// * `path` - A `PathBuf` that represents the path of the command socket of the service.
+/// * `store` - An `Option<PathBuf>` that represents the job store of the service. It is `None` if the jobs are only kept in memory.
+/// * `started` - An `Instant` that represents when the service was started.
+/// * `state` - A `Mutex<ServiceState>` that represents what the service is doing.
+pub struct Health {
+    name: String,
+    path: PathBuf,
+    store: Option<PathBuf>,
+    started: Instant,
+    state: Mutex<ServiceState>,
+}
+
+impl Health {
+    /// Constructs a new `Health` for a service being started.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string that represents the name of the command socket of the service.
+    /// * `path` - A `PathBuf` that represents the path of the command socket of the service.
+    /// * `store` - An `Option<PathBuf>` that represents the job store of the service, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Health` - Returns the health of the service, in the `Starting` state.
+    pub fn new(name: String, path: PathBuf, store: Option<PathBuf>) -> Self {
+        Self {
+            name,
+            path,
+            store,
+            started: Instant::now(),
+            state: Mutex::new(ServiceState::Starting),
+        }
+    }
+
+    /// Reports what the service is doing.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - A `ServiceState` that represents what the service is doing now.
+    pub fn set_state(&self, state: ServiceState) {
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = state;
+    }
+
+    /// Probes whether the service is alive, i.e. whether it is starting, on standby or stopping, or else whether its command loop
+    /// answers. A service failing this probe is stuck, and is to be restarted.
+    ///
+    /// # Returns
+    ///
+    /// * `HealthReport` - Returns the report of the probe.
+    pub async fn liveness(&self) -> HealthReport {
+        let mut report = self.report().await;
+        report.healthy = match (report.state, &report.command_loop) {
+            (ServiceState::Primary | ServiceState::Follower, Some(check)) => check.ok,
+            _ => true,
+        };
+        report
+    }
+
+    /// Probes whether the service is ready, i.e. whether it is a primary or a follower whose command loop answers and whose job store,
+    /// if any, can be written. A service failing this probe is to be sent no traffic, e.g. a standby.
+    ///
+    /// # Returns
+    ///
+    /// * `HealthReport` - Returns the report of the probe.
+    pub async fn readiness(&self) -> HealthReport {
+        let mut report = self.report().await;
+        report.healthy = matches!(report.state, ServiceState::Primary | ServiceState::Follower)
+            && report.command_loop.as_ref().is_some_and(|check| check.ok)
+            && report.store.as_ref().is_none_or(|check| check.ok);
+        report
+    }
+
+    /// Checks the command loop and the job store of the service, without judging its health.
+    ///
+    /// # Returns
+    ///
+    /// * `HealthReport` - Returns the report of the checks, not healthy yet.
+    async fn report(&self) -> HealthReport {
+        let state = *self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let command_loop = match state {
+            ServiceState::Starting | ServiceState::Standby => None,
+            _ => Some(Check::of(self.ping().await)),
+        };
+        let store = self.store.as_deref().map(|store| {
+            let journal = journal_file(store);
+            Check::of(
+                OpenOptions::new()
+                    .append(true)
+                    .open(&journal)
+                    .map(drop)
+                    .map_err(|e| format!("Cannot write `{}`: {e}", journal.display())),
+            )
+        });
+        HealthReport {
+            healthy: false,
+            state,
+            uptime: Duration::from_secs(self.started.elapsed().as_secs()),
+            command_loop,
+            store,
+        }
+    }
+
+    /// Pings the command loop of the service over its command socket, waiting for it `PROBE_TIMEOUT` at most.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the command loop answered, whatever its response, or an error message if not.
+    async fn ping(&self) -> Result<(), String> {
+        let name = self.name.clone();
+        let path = self.path.clone();
+        spawn_blocking(move || {
+            CommandClient::new(name, path)
+                .and_then(|cc| {
+                    cc.set_timeout(PROBE_TIMEOUT)?;
+                    cc.ping_service()
+                })
+                .map(drop)
+                .map_err(|e| format!("The command loop does not answer: {e}"))
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()))
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Duration;
+
+use chrono::{DateTime, Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::idempotency::IdempotencyLedger;
+use crate::job::RunId;
+use crate::stats::Usage;
+
+/// The maximum number of runs kept in the history of a single job.
+const MAX_RUNS_PER_JOB: usize = 100;
+
+/// The number of days the execution time of a job is accounted for.
+const MAX_USAGE_DAYS: u64 = 400;
+
+/// The weight of the latest run in the duration estimate of a job, the rest being the estimate before it.
+const DURATION_SMOOTHING: f64 = 0.3;
+
+/// `RunStatus` is an enumeration that represents the outcome of a run.
+///
+/// # Variants
+///
+/// * `Succeeded` - Represents a run that finished successfully.
+/// * `Failed` - Represents a run that failed.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+}
+
+/// `RunRecord` is a structure that represents a single execution of a job.
+///
+/// # Fields
+///
+/// * `job_id` - A string that represents the unique identifier of the executed job, or the stage and the name of the hook of a system run.
+/// * `run_id` - An `Option<RunId>` that represents the id of the execution, see `Tick::run_id`. It is `None` for executions recorded before runs had ids.
+/// * `scheduled_at` - A `u64` that represents the time the execution was scheduled for in Unix timestamp.
+/// * `started_at` - A `u64` that represents the time the execution actually started in Unix timestamp.
+/// * `delay` - A `Duration` that represents the effective random delay applied before the execution.
+/// * `duration` - A `Duration` that represents how long the execution took.
+/// * `status` - A `RunStatus` that represents the outcome of the execution.
+/// * `error` - An `Option<String>` that represents why the execution failed. It is `None` if the execution succeeded.
+/// * `cost_center` - An `Option<String>` that represents the cost center the execution is charged to. It is `None` if the execution is not charged to any cost center.
+/// * `idempotency_key` - An `Option<String>` that represents the idempotency key of the execution. It is `None` if the job has no idempotency key.
+/// * `result` - An `Option<Value>` that represents the value the execution returned, e.g. the last expression of a Rhai script. It is `None` if the execution returned nothing.
+/// * `exit_code` - An `Option<i32>` that represents the exit code of the process of the execution, e.g. of a command. It is `None` if the execution has no process, or its process was killed by a signal.
+/// * `window_start` - An `Option<u64>` that represents the start of the window the execution processed in Unix timestamp, see `Tick::window_start`. It is `None` for system runs, and executions recorded before windows were.
+/// * `window_end` - An `Option<u64>` that represents the end of the window the execution processed in Unix timestamp, the time its tick is for, see `Tick::window_end`. It is `None` for system runs, and executions recorded before windows were.
+/// * `retry_of` - An `Option<RunId>` that represents the id of the failed execution this one retries, see `cronus retry`. It is `None` if the execution is not a retry.
+/// * `declaration` - An `Option<String>` that represents the fingerprint of the payload and the options of the job the execution ran, see `crypto::fingerprint`, so a retry tells whether the job changed since. It is `None` for system runs, and executions recorded before declarations were.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RunRecord {
+    pub job_id: String,
+    #[serde(default)]
+    pub run_id: Option<RunId>,
+    pub scheduled_at: u64,
+    pub started_at: u64,
+    #[serde(with = "humantime_serde")]
+    pub delay: Duration,
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+    pub status: RunStatus,
+    pub error: Option<String>,
+    pub cost_center: Option<String>,
+    pub idempotency_key: Option<String>,
+    #[serde(default)]
+    pub result: Option<Value>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub window_start: Option<u64>,
+    #[serde(default)]
+    pub window_end: Option<u64>,
+    #[serde(default)]
+    pub retry_of: Option<RunId>,
+    #[serde(default)]
+    pub declaration: Option<String>,
+}
+
+/// `RunHistory` is a structure that keeps the most recent runs of every job.
+///
+/// Only the last `MAX_RUNS_PER_JOB` runs of a job are kept, older runs are discarded as new ones are recorded.
+/// The execution time of every run is also accounted by local day, for the last `MAX_USAGE_DAYS` days, both for its job and for its
+/// cost center. The execution time of a cost center outlives the jobs charged to it.
+/// The duration of the runs of every job is estimated by exponential smoothing, so the estimate follows a job whose runs get slower or
+/// faster while one-off outliers have little weight. The idempotency keys of the runs that succeeded are kept per job in a ledger, which outlives both the jobs and the service.
+#[derive(Debug, Default)]
+pub struct RunHistory {
+    runs: HashMap<Uuid, VecDeque<RunRecord>>,
+    usage: HashMap<Uuid, BTreeMap<NaiveDate, Usage>>,
+    cost_usage: HashMap<String, BTreeMap<NaiveDate, Usage>>,
+    estimates: HashMap<Uuid, Duration>,
+    last_failures: HashMap<Uuid, u64>,
+    last_successes: HashMap<Uuid, u64>,
+    keys: IdempotencyLedger,
+}
+
+impl RunHistory {
+    /// Constructs a new, empty `RunHistory` keeping the idempotency keys of runs in a ledger.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - An `IdempotencyLedger` that represents the idempotency keys of the runs that already succeeded.
+    ///
+    /// # Returns
+    ///
+    /// * `RunHistory` - Returns the run history.
+    pub fn new(keys: IdempotencyLedger) -> Self {
+        Self {
+            keys,
+            ..Self::default()
+        }
+    }
+
+    /// Claims the idempotency key of a run of a job about to be carried out.
+    ///
+    /// The key is released once the run is recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job of the run.
+    /// * `key` - A string that represents the idempotency key of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the run may be carried out, or `false` if a run of the job with the same key already succeeded or is
+    ///   in flight.
+    pub fn claim_key(&mut self, id: Uuid, key: &str) -> bool {
+        self.keys.claim(id, key)
+    }
+
+    /// Records a run of a job.
+    ///
+    /// The duration estimate of the job is updated with the duration of the run. The idempotency key of the run, if any, is released, and kept
+    /// in the ledger if the run succeeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the executed job.
+    /// * `record` - A `RunRecord` that represents the run to be recorded.
+    pub fn record(&mut self, id: Uuid, record: RunRecord) {
+        if let Some(started_at) = DateTime::from_timestamp(record.started_at as i64, 0) {
+            let day = started_at.with_timezone(&Local).date_naive();
+            let usage = Usage {
+                time: record.duration,
+                runs: 1,
+                failures: u32::from(record.status == RunStatus::Failed),
+            };
+            account(self.usage.entry(id).or_default(), day, usage);
+            if let Some(cost_center) = &record.cost_center {
+                account(
+                    self.cost_usage.entry(cost_center.clone()).or_default(),
+                    day,
+                    usage,
+                );
+            }
+        }
+        self.estimates
+            .entry(id)
+            .and_modify(|estimate| {
+                *estimate = record.duration.mul_f64(DURATION_SMOOTHING)
+                    + estimate.mul_f64(1.0 - DURATION_SMOOTHING);
+            })
+            .or_insert(record.duration);
+        match record.status {
+            RunStatus::Failed => {
+                self.last_failures.insert(id, record.started_at);
+            }
+            RunStatus::Succeeded => {
+                self.last_successes
+                    .insert(id, record.started_at + record.duration.as_secs());
+            }
+        }
+        if let Some(key) = &record.idempotency_key {
+            // A key that cannot be appended to the ledger file is still kept in memory, until the service restarts.
+            _ = self
+                .keys
+                .release(id, key, record.status == RunStatus::Succeeded);
+        }
+        let runs = self.runs.entry(id).or_default();
+        if runs.len() >= MAX_RUNS_PER_JOB {
+            runs.pop_front();
+        }
+        runs.push_back(record);
+    }
+
+    /// Returns the recorded runs of a job, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<RunRecord>` - Returns the recorded runs of the job, or an empty vector if the job has never been run.
+    pub fn runs(&self, id: &Uuid) -> Vec<RunRecord> {
+        self.runs
+            .get(id)
+            .map(|runs| runs.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Finds a recorded run by its id, among the runs of every job.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - A `RunId` that represents the id of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(Uuid, RunRecord)>` - Returns the ID of the job of the run and the run, or `None` if no run kept has the id.
+    pub fn find_run(&self, run_id: RunId) -> Option<(Uuid, RunRecord)> {
+        self.runs.iter().find_map(|(id, runs)| {
+            runs.iter()
+                .find(|run| run.run_id == Some(run_id))
+                .map(|run| (*id, run.clone()))
+        })
+    }
+
+    /// Returns the estimated duration of the runs of a job.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Duration>` - Returns the exponentially smoothed duration of the runs of the job, or `None` if the job has never been run.
+    pub fn estimated_duration(&self, id: &Uuid) -> Option<Duration> {
+        self.estimates.get(id).copied()
+    }
+
+    /// Returns the time the last failed run of a job started.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<u64>` - Returns the time the last failed run of the job started in Unix timestamp, or `None` if no run of the job failed.
+    pub fn last_failure(&self, id: &Uuid) -> Option<u64> {
+        self.last_failures.get(id).copied()
+    }
+
+    /// Returns the time the last successful run of a job finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<u64>` - Returns the time the last successful run of the job finished in Unix timestamp, or `None` if no run of the job succeeded.
+    pub fn last_success(&self, id: &Uuid) -> Option<u64> {
+        self.last_successes.get(id).copied()
+    }
+
+    /// Returns the execution time of a job by local day.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `BTreeMap<NaiveDate, Usage>` - Returns the usage of the job on every day it ran, or an empty map if the job has never been run.
+    pub fn daily_usage(&self, id: &Uuid) -> BTreeMap<NaiveDate, Usage> {
+        self.usage.get(id).cloned().unwrap_or_default()
+    }
+
+    /// Returns the execution time charged to every cost center by local day.
+    ///
+    /// # Returns
+    ///
+    /// * `BTreeMap<String, BTreeMap<NaiveDate, Usage>>` - Returns the usage of every cost center on every day it was charged, by cost center.
+    pub fn cost_center_usage(&self) -> BTreeMap<String, BTreeMap<NaiveDate, Usage>> {
+        self.cost_usage
+            .iter()
+            .map(|(cost_center, usage)| (cost_center.clone(), usage.clone()))
+            .collect()
+    }
+
+    /// Removes the recorded runs, the accounted execution time, the duration estimate and the last failure and success of a job.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    pub fn remove(&mut self, id: &Uuid) {
+        self.runs.remove(id);
+        self.usage.remove(id);
+        self.estimates.remove(id);
+        self.last_failures.remove(id);
+        self.last_successes.remove(id);
+    }
+}
+
+/// Accounts the execution time of a run on a day, discarding the days older than `MAX_USAGE_DAYS`.
+///
+/// # Arguments
+///
+/// * `daily` - A mutable reference to the usage by local day the run is accounted in.
+/// * `day` - A `NaiveDate` that represents the local day the run started on.
+/// * `usage` - A `Usage` that represents the run.
+fn account(daily: &mut BTreeMap<NaiveDate, Usage>, day: NaiveDate, usage: Usage) {
+    *daily.entry(day).or_default() += usage;
+    if let Some(oldest) = day.checked_sub_days(Days::new(MAX_USAGE_DAYS)) {
+        daily.retain(|day, _| *day > oldest);
+    }
+}
@@ -0,0 +1,313 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+use crate::credentials::RunAs;
+use crate::history::{RunRecord, RunStatus};
+use crate::http_client;
+use crate::job::{Job, JobBusiness, JobIdentity, JobOptions, KillSwitch, Tick};
+use crate::limits::ResourceLimits;
+use crate::output::{OutputPatterns, RunOutput};
+
+/// The id the runs of the service hooks are recorded under, listed with `cronus history -i system`.
+pub const SYSTEM_RUNS: Uuid = Uuid::nil();
+
+/// `HookStage` is an enumeration that represents when a service hook runs.
+///
+/// # Variants
+///
+/// * `Start` - Represents the start of the service, before it accepts commands.
+/// * `Stop` - Represents the shutdown of the service, once the runs in flight are over.
+/// * `Reload` - Represents the reload of the configuration of the service.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum HookStage {
+    Start,
+    Stop,
+    Reload,
+}
+
+impl fmt::Display for HookStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HookStage::Start => "start",
+            HookStage::Stop => "stop",
+            HookStage::Reload => "reload",
+        })
+    }
+}
+
+/// `Hook` is a structure that represents a job the service runs at a stage of its lifecycle, e.g. to mount the network storage its jobs need.
+///
+/// # Fields
+///
+/// * `name` - A string that represents the name of the hook, which its runs are recorded with.
+/// * `job` - A `Job` that represents what the hook runs, run like the job itself would be.
+/// * `timeout` - An `Option<Duration>` that represents how long the hook may run before it is killed. It is `None` if the hook is waited for however long it takes.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Hook {
+    pub name: String,
+    pub job: Job,
+    #[serde(default, with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+}
+
+/// Runs hooks one after the other, in the order they are declared.
+///
+/// A hook that fails does not keep the next ones from running. A hook running longer than its timeout is killed, like a killed run.
+///
+/// # Arguments
+///
+/// * `stage` - A `HookStage` that represents the stage the hooks run at.
+/// * `hooks` - A slice of `Hook` instances that represent the hooks to be run.
+///
+/// # Returns
+///
+/// * `Vec<RunRecord>` - Returns the runs of the hooks, to be recorded as system runs under `SYSTEM_RUNS`. The `job_id` of a run is the
+///   stage and the name of its hook, e.g. `start:mount`.
+pub async fn run_hooks(stage: HookStage, hooks: &[Hook]) -> Vec<RunRecord> {
+    let mut runs = Vec::with_capacity(hooks.len());
+    for hook in hooks {
+        runs.push(run_hook(&stage.to_string(), hook).await);
+    }
+    runs
+}
+
+/// Runs a hook, killing it if it runs longer than its timeout.
+///
+/// # Arguments
+///
+/// * `label` - A string that represents what the hook runs for, e.g. its stage, prefixed to the name of the hook in the `job_id` of its run.
+/// * `hook` - A reference to the `Hook` to be run.
+///
+/// # Returns
+///
+/// * `RunRecord` - Returns the run of the hook, whose `job_id` is the label and the name of the hook, e.g. `start:mount`.
+pub async fn run_hook(label: &str, hook: &Hook) -> RunRecord {
+    let identity = JobIdentity {
+        id: format!("{label}:{}", hook.name),
+        name: Some(hook.name.clone()),
+        ..JobIdentity::default()
+    };
+    let business =
+        hook.job
+            .clone()
+            .to_business(identity, ResourceLimits::default(), RunAs::default());
+    let kill_switch = KillSwitch::default();
+    let run_kill_switch = kill_switch.clone();
+    let output = RunOutput::default();
+    let run_output = output.clone();
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let tick = Tick::at(started_at);
+    let mut run = spawn_blocking(move || business(tick, &run_kill_switch, &run_output));
+    let result = match hook.timeout {
+        Some(limit) => match timeout(limit, &mut run).await {
+            Ok(result) => result,
+            Err(_) => {
+                kill_switch.kill();
+                _ = run.await;
+                Ok(Err(format!(
+                    "Hook timed out after {}",
+                    humantime::format_duration(limit)
+                )))
+            }
+        },
+        None => run.await,
+    }
+    .unwrap_or_else(|_| Err("Hook panicked".to_string()));
+    let (status, error) = match result {
+        Ok(()) => (RunStatus::Succeeded, None),
+        Err(message) => (RunStatus::Failed, Some(message)),
+    };
+    RunRecord {
+        job_id: format!("{label}:{}", hook.name),
+        run_id: Some(tick.run_id),
+        scheduled_at: started_at.timestamp() as u64,
+        started_at: started_at.timestamp() as u64,
+        delay: Duration::ZERO,
+        duration: started.elapsed(),
+        status,
+        error,
+        cost_center: None,
+        idempotency_key: None,
+        result: output.result(),
+        exit_code: output.exit_code(),
+        window_start: None,
+        window_end: None,
+        retry_of: None,
+        declaration: None,
+    }
+}
+
+/// `JobHooks` is a structure that represents the business functions of the hooks of a job, run by the runs of the job around its own
+/// business function, e.g. to ping a healthcheck URL before and after a backup.
+///
+/// # Fields
+///
+/// * `on_start` - An `Option<JobBusiness>` that represents the hook run before the job. It is `None` if the job has no such hook.
+/// * `on_success` - An `Option<JobBusiness>` that represents the hook run once the job has succeeded. It is `None` if the job has no such hook.
+/// * `on_failure` - An `Option<JobBusiness>` that represents the hook run once the job has failed. It is `None` if the job has no such hook.
+/// * `healthcheck_url` - An `Option<String>` that represents the ping URL of the job, without a trailing slash. It is `None` if the job is not monitored.
+/// * `patterns` - A `Result<OutputPatterns, String>` that represents the patterns the output of the job is matched against, or the error
+///   message of an invalid regular expression, which fails every run.
+#[derive(Clone)]
+pub struct JobHooks {
+    on_start: Option<JobBusiness>,
+    on_success: Option<JobBusiness>,
+    on_failure: Option<JobBusiness>,
+    healthcheck_url: Option<String>,
+    patterns: Result<OutputPatterns, String>,
+}
+
+impl JobHooks {
+    /// Builds the business functions of the hooks of a job.
+    ///
+    /// The hooks run on behalf of the job, with its identity, limits and user, so they see the same job id and state as the job.
+    ///
+    /// # Arguments
+    ///
+    /// * `identity` - A reference to the `JobIdentity` of the job.
+    /// * `options` - A reference to the `JobOptions` declaring the hooks of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `JobHooks` - Returns the hooks of the job.
+    pub fn new(identity: &JobIdentity, options: &JobOptions) -> Self {
+        let business = |hook: &Option<Job>| {
+            hook.clone()
+                .map(|job| job.to_business(identity.clone(), options.limits(), options.run_as()))
+        };
+        Self {
+            on_start: business(&options.on_start),
+            on_success: business(&options.on_success),
+            on_failure: business(&options.on_failure),
+            healthcheck_url: options
+                .healthcheck_url
+                .as_ref()
+                .map(|url| url.trim_end_matches('/').to_string()),
+            patterns: options.output_patterns(),
+        }
+    }
+
+    /// Runs the business function of a job between its hooks.
+    ///
+    /// The `on_start` hook runs first, then the job, then the `on_success` or the `on_failure` hook depending on the outcome of the job.
+    /// A job with a success or a failure regex fails when its output does not match them, even if it exited with `0`, see
+    /// `RunOutput::verdict`; only the lines of the job are matched, not those of its hooks.
+    /// A job with a healthcheck URL pings `<url>/start` before anything else, and `<url>` or `<url>/fail` once everything else is over,
+    /// following the healthchecks.io protocol, so a monitor alerts when a run fails, takes too long or never happens. The closing ping
+    /// carries the output of the run, and its error if it failed, as its body.
+    /// The hooks share the tick and the kill switch of the run, and their lines are written to the output of the run once they are over,
+    /// prefixed with their name, e.g. `[on_start] `. A hook that fails is reported in the output, but changes neither whether the job
+    /// runs nor the outcome of the run, and the value a hook returns is not recorded as the result of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `business` - A reference to the `JobBusiness` of the job.
+    /// * `tick` - A `Tick` that represents the tick of the run.
+    /// * `kill_switch` - A reference to the `KillSwitch` of the run.
+    /// * `output` - A reference to the `RunOutput` of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns the outcome of the business function of the job.
+    pub fn run_around(
+        &self,
+        business: &JobBusiness,
+        tick: Tick,
+        kill_switch: &KillSwitch,
+        output: &RunOutput,
+    ) -> Result<(), String> {
+        self.ping("/start", None, output);
+        Self::run_hook("on_start", &self.on_start, tick, kill_switch, output);
+        let result = self.patterns.clone().and_then(|patterns| {
+            output.expect(patterns);
+            let result = business(tick, kill_switch, output);
+            let verdict = output.verdict();
+            result.and(verdict)
+        });
+        match &result {
+            Ok(()) => {
+                Self::run_hook("on_success", &self.on_success, tick, kill_switch, output);
+                self.ping("", Some(Self::report(output, None)), output);
+            }
+            Err(e) => {
+                Self::run_hook("on_failure", &self.on_failure, tick, kill_switch, output);
+                self.ping("/fail", Some(Self::report(output, Some(e))), output);
+            }
+        }
+        result
+    }
+
+    /// Pings the healthcheck URL of a job, if the job has one, reporting a failed ping in the output of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A string that represents what is signaled, appended to the URL: `/start`, `/fail` or nothing for a success.
+    /// * `body` - An `Option<String>` that represents the report posted with the ping. It is `None` if the ping is a `GET` request.
+    /// * `output` - A reference to the `RunOutput` of the run.
+    fn ping(&self, path: &str, body: Option<String>, output: &RunOutput) {
+        let Some(url) = &self.healthcheck_url else {
+            return;
+        };
+        let url = format!("{url}{path}");
+        let sent = match &body {
+            Some(body) => http_client::request("POST", &url, Some(body)),
+            None => http_client::request("GET", &url, None),
+        };
+        if let Err(e) = sent {
+            output.push(format!("[healthcheck] Ping failed: {e}"));
+        }
+    }
+
+    /// Builds the report of a run posted with its closing ping.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - A reference to the `RunOutput` of the run.
+    /// * `error` - An `Option<&String>` that represents the error message of the run. It is `None` if the run succeeded.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns the lines of output of the run, followed with its error message if it failed.
+    fn report(output: &RunOutput, error: Option<&String>) -> String {
+        let mut lines = output.read_from(0).lines;
+        lines.extend(error.cloned());
+        lines.join("\n")
+    }
+
+    /// Runs a hook of a job, if the job has it, writing its lines to the output of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string that represents the name of the hook, prefixed to its lines.
+    /// * `hook` - A reference to the `Option<JobBusiness>` of the hook.
+    /// * `tick` - A `Tick` that represents the tick of the run.
+    /// * `kill_switch` - A reference to the `KillSwitch` of the run.
+    /// * `output` - A reference to the `RunOutput` of the run.
+    fn run_hook(
+        name: &str,
+        hook: &Option<JobBusiness>,
+        tick: Tick,
+        kill_switch: &KillSwitch,
+        output: &RunOutput,
+    ) {
+        let Some(hook) = hook else {
+            return;
+        };
+        let hook_output = RunOutput::default();
+        let result = hook(tick, kill_switch, &hook_output);
+        for line in hook_output.read_from(0).lines {
+            output.push(format!("[{name}] {line}"));
+        }
+        if let Err(e) = result {
+            output.push(format!("[{name}] Hook failed: {e}"));
+        }
+    }
+}
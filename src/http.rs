@@ -0,0 +1,415 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::spawn_blocking;
+use uuid::Uuid;
+
+use crate::command::{CommandClient, CommandResponse};
+use crate::error_code::ErrorCode;
+use crate::health::Health;
+use crate::job::RunId;
+use crate::manifest::JobSpec;
+use crate::CronusResult;
+
+/// The largest request head accepted by the HTTP API, in bytes.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// The largest request body accepted by the HTTP API, in bytes.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// The bundled single-page web UI served on `/`.
+#[cfg(feature = "web-ui")]
+const WEB_UI: &str = include_str!("../assets/web-ui.html");
+
+/// `ApiCall` is the command an HTTP API request is relayed as.
+type ApiCall = Box<dyn FnOnce(&CommandClient) -> CronusResult<CommandResponse> + Send>;
+
+/// `HttpResponse` is a structure that represents the response to an HTTP API request.
+///
+/// # Fields
+///
+/// * `status` - A `u16` that represents the HTTP status code of the response.
+/// * `content_type` - A string that represents the media type of the body.
+/// * `body` - A string that represents the body of the response.
+struct HttpResponse {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl HttpResponse {
+    /// Creates a new JSON `HttpResponse`.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - A `u16` that represents the HTTP status code of the response.
+    /// * `body` - A string that represents the JSON body of the response.
+    ///
+    /// # Returns
+    ///
+    /// * `HttpResponse` - Returns the new response.
+    fn json(status: u16, body: String) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body,
+        }
+    }
+
+    /// Creates a new JSON `HttpResponse` describing an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - A `u16` that represents the HTTP status code of the response.
+    /// * `message` - A string that describes the error.
+    ///
+    /// # Returns
+    ///
+    /// * `HttpResponse` - Returns the new response, with a `{"error": message}` body like the command line prints.
+    fn error(status: u16, message: impl ToString) -> Self {
+        Self::json(status, json!({"error": message.to_string()}).to_string())
+    }
+}
+
+/// Serves the HTTP API of a Cronus service.
+///
+/// Every request is relayed to the service as a command over its command socket, so the HTTP API behaves exactly like the command line.
+/// The API is made of the following routes, answering with the same JSON the command line prints:
+///
+/// * `GET /api/jobs` - Lists the jobs.
+/// * `POST /api/jobs` - Adds a job, declared by the body of the request as a JSON object like a job of a job file.
+/// * `DELETE /api/jobs/<id>` - Deletes a job.
+/// * `GET /api/events` - Lists the most recent events.
+/// * `GET /api/jobs/<id>/history` - Lists the recorded runs of a job.
+/// * `GET /api/jobs/<id>/output` - Reads the output of the run of a job started last.
+/// * `GET /api/stats` - Reports the machine time consumed by the jobs.
+/// * `GET /api/stats/cost-centers` - Reports the machine time charged to the cost centers.
+/// * `GET /api/digest` - Reports the jobs consuming the most machine time.
+/// * `POST /api/jobs/<id>/trigger` - Runs a job right away.
+/// * `POST /api/jobs/<id>/pause` - Pauses a job.
+/// * `POST /api/jobs/<id>/resume` - Resumes a job.
+/// * `POST /api/jobs/<id>/kill` - Kills the runs of a job in flight.
+///
+/// Every request presents the token of its `Authorization: Bearer <token>` header to the service, never the token of the service itself,
+/// so once the configuration declares tokens, a request its token does not permit is answered with `403 Forbidden`, like a job beyond
+/// the quota of the service. Every request works in the namespace of its token, see `AuthToken::namespace`. A command the service
+/// rejects as invalid is answered with `400 Bad Request`, and a command it fails to carry out with `500 Internal Server Error`.
+///
+/// With the `web-ui` feature, `GET /` also serves a bundled single-page web UI built on these routes.
+///
+/// # Arguments
+///
+/// * `listener` - A `TcpListener` that represents the socket the HTTP API listens on.
+/// * `name` - A string that represents the name of the command socket of the service.
+/// * `path` - A `PathBuf` that represents the path of the command socket of the service.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns an error if the listener stops accepting connections.
+pub async fn serve_http(listener: TcpListener, name: String, path: PathBuf) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let name = name.clone();
+        let path = path.clone();
+        tokio::spawn(async move {
+            _ = handle_connection(stream, name, path).await;
+        });
+    }
+}
+
+/// Serves the health probes of a Cronus service, for container orchestrators and load balancers.
+///
+/// The probes are served on an address of their own, without a token, and answer with a `HealthReport` as JSON, with `200 OK` if the
+/// probe passed or `503 Service Unavailable` if not:
+///
+/// * `GET /healthz` - Probes whether the service is alive, see `Health::liveness`.
+/// * `GET /readyz` - Probes whether the service is ready, see `Health::readiness`.
+///
+/// # Arguments
+///
+/// * `listener` - A `TcpListener` that represents the socket the probes are served on.
+/// * `health` - An `Arc<Health>` that represents the health of the service.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns an error if the listener stops accepting connections.
+pub async fn serve_health(listener: TcpListener, health: Arc<Health>) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let health = health.clone();
+        tokio::spawn(async move {
+            _ = handle_probe(stream, health).await;
+        });
+    }
+}
+
+/// Handles a single connection to the health probes, answering one probe and closing the connection.
+///
+/// # Arguments
+///
+/// * `stream` - A `TcpStream` that represents the connection.
+/// * `health` - An `Arc<Health>` that represents the health of the service.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns an error if the connection fails.
+async fn handle_probe(mut stream: TcpStream, health: Arc<Health>) -> io::Result<()> {
+    let (request, head_end) = match read_head(&mut stream).await? {
+        Some(Ok(read)) => read,
+        Some(Err(response)) => return write_response(&mut stream, response).await,
+        None => return Ok(()),
+    };
+    let head = String::from_utf8_lossy(&request[..head_end]);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let target = request_line.next().unwrap_or_default();
+    let (target, _) = target.split_once('?').unwrap_or((target, ""));
+    let report = match (method, target) {
+        ("GET", "/healthz") => health.liveness().await,
+        ("GET", "/readyz") => health.readiness().await,
+        _ => return write_response(&mut stream, HttpResponse::error(404, "Not found")).await,
+    };
+    let status = if report.healthy { 200 } else { 503 };
+    let body = serde_json::to_string(&report).unwrap_or_default();
+    write_response(&mut stream, HttpResponse::json(status, body)).await
+}
+
+/// Reads the head of an HTTP request from a connection.
+///
+/// # Arguments
+///
+/// * `stream` - A mutable reference to the `TcpStream` that represents the connection.
+///
+/// # Returns
+///
+/// * `io::Result<Option<Result<(Vec<u8>, usize), HttpResponse>>>` - Returns the bytes read, along with the length of the head they
+///   start with, or the response refusing a head larger than `MAX_HEAD_BYTES`, or `None` if the connection was closed before the head
+///   ended, or an error if the connection fails.
+async fn read_head(
+    stream: &mut TcpStream,
+) -> io::Result<Option<Result<(Vec<u8>, usize), HttpResponse>>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    loop {
+        if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            return Ok(Some(Ok((request, end + 4))));
+        }
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buf[..read]);
+        if request.len() > MAX_HEAD_BYTES {
+            return Ok(Some(Err(HttpResponse::error(431, "Request too large"))));
+        }
+    }
+}
+
+/// Handles a single HTTP connection, answering one request and closing the connection.
+///
+/// # Arguments
+///
+/// * `stream` - A `TcpStream` that represents the connection.
+/// * `name` - A string that represents the name of the command socket of the service.
+/// * `path` - A `PathBuf` that represents the path of the command socket of the service.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns an error if the connection fails.
+async fn handle_connection(mut stream: TcpStream, name: String, path: PathBuf) -> io::Result<()> {
+    let (mut request, head_end) = match read_head(&mut stream).await? {
+        Some(Ok(read)) => read,
+        Some(Err(response)) => return write_response(&mut stream, response).await,
+        None => return Ok(()),
+    };
+    let mut buf = [0; 1024];
+    let head = String::from_utf8_lossy(&request[..head_end]).into_owned();
+    let header = |name: &str| {
+        head.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(header, _)| header.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().to_string())
+    };
+    let content_length = header("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or_default();
+    let token =
+        header("authorization").and_then(|value| value.strip_prefix("Bearer ").map(str::to_string));
+    if content_length > MAX_BODY_BYTES {
+        return write_response(
+            &mut stream,
+            HttpResponse::error(413, "Request body too large"),
+        )
+        .await;
+    }
+    let mut body = request.split_off(head_end);
+    while body.len() < content_length {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        body.extend_from_slice(&buf[..read]);
+    }
+    body.truncate(content_length);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let target = request_line.next().unwrap_or_default();
+    let response = route(method, target, &body, token, name, path).await;
+    write_response(&mut stream, response).await
+}
+
+/// Routes an HTTP API request.
+///
+/// # Arguments
+///
+/// * `method` - A string that represents the method of the request.
+/// * `target` - A string that represents the target of the request, whose `run` query parameter, if any, picks the run whose output is
+///   read or which is killed.
+/// * `body` - A byte slice that represents the body of the request, empty if it has none.
+/// * `token` - An `Option<String>` that represents the bearer token of the request, presented to the service in place of its own. It is
+///   `None` if the request has no `Authorization` header.
+/// * `name` - A string that represents the name of the command socket of the service.
+/// * `path` - A `PathBuf` that represents the path of the command socket of the service.
+///
+/// # Returns
+///
+/// * `HttpResponse` - Returns the response to the request.
+async fn route(
+    method: &str,
+    target: &str,
+    body: &[u8],
+    token: Option<String>,
+    name: String,
+    path: PathBuf,
+) -> HttpResponse {
+    let (target, query) = target.split_once('?').unwrap_or((target, ""));
+    let run_id = match query
+        .split('&')
+        .find_map(|param| param.strip_prefix("run="))
+        .map(str::parse::<RunId>)
+        .transpose()
+    {
+        Ok(run_id) => run_id,
+        Err(e) => return HttpResponse::error(400, e),
+    };
+    let segments: Vec<_> = target.trim_matches('/').split('/').collect();
+    let call: ApiCall = match (method, segments.as_slice()) {
+        #[cfg(feature = "web-ui")]
+        ("GET", [""]) => {
+            return HttpResponse {
+                status: 200,
+                content_type: "text/html; charset=utf-8",
+                body: WEB_UI.to_string(),
+            }
+        }
+        ("GET", ["api", "jobs"]) => Box::new(|cc| cc.list_jobs()),
+        ("POST", ["api", "jobs"]) => {
+            let spec: JobSpec = match serde_json::from_slice(body) {
+                Ok(spec) => spec,
+                Err(e) => return HttpResponse::error(400, format!("Invalid job: {e}")),
+            };
+            if let Err(e) = spec.validate() {
+                return HttpResponse::error(400, e);
+            }
+            let options = spec.options();
+            Box::new(move |cc| cc.add_job(spec.trigger, spec.job, options, false, false, false))
+        }
+        ("DELETE", ["api", "jobs", id]) => {
+            if Uuid::parse_str(id).is_err() {
+                return HttpResponse::error(400, "Invalid job id");
+            }
+            let id = id.to_string();
+            Box::new(move |cc| cc.delete_job(id))
+        }
+        ("GET", ["api", "events"]) => Box::new(|cc| cc.list_events()),
+        ("GET", ["api", "stats"]) => Box::new(|cc| cc.job_stats(None)),
+        ("GET", ["api", "stats", "cost-centers"]) => Box::new(|cc| cc.cost_center_stats()),
+        ("GET", ["api", "digest"]) => Box::new(|cc| cc.usage_digest()),
+        (method, ["api", "jobs", id, action]) => {
+            if Uuid::parse_str(id).is_err() {
+                return HttpResponse::error(400, "Invalid job id");
+            }
+            let id = id.to_string();
+            match (method, *action) {
+                ("GET", "history") => Box::new(move |cc| cc.job_history(id)),
+                ("GET", "output") => Box::new(move |cc| cc.tail_output(id, 0, run_id)),
+                ("POST", "trigger") => Box::new(move |cc| cc.trigger_job(id)),
+                ("POST", "pause") => Box::new(move |cc| cc.pause_job(id)),
+                ("POST", "resume") => Box::new(move |cc| cc.resume_job(id)),
+                ("POST", "kill") => Box::new(move |cc| cc.kill_run(id, run_id)),
+                _ => return HttpResponse::error(404, "Not found"),
+            }
+        }
+        _ => return HttpResponse::error(404, "Not found"),
+    };
+    let result = spawn_blocking(move || {
+        CommandClient::new(name, path)
+            .and_then(|mut cc| {
+                cc.set_token(token);
+                call(&cc)
+            })
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok(response @ CommandResponse::Forbidden(_)) => {
+            HttpResponse::json(403, response.to_json_msg())
+        }
+        Ok(response @ CommandResponse::Throttled(_)) => {
+            HttpResponse::json(429, response.to_json_msg())
+        }
+        Ok(response @ CommandResponse::QuotaExceeded(_)) => {
+            HttpResponse::json(403, response.to_json_msg())
+        }
+        Ok(
+            response @ CommandResponse::Error {
+                code: ErrorCode::Rejected,
+                ..
+            },
+        ) => HttpResponse::json(400, response.to_json_msg()),
+        Ok(response @ CommandResponse::Error { .. }) => {
+            HttpResponse::json(500, response.to_json_msg())
+        }
+        Ok(response) => HttpResponse::json(200, response.to_json_msg()),
+        Err(e) => HttpResponse::error(502, e),
+    }
+}
+
+/// Writes an HTTP response to a connection.
+///
+/// # Arguments
+///
+/// * `stream` - A mutable reference to the `TcpStream` that represents the connection.
+/// * `response` - An `HttpResponse` that represents the response to be written.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns an error if the connection fails.
+async fn write_response(stream: &mut TcpStream, response: HttpResponse) -> io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        413 => "Content Too Large",
+        429 => "Too Many Requests",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Bad Gateway",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,281 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+
+/// How long an HTTP request may take to connect, and to send or receive data after it has connected.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The largest HTTP response read, in bytes.
+const MAX_RESPONSE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// The TLS configuration of the HTTPS requests, trusting the Mozilla root certificates, built on the first HTTPS request.
+static TLS_CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+
+/// Checks that a URL can be requested, i.e. that it is an `http://` or an `https://` URL.
+///
+/// # Arguments
+///
+/// * `url` - A string that represents the URL to be checked.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the URL can be requested, or an error message if not.
+pub fn validate_url(url: &str) -> Result<(), String> {
+    split_url(url).map(|_| ())
+}
+
+/// Sends an HTTP request and reads the body of its response, e.g. for the `http_get` and `http_post` functions of scripts.
+///
+/// The request is sent as HTTP/1.0 over TCP, or over TLS for an `https://` URL, so the response is never chunked and ends when the
+/// server closes the connection. The certificate of an HTTPS server must be issued by one of the Mozilla root certificates.
+///
+/// # Arguments
+///
+/// * `method` - A string that represents the method of the request, e.g. `GET`.
+/// * `url` - A string that represents the URL of the request, e.g. `http://localhost:8080/health`.
+/// * `body` - An `Option<&str>` that represents the body of the request. It is `None` if the request has no body.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - Returns the body of the response, or an error message if the URL is neither an `http://` nor an
+///   `https://` URL, the request fails, or the response does not have a 2xx status.
+pub fn request(method: &str, url: &str, body: Option<&str>) -> Result<String, String> {
+    send(method, url, body, None)
+}
+
+/// Posts a JSON document to a URL, e.g. a chat webhook, and reads the body of its response, see `request`.
+///
+/// # Arguments
+///
+/// * `url` - A string that represents the URL of the request, e.g. `https://hooks.slack.com/services/...`.
+/// * `body` - A reference to the JSON `Value` posted.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - Returns the body of the response, or an error message if the request fails or the response does not
+///   have a 2xx status.
+pub fn post_json(url: &str, body: &serde_json::Value) -> Result<String, String> {
+    send(
+        "POST",
+        url,
+        Some(&body.to_string()),
+        Some("application/json"),
+    )
+}
+
+/// Sends an HTTP request and reads the body of its response, see `request`.
+///
+/// # Arguments
+///
+/// * `method` - A string that represents the method of the request, e.g. `GET`.
+/// * `url` - A string that represents the URL of the request.
+/// * `body` - An `Option<&str>` that represents the body of the request. It is `None` if the request has no body.
+/// * `content_type` - An `Option<&str>` that represents the media type of the body. It is `None` if the request does not declare it.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - Returns the body of the response, or an error message if the request fails or the response does not
+///   have a 2xx status.
+fn send(
+    method: &str,
+    url: &str,
+    body: Option<&str>,
+    content_type: Option<&str>,
+) -> Result<String, String> {
+    let (tls, authority, target) = split_url(url)?;
+    let has_port = authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    let address = match (has_port, tls) {
+        (true, _) => authority.to_string(),
+        (false, false) => format!("{authority}:80"),
+        (false, true) => format!("{authority}:443"),
+    };
+    let socket = address
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {authority}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {authority}"))?;
+    let failed = |e: std::io::Error| format!("HTTP request to {url} failed: {e}");
+    let stream = TcpStream::connect_timeout(&socket, HTTP_TIMEOUT).map_err(failed)?;
+    stream
+        .set_read_timeout(Some(HTTP_TIMEOUT))
+        .map_err(failed)?;
+    stream
+        .set_write_timeout(Some(HTTP_TIMEOUT))
+        .map_err(failed)?;
+    let response = if tls {
+        let host = match authority.rsplit_once(':') {
+            Some((host, _)) if has_port => host,
+            _ => authority,
+        };
+        let name = ServerName::try_from(host.to_string())
+            .map_err(|e| format!("Invalid host name `{host}`: {e}"))?;
+        let connection = ClientConnection::new(tls_config()?, name)
+            .map_err(|e| format!("HTTP request to {url} failed: {e}"))?;
+        exchange(
+            StreamOwned::new(connection, stream),
+            authority,
+            method,
+            target,
+            body,
+            content_type,
+        )?
+    } else {
+        exchange(stream, authority, method, target, body, content_type)?
+    };
+    let (status, body) = response.ok_or_else(|| format!("Malformed HTTP response from {url}"))?;
+    if !(200..300).contains(&status) {
+        return Err(format!("HTTP request to {url} failed with status {status}"));
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Sends an HTTP request with a JSON body to a server listening on a Unix socket, e.g. the Docker Engine API, and reads its response.
+///
+/// # Arguments
+///
+/// * `socket` - A reference to the path of the Unix socket.
+/// * `method` - A string that represents the method of the request, e.g. `POST`.
+/// * `target` - A string that represents the target of the request, e.g. `/containers/create`.
+/// * `body` - An `Option<&str>` that represents the JSON body of the request. It is `None` if the request has no body.
+/// * `timeout` - An `Option<Duration>` that represents how long the request may wait for data. It is `None` if it may wait forever.
+///
+/// # Returns
+///
+/// * `Result<(u16, Vec<u8>), String>` - Returns the status and the body of the response whatever the status, or an error message if the
+///   request fails.
+pub fn unix_request(
+    socket: &Path,
+    method: &str,
+    target: &str,
+    body: Option<&str>,
+    timeout: Option<Duration>,
+) -> Result<(u16, Vec<u8>), String> {
+    let failed = |e: std::io::Error| format!("HTTP request to {} failed: {e}", socket.display());
+    let stream = UnixStream::connect(socket).map_err(failed)?;
+    stream.set_read_timeout(timeout).map_err(failed)?;
+    stream.set_write_timeout(timeout).map_err(failed)?;
+    exchange(
+        stream,
+        "localhost",
+        method,
+        target,
+        body,
+        Some("application/json"),
+    )?
+    .ok_or_else(|| format!("Malformed HTTP response from {}", socket.display()))
+}
+
+/// Splits a URL into its scheme, its authority and its target.
+///
+/// # Arguments
+///
+/// * `url` - A string that represents the URL, e.g. `https://hc-ping.com/<uuid>`.
+///
+/// # Returns
+///
+/// * `Result<(bool, &str, &str), String>` - Returns whether the URL is an `https://` URL, its host with its optional port, and its path
+///   with its query, or an error message if the URL is neither an `http://` nor an `https://` URL.
+fn split_url(url: &str) -> Result<(bool, &str, &str), String> {
+    let (tls, rest) = match (url.strip_prefix("http://"), url.strip_prefix("https://")) {
+        (Some(rest), _) => (false, rest),
+        (_, Some(rest)) => (true, rest),
+        _ => {
+            return Err(format!(
+                "Unsupported URL `{url}`, expected an http:// or https:// URL"
+            ))
+        }
+    };
+    let (authority, target) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(format!("Invalid URL `{url}`, it has no host"));
+    }
+    Ok((tls, authority, target))
+}
+
+/// Returns the TLS configuration of the HTTPS requests, building it on the first call.
+///
+/// # Returns
+///
+/// * `Result<Arc<ClientConfig>, String>` - Returns the configuration, trusting the Mozilla root certificates, or an error message if TLS
+///   cannot be configured.
+fn tls_config() -> Result<Arc<ClientConfig>, String> {
+    if let Some(config) = TLS_CONFIG.get() {
+        return Ok(config.clone());
+    }
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| format!("Cannot configure TLS: {e}"))?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    Ok(TLS_CONFIG.get_or_init(|| Arc::new(config)).clone())
+}
+
+/// Sends an HTTP/1.0 request over a connected stream and reads its response.
+///
+/// # Arguments
+///
+/// * `stream` - A connected stream, read until the server closes it.
+/// * `authority` - A string that represents the host of the request.
+/// * `method` - A string that represents the method of the request.
+/// * `target` - A string that represents the target of the request.
+/// * `body` - An `Option<&str>` that represents the body of the request. It is `None` if the request has no body.
+/// * `content_type` - An `Option<&str>` that represents the media type of the body. It is `None` if the request does not declare it.
+///
+/// # Returns
+///
+/// * `Result<Option<(u16, Vec<u8>)>, String>` - Returns the status and the body of the response, `None` if the response is malformed, or
+///   an error message if the request fails.
+fn exchange(
+    mut stream: impl Read + Write,
+    authority: &str,
+    method: &str,
+    target: &str,
+    body: Option<&str>,
+    content_type: Option<&str>,
+) -> Result<Option<(u16, Vec<u8>)>, String> {
+    let failed = |e: std::io::Error| format!("HTTP request to {authority}{target} failed: {e}");
+    let mut head = format!(
+        "{method} {target} HTTP/1.0\r\nHost: {authority}\r\nUser-Agent: cronus\r\nConnection: close\r\n"
+    );
+    if let Some(content_type) = content_type.filter(|_| body.is_some()) {
+        head.push_str(&format!("Content-Type: {content_type}\r\n"));
+    }
+    if let Some(body) = body {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).map_err(failed)?;
+    stream
+        .write_all(body.unwrap_or_default().as_bytes())
+        .map_err(failed)?;
+    let mut response = Vec::new();
+    match stream.take(MAX_RESPONSE_BYTES).read_to_end(&mut response) {
+        // Many HTTPS servers close the connection without notifying it first, ending the response all the same.
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof && !response.is_empty() => {}
+        result => _ = result.map_err(failed)?,
+    }
+    let Some(split) = response.windows(4).position(|window| window == b"\r\n\r\n") else {
+        return Ok(None);
+    };
+    let status = String::from_utf8_lossy(&response[..split])
+        .lines()
+        .next()
+        .and_then(|status_line| status_line.split_whitespace().nth(1))
+        .and_then(|status| status.parse().ok());
+    Ok(status.map(|status| (status, response[split + 4..].to_vec())))
+}
@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use uuid::Uuid;
+
+/// `IdempotencyLedger` is a structure that keeps the idempotency keys of the runs that succeeded, so a run whose key already
+/// succeeded for its job is not carried out again.
+///
+/// Keys are kept per job: two jobs rendering the same key do not skip each other's runs. The ledger is a file with one JSON
+/// `[job_id, key]` pair per line, appended to whenever a run succeeds. Lines appended by other services sharing the file are picked
+/// up before every claim, so a follower taking over after a failover does not run the ticks its leader already did. A line holding
+/// a bare key, written before keys were kept per job, still holds the key for every job.
+///
+/// # Fields
+///
+/// * `file` - An `Option<PathBuf>` that represents the path of the ledger file. It is `None` if the ledger is only kept in memory.
+/// * `read` - A `u64` that represents the number of bytes of the ledger file read so far.
+/// * `succeeded` - A `HashSet<(Uuid, String)>` that represents the jobs and keys of the runs that succeeded.
+/// * `unscoped` - A `HashSet<String>` that represents the keys of the runs that succeeded read from bare keys, held for every job.
+/// * `in_flight` - A `HashSet<(Uuid, String)>` that represents the jobs and keys of the runs in flight.
+#[derive(Debug, Default)]
+pub struct IdempotencyLedger {
+    file: Option<PathBuf>,
+    read: u64,
+    succeeded: HashSet<(Uuid, String)>,
+    unscoped: HashSet<String>,
+    in_flight: HashSet<(Uuid, String)>,
+}
+
+impl IdempotencyLedger {
+    /// Opens the ledger kept in a file, reading the keys it already holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - A `PathBuf` that represents the path of the ledger file, created on the first success if it does not exist.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<IdempotencyLedger>` - Returns the ledger, or an error if the file exists but cannot be read.
+    pub fn open(file: PathBuf) -> io::Result<Self> {
+        let mut ledger = Self {
+            file: Some(file),
+            ..Self::default()
+        };
+        ledger.refresh()?;
+        Ok(ledger)
+    }
+
+    /// Claims a key for a run of a job about to be carried out.
+    ///
+    /// # Arguments
+    ///
+    /// * `job` - A `Uuid` that represents the ID of the job of the run.
+    /// * `key` - A string that represents the idempotency key of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the run may be carried out, or `false` if a run of the same job with the same key already succeeded
+    ///   or is in flight.
+    pub fn claim(&mut self, job: Uuid, key: &str) -> bool {
+        // A ledger that cannot be read again still holds the keys read so far.
+        _ = self.refresh();
+        let claim = (job, key.to_string());
+        !self.unscoped.contains(key)
+            && !self.succeeded.contains(&claim)
+            && self.in_flight.insert(claim)
+    }
+
+    /// Releases a key claimed by a run of a job that is over, recording it for the job if the run succeeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `job` - A `Uuid` that represents the ID of the job of the run.
+    /// * `key` - A string that represents the idempotency key of the run.
+    /// * `succeeded` - A `bool` that represents whether the run succeeded.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<()>` - Returns `Ok(())` if the key was released, or an error if it could not be appended to the ledger file, in
+    ///   which case it is only kept in memory.
+    pub fn release(&mut self, job: Uuid, key: &str, succeeded: bool) -> io::Result<()> {
+        let claim = (job, key.to_string());
+        self.in_flight.remove(&claim);
+        if !succeeded || !self.succeeded.insert(claim) {
+            return Ok(());
+        }
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_string(&(job, key))?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Reads the keys appended to the ledger file since it was last read.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<()>` - Returns `Ok(())` if the new keys were read, or if there is no ledger file yet, or an error if the file cannot be read.
+    fn refresh(&mut self) -> io::Result<()> {
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        file.seek(SeekFrom::Start(self.read))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        // A line still being appended by another service is read once it is complete.
+        let complete = content.rfind('\n').map_or(0, |end| end + 1);
+        for line in content[..complete].lines() {
+            if let Ok(claim) = serde_json::from_str::<(Uuid, String)>(line) {
+                self.succeeded.insert(claim);
+            } else if let Ok(key) = serde_json::from_str::<String>(line) {
+                self.unscoped.insert(key);
+            }
+        }
+        self.read += complete as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cronus-{}-{name}", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn a_key_is_claimed_once_per_job() {
+        let (job, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut ledger = IdempotencyLedger::default();
+        assert!(ledger.claim(job, "etl-20260101"));
+        assert!(!ledger.claim(job, "etl-20260101"));
+        assert!(ledger.claim(other, "etl-20260101"));
+        ledger.release(other, "etl-20260101", true).unwrap();
+        assert!(!ledger.claim(other, "etl-20260101"));
+        assert!(!ledger.claim(job, "etl-20260101"));
+    }
+
+    #[test]
+    fn a_failed_run_releases_its_key() {
+        let job = Uuid::new_v4();
+        let mut ledger = IdempotencyLedger::default();
+        assert!(ledger.claim(job, "etl-20260101"));
+        ledger.release(job, "etl-20260101", false).unwrap();
+        assert!(ledger.claim(job, "etl-20260101"));
+        ledger.release(job, "etl-20260101", true).unwrap();
+        assert!(!ledger.claim(job, "etl-20260101"));
+    }
+
+    #[test]
+    fn succeeded_keys_are_shared_through_the_file() {
+        let file = temp_file("keys");
+        let (job, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut ledger = IdempotencyLedger::open(file.clone()).unwrap();
+        let mut follower = IdempotencyLedger::open(file.clone()).unwrap();
+        assert!(ledger.claim(job, "etl-20260101"));
+        ledger.release(job, "etl-20260101", true).unwrap();
+        assert!(!follower.claim(job, "etl-20260101"));
+        assert!(follower.claim(other, "etl-20260101"));
+        assert!(!IdempotencyLedger::open(file.clone())
+            .unwrap()
+            .claim(job, "etl-20260101"));
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn bare_keys_are_held_for_every_job() {
+        let file = temp_file("keys");
+        std::fs::write(&file, "\"etl-20260101\"\n").unwrap();
+        let mut ledger = IdempotencyLedger::open(file.clone()).unwrap();
+        assert!(!ledger.claim(Uuid::new_v4(), "etl-20260101"));
+        assert!(ledger.claim(Uuid::new_v4(), "etl-20260102"));
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -1,21 +1,308 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::num::{NonZeroU32, NonZeroU64};
+use std::os::fd::OwnedFd;
+use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::Rng;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use tokio::process::Child;
+use uuid::Uuid;
+
+use crate::calendar::{BusinessCalendar, HolidayPolicy};
+use crate::container;
+use crate::credentials::RunAs;
+use crate::dst::DstPolicy;
+use crate::format::FORMAT_VERSION;
+use crate::http_client;
+use crate::limits::ResourceLimits;
+#[cfg(feature = "lua")]
+use crate::lua;
+use crate::notify::NotifyEvent;
+use crate::output::{OutputPatterns, RunOutput};
+use crate::pipeline::{self, JobStep};
+use crate::sandbox::Sandbox;
+use crate::schedule;
+use crate::secret::{resolve_secrets, SecretSource};
+use crate::solar::SunSchedule;
+#[cfg(feature = "sql")]
+use crate::sql;
+#[cfg(feature = "ssh")]
+use crate::ssh;
+use crate::state;
+use crate::template;
+#[cfg(feature = "wasm")]
+use crate::wasm;
+use crate::workflow::{self, WorkflowStep};
+
+/// How often a running command checks whether its run has been killed.
+const KILL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long the processes of a killed command are given to exit after `SIGTERM`, before they are sent `SIGKILL`.
+pub const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// The error message of a run that has been killed.
+pub const RUN_KILLED: &str = "Run killed";
+
+/// `JobBusiness` is the business function of a job.
+///
+/// It takes the tick the job runs for, the kill switch of the run and the output the run writes its lines to, and returns `Ok(())` if
+/// the run succeeded or an error message describing why it failed. A business function aborts as soon as possible once its kill switch
+/// has been flipped.
+pub type JobBusiness =
+    Arc<dyn Fn(Tick, &KillSwitch, &RunOutput) -> Result<(), String> + Send + Sync>;
+
+/// `RunId` is a structure that represents the id of a run of a job, generated for every run, so overlapping runs of the same job can be
+/// told apart in the run history, the events, the output and the notifications of the service.
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
+#[serde(transparent)]
+pub struct RunId(Uuid);
+
+impl RunId {
+    /// Generates the id of a new run.
+    ///
+    /// # Returns
+    ///
+    /// * `RunId` - Returns a random id.
+    pub fn generate() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl fmt::Display for RunId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for RunId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s)
+            .map(Self)
+            .map_err(|_| format!("Invalid run id `{s}`"))
+    }
+}
+
+/// `Tick` is a structure that represents the nominal time a run of a job is for.
+///
+/// A run for a tick processes the window of time the tick closes, whether it runs on time, late or as a backfill, so idempotent jobs can
+/// process exactly the window they were scheduled for instead of the window ending now.
+///
+/// # Fields
+///
+/// * `scheduled_time` - A `DateTime<Utc>` that represents the time the run was scheduled for.
+/// * `window_start` - A `DateTime<Utc>` that represents the start of the window the run processes, e.g. the previous tick of a cron schedule.
+/// * `window_end` - A `DateTime<Utc>` that represents the end of the window the run processes, e.g. the tick of a cron schedule the run is for.
+/// * `run_id` - A `RunId` that represents the id of the run, unique to every run of the job.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Tick {
+    pub scheduled_time: DateTime<Utc>,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub run_id: RunId,
+}
+
+impl Tick {
+    /// Creates a new `Tick` whose window is empty, for a new run.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduled_time` - A `DateTime<Utc>` that represents the time the run is scheduled for.
+    ///
+    /// # Returns
+    ///
+    /// * `Tick` - Returns a tick whose window starts and ends at the scheduled time, with a new run id.
+    pub fn at(scheduled_time: DateTime<Utc>) -> Self {
+        Self {
+            scheduled_time,
+            window_start: scheduled_time,
+            window_end: scheduled_time,
+            run_id: RunId::generate(),
+        }
+    }
+
+    /// Lists the variables describing the tick, as passed to the environment of commands and the scope of Rhai scripts.
+    ///
+    /// # Returns
+    ///
+    /// * `[(&str, String); 4]` - Returns the `scheduled_time`, `window_start` and `window_end` variables, formatted as RFC 3339 in UTC,
+    ///   and the `run_id` variable.
+    pub fn variables(&self) -> [(&'static str, String); 4] {
+        let format = |time: DateTime<Utc>| time.to_rfc3339_opts(SecondsFormat::Secs, true);
+        [
+            ("scheduled_time", format(self.scheduled_time)),
+            ("window_start", format(self.window_start)),
+            ("window_end", format(self.window_end)),
+            ("run_id", self.run_id.to_string()),
+        ]
+    }
+}
+
+/// `JobIdentity` is a structure that represents the job a business function runs for, so its runs can tell which job they belong to.
+///
+/// # Fields
+///
+/// * `id` - A string that represents the id of the job, or the label of a hook, e.g. `start:mount`.
+/// * `name` - An `Option<String>` that represents the name of the job. It is `None` if the job has no name.
+/// * `secrets` - A `BTreeMap<String, SecretSource>` that represents the secrets given to the processes of the job as environment
+///   variables, by the name of their variable.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct JobIdentity {
+    pub id: String,
+    pub name: Option<String>,
+    pub secrets: BTreeMap<String, SecretSource>,
+}
+
+impl JobIdentity {
+    /// Resolves the secrets of the job for a run, masking their values in the output of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - A reference to the `RunOutput` of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(String, String)>, String>` - Returns the names and values of the secrets, or an error message describing the first
+    ///   secret that cannot be read, in which case the run fails.
+    pub fn resolve_secrets(&self, output: &RunOutput) -> Result<Vec<(String, String)>, String> {
+        let secrets = resolve_secrets(&self.secrets)?;
+        output.mask(secrets.iter().map(|(_, value)| value.clone()));
+        Ok(secrets)
+    }
+}
+
+/// `JobStateHandle` is a structure that represents the state of a job as seen by its Rhai scripts, the `state` variable.
+///
+/// It contains the id of the job whose state is read and written.
+#[derive(Debug, Clone)]
+struct JobStateHandle(String);
+
+/// `KillSwitch` is a structure that lets a run of a job be aborted while it is in flight.
+///
+/// Clones of a kill switch share its state, so the run holds one clone while the scheduler keeps another one to flip.
+#[derive(Debug, Default, Clone)]
+pub struct KillSwitch(Arc<AtomicBool>);
+
+impl KillSwitch {
+    /// Flips the kill switch, asking the run to abort.
+    pub fn kill(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Checks whether the kill switch has been flipped.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the run has been asked to abort.
+    pub fn is_killed(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Waits for the kill switch to be flipped, checking it every `KILL_POLL_INTERVAL`.
+    pub async fn killed(&self) {
+        while !self.is_killed() {
+            tokio::time::sleep(KILL_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Checks whether two kill switches belong to the same run.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - A reference to the other `KillSwitch`.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if both kill switches are clones of the same one.
+    pub fn is_same(&self, other: &KillSwitch) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// `CommandStdin` is an enumeration that represents what is written to the standard input of the process of a command job, so tools
+/// reading their input from it, e.g. `psql` or `mail`, can be scheduled without a shell wrapper. The process reads from `/dev/null`
+/// otherwise.
+///
+/// # Variants
+///
+/// * `File(PathBuf)` - Represents the content of a file, opened at every run, written `stdin_file` in the serialized format.
+/// * `Text(String)` - Represents a string kept with the job, written `stdin` in the serialized format.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CommandStdin {
+    File(PathBuf),
+    Text(String),
+}
 
 /// `Job` is an enumeration that represents the different types of jobs that can be scheduled.
 ///
 /// # Variants
 ///
-/// * `Command(PathBuf, Vec<String>)` - Represents a command job. It contains a `PathBuf` that represents the path of the command and a vector of strings that represent the arguments of the command.
+/// * `Command(PathBuf, Vec<String>, Option<CommandStdin>)` - Represents a command job. It contains a `PathBuf` that represents the path of the command, a vector of strings that represent the arguments of the command, which may contain time placeholders like `{{scheduled_time - 1d | %Y%m%d}}`, and an `Option<CommandStdin>` that represents the standard input of the command, `None` if it reads nothing.
 /// * `RhaiScript(String)` - Represents a Rhai script job. It contains a string that represents the Rhai script.
 /// * `RhaiScriptFile(PathBuf)` - Represents a Rhai script file job. It contains a `PathBuf` that represents the path of the Rhai script file.
-#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+/// * `Lua(String)` - Represents a Lua script job, when cronus is built with the `lua` feature. It contains a string that represents the Lua script.
+/// * `LuaFile(PathBuf)` - Represents a Lua script file job, when cronus is built with the `lua` feature. It contains a `PathBuf` that represents the path of the Lua script file.
+/// * `Wasm { module_path, func, args }` - Represents a WebAssembly job, when cronus is built with the `wasm` feature. It contains a `PathBuf` that represents the path of the module, a string that represents the name of the function it exports to be called, and a vector of strings that represent the arguments of the function, which may contain time placeholders like `{{scheduled_time | %s}}`.
+/// * `Container { image, cmd, env, volumes }` - Represents a container job, run through the Docker or Podman engine. It contains a string that represents the image of the container, a vector of strings that represent its command, which may contain time placeholders like `{{scheduled_time | %Y%m%d}}` and is the command of the image if empty, a `BTreeMap<String, String>` that represents its environment variables, and a vector of strings that represent the volumes mounted into it, e.g. `/srv/backups:/backups:ro`.
+/// * `Sql { url, statement }` - Represents an SQL job, run against a Postgres, MySQL or SQLite database when cronus is built with the `sql` feature. It contains a string that represents the URL of the database, and a string that represents the statements to execute, which may contain time placeholders like `{{scheduled_time - 30d | %Y-%m-%d}}`.
+/// * `Ssh { host, user, key_path, command }` - Represents a remote command job, run over SSH when cronus is built with the `ssh` feature. It contains a string that represents the remote host with an optional port, a string that represents the remote user, an `Option<PathBuf>` that represents the private key of the user, `None` to authenticate with the SSH agent, and a string that represents the command, which may contain time placeholders.
+/// * `Workflow(Vec<WorkflowStep>)` - Represents a workflow job. It contains a vector of `WorkflowStep` instances that represent the steps of the workflow.
+/// * `Pipeline(Vec<JobStep>)` - Represents a pipeline job, running its steps one after the other within a single run, like `cmd1 && cmd2` without a shell. It contains a vector of `JobStep` instances that represent the steps of the pipeline, in order.
+/// * `Unsupported` - Represents a job this version of cronus cannot run, read from a newer version. It contains the type of the job and the version of the format it was written in. Such a job is rejected rather than registered.
+///
+/// Jobs are serialized as versioned payloads tagged with their `type`, see the `format` module.
+#[derive(Debug, PartialEq, Clone)]
 pub enum Job {
-    Command(PathBuf, Vec<String>),
+    Command(PathBuf, Vec<String>, Option<CommandStdin>),
     RhaiScript(String),
     RhaiScriptFile(PathBuf),
+    #[cfg(feature = "lua")]
+    Lua(String),
+    #[cfg(feature = "lua")]
+    LuaFile(PathBuf),
+    #[cfg(feature = "wasm")]
+    Wasm {
+        module_path: PathBuf,
+        func: String,
+        args: Vec<String>,
+    },
+    Container {
+        image: String,
+        cmd: Vec<String>,
+        env: BTreeMap<String, String>,
+        volumes: Vec<String>,
+    },
+    #[cfg(feature = "sql")]
+    Sql {
+        url: String,
+        statement: String,
+    },
+    #[cfg(feature = "ssh")]
+    Ssh {
+        host: String,
+        user: String,
+        key_path: Option<PathBuf>,
+        command: String,
+    },
+    Workflow(Vec<WorkflowStep>),
+    Pipeline(Vec<JobStep>),
+    Unsupported {
+        kind: String,
+        version: u64,
+    },
 }
 
 impl Job {
@@ -25,12 +312,13 @@ impl Job {
     ///
     /// * `cmd_path` - A `PathBuf` that represents the path of the command.
     /// * `args` - A vector of strings that represent the arguments of the command.
+    /// * `stdin` - An `Option<CommandStdin>` that represents the standard input of the command. It is `None` if the command reads nothing.
     ///
     /// # Returns
     ///
     /// * `Self` - Returns a new `Command` variant of `Job`.
-    pub fn new_command(cmd_path: PathBuf, args: Vec<String>) -> Self {
-        Job::Command(cmd_path, args)
+    pub fn new_command(cmd_path: PathBuf, args: Vec<String>, stdin: Option<CommandStdin>) -> Self {
+        Job::Command(cmd_path, args, stdin)
     }
 
     /// Creates a new `RhaiScript` variant of `Job`.
@@ -59,6 +347,215 @@ impl Job {
         Job::RhaiScriptFile(file)
     }
 
+    /// Creates a new `Container` variant of `Job`.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - An instance of a type implementing `ToString` that represents the image of the container.
+    /// * `cmd` - A vector of strings that represent the command of the container.
+    /// * `env` - A `BTreeMap<String, String>` that represents the environment variables of the container.
+    /// * `volumes` - A vector of strings that represent the volumes mounted into the container.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns a new `Container` variant of `Job`.
+    pub fn new_container(
+        image: impl ToString,
+        cmd: Vec<String>,
+        env: BTreeMap<String, String>,
+        volumes: Vec<String>,
+    ) -> Self {
+        Job::Container {
+            image: image.to_string(),
+            cmd,
+            env,
+            volumes,
+        }
+    }
+
+    /// Creates a new `Sql` variant of `Job`.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - An instance of a type implementing `ToString` that represents the URL of the database.
+    /// * `statement` - An instance of a type implementing `ToString` that represents the statements to execute.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns a new `Sql` variant of `Job`.
+    #[cfg(feature = "sql")]
+    pub fn new_sql(url: impl ToString, statement: impl ToString) -> Self {
+        Job::Sql {
+            url: url.to_string(),
+            statement: statement.to_string(),
+        }
+    }
+
+    /// Creates a new `Ssh` variant of `Job`.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - An instance of a type implementing `ToString` that represents the remote host, with an optional port.
+    /// * `user` - An instance of a type implementing `ToString` that represents the remote user.
+    /// * `key_path` - An `Option<PathBuf>` that represents the private key of the user, or `None` to use the SSH agent.
+    /// * `command` - An instance of a type implementing `ToString` that represents the remote command.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns a new `Ssh` variant of `Job`.
+    #[cfg(feature = "ssh")]
+    pub fn new_ssh(
+        host: impl ToString,
+        user: impl ToString,
+        key_path: Option<PathBuf>,
+        command: impl ToString,
+    ) -> Self {
+        Job::Ssh {
+            host: host.to_string(),
+            user: user.to_string(),
+            key_path,
+            command: command.to_string(),
+        }
+    }
+
+    /// Creates a new `Lua` variant of `Job`.
+    ///
+    /// # Arguments
+    ///
+    /// * `script` - An instance of a type implementing `ToString` that represents the Lua script.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns a new `Lua` variant of `Job`.
+    #[cfg(feature = "lua")]
+    pub fn new_lua(script: impl ToString) -> Self {
+        Job::Lua(script.to_string())
+    }
+
+    /// Creates a new `LuaFile` variant of `Job`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - A `PathBuf` that represents the path of the Lua script file.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns a new `LuaFile` variant of `Job`.
+    #[cfg(feature = "lua")]
+    pub fn new_lua_file(file: PathBuf) -> Self {
+        Job::LuaFile(file)
+    }
+
+    /// Creates a new `Wasm` variant of `Job`.
+    ///
+    /// # Arguments
+    ///
+    /// * `module_path` - A `PathBuf` that represents the path of the WebAssembly module.
+    /// * `func` - An instance of a type implementing `ToString` that represents the name of the exported function to be called.
+    /// * `args` - A vector of strings that represent the arguments of the function.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns a new `Wasm` variant of `Job`.
+    #[cfg(feature = "wasm")]
+    pub fn new_wasm(module_path: PathBuf, func: impl ToString, args: Vec<String>) -> Self {
+        Job::Wasm {
+            module_path,
+            func: func.to_string(),
+            args,
+        }
+    }
+
+    /// Creates a new `Pipeline` variant of `Job`.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - A vector of `JobStep` instances that represent the steps of the pipeline, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns a new `Pipeline` variant of `Job`.
+    pub fn new_pipeline(steps: Vec<JobStep>) -> Self {
+        Job::Pipeline(steps)
+    }
+
+    /// Checks that a job can be run by this version of cronus, as can every step of a workflow or a pipeline job.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the job is supported, or an error message describing the first unsupported job.
+    pub fn check_supported(&self) -> Result<(), String> {
+        match self {
+            Job::Unsupported { kind, version } if *version > FORMAT_VERSION => Err(format!(
+                "Job of type `{kind}` is written in format version {version}, this version of cronus reads up to version {FORMAT_VERSION}"
+            )),
+            Job::Unsupported { kind, .. } => Err(format!(
+                "Unsupported job type `{kind}`, it may need a newer version of cronus"
+            )),
+            Job::Workflow(steps) => steps.iter().try_for_each(|step| {
+                step.job
+                    .check_supported()
+                    .map_err(|e| format!("Step `{}`: {e}", step.name))
+            }),
+            Job::Pipeline(steps) => steps.iter().try_for_each(|step| {
+                step.job
+                    .check_supported()
+                    .map_err(|e| format!("Step `{}`: {e}", step.name))
+            }),
+            Job::Command(..)
+            | Job::RhaiScript(_)
+            | Job::RhaiScriptFile(_)
+            | Job::Container { .. } => Ok(()),
+            #[cfg(feature = "lua")]
+            Job::Lua(_) | Job::LuaFile(_) => Ok(()),
+            #[cfg(feature = "wasm")]
+            Job::Wasm { .. } => Ok(()),
+            #[cfg(feature = "sql")]
+            Job::Sql { .. } => Ok(()),
+            #[cfg(feature = "ssh")]
+            Job::Ssh { .. } => Ok(()),
+        }
+    }
+
+    /// Checks that the templates of a job are valid.
+    ///
+    /// The arguments of a command job and of a WebAssembly job, the commands of container and SSH jobs, the values of the environment
+    /// variables of a container job, and the statements of an SQL job, are templates, see `template::render`. The steps of a workflow or a pipeline job are checked one by one.
+    /// An unsupported job is invalid, see `check_supported`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if every template can be rendered, or an error message describing the first invalid one.
+    pub fn validate_templates(&self) -> Result<(), String> {
+        match self {
+            Job::Command(_, args, _) => args.iter().try_for_each(|arg| template::validate(arg)),
+            Job::Container { cmd, env, .. } => cmd
+                .iter()
+                .chain(env.values())
+                .try_for_each(|arg| template::validate(arg)),
+            #[cfg(feature = "wasm")]
+            Job::Wasm { args, .. } => args.iter().try_for_each(|arg| template::validate(arg)),
+            #[cfg(feature = "sql")]
+            Job::Sql { statement, .. } => template::validate(statement),
+            #[cfg(feature = "ssh")]
+            Job::Ssh { command, .. } => template::validate(command),
+            Job::Workflow(steps) => steps.iter().try_for_each(|step| {
+                step.job
+                    .validate_templates()
+                    .map_err(|e| format!("Step `{}`: {e}", step.name))
+            }),
+            Job::Pipeline(steps) => steps.iter().try_for_each(|step| {
+                step.job
+                    .validate_templates()
+                    .map_err(|e| format!("Step `{}`: {e}", step.name))
+            }),
+            Job::RhaiScript(_) | Job::RhaiScriptFile(_) => Ok(()),
+            #[cfg(feature = "lua")]
+            Job::Lua(_) | Job::LuaFile(_) => Ok(()),
+            Job::Unsupported { .. } => self.check_supported(),
+        }
+    }
+
     /// Converts a `Job` instance into a business function.
     ///
     /// This method matches the `Job` variant and calls the corresponding method to convert it into a business function.
@@ -66,76 +563,1308 @@ impl Job {
     /// # Arguments
     ///
     /// * `self` - The instance of `Job` that needs to be converted.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for, passed on to the steps of a workflow or a pipeline.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the processes spawned by the job or the scripts it runs, and by the steps of a workflow or a pipeline.
+    /// * `run_as` - A `RunAs` that represents the user and group the processes spawned by the job, and by the steps of a workflow or a pipeline, run as.
     ///
     /// # Returns
     ///
-    /// * `Arc<dyn Fn(DateTime<Utc>) + Send + Sync>` - Returns an `Arc` containing a dynamic function that takes a `DateTime<Utc>` as an argument and implements `Send` and `Sync`.
-    pub fn to_business(self) -> Arc<dyn Fn(DateTime<Utc>) + Send + Sync> {
+    /// * `JobBusiness` - Returns the business function of the job.
+    pub fn to_business(
+        self,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
         match self {
-            Job::Command(cmd_path, args) => Job::command_to_business(cmd_path, args),
-            Job::RhaiScript(script) => Job::rhai_script_to_business(script),
-            Job::RhaiScriptFile(file) => Job::rhai_script_file_to_business(file),
+            Job::Command(cmd_path, args, stdin) => {
+                Job::command_to_business(cmd_path, args, stdin, identity, limits, run_as)
+            }
+            Job::RhaiScript(script) => {
+                Job::rhai_script_to_business(script, identity, limits, run_as)
+            }
+            Job::RhaiScriptFile(file) => {
+                Job::rhai_script_file_to_business(file, identity, limits, run_as)
+            }
+            #[cfg(feature = "lua")]
+            Job::Lua(script) => Job::lua_to_business(script, identity, limits, run_as),
+            #[cfg(feature = "lua")]
+            Job::LuaFile(file) => Job::lua_file_to_business(file, identity, limits, run_as),
+            #[cfg(feature = "wasm")]
+            Job::Wasm {
+                module_path,
+                func,
+                args,
+            } => Job::wasm_to_business(module_path, func, args, identity, limits),
+            Job::Container {
+                image,
+                cmd,
+                env,
+                volumes,
+            } => Job::container_to_business(image, cmd, env, volumes, identity, limits, run_as),
+            #[cfg(feature = "sql")]
+            Job::Sql { url, statement } => Job::sql_to_business(url, statement, identity),
+            #[cfg(feature = "ssh")]
+            Job::Ssh {
+                host,
+                user,
+                key_path,
+                command,
+            } => Job::ssh_to_business(host, user, key_path, command, identity),
+            Job::Workflow(steps) => Job::workflow_to_business(steps, identity, limits, run_as),
+            Job::Pipeline(steps) => Job::pipeline_to_business(steps, identity, limits, run_as),
+            Job::Unsupported { .. } => {
+                let error = self.check_supported().unwrap_err();
+                Arc::new(move |_, _, _| Err(error.clone()))
+            }
         }
     }
 
     /// Converts a `Command` variant of `Job` into a business function.
     ///
-    /// This function creates a new process for the command and its arguments. The process is then spawned asynchronously.
+    /// This function creates a new process for the command and its arguments, rendering the placeholders of the arguments for the tick of the run.
+    /// The tick is also passed to the process as the `CRONUS_SCHEDULED_TIME`, `CRONUS_WINDOW_START`, `CRONUS_WINDOW_END` and `CRONUS_RUN_ID` environment variables,
+    /// and the job as the `CRONUS_JOB_ID` and, if the job has a name, `CRONUS_JOB_NAME` environment variables, along with the secrets of
+    /// the job, read when the run starts and masked in its output and its error.
+    /// The process runs within the resource limits of the job, as the user and group of the job, and is placed in a cgroup of its own when the job has a CPU quota.
+    /// It leads a process group of its own, so killing the run terminates the processes it spawned as well, see `terminate`.
+    /// The process is then spawned with `tokio::process` on a runtime of the run, and its exit is awaited, so the business function
+    /// returns once the command has exited and has been reaped, with its exit code set on the output of the run.
+    /// Its standard input is the stdin of the job, written from a thread of its own so a command printing before it has read its whole
+    /// input cannot dead-lock with the run, and `/dev/null` if the job has none.
+    /// Its standard output and standard error are captured into the output of the run, line by line.
+    /// While waiting, the process is killed as soon as the kill switch of the run is flipped.
     ///
     /// # Arguments
     ///
     /// * `cmd_path` - A `PathBuf` that represents the path of the command.
-    /// * `args` - A vector of strings that represent the arguments of the command.
+    /// * `args` - A vector of strings that represent the argument templates of the command.
+    /// * `stdin` - An `Option<CommandStdin>` that represents the standard input of the command. It is `None` if the command reads nothing.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the process.
+    /// * `run_as` - A `RunAs` that represents the user and group the process runs as.
     ///
     /// # Returns
     ///
-    /// * `Arc<dyn Fn(DateTime<Utc>) + Send + Sync>` - Returns an `Arc` containing a dynamic function that takes a `DateTime<Utc>` as an argument and implements `Send` and `Sync`.
-    fn command_to_business(
+    /// * `JobBusiness` - Returns the business function of the job.
+    pub(crate) fn command_to_business(
         cmd_path: PathBuf,
         args: Vec<String>,
-    ) -> Arc<dyn Fn(DateTime<Utc>) + Send + Sync> {
-        Arc::new(move |_| {
+        stdin: Option<CommandStdin>,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            let input = match &stdin {
+                Some(CommandStdin::File(file)) => {
+                    Stdio::from(File::open(file).map_err(|e| {
+                        format!("Cannot read the stdin file {}: {e}", file.display())
+                    })?)
+                }
+                Some(CommandStdin::Text(_)) => Stdio::piped(),
+                None => Stdio::null(),
+            };
             let mut cmd = std::process::Command::new(cmd_path.clone());
             for arg in &args {
-                cmd.arg(arg);
+                cmd.arg(template::render(arg, tick, &identity)?);
+            }
+            for (name, value) in tick.variables() {
+                cmd.env(format!("CRONUS_{}", name.to_uppercase()), value);
             }
-            _ = cmd.spawn();
+            cmd.env("CRONUS_JOB_ID", &identity.id);
+            if let Some(name) = &identity.name {
+                cmd.env("CRONUS_JOB_NAME", name);
+            }
+            cmd.envs(identity.resolve_secrets(output)?);
+            cmd.process_group(0)
+                .stdin(input)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+            limits.apply(&mut cmd);
+            run_as
+                .apply(&mut cmd)
+                .map_err(|e| format!("Command failed to switch user: {e}"))?;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| format!("Command failed to start: {e}"))?;
+            // The child is registered with the runtime it is spawned in, which reaps it once it has exited.
+            let _runtime = runtime.enter();
+            let mut child = tokio::process::Command::from(cmd)
+                .spawn()
+                .map_err(|e| format!("Command failed to start: {e}"))?;
+            let pid = child.id().ok_or("Command failed to start")?;
+            let _cgroup = match limits.confine(pid) {
+                Ok(cgroup) => cgroup,
+                Err(e) => {
+                    runtime.block_on(Job::terminate(&mut child, pid));
+                    return Err(format!("Command failed to get its CPU quota: {e}"));
+                }
+            };
+            let pipe = |pipe: Option<OwnedFd>| pipe.map(File::from);
+            if let (Some(mut pipe), Some(CommandStdin::Text(text))) = (
+                pipe(
+                    child
+                        .stdin
+                        .take()
+                        .and_then(|stdin| stdin.into_owned_fd().ok()),
+                ),
+                &stdin,
+            ) {
+                let text = text.clone();
+                thread::spawn(move || _ = pipe.write_all(text.as_bytes()));
+            }
+            let readers = [
+                pipe(
+                    child
+                        .stdout
+                        .take()
+                        .and_then(|stdout| stdout.into_owned_fd().ok()),
+                )
+                .map(|stdout| output.capture(stdout)),
+                pipe(
+                    child
+                        .stderr
+                        .take()
+                        .and_then(|stderr| stderr.into_owned_fd().ok()),
+                )
+                .map(|stderr| output.capture(stderr)),
+            ];
+            let result = runtime.block_on(async {
+                tokio::select! {
+                    status = child.wait() => match status {
+                        Ok(status) => {
+                            if let Some(code) = status.code() {
+                                output.set_exit_code(code);
+                            }
+                            if status.success() {
+                                Ok(())
+                            } else {
+                                Err(format!("Command failed with {status}"))
+                            }
+                        }
+                        Err(e) => Err(format!("Command failed: {e}")),
+                    },
+                    _ = kill_switch.killed() => {
+                        Job::terminate(&mut child, pid).await;
+                        Err(RUN_KILLED.to_string())
+                    }
+                }
+            });
+            RunOutput::drain(readers.into_iter().flatten().collect());
+            result.map_err(|e| output.redact(&e))
         })
     }
 
+    /// Terminates a command along with every process of its process group, then reaps it.
+    ///
+    /// The process group is sent `SIGTERM`, then `SIGKILL` once the command has exited or `KILL_GRACE_PERIOD` has passed, so processes
+    /// spawned by the command that outlive it or ignore `SIGTERM` are not left behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `child` - A mutable reference to the `Child` of the command, which leads its process group.
+    /// * `pid` - A `u32` that represents the process id of the command, read before it could be reaped.
+    async fn terminate(child: &mut Child, pid: u32) {
+        let group = -(pid as libc::pid_t);
+        // SAFETY: `kill` has no memory safety requirements, and the group is the one of a child that has not been reaped yet.
+        unsafe { libc::kill(group, libc::SIGTERM) };
+        _ = tokio::time::timeout(KILL_GRACE_PERIOD, child.wait()).await;
+        // SAFETY: the group id cannot be reused while processes of the group are left, and signalling an empty group is a no-op.
+        unsafe { libc::kill(group, libc::SIGKILL) };
+        _ = child.wait().await;
+    }
+
     /// Converts a `RhaiScript` variant of `Job` into a business function.
     ///
-    /// This function runs the Rhai script asynchronously.
+    /// This function runs the Rhai script asynchronously, with the tick of the run and the job in scope. The value the script returns
+    /// becomes the result of the run, see `rhai_result`, and the state of the job is written once the script is over, even if it failed.
     ///
     /// # Arguments
     ///
     /// * `script` - A string that represents the Rhai script.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the script, and to the processes it runs.
+    /// * `run_as` - A `RunAs` that represents the user and group the processes run by the script run as.
     ///
     /// # Returns
     ///
-    /// * `Arc<dyn Fn(DateTime<Utc>) + Send + Sync>` - Returns an `Arc` containing a dynamic function that takes a `DateTime<Utc>` as an argument and implements `Send` and `Sync`.
-    fn rhai_script_to_business(script: String) -> Arc<dyn Fn(DateTime<Utc>) + Send + Sync> {
-        Arc::new(move |_| {
-            _ = rhai::run(&script);
+    /// * `JobBusiness` - Returns the business function of the job.
+    fn rhai_script_to_business(
+        script: String,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            let result = Job::rhai_engine(tick, &identity, kill_switch, output, limits, &run_as)
+                .eval_with_scope(&mut Job::rhai_scope(tick, &identity), &script)
+                .map(|value| Job::rhai_result(value, output))
+                .map_err(|e| Job::rhai_error(&e, kill_switch));
+            state::persist().map_err(|e| format!("Cannot write the state of the job: {e}"))?;
+            result
         })
     }
 
     /// Converts a `RhaiScriptFile` variant of `Job` into a business function.
     ///
-    /// This function runs the Rhai script file asynchronously.
+    /// This function runs the Rhai script file asynchronously, with the tick of the run and the job in scope. The value the script returns
+    /// becomes the result of the run, see `rhai_result`, and the state of the job is written once the script is over, even if it failed.
     ///
     /// # Arguments
     ///
     /// * `file` - A `PathBuf` that represents the path of the Rhai script file.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the script, and to the processes it runs.
+    /// * `run_as` - A `RunAs` that represents the user and group the processes run by the script run as.
+    ///
+    /// # Returns
+    ///
+    /// * `JobBusiness` - Returns the business function of the job.
+    fn rhai_script_file_to_business(
+        file: PathBuf,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            let result = Job::rhai_engine(tick, &identity, kill_switch, output, limits, &run_as)
+                .eval_file_with_scope(&mut Job::rhai_scope(tick, &identity), file.clone())
+                .map(|value| Job::rhai_result(value, output))
+                .map_err(|e| Job::rhai_error(&e, kill_switch));
+            state::persist().map_err(|e| format!("Cannot write the state of the job: {e}"))?;
+            result
+        })
+    }
+
+    /// Converts a `Container` variant of `Job` into a business function.
+    ///
+    /// This function runs the container through the Docker or Podman engine and waits for it to exit, see `container::run`.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - A string that represents the image of the container.
+    /// * `cmd` - A vector of strings that represent the command templates of the container.
+    /// * `env` - A `BTreeMap<String, String>` that represents the environment variables of the container.
+    /// * `volumes` - A vector of strings that represent the volumes mounted into the container.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
+    /// * `limits` - A `ResourceLimits` that represents the memory and CPU limits applied to the container.
+    /// * `run_as` - A `RunAs` that represents the user and group the container runs as.
+    ///
+    /// # Returns
+    ///
+    /// * `JobBusiness` - Returns the business function of the job.
+    #[allow(clippy::too_many_arguments)]
+    fn container_to_business(
+        image: String,
+        cmd: Vec<String>,
+        env: BTreeMap<String, String>,
+        volumes: Vec<String>,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            container::run(
+                &image,
+                &cmd,
+                &env,
+                &volumes,
+                tick,
+                &identity,
+                kill_switch,
+                output,
+                limits,
+                &run_as,
+            )
+            .map_err(|e| output.redact(&e))
+        })
+    }
+
+    /// Converts a `Sql` variant of `Job` into a business function.
+    ///
+    /// This function executes the statements against the database, see `sql::run`. The limits and the user of the job do not apply to
+    /// the statements, which run with the privileges of the database user of the URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - A string that represents the URL of the database.
+    /// * `statement` - A string that represents the template of the statements.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
     ///
     /// # Returns
     ///
-    /// * `Arc<dyn Fn(DateTime<Utc>) + Send + Sync>` - Returns an `Arc` containing a dynamic function that takes a `DateTime<Utc>` as an argument and implements `Send` and `Sync`.
-    fn rhai_script_file_to_business(file: PathBuf) -> Arc<dyn Fn(DateTime<Utc>) + Send + Sync> {
-        Arc::new(move |_| {
-            _ = rhai::run_file(file.clone());
+    /// * `JobBusiness` - Returns the business function of the job.
+    #[cfg(feature = "sql")]
+    fn sql_to_business(url: String, statement: String, identity: JobIdentity) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            sql::run(&url, &statement, tick, &identity, kill_switch, output)
+        })
+    }
+
+    /// Converts a `Ssh` variant of `Job` into a business function.
+    ///
+    /// This function runs the command on the remote host over SSH and waits for it to exit, see `ssh::run`. The limits and the user of
+    /// the job do not apply to remote commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - A string that represents the remote host, with an optional port.
+    /// * `user` - A string that represents the remote user.
+    /// * `key_path` - An `Option<PathBuf>` that represents the private key of the user. It is `None` if the SSH agent is used.
+    /// * `command` - A string that represents the command template.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
+    ///
+    /// # Returns
+    ///
+    /// * `JobBusiness` - Returns the business function of the job.
+    #[cfg(feature = "ssh")]
+    fn ssh_to_business(
+        host: String,
+        user: String,
+        key_path: Option<PathBuf>,
+        command: String,
+        identity: JobIdentity,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            ssh::run(
+                &host,
+                &user,
+                key_path.as_deref(),
+                &command,
+                tick,
+                &identity,
+                kill_switch,
+                output,
+            )
+        })
+    }
+
+    /// Converts a `Lua` variant of `Job` into a business function.
+    ///
+    /// This function runs the Lua script, with the tick of the run and the job as globals, see `lua::run`, and the state of the job is
+    /// written once the script is over, even if it failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `script` - A string that represents the Lua script.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the script, and to the processes it runs.
+    /// * `run_as` - A `RunAs` that represents the user and group the processes run by the script run as.
+    ///
+    /// # Returns
+    ///
+    /// * `JobBusiness` - Returns the business function of the job.
+    #[cfg(feature = "lua")]
+    fn lua_to_business(
+        script: String,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            let result = lua::run(
+                &script,
+                "=script",
+                tick,
+                &identity,
+                kill_switch,
+                output,
+                limits,
+                &run_as,
+            );
+            state::persist().map_err(|e| format!("Cannot write the state of the job: {e}"))?;
+            result
+        })
+    }
+
+    /// Converts a `LuaFile` variant of `Job` into a business function.
+    ///
+    /// This function reads the Lua script file on every run, then runs it like a `Lua` job, see `lua::run`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - A `PathBuf` that represents the path of the Lua script file.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the script, and to the processes it runs.
+    /// * `run_as` - A `RunAs` that represents the user and group the processes run by the script run as.
+    ///
+    /// # Returns
+    ///
+    /// * `JobBusiness` - Returns the business function of the job.
+    #[cfg(feature = "lua")]
+    fn lua_file_to_business(
+        file: PathBuf,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            let script = std::fs::read_to_string(&file)
+                .map_err(|e| format!("Cannot read {}: {e}", file.display()))?;
+            let chunk_name = format!("@{}", file.display());
+            let result = lua::run(
+                &script,
+                &chunk_name,
+                tick,
+                &identity,
+                kill_switch,
+                output,
+                limits,
+                &run_as,
+            );
+            state::persist().map_err(|e| format!("Cannot write the state of the job: {e}"))?;
+            result
+        })
+    }
+
+    /// Converts a `Wasm` variant of `Job` into a business function.
+    ///
+    /// This function calls the exported function of the WebAssembly module with its arguments rendered for the tick of the run, see
+    /// `wasm::run`.
+    ///
+    /// # Arguments
+    ///
+    /// * `module_path` - A `PathBuf` that represents the path of the WebAssembly module.
+    /// * `func` - A string that represents the name of the exported function.
+    /// * `args` - A vector of strings that represent the argument templates of the function.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the module.
+    ///
+    /// # Returns
+    ///
+    /// * `JobBusiness` - Returns the business function of the job.
+    #[cfg(feature = "wasm")]
+    fn wasm_to_business(
+        module_path: PathBuf,
+        func: String,
+        args: Vec<String>,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            wasm::run(
+                &module_path,
+                &func,
+                &args,
+                tick,
+                &identity,
+                kill_switch,
+                output,
+                limits,
+            )
         })
     }
+
+    /// Creates the scope a Rhai script runs in.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick` - A `Tick` that represents the tick of the run.
+    /// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+    ///
+    /// # Returns
+    ///
+    /// * `rhai::Scope` - Returns a scope holding the `scheduled_time`, `window_start` and `window_end` constants, and the `started_time`
+    ///   constant holding the time the run actually started, formatted as RFC 3339 in UTC, along with the `run_id`, `job_id` and `job_name`
+    ///   constants, `job_name` being `()` if the job has no name, and the `state` variable holding the state of the job.
+    fn rhai_scope(tick: Tick, identity: &JobIdentity) -> rhai::Scope<'static> {
+        let mut scope = rhai::Scope::new();
+        for (name, value) in tick.variables() {
+            scope.push_constant(name, value);
+        }
+        scope.push_constant(
+            "started_time",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        );
+        scope.push_constant("job_id", identity.id.clone());
+        scope.push_constant(
+            "job_name",
+            identity
+                .name
+                .clone()
+                .map_or(rhai::Dynamic::UNIT, rhai::Dynamic::from),
+        );
+        scope.push("state", JobStateHandle(identity.id.clone()));
+        scope
+    }
+
+    /// Creates the Rhai engine a script runs on.
+    ///
+    /// The engine terminates the script at its next operation once the kill switch of the run is flipped, e.g. when the run times out,
+    /// and aborts it once it has carried out its maximum number of operations. The engine is confined to the sandbox of the job, and
+    /// given the helpers the sandbox exposes, see `register_rhai_helpers`. What the script prints with `print` and `debug` is written to
+    /// the output of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick` - A `Tick` that represents the tick of the run.
+    /// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+    /// * `kill_switch` - A reference to the `KillSwitch` of the run.
+    /// * `output` - A reference to the `RunOutput` of the run.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the script, and to the processes it runs.
+    /// * `run_as` - A reference to the `RunAs` that represents the user and group the processes run by the script run as.
+    ///
+    /// # Returns
+    ///
+    /// * `rhai::Engine` - Returns the engine.
+    fn rhai_engine(
+        tick: Tick,
+        identity: &JobIdentity,
+        kill_switch: &KillSwitch,
+        output: &RunOutput,
+        limits: ResourceLimits,
+        run_as: &RunAs,
+    ) -> rhai::Engine {
+        let mut engine = rhai::Engine::new();
+        limits.sandbox.confine(&mut engine);
+        if let Some(max_operations) = limits.max_operations {
+            engine.set_max_operations(max_operations.get());
+        }
+        Job::register_rhai_helpers(
+            &mut engine,
+            tick,
+            identity,
+            kill_switch,
+            output,
+            limits,
+            run_as,
+        );
+        let kill_switch = kill_switch.clone();
+        engine.on_progress(move |_| kill_switch.is_killed().then(|| RUN_KILLED.into()));
+        let print_output = output.clone();
+        engine.on_print(move |text| print_output.push(text.to_string()));
+        let debug_output = output.clone();
+        engine.on_debug(move |text, _, _| debug_output.push(text.to_string()));
+        engine
+    }
+
+    /// Registers the helpers the sandbox of a Rhai script exposes on its engine.
+    ///
+    /// In every sandbox, a script can call `state.get(key)`, which returns a value of the state of the job or `()` if it has none,
+    /// `state.set(key, value)` and `state.remove(key)`, which returns the value removed. Outside the `strict` sandbox, a script can call `env(name)`, which returns the value of an environment variable of the service
+    /// or `()` if it is not set, and `http_get(url)` and `http_post(url, body)`, which return the body of the response and fail unless
+    /// its status is 2xx. A script of the `full` sandbox can also call `read_file(path)`, `write_file(path, text)`,
+    /// `append_file(path, text)` and `file_exists(path)`, and `exec(program, args)`, which runs a process like a command job, within the
+    /// limits and as the user of the job, and returns its output, failing if the process does.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - A mutable reference to the `rhai::Engine` of the script.
+    /// * `tick` - A `Tick` that represents the tick of the run.
+    /// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+    /// * `kill_switch` - A reference to the `KillSwitch` of the run.
+    /// * `output` - A reference to the `RunOutput` of the run, which the output of the processes is written to as well.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the script, and to the processes it runs.
+    /// * `run_as` - A reference to the `RunAs` that represents the user and group the processes run by the script run as.
+    fn register_rhai_helpers(
+        engine: &mut rhai::Engine,
+        tick: Tick,
+        identity: &JobIdentity,
+        kill_switch: &KillSwitch,
+        output: &RunOutput,
+        limits: ResourceLimits,
+        run_as: &RunAs,
+    ) {
+        type RhaiResult<T> = Result<T, Box<rhai::EvalAltResult>>;
+        engine
+            .register_type_with_name::<JobStateHandle>("JobState")
+            .register_fn("get", |handle: &mut JobStateHandle, key: &str| {
+                state::get(&handle.0, key).map_or(rhai::Dynamic::UNIT, Job::json_to_rhai)
+            })
+            .register_fn(
+                "set",
+                |handle: &mut JobStateHandle, key: &str, value: rhai::Dynamic| {
+                    state::set(&handle.0, key, Job::rhai_to_json(value));
+                },
+            )
+            .register_fn("remove", |handle: &mut JobStateHandle, key: &str| {
+                state::remove(&handle.0, key).map_or(rhai::Dynamic::UNIT, Job::json_to_rhai)
+            });
+        if limits.sandbox.exposes_environment() {
+            engine.register_fn("env", |name: &str| {
+                std::env::var(name).map_or(rhai::Dynamic::UNIT, rhai::Dynamic::from)
+            });
+            engine.register_fn("http_get", |url: &str| -> RhaiResult<String> {
+                Ok(http_client::request("GET", url, None)?)
+            });
+            engine.register_fn("http_post", |url: &str, body: &str| -> RhaiResult<String> {
+                Ok(http_client::request("POST", url, Some(body))?)
+            });
+        }
+        if !limits.sandbox.exposes_host() {
+            return;
+        }
+        engine.register_fn("read_file", |path: &str| -> RhaiResult<String> {
+            std::fs::read_to_string(path).map_err(|e| format!("Cannot read {path}: {e}").into())
+        });
+        engine.register_fn("write_file", |path: &str, text: &str| -> RhaiResult<()> {
+            std::fs::write(path, text).map_err(|e| format!("Cannot write {path}: {e}").into())
+        });
+        engine.register_fn("append_file", |path: &str, text: &str| -> RhaiResult<()> {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+                .map_err(|e| format!("Cannot write {path}: {e}").into())
+        });
+        engine.register_fn("file_exists", |path: &str| {
+            std::path::Path::new(path).exists()
+        });
+        let identity = identity.clone();
+        let kill_switch = kill_switch.clone();
+        let output = output.clone();
+        let run_as = run_as.clone();
+        engine.register_fn(
+            "exec",
+            move |program: &str, args: rhai::Array| -> RhaiResult<String> {
+                let args = args.into_iter().map(|arg| arg.to_string()).collect();
+                let business = Job::command_to_business(
+                    PathBuf::from(program),
+                    args,
+                    None,
+                    identity.clone(),
+                    limits,
+                    run_as.clone(),
+                );
+                let captured = RunOutput::default();
+                let result = business(tick, &kill_switch, &captured);
+                let lines = captured.read_from(0).lines;
+                for line in &lines {
+                    output.push(line.clone());
+                }
+                result?;
+                Ok(lines.join("\n"))
+            },
+        );
+    }
+
+    /// Sets the value a Rhai script returned as the result of its run, unless it returned `()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - A `rhai::Dynamic` that represents the value returned.
+    /// * `output` - A reference to the `RunOutput` of the run.
+    fn rhai_result(value: rhai::Dynamic, output: &RunOutput) {
+        if !value.is_unit() {
+            output.set_result(Job::rhai_to_json(value));
+        }
+    }
+
+    /// Converts a Rhai value into JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - A `rhai::Dynamic` that represents the value.
+    ///
+    /// # Returns
+    ///
+    /// * `serde_json::Value` - Returns the value as JSON: arrays and object maps are converted item by item, and values without a JSON
+    ///   counterpart, e.g. timestamps, are converted to their string representation.
+    fn rhai_to_json(value: rhai::Dynamic) -> serde_json::Value {
+        if value.is_unit() {
+            serde_json::Value::Null
+        } else if let Some(flag) = value.clone().try_cast::<bool>() {
+            flag.into()
+        } else if let Some(number) = value.clone().try_cast::<rhai::INT>() {
+            number.into()
+        } else if let Some(number) = value.clone().try_cast::<rhai::FLOAT>() {
+            number.into()
+        } else if let Some(array) = value.clone().try_cast::<rhai::Array>() {
+            array.into_iter().map(Job::rhai_to_json).collect()
+        } else if let Some(map) = value.clone().try_cast::<rhai::Map>() {
+            map.into_iter()
+                .map(|(key, value)| (key.to_string(), Job::rhai_to_json(value)))
+                .collect::<serde_json::Map<_, _>>()
+                .into()
+        } else {
+            value.to_string().into()
+        }
+    }
+
+    /// Converts JSON into a Rhai value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - A `serde_json::Value` that represents the JSON value.
+    ///
+    /// # Returns
+    ///
+    /// * `rhai::Dynamic` - Returns the value for Rhai: `null` is converted to `()`, integral numbers to integers, other numbers to
+    ///   floats, arrays and objects item by item.
+    fn json_to_rhai(value: serde_json::Value) -> rhai::Dynamic {
+        match value {
+            serde_json::Value::Null => rhai::Dynamic::UNIT,
+            serde_json::Value::Bool(flag) => flag.into(),
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(number) => number.into(),
+                None => number.as_f64().unwrap_or_default().into(),
+            },
+            serde_json::Value::String(text) => text.into(),
+            serde_json::Value::Array(array) => array
+                .into_iter()
+                .map(Job::json_to_rhai)
+                .collect::<rhai::Array>()
+                .into(),
+            serde_json::Value::Object(map) => map
+                .into_iter()
+                .map(|(key, value)| (key.into(), Job::json_to_rhai(value)))
+                .collect::<rhai::Map>()
+                .into(),
+        }
+    }
+
+    /// Describes why a Rhai script failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `e` - A reference to the error the script failed with.
+    /// * `kill_switch` - A reference to the `KillSwitch` of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns the error message of the run.
+    fn rhai_error(e: &rhai::EvalAltResult, kill_switch: &KillSwitch) -> String {
+        match e {
+            rhai::EvalAltResult::ErrorTerminated(..) if kill_switch.is_killed() => {
+                RUN_KILLED.to_string()
+            }
+            rhai::EvalAltResult::ErrorTooManyOperations(position) if position.is_none() => {
+                "Script exceeded its maximum number of operations".to_string()
+            }
+            rhai::EvalAltResult::ErrorTooManyOperations(position) => {
+                format!("Script exceeded its maximum number of operations ({position})")
+            }
+            _ => e.to_string(),
+        }
+    }
+
+    /// Converts a `Workflow` variant of `Job` into a business function.
+    ///
+    /// This function runs the steps of the workflow with the workflow engine, and fails if any of the steps did not succeed.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - A vector of `WorkflowStep` instances that represent the steps of the workflow.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for, passed on to the steps.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the processes spawned by the steps.
+    /// * `run_as` - A `RunAs` that represents the user and group the processes spawned by the steps run as.
+    ///
+    /// # Returns
+    ///
+    /// * `JobBusiness` - Returns the business function of the job.
+    fn workflow_to_business(
+        steps: Vec<WorkflowStep>,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            workflow::validate_steps(&steps)?;
+            workflow::summarize(&workflow::run_steps(
+                &steps,
+                tick,
+                &identity,
+                limits,
+                &run_as,
+                kill_switch,
+                output,
+            ))
+        })
+    }
+
+    /// Converts a `Pipeline` variant of `Job` into a business function.
+    ///
+    /// This function runs the steps of the pipeline one after the other, records their outcomes as the result of the run, and fails if
+    /// any of the steps did not succeed.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - A vector of `JobStep` instances that represent the steps of the pipeline.
+    /// * `identity` - A `JobIdentity` that represents the job the runs are for, passed on to the steps.
+    /// * `limits` - A `ResourceLimits` that represents the limits applied to the processes spawned by the steps.
+    /// * `run_as` - A `RunAs` that represents the user and group the processes spawned by the steps run as.
+    ///
+    /// # Returns
+    ///
+    /// * `JobBusiness` - Returns the business function of the job.
+    fn pipeline_to_business(
+        steps: Vec<JobStep>,
+        identity: JobIdentity,
+        limits: ResourceLimits,
+        run_as: RunAs,
+    ) -> JobBusiness {
+        Arc::new(move |tick, kill_switch, output| {
+            pipeline::validate_steps(&steps)?;
+            let outcomes = pipeline::run_steps(
+                &steps,
+                tick,
+                &identity,
+                limits,
+                &run_as,
+                kill_switch,
+                output,
+            );
+            if let Ok(result) = serde_json::to_value(&outcomes) {
+                output.set_result(result);
+            }
+            workflow::summarize(&outcomes)
+        })
+    }
+}
+
+/// `Trigger` is an enumeration that represents what makes a job run.
+///
+/// # Variants
+///
+/// * `Cron(String)` - Represents a job running on a cron schedule. It contains a string that represents the cron expression, which may
+///   be a macro like `@daily`, see `schedule::translate`, or `@reboot` for a job running once when the service starts.
+/// * `After(String)` - Represents a job running whenever another job finishes successfully. It contains a string that represents the id or the name of the other job.
+/// * `Event(String)` - Represents a job running whenever an event is emitted on a topic with `EmitEvent`. It contains a string that represents the topic, e.g. `deploy.finished`.
+/// * `Rrule(String)` - Represents a job running on an RFC 5545 recurrence, for schedules cron cannot express. It contains a string that
+///   represents the `DTSTART`, `RRULE`, `RDATE` and `EXDATE` lines of the recurrence, see `recurrence::parse_rrule`.
+/// * `Sun(SunSchedule)` - Represents a job running every day at a time given by the position of the sun at a place, e.g. 30 minutes
+///   after sunset. It contains a `SunSchedule` that represents the time of the sun and the place.
+/// * `Manual` - Represents a job that is never scheduled and only runs when it is triggered, e.g. a command run by `cronus exec`.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    Cron(String),
+    After(String),
+    Event(String),
+    Rrule(String),
+    Sun(SunSchedule),
+    Manual,
+}
+
+impl Trigger {
+    /// Returns the cron expression of the trigger.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - Returns the cron expression, or `None` if the job does not run on a cron schedule, including a job running
+    ///   once when the service starts.
+    pub fn cron(&self) -> Option<&str> {
+        match self {
+            Trigger::Cron(cron) if !schedule::is_reboot(cron) => Some(cron),
+            Trigger::Cron(_)
+            | Trigger::After(_)
+            | Trigger::Event(_)
+            | Trigger::Rrule(_)
+            | Trigger::Sun(_)
+            | Trigger::Manual => None,
+        }
+    }
+
+    /// Checks whether the job runs once when the service starts, on the `@reboot` macro.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the job runs when the service starts.
+    pub fn at_startup(&self) -> bool {
+        matches!(self, Trigger::Cron(cron) if schedule::is_reboot(cron))
+    }
+
+    /// Returns the job the trigger waits for.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - Returns the id or the name of the job, or `None` if the job does not run after another job.
+    pub fn after(&self) -> Option<&str> {
+        match self {
+            Trigger::Cron(_)
+            | Trigger::Event(_)
+            | Trigger::Rrule(_)
+            | Trigger::Sun(_)
+            | Trigger::Manual => None,
+            Trigger::After(after) => Some(after),
+        }
+    }
+
+    /// Returns the topic the trigger listens on.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - Returns the topic, or `None` if the job does not run on emitted events.
+    pub fn event(&self) -> Option<&str> {
+        match self {
+            Trigger::Cron(_)
+            | Trigger::After(_)
+            | Trigger::Rrule(_)
+            | Trigger::Sun(_)
+            | Trigger::Manual => None,
+            Trigger::Event(topic) => Some(topic),
+        }
+    }
+
+    /// Returns the recurrence of the trigger.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - Returns the recurrence, or `None` if the job does not run on an RFC 5545 recurrence.
+    pub fn rrule(&self) -> Option<&str> {
+        match self {
+            Trigger::Rrule(rule) => Some(rule),
+            Trigger::Cron(_)
+            | Trigger::After(_)
+            | Trigger::Event(_)
+            | Trigger::Sun(_)
+            | Trigger::Manual => None,
+        }
+    }
+
+    /// Returns the time of the sun of the trigger.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&SunSchedule>` - Returns the time of the sun and the place, or `None` if the job does not run on the sun.
+    pub fn sun(&self) -> Option<&SunSchedule> {
+        match self {
+            Trigger::Sun(sun) => Some(sun),
+            Trigger::Cron(_)
+            | Trigger::After(_)
+            | Trigger::Event(_)
+            | Trigger::Rrule(_)
+            | Trigger::Manual => None,
+        }
+    }
+}
+
+/// `ExpiryAction` is an enumeration that represents what happens to a job once its schedule window has passed.
+///
+/// # Variants
+///
+/// * `Remove` - Represents removing the job from the scheduler.
+/// * `Pause` - Represents keeping the job registered, but no longer executing it.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpiryAction {
+    #[default]
+    Remove,
+    Pause,
+}
+
+impl FromStr for ExpiryAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "remove" => Ok(Self::Remove),
+            "pause" => Ok(Self::Pause),
+            _ => Err(format!(
+                "Invalid expiry action `{s}`, expected `remove` or `pause`"
+            )),
+        }
+    }
+}
+
+/// `OverlapPolicy` is an enumeration that represents what happens to a run of a job beyond its concurrency limits.
+///
+/// # Variants
+///
+/// * `Queue` - Represents waiting for a run in flight to finish before starting the run.
+/// * `Skip` - Represents skipping the run, emitting a `RunSkipped` event.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlapPolicy {
+    #[default]
+    Queue,
+    Skip,
+}
+
+impl FromStr for OverlapPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queue" => Ok(Self::Queue),
+            "skip" => Ok(Self::Skip),
+            _ => Err(format!(
+                "Invalid overlap policy `{s}`, expected `queue` or `skip`"
+            )),
+        }
+    }
+}
+
+/// `Priority` is an enumeration that represents how urgently the runs of a job are picked up by the workers of the service under load.
+///
+/// Priorities are ordered from `Low` to `High`, and queued runs of a higher priority are run before queued runs of a lower one.
+///
+/// # Variants
+///
+/// * `Low` - Represents bulk jobs that can wait for the other jobs.
+/// * `Normal` - Represents regular jobs.
+/// * `High` - Represents critical jobs that are run before the other jobs.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(Self::Low),
+            "normal" => Ok(Self::Normal),
+            "high" => Ok(Self::High),
+            _ => Err(format!(
+                "Invalid priority `{s}`, expected `high`, `normal` or `low`"
+            )),
+        }
+    }
+}
+
+/// `JobOptions` is a structure that represents the optional execution settings of a job.
+///
+/// # Fields
+///
+/// * `name` - An `Option<String>` that represents the unique name other jobs can refer to the job by. It is `None` if the job can only be referred to by its id.
+/// * `key` - An `Option<String>` that represents the stable key of a job declared in a job file. Jobs with a key are managed by `apply`.
+/// * `namespace` - An `Option<String>` that represents the namespace of the job, the only one whose clients see it, see `Scope`. It is `None` for the `default` namespace.
+/// * `description` - An `Option<String>` that represents what the job is for, in free text, e.g. `Purges the uploads older than 30 days`. It is `None` if the job is not described.
+/// * `labels` - A `BTreeMap<String, String>` that represents the free-form metadata of the job, by key, e.g. `team = "data"`, see `parse_label`.
+/// * `jitter` - An `Option<Duration>` that represents the maximum random delay applied before each execution of the job. It is `None` if the job runs exactly on its schedule.
+/// * `not_before` - An `Option<DateTime<Utc>>` that represents the time the schedule of the job becomes active. It is `None` if the schedule is active right away.
+/// * `not_after` - An `Option<DateTime<Utc>>` that represents the time the schedule of the job expires. It is `None` if the schedule never expires.
+/// * `on_expiry` - An `ExpiryAction` that represents what happens to the job once its schedule has expired.
+/// * `skip_if_failed` - A `bool` that represents whether the job is skipped while the failure of its previous run has not been acknowledged.
+/// * `cost_center` - An `Option<String>` that represents the cost center the runs of the job are charged to. It is `None` if the runs are not charged to any cost center.
+/// * `timezone` - An `Option<String>` that represents the IANA name of the timezone the cron schedule of the job is evaluated in, e.g. `Europe/Paris`. It is `None` if the schedule is evaluated in local time.
+/// * `on_dst` - A `DstPolicy` that represents what happens to a tick of the cron schedule of the job that falls into an hour a daylight-saving transition removes or repeats.
+/// * `business_days` - A `bool` that represents whether the job only runs from Monday to Friday, counted in the timezone of the job.
+/// * `holidays` - An `Option<PathBuf>` that represents the holiday calendar file of the job, one `YYYY-MM-DD` date per line, the job does not run on, see `calendar::parse_holidays`. It is read at every run, and `None` if the job has no holidays.
+/// * `on_holiday` - A `HolidayPolicy` that represents what happens to a run due on a day that is not a business day.
+/// * `max_concurrent` - An `Option<NonZeroU32>` that represents the maximum number of runs of the job in flight at once. It is `None` if the runs of the job are only limited by the service.
+/// * `on_overlap` - An `OverlapPolicy` that represents what happens to a run beyond the concurrency limits of the job or of the service.
+/// * `priority` - A `Priority` that represents how urgently the runs of the job are picked up by the workers of the service under load.
+/// * `nice` - An `Option<i32>` that represents the niceness the processes of the job run with, from -20 to 19. It is `None` if they inherit the niceness of the service.
+/// * `max_mem` - An `Option<u64>` that represents the maximum address space of the processes of the job, in bytes. It is `None` if their memory is not limited.
+/// * `cpu_quota` - An `Option<f64>` that represents the maximum number of CPUs the processes of the job may use, enforced with a cgroup v2 on Linux. It is `None` if their CPU time is not limited.
+/// * `user` - An `Option<String>` that represents the name or the uid of the user the processes of the job run as. It is `None` if they run as the user of the service.
+/// * `group` - An `Option<String>` that represents the name or the gid of the group the processes of the job run with. It is `None` if they run with the primary group of the user.
+/// * `idempotency_key` - An `Option<String>` that represents the template the idempotency key of a run is rendered from, e.g. `etl-{{scheduled_time | %Y%m%d}}`. A run whose key already succeeded is skipped. It is `None` if runs are not deduplicated.
+/// * `expect_within` - An `Option<Duration>` that represents the window a run of the job is expected to succeed within, beyond which a `RunOverdue` event is emitted. It is `None` if the job is not watched.
+/// * `disable_after` - An `Option<NonZeroU32>` that represents the number of consecutive failed runs after which the job is paused, emitting a `JobDisabled` event. It is `None` if the job is never paused for failing.
+/// * `timeout` - An `Option<Duration>` that represents how long a run of the job may take before it is killed and recorded as timed out. It is `None` if runs may take any time.
+/// * `max_operations` - An `Option<NonZeroU64>` that represents the maximum number of operations a Rhai or Lua script, or a WebAssembly module, of the job may carry out before it is aborted. It is `None` if the operations of the script are not limited.
+/// * `sandbox` - A `Sandbox` that represents what a Rhai or Lua script, or a WebAssembly module, of the job is allowed to do.
+/// * `on_start` - An `Option<Job>` that represents the hook run before each run of the job, e.g. to ping a healthcheck URL. It is `None` if the job has no such hook.
+/// * `on_success` - An `Option<Job>` that represents the hook run after each successful run of the job. It is `None` if the job has no such hook.
+/// * `on_failure` - An `Option<Job>` that represents the hook run after each failed run of the job. It is `None` if the job has no such hook.
+/// * `healthcheck_url` - An `Option<String>` that represents the healthchecks.io style ping URL of the job, pinged when each run starts and ends, see `JobHooks::run_around`. It is `None` if the job is not monitored.
+/// * `success_regex` - An `Option<String>` that represents the regular expression a line of output of a run must match for the run to succeed, e.g. `OK$`. It is `None` if the outcome of a run does not depend on its output.
+/// * `failure_regex` - An `Option<String>` that represents the regular expression no line of output of a run may match for the run to succeed, e.g. `ERROR`. It is `None` if the outcome of a run does not depend on its output.
+/// * `notify` - A vector of strings that represent the names of the notifiers of the service alerted about the runs of the job, see `Notifier`.
+/// * `notify_on` - A vector of `NotifyEvent` instances that represent the outcomes of the runs the notifiers are alerted about. The notifiers are only alerted about failures if it is empty.
+/// * `notify_after` - An `Option<Duration>` that represents how long a run may be in flight before the notifiers are alerted that it runs long. It is `None` if long runs are not alerted about.
+/// * `secrets` - A `BTreeMap<String, SecretSource>` that represents the secrets given to the processes of command and container jobs as environment variables, by the name of their variable, e.g. `DB_PASSWORD = "@/run/secrets/db"`. Only their sources are kept, their values are read at every run.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct JobOptions {
+    pub name: Option<String>,
+    pub key: Option<String>,
+    pub namespace: Option<String>,
+    pub description: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    #[serde(with = "humantime_serde")]
+    pub jitter: Option<Duration>,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+    pub on_expiry: ExpiryAction,
+    pub skip_if_failed: bool,
+    pub cost_center: Option<String>,
+    pub timezone: Option<String>,
+    pub on_dst: DstPolicy,
+    pub business_days: bool,
+    pub holidays: Option<PathBuf>,
+    pub on_holiday: HolidayPolicy,
+    pub max_concurrent: Option<NonZeroU32>,
+    pub on_overlap: OverlapPolicy,
+    pub priority: Priority,
+    pub nice: Option<i32>,
+    pub max_mem: Option<u64>,
+    pub cpu_quota: Option<f64>,
+    pub user: Option<String>,
+    pub group: Option<String>,
+    pub idempotency_key: Option<String>,
+    #[serde(with = "humantime_serde")]
+    pub expect_within: Option<Duration>,
+    pub disable_after: Option<NonZeroU32>,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+    pub max_operations: Option<NonZeroU64>,
+    pub sandbox: Sandbox,
+    pub on_start: Option<Job>,
+    pub on_success: Option<Job>,
+    pub on_failure: Option<Job>,
+    pub healthcheck_url: Option<String>,
+    pub success_regex: Option<String>,
+    pub failure_regex: Option<String>,
+    pub notify: Vec<String>,
+    pub notify_on: Vec<NotifyEvent>,
+    #[serde(with = "humantime_serde")]
+    pub notify_after: Option<Duration>,
+    pub secrets: BTreeMap<String, SecretSource>,
+}
+
+impl JobOptions {
+    /// Returns the resource limits applied to the processes of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `ResourceLimits` - Returns the niceness, memory, CPU and operation limits of the job, and its sandbox.
+    pub fn limits(&self) -> ResourceLimits {
+        ResourceLimits {
+            nice: self.nice,
+            max_mem: self.max_mem,
+            cpu_quota: self.cpu_quota,
+            max_operations: self.max_operations,
+            sandbox: self.sandbox,
+        }
+    }
+
+    /// Returns the user and group the processes of the job run as.
+    ///
+    /// # Returns
+    ///
+    /// * `RunAs` - Returns the user and the group of the job.
+    pub fn run_as(&self) -> RunAs {
+        RunAs {
+            user: self.user.clone(),
+            group: self.group.clone(),
+        }
+    }
+
+    /// Loads the calendar of the business days of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<BusinessCalendar>, String>` - Returns the calendar, `None` if the job runs on any day, or an error message if the
+    ///   holiday calendar file of the job cannot be read or is malformed.
+    pub fn calendar(&self) -> Result<Option<BusinessCalendar>, String> {
+        if !self.business_days && self.holidays.is_none() {
+            return Ok(None);
+        }
+        BusinessCalendar::load(
+            self.business_days,
+            self.holidays.as_deref(),
+            self.timezone.as_deref(),
+        )
+        .map(Some)
+    }
+
+    /// Compiles the patterns the output of the runs of the job is matched against, see `RunOutput::verdict`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<OutputPatterns, String>` - Returns the patterns, or an error message if a regular expression of the job is invalid.
+    pub fn output_patterns(&self) -> Result<OutputPatterns, String> {
+        let compile = |pattern: &Option<String>, kind: &str| {
+            pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| format!("Invalid {kind} regex: {e}"))
+        };
+        Ok(OutputPatterns {
+            success: compile(&self.success_regex, "success")?,
+            failure: compile(&self.failure_regex, "failure")?,
+        })
+    }
+
+    /// Returns the hooks run around the runs of the job, see `JobHooks`.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = (&'static str, &Job)>` - Returns the name and the job of every hook declared, in the order they may run.
+    pub fn hooks(&self) -> impl Iterator<Item = (&'static str, &Job)> {
+        [
+            ("on_start", &self.on_start),
+            ("on_success", &self.on_success),
+            ("on_failure", &self.on_failure),
+        ]
+        .into_iter()
+        .filter_map(|(name, hook)| Some((name, hook.as_ref()?)))
+    }
+
+    /// Checks that the hooks of the job can be run by this version of cronus and that their templates are valid.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if every hook is valid, or an error message describing the first invalid one.
+    pub fn validate_hooks(&self) -> Result<(), String> {
+        self.hooks().try_for_each(|(name, hook)| {
+            hook.validate_templates()
+                .map_err(|e| format!("Hook `{name}`: {e}"))
+        })
+    }
+
+    /// Checks whether the notifiers of the job are alerted about an outcome of its runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - A `NotifyEvent` that represents the outcome of a run.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the outcome is one of `notify_on`, or is a failure and `notify_on` is empty.
+    pub fn notifies(&self, event: NotifyEvent) -> bool {
+        if self.notify_on.is_empty() {
+            event == NotifyEvent::Failure
+        } else {
+            self.notify_on.contains(&event)
+        }
+    }
+
+    /// Checks whether the schedule of the job is active at a given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - A `DateTime<Utc>` that represents the time to check.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if `now` lies within the `not_before` and `not_after` window of the job.
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.not_before.is_none_or(|not_before| now >= not_before) && !self.is_expired_at(now)
+    }
+
+    /// Checks whether the schedule of the job has expired at a given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - A `DateTime<Utc>` that represents the time to check.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if `now` lies after the `not_after` time of the job.
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.not_after.is_some_and(|not_after| now > not_after)
+    }
+
+    /// Picks the delay to apply before an execution of the job.
+    ///
+    /// The delay is chosen uniformly between zero and the configured `jitter`, so jobs sharing a schedule are spread over the jitter window.
+    ///
+    /// # Returns
+    ///
+    /// * `Duration` - Returns the random delay, or `Duration::ZERO` if no jitter is configured.
+    pub fn jitter_delay(&self) -> Duration {
+        match self.jitter {
+            Some(max) if !max.is_zero() => {
+                let millis = rand::thread_rng().gen_range(0..=max.as_millis() as u64);
+                Duration::from_millis(millis)
+            }
+            _ => Duration::ZERO,
+        }
+    }
 }
 
 /// `JobInfo` is a structure that represents the information of a job.
@@ -143,15 +1872,38 @@ impl Job {
 /// # Fields
 ///
 /// * `id` - A string that represents the unique identifier of the job.
-/// * `cron` - A string that represents the cron schedule of the job.
+/// * `trigger` - A `Trigger` that represents what makes the job run.
 /// * `last_run` - An `Option<u64>` that represents the last run time of the job in Unix timestamp. It is `None` if the job has never been run.
 /// * `next_run` - An `Option<u64>` that represents the next run time of the job in Unix timestamp. It is `None` if the job is not scheduled to run.
 /// * `job` - A `Job` that represents the job itself.
+/// * `options` - A `JobOptions` that represents the execution settings of the job.
+/// * `paused` - A `bool` that represents whether the executions of the job are suspended.
+/// * `unacknowledged_failure` - A `bool` that represents whether the last run of the job failed and the failure has not been acknowledged yet.
+/// * `estimated_duration` - An `Option<Duration>` that represents the estimated duration of the runs of the job. It is `None` if the job has never been run.
+/// * `overruns_next_tick` - A `bool` that represents whether the next run of the job is estimated to still be in flight on the tick after it.
+/// * `last_result` - An `Option<serde_json::Value>` that represents the value the last run of the job returned, e.g. the last expression of a Rhai script. It is `None` if the last run returned nothing.
+/// * `runs_in_flight` - A `usize` that represents the number of runs of the job in flight.
+/// * `agent` - An `Option<String>` that represents the agent the job runs on, when listed by a controller. It is `None` if the job is
+///   listed by the service it runs on.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct JobInfo {
     pub id: String,
-    pub cron: String,
+    #[serde(flatten)]
+    pub trigger: Trigger,
     pub last_run: Option<u64>,
     pub next_run: Option<u64>,
     pub job: Job,
+    pub options: JobOptions,
+    pub paused: bool,
+    pub unacknowledged_failure: bool,
+    #[serde(default, with = "humantime_serde")]
+    pub estimated_duration: Option<Duration>,
+    #[serde(default)]
+    pub overruns_next_tick: bool,
+    #[serde(default)]
+    pub last_result: Option<serde_json::Value>,
+    #[serde(default)]
+    pub runs_in_flight: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
 }
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+/// Parses a label of a job given on the command line.
+///
+/// # Arguments
+///
+/// * `label` - A string that represents the label, as `key=value`, e.g. `team=data`.
+///
+/// # Returns
+///
+/// * `Result<(String, String), String>` - Returns the key and the value of the label, or an error message if the label is not
+///   `key=value` or its key is not valid, see `validate_labels`.
+pub fn parse_label(label: &str) -> Result<(String, String), String> {
+    let (key, value) = label
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid label `{label}`, expected key=value"))?;
+    validate_key(key)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Checks that the labels of a job have valid keys: made of letters, digits, `-`, `_`, `.` and `/`, e.g. `team` or `app.io/owner`.
+///
+/// # Arguments
+///
+/// * `labels` - A reference to the labels of the job, by their key.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if every key is valid, or an error message describing the first invalid one.
+pub fn validate_labels(labels: &BTreeMap<String, String>) -> Result<(), String> {
+    labels.keys().try_for_each(|key| validate_key(key))
+}
+
+/// Checks that the key of a label is a valid name.
+///
+/// # Arguments
+///
+/// * `key` - A string that represents the key of the label.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the key is valid, or an error message if not.
+fn validate_key(key: &str) -> Result<(), String> {
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+    {
+        return Err(format!(
+            "Invalid label key `{key}`, expected letters, digits, `-`, `_`, `.` and `/`"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::task;
+use tokio::time::{sleep, timeout};
+
+/// How many times within its window a lease is renewed by its holder, and checked by the services waiting for it.
+const CHECKS_PER_WINDOW: u32 = 3;
+
+/// `Lease` is a structure that represents the lease on a job store shared by a pair of services, so only one of them, the leader, runs
+/// its jobs, while the other one, the standby, waits to take over.
+///
+/// The lease is kept next to the job store, in `<file>.lease`, as the holder of the lease and the time it expires at. The holder renews
+/// the lease `CHECKS_PER_WINDOW` times within its window, and a service waiting for the lease takes it once it has expired, i.e. once
+/// its holder has failed to renew it for a whole window. The holder gives the lease up a check before it expires, so it has stopped
+/// running jobs by the time the other service may take over. The lease file is locked while it is read and written, so two services
+/// never take the lease at once.
+///
+/// # Fields
+///
+/// * `file` - A `PathBuf` that represents the path of the lease file.
+/// * `holder` - A `String` that represents the service taking the lease, as `<name>@<host>:<pid>`.
+/// * `window` - A `Duration` that represents how long the lease lasts once taken or renewed.
+#[derive(Debug, Clone)]
+pub struct Lease {
+    file: PathBuf,
+    holder: String,
+    window: Duration,
+}
+
+/// `LeaseRecord` is a structure that represents the content of a lease file.
+///
+/// # Fields
+///
+/// * `holder` - A `String` that represents the service holding the lease.
+/// * `expires_at` - A `u64` that represents the time the lease expires at, in milliseconds since the Unix epoch.
+#[derive(Debug, Serialize, Deserialize)]
+struct LeaseRecord {
+    holder: String,
+    expires_at: u64,
+}
+
+impl Lease {
+    /// Constructs a new `Lease` on a job store, for a service of this process.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - A path to the store file.
+    /// * `name` - A string that represents the name of the service taking the lease.
+    /// * `window` - A `Duration` that represents how long the lease lasts once taken or renewed.
+    ///
+    /// # Returns
+    ///
+    /// * `Lease` - Returns the lease, not taken yet.
+    pub fn new(store: &Path, name: &str, window: Duration) -> Self {
+        let mut file = store.as_os_str().to_owned();
+        file.push(".lease");
+        let host = std::fs::read_to_string("/proc/sys/kernel/hostname").unwrap_or_default();
+        Self {
+            file: file.into(),
+            holder: format!("{name}@{}:{}", host.trim(), std::process::id()),
+            window,
+        }
+    }
+
+    /// Waits for the lease to be taken by this service, checking it `CHECKS_PER_WINDOW` times per window while another service holds it.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<()>` - Returns `Ok(())` once the lease is held by this service, or an error if the lease file cannot be read or written.
+    pub async fn acquire(&self) -> io::Result<()> {
+        while self.take().await?.is_some() {
+            sleep(self.window / CHECKS_PER_WINDOW).await;
+        }
+        Ok(())
+    }
+
+    /// Keeps the lease held by this service, renewing it `CHECKS_PER_WINDOW` times per window.
+    ///
+    /// A renewal that cannot write the lease file, or is still waiting for its lock, is retried until a check before the lease expires.
+    /// The lease is given up then, so this service stops running jobs before the other service may take it.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Never returns while the lease is held, and returns an error message once it is held by another service,
+    ///   or is about to expire without being renewed, as this service must stop running jobs then.
+    pub async fn keep(self) -> Result<(), String> {
+        let check = self.window / CHECKS_PER_WINDOW;
+        let deadline = self.window - check;
+        let mut renewed_at = Instant::now();
+        loop {
+            sleep(check).await;
+            let attempted_at = Instant::now();
+            let left = deadline.saturating_sub(renewed_at.elapsed());
+            let renewal = timeout(left, self.take()).await.unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "the lease file is still locked",
+                ))
+            });
+            match renewal {
+                Ok(None) => renewed_at = attempted_at,
+                Ok(Some(holder)) => {
+                    return Err(format!(
+                        "The lease of the job store was taken over by `{holder}`"
+                    ));
+                }
+                Err(e) if renewed_at.elapsed() >= deadline => {
+                    return Err(format!("Cannot renew the lease of the job store: {e}"));
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Releases the lease if it is held by this service, so the other service takes over without waiting for it to expire.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<()>` - Returns `Ok(())` if the lease is not held by this service anymore, or an error if the lease file cannot be
+    ///   read or written.
+    pub fn release(&self) -> io::Result<()> {
+        self.with_record(|record| match record {
+            Some(record) if record.holder == self.holder => (None, ()),
+            record => (record, ()),
+        })
+    }
+
+    /// Takes or renews the lease for this service like `try_take`, on the blocking threads of the runtime, as locking the lease file
+    /// blocks while another service holds the lock.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<Option<String>>` - Returns the result of `try_take`.
+    async fn take(&self) -> io::Result<Option<String>> {
+        let lease = self.clone();
+        task::spawn_blocking(move || lease.try_take())
+            .await
+            .unwrap_or_else(|e| Err(io::Error::other(e)))
+    }
+
+    /// Takes or renews the lease for this service, unless another service holds it and it has not expired yet.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<Option<String>>` - Returns `None` if the lease is held by this service, or the holder of the lease if it is held by
+    ///   another service, or an error if the lease file cannot be read or written.
+    fn try_take(&self) -> io::Result<Option<String>> {
+        let now = now_millis();
+        self.with_record(|record| match record {
+            Some(record) if record.holder != self.holder && record.expires_at > now => {
+                let holder = record.holder.clone();
+                (Some(record), Some(holder))
+            }
+            _ => {
+                let taken = LeaseRecord {
+                    holder: self.holder.clone(),
+                    expires_at: now + self.window.as_millis() as u64,
+                };
+                (Some(taken), None)
+            }
+        })
+    }
+
+    /// Reads and rewrites the lease file while holding an exclusive lock on it.
+    ///
+    /// # Arguments
+    ///
+    /// * `update` - A function given the record of the lease file, `None` if it is empty or missing, and returning the record written
+    ///   in its place, `None` to empty the file, along with its result.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<T>` - Returns the result of `update`, or an error if the lease file cannot be locked, read or written.
+    fn with_record<T>(
+        &self,
+        update: impl FnOnce(Option<LeaseRecord>) -> (Option<LeaseRecord>, T),
+    ) -> io::Result<T> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.file)?;
+        // SAFETY: `flock` has no memory safety requirements, and the descriptor is the one of an open file. The lock is released
+        // when the file is closed.
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        // A lease file that cannot be parsed was left half written by a crash, and holds no lease.
+        let (record, result) = update(serde_json::from_slice(&content).ok());
+        file.set_len(0)?;
+        file.rewind()?;
+        if let Some(record) = record {
+            file.write_all(&serde_json::to_vec(&record)?)?;
+        }
+        file.sync_all()?;
+        Ok(result)
+    }
+}
+
+/// Returns the current time, in milliseconds since the Unix epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
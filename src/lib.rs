@@ -1,6 +1,76 @@
+pub mod analyze;
+pub mod apply;
+pub mod archive;
+pub mod audit;
+pub mod auth;
+pub mod backup;
+pub mod calendar;
+pub mod chain;
+pub mod chaos;
 pub mod command;
+pub mod completions;
+pub mod config;
+pub mod container;
+pub mod context;
+pub mod controller;
+pub mod credentials;
+pub mod crypto;
+pub mod doctor;
+pub mod dst;
+pub mod error_code;
+pub mod events;
+pub mod follower;
+pub mod format;
+pub mod health;
+pub mod history;
+pub mod hooks;
+pub mod http;
+pub mod http_client;
+pub mod idempotency;
 pub mod job;
+pub mod label;
+pub mod lease;
+pub mod limits;
+pub mod lint;
+pub mod listing;
+#[cfg(feature = "lua")]
+pub mod lua;
+pub mod man;
+pub mod manifest;
+pub mod namespace;
 mod nng_socket;
+pub mod notify;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod output;
+pub mod pipeline;
+pub mod policy;
+pub mod pool;
+pub mod preview;
+pub mod projection;
+pub mod quota;
+pub mod recurrence;
+pub mod sandbox;
+pub mod schedule;
 pub mod scheduler;
+pub mod secret;
+pub mod solar;
+#[cfg(feature = "sql")]
+pub mod sql;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+pub mod state;
+pub mod stats;
+pub mod store;
+pub mod supervisor;
+pub mod table;
+pub mod template;
+pub mod throttle;
+pub mod timeline;
+pub mod top;
+pub mod tz;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod workflow;
 
 pub type CronusResult<T> = Result<T, Box<dyn std::error::Error>>;
@@ -0,0 +1,173 @@
+use std::fs;
+use std::io;
+use std::num::NonZeroU64;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sandbox::Sandbox;
+
+/// The mount point of the cgroup v2 hierarchy.
+const CGROUP_MOUNT: &str = "/sys/fs/cgroup";
+
+/// The cgroup the runs with a CPU quota are placed under, one child cgroup per run.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup/cronus";
+
+/// The period the CPU quota of a run is enforced over, in microseconds.
+const CPU_PERIOD_MICROS: u64 = 100_000;
+
+/// `ResourceLimits` is a structure that represents the limits applied to the processes spawned by a command job, and to the scripts run
+/// by a Rhai, Lua or WebAssembly job.
+///
+/// # Fields
+///
+/// * `nice` - An `Option<i32>` that represents the niceness the process runs with, from -20 to 19. It is `None` if the process inherits the niceness of the service.
+/// * `max_mem` - An `Option<u64>` that represents the maximum address space of the process, in bytes. It is `None` if the memory of the process is not limited.
+/// * `cpu_quota` - An `Option<f64>` that represents the maximum number of CPUs the process may use, e.g. `0.5`. It is `None` if the CPU time of the process is not limited.
+/// * `max_operations` - An `Option<NonZeroU64>` that represents the maximum number of operations a Rhai or Lua script, or a WebAssembly module, may carry out before it is aborted. It is `None` if the operations of the script are not limited.
+/// * `sandbox` - A `Sandbox` that represents what a Rhai or Lua script, or a WebAssembly module, is allowed to do.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub struct ResourceLimits {
+    pub nice: Option<i32>,
+    pub max_mem: Option<u64>,
+    pub cpu_quota: Option<f64>,
+    pub max_operations: Option<NonZeroU64>,
+    pub sandbox: Sandbox,
+}
+
+impl ResourceLimits {
+    /// Sets up a command so the process it spawns runs within the niceness and memory limits.
+    ///
+    /// The niceness is set with `setpriority` and the memory limit with `setrlimit(RLIMIT_AS)` in the process, before it executes the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd` - A mutable reference to the `Command` to be set up.
+    pub fn apply(&self, cmd: &mut Command) {
+        let Self { nice, max_mem, .. } = *self;
+        if nice.is_none() && max_mem.is_none() {
+            return;
+        }
+        // SAFETY: the closure only calls async-signal-safe functions, as required between `fork` and `exec`.
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some(nice) = nice {
+                    if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                if let Some(max_mem) = max_mem {
+                    let limit = libc::rlimit {
+                        rlim_cur: max_mem as libc::rlim_t,
+                        rlim_max: max_mem as libc::rlim_t,
+                    };
+                    if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// Places a spawned process in a cgroup enforcing its CPU quota, and its memory limit as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `pid` - A `u32` that represents the process id of the spawned process.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<Option<Cgroup>>` - Returns the cgroup of the process, `None` if no CPU quota is set, or an error if the cgroup v2
+    ///   hierarchy is not available or not writable.
+    pub fn confine(&self, pid: u32) -> io::Result<Option<Cgroup>> {
+        let Some(cpu_quota) = self.cpu_quota else {
+            return Ok(None);
+        };
+        if !Path::new(CGROUP_MOUNT).join("cgroup.controllers").exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("no cgroup v2 hierarchy is mounted at {CGROUP_MOUNT}"),
+            ));
+        }
+        let root = Path::new(CGROUP_ROOT);
+        if !root.exists() {
+            fs::create_dir(root)?;
+        }
+        fs::write(root.join("cgroup.subtree_control"), "+cpu +memory")?;
+        let cgroup = Cgroup(root.join(format!("run-{pid}")));
+        fs::create_dir(&cgroup.0)?;
+        let quota = (cpu_quota * CPU_PERIOD_MICROS as f64).round().max(1.0) as u64;
+        fs::write(
+            cgroup.0.join("cpu.max"),
+            format!("{quota} {CPU_PERIOD_MICROS}"),
+        )?;
+        if let Some(max_mem) = self.max_mem {
+            fs::write(cgroup.0.join("memory.max"), max_mem.to_string())?;
+        }
+        fs::write(cgroup.0.join("cgroup.procs"), pid.to_string())?;
+        Ok(Some(cgroup))
+    }
+
+    /// Checks that the limits are valid.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the limits are valid, or an error message describing the first invalid limit.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(nice) = self.nice.filter(|nice| !(-20..=19).contains(nice)) {
+            return Err(format!("Invalid niceness `{nice}`, expected -20 to 19"));
+        }
+        if let Some(cpu_quota) = self
+            .cpu_quota
+            .filter(|quota| !quota.is_finite() || *quota <= 0.0)
+        {
+            return Err(format!(
+                "Invalid CPU quota `{cpu_quota}`, expected a positive number of CPUs"
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `Cgroup` is a structure that represents the cgroup of a run, removed once the run is over.
+pub struct Cgroup(PathBuf);
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        _ = fs::remove_dir(&self.0);
+    }
+}
+
+/// Parses a memory size, e.g. `512M` or `2GiB`.
+///
+/// The size is a number of bytes, optionally followed by a `K`, `M`, `G` or `T` binary unit, with an optional `B` or `iB` suffix.
+///
+/// # Arguments
+///
+/// * `s` - A string that represents the memory size.
+///
+/// # Returns
+///
+/// * `Result<u64, String>` - Returns the size in bytes, or an error message if the size is invalid.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid memory size `{s}`, expected e.g. 512M or 2G");
+    let trimmed = s.trim();
+    let number = trimmed.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = &trimmed[number.len()..];
+    let shift = match unit.trim_end_matches("iB").trim_end_matches('B') {
+        "" => 0,
+        "K" | "k" => 10,
+        "M" | "m" => 20,
+        "G" | "g" => 30,
+        "T" | "t" => 40,
+        _ => return Err(invalid()),
+    };
+    let number: u64 = number.trim().parse().map_err(|_| invalid())?;
+    number
+        .checked_shl(shift)
+        .filter(|size| size >> shift == number)
+        .ok_or_else(invalid)
+}
@@ -0,0 +1,569 @@
+use std::env;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Local, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+
+use crate::dst::{displaced_ticks, DstChange};
+use crate::http_client;
+use crate::job::{Job, JobOptions, Trigger};
+use crate::pipeline;
+use crate::projection::shortest_interval;
+use crate::recurrence::parse_rrule;
+use crate::schedule::parse_schedule;
+use crate::solar::SunSchedule;
+use crate::template;
+use crate::tz::parse_timezone;
+use crate::workflow::validate_steps;
+
+/// `LintSeverity` is an enumeration that represents how serious a lint finding is.
+///
+/// # Variants
+///
+/// * `Warning` - Represents a smell that may be intended, but usually is not.
+/// * `Error` - Represents a problem that prevents the job from working as declared.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Warning,
+    Error,
+}
+
+/// `LintFinding` is a structure that represents a single smell detected in a job definition.
+///
+/// # Fields
+///
+/// * `job` - A string that identifies the linted job, either its id or its position in the job file.
+/// * `rule` - A string that represents the name of the rule that detected the smell.
+/// * `severity` - A `LintSeverity` that represents how serious the smell is.
+/// * `message` - A string that represents a human readable description of the smell.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct LintFinding {
+    pub job: String,
+    pub rule: String,
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+impl LintFinding {
+    /// Creates a new `LintFinding`.
+    ///
+    /// # Arguments
+    ///
+    /// * `job` - A string that identifies the linted job.
+    /// * `rule` - A string that represents the name of the rule that detected the smell.
+    /// * `severity` - A `LintSeverity` that represents how serious the smell is.
+    /// * `message` - A string that represents a human readable description of the smell.
+    ///
+    /// # Returns
+    ///
+    /// * `LintFinding` - Returns the new finding.
+    fn new(job: &str, rule: &str, severity: LintSeverity, message: String) -> Self {
+        Self {
+            job: job.to_string(),
+            rule: rule.to_string(),
+            severity,
+            message,
+        }
+    }
+}
+
+/// The interval below which a schedule fires too often to go unnoticed, see the `frequent-schedule` rule.
+const FREQUENT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many days ahead the ticks of a schedule are checked for falling into DST transitions.
+const DST_LINT_DAYS: i64 = 366;
+
+/// The rules whose findings refuse a job when it is registered, see `preflight_job`.
+const PREFLIGHT_RULES: &[&str] = &[
+    "command-not-found",
+    "command-not-executable",
+    "script-file-not-found",
+    "script-file-not-readable",
+    "module-file-not-found",
+];
+
+/// Describes the ticks of a schedule within the next year that fall into a DST transition and do not run under the DST policy of a job.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule` of the job.
+/// * `options` - A reference to the `JobOptions` of the job, with its timezone and DST policy.
+///
+/// # Returns
+///
+/// * `Option<String>` - Returns a description of the ticks, or `None` if every tick runs.
+fn dst_skipped_ticks(schedule: &Schedule, options: &JobOptions) -> Option<String> {
+    let now = Utc::now();
+    let until = now + chrono::Duration::days(DST_LINT_DAYS);
+    let ticks = match options.timezone.as_deref().map(parse_timezone) {
+        Some(Ok(tz)) => displaced_ticks(schedule, &tz, now, until),
+        Some(Err(_)) => return None,
+        None => displaced_ticks(schedule, &Local, now, until),
+    };
+    let skipped: Vec<_> = ticks
+        .iter()
+        .filter(|tick| !options.on_dst.runs(tick.change))
+        .map(|tick| match tick.change {
+            DstChange::Gap => format!("{} does not exist", tick.local),
+            DstChange::Overlap => format!("{} occurs twice", tick.local),
+        })
+        .collect();
+    let first = skipped.first()?;
+    let more = match skipped.len() {
+        1 => String::new(),
+        count => format!(" (and {} more)", count - 1),
+    };
+    Some(format!(
+        "local time {first}{more} because of a DST transition, so the job does not run then; set on_dst to shift to run such ticks"
+    ))
+}
+
+/// Lints a single job definition.
+///
+/// The following rules are checked:
+///
+/// * `invalid-schedule` - The cron expression cannot be parsed.
+/// * `schedule-faster-than-duration` - The job fires more often than its runs are estimated to take.
+/// * `frequent-schedule` - The job fires more than once a minute, e.g. every second for a cron expression with a seconds field of `*`.
+/// * `dst-skipped-tick` - A tick of the schedule within the next year falls into a DST transition and does not run under the DST policy
+///   of the job.
+/// * `command-not-found` - The command of a command job does not exist on disk.
+/// * `command-not-executable` - The command of a command job is not executable.
+/// * `invalid-template` - An argument of a command job has an invalid time placeholder.
+/// * `script-file-not-found` - The script file of a Rhai script file job does not exist on disk.
+/// * `script-file-not-readable` - The script file of a Rhai script file job cannot be read by the service.
+/// * `invalid-script` - The script of a Rhai script job does not compile.
+/// * `invalid-workflow` - The steps of a workflow job have duplicate names, unknown dependencies or a dependency cycle.
+/// * `invalid-pipeline` - A pipeline job has no steps, or steps with duplicate names.
+/// * `invalid-url` - The healthcheck URL of the job is not a valid `http` or `https` URL.
+/// * `missing-timeout` - A command or script job has no timeout, so a run that hangs is never killed.
+///
+/// The hooks of the job are linted like what the job runs, identified as `<job_ref>/<hook>` in the findings, e.g. `#0/on_failure`.
+///
+/// # Arguments
+///
+/// * `job_ref` - A string that identifies the linted job in the findings.
+/// * `trigger` - A reference to the `Trigger` of the job. The schedule rules only apply to jobs running on a cron schedule.
+/// * `job` - A reference to the `Job` to be linted.
+/// * `options` - A reference to the `JobOptions` of the job, declaring its hooks.
+/// * `estimated_duration` - An `Option<Duration>` that represents the estimated duration of the runs of the job, if it has run.
+///
+/// # Returns
+///
+/// * `Vec<LintFinding>` - Returns the detected smells, or an empty vector if the job is clean.
+pub fn lint_job(
+    job_ref: &str,
+    trigger: &Trigger,
+    job: &Job,
+    options: &JobOptions,
+    estimated_duration: Option<Duration>,
+) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    match trigger.cron().map(parse_schedule) {
+        Some(Ok(schedule)) => {
+            if let Some(interval) =
+                shortest_interval(&schedule).filter(|interval| *interval < FREQUENT_INTERVAL)
+            {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "frequent-schedule",
+                    LintSeverity::Warning,
+                    format!(
+                        "schedule fires every {}, up to {} times a day",
+                        humantime::format_duration(interval),
+                        86400 / interval.as_secs().max(1)
+                    ),
+                ));
+            }
+            if let (Some(interval), Some(duration)) =
+                (shortest_interval(&schedule), estimated_duration)
+            {
+                if duration >= interval {
+                    findings.push(LintFinding::new(
+                        job_ref,
+                        "schedule-faster-than-duration",
+                        LintSeverity::Warning,
+                        format!(
+                            "schedule fires every {} but runs are estimated to take {}",
+                            humantime::format_duration(interval),
+                            humantime::format_duration(Duration::from_millis(
+                                duration.as_millis() as u64
+                            ))
+                        ),
+                    ));
+                }
+            }
+            if let Some(message) = dst_skipped_ticks(&schedule, options) {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "dst-skipped-tick",
+                    LintSeverity::Warning,
+                    message,
+                ));
+            }
+        }
+        Some(Err(e)) => findings.push(LintFinding::new(
+            job_ref,
+            "invalid-schedule",
+            LintSeverity::Error,
+            e.to_string(),
+        )),
+        None => {}
+    }
+    if let Some(Err(e)) = trigger.rrule().map(parse_rrule) {
+        findings.push(LintFinding::new(
+            job_ref,
+            "invalid-schedule",
+            LintSeverity::Error,
+            e.to_string(),
+        ));
+    }
+    if let Some(Err(e)) = trigger.sun().map(SunSchedule::validate) {
+        findings.push(LintFinding::new(
+            job_ref,
+            "invalid-schedule",
+            LintSeverity::Error,
+            e,
+        ));
+    }
+    if let Some(Err(e)) = options
+        .healthcheck_url
+        .as_deref()
+        .map(http_client::validate_url)
+    {
+        findings.push(LintFinding::new(
+            job_ref,
+            "invalid-url",
+            LintSeverity::Error,
+            e,
+        ));
+    }
+    if options.timeout.is_none() && may_hang(job) {
+        findings.push(LintFinding::new(
+            job_ref,
+            "missing-timeout",
+            LintSeverity::Warning,
+            "job has no timeout, so a run that hangs is never killed; set timeout, e.g. --timeout 10m".to_string(),
+        ));
+    }
+    findings.extend(lint_payload(job_ref, job));
+    for (name, hook) in options.hooks() {
+        findings.extend(lint_payload(&format!("{job_ref}/{name}"), hook));
+    }
+    findings
+}
+
+/// Tells whether a job runs a command or a script, which may hang until it is killed, see the `missing-timeout` rule.
+///
+/// # Arguments
+///
+/// * `job` - A reference to the `Job`.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` for a command job or a script job, and `false` otherwise.
+fn may_hang(job: &Job) -> bool {
+    match job {
+        Job::Command(..) | Job::RhaiScript(_) | Job::RhaiScriptFile(_) => true,
+        #[cfg(feature = "lua")]
+        Job::Lua(_) | Job::LuaFile(_) => true,
+        _ => false,
+    }
+}
+
+/// Lints what a job runs.
+///
+/// The steps of a workflow or a pipeline job are linted one by one, identified as `<job_ref>/<step>` in the findings.
+///
+/// # Arguments
+///
+/// * `job_ref` - A string that identifies the linted job in the findings.
+/// * `job` - A reference to the `Job` to be linted.
+///
+/// # Returns
+///
+/// * `Vec<LintFinding>` - Returns the detected smells, or an empty vector if the job is clean.
+fn lint_payload(job_ref: &str, job: &Job) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    match job {
+        Job::Command(cmd_path, args, _) => {
+            match resolve_command(cmd_path) {
+                Some(resolved) if !is_executable(&resolved) => findings.push(LintFinding::new(
+                    job_ref,
+                    "command-not-executable",
+                    LintSeverity::Error,
+                    format!("command `{}` is not executable", resolved.display()),
+                )),
+                Some(_) => {}
+                None => findings.push(LintFinding::new(
+                    job_ref,
+                    "command-not-found",
+                    LintSeverity::Error,
+                    format!("command `{}` does not exist", cmd_path.display()),
+                )),
+            }
+            for arg in args {
+                if let Err(e) = template::validate(arg) {
+                    findings.push(LintFinding::new(
+                        job_ref,
+                        "invalid-template",
+                        LintSeverity::Error,
+                        e,
+                    ));
+                }
+            }
+        }
+        Job::Container {
+            image, cmd, env, ..
+        } => {
+            if image.trim().is_empty() {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "missing-image",
+                    LintSeverity::Error,
+                    "container has no image".to_string(),
+                ));
+            }
+            for arg in cmd.iter().chain(env.values()) {
+                if let Err(e) = template::validate(arg) {
+                    findings.push(LintFinding::new(
+                        job_ref,
+                        "invalid-template",
+                        LintSeverity::Error,
+                        e,
+                    ));
+                }
+            }
+        }
+        Job::RhaiScript(script) => {
+            if let Err(e) = rhai::Engine::new().compile(script) {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "invalid-script",
+                    LintSeverity::Error,
+                    format!("script does not compile: {e}"),
+                ));
+            }
+        }
+        Job::Workflow(steps) => {
+            if let Err(e) = validate_steps(steps) {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "invalid-workflow",
+                    LintSeverity::Error,
+                    e,
+                ));
+            }
+            for step in steps {
+                findings.extend(lint_payload(&format!("{job_ref}/{}", step.name), &step.job));
+            }
+        }
+        Job::Pipeline(steps) => {
+            if let Err(e) = pipeline::validate_steps(steps) {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "invalid-pipeline",
+                    LintSeverity::Error,
+                    e,
+                ));
+            }
+            for step in steps {
+                findings.extend(lint_payload(&format!("{job_ref}/{}", step.name), &step.job));
+            }
+        }
+        Job::RhaiScriptFile(file) => findings.extend(lint_script_file(job_ref, file)),
+        #[cfg(feature = "lua")]
+        Job::Lua(script) => {
+            if let Err(e) = mlua::Lua::new().load(script).into_function() {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "invalid-script",
+                    LintSeverity::Error,
+                    format!("script does not compile: {e}"),
+                ));
+            }
+        }
+        #[cfg(feature = "lua")]
+        Job::LuaFile(file) => findings.extend(lint_script_file(job_ref, file)),
+        #[cfg(feature = "wasm")]
+        Job::Wasm {
+            module_path, args, ..
+        } => {
+            if !module_path.is_file() {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "module-file-not-found",
+                    LintSeverity::Error,
+                    format!("module file `{}` does not exist", module_path.display()),
+                ));
+            }
+            for arg in args {
+                if let Err(e) = template::validate(arg) {
+                    findings.push(LintFinding::new(
+                        job_ref,
+                        "invalid-template",
+                        LintSeverity::Error,
+                        e,
+                    ));
+                }
+            }
+        }
+        #[cfg(feature = "sql")]
+        Job::Sql { url, statement } => {
+            if !crate::sql::SUPPORTED_SCHEMES
+                .iter()
+                .any(|scheme| url.starts_with(scheme))
+            {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "unsupported-database",
+                    LintSeverity::Error,
+                    "database URL must start with postgres:, postgresql:, mysql:, mariadb: or sqlite:"
+                        .to_string(),
+                ));
+            }
+            if let Err(e) = template::validate(statement) {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "invalid-template",
+                    LintSeverity::Error,
+                    e,
+                ));
+            }
+        }
+        #[cfg(feature = "ssh")]
+        Job::Ssh {
+            key_path, command, ..
+        } => {
+            if let Some(key_path) = key_path.as_ref().filter(|key_path| !key_path.is_file()) {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "key-file-not-found",
+                    LintSeverity::Error,
+                    format!("key file `{}` does not exist", key_path.display()),
+                ));
+            }
+            if let Err(e) = template::validate(command) {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "invalid-template",
+                    LintSeverity::Error,
+                    e,
+                ));
+            }
+        }
+        Job::Unsupported { .. } => {
+            if let Err(e) = job.check_supported() {
+                findings.push(LintFinding::new(
+                    job_ref,
+                    "unsupported-job",
+                    LintSeverity::Error,
+                    e,
+                ));
+            }
+        }
+    }
+    findings
+}
+
+/// Lints the script file of a Rhai or Lua script file job.
+///
+/// # Arguments
+///
+/// * `job_ref` - A string that identifies the linted job in the findings.
+/// * `file` - A reference to the path of the script file.
+///
+/// # Returns
+///
+/// * `Option<LintFinding>` - Returns the finding if the script file does not exist or cannot be read, or `None` if it can be read.
+fn lint_script_file(job_ref: &str, file: &Path) -> Option<LintFinding> {
+    if !file.is_file() {
+        return Some(LintFinding::new(
+            job_ref,
+            "script-file-not-found",
+            LintSeverity::Error,
+            format!("script file `{}` does not exist", file.display()),
+        ));
+    }
+    File::open(file).err().map(|e| {
+        LintFinding::new(
+            job_ref,
+            "script-file-not-readable",
+            LintSeverity::Error,
+            format!("script file `{}` cannot be read: {e}", file.display()),
+        )
+    })
+}
+
+/// Checks that what a job, and every hook of it, runs can be found on the host of the service before the job is registered: that
+/// the command of a command job exists and is executable, and that the script file of a script file job, or the module of a
+/// WebAssembly job, exists and can be read. A job failing these checks would never run without the service telling why.
+///
+/// # Arguments
+///
+/// * `job` - A reference to the `Job` to be registered.
+/// * `options` - A reference to the `JobOptions` of the job, declaring its hooks.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if everything the job runs is found, or an error message describing the first problem.
+pub fn preflight_job(job: &Job, options: &JobOptions) -> Result<(), String> {
+    let problem = |job_ref: &str, job: &Job| {
+        lint_payload(job_ref, job)
+            .into_iter()
+            .find(|finding| PREFLIGHT_RULES.contains(&finding.rule.as_str()))
+            .map(|finding| finding.message)
+    };
+    if let Some(e) = problem("job", job) {
+        return Err(format!("Job cannot run: {e}"));
+    }
+    for (name, hook) in options.hooks() {
+        if let Some(e) = problem(name, hook) {
+            return Err(format!("Hook `{name}` cannot run: {e}"));
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the path of a command the way a process spawn would.
+///
+/// Paths containing a separator are used as is, bare names are looked up in the `PATH` environment variable.
+///
+/// # Arguments
+///
+/// * `cmd_path` - A reference to the path of the command.
+///
+/// # Returns
+///
+/// * `Option<PathBuf>` - Returns the resolved path of the command, or `None` if it does not exist.
+fn resolve_command(cmd_path: &Path) -> Option<PathBuf> {
+    if cmd_path.components().count() > 1 {
+        return cmd_path.is_file().then(|| cmd_path.to_path_buf());
+    }
+    env::var_os("PATH").and_then(|paths| {
+        env::split_paths(&paths)
+            .map(|dir| dir.join(cmd_path))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Checks whether a file is executable.
+///
+/// # Arguments
+///
+/// * `path` - A reference to the path of the file.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` if any of the execute permission bits of the file is set.
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
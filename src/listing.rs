@@ -0,0 +1,145 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::job::{Job, JobInfo, JobOptions};
+use crate::namespace::DEFAULT_NAMESPACE;
+
+/// `JobSort` is an enumeration that represents the order jobs are listed in.
+///
+/// # Variants
+///
+/// * `Id` - Represents listing the jobs by id, an order that does not change as the jobs run, so pages can be walked through.
+/// * `Name` - Represents listing the jobs by name, the jobs without a name last.
+/// * `NextRun` - Represents listing the jobs by the time they run next, the jobs that are not scheduled last.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum JobSort {
+    #[default]
+    Id,
+    Name,
+    NextRun,
+}
+
+impl FromStr for JobSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(Self::Id),
+            "name" => Ok(Self::Name),
+            "next_run" => Ok(Self::NextRun),
+            _ => Err(format!(
+                "Invalid sort `{s}`, expected `id`, `name` or `next_run`"
+            )),
+        }
+    }
+}
+
+/// `JobFilter` is a structure that represents which jobs are listed.
+///
+/// # Fields
+///
+/// * `name` - An `Option<String>` that represents a part of the name of the listed jobs. It is `None` if jobs are listed whatever their name.
+/// * `job_type` - An `Option<String>` that represents the `type` of the listed jobs, e.g. `command` or `rhai_script`, see the `format`
+///   module. It is `None` if jobs of every type are listed.
+/// * `namespace` - An `Option<String>` that represents the namespace of the listed jobs, set by the service to the namespace of the
+///   client. It is `None` if jobs of every namespace are listed.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct JobFilter {
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub job_type: Option<String>,
+    pub namespace: Option<String>,
+}
+
+impl JobFilter {
+    /// Checks whether a job is listed.
+    ///
+    /// # Arguments
+    ///
+    /// * `job` - A reference to the `Job` of the job.
+    /// * `options` - A reference to the `JobOptions` of the job, holding its name.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the job matches every criterion of the filter, and `false` if not.
+    pub fn matches(&self, job: &Job, options: &JobOptions) -> bool {
+        let named = self.name.as_deref().is_none_or(|name| {
+            options
+                .name
+                .as_deref()
+                .is_some_and(|job_name| job_name.contains(name))
+        });
+        named
+            && self.job_type.as_deref().is_none_or(|job_type| {
+                serde_json::to_value(job)
+                    .ok()
+                    .is_some_and(|job| job.get("type").and_then(Value::as_str) == Some(job_type))
+            })
+            && self.namespace.as_deref().is_none_or(|namespace| {
+                options.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE) == namespace
+            })
+    }
+}
+
+/// `JobPage` is a structure that represents a page of the listed jobs.
+///
+/// # Fields
+///
+/// * `jobs` - A vector of `JobInfo` instances that represent the jobs of the page.
+/// * `total` - A `usize` that represents the number of jobs matching the filter, on every page.
+/// * `next_offset` - An `Option<usize>` that represents the offset of the next page. It is `None` if the page is the last one.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct JobPage {
+    pub jobs: Vec<JobInfo>,
+    pub total: usize,
+    pub next_offset: Option<usize>,
+}
+
+/// Sorts the listed jobs.
+///
+/// # Arguments
+///
+/// * `jobs` - A mutable slice of `JobInfo` instances that represent the listed jobs.
+/// * `sort` - A `JobSort` that represents the order they are listed in. Jobs sorting alike are listed by id.
+pub fn sort_jobs(jobs: &mut [JobInfo], sort: JobSort) {
+    jobs.sort_by(|a, b| {
+        let order = match sort {
+            JobSort::Id => std::cmp::Ordering::Equal,
+            JobSort::Name => match (&a.options.name, &b.options.name) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+            JobSort::NextRun => match (a.next_run, b.next_run) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+        };
+        order.then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Cuts a page out of the sorted listed jobs.
+///
+/// # Arguments
+///
+/// * `jobs` - A vector of `JobInfo` instances that represent the listed jobs, sorted.
+/// * `offset` - A `usize` that represents the number of jobs skipped.
+/// * `limit` - A `usize` that represents the maximum number of jobs of the page.
+///
+/// # Returns
+///
+/// * `JobPage` - Returns the jobs from `offset`, at most `limit` of them, with the offset of the next page if any jobs are left.
+pub fn page_jobs(jobs: Vec<JobInfo>, offset: usize, limit: usize) -> JobPage {
+    let total = jobs.len();
+    let jobs: Vec<_> = jobs.into_iter().skip(offset).take(limit).collect();
+    let end = offset.saturating_add(jobs.len());
+    JobPage {
+        jobs,
+        total,
+        next_offset: (end < total).then_some(end),
+    }
+}
@@ -0,0 +1,330 @@
+use std::cell::Cell;
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use chrono::{SecondsFormat, Utc};
+use mlua::{
+    DeserializeOptions, Function, HookTriggers, Lua, LuaSerdeExt, SerializeOptions, Table, Value,
+    Variadic,
+};
+
+use crate::credentials::RunAs;
+use crate::http_client;
+use crate::job::{Job, JobIdentity, KillSwitch, Tick, RUN_KILLED};
+use crate::limits::ResourceLimits;
+use crate::output::RunOutput;
+use crate::state;
+
+/// How many Lua instructions are carried out between two checks of the kill switch of a run and of the operations of its script.
+const HOOK_INTERVAL: u32 = 1000;
+
+/// The error message of a Lua script that exceeded its maximum number of operations.
+const TOO_MANY_OPERATIONS: &str = "Script exceeded its maximum number of operations";
+
+/// Runs a Lua script for a run of a job, with the tick of the run and the job as globals.
+///
+/// The script is given the same context as a Rhai script: the `scheduled_time`, `window_start`, `window_end` and `started_time`
+/// globals, formatted as RFC 3339 in UTC, the `run_id`, `job_id` and `job_name` globals, `job_name` being `nil` if the job has no name, and the
+/// `state` table, see `register_helpers`. The script runs in a Lua state confined to the sandbox of the job, see `Sandbox::lua`, and
+/// is terminated once the kill switch of the run is flipped or it has carried out its maximum number of operations, counted by
+/// batches of instructions. What the script prints with `print` is written to the output of the run, and the first value it returns
+/// becomes the result of the run, unless it returns `nil`.
+///
+/// # Arguments
+///
+/// * `script` - A string that represents the Lua script.
+/// * `chunk_name` - A string that represents the name of the script in error messages, e.g. `@/etc/cronus/report.lua` for a file.
+/// * `tick` - A `Tick` that represents the tick of the run.
+/// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+/// * `kill_switch` - A reference to the `KillSwitch` of the run.
+/// * `output` - A reference to the `RunOutput` of the run.
+/// * `limits` - A `ResourceLimits` that represents the limits applied to the script, and to the processes it runs.
+/// * `run_as` - A reference to the `RunAs` that represents the user and group the processes run by the script run as.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the script succeeded, or an error message describing why it failed.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    script: &str,
+    chunk_name: &str,
+    tick: Tick,
+    identity: &JobIdentity,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+    limits: ResourceLimits,
+    run_as: &RunAs,
+) -> Result<(), String> {
+    let lua = limits
+        .sandbox
+        .lua()
+        .map_err(|e| format!("Cannot create the Lua state: {e}"))?;
+    let exceeded = Rc::new(Cell::new(false));
+    let interval = limits
+        .max_operations
+        .map_or(HOOK_INTERVAL, |max_operations| {
+            max_operations.get().min(u64::from(HOOK_INTERVAL)) as u32
+        });
+    let operations = Cell::new(0u64);
+    let hook_kill_switch = kill_switch.clone();
+    let hook_exceeded = exceeded.clone();
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(interval),
+        move |_, _| {
+            if hook_kill_switch.is_killed() {
+                return Err(mlua::Error::runtime(RUN_KILLED));
+            }
+            operations.set(operations.get() + u64::from(interval));
+            if limits
+                .max_operations
+                .is_some_and(|max_operations| operations.get() > max_operations.get())
+            {
+                hook_exceeded.set(true);
+                return Err(mlua::Error::runtime(TOO_MANY_OPERATIONS));
+            }
+            Ok(())
+        },
+    );
+    let value = set_globals(&lua, tick, identity)
+        .and_then(|_| register_helpers(&lua, tick, identity, kill_switch, output, limits, run_as))
+        .and_then(|_| lua.load(script).set_name(chunk_name).eval::<Value>())
+        .map_err(|e| describe_error(&e, kill_switch, exceeded.get()))?;
+    if !value.is_nil() {
+        let result = lua
+            .from_value_with(value, json_options())
+            .map_err(|e| format!("Cannot record the result of the script: {e}"))?;
+        output.set_result(result);
+    }
+    Ok(())
+}
+
+/// Sets the globals describing the tick of a run and the job it is for.
+///
+/// # Arguments
+///
+/// * `lua` - A reference to the `Lua` state of the script.
+/// * `tick` - A `Tick` that represents the tick of the run.
+/// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+///
+/// # Returns
+///
+/// * `mlua::Result<()>` - Returns `Ok(())` if the globals were set, or an error if not.
+fn set_globals(lua: &Lua, tick: Tick, identity: &JobIdentity) -> mlua::Result<()> {
+    let globals = lua.globals();
+    for (name, value) in tick.variables() {
+        globals.set(name, value)?;
+    }
+    globals.set(
+        "started_time",
+        Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+    )?;
+    globals.set("job_id", identity.id.clone())?;
+    globals.set("job_name", identity.name.clone())
+}
+
+/// Registers the functions a Lua script can call, depending on its sandbox.
+///
+/// `print` writes its arguments to the output of the run, separated by tabs. In every sandbox, a script can call `state.get(key)`,
+/// which returns a value of the state of the job or `nil` if it has none, `state.set(key, value)` and `state.remove(key)`, which
+/// returns the value removed. Outside the `strict` sandbox, a script can call `env(name)`, `http_get(url)` and `http_post(url, body)`,
+/// and a script of the `full` sandbox `read_file(path)`, `write_file(path, text)`, `append_file(path, text)`, `file_exists(path)` and
+/// `exec(program, args)`, which behave like the helpers of Rhai scripts.
+///
+/// # Arguments
+///
+/// * `lua` - A reference to the `Lua` state of the script.
+/// * `tick` - A `Tick` that represents the tick of the run.
+/// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+/// * `kill_switch` - A reference to the `KillSwitch` of the run.
+/// * `output` - A reference to the `RunOutput` of the run, which the output of the processes is written to as well.
+/// * `limits` - A `ResourceLimits` that represents the limits applied to the script, and to the processes it runs.
+/// * `run_as` - A reference to the `RunAs` that represents the user and group the processes run by the script run as.
+///
+/// # Returns
+///
+/// * `mlua::Result<()>` - Returns `Ok(())` if the functions were registered, or an error if not.
+fn register_helpers(
+    lua: &Lua,
+    tick: Tick,
+    identity: &JobIdentity,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+    limits: ResourceLimits,
+    run_as: &RunAs,
+) -> mlua::Result<()> {
+    let globals = lua.globals();
+    let print_output = output.clone();
+    globals.set(
+        "print",
+        lua.create_function(move |lua, values: Variadic<Value>| {
+            let tostring: Function = lua.globals().get("tostring")?;
+            let texts = values
+                .into_iter()
+                .map(|value| tostring.call::<_, String>(value))
+                .collect::<mlua::Result<Vec<_>>>()?;
+            print_output.push(texts.join("\t"));
+            Ok(())
+        })?,
+    )?;
+    globals.set("state", state_table(lua, &identity.id)?)?;
+    if limits.sandbox.exposes_environment() {
+        globals.set(
+            "env",
+            lua.create_function(|_, name: String| Ok(std::env::var(name).ok()))?,
+        )?;
+        globals.set(
+            "http_get",
+            lua.create_function(|_, url: String| {
+                http_client::request("GET", &url, None).map_err(mlua::Error::runtime)
+            })?,
+        )?;
+        globals.set(
+            "http_post",
+            lua.create_function(|_, (url, body): (String, String)| {
+                http_client::request("POST", &url, Some(&body)).map_err(mlua::Error::runtime)
+            })?,
+        )?;
+    }
+    if !limits.sandbox.exposes_host() {
+        return Ok(());
+    }
+    globals.set(
+        "read_file",
+        lua.create_function(|_, path: String| {
+            std::fs::read_to_string(&path)
+                .map_err(|e| mlua::Error::runtime(format!("Cannot read {path}: {e}")))
+        })?,
+    )?;
+    globals.set(
+        "write_file",
+        lua.create_function(|_, (path, text): (String, String)| {
+            std::fs::write(&path, text)
+                .map_err(|e| mlua::Error::runtime(format!("Cannot write {path}: {e}")))
+        })?,
+    )?;
+    globals.set(
+        "append_file",
+        lua.create_function(|_, (path, text): (String, String)| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+                .map_err(|e| mlua::Error::runtime(format!("Cannot write {path}: {e}")))
+        })?,
+    )?;
+    globals.set(
+        "file_exists",
+        lua.create_function(|_, path: String| Ok(std::path::Path::new(&path).exists()))?,
+    )?;
+    let identity = identity.clone();
+    let kill_switch = kill_switch.clone();
+    let output = output.clone();
+    let run_as = run_as.clone();
+    globals.set(
+        "exec",
+        lua.create_function(move |_, (program, args): (String, Option<Vec<String>>)| {
+            let business = Job::command_to_business(
+                PathBuf::from(program),
+                args.unwrap_or_default(),
+                None,
+                identity.clone(),
+                limits,
+                run_as.clone(),
+            );
+            let captured = RunOutput::default();
+            let result = business(tick, &kill_switch, &captured);
+            let lines = captured.read_from(0).lines;
+            for line in &lines {
+                output.push(line.clone());
+            }
+            result.map_err(mlua::Error::runtime)?;
+            Ok(lines.join("\n"))
+        })?,
+    )
+}
+
+/// Creates the `state` table a Lua script reads and writes the state of its job with.
+///
+/// # Arguments
+///
+/// * `lua` - A reference to the `Lua` state of the script.
+/// * `job` - A string that represents the id of the job.
+///
+/// # Returns
+///
+/// * `mlua::Result<Table>` - Returns the table holding the `get`, `set` and `remove` functions, or an error if it cannot be created.
+fn state_table<'lua>(lua: &'lua Lua, job: &str) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    let get_job = job.to_string();
+    table.set(
+        "get",
+        lua.create_function(move |lua, key: String| match state::get(&get_job, &key) {
+            Some(value) => lua.to_value_with(&value, lua_options()),
+            None => Ok(Value::Nil),
+        })?,
+    )?;
+    let set_job = job.to_string();
+    table.set(
+        "set",
+        lua.create_function(move |lua, (key, value): (String, Value)| {
+            state::set(&set_job, &key, lua.from_value_with(value, json_options())?);
+            Ok(())
+        })?,
+    )?;
+    let remove_job = job.to_string();
+    table.set(
+        "remove",
+        lua.create_function(
+            move |lua, key: String| match state::remove(&remove_job, &key) {
+                Some(value) => lua.to_value_with(&value, lua_options()),
+                None => Ok(Value::Nil),
+            },
+        )?,
+    )?;
+    Ok(table)
+}
+
+/// Returns how JSON is converted into Lua values.
+///
+/// # Returns
+///
+/// * `SerializeOptions` - Returns options converting `null` to `nil`, and arrays to tables converted back into arrays.
+fn lua_options() -> SerializeOptions {
+    SerializeOptions::new()
+        .serialize_none_to_null(false)
+        .serialize_unit_to_null(false)
+}
+
+/// Returns how Lua values are converted into JSON.
+///
+/// # Returns
+///
+/// * `DeserializeOptions` - Returns options converting values without a JSON counterpart, e.g. functions, to `null`.
+fn json_options() -> DeserializeOptions {
+    DeserializeOptions::new().deny_unsupported_types(false)
+}
+
+/// Describes why a Lua script failed.
+///
+/// # Arguments
+///
+/// * `e` - A reference to the error the script failed with.
+/// * `kill_switch` - A reference to the `KillSwitch` of the run.
+/// * `exceeded` - A `bool` that represents whether the script exceeded its maximum number of operations.
+///
+/// # Returns
+///
+/// * `String` - Returns the error message of the run.
+fn describe_error(e: &mlua::Error, kill_switch: &KillSwitch, exceeded: bool) -> String {
+    if kill_switch.is_killed() {
+        RUN_KILLED.to_string()
+    } else if exceeded {
+        TOO_MANY_OPERATIONS.to_string()
+    } else if let mlua::Error::MemoryError(_) = e {
+        "Script exceeded its memory limit".to_string()
+    } else {
+        e.to_string()
+    }
+}
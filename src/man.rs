@@ -0,0 +1,110 @@
+use structopt::clap::{App, AppSettings, ErrorKind};
+
+/// Renders the man page of the CLI, in roff.
+///
+/// The page describes the CLI with its help, followed by a section for every subcommand and nested subcommand, e.g. `cronus add
+/// cmd-job`, with the help of the subcommand. Hidden subcommands are left out.
+///
+/// # Arguments
+///
+/// * `app` - An `App` that represents the command line of the CLI.
+/// * `bin_name` - A string that represents the name the CLI is run as.
+///
+/// # Returns
+///
+/// * `String` - Returns the man page, to be read with `man -l` or installed in the section 1 of the manual.
+pub fn render(app: App, bin_name: &str) -> String {
+    let about = app.p.meta.about.unwrap_or_default();
+    let version = app.p.meta.version.unwrap_or_default();
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{bin_name} {version}\" \"User Commands\"\n.SH NAME\n{bin_name} \\- {}\n.SH SYNOPSIS\n.B {bin_name}\n<SUBCOMMAND> [OPTIONS]\n.SH DESCRIPTION\n",
+        bin_name.to_uppercase(),
+        escape(about),
+    );
+    page.push_str(&verbatim(&help(&app, bin_name, &[])));
+    page.push_str(".SH COMMANDS\n");
+    for path in commands(&app, Vec::new()) {
+        page.push_str(&format!(
+            ".SS \"{bin_name} {}\"\n{}",
+            path.join(" "),
+            verbatim(&help(&app, bin_name, &path))
+        ));
+    }
+    page
+}
+
+/// Lists the visible subcommands of a command, each followed by its own subcommands.
+///
+/// # Arguments
+///
+/// * `app` - A reference to the `App` of the command.
+/// * `path` - A vector of strings that represent the names of the command and the commands it is nested in, empty for the CLI itself.
+///
+/// # Returns
+///
+/// * `Vec<Vec<String>>` - Returns the path of every subcommand, in the order they are declared.
+fn commands(app: &App, path: Vec<String>) -> Vec<Vec<String>> {
+    app.p
+        .subcommands
+        .iter()
+        .filter(|sub| !sub.p.is_set(AppSettings::Hidden))
+        .flat_map(|sub| {
+            let mut sub_path = path.clone();
+            sub_path.push(sub.p.meta.name.clone());
+            std::iter::once(sub_path.clone()).chain(commands(sub, sub_path))
+        })
+        .collect()
+}
+
+/// Returns the help of a command, as printed by `--help`.
+///
+/// # Arguments
+///
+/// * `app` - A reference to the `App` of the CLI.
+/// * `bin_name` - A string that represents the name the CLI is run as.
+/// * `path` - A slice of strings that represent the path of the command, empty for the CLI itself.
+///
+/// # Returns
+///
+/// * `String` - Returns the help of the command.
+fn help(app: &App, bin_name: &str, path: &[String]) -> String {
+    let args = std::iter::once(bin_name)
+        .chain(path.iter().map(String::as_str))
+        .chain(std::iter::once("--help"));
+    match app.clone().get_matches_from_safe(args) {
+        Err(e) if e.kind == ErrorKind::HelpDisplayed => e.message,
+        _ => String::new(),
+    }
+}
+
+/// Writes text as a block of roff printed as is, without filling its lines.
+///
+/// # Arguments
+///
+/// * `text` - A string that represents the text.
+///
+/// # Returns
+///
+/// * `String` - Returns the block.
+fn verbatim(text: &str) -> String {
+    let lines: Vec<_> = text.lines().map(escape).collect();
+    format!(".nf\n{}\n.fi\n", lines.join("\n"))
+}
+
+/// Escapes a line of text for roff, so its backslashes are printed and it is not read as a request.
+///
+/// # Arguments
+///
+/// * `line` - A string that represents the line.
+///
+/// # Returns
+///
+/// * `String` - Returns the escaped line.
+fn escape(line: &str) -> String {
+    let line = line.replace('\\', "\\e");
+    if line.starts_with(['.', '\'']) {
+        format!("\\&{line}")
+    } else {
+        line
+    }
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::http_client;
+use crate::job::{Job, JobOptions, Trigger};
+use crate::label::validate_labels;
+use crate::recurrence::parse_rrule;
+use crate::schedule::parse_schedule;
+use crate::secret::validate_secrets;
+use crate::template;
+use crate::tz::parse_timezone;
+use crate::CronusResult;
+
+/// `JobSpec` is a structure that represents a job as declared in a job file.
+///
+/// # Fields
+///
+/// * `key` - An `Option<String>` that represents the stable key `apply` matches the job against live jobs by. Job files used with `apply` must give every job a key.
+/// * `trigger` - A `Trigger` that represents what makes the job run, declared as a `cron`, an `after`, an `event`, an `rrule` or a `sun` key.
+/// * `job` - A `Job` that represents the job itself.
+/// * `options` - A `JobOptions` that represents the execution settings of the job.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct JobSpec {
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(flatten)]
+    pub trigger: Trigger,
+    pub job: Job,
+    #[serde(default)]
+    pub options: JobOptions,
+}
+
+impl JobSpec {
+    /// Returns the execution settings the job is registered with.
+    ///
+    /// # Returns
+    ///
+    /// * `JobOptions` - Returns the options of the job, carrying the key of the job.
+    pub fn options(&self) -> JobOptions {
+        JobOptions {
+            key: self.key.clone(),
+            ..self.options.clone()
+        }
+    }
+
+    /// Checks that the job can be registered: its cron expression, recurrence or place on Earth, timezone, templates, hooks, resource limits, user, group,
+    /// healthcheck URL, secrets, holiday calendar, idempotency key, expect-within window and timeout must be valid.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the job is valid, or an error message describing the first invalid setting.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(cron) = self.trigger.cron() {
+            parse_schedule(cron).map_err(|e| e.to_string())?;
+        }
+        if let Some(rule) = self.trigger.rrule() {
+            parse_rrule(rule).map_err(|e| e.to_string())?;
+        }
+        if let Some(sun) = self.trigger.sun() {
+            sun.validate()?;
+        }
+        if let Some(zone) = &self.options.timezone {
+            parse_timezone(zone).map_err(|e| e.to_string())?;
+        }
+        self.job.validate_templates()?;
+        self.options.validate_hooks()?;
+        self.options.limits().validate()?;
+        self.options.run_as().validate()?;
+        if let Some(url) = &self.options.healthcheck_url {
+            http_client::validate_url(url)?;
+        }
+        validate_secrets(&self.options.secrets)?;
+        validate_labels(&self.options.labels)?;
+        self.options.calendar()?;
+        self.options.output_patterns()?;
+        if let Some(idempotency_key) = &self.options.idempotency_key {
+            template::validate(idempotency_key)
+                .map_err(|e| format!("invalid idempotency key: {e}"))?;
+        }
+        if self
+            .options
+            .expect_within
+            .is_some_and(|window| window.as_secs() == 0)
+        {
+            return Err("the expect-within window must be at least 1s".to_string());
+        }
+        if self
+            .options
+            .timeout
+            .is_some_and(|timeout| timeout.is_zero())
+        {
+            return Err("the timeout must not be zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// `JobFile` is a structure that represents a TOML job file.
+///
+/// # Fields
+///
+/// * `jobs` - A vector of `JobSpec` instances declared as `[[jobs]]` tables.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+struct JobFile {
+    #[serde(default)]
+    jobs: Vec<JobSpec>,
+}
+
+/// Loads the jobs declared in a job file.
+///
+/// A job file with a `.toml` extension declares its jobs as `[[jobs]]` tables, any other job file is a JSON array of `JobSpec` objects.
+///
+/// # Arguments
+///
+/// * `path` - A path to the job file.
+///
+/// # Returns
+///
+/// * `CronusResult<Vec<JobSpec>>` - Returns a `CronusResult` that contains the declared jobs on success or an error.
+pub fn load_job_specs(path: &Path) -> CronusResult<Vec<JobSpec>> {
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        let content = std::fs::read_to_string(path)?;
+        let file: JobFile = toml::from_str(&content)?;
+        return Ok(file.jobs);
+    }
+    let content = std::fs::read(path)?;
+    serde_json::from_slice(&content).map_err(Into::into)
+}
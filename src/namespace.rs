@@ -0,0 +1,109 @@
+use std::fmt;
+
+/// The environment variable naming the namespace a client works in, instead of the namespace of its token.
+pub const NAMESPACE_VAR: &str = "CRONUS_NAMESPACE";
+
+/// The namespace of the jobs added without one, and of the clients that name none and whose token, if any, is confined to none.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// The namespace a client names to work across every namespace, which only an admin may.
+pub const ALL_NAMESPACES: &str = "*";
+
+/// `Scope` is an enumeration that represents the jobs a client works on, so teams sharing a service only see, and only change, the jobs
+/// of their own namespace.
+///
+/// # Variants
+///
+/// * `Namespace(String)` - Represents the jobs of one namespace, e.g. `data`.
+/// * `All` - Represents the jobs of every namespace, named `*`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Scope {
+    Namespace(String),
+    All,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::Namespace(DEFAULT_NAMESPACE.to_string())
+    }
+}
+
+impl Scope {
+    /// Parses the scope a client names.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - A string that represents the namespace, or `*` for every namespace.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Scope, String>` - Returns the scope, or an error message if the namespace is not a valid name, see `validate_namespace`.
+    pub fn named(namespace: &str) -> Result<Self, String> {
+        if namespace == ALL_NAMESPACES {
+            return Ok(Self::All);
+        }
+        validate_namespace(namespace)?;
+        Ok(Self::Namespace(namespace.to_string()))
+    }
+
+    /// Checks whether a job is in the scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - An `Option<&str>` that represents the namespace of the job, see `JobOptions::namespace`. It is `None` for the
+    ///   `default` namespace.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the scope is every namespace or the namespace of the job.
+    pub fn contains(&self, namespace: Option<&str>) -> bool {
+        match self {
+            Self::Namespace(scope) => scope == namespace.unwrap_or(DEFAULT_NAMESPACE),
+            Self::All => true,
+        }
+    }
+
+    /// Returns the namespace the jobs added in the scope are recorded in.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - Returns the namespace of the scope, or `None` for the `default` namespace, in which the jobs added across every
+    ///   namespace are recorded too.
+    pub fn recorded(&self) -> Option<String> {
+        match self {
+            Self::Namespace(scope) if scope != DEFAULT_NAMESPACE => Some(scope.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Namespace(scope) => write!(f, "{scope}"),
+            Self::All => write!(f, "{ALL_NAMESPACES}"),
+        }
+    }
+}
+
+/// Checks that a namespace is a valid name: made of letters, digits, `-`, `_` and `.`, e.g. `data` or `team-web`.
+///
+/// # Arguments
+///
+/// * `namespace` - A string that represents the namespace.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the namespace is valid, or an error message if not.
+pub fn validate_namespace(namespace: &str) -> Result<(), String> {
+    if namespace.is_empty()
+        || !namespace
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(format!(
+            "Invalid namespace `{namespace}`, expected letters, digits, `-`, `_` and `.`"
+        ));
+    }
+    Ok(())
+}
@@ -1,6 +1,9 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use nng::{Error, Message, Protocol, Socket};
+use nng::options::{Options, RecvMaxSize, RecvTimeout, SendTimeout};
+use nng::{Aio, AioResult, Context, Error, Message, Protocol, Socket};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
 
 use crate::CronusResult;
 
@@ -69,6 +72,41 @@ impl NngIpcSocket {
         Ok(sock)
     }
 
+    /// Constructs a new `NngIpcSocket` that dials to the given URL, e.g. `tcp://web1:7070` for a service on another host.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - A string that represents the address of the socket, with its transport, e.g. `ipc://` or `tcp://`.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<Self>` - Returns a `CronusResult` that contains the newly created `NngIpcSocket` or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the socket fails to dial synchronously to the given URL.
+    pub fn new_dial_url(url: &str) -> CronusResult<Self> {
+        let sock = Self {
+            raw: Socket::new(Protocol::Req0)?,
+            addr: url.to_string(),
+        };
+        sock.dial()?;
+        Ok(sock)
+    }
+
+    /// Listens for connections on another address as well, e.g. `tcp://0.0.0.0:7070`, so the `NngIpcSocket` is reached over TCP.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - A string that represents the other address, with its transport.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains an empty tuple on success or an error.
+    pub fn listen_on(&self, url: &str) -> CronusResult<()> {
+        self.raw.listen(url).map_err(Into::into)
+    }
+
     /// Initiates listening for connections on the `NngIpcSocket`.
     ///
     /// # Returns
@@ -124,6 +162,102 @@ impl NngIpcSocket {
     pub fn send<M: Into<Message>>(&self, msg: M) -> CronusResult<()> {
         self.raw.send(msg).map_err(Error::from).map_err(Into::into)
     }
+
+    /// Bounds how long sending and receiving a message through the `NngIpcSocket` may block.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - A `Duration` after which a send or a receive fails with a timeout error.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains an empty tuple on success or an error.
+    pub fn set_timeout(&self, timeout: Duration) -> CronusResult<()> {
+        self.raw.set_opt::<RecvTimeout>(Some(timeout))?;
+        self.raw.set_opt::<SendTimeout>(Some(timeout))?;
+        Ok(())
+    }
+
+    /// Bounds the size of the messages received through the `NngIpcSocket`, on every address it listens on, including the connections
+    /// already open. A larger message is dropped without being buffered, closing the connection it was sent on.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - A `usize` that represents the size of the largest message received in bytes, or `0` for messages of any size.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains an empty tuple on success or an error.
+    pub fn set_recv_max_size(&self, max: usize) -> CronusResult<()> {
+        self.raw.set_opt::<RecvMaxSize>(max)?;
+        Ok(())
+    }
+
+    /// Opens a context on the `NngIpcSocket`, to exchange messages independently of the other contexts of the socket.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<NngIpcContext>` - Returns a `CronusResult` that contains the newly opened `NngIpcContext` or an error.
+    pub fn context(&self) -> CronusResult<NngIpcContext> {
+        let (sender, results) = mpsc::unbounded_channel();
+        Ok(NngIpcContext {
+            raw: Context::new(&self.raw)?,
+            aio: Aio::new(move |_, result| _ = sender.send(result))?,
+            results,
+        })
+    }
+}
+
+/// `NngIpcContext` is a structure that represents a context of an `NngIpcSocket`.
+/// On a listening socket, every context receives requests and sends their replies on its own, so a request is answered on the
+/// context it was received on while the other contexts keep receiving requests.
+pub struct NngIpcContext {
+    /// `raw` is the raw NNG context.
+    raw: Context,
+    /// `aio` is the handle of the asynchronous operations run on the context.
+    aio: Aio,
+    /// `results` receives the results of the operations of `aio` as they complete.
+    results: UnboundedReceiver<AioResult>,
+}
+
+impl NngIpcContext {
+    /// Receives a message from the `NngIpcContext`, without blocking the thread.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<Message>` - Returns a `CronusResult` that contains the received message or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the context fails to receive a message, e.g. once its socket is closed.
+    pub async fn recv(&mut self) -> CronusResult<Message> {
+        self.raw.recv(&self.aio)?;
+        match self.results.recv().await {
+            Some(AioResult::Recv(result)) => result.map_err(Into::into),
+            _ => Err(Error::Closed.into()),
+        }
+    }
+
+    /// Sends a message through the `NngIpcContext`, without blocking the thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - A message that can be converted into a `Message` type.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains an empty tuple on success or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the context fails to send the message.
+    pub async fn send<M: Into<Message>>(&mut self, msg: M) -> CronusResult<()> {
+        self.raw.send(&self.aio, msg).map_err(|(_, e)| e)?;
+        match self.results.recv().await {
+            Some(AioResult::Send(result)) => result.map_err(|(_, e)| e.into()),
+            _ => Err(Error::Closed.into()),
+        }
+    }
 }
 
 /// Implementation of the `Drop` trait for `NngIpcSocket`.
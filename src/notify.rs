@@ -0,0 +1,96 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::http_client;
+
+/// `Notifier` is an enumeration that represents a backend the alerts about jobs are sent to, declared as a `[notifiers.<name>]` table
+/// of the configuration file of the service, and attached to jobs by its name.
+///
+/// # Variants
+///
+/// * `Slack` - Represents a Slack incoming webhook. It contains the URL of the webhook.
+/// * `Discord` - Represents a Discord webhook. It contains the URL of the webhook.
+/// * `Telegram` - Represents a Telegram bot. It contains the token of the bot and the id of the chat the alerts are sent to, e.g.
+///   `-1001234567890` or `@ops_alerts`.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notifier {
+    Slack { webhook_url: String },
+    Discord { webhook_url: String },
+    Telegram { bot_token: String, chat_id: String },
+}
+
+impl Notifier {
+    /// Sends an alert through the backend.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - A string that represents the text of the alert.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the backend accepted the alert, or an error message if not.
+    pub fn send(&self, message: &str) -> Result<(), String> {
+        match self {
+            Notifier::Slack { webhook_url } => {
+                http_client::post_json(webhook_url, &json!({ "text": message }))
+            }
+            Notifier::Discord { webhook_url } => {
+                http_client::post_json(webhook_url, &json!({ "content": message }))
+            }
+            // The token is part of the URL, so it is kept out of the error messages recorded in the run history.
+            Notifier::Telegram { bot_token, chat_id } => http_client::post_json(
+                &format!("https://api.telegram.org/bot{bot_token}/sendMessage"),
+                &json!({ "chat_id": chat_id, "text": message }),
+            )
+            .map_err(|e| e.replace(bot_token.as_str(), "<bot_token>")),
+        }
+        .map(|_| ())
+    }
+
+    /// Checks that the backend can be sent alerts, i.e. that its webhook URL can be requested.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the backend is valid, or an error message if not.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Notifier::Slack { webhook_url } | Notifier::Discord { webhook_url } => {
+                http_client::validate_url(webhook_url)
+            }
+            Notifier::Telegram { bot_token, .. } if bot_token.is_empty() => {
+                Err("The bot token of a Telegram notifier must not be empty".to_string())
+            }
+            Notifier::Telegram { .. } => Ok(()),
+        }
+    }
+}
+
+/// `NotifyEvent` is an enumeration that represents the outcomes of the runs of a job its notifiers are alerted about.
+///
+/// # Variants
+///
+/// * `Failure` - Represents a run that failed, timed out or was killed.
+/// * `Success` - Represents a run that succeeded.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyEvent {
+    Failure,
+    Success,
+}
+
+impl FromStr for NotifyEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "failure" => Ok(Self::Failure),
+            "success" => Ok(Self::Success),
+            _ => Err(format!(
+                "Invalid notification event `{s}`, expected `failure` or `success`"
+            )),
+        }
+    }
+}
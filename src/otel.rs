@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::history::{RunRecord, RunStatus};
+use crate::http_client;
+
+/// How often the spans and the metrics are exported by default.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The upper bounds of the buckets of the duration histogram of the runs, in seconds.
+const DURATION_BOUNDS: [f64; 8] = [0.1, 1.0, 10.0, 60.0, 300.0, 900.0, 3600.0, 21600.0];
+
+/// `OtelConfig` is a structure that represents how the service exports its telemetry to an OpenTelemetry collector, declared as an
+/// `[otel]` table, when cronus is built with the `otel` feature.
+///
+/// # Fields
+///
+/// * `endpoint` - A string that represents the base URL of the OTLP/HTTP receiver of the collector, e.g. `http://localhost:4318`. The
+///   spans are posted to `<endpoint>/v1/traces` and the metrics to `<endpoint>/v1/metrics`, encoded in JSON.
+/// * `service_name` - A string that represents the `service.name` the telemetry is reported under. It is `cronus` by default.
+/// * `interval` - A `Duration` that represents how often the spans gathered since the last export, and the metrics, are exported. It is
+///   60 seconds by default.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct OtelConfig {
+    pub endpoint: String,
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    #[serde(default = "default_interval", with = "humantime_serde")]
+    pub interval: Duration,
+}
+
+/// Returns the service name the telemetry is reported under by default.
+///
+/// # Returns
+///
+/// * `String` - Returns `cronus`.
+fn default_service_name() -> String {
+    "cronus".to_string()
+}
+
+/// Returns how often the telemetry is exported by default.
+///
+/// # Returns
+///
+/// * `Duration` - Returns `DEFAULT_INTERVAL`.
+fn default_interval() -> Duration {
+    DEFAULT_INTERVAL
+}
+
+impl OtelConfig {
+    /// Checks that the telemetry can be exported, i.e. that the endpoint can be requested and the interval is not zero.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the configuration is valid, or an error message if not.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval.is_zero() {
+            return Err("The export interval must not be zero".to_string());
+        }
+        http_client::validate_url(&self.endpoint)
+    }
+
+    /// Exports spans to the collector.
+    ///
+    /// # Arguments
+    ///
+    /// * `spans` - A vector of `Value` instances that represent the spans, built with `run_span`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the collector accepted the spans, or an error message if not.
+    pub fn export_spans(&self, spans: Vec<Value>) -> Result<(), String> {
+        let body = json!({
+            "resourceSpans": [{
+                "resource": self.resource(),
+                "scopeSpans": [{ "scope": { "name": "cronus" }, "spans": spans }],
+            }],
+        });
+        http_client::post_json(&self.url("traces"), &body).map(|_| ())
+    }
+
+    /// Exports the metrics of the service to the collector.
+    ///
+    /// # Arguments
+    ///
+    /// * `gauges` - A reference to the `SchedulerGauges` sampled from the scheduler.
+    /// * `runs` - A reference to the `RunMetrics` accumulated since the service started.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the collector accepted the metrics, or an error message if not.
+    pub fn export_metrics(
+        &self,
+        gauges: &SchedulerGauges,
+        runs: &RunMetrics,
+    ) -> Result<(), String> {
+        let now = nanos(Utc::now().timestamp_nanos_opt().unwrap_or_default() as u128);
+        let start = nanos(runs.since);
+        let metrics = json!([
+            {
+                "name": "cronus.jobs",
+                "description": "Jobs registered on the service",
+                "unit": "{job}",
+                "gauge": { "dataPoints": [
+                    gauge(&now, gauges.active_jobs, json!([attribute("state", json!({ "stringValue": "active" }))])),
+                    gauge(&now, gauges.paused_jobs, json!([attribute("state", json!({ "stringValue": "paused" }))])),
+                ]},
+            },
+            {
+                "name": "cronus.runs.in_flight",
+                "description": "Runs of the jobs in flight",
+                "unit": "{run}",
+                "gauge": { "dataPoints": [gauge(&now, gauges.runs_in_flight, json!([]))] },
+            },
+            {
+                "name": "cronus.runs",
+                "description": "Runs of the jobs recorded since the service started",
+                "unit": "{run}",
+                "sum": {
+                    "aggregationTemporality": 2,
+                    "isMonotonic": true,
+                    "dataPoints": runs.outcomes.iter().map(|(outcome, count)| json!({
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                        "asInt": count.to_string(),
+                        "attributes": [attribute("outcome", json!({ "stringValue": outcome }))],
+                    })).collect::<Vec<_>>(),
+                },
+            },
+            {
+                "name": "cronus.run.duration",
+                "description": "Duration of the runs of the jobs recorded since the service started",
+                "unit": "s",
+                "histogram": {
+                    "aggregationTemporality": 2,
+                    "dataPoints": [{
+                        "startTimeUnixNano": start,
+                        "timeUnixNano": now,
+                        "count": runs.durations.iter().sum::<u64>().to_string(),
+                        "sum": runs.duration_sum,
+                        "bucketCounts": runs.durations.iter().map(u64::to_string).collect::<Vec<_>>(),
+                        "explicitBounds": DURATION_BOUNDS,
+                    }],
+                },
+            },
+        ]);
+        let body = json!({
+            "resourceMetrics": [{
+                "resource": self.resource(),
+                "scopeMetrics": [{ "scope": { "name": "cronus" }, "metrics": metrics }],
+            }],
+        });
+        http_client::post_json(&self.url("metrics"), &body).map(|_| ())
+    }
+
+    /// Returns the URL a signal is posted to.
+    ///
+    /// # Arguments
+    ///
+    /// * `signal` - A string that represents the signal, `traces` or `metrics`.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns the URL of the signal on the OTLP/HTTP receiver.
+    fn url(&self, signal: &str) -> String {
+        format!("{}/v1/{signal}", self.endpoint.trim_end_matches('/'))
+    }
+
+    /// Returns the resource the telemetry is reported for.
+    ///
+    /// # Returns
+    ///
+    /// * `Value` - Returns the OTLP resource, carrying the `service.name` of the configuration.
+    fn resource(&self) -> Value {
+        json!({ "attributes": [attribute("service.name", json!({ "stringValue": self.service_name }))] })
+    }
+}
+
+/// `SchedulerGauges` is a structure that represents the state of the scheduler sampled when the metrics are exported.
+///
+/// # Fields
+///
+/// * `active_jobs` - A `u64` that represents the number of jobs registered and not paused.
+/// * `paused_jobs` - A `u64` that represents the number of jobs registered and paused.
+/// * `runs_in_flight` - A `u64` that represents the number of runs of all jobs in flight.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct SchedulerGauges {
+    pub active_jobs: u64,
+    pub paused_jobs: u64,
+    pub runs_in_flight: u64,
+}
+
+/// `RunMetrics` is a structure that accumulates the runs of the jobs since the service started, exported as cumulative metrics.
+///
+/// # Fields
+///
+/// * `since` - A `u128` that represents the time the metrics are accumulated from, in nanoseconds since the Unix epoch.
+/// * `outcomes` - A `BTreeMap<&'static str, u64>` that represents the number of runs by outcome: `succeeded`, `failed` or `skipped`.
+/// * `durations` - A vector of `u64` that represents the number of recorded runs in every bucket of `DURATION_BOUNDS`, and beyond.
+/// * `duration_sum` - An `f64` that represents the total duration of the recorded runs, in seconds.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RunMetrics {
+    since: u128,
+    outcomes: BTreeMap<&'static str, u64>,
+    durations: Vec<u64>,
+    duration_sum: f64,
+}
+
+impl Default for RunMetrics {
+    fn default() -> Self {
+        Self {
+            since: Utc::now().timestamp_nanos_opt().unwrap_or_default() as u128,
+            outcomes: BTreeMap::new(),
+            durations: vec![0; DURATION_BOUNDS.len() + 1],
+            duration_sum: 0.0,
+        }
+    }
+}
+
+impl RunMetrics {
+    /// Accounts a run recorded in the run history.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - A reference to the `RunRecord` of the run.
+    pub fn record(&mut self, record: &RunRecord) {
+        *self.outcomes.entry(outcome(record.status)).or_default() += 1;
+        let seconds = record.duration.as_secs_f64();
+        let bucket = DURATION_BOUNDS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(DURATION_BOUNDS.len());
+        self.durations[bucket] += 1;
+        self.duration_sum += seconds;
+    }
+
+    /// Accounts a tick of a job that was not executed.
+    pub fn skip(&mut self) {
+        *self.outcomes.entry("skipped").or_default() += 1;
+    }
+}
+
+/// Builds the span of a run of a job.
+///
+/// The span lasts from the time the run started to the time it finished, and carries the id and the name of the job, the id of the run, the outcome of the
+/// run, its exit code if it has one, and its error message as the status of the span if it failed.
+///
+/// # Arguments
+///
+/// * `record` - A reference to the `RunRecord` of the run.
+/// * `job_name` - An `Option<&str>` that represents the name of the job. It is `None` if the job has no name.
+///
+/// # Returns
+///
+/// * `Value` - Returns the OTLP span of the run, in a trace of its own.
+pub fn run_span(record: &RunRecord, job_name: Option<&str>) -> Value {
+    let start = record.started_at as u128 * 1_000_000_000;
+    let mut attributes = vec![
+        attribute("cronus.job.id", json!({ "stringValue": record.job_id })),
+        attribute(
+            "cronus.run.status",
+            json!({ "stringValue": outcome(record.status) }),
+        ),
+        attribute(
+            "cronus.run.scheduled_at",
+            json!({ "intValue": record.scheduled_at.to_string() }),
+        ),
+        attribute(
+            "cronus.run.delay_ms",
+            json!({ "intValue": record.delay.as_millis().to_string() }),
+        ),
+    ];
+    if let Some(name) = job_name {
+        attributes.push(attribute("cronus.job.name", json!({ "stringValue": name })));
+    }
+    if let Some(run_id) = record.run_id {
+        attributes.push(attribute(
+            "cronus.run.id",
+            json!({ "stringValue": run_id.to_string() }),
+        ));
+    }
+    if let Some(exit_code) = record.exit_code {
+        attributes.push(attribute(
+            "process.exit.code",
+            json!({ "intValue": exit_code.to_string() }),
+        ));
+    }
+    let status = match record.status {
+        RunStatus::Failed => {
+            json!({ "code": 2, "message": record.error.as_deref().unwrap_or_default() })
+        }
+        RunStatus::Succeeded => json!({ "code": 1 }),
+    };
+    json!({
+        "traceId": random_id::<16>(),
+        "spanId": random_id::<8>(),
+        "name": format!("run {}", job_name.unwrap_or(&record.job_id)),
+        "kind": 1,
+        "startTimeUnixNano": nanos(start),
+        "endTimeUnixNano": nanos(start + record.duration.as_nanos()),
+        "attributes": attributes,
+        "status": status,
+    })
+}
+
+/// Returns the outcome of a run as reported in the telemetry.
+///
+/// # Arguments
+///
+/// * `status` - A `RunStatus` that represents the outcome of the run.
+///
+/// # Returns
+///
+/// * `&'static str` - Returns `succeeded` or `failed`.
+fn outcome(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Succeeded => "succeeded",
+        RunStatus::Failed => "failed",
+    }
+}
+
+/// Builds a data point of a gauge.
+///
+/// # Arguments
+///
+/// * `time` - A string that represents the time the gauge was sampled, see `nanos`.
+/// * `value` - A `u64` that represents the value of the gauge.
+/// * `attributes` - A `Value` that represents the attributes of the data point.
+///
+/// # Returns
+///
+/// * `Value` - Returns the OTLP data point.
+fn gauge(time: &str, value: u64, attributes: Value) -> Value {
+    json!({ "timeUnixNano": time, "asInt": value.to_string(), "attributes": attributes })
+}
+
+/// Builds an OTLP attribute.
+///
+/// # Arguments
+///
+/// * `key` - A string that represents the key of the attribute.
+/// * `value` - A `Value` that represents the OTLP value of the attribute, e.g. `{"stringValue": "..."}`.
+///
+/// # Returns
+///
+/// * `Value` - Returns the attribute.
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+/// Formats a time in nanoseconds since the Unix epoch the way OTLP encodes 64-bit integers in JSON, as a string.
+///
+/// # Arguments
+///
+/// * `nanos` - A `u128` that represents the time in nanoseconds since the Unix epoch.
+///
+/// # Returns
+///
+/// * `String` - Returns the time as a decimal string.
+fn nanos(nanos: u128) -> String {
+    nanos.to_string()
+}
+
+/// Draws a random trace or span id.
+///
+/// # Returns
+///
+/// * `String` - Returns `N` random bytes, hex encoded.
+fn random_id<const N: usize>() -> String {
+    let mut bytes = [0u8; N];
+    rand::thread_rng().fill(&mut bytes[..]);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
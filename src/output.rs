@@ -0,0 +1,320 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::job::RunId;
+use crate::secret::mask;
+
+/// The number of lines of output kept for a run, beyond which the oldest lines are dropped.
+pub const OUTPUT_LINES: usize = 1000;
+
+/// How long the output of a command is still read once the command has exited, for the processes it spawned that keep its pipes open.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often reading the output of a command that has exited checks whether the pipes are closed.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// `OutputChunk` is a structure that represents the lines of output of a run read from a position on.
+///
+/// # Fields
+///
+/// * `started_at` - A `u64` that represents the time the run started in Unix timestamp.
+/// * `run_id` - An `Option<RunId>` that represents the id of the run. It is `None` until the run starts.
+/// * `lines` - A vector of strings that represent the lines of output, without their line endings.
+/// * `next` - A `u64` that represents the position of the line following the last one, to read the output from next.
+/// * `finished` - A `bool` that represents whether the run is over, in which case no more lines will follow.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct OutputChunk {
+    pub started_at: u64,
+    #[serde(default)]
+    pub run_id: Option<RunId>,
+    pub lines: Vec<String>,
+    pub next: u64,
+    pub finished: bool,
+}
+
+/// `OutputPatterns` is a structure that represents the patterns the output of a run is matched against to tell whether the run succeeded,
+/// for tools that exit with `0` whatever happened.
+///
+/// # Fields
+///
+/// * `success` - An `Option<Regex>` that represents the pattern a line of output must match for the run to succeed. It is `None` if the run does not have to print anything.
+/// * `failure` - An `Option<Regex>` that represents the pattern no line of output may match for the run to succeed. It is `None` if the run may print anything.
+#[derive(Debug, Default, Clone)]
+pub struct OutputPatterns {
+    pub success: Option<Regex>,
+    pub failure: Option<Regex>,
+}
+
+/// `OutputLog` is a structure that holds the output of a run.
+///
+/// # Fields
+///
+/// * `started_at` - A `u64` that represents the time the run started in Unix timestamp, `0` until it starts.
+/// * `run_id` - An `Option<RunId>` that represents the id of the run. It is `None` until the run starts.
+/// * `lines` - A `VecDeque<String>` that represents the last `OUTPUT_LINES` lines of output.
+/// * `dropped` - A `u64` that represents the number of lines dropped, i.e. the position of the first line kept.
+/// * `finished` - A `bool` that represents whether the run is over.
+/// * `result` - An `Option<Value>` that represents the value the run returned, e.g. by a Rhai script. It is `None` if the run returned nothing.
+/// * `exit_code` - An `Option<i32>` that represents the exit code of the process of the run, e.g. of a command. It is `None` if the run has no process, or its process was killed by a signal.
+/// * `masked` - A vector of strings that represent the values of the secrets of the run, masked in every line of output.
+/// * `patterns` - An `OutputPatterns` that represents the patterns the lines of output appended are matched against.
+/// * `succeeded` - A `bool` that represents whether a line of output matched the success pattern.
+/// * `failed` - An `Option<String>` that represents the first line of output that matched the failure pattern. It is `None` if no line did.
+#[derive(Debug, Default)]
+struct OutputLog {
+    started_at: u64,
+    run_id: Option<RunId>,
+    lines: VecDeque<String>,
+    dropped: u64,
+    finished: bool,
+    result: Option<Value>,
+    exit_code: Option<i32>,
+    masked: Vec<String>,
+    patterns: OutputPatterns,
+    succeeded: bool,
+    failed: Option<String>,
+}
+
+/// `RunOutput` is a structure that captures the output of a run while it is in flight, so it can be followed with `cronus tail`.
+///
+/// The output of a command is its standard output and standard error, and the output of a Rhai script is what it prints. Clones of a
+/// run output share its lines, so the run holds one clone while the scheduler keeps another one to read.
+#[derive(Debug, Default, Clone)]
+pub struct RunOutput(Arc<Mutex<OutputLog>>);
+
+impl RunOutput {
+    /// Marks the run as started now.
+    ///
+    /// # Arguments
+    ///
+    /// * `run_id` - A `RunId` that represents the id of the run.
+    pub fn start(&self, run_id: RunId) {
+        let mut log = self.lock();
+        log.started_at = Utc::now().timestamp() as u64;
+        log.run_id = Some(run_id);
+    }
+
+    /// Appends a line to the output, dropping the oldest line once `OUTPUT_LINES` lines are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - A string that represents the line, without its line ending.
+    pub fn push(&self, line: String) {
+        let mut log = self.lock();
+        if log.lines.len() == OUTPUT_LINES {
+            log.lines.pop_front();
+            log.dropped += 1;
+        }
+        let line = mask(&line, &log.masked);
+        if log
+            .patterns
+            .success
+            .as_ref()
+            .is_some_and(|success| success.is_match(&line))
+        {
+            log.succeeded = true;
+        }
+        if log.failed.is_none()
+            && log
+                .patterns
+                .failure
+                .as_ref()
+                .is_some_and(|failure| failure.is_match(&line))
+        {
+            log.failed = Some(line.clone());
+        }
+        log.lines.push_back(line);
+    }
+
+    /// Matches the lines of output appended from now on against patterns, until `verdict` is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - An `OutputPatterns` that represents the patterns.
+    pub fn expect(&self, patterns: OutputPatterns) {
+        let mut log = self.lock();
+        log.patterns = patterns;
+        log.succeeded = false;
+        log.failed = None;
+    }
+
+    /// Tells whether the lines of output appended since `expect` was called match its patterns, and stops matching them.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if no line matched the failure pattern and a line matched the success pattern, if any,
+    ///   or an error message with the first line that matched the failure pattern, or telling that no line matched the success pattern.
+    pub fn verdict(&self) -> Result<(), String> {
+        let mut log = self.lock();
+        let patterns = std::mem::take(&mut log.patterns);
+        match (&log.failed, &patterns) {
+            (
+                Some(line),
+                OutputPatterns {
+                    failure: Some(failure),
+                    ..
+                },
+            ) => Err(format!(
+                "Output matched the failure regex `{failure}`: {line}"
+            )),
+            (
+                _,
+                OutputPatterns {
+                    success: Some(success),
+                    ..
+                },
+            ) if !log.succeeded => Err(format!(
+                "Output did not match the success regex `{success}`"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Masks the values of secrets in the lines of output appended from now on, and in the texts redacted with `redact`.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - An iterator of strings that represent the values of the secrets.
+    pub fn mask(&self, values: impl IntoIterator<Item = String>) {
+        self.lock().masked.extend(values);
+    }
+
+    /// Masks the values of the secrets of the run in a text, e.g. the error of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - A string that represents the text.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns the text, with every value masked with `mask` replaced with `secret::MASK`.
+    pub fn redact(&self, text: &str) -> String {
+        mask(text, &self.lock().masked)
+    }
+
+    /// Marks the run as over.
+    pub fn finish(&self) {
+        self.lock().finished = true;
+    }
+
+    /// Sets the value the run returned, recorded in the run history along with the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - A `Value` that represents the value returned, e.g. a count or a status.
+    pub fn set_result(&self, result: Value) {
+        self.lock().result = Some(result);
+    }
+
+    /// Returns the value the run returned.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Value>` - Returns the value, or `None` if the run returned nothing.
+    pub fn result(&self) -> Option<Value> {
+        self.lock().result.clone()
+    }
+
+    /// Sets the exit code of the process of the run, recorded in the run history along with the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `exit_code` - An `i32` that represents the exit code, e.g. of a command or a container.
+    pub fn set_exit_code(&self, exit_code: i32) {
+        self.lock().exit_code = Some(exit_code);
+    }
+
+    /// Returns the exit code of the process of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<i32>` - Returns the exit code, or `None` if the run has no process, or its process was killed by a signal.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.lock().exit_code
+    }
+
+    /// Returns the id of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<RunId>` - Returns the id of the run, or `None` if the run has not started yet.
+    pub fn run_id(&self) -> Option<RunId> {
+        self.lock().run_id
+    }
+
+    /// Reads the lines of output from a position on.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - A `u64` that represents the position of the first line to be read, `0` for the first line of the run. Lines already
+    ///   dropped are skipped.
+    ///
+    /// # Returns
+    ///
+    /// * `OutputChunk` - Returns the lines kept from the position on.
+    pub fn read_from(&self, from: u64) -> OutputChunk {
+        let log = self.lock();
+        let skip = from.saturating_sub(log.dropped) as usize;
+        OutputChunk {
+            started_at: log.started_at,
+            run_id: log.run_id,
+            lines: log.lines.iter().skip(skip).cloned().collect(),
+            next: log.dropped + log.lines.len() as u64,
+            finished: log.finished,
+        }
+    }
+
+    /// Starts reading the lines of a stream, e.g. the standard output of a command, into the output.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The stream to be read, until its end.
+    ///
+    /// # Returns
+    ///
+    /// * `JoinHandle<()>` - Returns the thread reading the stream, to be drained with `drain` once the command has exited.
+    pub fn capture(&self, stream: impl Read + Send + 'static) -> JoinHandle<()> {
+        let output = self.clone();
+        thread::spawn(move || {
+            let mut stream = BufReader::new(stream);
+            let mut line = Vec::new();
+            while matches!(stream.read_until(b'\n', &mut line), Ok(read) if read > 0) {
+                let text = String::from_utf8_lossy(&line);
+                output.push(text.trim_end_matches(['\n', '\r']).to_string());
+                line.clear();
+            }
+        })
+    }
+
+    /// Waits for the threads capturing the streams of a command that has exited to read what is left.
+    ///
+    /// The threads are given up to `DRAIN_TIMEOUT`, as the processes spawned by the command may keep its pipes open. The threads still
+    /// reading then are left behind, and end with these processes.
+    ///
+    /// # Arguments
+    ///
+    /// * `readers` - A vector of `JoinHandle` instances that represent the threads capturing the streams.
+    pub fn drain(readers: Vec<JoinHandle<()>>) {
+        let deadline = Instant::now() + DRAIN_TIMEOUT;
+        while readers.iter().any(|reader| !reader.is_finished()) && Instant::now() < deadline {
+            thread::sleep(DRAIN_POLL_INTERVAL);
+        }
+    }
+
+    /// Locks the log of the output.
+    ///
+    /// # Returns
+    ///
+    /// * `MutexGuard<OutputLog>` - Returns the guard of the log, even if a thread panicked while holding it.
+    fn lock(&self) -> MutexGuard<'_, OutputLog> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use serde::{Deserialize, Serialize};
+
+use crate::credentials::RunAs;
+use crate::job::{Job, JobIdentity, KillSwitch, Tick, RUN_KILLED};
+use crate::limits::ResourceLimits;
+use crate::output::RunOutput;
+use crate::workflow::{StepOutcome, StepStatus};
+
+/// `JobStep` is a structure that represents a single step of a pipeline job.
+///
+/// # Fields
+///
+/// * `name` - A string that represents the name of the step, unique within the pipeline.
+/// * `job` - A `Job` that represents what the step runs.
+/// * `continue_on_failure` - A `bool` that represents whether the steps after this one still run if it fails. The pipeline run fails
+///   all the same.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct JobStep {
+    pub name: String,
+    pub job: Job,
+    #[serde(default)]
+    pub continue_on_failure: bool,
+}
+
+/// Checks that steps form a valid pipeline: there is at least one step, and step names are unique.
+///
+/// # Arguments
+///
+/// * `steps` - A slice of `JobStep` instances that represent the steps of the pipeline.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the steps are valid, or an error message describing the problem.
+pub fn validate_steps(steps: &[JobStep]) -> Result<(), String> {
+    if steps.is_empty() {
+        return Err("Pipeline has no steps".to_string());
+    }
+    let mut names = HashSet::new();
+    match steps.iter().find(|step| !names.insert(step.name.as_str())) {
+        Some(step) => Err(format!("Step `{}` is declared more than once", step.name)),
+        None => Ok(()),
+    }
+}
+
+/// Runs the steps of a pipeline one after the other.
+///
+/// Once a step fails, the steps after it are skipped, unless the step continues on failure. Once the run is killed, the steps that
+/// have not started yet are skipped. Every step writes its lines to the output of the run, preceded by a line telling which step
+/// starts and followed by a line telling how it ended.
+///
+/// # Arguments
+///
+/// * `steps` - A slice of `JobStep` instances that represent the steps of the pipeline.
+/// * `tick` - A `Tick` that represents the tick the pipeline runs for, passed on to every step.
+/// * `identity` - A reference to the `JobIdentity` of the pipeline job, passed on to every step.
+/// * `limits` - A `ResourceLimits` that represents the limits applied to the processes spawned by the steps.
+/// * `run_as` - A reference to the `RunAs` that represents the user and group the processes spawned by the steps run as.
+/// * `kill_switch` - A reference to the `KillSwitch` of the pipeline run.
+/// * `output` - A reference to the `RunOutput` of the pipeline run.
+///
+/// # Returns
+///
+/// * `Vec<StepOutcome>` - Returns the outcome of every step, in order.
+pub fn run_steps(
+    steps: &[JobStep],
+    tick: Tick,
+    identity: &JobIdentity,
+    limits: ResourceLimits,
+    run_as: &RunAs,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+) -> Vec<StepOutcome> {
+    let mut outcomes = Vec::with_capacity(steps.len());
+    let mut stopped_by: Option<&str> = None;
+    for step in steps {
+        let skipped = |error: String| StepOutcome {
+            step: step.name.clone(),
+            status: StepStatus::Skipped,
+            attempts: 0,
+            error: Some(error),
+        };
+        let outcome = match stopped_by {
+            Some(name) => skipped(format!("Step `{name}` did not succeed")),
+            None if kill_switch.is_killed() => skipped(RUN_KILLED.to_string()),
+            None => {
+                output.push(format!("==> step `{}` started", step.name));
+                let outcome = run_step(step, tick, identity, limits, run_as, kill_switch, output);
+                match &outcome.error {
+                    None => output.push(format!("==> step `{}` succeeded", step.name)),
+                    Some(e) => output.push(format!("==> step `{}` failed: {e}", step.name)),
+                }
+                outcome
+            }
+        };
+        if outcome.status == StepStatus::Failed && !step.continue_on_failure {
+            stopped_by = Some(&step.name);
+        }
+        outcomes.push(outcome);
+    }
+    outcomes
+}
+
+/// Runs a single step of a pipeline.
+///
+/// # Arguments
+///
+/// * `step` - A reference to the `JobStep` to be run.
+/// * `tick` - A `Tick` that represents the tick the pipeline runs for.
+/// * `identity` - A reference to the `JobIdentity` of the pipeline job.
+/// * `limits` - A `ResourceLimits` that represents the limits applied to the processes spawned by the step.
+/// * `run_as` - A reference to the `RunAs` that represents the user and group the processes spawned by the step run as.
+/// * `kill_switch` - A reference to the `KillSwitch` of the pipeline run.
+/// * `output` - A reference to the `RunOutput` of the pipeline run.
+///
+/// # Returns
+///
+/// * `StepOutcome` - Returns the outcome of the step.
+fn run_step(
+    step: &JobStep,
+    tick: Tick,
+    identity: &JobIdentity,
+    limits: ResourceLimits,
+    run_as: &RunAs,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+) -> StepOutcome {
+    let business = step
+        .job
+        .clone()
+        .to_business(identity.clone(), limits, run_as.clone());
+    let result = catch_unwind(AssertUnwindSafe(|| business(tick, kill_switch, output)))
+        .unwrap_or_else(|_| Err("Step panicked".to_string()));
+    StepOutcome {
+        step: step.name.clone(),
+        status: match result {
+            Ok(()) => StepStatus::Succeeded,
+            Err(_) => StepStatus::Failed,
+        },
+        attempts: 1,
+        error: result.err(),
+    }
+}
@@ -0,0 +1,342 @@
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::job::{Job, JobOptions};
+use crate::sandbox::Sandbox;
+
+/// The file names of the shells a policy may deny, as the executable of a command job.
+const SHELLS: [&str; 9] = [
+    "sh", "bash", "dash", "ash", "zsh", "ksh", "fish", "csh", "tcsh",
+];
+
+/// `JobPolicy` is a structure that represents which jobs the service accepts, declared as a `[policy]` table.
+///
+/// # Fields
+///
+/// * `commands` - An `Option<Vec<String>>` that represents the executables command jobs may run, as paths or globs where `*` matches
+///   any part of a file name and `?` any character of it, e.g. `/usr/local/bin/*`. It is `None` if command jobs may run any executable.
+///   While it is set, the jobs whose executables cannot be told, i.e. container, SSH, SQL and WebAssembly jobs, are denied.
+/// * `scripts` - A `bool` that represents whether Rhai and Lua script jobs are accepted, `true` by default.
+/// * `shells` - A `bool` that represents whether command jobs may run a shell, e.g. `/bin/sh -c ...`, `true` by default.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct JobPolicy {
+    #[serde(default)]
+    pub commands: Option<Vec<String>>,
+    #[serde(default = "permitted")]
+    pub scripts: bool,
+    #[serde(default = "permitted")]
+    pub shells: bool,
+}
+
+impl Default for JobPolicy {
+    fn default() -> Self {
+        Self {
+            commands: None,
+            scripts: true,
+            shells: true,
+        }
+    }
+}
+
+/// Returns the default of the switches of a policy, which permit what they control.
+fn permitted() -> bool {
+    true
+}
+
+impl JobPolicy {
+    /// Checks that the policy accepts a job, along with every hook of it.
+    ///
+    /// The full sandbox is denied while the policy restricts commands or shells, as its scripts may run any process.
+    ///
+    /// # Arguments
+    ///
+    /// * `job` - A reference to the `Job` to be checked.
+    /// * `options` - A reference to the `JobOptions` of the job, declaring its hooks and its sandbox.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the job is accepted, or an error message describing why it is denied.
+    pub fn check(&self, job: &Job, options: &JobOptions) -> Result<(), String> {
+        if options.sandbox == Sandbox::Full && (self.commands.is_some() || !self.shells) {
+            return Err(
+                "The full sandbox is denied by the policy, as its scripts may run any process"
+                    .to_string(),
+            );
+        }
+        self.check_payload(job)?;
+        options.hooks().try_for_each(|(name, hook)| {
+            self.check_payload(hook)
+                .map_err(|e| format!("Hook `{name}`: {e}"))
+        })
+    }
+
+    /// Checks that the policy accepts what a job runs, as it does every step of a workflow or a pipeline job.
+    ///
+    /// # Arguments
+    ///
+    /// * `job` - A reference to the `Job` to be checked.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the job is accepted, or an error message describing why it is denied.
+    fn check_payload(&self, job: &Job) -> Result<(), String> {
+        match job {
+            Job::Command(cmd_path, ..) => self.check_command(cmd_path),
+            Job::RhaiScript(_) | Job::RhaiScriptFile(_) if !self.scripts => {
+                Err("Rhai script jobs are denied by the policy".to_string())
+            }
+            #[cfg(feature = "lua")]
+            Job::Lua(_) | Job::LuaFile(_) if !self.scripts => {
+                Err("Lua script jobs are denied by the policy".to_string())
+            }
+            Job::Workflow(steps) => steps.iter().try_for_each(|step| {
+                self.check_payload(&step.job)
+                    .map_err(|e| format!("Step `{}`: {e}", step.name))
+            }),
+            Job::Pipeline(steps) => steps.iter().try_for_each(|step| {
+                self.check_payload(&step.job)
+                    .map_err(|e| format!("Step `{}`: {e}", step.name))
+            }),
+            Job::Container { .. } if self.commands.is_some() => {
+                Err(Self::unrestricted("Container"))
+            }
+            #[cfg(feature = "ssh")]
+            Job::Ssh { .. } if self.commands.is_some() => Err(Self::unrestricted("SSH")),
+            #[cfg(feature = "sql")]
+            Job::Sql { .. } if self.commands.is_some() => Err(Self::unrestricted("SQL")),
+            #[cfg(feature = "wasm")]
+            Job::Wasm { .. } if self.commands.is_some() => Err(Self::unrestricted("WebAssembly")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Describes why a kind of job whose executables cannot be told is denied while the policy restricts commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - A string that represents the kind of job, e.g. `Container`.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns the error message.
+    fn unrestricted(kind: &str) -> String {
+        format!("{kind} jobs are denied by the policy, as it restricts the commands jobs may run")
+    }
+
+    /// Checks that the policy accepts the executable of a command job.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd_path` - A reference to the `Path` of the executable, as written in the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the executable is accepted, or an error message describing why it is denied.
+    fn check_command(&self, cmd_path: &Path) -> Result<(), String> {
+        let shown = cmd_path.display();
+        if !self.shells
+            && cmd_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| SHELLS.contains(&name))
+        {
+            return Err(format!("Shell `{shown}` is denied by the policy"));
+        }
+        let Some(commands) = &self.commands else {
+            return Ok(());
+        };
+        // A `..` could climb out of an allowed directory, e.g. `/usr/local/bin/../../../tmp/payload`.
+        if cmd_path
+            .components()
+            .any(|component| component == Component::ParentDir)
+        {
+            return Err(format!("Command `{shown}` is denied by the policy"));
+        }
+        let cmd = cmd_path.to_string_lossy();
+        if commands.iter().any(|pattern| glob_match(pattern, &cmd)) {
+            Ok(())
+        } else {
+            Err(format!("Command `{shown}` is not allowed by the policy"))
+        }
+    }
+}
+
+/// Matches a path against a glob, where `*` matches any run of characters but `/`, and `?` any character but `/`.
+///
+/// # Arguments
+///
+/// * `pattern` - A string that represents the glob.
+/// * `path` - A string that represents the path.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` if the whole path matches the glob.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<_> = pattern.chars().collect();
+    let path: Vec<_> = path.chars().collect();
+    let (mut p, mut s) = (0, 0);
+    // The position of the last `*` of the pattern and of the path where it started matching, to backtrack to.
+    let mut star: Option<(usize, usize)> = None;
+    while s < path.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, s));
+                p += 1;
+            }
+            Some('?') if path[s] != '/' => {
+                p += 1;
+                s += 1;
+            }
+            Some(&c) if c != '?' && c == path[s] => {
+                p += 1;
+                s += 1;
+            }
+            _ => match star {
+                Some((star_p, star_s)) if path[star_s] != '/' => {
+                    star = Some((star_p, star_s + 1));
+                    p = star_p + 1;
+                    s = star_s + 1;
+                }
+                _ => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::pipeline::JobStep;
+    use crate::workflow::WorkflowStep;
+
+    fn command(path: &str, args: &[&str]) -> Job {
+        Job::Command(
+            PathBuf::from(path),
+            args.iter().map(|arg| arg.to_string()).collect(),
+            None,
+        )
+    }
+
+    fn restricted() -> JobPolicy {
+        JobPolicy {
+            commands: Some(vec!["/usr/local/bin/*".to_string()]),
+            ..JobPolicy::default()
+        }
+    }
+
+    #[test]
+    fn default_policy_accepts_any_job() {
+        let policy = JobPolicy::default();
+        let options = JobOptions {
+            sandbox: Sandbox::Full,
+            ..JobOptions::default()
+        };
+        assert!(policy
+            .check(&command("/bin/sh", &["-c", "true"]), &options)
+            .is_ok());
+        assert!(policy
+            .check(&Job::RhaiScript("1".to_string()), &options)
+            .is_ok());
+    }
+
+    #[test]
+    fn commands_are_matched_against_the_globs() {
+        let policy = restricted();
+        let options = JobOptions::default();
+        assert!(policy
+            .check(&command("/usr/local/bin/backup", &[]), &options)
+            .is_ok());
+        assert!(policy
+            .check(&command("/usr/bin/rm", &[]), &options)
+            .is_err());
+        assert!(policy
+            .check(&command("/usr/local/bin/../../bin/rm", &[]), &options)
+            .is_err());
+    }
+
+    #[test]
+    fn shells_and_scripts_can_be_denied() {
+        let policy = JobPolicy {
+            scripts: false,
+            shells: false,
+            ..JobPolicy::default()
+        };
+        let options = JobOptions::default();
+        assert!(policy.check(&command("/bin/bash", &[]), &options).is_err());
+        assert!(policy.check(&command("/bin/true", &[]), &options).is_ok());
+        assert!(policy
+            .check(&Job::RhaiScript("1".to_string()), &options)
+            .is_err());
+    }
+
+    #[test]
+    fn hooks_are_checked() {
+        let policy = restricted();
+        let options = JobOptions {
+            on_failure: Some(command("/usr/bin/curl", &[])),
+            ..JobOptions::default()
+        };
+        let err = policy
+            .check(&command("/usr/local/bin/backup", &[]), &options)
+            .unwrap_err();
+        assert!(err.starts_with("Hook `on_failure`"), "{err}");
+    }
+
+    #[test]
+    fn workflow_and_pipeline_steps_are_checked() {
+        let policy = restricted();
+        let options = JobOptions::default();
+        let workflow = Job::Workflow(vec![WorkflowStep {
+            name: "fetch".to_string(),
+            after: vec![],
+            job: command("/usr/bin/wget", &[]),
+            retries: 0,
+            retry_delay: None,
+        }]);
+        let err = policy.check(&workflow, &options).unwrap_err();
+        assert!(err.starts_with("Step `fetch`"), "{err}");
+        let pipeline = Job::Pipeline(vec![JobStep {
+            name: "backup".to_string(),
+            job: command("/usr/local/bin/backup", &[]),
+            continue_on_failure: false,
+        }]);
+        assert!(policy.check(&pipeline, &options).is_ok());
+    }
+
+    #[test]
+    fn other_job_kinds_are_denied_while_commands_are_restricted() {
+        let container = Job::Container {
+            image: "alpine".to_string(),
+            cmd: vec![],
+            env: BTreeMap::new(),
+            volumes: vec![],
+        };
+        assert!(restricted()
+            .check(&container, &JobOptions::default())
+            .is_err());
+        assert!(JobPolicy::default()
+            .check(&container, &JobOptions::default())
+            .is_ok());
+    }
+
+    #[test]
+    fn full_sandbox_is_denied_while_commands_or_shells_are_restricted() {
+        let script = Job::RhaiScript("exec(\"rm\", [])".to_string());
+        let options = JobOptions {
+            sandbox: Sandbox::Full,
+            ..JobOptions::default()
+        };
+        assert!(restricted().check(&script, &options).is_err());
+        let no_shells = JobPolicy {
+            shells: false,
+            ..JobPolicy::default()
+        };
+        assert!(no_shells.check(&script, &options).is_err());
+        assert!(restricted().check(&script, &JobOptions::default()).is_ok());
+    }
+}
@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{self, AtomicU64, AtomicUsize};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tokio::sync::{oneshot, Semaphore};
+use tokio::task::spawn_blocking;
+
+use crate::job::Priority;
+
+/// The number of workers of a pool when the service is not given a `--max-parallel`.
+pub const DEFAULT_WORKERS: usize = 64;
+
+/// `Work` is a work item run by a worker of a `WorkerPool`.
+type Work = Box<dyn FnOnce() + Send>;
+
+/// `QueuedWork` is a structure that represents a work item waiting in the queue of a `WorkerPool`.
+///
+/// Queued work items are ordered by priority, then by the order they were queued in, so the heap pops the oldest item of the highest priority first.
+///
+/// # Fields
+///
+/// * `priority` - A `Priority` that represents the priority of the work item.
+/// * `seq` - A `u64` that represents the order the work item was queued in.
+/// * `work` - A `Work` that represents the work item itself.
+struct QueuedWork {
+    priority: Priority,
+    seq: u64,
+    work: Work,
+}
+
+impl PartialEq for QueuedWork {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedWork {}
+
+impl PartialOrd for QueuedWork {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedWork {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// `WorkerPool` is a structure that represents a fixed pool of workers running the work items of a prioritized queue.
+///
+/// Every worker runs one work item at a time on a blocking thread, so the pool bounds the number of work items in flight at once.
+/// Under load, work items queue up and are run highest priority first, in the order they were queued within a priority.
+///
+/// # Fields
+///
+/// * `queue` - A `Mutex<BinaryHeap<QueuedWork>>` that represents the work items waiting for a worker.
+/// * `queued` - A `Semaphore` that holds a permit for every work item in the queue, which the workers wait on.
+/// * `idle` - An `AtomicUsize` that represents the number of workers waiting for a work item.
+/// * `seq` - An `AtomicU64` that represents the order of the next queued work item.
+pub struct WorkerPool {
+    queue: Mutex<BinaryHeap<QueuedWork>>,
+    queued: Semaphore,
+    idle: AtomicUsize,
+    seq: AtomicU64,
+}
+
+impl WorkerPool {
+    /// Starts a new `WorkerPool`.
+    ///
+    /// # Arguments
+    ///
+    /// * `workers` - A `usize` that represents the number of workers of the pool.
+    ///
+    /// # Returns
+    ///
+    /// * `Arc<WorkerPool>` - Returns the pool, whose workers run until the runtime shuts down.
+    pub fn start(workers: usize) -> Arc<Self> {
+        let pool = Arc::new(Self {
+            queue: Mutex::new(BinaryHeap::new()),
+            queued: Semaphore::new(0),
+            idle: AtomicUsize::new(0),
+            seq: AtomicU64::new(0),
+        });
+        for _ in 0..workers {
+            tokio::spawn(Self::work(pool.clone()));
+        }
+        pool
+    }
+
+    /// Runs the work items of the queue, one at a time, for as long as the pool lives.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - An `Arc<WorkerPool>` that represents the pool the worker belongs to.
+    async fn work(pool: Arc<Self>) {
+        loop {
+            pool.idle.fetch_add(1, atomic::Ordering::SeqCst);
+            let permit = pool.queued.acquire().await;
+            pool.idle.fetch_sub(1, atomic::Ordering::SeqCst);
+            let Ok(permit) = permit else {
+                return;
+            };
+            permit.forget();
+            let queued = pool
+                .queue
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pop();
+            if let Some(queued) = queued {
+                _ = spawn_blocking(queued.work).await;
+            }
+        }
+    }
+
+    /// Checks whether every worker of the pool is busy.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if a work item submitted now would have to wait in the queue.
+    pub fn is_saturated(&self) -> bool {
+        self.idle.load(atomic::Ordering::SeqCst) <= self.queued.available_permits()
+    }
+
+    /// Runs a function on a worker of the pool, waiting in the queue until a worker is free.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - A `Priority` that represents where the function is queued.
+    /// * `f` - A function to be run on a blocking thread.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<T>` - Returns what the function returned, or `None` if the function panicked.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        priority: Priority,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> Option<T> {
+        let (sender, receiver) = oneshot::channel();
+        let work: Work = Box::new(move || {
+            _ = sender.send(f());
+        });
+        self.queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(QueuedWork {
+                priority,
+                seq: self.seq.fetch_add(1, atomic::Ordering::SeqCst),
+                work,
+            });
+        self.queued.add_permits(1);
+        receiver.await.ok()
+    }
+}
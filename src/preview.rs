@@ -0,0 +1,100 @@
+use std::iter::successors;
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::job::{Job, JobOptions, Trigger};
+use crate::lint::{LintFinding, LintSeverity};
+use crate::projection::following_tick;
+use crate::recurrence::{following_occurrence, parse_rrule};
+use crate::schedule::parse_schedule;
+use crate::tz::parse_timezone;
+
+/// The number of upcoming runs a preview projects.
+const PREVIEWED_RUNS: usize = 5;
+
+/// `JobPreview` is a structure that represents what adding a job would do, as checked by the service without registering the job, see
+/// `cronus add --dry-run`.
+///
+/// # Fields
+///
+/// * `job_id` - An `Option<String>` that represents the id of the job the key of the job is registered for, which would be updated in
+///   place. It is `None` if a job would be created.
+/// * `created` - A `bool` that represents whether a job would be created, rather than a job updated in place.
+/// * `duplicate_of` - An `Option<String>` that represents the id of the registered job with the same schedule and payload, see
+///   `StoredJob::duplicates`. It is `None` if the job duplicates no job.
+/// * `trigger` - A `Trigger` that represents what would make the job run.
+/// * `job` - A `Job` that represents what the job would run.
+/// * `options` - A `JobOptions` that represents the execution settings the job would be registered with, e.g. in the namespace of the
+///   client.
+/// * `upcoming_runs` - A vector of Unix timestamps that represent the first runs of the job on its schedule, see `upcoming_runs`.
+/// * `findings` - A vector of `LintFinding` instances that represent the problems the service finds with the job, e.g. a command
+///   missing on its host, see `lint_job`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct JobPreview {
+    pub job_id: Option<String>,
+    pub created: bool,
+    pub duplicate_of: Option<String>,
+    #[serde(flatten)]
+    pub trigger: Trigger,
+    pub job: Job,
+    pub options: JobOptions,
+    pub upcoming_runs: Vec<u64>,
+    pub findings: Vec<LintFinding>,
+}
+
+impl JobPreview {
+    /// Checks whether the service would refuse to run the job, as one of its findings is an error.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if a finding is an error, or `false` if the job is only warned about, if at all.
+    pub fn rejected(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.severity == LintSeverity::Error)
+    }
+}
+
+/// Projects the first runs of a job on its schedule: the ticks of its cron expression in its timezone, the occurrences of its
+/// recurrence or its times of the sun.
+///
+/// # Arguments
+///
+/// * `trigger` - A reference to the `Trigger` of the job.
+/// * `options` - A reference to the `JobOptions` of the job, holding its timezone.
+/// * `now` - A `DateTime<Utc>` that represents the time the runs are projected from, exclusive.
+///
+/// # Returns
+///
+/// * `Vec<u64>` - Returns the Unix timestamps of the first `PREVIEWED_RUNS` runs, or fewer if the schedule ends before. It is empty if
+///   the job does not run on a schedule, or its schedule or its timezone is invalid.
+pub fn upcoming_runs(trigger: &Trigger, options: &JobOptions, now: DateTime<Utc>) -> Vec<u64> {
+    let following: Box<dyn Fn(DateTime<Utc>) -> Option<DateTime<Utc>>> = match trigger {
+        Trigger::Cron(cron) => {
+            let Ok(schedule) = parse_schedule(cron) else {
+                return Vec::new();
+            };
+            match options.timezone.as_deref().map(parse_timezone) {
+                Some(Ok(tz)) => Box::new(move |at| following_tick(&schedule, &tz, at)),
+                Some(Err(_)) => return Vec::new(),
+                None => Box::new(move |at| following_tick(&schedule, &Local, at)),
+            }
+        }
+        Trigger::Rrule(rule) => {
+            let Ok(rule) = parse_rrule(rule) else {
+                return Vec::new();
+            };
+            Box::new(move |at| following_occurrence(&rule, at))
+        }
+        Trigger::Sun(sun) => {
+            let sun = *sun;
+            Box::new(move |at| sun.following(at))
+        }
+        Trigger::After(_) | Trigger::Event(_) | Trigger::Manual => return Vec::new(),
+    };
+    successors(following(now), |at| following(*at))
+        .take(PREVIEWED_RUNS)
+        .map(|at| at.timestamp() as u64)
+        .collect()
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use cron::Schedule;
+
+/// The number of upcoming ticks inspected when computing the shortest interval of a schedule.
+const PROJECTED_TICKS: usize = 100;
+
+/// Projects the ticks of a schedule within a time window.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule` to be projected.
+/// * `from` - A `DateTime<Local>` that represents the start of the window, exclusive.
+/// * `until` - A `DateTime<Local>` that represents the end of the window, inclusive.
+///
+/// # Returns
+///
+/// * `Vec<DateTime<Local>>` - Returns the ticks of the schedule within the window, in chronological order.
+pub fn project(
+    schedule: &Schedule,
+    from: DateTime<Local>,
+    until: DateTime<Local>,
+) -> Vec<DateTime<Local>> {
+    schedule
+        .after(&from)
+        .take_while(|tick| *tick <= until)
+        .collect()
+}
+
+/// Projects the ticks of a schedule within a time window, evaluating the schedule in a timezone.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule` to be projected.
+/// * `timezone` - A reference to the timezone the schedule is evaluated in.
+/// * `from` - A `DateTime<Utc>` that represents the start of the window, exclusive.
+/// * `until` - A `DateTime<Utc>` that represents the end of the window, inclusive.
+///
+/// # Returns
+///
+/// * `Vec<DateTime<Utc>>` - Returns the ticks of the schedule within the window, in chronological order.
+pub fn project_in<Z: TimeZone>(
+    schedule: &Schedule,
+    timezone: &Z,
+    from: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<DateTime<Utc>> {
+    schedule
+        .after(&from.with_timezone(timezone))
+        .map(|tick| tick.with_timezone(&Utc))
+        .take_while(|tick| *tick <= until)
+        .collect()
+}
+
+/// Finds the tick of a schedule following a time.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule` to be inspected.
+/// * `timezone` - A reference to the timezone the schedule is evaluated in.
+/// * `at` - A `DateTime<Utc>` that represents the time to find the following tick of.
+///
+/// # Returns
+///
+/// * `Option<DateTime<Utc>>` - Returns the first tick of the schedule after `at`, or `None` if the schedule never fires again.
+pub fn following_tick<Z: TimeZone>(
+    schedule: &Schedule,
+    timezone: &Z,
+    at: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    schedule
+        .after(&at.with_timezone(timezone))
+        .next()
+        .map(|tick| tick.with_timezone(&Utc))
+}
+
+/// Finds the period of a schedule a time falls in.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule` to be inspected.
+/// * `timezone` - A reference to the timezone the schedule is evaluated in.
+/// * `at` - A `DateTime<Utc>` that represents the time to find the period of.
+///
+/// # Returns
+///
+/// * `Option<(DateTime<Utc>, DateTime<Utc>)>` - Returns the last two ticks of the schedule up to `at`, oldest first, or `None` if the schedule has not fired twice by then.
+pub fn period_at<Z: TimeZone>(
+    schedule: &Schedule,
+    timezone: &Z,
+    at: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut ticks = schedule
+        .after(&(at + chrono::Duration::milliseconds(1)).with_timezone(timezone))
+        .rev();
+    let end = ticks.next()?.with_timezone(&Utc);
+    let start = ticks.next()?.with_timezone(&Utc);
+    Some((start, end))
+}
+
+/// Computes the shortest interval between the upcoming ticks of a schedule.
+///
+/// # Arguments
+///
+/// * `schedule` - A reference to the `Schedule` to be inspected.
+///
+/// # Returns
+///
+/// * `Option<Duration>` - Returns the shortest interval, or `None` if the schedule fires less than twice.
+pub fn shortest_interval(schedule: &Schedule) -> Option<Duration> {
+    let ticks: Vec<_> = schedule.upcoming(Local).take(PROJECTED_TICKS).collect();
+    ticks
+        .windows(2)
+        .filter_map(|pair| (pair[1] - pair[0]).to_std().ok())
+        .min()
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+
+use serde::{Deserialize, Serialize};
+
+use crate::job::JobOptions;
+use crate::namespace::DEFAULT_NAMESPACE;
+
+/// `JobQuota` is a structure that represents how many jobs the service accepts, in all, per namespace and per cost center, declared as
+/// a `[quota]` table of the configuration file, so one team's automation cannot fill a service shared by many:
+///
+/// ```toml
+/// [quota]
+/// max_jobs = 500
+///
+/// [quota.namespaces]
+/// data = 200
+///
+/// [quota.cost_centers]
+/// data = 100
+/// web = 50
+/// ```
+///
+/// # Fields
+///
+/// * `max_jobs` - An `Option<NonZeroUsize>` that represents how many jobs the service may have registered at once. It is `None` if the
+///   jobs are not counted.
+/// * `namespaces` - A `BTreeMap<String, usize>` that represents how many jobs of a namespace may be registered at once, by namespace,
+///   see `Scope`. A namespace it does not name is only limited by `max_jobs`.
+/// * `cost_centers` - A `BTreeMap<String, usize>` that represents how many jobs charged to a cost center may be registered at once, by
+///   cost center. A cost center it does not name is only limited by `max_jobs`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct JobQuota {
+    #[serde(default)]
+    pub max_jobs: Option<NonZeroUsize>,
+    #[serde(default)]
+    pub namespaces: BTreeMap<String, usize>,
+    #[serde(default)]
+    pub cost_centers: BTreeMap<String, usize>,
+}
+
+impl JobQuota {
+    /// Checks a job can be registered next to the jobs registered already.
+    ///
+    /// # Arguments
+    ///
+    /// * `registered` - An iterator over the `JobOptions` of the jobs registered, leaving out the job the new one replaces, if any.
+    /// * `options` - A reference to the `JobOptions` of the job, holding its namespace and its cost center.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the job fits in the quota, or an error message telling which limit it exceeds.
+    pub fn check<'a>(
+        &self,
+        registered: impl IntoIterator<Item = &'a JobOptions>,
+        options: &JobOptions,
+    ) -> Result<(), String> {
+        let namespace = options.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE);
+        let namespace_limit = self.namespaces.get(namespace);
+        let cost_center_limit = options.cost_center.as_deref().and_then(|cost_center| {
+            self.cost_centers
+                .get(cost_center)
+                .map(|limit| (cost_center, *limit))
+        });
+        if self.max_jobs.is_none() && namespace_limit.is_none() && cost_center_limit.is_none() {
+            return Ok(());
+        }
+        let (mut total, mut namespaced, mut charged) = (0, 0, 0);
+        for registered in registered {
+            total += 1;
+            if registered.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE) == namespace {
+                namespaced += 1;
+            }
+            if registered.cost_center.is_some() && registered.cost_center == options.cost_center {
+                charged += 1;
+            }
+        }
+        if let Some(max) = self.max_jobs.filter(|max| total >= max.get()) {
+            return Err(format!(
+                "The service has {total} jobs, the maximum of its quota is {max}"
+            ));
+        }
+        if let Some(max) = namespace_limit.filter(|max| namespaced >= **max) {
+            return Err(format!(
+                "Namespace `{namespace}` has {namespaced} jobs, the maximum of its quota is {max}"
+            ));
+        }
+        match cost_center_limit {
+            Some((cost_center, max)) if charged >= max => Err(format!(
+                "Cost center `{cost_center}` has {charged} jobs, the maximum of its quota is {max}"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
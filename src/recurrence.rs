@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use rrule::{Frequency, RRuleSet, Tz};
+
+use crate::CronusResult;
+
+/// Parses an RFC 5545 recurrence, a `DTSTART` line followed by `RRULE`, `RDATE` and `EXDATE` lines, e.g.
+/// `DTSTART;TZID=Europe/Paris:20260101T090000` and `RRULE:FREQ=MONTHLY;BYDAY=2TU` for 9:00 on the second Tuesday of every month.
+///
+/// The lines may also be separated by spaces, so a recurrence fits on the command line. The occurrences are evaluated in the timezone
+/// of `DTSTART`, in UTC if it ends with `Z`, or else in local time. Recurrences more frequent than hourly are refused, as their
+/// occurrences are counted from `DTSTART`: a cron expression, with `--seconds` if need be, schedules them instead.
+///
+/// # Arguments
+///
+/// * `rule` - A string that represents the recurrence.
+///
+/// # Returns
+///
+/// * `CronusResult<RRuleSet>` - Returns a `CronusResult` that contains the parsed recurrence on success or an error.
+pub fn parse_rrule(rule: &str) -> CronusResult<RRuleSet> {
+    let lines: Vec<_> = rule.split_whitespace().collect();
+    if !lines.iter().any(|line| line.starts_with("DTSTART")) {
+        return Err(format!(
+            "Invalid RRULE `{rule}`: a DTSTART is required, e.g. DTSTART;TZID=Europe/Paris:20260101T090000"
+        )
+        .into());
+    }
+    let recurrence: RRuleSet = lines
+        .join("\n")
+        .parse()
+        .map_err(|e| format!("Invalid RRULE `{rule}`: {e}"))?;
+    if recurrence
+        .get_rrule()
+        .iter()
+        .any(|rrule| rrule.get_freq() > Frequency::Hourly)
+    {
+        return Err(format!(
+            "Invalid RRULE `{rule}`: recurrences more frequent than hourly are not supported, use a cron expression instead"
+        )
+        .into());
+    }
+    Ok(recurrence)
+}
+
+/// Finds the occurrence of a recurrence following a time.
+///
+/// # Arguments
+///
+/// * `rule` - A reference to the `RRuleSet` to be inspected.
+/// * `at` - A `DateTime<Utc>` that represents the time to find the following occurrence of.
+///
+/// # Returns
+///
+/// * `Option<DateTime<Utc>>` - Returns the first occurrence after `at`, or `None` if the recurrence is over.
+pub fn following_occurrence(rule: &RRuleSet, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let after = (at + chrono::Duration::seconds(1)).with_timezone(&Tz::UTC);
+    rule.clone()
+        .after(after)
+        .all(1)
+        .dates
+        .first()
+        .map(|occurrence| occurrence.with_timezone(&Utc))
+}
+
+/// Finds the period of a recurrence a time falls in.
+///
+/// # Arguments
+///
+/// * `rule` - A reference to the `RRuleSet` to be inspected.
+/// * `at` - A `DateTime<Utc>` that represents the time to find the period of.
+///
+/// # Returns
+///
+/// * `Option<(DateTime<Utc>, DateTime<Utc>)>` - Returns the last two occurrences up to `at`, oldest first, or `None` if the recurrence
+///   has not occurred twice by then.
+pub fn period_at(rule: &RRuleSet, at: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (start, end) = rule
+        .into_iter()
+        .map(|occurrence| occurrence.with_timezone(&Utc))
+        .take_while(|occurrence| *occurrence <= at)
+        .fold((None, None), |(_, end), occurrence| (end, Some(occurrence)));
+    start.zip(end)
+}
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::Engine;
+use serde::{Deserialize, Serialize};
+
+/// `Sandbox` is an enumeration that represents what a Rhai or Lua script, or a WebAssembly module, of a job is allowed to do, so untrusted
+/// scripts can be scheduled safely.
+///
+/// # Variants
+///
+/// * `Strict` - Represents a script confined to small values, e.g. strings up to 64 KiB, which cannot import modules nor reach beyond
+///   its run.
+/// * `Standard` - Represents a script confined to large values, e.g. strings up to 16 MiB, which can import modules from files, read
+///   environment variables and make HTTP requests.
+/// * `Full` - Represents a trusted script with no size limits, which can also access files and run processes.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Sandbox {
+    Strict,
+    #[default]
+    Standard,
+    Full,
+}
+
+impl FromStr for Sandbox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "standard" => Ok(Self::Standard),
+            "full" => Ok(Self::Full),
+            _ => Err(format!(
+                "Invalid sandbox `{s}`, expected `strict`, `standard` or `full`"
+            )),
+        }
+    }
+}
+
+impl Sandbox {
+    /// Confines a Rhai engine to the sandbox, limiting the sizes of the values of its scripts and the modules they can import.
+    ///
+    /// The helpers a sandbox exposes depend on the run, so they are registered by the job itself, see `Sandbox::exposes_environment`
+    /// and `Sandbox::exposes_host`.
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - A mutable reference to the `Engine` to be confined.
+    pub fn confine(self, engine: &mut Engine) {
+        match self {
+            Sandbox::Strict => {
+                engine
+                    .set_max_string_size(64 * 1024)
+                    .set_max_array_size(10_000)
+                    .set_max_map_size(10_000)
+                    .set_max_call_levels(32)
+                    .set_max_expr_depths(64, 32)
+                    .set_module_resolver(DummyModuleResolver::new());
+            }
+            Sandbox::Standard => {
+                engine
+                    .set_max_string_size(16 * 1024 * 1024)
+                    .set_max_array_size(1_000_000)
+                    .set_max_map_size(1_000_000);
+            }
+            Sandbox::Full => {}
+        }
+    }
+
+    /// Returns the memory a Lua script or a WebAssembly module of the sandbox may use.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<usize>` - Returns 16 MiB for `strict`, 256 MiB for `standard`, or `None` for `full`, whose memory is not limited.
+    pub fn memory_limit(self) -> Option<usize> {
+        match self {
+            Sandbox::Strict => Some(16 * 1024 * 1024),
+            Sandbox::Standard => Some(256 * 1024 * 1024),
+            Sandbox::Full => None,
+        }
+    }
+
+    /// Creates a Lua state confined to the sandbox, limiting the libraries and the memory of its scripts, see `Sandbox::memory_limit`.
+    ///
+    /// A `strict` script gets the `coroutine`, `table`, `string`, `utf8` and `math` libraries and cannot load files. A `standard` script
+    /// can also load modules with `require`. A `full` script gets every safe library, including `io` and `os`.
+    ///
+    /// # Returns
+    ///
+    /// * `mlua::Result<mlua::Lua>` - Returns the Lua state, or an error if it cannot be created.
+    #[cfg(feature = "lua")]
+    pub fn lua(self) -> mlua::Result<mlua::Lua> {
+        use mlua::{Lua, LuaOptions, StdLib};
+
+        let confined =
+            StdLib::COROUTINE | StdLib::TABLE | StdLib::STRING | StdLib::UTF8 | StdLib::MATH;
+        let libraries = match self {
+            Sandbox::Strict => confined,
+            Sandbox::Standard => confined | StdLib::PACKAGE,
+            Sandbox::Full => StdLib::ALL_SAFE,
+        };
+        let lua = Lua::new_with(libraries, LuaOptions::default())?;
+        if let Some(memory_limit) = self.memory_limit() {
+            lua.set_memory_limit(memory_limit)?;
+        }
+        if self == Sandbox::Strict {
+            lua.globals().set("dofile", mlua::Nil)?;
+            lua.globals().set("loadfile", mlua::Nil)?;
+        }
+        Ok(lua)
+    }
+
+    /// Checks whether the scripts of the sandbox can read the environment variables of the service and make HTTP requests.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` for every sandbox but `Strict`.
+    pub fn exposes_environment(self) -> bool {
+        self != Sandbox::Strict
+    }
+
+    /// Checks whether the scripts of the sandbox can access files and run processes.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` for the `Full` sandbox.
+    pub fn exposes_host(self) -> bool {
+        self == Sandbox::Full
+    }
+}
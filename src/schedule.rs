@@ -0,0 +1,248 @@
+use std::str::FromStr;
+
+use chrono::Weekday;
+use cron::Schedule;
+
+use crate::CronusResult;
+
+/// The cron macro of the jobs running once, when the service starts, like the `@reboot` lines of a crontab.
+pub const REBOOT: &str = "@reboot";
+
+/// The number of fields of a crontab expression, which has no seconds field.
+const CRONTAB_FIELDS: usize = 5;
+
+/// The cron macros accepted in place of a cron expression, with the expression each one stands for.
+const MACROS: [(&str, &str); 7] = [
+    ("@yearly", "0 0 0 1 1 *"),
+    ("@annually", "0 0 0 1 1 *"),
+    ("@monthly", "0 0 0 1 * *"),
+    ("@weekly", "0 0 0 * * Sun"),
+    ("@daily", "0 0 0 * * *"),
+    ("@midnight", "0 0 0 * * *"),
+    ("@hourly", "0 0 * * * *"),
+];
+
+/// Checks whether a cron expression is the `@reboot` macro.
+///
+/// # Arguments
+///
+/// * `cron` - A string that represents the cron expression.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` if the job runs once when the service starts instead of on a schedule.
+pub fn is_reboot(cron: &str) -> bool {
+    cron.trim() == REBOOT
+}
+
+/// Checks whether a cron expression has a seconds field, i.e. whether it has the 6 or 7 fields of the job scheduler instead of the 5
+/// fields of crontab.
+///
+/// # Arguments
+///
+/// * `cron` - A string that represents the cron expression.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` if the expression starts with a seconds field, and `false` if it is a crontab expression or a macro.
+pub fn has_seconds(cron: &str) -> bool {
+    !cron.trim().starts_with('@') && cron.split_whitespace().count() > CRONTAB_FIELDS
+}
+
+/// Translates a cron expression into the expression the job scheduler runs.
+///
+/// A crontab expression of 5 fields, e.g. `*/5 * * * *`, fires at second 0, an expression of 6 or 7 fields starts with a seconds
+/// field and is run as is, and the `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` macros are expanded, along with their
+/// `@midnight` and `@annually` aliases.
+///
+/// # Arguments
+///
+/// * `cron` - A string that represents the cron expression, or a macro.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - Returns the translated expression, or an error message if the expression is a macro that is not a
+///   schedule, i.e. `@reboot`, or an unknown macro.
+pub fn translate(cron: &str) -> Result<String, String> {
+    let cron = cron.trim();
+    if !cron.starts_with('@') {
+        if cron.split_whitespace().count() == CRONTAB_FIELDS {
+            return Ok(format!("0 {cron}"));
+        }
+        return Ok(cron.to_string());
+    }
+    if is_reboot(cron) {
+        return Err(format!(
+            "`{REBOOT}` runs the job when the service starts, it is not a schedule"
+        ));
+    }
+    MACROS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(cron))
+        .map(|(_, expression)| expression.to_string())
+        .ok_or_else(|| {
+            format!(
+                "Unknown cron macro `{cron}`, expected @yearly, @annually, @monthly, @weekly, @daily, @midnight, @hourly or {REBOOT}"
+            )
+        })
+}
+
+/// Parses a cron expression into a `Schedule`, see `translate`.
+///
+/// # Arguments
+///
+/// * `cron` - A string that represents the cron expression.
+///
+/// # Returns
+///
+/// * `CronusResult<Schedule>` - Returns a `CronusResult` that contains the parsed `Schedule` on success or an error.
+pub fn parse_schedule(cron: &str) -> CronusResult<Schedule> {
+    let expression = translate(cron)?;
+    Schedule::from_str(&expression)
+        .map_err(|e| format!("Invalid cron expression `{cron}`: {e}").into())
+}
+
+/// Checks that a cron expression given on the command line has the precision it is declared with: the 5 fields of crontab, or a
+/// seconds field when the precision of a second is asked for with `--seconds`.
+///
+/// # Arguments
+///
+/// * `cron` - A string that represents the cron expression.
+/// * `seconds` - A `bool` that represents whether the expression is declared with a seconds field.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the expression matches its precision, or an error message if not. Macros have no
+///   precision to match.
+pub fn validate_precision(cron: &str, seconds: bool) -> Result<(), String> {
+    if cron.trim().starts_with('@') || has_seconds(cron) == seconds {
+        return Ok(());
+    }
+    if seconds {
+        Err(format!(
+            "Invalid cron expression `{cron}`: --seconds expects a seconds field, e.g. `*/30 * * * * *`"
+        ))
+    } else {
+        Err(format!(
+            "Invalid cron expression `{cron}`: it has a seconds field, add --seconds to schedule with the precision of a second, or give the 5 fields of crontab"
+        ))
+    }
+}
+
+/// Checks a cron expression given to a job, which may also be `@reboot`.
+///
+/// # Arguments
+///
+/// * `cron` - A string that represents the cron expression.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the job can run on the expression, or an error message describing why not.
+pub fn validate(cron: &str) -> Result<(), String> {
+    if is_reboot(cron) {
+        return Ok(());
+    }
+    parse_schedule(cron).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// `TimeOfDay` is a structure that represents the time of the day a schedule shorthand fires at, given as `HH:MM`, e.g. `03:30`.
+///
+/// # Fields
+///
+/// * `hour` - A `u32` that represents the hour, from 0 to 23.
+/// * `minute` - A `u32` that represents the minute, from 0 to 59.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct TimeOfDay {
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid time `{s}`, expected HH:MM, e.g. 03:30");
+        let (hour, minute) = s.split_once(':').ok_or_else(invalid)?;
+        let hour = hour
+            .parse()
+            .ok()
+            .filter(|hour| *hour < 24)
+            .ok_or_else(invalid)?;
+        let minute = minute
+            .parse()
+            .ok()
+            .filter(|minute| *minute < 60)
+            .ok_or_else(invalid)?;
+        Ok(Self { hour, minute })
+    }
+}
+
+/// `Weekdays` is a structure that represents the days of the week a weekly schedule shorthand fires on, given as their names
+/// separated by commas, e.g. `mon` or `mon,wed,fri`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Weekdays(pub Vec<Weekday>);
+
+impl FromStr for Weekdays {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|day| {
+                day.trim().parse().map_err(|_| {
+                    format!("Invalid day of the week `{day}`, expected mon, tue, wed, thu, fri, sat or sun")
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// `Shorthand` is an enumeration that represents a schedule given on the command line without cron syntax, compiled to a cron
+/// expression of the 5 fields of crontab, see `to_cron`.
+///
+/// # Variants
+///
+/// * `Daily(TimeOfDay)` - Represents a schedule firing every day at a time, given with `--daily-at 03:30`.
+/// * `Weekly(Weekdays, TimeOfDay)` - Represents a schedule firing on days of the week at a time, given with `--weekly-on mon --at 07:00`.
+/// * `Monthly(u32, TimeOfDay)` - Represents a schedule firing on a day of the month at a time, given with `--monthly-on 1 --at 06:00`.
+///   Months without that day, e.g. February for 31, are skipped.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Shorthand {
+    Daily(TimeOfDay),
+    Weekly(Weekdays, TimeOfDay),
+    Monthly(u32, TimeOfDay),
+}
+
+impl Shorthand {
+    /// Compiles the shorthand into a cron expression.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns the cron expression of the 5 fields of crontab, e.g. `30 3 * * *` for `--daily-at 03:30`.
+    pub fn to_cron(&self) -> String {
+        match self {
+            Self::Daily(at) => format!("{} {} * * *", at.minute, at.hour),
+            Self::Weekly(Weekdays(days), at) => {
+                let days: Vec<_> = days.iter().map(Weekday::to_string).collect();
+                format!("{} {} * * {}", at.minute, at.hour, days.join(","))
+            }
+            Self::Monthly(day, at) => format!("{} {} {day} * *", at.minute, at.hour),
+        }
+    }
+}
+
+/// Parses the day of the month a monthly schedule shorthand fires on.
+///
+/// # Arguments
+///
+/// * `day` - A string that represents the day of the month, from 1 to 31.
+///
+/// # Returns
+///
+/// * `Result<u32, String>` - Returns the day of the month, or an error message if it is not a day of a month.
+pub fn parse_day_of_month(day: &str) -> Result<u32, String> {
+    day.parse()
+        .ok()
+        .filter(|day| (1..=31).contains(day))
+        .ok_or_else(|| format!("Invalid day of the month `{day}`, expected 1 to 31"))
+}
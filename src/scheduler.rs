@@ -1,21 +1,245 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::error::Error;
 use std::future::Future;
-use std::path::PathBuf;
+use std::io;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
-use chrono::Local;
+use chrono::{DateTime, Local, Utc};
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc::{Receiver, Sender};
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, oneshot, watch, Notify, RwLock};
+use tokio::task::{spawn_blocking, AbortHandle, JoinError, JoinHandle, JoinSet};
+use tokio::time::{interval, sleep, MissedTickBehavior};
 use tokio::try_join;
-use tokio_cron_scheduler::{JobBuilder, JobScheduler};
+use tokio_cron_scheduler::{JobBuilder, JobScheduler, JobToRunAsync};
 use uuid::Uuid;
 
-use crate::command::{Command, CommandResponse};
-use crate::job::{Job, JobInfo};
-use crate::nng_socket::NngIpcSocket;
+use crate::analyze::{analyze_hotspots, AnalyzedJob};
+use crate::apply::{plan_apply, PlanAction};
+use crate::audit::{AuditEntry, AuditLog, Caller};
+use crate::auth::{authorize, scope};
+use crate::backup::{snapshot, BackupConfig};
+use crate::calendar::HolidayPolicy;
+use crate::chain::{find_cycle, ChainLink};
+use crate::chaos::{self, Chaos};
+use crate::command::{Command, CommandClient, CommandResponse, Envelope};
+use crate::config::ServiceConfig;
+use crate::crypto::{fingerprint, StoreKey};
+use crate::dst::{self, DstPolicy, DstRuns};
+use crate::error_code::ErrorCode;
+use crate::events::{Event, EventBus, EventKind};
+use crate::follower::{ServiceSnapshot, FOLLOW_POLL_INTERVAL};
+use crate::health::{Health, ServiceState};
+use crate::history::{RunHistory, RunRecord, RunStatus};
+use crate::hooks::{run_hook, run_hooks, HookStage, JobHooks, SYSTEM_RUNS};
+use crate::http::{serve_health, serve_http};
+use crate::idempotency::IdempotencyLedger;
+use crate::job::{
+    ExpiryAction, Job, JobBusiness, JobIdentity, JobInfo, JobOptions, KillSwitch, OverlapPolicy,
+    RunId, Tick, Trigger, KILL_GRACE_PERIOD,
+};
+use crate::lease::Lease;
+use crate::lint::{lint_job, preflight_job};
+use crate::listing::{page_jobs, sort_jobs, JobFilter, JobSort};
+use crate::manifest::load_job_specs;
+use crate::namespace::{validate_namespace, Scope, ALL_NAMESPACES, DEFAULT_NAMESPACE};
+use crate::nng_socket::{NngIpcContext, NngIpcSocket};
+use crate::notify::{Notifier, NotifyEvent};
+#[cfg(feature = "otel")]
+use crate::otel::{self, OtelConfig, RunMetrics, SchedulerGauges};
+use crate::output::RunOutput;
+use crate::pool::{WorkerPool, DEFAULT_WORKERS};
+use crate::preview::{upcoming_runs, JobPreview};
+use crate::projection::following_tick;
+use crate::quota::JobQuota;
+use crate::recurrence::{self, following_occurrence, parse_rrule};
+use crate::schedule::parse_schedule;
+use crate::state;
+use crate::stats::{build_digest, cost_center_stats, job_stats, AccountedJob, Usage};
+use crate::store::{find_duplicate, JobStore, JournalEntry, StoredJob};
+use crate::template;
+use crate::throttle::{client_of, Throttle, ThrottleConfig};
+use crate::timeline::{build_timeline, TimelinedJob};
+use crate::tz::{check_timezones, parse_timezone, system_tzdb_version};
 use crate::CronusResult;
 
+/// How early the job scheduler may fire a tick, as it compares times by the second.
+const TICK_TOLERANCE: chrono::Duration = chrono::Duration::seconds(1);
+
+/// How long stopping the service waits for the killed runs in flight to be over, a little longer than their processes are given to exit.
+const STOP_TIMEOUT: Duration = Duration::from_secs(KILL_GRACE_PERIOD.as_secs() + 1);
+
+/// How often stopping the service checks whether the killed runs in flight are over.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The longest a job running on a recurrence or on the sun sleeps at once before checking the time of its next occurrence again.
+const RECURRENCE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The times a job running on a recurrence or on the sun runs at, see `follow_recurrence`.
+type Occurrences = Box<dyn Iterator<Item = DateTime<Utc>> + Send>;
+
+/// The number of runs of a job started last whose output is kept, so a run can still be tailed by its id once a run started after it.
+const KEPT_OUTPUTS: usize = 5;
+
+/// How many requests the command server takes at once, each on a context of its own, see `take_requests`.
+const INTAKE_CONTEXTS: usize = 16;
+
+/// How much of a request that is not a valid command is recorded in the audit log, in bytes.
+const MAX_AUDITED_INVALID_BYTES: usize = 1024;
+
+/// A command received by the command server, with the envelope its client presented and the sender its response is routed back with.
+type CommandRequest = (Command, Envelope, oneshot::Sender<CommandResponse>);
+
+/// How often the jobs expected to succeed within a window are checked for being overdue.
+const OVERDUE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the manifest of a service reconciling its jobs is read again and converged to, see `reconcile`.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// `InFlightRun` is a struct that represents a run of a job in flight, so it can be killed or followed on its own.
+///
+/// # Fields
+///
+/// * `run_id` - A `RunId` that represents the id of the run.
+/// * `kill_switch` - A `KillSwitch` that aborts the run.
+/// * `output` - A `RunOutput` that represents the output of the run.
+#[derive(Clone)]
+struct InFlightRun {
+    run_id: RunId,
+    kill_switch: KillSwitch,
+    output: RunOutput,
+}
+
+impl InFlightRun {
+    /// Flips the kill switch of the run.
+    fn kill(&self) {
+        self.kill_switch.kill();
+    }
+}
+
+/// `CronTicks` is a struct that represents the ticks of a job running on a cron schedule, as the job scheduler fires them, cached so
+/// listing jobs reads them without locking the metadata storage of the job scheduler.
+///
+/// # Fields
+///
+/// * `last` - An `Option<u64>` that represents the time the job scheduler last fired the job in Unix timestamp, or `None` if it has not
+///   fired it since the job was registered.
+/// * `next` - An `Option<u64>` that represents the time the job scheduler fires the job next in Unix timestamp, or `None` if the
+///   schedule never fires again.
+#[derive(Clone, Copy)]
+struct CronTicks {
+    last: Option<u64>,
+    next: Option<u64>,
+}
+
+/// `JobEntry` is a struct that represents a job registered on the scheduler.
+///
+/// # Fields
+///
+/// * `trigger` - A `Trigger` that represents what makes the job run.
+/// * `job` - A `Job` that represents the job itself.
+/// * `business` - A `JobBusiness` that represents the business function of the job.
+/// * `hooks` - A `JobHooks` that represents the business functions of the hooks run around the runs of the job.
+/// * `options` - A `JobOptions` that represents the execution settings of the job.
+/// * `paused` - A `bool` that represents whether the executions of the job are suspended.
+/// * `unacknowledged_failure` - A `bool` that represents whether the last run of the job failed and the failure has not been acknowledged yet.
+/// * `running` - An `Arc<Mutex<Vec<InFlightRun>>>` that represents the runs of the job in flight, queued ones included. It is kept when the job is updated.
+/// * `finished` - An `Arc<Notify>` that is notified whenever a run of the job finishes, waking the runs queued behind its concurrency limit. It is kept when the job is updated.
+/// * `output` - An `Arc<Mutex<VecDeque<RunOutput>>>` that represents the outputs of the last `KEPT_OUTPUTS` runs of the job started, the last one last, followed with `TailOutput`. It is empty until the job first runs, and is kept when the job is updated.
+/// * `watched_since` - A `u64` that represents the time the window of `expect_within` is counted from until the job first succeeds, in Unix timestamp: when the job was registered or last resumed.
+/// * `consecutive_failures` - A `u32` that represents the number of runs of the job that failed in a row, compared to `disable_after`. It is reset when the job succeeds or is resumed.
+/// * `recurrence` - An `Option<Arc<AbortHandle>>` that represents the task running a job on an RFC 5545 recurrence, on the sun or on the
+///   ticks of its cron schedule moved by DST transitions, see `follow_recurrence`. It is `None` if the job runs on none of them, or is
+///   only mirrored, and the task is aborted when the job is deleted or replaced.
+/// * `expiry` - An `Option<Arc<AbortHandle>>` that represents the timer expiring the job once its `not_after` time has passed, see
+///   `expire_job`. It is `None` if the job has no `not_after` time, or is only mirrored, and the timer is aborted when the job is deleted
+///   or replaced.
+/// * `ticks` - An `Arc<Mutex<Option<CronTicks>>>` that represents the ticks of the job, updated whenever the job scheduler fires it. It
+///   is `None` if the job does not run on a cron schedule.
+#[derive(Clone)]
+struct JobEntry {
+    trigger: Trigger,
+    job: Job,
+    business: JobBusiness,
+    hooks: JobHooks,
+    options: JobOptions,
+    paused: bool,
+    unacknowledged_failure: bool,
+    running: Arc<Mutex<Vec<InFlightRun>>>,
+    finished: Arc<Notify>,
+    output: Arc<Mutex<VecDeque<RunOutput>>>,
+    watched_since: u64,
+    consecutive_failures: u32,
+    recurrence: Option<Arc<AbortHandle>>,
+    expiry: Option<Arc<AbortHandle>>,
+    ticks: Arc<Mutex<Option<CronTicks>>>,
+}
+
+impl JobEntry {
+    /// Constructs a new `JobEntry` for a job that has not run yet, building its business function from its declaration.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job, handed to its runs along with its name.
+    /// * `trigger` - A `Trigger` that represents what makes the job run.
+    /// * `job` - A `Job` that represents the job itself.
+    /// * `options` - A `JobOptions` that represents the execution settings of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `JobEntry` - Returns the entry of the job, neither paused nor failed.
+    fn new(id: Uuid, trigger: Trigger, job: Job, options: JobOptions) -> Self {
+        let identity = JobIdentity {
+            id: id.to_string(),
+            name: options.name.clone(),
+            secrets: options.secrets.clone(),
+        };
+        Self {
+            trigger,
+            business: job
+                .clone()
+                .to_business(identity.clone(), options.limits(), options.run_as()),
+            hooks: JobHooks::new(&identity, &options),
+            job,
+            options,
+            paused: false,
+            unacknowledged_failure: false,
+            running: Arc::default(),
+            finished: Arc::default(),
+            output: Arc::default(),
+            watched_since: Utc::now().timestamp() as u64,
+            consecutive_failures: 0,
+            recurrence: None,
+            expiry: None,
+            ticks: Arc::default(),
+        }
+    }
+}
+
+/// `BatchChange` is an enumeration that represents a change of a job carried out by a `Batch` command, undone if a later command of
+/// the batch fails, see `undo_batch`.
+///
+/// # Variants
+///
+/// * `Added(Uuid)` - Represents a job added by the batch. It contains the id of the job.
+/// * `Updated(Uuid, JobEntry)` - Represents a job updated by the batch. It contains the id of the job and its entry before the update.
+/// * `Paused(Uuid, JobEntry)` - Represents a job paused or resumed by the batch. It contains the id of the job and its entry before.
+/// * `Deleted(Uuid, JobEntry)` - Represents a job deleted by the batch. It contains the id of the job and its entry, whose runs in
+///   flight are only killed, and whose run history is only forgotten, once the batch succeeds.
+enum BatchChange {
+    Added(Uuid),
+    Updated(Uuid, JobEntry),
+    Paused(Uuid, JobEntry),
+    Deleted(Uuid, JobEntry),
+}
+
 /// `CronusScheduler` is a struct that represents a scheduler for cron jobs.
 ///
 /// It provides methods to parse and handle commands that are related to the management of cron jobs.
@@ -24,49 +248,227 @@ use crate::CronusResult;
 ///
 /// * `cmd_parser` - A `Pin<Box<dyn Future<Output=CronusResult<()>>>>` that represents a future for parsing commands.
 /// * `cmd_handler` - A `Pin<Box<dyn Future<Output=CronusResult<()>>>>` that represents a future for handling commands.
+/// * `http_server` - An `Option<JoinHandle<io::Result<()>>>` that represents the task serving the HTTP API, if enabled. It runs on its own task, as the command parser blocks while waiting for commands.
+/// * `health_server` - An `Option<JoinHandle<io::Result<()>>>` that represents the task serving the health probes, if enabled, from before the service waits for the lease on its job store.
+/// * `lease` - An `Option<(Lease, JoinHandle<Result<(), String>>)>` that represents the lease the service holds on its job store, with the task renewing it. It is `None` if the service is not part of a pair sharing its job store.
 pub struct CronusScheduler {
     cmd_parser: Pin<Box<dyn Future<Output = CronusResult<()>>>>,
     cmd_handler: Pin<Box<dyn Future<Output = CronusResult<()>>>>,
+    http_server: Option<JoinHandle<io::Result<()>>>,
+    health_server: Option<JoinHandle<io::Result<()>>>,
+    lease: Option<(Lease, JoinHandle<Result<(), String>>)>,
 }
 
 impl CronusScheduler {
     /// Constructs a new `CronusScheduler`.
     ///
     /// This function initializes a new `JobScheduler`, starts it, and sets up command receivers.
-    /// It also initializes the command parser and handler.
+    /// It also initializes the command parser and handler, and the HTTP API if an address is given.
+    /// The idempotency keys of the runs that succeeded are kept in a `<name>.keys` ledger file next to the command path.
+    /// The configuration file, if any, is read right away, so an invalid one keeps the service from starting, and the start hooks are run
+    /// before the service accepts commands. The jobs of the job store, if any, are registered again with their ids before the start hooks
+    /// run, so a store that cannot be read or migrated also keeps the service from starting. The state of the service is then backed up
+    /// in the background, if the configuration asks for it, and the jobs expected to succeed within a window are watched.
+    ///
+    /// A follower mirrors the jobs and the run history of its primary from the events of the primary instead, without scheduling them
+    /// or watching them, until it is promoted. In chaos mode, the service injects failures into its ticks, runs and commands.
+    ///
+    /// With a lease window, the service shares its job store with another service, and waits for the lease on the job store, see
+    /// `Lease`, before it starts at all, so only the leader of the pair runs the jobs while the standby waits to take over.
+    ///
+    /// With a manifest, the service keeps converging the jobs with a key to the jobs declared in the manifest, see `reconcile`.
+    ///
+    /// With a health address, the health probes of the service are served right away, see `serve_health`, so a standby is probed
+    /// alive but not ready while it waits for the lease, and a service not ready until it takes commands.
     ///
     /// # Arguments
     ///
     /// * `name` - A string that represents the name of the command path.
     /// * `path` - A `PathBuf` that represents the path of the command.
+    /// * `http_addr` - An `Option<SocketAddr>` that represents the address the HTTP API listens on. The HTTP API is disabled if it is `None`.
+    /// * `tcp_addr` - An `Option<SocketAddr>` that represents the address the command server also listens on over TCP, e.g. for a controller. It is `None` if commands are only accepted on the command path.
+    /// * `health_addr` - An `Option<SocketAddr>` that represents the address the health probes are served on. They are disabled if it is `None`.
+    /// * `max_parallel` - An `Option<NonZeroUsize>` that represents the number of workers running jobs, i.e. the maximum number of runs of all jobs in flight at once. It is `None` for `DEFAULT_WORKERS` workers.
+    /// * `config_file` - An `Option<PathBuf>` that represents the path of the configuration file of the service. It is `None` if the service has no hooks.
+    /// * `store_file` - An `Option<PathBuf>` that represents the path of the job store of the service. It is `None` if the jobs are only kept in memory.
+    /// * `store_key` - An `Option<PathBuf>` that represents the path of the key file the job store and the snapshots of the service are encrypted with. It is `None` if they are written in clear.
+    /// * `lease_window` - An `Option<Duration>` that represents how long the lease on the job store lasts without being renewed, i.e. how long the standby waits for a failed leader. It is `None` if the service does not share its job store.
+    /// * `reconcile` - An `Option<PathBuf>` that represents the path of the job file the jobs of the service are converged to. It is `None` if the jobs are only changed by commands.
+    /// * `follow` - An `Option<String>` that represents the name of the primary the service follows, whose command path is next to its own. It is `None` if the service is a primary itself.
+    /// * `chaos` - An `Option<Chaos>` that represents the failures injected in chaos mode. It is `None` if chaos mode is off.
     ///
     /// # Returns
     ///
     /// * `CronusResult<Self>` - Returns a `CronusResult` that contains a `CronusScheduler` if successful, or an error if not.
-    pub async fn new(name: String, path: PathBuf) -> CronusResult<Self> {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        name: String,
+        path: PathBuf,
+        http_addr: Option<SocketAddr>,
+        tcp_addr: Option<SocketAddr>,
+        health_addr: Option<SocketAddr>,
+        max_parallel: Option<NonZeroUsize>,
+        config_file: Option<PathBuf>,
+        store_file: Option<PathBuf>,
+        store_key: Option<PathBuf>,
+        lease_window: Option<Duration>,
+        reconcile: Option<PathBuf>,
+        follow: Option<String>,
+        chaos: Option<Chaos>,
+    ) -> CronusResult<Self> {
+        if let Some(chaos) = chaos {
+            chaos::enable(chaos);
+        }
+        // init config
+        let config = config_file
+            .as_deref()
+            .map(ServiceConfig::load)
+            .transpose()?
+            .unwrap_or_default();
+
+        // init health probes
+        let health = Arc::new(Health::new(name.clone(), path.clone(), store_file.clone()));
+        let health_server = match health_addr {
+            Some(addr) => {
+                let listener = TcpListener::bind(addr).await?;
+                Some(tokio::spawn(serve_health(listener, health.clone())))
+            }
+            None => None,
+        };
+
+        // wait for the lease on the job store, if shared
+        let lease = match (lease_window, &store_file) {
+            (Some(window), Some(store_file)) => {
+                let lease = Lease::new(store_file, &name, window);
+                health.set_state(ServiceState::Standby);
+                lease.acquire().await?;
+                health.set_state(ServiceState::Starting);
+                let keeper = tokio::spawn(lease.clone().keep());
+                Some((lease, keeper))
+            }
+            (Some(_), None) => return Err("A lease window requires a job store".into()),
+            (None, _) => None,
+        };
+
         // init scheduler
         let scheduler = JobScheduler::new().await?;
         scheduler.start().await?;
 
         // init cmd receiver
         let (cmd_sender, cmd_receiver) = mpsc::channel(1024);
-        let (cmd_res_sender, cmd_res_receiver) = mpsc::channel(1024);
+
+        // init http api
+        let http_server = match http_addr {
+            Some(addr) => {
+                let listener = TcpListener::bind(addr).await?;
+                Some(tokio::spawn(serve_http(
+                    listener,
+                    name.clone(),
+                    path.clone(),
+                )))
+            }
+            None => None,
+        };
+
+        // init history, restore stored jobs and run start hooks
+        let store_key = store_key.as_deref().map(StoreKey::load).transpose()?;
+        let keys = IdempotencyLedger::open(path.join(format!("{name}.keys")))?;
+        state::open(path.join(format!("{name}.state")))?;
+        let history = Arc::new(RwLock::new(RunHistory::new(keys)));
+        let jobs = Arc::new(RwLock::new(HashMap::new()));
+        let events = Arc::new(EventBus::default());
+        let audit = AuditLog::new(path.join(format!("{name}.audit")), store_key.clone());
+        let pool = WorkerPool::start(max_parallel.map_or(DEFAULT_WORKERS, NonZeroUsize::get));
+        let store = match store_file {
+            Some(file) => {
+                let (store, stored) = JobStore::open(file, store_key.clone())?;
+                Self::restore_jobs(
+                    &scheduler,
+                    jobs.clone(),
+                    history.clone(),
+                    events.clone(),
+                    pool.clone(),
+                    stored,
+                )
+                .await?;
+                Some(store)
+            }
+            None => None,
+        };
+        Self::run_hooks(history.clone(), HookStage::Start, &config).await;
+        let (backup_sender, backup_receiver) = watch::channel(config.backup.clone());
+        let (notifiers_sender, _) = watch::channel(config.notifiers.clone());
+        let (throttle_sender, throttle_receiver) = watch::channel(config.throttle);
+        #[cfg(feature = "otel")]
+        let (otel_sender, _) = watch::channel(config.otel.clone());
+        tokio::spawn(Self::back_up_state(
+            name.clone(),
+            jobs.clone(),
+            history.clone(),
+            backup_receiver,
+            store_key.clone(),
+        ));
+        let follower = follow.map(|primary| {
+            let task = tokio::spawn(Self::follow_primary(
+                primary.clone(),
+                path.clone(),
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+            ));
+            (primary, task)
+        });
+        if follower.is_none() {
+            tokio::spawn(Self::watch_overdue_jobs(
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+            ));
+            Self::run_startup_jobs(
+                &scheduler,
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                pool.clone(),
+            )
+            .await;
+        }
 
         // init parser and handler
         let cmd_parser = Box::pin(Self::parse_command(
             path.join(name),
+            tcp_addr,
             cmd_sender,
-            cmd_res_receiver,
+            audit.clone(),
+            throttle_receiver,
         ));
         let cmd_handler = Box::pin(Self::handle_command(
             scheduler,
             cmd_receiver,
-            cmd_res_sender,
+            pool,
+            history,
+            jobs,
+            events,
+            audit,
+            store,
+            config_file,
+            config,
+            reconcile,
+            health,
+            backup_sender,
+            notifiers_sender,
+            throttle_sender,
+            #[cfg(feature = "otel")]
+            otel_sender,
+            follower,
         ));
 
         Ok(Self {
             cmd_parser,
             cmd_handler,
+            http_server,
+            health_server,
+            lease,
         })
     }
 
@@ -75,229 +477,4622 @@ impl CronusScheduler {
     /// This function concurrently runs the command parser and handler of the `CronusScheduler`.
     /// If either the command parser or handler fails, it will return the error.
     /// If both the command parser and handler complete successfully, it will return `CommandResponse::ServiceStopped`.
+    /// The HTTP API and the health probes, if enabled, are served until then, and their failure also stops the service.
+    /// A leader stops as well once it loses the lease on its job store, so it does not run jobs along with the service that took over,
+    /// and releases the lease when it is stopped, so the standby takes over right away.
     ///
     /// # Returns
     ///
     /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::ServiceStopped` if successful, or an error if not.
     pub async fn run(self) -> CronusResult<CommandResponse> {
-        try_join!(self.cmd_parser, self.cmd_handler)?;
+        let service = async { try_join!(self.cmd_parser, self.cmd_handler) };
+        let (lease, keeper) = self.lease.unzip();
+        tokio::select! {
+            res = service => {
+                res?;
+            }
+            res = Self::ended(self.http_server) => {
+                res??;
+            }
+            res = Self::ended(self.health_server) => {
+                res??;
+            }
+            res = Self::ended(keeper) => {
+                res??;
+            }
+        }
+        if let Some(lease) = lease {
+            lease.release()?;
+        }
         Ok(CommandResponse::ServiceStopped)
     }
 
+    /// Waits for a task of the service to end.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - An `Option<JoinHandle<T>>` that represents the task. It is `None` if the service does not run it, and is then waited for forever.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<T, JoinError>` - Returns the output of the task once it ended, or an error if it panicked.
+    async fn ended<T>(task: Option<JoinHandle<T>>) -> Result<T, JoinError> {
+        match task {
+            Some(task) => task.await,
+            None => std::future::pending().await,
+        }
+    }
+
     /// Parses commands received from the command server.
     ///
-    /// This function listens for commands from the command server, converts them from bytes to `Command` objects,
-    /// and sends them to the command sender. If a `Command::StopService` command is received, it stops the service
-    /// and returns. It also sends command responses back to the command server.
+    /// This function takes the requests of the command server on `INTAKE_CONTEXTS` contexts at once, see `take_requests`, so a request
+    /// is received while the requests before it are still being handled, and every response is sent back on the context its request was
+    /// received on. Once a `Command::StopService` command is answered with `ServiceStopped`, it stops the service and returns. The
+    /// clients of every context are throttled together, see `Throttle`, and the command server drops the messages much larger than the
+    /// throttle accepts before they are received, see `ThrottleConfig::recv_max_size`, as the throttle is reloaded.
     ///
     /// # Arguments
     ///
     /// * `cmd_path` - A `PathBuf` that represents the path of the command server.
-    /// * `cmd_sender` - A `Sender<Command>` that is used to send commands to the command handler.
-    /// * `mut cmd_res_receiver` - A `Receiver<CommandResponse>` that is used to receive command responses from the command handler.
+    /// * `tcp_addr` - An `Option<SocketAddr>` that represents the address the command server also listens on over TCP. It is `None` if
+    ///   the command server is only reached on its path.
+    /// * `cmd_sender` - A `Sender<CommandRequest>` that is used to send commands to the command handler, with the token and the namespace the client presented.
+    /// * `audit` - An `AuditLog` that is used to record the management commands.
+    /// * `limits` - A `watch::Receiver<ThrottleConfig>` that is used to receive the throttle of the configuration, as it is reloaded.
     ///
     /// # Returns
     ///
     /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if successful, or an error if not.
     async fn parse_command(
         cmd_path: PathBuf,
-        cmd_sender: Sender<Command>,
-        mut cmd_res_receiver: Receiver<CommandResponse>,
+        tcp_addr: Option<SocketAddr>,
+        cmd_sender: Sender<CommandRequest>,
+        audit: AuditLog,
+        mut limits: watch::Receiver<ThrottleConfig>,
     ) -> CronusResult<()> {
         let cmd_server = NngIpcSocket::new_listen(cmd_path)?;
+        let throttle = Arc::new(Mutex::new(Throttle::default()));
+        if let Some(tcp_addr) = tcp_addr {
+            cmd_server.listen_on(&format!("tcp://{tcp_addr}"))?;
+        }
+        cmd_server.set_recv_max_size(limits.borrow_and_update().recv_max_size())?;
+        let mut intakes = JoinSet::new();
+        for _ in 0..INTAKE_CONTEXTS {
+            intakes.spawn(Self::take_requests(
+                cmd_server.context()?,
+                cmd_sender.clone(),
+                audit.clone(),
+                throttle.clone(),
+                limits.clone(),
+            ));
+        }
+        loop {
+            tokio::select! {
+                // The other contexts are aborted once one of them answered `StopService`, or failed.
+                taken = intakes.join_next() => {
+                    if let Some(taken) = taken {
+                        taken??;
+                    }
+                    return Ok(());
+                }
+                Ok(()) = limits.changed() => {
+                    cmd_server.set_recv_max_size(limits.borrow_and_update().recv_max_size())?;
+                }
+            }
+        }
+    }
+
+    /// Takes the requests of the command server on one of its contexts, one at a time.
+    ///
+    /// This function receives a command on the context, converts it from bytes to a `Command` object and sends it to the command handler
+    /// with a sender of its own, then sends the response it is routed back on the context. Every management command, see
+    /// `Command::is_audited`, is recorded in the audit log with the process it was received from and its response, before the response
+    /// is sent. In chaos mode, some commands are dropped without being handled or answered, as if they were lost.
+    /// A request larger than the throttle accepts, though small enough for the command server to receive it, or sent by a client that
+    /// sent too many requests, is answered with `Throttled` without being handled, see `Throttle::admit`, except for the `PingService` and `StopService` commands, which are never rate limited, so
+    /// the service can always be probed and stopped.
+    /// A request that is not a valid command is answered with an `Error` response, and recorded in the audit log with the beginning
+    /// of the request, while the context goes on taking requests. A command the command handler drops without answering it, e.g. a
+    /// query that panicked, is answered with an `Error` response too, so its client is not left waiting.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - An `NngIpcContext` that represents the context of the command server the requests are taken on.
+    /// * `cmd_sender` - A `Sender<CommandRequest>` that is used to send commands to the command handler.
+    /// * `audit` - An `AuditLog` that is used to record the management commands.
+    /// * `throttle` - An `Arc<Mutex<Throttle>>` that represents the requests every client may still send.
+    /// * `limits` - A `watch::Receiver<ThrottleConfig>` that represents the throttle of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` once a `Command::StopService` command is answered with `ServiceStopped`, or an error
+    ///   message if a request cannot be received, read or answered.
+    async fn take_requests(
+        mut context: NngIpcContext,
+        cmd_sender: Sender<CommandRequest>,
+        audit: AuditLog,
+        throttle: Arc<Mutex<Throttle>>,
+        limits: watch::Receiver<ThrottleConfig>,
+    ) -> Result<(), String> {
         loop {
-            let msg = cmd_server.recv()?;
-            let cmd = Command::from_bytes(&msg[..])?;
-            let stop_service = cmd == Command::StopService;
-            cmd_sender.send(cmd).await?;
-            if let Some(res) = cmd_res_receiver.recv().await {
-                cmd_server.send(&res.to_bytes()?)?;
-            }
-            if stop_service {
+            let mut msg = context.recv().await.map_err(|e| e.to_string())?;
+            if chaos::drops_command() {
+                continue;
+            }
+            let limits = *limits.borrow();
+            if let Err(e) = limits.check_size(msg.len()) {
+                Self::send_response(&mut context, &CommandResponse::Throttled(e)).await?;
+                continue;
+            }
+            let (cmd, envelope) = match Command::from_bytes(&msg[..]).map_err(|e| e.to_string()) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    let res = CommandResponse::Error {
+                        code: ErrorCode::Rejected,
+                        message: format!("Invalid request: {e}"),
+                    };
+                    let request = msg[..msg.len().min(MAX_AUDITED_INVALID_BYTES)].to_vec();
+                    // The request was refused already, so it is answered even if it cannot be recorded.
+                    _ = audit.append(&AuditEntry {
+                        at: Utc::now().timestamp() as u64,
+                        caller: Caller::of(&mut msg),
+                        job_id: None,
+                        command: String::from_utf8_lossy(&request).into(),
+                        response: res.to_json(),
+                    });
+                    Self::send_response(&mut context, &res).await?;
+                    continue;
+                }
+            };
+            if limits.requests_per_second.is_some()
+                && !matches!(cmd, Command::PingService | Command::StopService)
+            {
+                let client = client_of(&mut msg);
+                let admitted = throttle
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .admit(&limits, &client, Instant::now());
+                if let Err(e) = admitted {
+                    Self::send_response(&mut context, &CommandResponse::Throttled(e)).await?;
+                    continue;
+                }
+            }
+            let audited = cmd.is_audited().then(|| {
+                (
+                    Caller::of(&mut msg),
+                    serde_json::to_value(&cmd).unwrap_or_default(),
+                    cmd.job_id().map(str::to_string),
+                )
+            });
+            let (reply, response) = oneshot::channel();
+            cmd_sender
+                .send((cmd, envelope, reply))
+                .await
+                .map_err(|e| e.to_string())?;
+            let res = response.await.unwrap_or_else(|_| CommandResponse::Error {
+                code: ErrorCode::Failed,
+                message: "The command was dropped before it was answered".to_string(),
+            });
+            if let Some((caller, command, job_id)) = audited {
+                let job_id = match &res {
+                    CommandResponse::JobAdded(id)
+                    | CommandResponse::JobDuplicated(id, _)
+                    | CommandResponse::JobReplaced(id) => Some(id.clone()),
+                    _ => job_id,
+                };
+                // The command was carried out already, so it is answered even if it cannot be recorded.
+                _ = audit.append(&AuditEntry {
+                    at: Utc::now().timestamp() as u64,
+                    caller,
+                    job_id,
+                    command,
+                    response: res.to_json(),
+                });
+            }
+            Self::send_response(&mut context, &res).await?;
+            if res == CommandResponse::ServiceStopped {
                 return Ok(());
             }
         }
     }
 
+    /// Sends a response on a context of the command server.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - A mutable reference to the `NngIpcContext` the request was received on.
+    /// * `res` - A reference to the `CommandResponse` that represents the response to the request.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` once the response is sent, or an error message if it cannot be.
+    async fn send_response(
+        context: &mut NngIpcContext,
+        res: &CommandResponse,
+    ) -> Result<(), String> {
+        let bytes = res.to_bytes().map_err(|e| e.to_string())?;
+        context.send(&bytes[..]).await.map_err(|e| e.to_string())
+    }
+
     /// Handles commands received from the command receiver.
     ///
     /// This function listens for commands from the command receiver and handles them accordingly.
     /// It maintains a loop that continues to listen for commands until the receiver is closed.
-    /// The commands are handled based on their type: `AddJob`, `ListJobs`, `DeleteJob`, and `StopService`.
-    /// For each command, it calls the appropriate handler function and sends the response back to the command sender.
+    /// The commands are handled based on their type: `AddJob`, `ListJobs`, `GetJob`, `DeleteJob`, `UpdateJob`, `JobHistory`, `LintJobs`, `AnalyzeSchedules`, `Timeline`, `AckFailure`, `TriggerJob`, `RetryRun`, `PauseJob`, `ResumeJob`, `KillRun`, `TailOutput`, `EmitEvent`, `JobStats`, `CostCenterStats`, `UsageDigest`, `CheckTimezones`, `ListEvents`, `ListAudit`, `FollowEvents`, `Snapshot`, `Restore`, `Promote`, `ReloadConfig`, `Batch`, `StopService`, and `Unsupported`.
+    /// For each command, it calls the appropriate handler function and routes the response back to the request of the command. The
+    /// commands only reading the state of the service are answered on tasks of their own, see `answer_query`, so the commands received
+    /// after them are handled meanwhile.
     /// If a `Command::StopService` command is received, it stops the service and returns.
+    /// A command that fails, e.g. one naming a job by an invalid id, is answered with an `Error` response, see `failure_response`,
+    /// and the commands after it are handled as usual. Only the failures the service cannot recover from stop the command handler: a
+    /// job that cannot be journaled in the job store, as its change would be lost on restart, a scheduler that fails to shut down on
+    /// `StopService`, and the changes of an aborted reconciliation that cannot be undone.
+    /// A `SIGHUP` received by the service reloads its configuration like a `ReloadConfig` command, without a client to answer, so the
+    /// reload and its response are recorded in the audit log only.
+    /// Every command is scoped to the namespace of its client, see `scope_command`, so teams sharing the service only see and change the
+    /// jobs of their own namespace.
+    /// Once the configuration declares tokens, a command the token of its client does not permit is refused, see `authorize`, and a
+    /// job the policy of the configuration denies is never added or updated, see `JobPolicy::check`, nor a job beyond the quota of the
+    /// configuration, see `check_quota`, which is answered with `QuotaExceeded`.
+    /// Every command that adds, deletes, updates, pauses or resumes a job is journaled in the job store, if any, before it is acknowledged,
+    /// and announced with a `JobChanged` or `JobRemoved` event, as is every job changed by a batch once all of its commands succeeded
+    /// and every job restored from a snapshot.
+    /// It also starts dispatching the completions of jobs to the jobs that run after them, unless the service is a follower, which
+    /// refuses the commands changing or running jobs until it is promoted.
+    /// With a manifest, the jobs are converged to it every `RECONCILE_INTERVAL` in between commands, see `reconcile`, unless the service
+    /// is a follower, and the jobs the convergence changed are journaled and announced like the jobs changed by a batch.
     ///
     /// # Arguments
     ///
     /// * `mut scheduler` - A mutable `JobScheduler` that is used to manage jobs.
-    /// * `mut cmd_receiver` - A mutable `Receiver<Command>` that is used to receive commands.
-    /// * `cmd_res_sender` - A `Sender<CommandResponse>` that is used to send command responses.
+    /// * `mut cmd_receiver` - A mutable `Receiver<CommandRequest>` that is used to receive commands, with the token and the namespace the client presented and the sender their response is routed back with.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs, holding the runs of the start hooks.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs, holding the jobs restored from the job store.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `audit` - An `AuditLog` that represents the audit log of the service, read on `ListAudit` and recording the reloads on `SIGHUP`.
+    /// * `store` - An `Option<JobStore>` that represents the job store of the service. It is `None` if the jobs are only kept in memory.
+    /// * `config_file` - An `Option<PathBuf>` that represents the path of the configuration file of the service, read again on `ReloadConfig` and on `SIGHUP`.
+    /// * `config` - A `ServiceConfig` that represents the configuration of the service.
+    /// * `manifest` - An `Option<PathBuf>` that represents the path of the job file the jobs are converged to. It is `None` if the service does not reconcile its jobs.
+    /// * `health` - An `Arc<Health>` that represents the health of the service, told whether it is a primary or a follower, and when it is stopping.
+    /// * `backup` - A `watch::Sender<Option<BackupConfig>>` that is used to pass the backup settings of a reloaded configuration to the backups.
+    /// * `notifiers` - A `watch::Sender<BTreeMap<String, Notifier>>` that is used to pass the notifiers of a reloaded configuration to the notifications.
+    /// * `throttle` - A `watch::Sender<ThrottleConfig>` that is used to pass the throttle of a reloaded configuration to the command server.
+    /// * `otel` - A `watch::Sender<Option<OtelConfig>>` that is used to pass the telemetry exporter of a reloaded configuration to the exporter, with the `otel` feature.
+    /// * `follower` - An `Option<(String, JoinHandle<()>)>` that represents the name of the primary the service follows and the task mirroring it. It is `None` if the service is a primary.
     ///
     /// # Returns
     ///
     /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if successful, or an error if not.
+    #[allow(clippy::too_many_arguments)]
     async fn handle_command(
         mut scheduler: JobScheduler,
-        mut cmd_receiver: Receiver<Command>,
-        cmd_res_sender: Sender<CommandResponse>,
+        mut cmd_receiver: Receiver<CommandRequest>,
+        pool: Arc<WorkerPool>,
+        history: Arc<RwLock<RunHistory>>,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        events: Arc<EventBus>,
+        audit: AuditLog,
+        mut store: Option<JobStore>,
+        config_file: Option<PathBuf>,
+        mut config: ServiceConfig,
+        manifest: Option<PathBuf>,
+        health: Arc<Health>,
+        backup: watch::Sender<Option<BackupConfig>>,
+        notifiers: watch::Sender<BTreeMap<String, Notifier>>,
+        throttle: watch::Sender<ThrottleConfig>,
+        #[cfg(feature = "otel")] otel: watch::Sender<Option<OtelConfig>>,
+        mut follower: Option<(String, JoinHandle<()>)>,
     ) -> CronusResult<()> {
-        let jobs = Arc::new(RwLock::new(HashMap::new()));
+        if follower.is_none() {
+            tokio::spawn(Self::dispatch_chains(
+                scheduler.clone(),
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                pool.clone(),
+            ));
+            tokio::spawn(Self::dispatch_notifications(
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                notifiers.subscribe(),
+            ));
+            #[cfg(feature = "otel")]
+            tokio::spawn(Self::export_telemetry(
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                otel.subscribe(),
+            ));
+        }
+        health.set_state(match follower {
+            Some(_) => ServiceState::Follower,
+            None => ServiceState::Primary,
+        });
+        let mut hangup = signal(SignalKind::hangup())?;
+        let mut queries = JoinSet::new();
+        let mut reconciles = interval(RECONCILE_INTERVAL);
+        reconciles.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut reconcile_failure = None;
+        // Jobs paused or removed in the background, see `background_change`, are journaled and announced like commands change them.
+        let mut background = events.subscribe();
         loop {
-            if let Some(cmd) = cmd_receiver.recv().await {
-                let res = match cmd {
-                    Command::AddJob { cron, job } => {
-                        Self::handle_cmd_add_job(&scheduler, jobs.clone(), cron, job).await?
+            let received = tokio::select! {
+                received = cmd_receiver.recv() => received,
+                event = background.recv(), if follower.is_none() => {
+                    match event {
+                        Ok(event) => {
+                            let Some(id) = Self::background_change(&event) else {
+                                continue;
+                            };
+                            if let Some(store) = store.as_mut() {
+                                Self::journal_job(store, jobs.clone(), id).await?;
+                            }
+                            Self::announce_job(jobs.clone(), events.clone(), id).await;
+                        }
+                        // The changes missed are not known, so every job is written to the store.
+                        Err(RecvError::Lagged(_)) => {
+                            if let Some(store) = store.as_mut() {
+                                store.compact(&Self::stored_jobs(&*jobs.read().await))?;
+                            }
+                        }
+                        Err(RecvError::Closed) => {}
+                    }
+                    continue;
+                }
+                // A query that panicked drops its reply, which its request is answered for, see `take_requests`.
+                Some(_) = queries.join_next() => continue,
+                Some(()) = hangup.recv() => {
+                    let res = Self::handle_cmd_reload_config(
+                        history.clone(),
+                        config_file.as_deref(),
+                        &mut config,
+                        &backup,
+                        &notifiers,
+                        &throttle,
+                        #[cfg(feature = "otel")]
+                        &otel,
+                    )
+                    .await
+                    .unwrap_or_else(|e| Self::failure_response(e.as_ref()));
+                    // The configuration was reloaded already, so a reload that cannot be recorded is not an error.
+                    _ = audit.append(&AuditEntry {
+                        at: Utc::now().timestamp() as u64,
+                        caller: Caller::default(),
+                        job_id: None,
+                        command: serde_json::to_value(&Command::ReloadConfig).unwrap_or_default(),
+                        response: res.to_json(),
+                    });
+                    continue;
+                }
+                _ = reconciles.tick(), if manifest.is_some() && follower.is_none() => {
+                    let Some(manifest) = manifest.as_deref() else {
+                        continue;
+                    };
+                    let changed = Self::reconcile(
+                        manifest,
+                        &scheduler,
+                        jobs.clone(),
+                        history.clone(),
+                        events.clone(),
+                        pool.clone(),
+                        &config,
+                        &audit,
+                        &mut reconcile_failure,
+                    )
+                    .await?;
+                    for id in changed {
+                        if let Some(store) = store.as_mut() {
+                            Self::journal_job(store, jobs.clone(), id).await?;
+                        }
+                        Self::announce_job(jobs.clone(), events.clone(), id).await;
+                    }
+                    continue;
+                }
+            };
+            if let Some((mut cmd, envelope, reply)) = received {
+                let token = envelope.token.as_deref();
+                if let Err(e) = authorize(&config.tokens, token, &cmd) {
+                    _ = reply.send(CommandResponse::Forbidden(e));
+                    continue;
+                }
+                let scope = match scope(&config.tokens, token, envelope.namespace.as_deref()) {
+                    Ok(scope) => scope,
+                    Err(e) => {
+                        _ = reply.send(CommandResponse::Forbidden(e));
+                        continue;
+                    }
+                };
+                let scoped = Self::scope_command(
+                    &mut cmd,
+                    &scope,
+                    &*jobs.read().await,
+                    &*history.read().await,
+                );
+                if let Err(res) = scoped {
+                    _ = reply.send(*res);
+                    continue;
+                }
+                if let Command::AddJob { job, options, .. }
+                | Command::UpdateJob { job, options, .. } = &cmd
+                {
+                    if let Err(e) = config.policy.check(job, options) {
+                        _ = reply.send(CommandResponse::Forbidden(e));
+                        continue;
+                    }
+                }
+                if let Err(e) = Self::check_preflight(&cmd) {
+                    _ = reply.send(CommandResponse::Error {
+                        code: ErrorCode::Rejected,
+                        message: e,
+                    });
+                    continue;
+                }
+                if let Err(e) = Self::check_quota(&config.quota, jobs.clone(), &cmd).await {
+                    _ = reply.send(CommandResponse::QuotaExceeded(e));
+                    continue;
+                }
+                if let Command::Restore { snapshot } = &cmd {
+                    if let Err(e) = snapshot.jobs.iter().try_for_each(|stored| {
+                        config
+                            .policy
+                            .check(&stored.job, &stored.options)
+                            .map_err(|e| format!("Job `{}`: {e}", stored.id))
+                    }) {
+                        _ = reply.send(CommandResponse::Forbidden(e));
+                        continue;
                     }
-                    Command::ListJobs => {
-                        Self::handle_cmd_list_job(&scheduler, jobs.clone()).await?
+                }
+                if let Some((primary, _)) = follower.as_ref().filter(|_| cmd.mutates_jobs()) {
+                    _ = reply.send(CommandResponse::ReadOnly(primary.clone()));
+                    continue;
+                }
+                if cmd.is_query() {
+                    queries.spawn(Self::answer_query(
+                        cmd,
+                        scope,
+                        reply,
+                        jobs.clone(),
+                        history.clone(),
+                        events.clone(),
+                        audit.clone(),
+                    ));
+                    continue;
+                }
+                let changed: Vec<_> = match &cmd {
+                    Command::Batch { commands } => {
+                        commands.iter().filter_map(Self::changed_job).collect()
                     }
-                    Command::DeleteJob { id } => {
-                        Self::handle_cmd_delete_job(&scheduler, jobs.clone(), Uuid::parse_str(&id)?)
+                    cmd => Self::changed_job(cmd).into_iter().collect(),
+                };
+                let stopping = cmd == Command::StopService;
+                let warnings = Self::job_warnings(&cmd, &*history.read().await);
+                let res: CronusResult<CommandResponse> = async {
+                    Ok(match cmd {
+                        Command::AddJob {
+                            trigger,
+                            job,
+                            options,
+                            no_duplicates,
+                            paused,
+                            dry_run,
+                        } => {
+                            Self::handle_cmd_add_job(
+                                &scheduler,
+                                jobs.clone(),
+                                history.clone(),
+                                events.clone(),
+                                pool.clone(),
+                                trigger,
+                                job,
+                                options,
+                                no_duplicates,
+                                paused,
+                                dry_run,
+                            )
+                            .await?
+                        }
+                        Command::DeleteJob { id } => {
+                            Self::handle_cmd_delete_job(
+                                &scheduler,
+                                jobs.clone(),
+                                history.clone(),
+                                Uuid::parse_str(&id)?,
+                            )
                             .await?
+                        }
+                        Command::UpdateJob {
+                            id,
+                            trigger,
+                            job,
+                            options,
+                        } => {
+                            Self::handle_cmd_update_job(
+                                &scheduler,
+                                jobs.clone(),
+                                history.clone(),
+                                events.clone(),
+                                pool.clone(),
+                                Uuid::parse_str(&id)?,
+                                trigger,
+                                job,
+                                options,
+                            )
+                            .await?
+                        }
+                        Command::AckFailure { id } => {
+                            Self::handle_cmd_ack_failure(
+                                jobs.clone(),
+                                events.clone(),
+                                Uuid::parse_str(&id)?,
+                            )
+                            .await?
+                        }
+                        Command::TriggerJob { id } => {
+                            Self::handle_cmd_trigger_job(
+                                jobs.clone(),
+                                history.clone(),
+                                events.clone(),
+                                pool.clone(),
+                                Uuid::parse_str(&id)?,
+                            )
+                            .await?
+                        }
+                        Command::RetryRun { run_id } => {
+                            Self::handle_cmd_retry_run(
+                                jobs.clone(),
+                                history.clone(),
+                                events.clone(),
+                                pool.clone(),
+                                run_id,
+                            )
+                            .await?
+                        }
+                        Command::PauseJob { id } => {
+                            Self::handle_cmd_pause_job(jobs.clone(), Uuid::parse_str(&id)?, true)
+                                .await?
+                        }
+                        Command::ResumeJob { id } => {
+                            Self::handle_cmd_pause_job(jobs.clone(), Uuid::parse_str(&id)?, false)
+                                .await?
+                        }
+                        Command::KillRun { id, run_id } => {
+                            Self::handle_cmd_kill_run(jobs.clone(), Uuid::parse_str(&id)?, run_id)
+                                .await?
+                        }
+                        Command::EmitEvent { topic, payload } => {
+                            Self::handle_cmd_emit_event(
+                                &scheduler,
+                                jobs.clone(),
+                                history.clone(),
+                                events.clone(),
+                                pool.clone(),
+                                &scope,
+                                topic,
+                                payload,
+                            )
+                            .await?
+                        }
+                        Command::Restore { snapshot } => {
+                            Self::handle_cmd_restore(
+                                &scheduler,
+                                jobs.clone(),
+                                history.clone(),
+                                events.clone(),
+                                pool.clone(),
+                                snapshot,
+                            )
+                            .await?
+                        }
+                        Command::Promote => {
+                            let res = Self::handle_cmd_promote(
+                                &scheduler,
+                                jobs.clone(),
+                                history.clone(),
+                                events.clone(),
+                                pool.clone(),
+                                notifiers.subscribe(),
+                                #[cfg(feature = "otel")]
+                                otel.subscribe(),
+                                follower.take(),
+                            )
+                            .await?;
+                            health.set_state(ServiceState::Primary);
+                            // The events mirrored while following were changes of the primary, not of this service.
+                            if let CommandResponse::Promoted(Some(_)) = &res {
+                                background = events.subscribe();
+                            }
+                            res
+                        }
+                        Command::ReloadConfig => {
+                            Self::handle_cmd_reload_config(
+                                history.clone(),
+                                config_file.as_deref(),
+                                &mut config,
+                                &backup,
+                                &notifiers,
+                                &throttle,
+                                #[cfg(feature = "otel")]
+                                &otel,
+                            )
+                            .await?
+                        }
+                        Command::Batch { commands } => {
+                            Self::handle_cmd_batch(
+                                &scheduler,
+                                jobs.clone(),
+                                history.clone(),
+                                events.clone(),
+                                pool.clone(),
+                                &config,
+                                token,
+                                commands,
+                            )
+                            .await?
+                        }
+                        Command::StopService => {
+                            health.set_state(ServiceState::Stopping);
+                            Self::handle_cmd_stop_service(
+                                &mut scheduler,
+                                jobs.clone(),
+                                history.clone(),
+                                &config,
+                            )
+                            .await?
+                        }
+                        Command::PingService => Self::handle_cmd_ping_service().await?,
+                        _ => Self::handle_cmd_unsupported().await?,
+                    })
+                }
+                .await;
+                let res = match res {
+                    Ok(res) => res,
+                    // A scheduler failing to shut down cannot be told apart from one shut down, so the service stops anyway.
+                    Err(e) if stopping => return Err(e),
+                    Err(e) => Self::failure_response(e.as_ref()),
+                };
+                let mut changed = match &res {
+                    CommandResponse::JobAdded(id)
+                    | CommandResponse::JobDuplicated(id, _)
+                    | CommandResponse::JobReplaced(id) => {
+                        Uuid::parse_str(id).ok().into_iter().collect()
+                    }
+                    CommandResponse::Batch(responses) => changed
+                        .into_iter()
+                        .chain(responses.iter().filter_map(|res| match res {
+                            CommandResponse::JobAdded(id)
+                            | CommandResponse::JobDuplicated(id, _)
+                            | CommandResponse::JobReplaced(id) => Uuid::parse_str(id).ok(),
+                            _ => None,
+                        }))
+                        .collect(),
+                    CommandResponse::BatchAborted(..) => Vec::new(),
+                    CommandResponse::Restored(ids) => ids
+                        .iter()
+                        .filter_map(|id| Uuid::parse_str(id).ok())
+                        .collect(),
+                    _ => changed,
+                };
+                changed.sort();
+                changed.dedup();
+                for id in changed {
+                    if let Some(store) = store.as_mut() {
+                        Self::journal_job(store, jobs.clone(), id).await?;
                     }
-                    Command::StopService => Self::handle_cmd_stop_service(&mut scheduler).await?,
-                    Command::PingService => Self::handle_cmd_ping_service().await?,
+                    Self::announce_job(jobs.clone(), events.clone(), id).await;
+                }
+                let res = match res.error_code() {
+                    None => res.with_warnings(warnings),
+                    Some(_) => res,
                 };
-                cmd_res_sender.send(res).await?;
+                _ = reply.send(res);
             } else {
                 return Ok(());
             }
         }
     }
 
+    /// Converges the jobs of the service to the jobs declared in its manifest, like `cronus apply --file <manifest>`.
+    ///
+    /// The manifest is planned against the live jobs of the `default` namespace, see `plan_apply`, so only the jobs with a key of that
+    /// namespace are managed, and a plan that changes nothing is left at that. Otherwise the plan is carried out as a batch, so a plan failing part way changes no job. The batch is the
+    /// service's own: it is checked against the policy of the configuration but not against its tokens, and it is recorded in the audit
+    /// log without a caller. Every job the batch converged emits a `JobReconciled` event with the fields it changed, and the convergence
+    /// is recorded as a `reconcile:manifest` system run. A manifest that cannot be read or planned, declaring a job of another namespace,
+    /// or a batch aborted, is recorded as a failed run, only once while it keeps failing the same way, and the manifest is tried again on the next reconciliation.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - A path to the job file the jobs are converged to.
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs and of the reconciliations.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `config` - A reference to the `ServiceConfig` whose policy the jobs of the manifest are checked against.
+    /// * `audit` - A reference to the `AuditLog` the batches converging the jobs are recorded in.
+    /// * `failure` - A mutable reference to the failure of the last reconciliation, if it failed, so a failure is not recorded again.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<Vec<Uuid>>` - Returns a `CronusResult` that contains the ids of the jobs changed to converge to the manifest, to be journaled and announced, or an error if the changes of an aborted batch cannot be undone.
+    #[allow(clippy::too_many_arguments)]
+    async fn reconcile(
+        manifest: &Path,
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        config: &ServiceConfig,
+        audit: &AuditLog,
+        failure: &mut Option<String>,
+    ) -> CronusResult<Vec<Uuid>> {
+        let started_at = Utc::now();
+        let started = Instant::now();
+        let own_namespace = JobFilter {
+            namespace: Some(DEFAULT_NAMESPACE.to_string()),
+            ..JobFilter::default()
+        };
+        let planned = match load_job_specs(manifest) {
+            Ok(specs) => Self::describe_jobs(jobs.clone(), history.clone(), &own_namespace)
+                .await
+                .and_then(|live| plan_apply(&specs, &live))
+                .map(|plan| (specs, plan)),
+            Err(e) => Err(e),
+        };
+        let (specs, mut plan) = match planned {
+            Ok((_, plan)) if plan.steps.is_empty() => {
+                *failure = None;
+                return Ok(Vec::new());
+            }
+            Ok(planned) => planned,
+            Err(e) => {
+                let e = format!("Cannot converge to `{}`: {e}", manifest.display());
+                Self::record_reconcile(history, started_at, started, Err(e), failure).await;
+                return Ok(Vec::new());
+            }
+        };
+        let mut commands = Vec::new();
+        for step in &plan.steps {
+            let spec = specs
+                .iter()
+                .find(|spec| spec.key.as_ref() == Some(&step.key));
+            commands.push(match (step.action, spec, step.id.clone()) {
+                (PlanAction::Remove, _, Some(id)) => Command::DeleteJob { id },
+                (PlanAction::Update, Some(spec), Some(id)) => Command::UpdateJob {
+                    id,
+                    trigger: spec.trigger.clone(),
+                    job: spec.job.clone(),
+                    options: spec.options(),
+                },
+                (PlanAction::Add, Some(spec), None) => Command::AddJob {
+                    trigger: spec.trigger.clone(),
+                    job: spec.job.clone(),
+                    options: spec.options(),
+                    no_duplicates: false,
+                    paused: false,
+                    dry_run: false,
+                },
+                _ => return Err(format!("Inconsistent plan step for job `{}`", step.key).into()),
+            });
+        }
+        let mut batch = Command::Batch { commands };
+        let scoped = Self::scope_command(
+            &mut batch,
+            &Scope::default(),
+            &*jobs.read().await,
+            &*history.read().await,
+        );
+        if let Err(res) = scoped {
+            let res = res.to_json();
+            let e = res["error"].as_str().map(str::to_string);
+            let e = format!(
+                "Cannot converge to `{}`: {}",
+                manifest.display(),
+                e.unwrap_or_else(|| res.to_string())
+            );
+            Self::record_reconcile(history, started_at, started, Err(e), failure).await;
+            return Ok(Vec::new());
+        }
+        let command = serde_json::to_value(&batch).unwrap_or_default();
+        let Command::Batch { commands } = batch else {
+            unreachable!("the command is a batch");
+        };
+        let own = ServiceConfig {
+            tokens: BTreeMap::new(),
+            ..config.clone()
+        };
+        let res = Self::handle_cmd_batch(
+            scheduler,
+            jobs,
+            history.clone(),
+            events.clone(),
+            pool,
+            &own,
+            None,
+            commands,
+        )
+        .await?;
+        // The jobs were converged already, so a convergence that cannot be recorded is not an error.
+        _ = audit.append(&AuditEntry {
+            at: Utc::now().timestamp() as u64,
+            caller: Caller::default(),
+            job_id: None,
+            command,
+            response: res.to_json(),
+        });
+        let CommandResponse::Batch(responses) = res else {
+            let e = match res {
+                CommandResponse::BatchAborted(index, e) => format!(
+                    "Cannot converge job `{}` to `{}`: {e}",
+                    plan.steps[index].key,
+                    manifest.display()
+                ),
+                res => res.to_json().to_string(),
+            };
+            Self::record_reconcile(history, started_at, started, Err(e), failure).await;
+            return Ok(Vec::new());
+        };
+        let mut changed = Vec::new();
+        for (step, res) in plan.steps.iter_mut().zip(responses) {
+            if let CommandResponse::JobAdded(id) = res {
+                step.id = Some(id);
+            }
+            let Some(id) = step.id.as_deref().and_then(|id| Uuid::parse_str(id).ok()) else {
+                continue;
+            };
+            events.emit(
+                id,
+                EventKind::JobReconciled {
+                    action: step.action,
+                    changes: step.changes.clone(),
+                },
+            );
+            changed.push(id);
+        }
+        let summary = serde_json::to_value(&plan.summary).unwrap_or_default();
+        Self::record_reconcile(history, started_at, started, Ok(summary), failure).await;
+        Ok(changed)
+    }
+
+    /// Records a reconciliation of the jobs of the service to its manifest as a `reconcile:manifest` system run.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of the reconciliations.
+    /// * `started_at` - A `DateTime<Utc>` that represents the time the reconciliation started at.
+    /// * `started` - An `Instant` that represents when the reconciliation started, to measure how long it took.
+    /// * `result` - A `Result<Value, String>` that represents the summary of the plan carried out, or the error the reconciliation failed with.
+    /// * `failure` - A mutable reference to the failure of the last reconciliation, if it failed. A failure already recorded is not recorded again.
+    async fn record_reconcile(
+        history: Arc<RwLock<RunHistory>>,
+        started_at: DateTime<Utc>,
+        started: Instant,
+        result: Result<serde_json::Value, String>,
+        failure: &mut Option<String>,
+    ) {
+        let (status, error, result) = match result {
+            Ok(summary) => {
+                *failure = None;
+                (RunStatus::Succeeded, None, Some(summary))
+            }
+            Err(e) if failure.as_ref() == Some(&e) => return,
+            Err(e) => {
+                *failure = Some(e.clone());
+                (RunStatus::Failed, Some(e), None)
+            }
+        };
+        history.write().await.record(
+            SYSTEM_RUNS,
+            RunRecord {
+                job_id: "reconcile:manifest".to_string(),
+                run_id: Some(RunId::generate()),
+                scheduled_at: started_at.timestamp() as u64,
+                started_at: started_at.timestamp() as u64,
+                delay: Duration::ZERO,
+                duration: started.elapsed(),
+                status,
+                error,
+                cost_center: None,
+                idempotency_key: None,
+                result,
+                exit_code: None,
+                window_start: None,
+                window_end: None,
+                retry_of: None,
+                declaration: None,
+            },
+        );
+    }
+
+    /// Answers a command that only reads the state of the service, e.g. `ListJobs`, on a task of its own.
+    ///
+    /// The command handler spawns these commands instead of handling them in turn, so a command that is slow to answer, like listing
+    /// many jobs, does not hold up the commands received after it, like `StopService`. A command that is not one of them is answered as
+    /// unsupported, and a command that fails is answered with an error, see `failure_response`, like the failure of any other command.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - A `Command` that represents the command to be answered.
+    /// * `scope` - A `Scope` that represents the namespaces of the client, whose jobs the commands reporting on the whole service answer
+    ///   about.
+    /// * `reply` - A `oneshot::Sender<CommandResponse>` that is used to route the response back to the request of the command.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `audit` - An `AuditLog` that represents the audit log of the service.
+    async fn answer_query(
+        query: Command,
+        scope: Scope,
+        reply: oneshot::Sender<CommandResponse>,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        audit: AuditLog,
+    ) {
+        let res: CronusResult<CommandResponse> = async {
+            Ok(match query {
+                Command::ListJobs {
+                    offset,
+                    limit,
+                    filter,
+                    sort,
+                } => {
+                    Self::handle_cmd_list_job(
+                        jobs.clone(),
+                        history.clone(),
+                        offset,
+                        limit,
+                        &filter,
+                        sort,
+                    )
+                    .await?
+                }
+                Command::GetJob { id } => {
+                    Self::handle_cmd_get_job(jobs.clone(), history.clone(), Uuid::parse_str(&id)?)
+                        .await?
+                }
+                Command::JobHistory { id } => {
+                    Self::handle_cmd_job_history(history.clone(), Uuid::parse_str(&id)?).await?
+                }
+                Command::LintJobs => {
+                    Self::handle_cmd_lint_jobs(jobs.clone(), history.clone(), &scope).await?
+                }
+                Command::AnalyzeSchedules { window, threshold } => {
+                    Self::handle_cmd_analyze_schedules(
+                        jobs.clone(),
+                        history.clone(),
+                        &scope,
+                        window,
+                        threshold,
+                    )
+                    .await?
+                }
+                Command::Timeline { window } => {
+                    Self::handle_cmd_timeline(jobs.clone(), history.clone(), &scope, window).await?
+                }
+                Command::TailOutput { id, from, run_id } => {
+                    Self::handle_cmd_tail_output(jobs.clone(), Uuid::parse_str(&id)?, from, run_id)
+                        .await?
+                }
+                Command::JobStats { id } => {
+                    let id = id.as_deref().map(Uuid::parse_str).transpose()?;
+                    Self::handle_cmd_job_stats(jobs.clone(), history.clone(), &scope, id).await?
+                }
+                Command::CostCenterStats => {
+                    Self::handle_cmd_cost_center_stats(jobs.clone(), history.clone(), &scope)
+                        .await?
+                }
+                Command::UsageDigest => {
+                    Self::handle_cmd_usage_digest(jobs.clone(), history.clone(), &scope).await?
+                }
+                Command::CheckTimezones => {
+                    Self::handle_cmd_check_timezones(jobs.clone(), &scope).await?
+                }
+                Command::ListEvents => {
+                    Self::handle_cmd_list_events(jobs.clone(), events.clone(), &scope).await?
+                }
+                Command::ListAudit { id, limit } => {
+                    Self::handle_cmd_list_audit(jobs.clone(), audit.clone(), &scope, id, limit)
+                        .await?
+                }
+                Command::FollowEvents { after } => {
+                    Self::handle_cmd_follow_events(jobs.clone(), events.clone(), &scope, after)
+                        .await?
+                }
+                Command::Snapshot => {
+                    Self::handle_cmd_snapshot(jobs.clone(), history.clone(), events.clone(), &scope)
+                        .await?
+                }
+                _ => Self::handle_cmd_unsupported().await?,
+            })
+        }
+        .await;
+        // The client may be gone already, which is no error of the service.
+        _ = reply.send(res.unwrap_or_else(|e| Self::failure_response(e.as_ref())));
+    }
+
+    /// Answers a command that failed with an error, so the failure of one command does not stop the command handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `e` - A reference to the error the command failed with.
+    ///
+    /// # Returns
+    ///
+    /// * `CommandResponse` - Returns a `CommandResponse::Error`, with the code `Rejected` if the command named a job by an invalid id,
+    ///   or `Failed` otherwise.
+    fn failure_response(e: &(dyn Error + 'static)) -> CommandResponse {
+        match e.downcast_ref::<uuid::Error>() {
+            Some(e) => CommandResponse::Error {
+                code: ErrorCode::Rejected,
+                message: format!("Invalid job id: {e}"),
+            },
+            None => CommandResponse::Error {
+                code: ErrorCode::Failed,
+                message: e.to_string(),
+            },
+        }
+    }
+
+    /// Returns the id of the job a command changes, journaled and announced once the command is carried out.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmd` - A reference to the `Command`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Uuid>` - Returns the id of the job the command deletes, updates, pauses or resumes, or `None` if it changes no existing job.
+    fn changed_job(cmd: &Command) -> Option<Uuid> {
+        match cmd {
+            Command::DeleteJob { id }
+            | Command::UpdateJob { id, .. }
+            | Command::PauseJob { id }
+            | Command::ResumeJob { id } => Uuid::parse_str(id).ok(),
+            _ => None,
+        }
+    }
+
     /// Handles the `AddJob` command.
     ///
-    /// This function creates a new cron job and adds it to the job scheduler.
-    /// It also adds the job to the jobs map.
+    /// This function checks that the job can be registered, then registers it under a new id. A job with a key already registered for
+    /// another job replaces that job instead, like an `UpdateJob` command, keeping its id and its run history, so adding the same job
+    /// twice does not duplicate it. A job with the schedule and the payload of another registered job, see `StoredJob::duplicates`, is
+    /// added with a warning, or refused if duplicates are. A job added paused is registered like a paused job, so it does not run until
+    /// it is resumed, e.g. to review a job before it runs for the first time. A dry run goes through the same checks, then reports what
+    /// adding the job would do, along with the problems `lint_job` finds with it on the host of the service, without registering it.
     ///
     /// # Arguments
     ///
     /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
-    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, Job>>>` that is used to store jobs.
-    /// * `cron` - A `String` that represents the cron schedule for the job.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `trigger` - A `Trigger` that represents what makes the job run.
     /// * `job` - A `Job` that represents the job to be added.
+    /// * `options` - A `JobOptions` that represents the execution settings of the job.
+    /// * `no_duplicates` - A `bool` that represents whether the job is refused if it duplicates another registered job.
+    /// * `paused` - A `bool` that represents whether the job is registered paused.
+    /// * `dry_run` - A `bool` that represents whether the job is only checked, without being registered.
     ///
     /// # Returns
     ///
-    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobAdded` if successful, or an error if not.
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobPreview` for a dry run, a `CommandResponse::JobAdded`, a `CommandResponse::JobDuplicated` if it duplicates another job, or a `CommandResponse::JobReplaced` if the key of the job was registered already, if successful, a `CommandResponse::DuplicateJob` if it duplicates another job and duplicates are refused, a `CommandResponse::Unsupported` if the job cannot be run by this version of the service, or an error if not.
+    #[allow(clippy::too_many_arguments)]
     async fn handle_cmd_add_job(
         scheduler: &JobScheduler,
-        jobs: Arc<RwLock<HashMap<Uuid, Job>>>,
-        cron: String,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        trigger: Trigger,
         job: Job,
+        options: JobOptions,
+        no_duplicates: bool,
+        paused: bool,
+        dry_run: bool,
     ) -> CronusResult<CommandResponse> {
-        let business = job.clone().to_business();
-        let cron_job = JobBuilder::new()
-            .with_timezone(Local)
-            .with_cron_job_type()
-            .with_schedule(cron.as_ref())?
-            .with_run_async(Box::new(move |id, mut scheduler| {
-                let business = business.clone();
-                Box::pin(async move {
-                    if let Ok(Some(ts)) = scheduler.next_tick_for_job(id).await {
-                        business(ts);
-                    }
+        if let Err(e) = Self::check_supported(&job, &options) {
+            return Ok(CommandResponse::Unsupported(e));
+        }
+        let (keyed, duplicate) = {
+            let jobs = jobs.read().await;
+            let keyed = Self::keyed_job(&jobs, &options);
+            // A job only duplicates the jobs of its namespace, whose clients may see them.
+            let others: Vec<_> = Self::stored_jobs(&jobs)
+                .into_iter()
+                .filter(|stored| {
+                    Some(stored.id) != keyed && stored.options.namespace == options.namespace
                 })
-            }))
-            .build()?;
-        let id = cron_job.guid();
-        scheduler.add(cron_job).await?;
-        jobs.write().await.insert(id, job);
-        Ok(CommandResponse::JobAdded(id.to_string()))
+                .collect();
+            let duplicate =
+                find_duplicate(&others, &trigger, &job, &options).map(|stored| stored.id);
+            (keyed, duplicate)
+        };
+        if let Some(duplicate) = duplicate.filter(|_| no_duplicates) {
+            return Ok(CommandResponse::DuplicateJob(duplicate.to_string()));
+        }
+        let id = keyed.unwrap_or_else(Uuid::new_v4);
+        Self::check_registration(jobs.clone(), id, &trigger, &options).await?;
+        if dry_run {
+            // The id of a job that would be created is not reserved, so its findings refer to it as new.
+            let job_ref = keyed.map_or_else(|| "new".to_string(), |id| id.to_string());
+            let findings = lint_job(
+                &job_ref,
+                &trigger,
+                &job,
+                &options,
+                history.read().await.estimated_duration(&id),
+            );
+            return Ok(CommandResponse::JobPreview(JobPreview {
+                job_id: keyed.map(|id| id.to_string()),
+                created: keyed.is_none(),
+                duplicate_of: duplicate.map(|id| id.to_string()),
+                upcoming_runs: upcoming_runs(&trigger, &options, Utc::now()),
+                trigger,
+                job,
+                options,
+                findings,
+            }));
+        }
+        Self::register_job(
+            scheduler,
+            jobs.clone(),
+            history,
+            events,
+            pool,
+            id,
+            trigger,
+            job,
+            options,
+            paused,
+        )
+        .await?;
+        Ok(match (keyed, duplicate) {
+            (Some(_), _) => CommandResponse::JobReplaced(id.to_string()),
+            (None, Some(duplicate)) => {
+                CommandResponse::JobDuplicated(id.to_string(), duplicate.to_string())
+            }
+            (None, None) => CommandResponse::JobAdded(id.to_string()),
+        })
     }
 
-    /// Handles the `ListJobs` command.
+    /// Finds the job registered with the key of a job, in its namespace, so the jobs of two namespaces may have the same key.
     ///
-    /// This function retrieves a list of all jobs from the job scheduler and the jobs map.
-    /// It creates a `JobInfo` object for each job, which includes the job's ID, cron schedule, last run time, next run time, and the job itself.
-    /// It then returns a `CommandResponse::JobList` that contains the list of `JobInfo` objects.
+    /// # Arguments
+    ///
+    /// * `jobs` - A reference to the registered jobs.
+    /// * `options` - A reference to the `JobOptions` of the job, holding its key and its namespace.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Uuid>` - Returns the id of the job with the key, or `None` if the job has no key or no job of its namespace has it.
+    fn keyed_job(jobs: &HashMap<Uuid, JobEntry>, options: &JobOptions) -> Option<Uuid> {
+        let key = options.key.as_deref()?;
+        let namespace = options.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE);
+        jobs.iter()
+            .find(|(_, entry)| {
+                entry.options.key.as_deref() == Some(key)
+                    && entry
+                        .options
+                        .namespace
+                        .as_deref()
+                        .unwrap_or(DEFAULT_NAMESPACE)
+                        == namespace
+            })
+            .map(|(id, _)| *id)
+    }
+
+    /// Scopes a command to the namespaces its client works on, see `Scope`.
+    ///
+    /// A command about a job of another namespace, or about one of its runs, is refused as if the job did not exist. A job added, or updated, without a namespace
+    /// is recorded in the namespace of the client, or keeps its namespace when updated across every namespace, and a job declaring a
+    /// namespace outside the scope of the client is refused. A list of jobs only lists the jobs of the namespace of the client, and every
+    /// command of a batch is scoped in turn.
     ///
     /// # Arguments
     ///
-    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
-    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, Job>>>` that is used to store jobs.
+    /// * `cmd` - A mutable reference to the `Command`, scoped in place.
+    /// * `scope` - A reference to the `Scope` of the client.
+    /// * `jobs` - A reference to the registered jobs.
+    /// * `history` - A reference to the `RunHistory` the run a command retries is looked up in.
     ///
     /// # Returns
     ///
-    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobList` if successful, or an error if not.
-    async fn handle_cmd_list_job(
-        scheduler: &JobScheduler,
-        jobs: Arc<RwLock<HashMap<Uuid, Job>>>,
-    ) -> CronusResult<CommandResponse> {
-        let mut job_list = Vec::new();
-        let jobs = jobs.read().await.clone();
-        let metadata = scheduler.context().metadata_storage.clone();
-        let mut metadata = metadata.write().await;
-        for (id, job) in jobs {
-            if let Some(job_data) = metadata.get(id).await? {
-                let id = if let Some(id) = &job_data.id {
-                    Uuid::from(id).to_string()
-                } else {
-                    Default::default()
-                };
-                let cron = if let Some(schedule) = job_data.schedule() {
-                    String::from(schedule)
-                } else {
-                    Default::default()
-                };
-                let job = JobInfo {
-                    id,
-                    cron,
-                    last_run: job_data.last_tick,
-                    next_run: Some(job_data.next_tick),
-                    job,
-                };
-                job_list.push(job);
+    /// * `Result<(), Box<CommandResponse>>` - Returns `Ok(())` if the command is within the scope, or the response refusing it: an `Error`
+    ///   of code `NotFound` for a job of another namespace, or a `Forbidden` for a job declaring a namespace outside the scope.
+    fn scope_command(
+        cmd: &mut Command,
+        scope: &Scope,
+        jobs: &HashMap<Uuid, JobEntry>,
+        history: &RunHistory,
+    ) -> Result<(), Box<CommandResponse>> {
+        if let Command::RetryRun { run_id } = &*cmd {
+            if history
+                .find_run(*run_id)
+                .and_then(|(id, _)| jobs.get(&id))
+                .is_some_and(|entry| !scope.contains(entry.options.namespace.as_deref()))
+            {
+                return Err(Box::new(CommandResponse::Error {
+                    code: ErrorCode::NotFound,
+                    message: format!("No run `{run_id}` in namespace `{scope}`"),
+                }));
+            }
+        }
+        let id = match &*cmd {
+            Command::GetJob { id }
+            | Command::JobHistory { id }
+            | Command::TailOutput { id, .. }
+            | Command::JobStats { id: Some(id) } => Some(id.as_str()),
+            cmd => cmd.job_id(),
+        };
+        let entry = id
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .and_then(|id| jobs.get(&id));
+        if let Some(entry) = entry {
+            if !scope.contains(entry.options.namespace.as_deref()) {
+                return Err(Box::new(CommandResponse::Error {
+                    code: ErrorCode::NotFound,
+                    message: format!("No job `{}` in namespace `{scope}`", id.unwrap_or_default()),
+                }));
+            }
+        }
+        match cmd {
+            Command::AddJob { options, .. } | Command::UpdateJob { options, .. } => {
+                match options.namespace.as_deref() {
+                    Some(namespace) => {
+                        validate_namespace(namespace).map_err(|e| {
+                            Box::new(CommandResponse::Error {
+                                code: ErrorCode::Rejected,
+                                message: e,
+                            })
+                        })?;
+                        if !scope.contains(Some(namespace)) {
+                            return Err(Box::new(CommandResponse::Forbidden(format!(
+                                "Job of namespace `{namespace}` is outside namespace `{scope}`"
+                            ))));
+                        }
+                        if namespace == DEFAULT_NAMESPACE {
+                            options.namespace = None;
+                        }
+                    }
+                    None => {
+                        options.namespace = match (scope, entry) {
+                            (Scope::All, Some(entry)) => entry.options.namespace.clone(),
+                            _ => scope.recorded(),
+                        };
+                    }
+                }
+                Ok(())
             }
+            Command::ListJobs { filter, .. } => {
+                if let Scope::Namespace(namespace) = scope {
+                    filter.namespace = Some(namespace.clone());
+                }
+                Ok(())
+            }
+            Command::Batch { commands } => commands
+                .iter_mut()
+                .try_for_each(|cmd| Self::scope_command(cmd, scope, jobs, history)),
+            _ => Ok(()),
         }
-        Ok(CommandResponse::JobList(job_list))
     }
 
-    /// Handles the `DeleteJob` command.
+    /// Lists the non-fatal problems of the job an `AddJob` or `UpdateJob` command registers, answered as warnings once it is registered,
+    /// see `CommandResponse::Warned`: the findings of `lint_job` that do not refuse the job, e.g. a schedule firing every second.
     ///
-    /// This function removes a job from the job scheduler and the jobs map.
-    /// It uses the job's ID to find and remove the job.
+    /// # Arguments
+    ///
+    /// * `cmd` - A reference to the `Command`.
+    /// * `history` - A reference to the `RunHistory` the runs of an updated job are estimated from.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - Returns the messages of the findings, or an empty vector for a dry run, which reports them as findings, or a
+    ///   command registering no job.
+    fn job_warnings(cmd: &Command, history: &RunHistory) -> Vec<String> {
+        let (trigger, job, options, estimated_duration) = match cmd {
+            Command::AddJob {
+                trigger,
+                job,
+                options,
+                dry_run: false,
+                ..
+            } => (trigger, job, options, None),
+            Command::UpdateJob {
+                id,
+                trigger,
+                job,
+                options,
+            } => (
+                trigger,
+                job,
+                options,
+                Uuid::parse_str(id)
+                    .ok()
+                    .and_then(|id| history.estimated_duration(&id)),
+            ),
+            _ => return Vec::new(),
+        };
+        lint_job("job", trigger, job, options, estimated_duration)
+            .into_iter()
+            .map(|finding| match finding.job.strip_prefix("job/") {
+                Some(part) => format!("`{part}`: {}", finding.message),
+                None => finding.message,
+            })
+            .collect()
+    }
+
+    /// Checks that what the job an `AddJob` or `UpdateJob` command registers runs can be found on the host of the service, see
+    /// `preflight_job`. A dry run is not checked, as it reports the same problems as findings instead.
     ///
     /// # Arguments
     ///
-    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
-    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, Job>>>` that is used to store jobs.
-    /// * `id` - A `Uuid` that represents the ID of the job to be deleted.
+    /// * `cmd` - A reference to the `Command`.
     ///
     /// # Returns
     ///
-    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobDeleted` if successful, or an error if not.
-    async fn handle_cmd_delete_job(
-        scheduler: &JobScheduler,
-        jobs: Arc<RwLock<HashMap<Uuid, Job>>>,
-        id: Uuid,
-    ) -> CronusResult<CommandResponse> {
-        scheduler.remove(&id).await?;
-        jobs.write().await.retain(|job_id, _| job_id.ne(&id));
-        Ok(CommandResponse::JobDeleted)
+    /// * `Result<(), String>` - Returns `Ok(())` if the job can run or the command registers no job, or an error message describing why
+    ///   the job cannot run.
+    fn check_preflight(cmd: &Command) -> Result<(), String> {
+        match cmd {
+            Command::AddJob {
+                job,
+                options,
+                dry_run: false,
+                ..
+            }
+            | Command::UpdateJob { job, options, .. } => preflight_job(job, options),
+            _ => Ok(()),
+        }
     }
 
-    /// Handles the `StopService` command.
+    /// Checks the job an `AddJob` or `UpdateJob` command registers fits in the quota of the service, see `JobQuota::check`.
     ///
-    /// This function shuts down the job scheduler and returns a `CommandResponse::ServiceStopped`.
+    /// The job the command replaces, the job of the same key for `AddJob` or the job updated for `UpdateJob`, is not counted, so a job
+    /// can always be replaced in place, except by a job of a cost center at its limit.
     ///
     /// # Arguments
     ///
-    /// * `scheduler` - A mutable reference to the `JobScheduler` that is used to manage jobs.
+    /// * `quota` - A reference to the `JobQuota` of the configuration.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `cmd` - A reference to the `Command`.
     ///
     /// # Returns
     ///
-    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::ServiceStopped` if successful, or an error if not.
-    async fn handle_cmd_stop_service(
-        scheduler: &mut JobScheduler,
+    /// * `Result<(), String>` - Returns `Ok(())` if the job fits in the quota or the command registers no job, or an error message
+    ///   telling which limit the job exceeds.
+    async fn check_quota(
+        quota: &JobQuota,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        cmd: &Command,
+    ) -> Result<(), String> {
+        let jobs = jobs.read().await;
+        let (options, replaced) = match cmd {
+            Command::AddJob { options, .. } => (options, Self::keyed_job(&jobs, options)),
+            Command::UpdateJob { id, options, .. } => (options, Uuid::parse_str(id).ok()),
+            _ => return Ok(()),
+        };
+        quota.check(
+            jobs.iter()
+                .filter(|(id, _)| Some(**id) != replaced)
+                .map(|(_, entry)| &entry.options),
+            options,
+        )
+    }
+
+    /// Handles the `UpdateJob` command.
+    ///
+    /// This function checks that the new declaration of the job can be registered, then registers it in place of the old one.
+    /// The job keeps its id and its run history, while its paused state and unacknowledged failure are reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job to be updated.
+    /// * `trigger` - A `Trigger` that represents what makes the job run.
+    /// * `job` - A `Job` that represents the job itself.
+    /// * `options` - A `JobOptions` that represents the execution settings of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobUpdated` if successful, a `CommandResponse::Unsupported` if the job cannot be run by this version of the service, or an error if not.
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_cmd_update_job(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        id: Uuid,
+        trigger: Trigger,
+        job: Job,
+        options: JobOptions,
     ) -> CronusResult<CommandResponse> {
-        scheduler.shutdown().await?;
-        Ok(CommandResponse::ServiceStopped)
+        if !jobs.read().await.contains_key(&id) {
+            return Err(format!("Job `{id}` does not exist").into());
+        }
+        if let Err(e) = Self::check_supported(&job, &options) {
+            return Ok(CommandResponse::Unsupported(e));
+        }
+        Self::check_registration(jobs.clone(), id, &trigger, &options).await?;
+        Self::register_job(
+            scheduler, jobs, history, events, pool, id, trigger, job, options, false,
+        )
+        .await?;
+        Ok(CommandResponse::JobUpdated)
     }
 
-    /// Handles the `PingService` command.
+    /// Checks that a job, and every hook of it, can be run by this version of the service.
     ///
-    /// This function responds to a ping request to the service. It is used to check if the service is running.
+    /// # Arguments
+    ///
+    /// * `job` - A reference to the `Job` to be registered.
+    /// * `options` - A reference to the `JobOptions` declaring the hooks of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the job and its hooks are supported, or an error message describing the first unsupported one.
+    fn check_supported(job: &Job, options: &JobOptions) -> Result<(), String> {
+        job.check_supported()?;
+        options.hooks().try_for_each(|(name, hook)| {
+            hook.check_supported()
+                .map_err(|e| format!("Hook `{name}`: {e}"))
+        })
+    }
+
+    /// Checks that a job can be registered under an id.
+    ///
+    /// The name and the key of the job must not be taken by another job of its namespace, and a job running after another job must not close
+    /// a dependency cycle, a job only running after the jobs of its namespace. A job already registered under the id is ignored, since it is
+    /// replaced by the registration.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `id` - A `Uuid` that represents the ID the job is registered under.
+    /// * `trigger` - A reference to the `Trigger` of the job.
+    /// * `options` - A reference to the `JobOptions` of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if the job can be registered, or an error describing the conflict if not.
+    async fn check_registration(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        id: Uuid,
+        trigger: &Trigger,
+        options: &JobOptions,
+    ) -> CronusResult<()> {
+        let jobs = jobs.read().await;
+        let namespace = options.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE);
+        let others: Vec<_> = jobs
+            .iter()
+            .filter(|(job_id, entry)| {
+                **job_id != id
+                    && entry
+                        .options
+                        .namespace
+                        .as_deref()
+                        .unwrap_or(DEFAULT_NAMESPACE)
+                        == namespace
+            })
+            .map(|(job_id, entry)| (job_id.to_string(), entry))
+            .collect();
+        if let Some(name) = &options.name {
+            if others
+                .iter()
+                .any(|(_, entry)| entry.options.name.as_ref() == Some(name))
+            {
+                return Err(format!("Job name `{name}` is already taken").into());
+            }
+        }
+        if let Some(key) = &options.key {
+            if others
+                .iter()
+                .any(|(_, entry)| entry.options.key.as_ref() == Some(key))
+            {
+                return Err(format!("Job key `{key}` is already taken").into());
+            }
+        }
+        if let Some(after) = trigger.after() {
+            let id = id.to_string();
+            let mut links: Vec<_> = others
+                .iter()
+                .map(|(id, entry)| ChainLink {
+                    id,
+                    name: entry.options.name.as_deref(),
+                    after: entry.trigger.after(),
+                })
+                .collect();
+            links.push(ChainLink {
+                id: &id,
+                name: options.name.as_deref(),
+                after: Some(after),
+            });
+            if let Some(cycle) = find_cycle(&links, &id) {
+                return Err(format!("Dependency cycle: {}", cycle.join(" -> ")).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Registers a job under an id, replacing the job already registered under it, if any.
+    ///
+    /// A job running on a cron schedule is added to the job scheduler, in its timezone if it has one or in local time otherwise, and the
+    /// ticks the job scheduler does not fire as they fall into a DST transition are run by a task of their own when the DST policy of
+    /// the job runs them, see `follow_recurrence`. A job running on an RFC 5545 recurrence or on the sun is run by such a task too, while
+    /// a job running after another job, on events or when the service starts is only added to the jobs map.
+    /// If the job has a `not_after` time, a timer is started that expires the job once that time has passed, replacing the timer of the
+    /// job replaced, if any. The job is added to the jobs
+    /// map before any of these is armed, so a tick firing right away finds the job as registered, e.g. paused.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `id` - A `Uuid` that represents the ID the job is registered under.
+    /// * `trigger` - A `Trigger` that represents what makes the job run.
+    /// * `job` - A `Job` that represents the job itself.
+    /// * `options` - A `JobOptions` that represents the execution settings of the job.
+    /// * `paused` - A `bool` that represents whether the job is registered paused, so it does not run until it is resumed.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if successful, or an error if not.
+    #[allow(clippy::too_many_arguments)]
+    async fn register_job(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        id: Uuid,
+        trigger: Trigger,
+        job: Job,
+        options: JobOptions,
+        paused: bool,
+    ) -> CronusResult<()> {
+        let started = Utc::now();
+        let occurrences: Option<Occurrences> =
+            match (trigger.cron(), trigger.rrule(), trigger.sun()) {
+                (Some(_), _, _) if options.on_dst == DstPolicy::Skip => None,
+                (Some(cron), _, _) => {
+                    let schedule = parse_schedule(cron)?;
+                    match options.timezone.as_deref() {
+                        Some(zone) => Some(Box::new(DstRuns::new(
+                            schedule,
+                            parse_timezone(zone)?,
+                            options.on_dst,
+                            started,
+                        ))),
+                        None => Some(Box::new(DstRuns::new(
+                            schedule,
+                            Local,
+                            options.on_dst,
+                            started,
+                        ))),
+                    }
+                }
+                (None, Some(rule), _) => Some(Box::new(
+                    parse_rrule(rule)?
+                        .into_iter()
+                        .map(|occurrence| occurrence.with_timezone(&Utc))
+                        .skip_while(move |occurrence| *occurrence <= started),
+                )),
+                (None, None, Some(sun)) => {
+                    sun.validate()?;
+                    Some(Box::new(sun.occurrences(started)))
+                }
+                (None, None, None) => None,
+            };
+        let recurrence_pool = pool.clone();
+        let ticks: Arc<Mutex<Option<CronTicks>>> = Arc::default();
+        let cron_job = match trigger.cron() {
+            Some(cron) => {
+                let run_jobs = jobs.clone();
+                let run_history = history.clone();
+                let run_events = events.clone();
+                let timezone = options
+                    .timezone
+                    .as_deref()
+                    .map(parse_timezone)
+                    .transpose()?;
+                let tick_schedule = parse_schedule(cron)?;
+                // The ticks are computed like the job scheduler does, from the time it fires the job.
+                let following = move |at: DateTime<Utc>| {
+                    let tick = match &timezone {
+                        Some(tz) => following_tick(&tick_schedule, tz, at),
+                        None => following_tick(&tick_schedule, &Local, at),
+                    };
+                    tick.map(|tick| tick.timestamp() as u64)
+                };
+                *Self::lock_ticks(&ticks) = Some(CronTicks {
+                    last: None,
+                    next: following(started),
+                });
+                let run_ticks = ticks.clone();
+                let run: Box<JobToRunAsync> = Box::new(move |id, scheduler| {
+                    let fired_at = Utc::now();
+                    *Self::lock_ticks(&run_ticks) = Some(CronTicks {
+                        last: Some(fired_at.timestamp() as u64),
+                        next: following(fired_at),
+                    });
+                    Box::pin(Self::execute_job(
+                        id,
+                        scheduler,
+                        run_jobs.clone(),
+                        run_history.clone(),
+                        run_events.clone(),
+                        pool.clone(),
+                    ))
+                });
+                let schedule = parse_schedule(cron)?;
+                let builder = JobBuilder::new()
+                    .with_cron_job_type()
+                    .with_job_id(id.into());
+                let cron_job = match options.timezone.as_deref() {
+                    Some(zone) => builder
+                        .with_timezone(parse_timezone(zone)?)
+                        .with_schedule(schedule)?
+                        .with_run_async(run)
+                        .build()?,
+                    None => builder
+                        .with_timezone(Local)
+                        .with_schedule(schedule)?
+                        .with_run_async(run)
+                        .build()?,
+                };
+                Some(cron_job)
+            }
+            None => None,
+        };
+        let replaced = jobs.read().await.get(&id).map(|entry| {
+            (
+                entry.trigger.clone(),
+                entry.running.clone(),
+                entry.finished.clone(),
+                entry.output.clone(),
+                entry.recurrence.clone(),
+                entry.expiry.clone(),
+            )
+        });
+        if replaced
+            .as_ref()
+            .is_some_and(|(trigger, _, _, _, _, _)| trigger.cron().is_some())
+        {
+            scheduler.remove(&id).await?;
+        }
+        if let Some((_, _, _, _, recurrence, expiry)) = &replaced {
+            recurrence
+                .iter()
+                .chain(expiry)
+                .for_each(|task| task.abort());
+        }
+        let not_after = options.not_after;
+        let (running, finished, output) = replaced
+            .map(|(_, running, finished, output, _, _)| (running, finished, output))
+            .unwrap_or_default();
+        jobs.write().await.insert(
+            id,
+            JobEntry {
+                paused,
+                running,
+                finished,
+                output,
+                ticks,
+                ..JobEntry::new(id, trigger, job, options)
+            },
+        );
+        if let Some(cron_job) = cron_job {
+            if let Err(e) = scheduler.add(cron_job).await {
+                jobs.write().await.remove(&id);
+                return Err(e.into());
+            }
+        }
+        let recurrence = occurrences.map(|occurrences| {
+            tokio::spawn(Self::follow_recurrence(
+                id,
+                occurrences,
+                scheduler.clone(),
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                recurrence_pool,
+            ))
+            .abort_handle()
+            .into()
+        });
+        let expiry = not_after.map(|not_after| {
+            let scheduler = scheduler.clone();
+            let (jobs, history, events) = (jobs.clone(), history.clone(), events.clone());
+            tokio::spawn(async move {
+                let remaining = (not_after - Utc::now()).to_std().unwrap_or_default();
+                sleep(remaining).await;
+                // Removing the job aborts this timer, so the job is expired on a task of its own.
+                tokio::spawn(async move {
+                    _ = Self::expire_job(&scheduler, jobs, history, events, id).await;
+                });
+            })
+            .abort_handle()
+            .into()
+        });
+        if let Some(entry) = jobs.write().await.get_mut(&id) {
+            entry.recurrence = recurrence;
+            entry.expiry = expiry;
+        }
+        Ok(())
+    }
+
+    /// Runs a job on the occurrences of its RFC 5545 recurrence, on its times of the sun, or on the ticks of its cron schedule moved by
+    /// DST transitions, see `DstRuns`, like the job scheduler runs the ticks of cron jobs, see `execute_job`, until they are over. The occurrences are walked once, the times of the sun being computed a day at
+    /// a time, and the task sleeps by steps of at most `RECURRENCE_CHECK_INTERVAL`, so an occurrence is not missed when the clock of the
+    /// host is changed or the host is suspended.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    /// * `occurrences` - An `Occurrences` that represents the times the job runs at, from the time it was registered.
+    /// * `scheduler` - A `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    async fn follow_recurrence(
+        id: Uuid,
+        occurrences: Occurrences,
+        scheduler: JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+    ) {
+        for occurrence in occurrences {
+            Self::sleep_until(occurrence).await;
+            tokio::spawn(Self::execute_job(
+                id,
+                scheduler.clone(),
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                pool.clone(),
+            ));
+        }
+    }
+
+    /// Sleeps until a time, by steps of at most `RECURRENCE_CHECK_INTERVAL`, so the time is not overslept when the clock of the host is
+    /// changed or the host is suspended.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - A `DateTime<Utc>` that represents the time to wake up at.
+    async fn sleep_until(at: DateTime<Utc>) {
+        while let Ok(remaining) = (at - Utc::now()).to_std() {
+            sleep(remaining.min(RECURRENCE_CHECK_INTERVAL)).await;
+        }
+    }
+
+    /// Registers the jobs read from the job store again, with their ids and whether they are paused.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `stored` - A vector of `StoredJob` instances that represent the jobs read from the job store.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if successful, or an error naming the first job that cannot be registered.
+    async fn restore_jobs(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        stored: Vec<StoredJob>,
+    ) -> CronusResult<()> {
+        for stored in stored {
+            Self::register_job(
+                scheduler,
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                pool.clone(),
+                stored.id,
+                stored.trigger,
+                stored.job,
+                stored.options,
+                stored.paused,
+            )
+            .await
+            .map_err(|e| format!("Stored job `{}`: {e}", stored.id))?;
+        }
+        Ok(())
+    }
+
+    /// Journals the change of a job in the job store, compacting the journal once it holds enough entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - A mutable reference to the `JobStore` the change is journaled in.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job that changed, journaled as deleted if it is not registered anymore.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if the change is on disk, or an error if not.
+    async fn journal_job(
+        store: &mut JobStore,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        id: Uuid,
+    ) -> CronusResult<()> {
+        let jobs = jobs.read().await;
+        let entry = match jobs.get(&id) {
+            Some(entry) => JournalEntry::Put(Self::stored_job(id, entry)),
+            None => JournalEntry::Delete { id },
+        };
+        if store.record(&entry)? {
+            store.compact(&Self::stored_jobs(&jobs))?;
+        }
+        Ok(())
+    }
+
+    /// Describes every registered job as it is kept in the job store.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - A reference to the registered jobs.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<StoredJob>` - Returns the jobs as they are kept in the job store, sorted by id.
+    fn stored_jobs(jobs: &HashMap<Uuid, JobEntry>) -> Vec<StoredJob> {
+        let mut stored: Vec<_> = jobs
+            .iter()
+            .map(|(id, entry)| Self::stored_job(*id, entry))
+            .collect();
+        stored.sort_by_key(|job| job.id);
+        stored
+    }
+
+    /// Tells which job an event reports a change of made in the background, i.e. not by a command.
+    ///
+    /// A job is changed in the background when it is paused after failing too many times in a row, or paused or removed once its
+    /// schedule expired. Such a change is journaled and announced by the command loop, which owns the job store.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - A reference to the `Event` emitted.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Uuid>` - Returns the ID of the job changed, or `None` if the event does not report a change made in the background.
+    fn background_change(event: &Event) -> Option<Uuid> {
+        match event.kind {
+            EventKind::JobDisabled { .. } | EventKind::JobExpired { .. } => {
+                Uuid::parse_str(&event.job_id).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Announces the change of a job with a `JobChanged` event, or a `JobRemoved` event if it is not registered anymore.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job that changed.
+    async fn announce_job(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        events: Arc<EventBus>,
+        id: Uuid,
+    ) {
+        let kind = match jobs.read().await.get(&id) {
+            Some(entry) => EventKind::JobChanged {
+                job: Self::stored_job(id, entry),
+            },
+            None => EventKind::JobRemoved,
+        };
+        events.emit(id, kind);
+    }
+
+    /// Follows a primary, mirroring its jobs and its run history from its events.
+    ///
+    /// The follower reads a snapshot of the primary first, across every namespace, then the events the primary emitted since, every `FOLLOW_POLL_INTERVAL`,
+    /// applying the `JobChanged`, `JobRemoved` and `RunRecorded` events and publishing every event on its own bus. It reads a snapshot
+    /// again whenever it cannot catch up with the events, e.g. because the primary restarted, and keeps trying while the primary is down.
+    ///
+    /// # Arguments
+    ///
+    /// * `primary` - A string that represents the name of the primary.
+    /// * `path` - A `PathBuf` that represents the command path of the primary.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store the mirrored jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the mirrored runs.
+    /// * `events` - An `Arc<EventBus>` that is used to publish the mirrored events.
+    async fn follow_primary(
+        primary: String,
+        path: PathBuf,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+    ) {
+        let mut after = None;
+        loop {
+            let (name, path) = (primary.clone(), path.clone());
+            let response = spawn_blocking(move || {
+                CommandClient::new(name, path)
+                    .and_then(|mut cc| {
+                        cc.set_namespace(Some(ALL_NAMESPACES.to_string()));
+                        match after {
+                            Some(after) => cc.follow_events(after),
+                            None => cc.snapshot(),
+                        }
+                    })
+                    .ok()
+            })
+            .await
+            .ok()
+            .flatten();
+            match response {
+                Some(CommandResponse::Snapshot(snapshot)) => {
+                    after = Some(snapshot.seq);
+                    Self::mirror_snapshot(jobs.clone(), history.clone(), snapshot).await;
+                    continue;
+                }
+                Some(CommandResponse::EventFeed(feed)) if feed.missed => {
+                    after = None;
+                    continue;
+                }
+                Some(CommandResponse::EventFeed(feed)) => {
+                    for event in feed.events {
+                        after = Some(event.seq);
+                        Self::mirror_event(jobs.clone(), history.clone(), &events, event).await;
+                    }
+                }
+                // The primary is down, or too old to be followed: try again later.
+                _ => {}
+            }
+            sleep(FOLLOW_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Replaces the mirrored jobs and run history of a follower with a snapshot of its primary.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store the mirrored jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the mirrored runs.
+    /// * `snapshot` - A `ServiceSnapshot` that represents the state of the primary.
+    async fn mirror_snapshot(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        snapshot: ServiceSnapshot,
+    ) {
+        let replaced: Vec<_> = {
+            let mut jobs = jobs.write().await;
+            let replaced = jobs.drain().map(|(id, _)| id).collect();
+            for stored in snapshot.jobs {
+                jobs.insert(
+                    stored.id,
+                    JobEntry {
+                        paused: stored.paused,
+                        ..JobEntry::new(stored.id, stored.trigger, stored.job, stored.options)
+                    },
+                );
+            }
+            replaced
+        };
+        let mut history = history.write().await;
+        for id in replaced.iter().chain(snapshot.runs.keys()) {
+            history.remove(id);
+        }
+        for (id, runs) in snapshot.runs {
+            for run in runs {
+                history.record(id, run);
+            }
+        }
+    }
+
+    /// Applies an event of its primary to a follower, then publishes it on the bus of the follower.
+    ///
+    /// A run already mirrored, e.g. by the snapshot taken while it was recorded, is not recorded twice.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store the mirrored jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the mirrored runs.
+    /// * `events` - A reference to the `EventBus` of the follower.
+    /// * `event` - An `Event` that represents what happened on the primary.
+    async fn mirror_event(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: &EventBus,
+        event: Event,
+    ) {
+        if let Ok(id) = Uuid::parse_str(&event.job_id) {
+            match &event.kind {
+                EventKind::JobChanged { job } => {
+                    let entry = JobEntry {
+                        paused: job.paused,
+                        ..JobEntry::new(
+                            id,
+                            job.trigger.clone(),
+                            job.job.clone(),
+                            job.options.clone(),
+                        )
+                    };
+                    jobs.write().await.insert(id, entry);
+                }
+                EventKind::JobRemoved => {
+                    jobs.write().await.remove(&id);
+                    history.write().await.remove(&id);
+                }
+                EventKind::RunRecorded { record } => {
+                    let mut history = history.write().await;
+                    if !history.runs(&id).contains(record) {
+                        history.record(id, record.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        events.publish(event);
+    }
+
+    /// Watches the jobs expected to succeed within a window, emitting a `RunOverdue` event for a job whose last successful run finished
+    /// longer ago than its `expect_within` window, e.g. a job whose schedule never fires.
+    ///
+    /// The window is counted from the time the job was registered or last resumed until it first succeeds. An overdue job is reported
+    /// again every window until it succeeds, and a paused job is not watched.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to look up the last successful run of the jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of the jobs.
+    async fn watch_overdue_jobs(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+    ) {
+        let mut reported: HashMap<Uuid, u64> = HashMap::new();
+        loop {
+            sleep(OVERDUE_CHECK_INTERVAL).await;
+            let watched: Vec<_> = jobs
+                .read()
+                .await
+                .iter()
+                .filter(|(_, entry)| !entry.paused)
+                .filter_map(|(id, entry)| {
+                    Some((*id, entry.options.expect_within?, entry.watched_since))
+                })
+                .collect();
+            reported.retain(|id, _| watched.iter().any(|(watched, _, _)| watched == id));
+            let now = Utc::now().timestamp() as u64;
+            let history = history.read().await;
+            for (id, expect_within, watched_since) in watched {
+                let last_success = history.last_success(&id);
+                let since = last_success
+                    .unwrap_or_default()
+                    .max(watched_since)
+                    .max(reported.get(&id).copied().unwrap_or_default());
+                if now.saturating_sub(since) >= expect_within.as_secs() {
+                    events.emit(
+                        id,
+                        EventKind::RunOverdue {
+                            expect_within,
+                            last_success,
+                        },
+                    );
+                    reported.insert(id, now);
+                }
+            }
+        }
+    }
+
+    /// Backs up the state of the service, taking a snapshot of its jobs at every interval of the backup settings.
+    ///
+    /// Every snapshot is recorded as a `backup:snapshot` system run, followed by the run of the upload hook, if any, once the snapshot is
+    /// written. The backups follow the settings of reloaded configurations, and are suspended while the configuration has none.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string that represents the name of the service, prefixed to its snapshots.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of the backups.
+    /// * `backup` - A `watch::Receiver<Option<BackupConfig>>` that is used to receive the backup settings of the configuration.
+    /// * `key` - An `Option<StoreKey>` that represents the key the snapshots are encrypted with. It is `None` if they are written in clear.
+    async fn back_up_state(
+        name: String,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        mut backup: watch::Receiver<Option<BackupConfig>>,
+        key: Option<StoreKey>,
+    ) {
+        loop {
+            let Some(config) = backup.borrow_and_update().clone() else {
+                if backup.changed().await.is_err() {
+                    return;
+                }
+                continue;
+            };
+            tokio::select! {
+                _ = sleep(config.interval) => {}
+                changed = backup.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    continue;
+                }
+            }
+            let stored = Self::stored_jobs(&*jobs.read().await);
+            let started_at = Utc::now();
+            let started = Instant::now();
+            let snapshot_config = config.clone();
+            let snapshot_name = name.clone();
+            let snapshot_key = key.clone();
+            let result = spawn_blocking(move || {
+                snapshot(
+                    &snapshot_config,
+                    &snapshot_name,
+                    &stored,
+                    snapshot_key.as_ref(),
+                )
+            })
+            .await
+            .unwrap_or_else(|_| Err("Snapshot panicked".to_string()));
+            let (status, error, file) = match &result {
+                Ok(file) => (
+                    RunStatus::Succeeded,
+                    None,
+                    Some(file.display().to_string().into()),
+                ),
+                Err(message) => (RunStatus::Failed, Some(message.clone()), None),
+            };
+            history.write().await.record(
+                SYSTEM_RUNS,
+                RunRecord {
+                    job_id: "backup:snapshot".to_string(),
+                    run_id: Some(RunId::generate()),
+                    scheduled_at: started_at.timestamp() as u64,
+                    started_at: started_at.timestamp() as u64,
+                    delay: Duration::ZERO,
+                    duration: started.elapsed(),
+                    status,
+                    error,
+                    cost_center: None,
+                    idempotency_key: None,
+                    result: file,
+                    exit_code: None,
+                    window_start: None,
+                    window_end: None,
+                    retry_of: None,
+                    declaration: None,
+                },
+            );
+            if let (Ok(_), Some(upload)) = (result, &config.upload) {
+                let run = run_hook("backup", upload).await;
+                history.write().await.record(SYSTEM_RUNS, run);
+            }
+        }
+    }
+
+    /// Describes a registered job as it is kept in the job store.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    /// * `entry` - A reference to the `JobEntry` of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `StoredJob` - Returns the job as it is kept in the job store.
+    fn stored_job(id: Uuid, entry: &JobEntry) -> StoredJob {
+        StoredJob {
+            id,
+            trigger: entry.trigger.clone(),
+            job: entry.job.clone(),
+            options: entry.options.clone(),
+            paused: entry.paused,
+        }
+    }
+
+    /// Fingerprints the declaration of a registered job, i.e. its payload and its options, which its runs are recorded with, see
+    /// `RunRecord::declaration`.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - A reference to the `JobEntry` of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns the fingerprint of the declaration of the job.
+    fn declaration(entry: &JobEntry) -> String {
+        fingerprint(&serde_json::to_vec(&(&entry.job, &entry.options)).unwrap_or_default())
+    }
+
+    /// Executes a job on one of its ticks, or once the job it runs after has finished successfully.
+    ///
+    /// The execution is skipped if the job is paused or its schedule is not active, expiring the job if its schedule has passed.
+    /// It is also skipped, emitting a `RunSkipped` event, while the job skips on failure and its last failure has not been acknowledged,
+    /// or when it is due on a day that is not a business day of the job, unless the job shifts such runs to the same time on the next
+    /// business day, where they run for the tick they were due for. Otherwise it waits for a random delay within the configured jitter, and the delay injected in chaos mode, and runs the job.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    /// * `scheduler` - A `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    async fn execute_job(
+        id: Uuid,
+        scheduler: JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+    ) {
+        let scheduled_at = Utc::now();
+        let Some(entry) = jobs.read().await.get(&id).cloned() else {
+            return;
+        };
+        if entry.paused {
+            return;
+        }
+        if !entry.options.is_active_at(scheduled_at) {
+            if entry.options.is_expired_at(scheduled_at) {
+                _ = Self::expire_job(&scheduler, jobs, history, events, id).await;
+            }
+            return;
+        }
+        if entry.options.skip_if_failed && entry.unacknowledged_failure {
+            events.emit(
+                id,
+                EventKind::RunSkipped {
+                    reason: "previous run failed and has not been acknowledged".to_string(),
+                },
+            );
+            return;
+        }
+        let runs_at = match entry.options.calendar() {
+            Ok(Some(calendar)) if !calendar.is_business_day(calendar.date_of(scheduled_at)) => {
+                let shifted = match entry.options.on_holiday {
+                    HolidayPolicy::Shift => calendar.next_business_time(scheduled_at),
+                    HolidayPolicy::Skip => None,
+                };
+                let Some(shifted) = shifted else {
+                    events.emit(
+                        id,
+                        EventKind::RunSkipped {
+                            reason: format!(
+                                "{} is not a business day",
+                                calendar.date_of(scheduled_at)
+                            ),
+                        },
+                    );
+                    return;
+                };
+                shifted
+            }
+            Ok(_) => scheduled_at,
+            Err(e) => {
+                events.emit(id, EventKind::RunSkipped { reason: e });
+                return;
+            }
+        };
+        let delay = entry.options.jitter_delay() + chaos::tick_delay();
+        let tick = Self::tick_for(&id, &entry, &*history.read().await, scheduled_at);
+        if runs_at > scheduled_at {
+            Self::sleep_until(runs_at).await;
+            if jobs.read().await.get(&id).is_none_or(|entry| entry.paused) {
+                return;
+            }
+        }
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
+        Self::run_job(
+            id,
+            jobs,
+            history,
+            events,
+            pool,
+            entry.business,
+            runs_at,
+            delay,
+            tick,
+            None,
+        )
+        .await;
+    }
+
+    /// Runs the jobs running once when the service starts, on the `@reboot` macro, once the stored jobs are restored and the start hooks
+    /// have run. They run like the ticks of cron jobs, see `execute_job`, so a paused job or a job outside of its schedule window does
+    /// not run. A job added with `@reboot` while the service is running waits for the next start of the service.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    async fn run_startup_jobs(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+    ) {
+        let startup_jobs: Vec<_> = jobs
+            .read()
+            .await
+            .iter()
+            .filter(|(_, entry)| entry.trigger.at_startup())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in startup_jobs {
+            tokio::spawn(Self::execute_job(
+                id,
+                scheduler.clone(),
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                pool.clone(),
+            ));
+        }
+    }
+
+    /// Computes the tick a run of a job is for.
+    ///
+    /// A run of a job running on a cron schedule is for the last tick of its schedule up to the time the run is due, give or take the
+    /// `TICK_TOLERANCE` of the job scheduler, counting the ticks moved by DST transitions its DST policy runs, so a late run still
+    /// processes the window it was scheduled for, and its window starts at the tick before. The same goes for the occurrences of a job
+    /// running on a recurrence, and the times of a job running on the sun. A run of any other job is for the time it is due,
+    /// and its window starts at the time its previous recorded run was due.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A reference to the `Uuid` of the job.
+    /// * `entry` - A reference to the `JobEntry` of the job.
+    /// * `history` - A reference to the `RunHistory` the runs of jobs are recorded in.
+    /// * `at` - A `DateTime<Utc>` that represents the time the run is due.
+    ///
+    /// # Returns
+    ///
+    /// * `Tick` - Returns the tick of the run.
+    fn tick_for(id: &Uuid, entry: &JobEntry, history: &RunHistory, at: DateTime<Utc>) -> Tick {
+        let period = match (entry.trigger.cron(), entry.trigger.rrule()) {
+            (Some(cron), _) => parse_schedule(cron).ok().and_then(|schedule| {
+                let at = at + TICK_TOLERANCE;
+                match entry.options.timezone.as_deref() {
+                    Some(zone) => dst::period_at(
+                        &schedule,
+                        &parse_timezone(zone).ok()?,
+                        entry.options.on_dst,
+                        at,
+                    ),
+                    None => dst::period_at(&schedule, &Local, entry.options.on_dst, at),
+                }
+            }),
+            (None, Some(rule)) => parse_rrule(rule)
+                .ok()
+                .and_then(|rule| recurrence::period_at(&rule, at + TICK_TOLERANCE)),
+            (None, None) => entry
+                .trigger
+                .sun()
+                .and_then(|sun| sun.period_at(at + TICK_TOLERANCE)),
+        };
+        match period {
+            Some((window_start, window_end)) => Tick {
+                window_start,
+                ..Tick::at(window_end)
+            },
+            None => Tick {
+                window_start: history
+                    .runs(id)
+                    .last()
+                    .and_then(|run| DateTime::from_timestamp(run.scheduled_at as i64, 0))
+                    .unwrap_or(at),
+                ..Tick::at(at)
+            },
+        }
+    }
+
+    /// Runs a job on the worker pool and records the run.
+    ///
+    /// The run first waits for a free slot within the `max_concurrent` limit of the job, then for a free worker of the pool, queued by the
+    /// priority of the job. Depending on the overlap policy of the job, a run beyond the limit of the job or finding every worker busy is
+    /// queued, or skipped, emitting a `RunSkipped` event. A run whose idempotency key already succeeded for the job, or is claimed by a run of it in flight,
+    /// is skipped as well. Once started, the job runs between its hooks, see `JobHooks::run_around`, and the run can be killed with a
+    /// `KillRun` command while it is in flight, and is killed once it has run for the `timeout` of the job, failing as timed out. A run
+    /// still in flight once it has run for the `notify_after` threshold of the job emits a `RunLongRunning` event.
+    /// Once the job has finished, the run is recorded in the run history, charged to the cost center the job has at that time,
+    /// the unacknowledged failure of the job is updated, and an event with the outcome of the run is emitted. A job failing `disable_after`
+    /// times in a row is paused, emitting a `JobDisabled` event, from which the pause is journaled and announced by the command loop. In chaos mode, some runs fail without the job being carried out.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `business` - A `JobBusiness` that represents the business function of the job.
+    /// * `scheduled_at` - A `DateTime<Utc>` that represents the time the run was due.
+    /// * `delay` - A `Duration` that represents the delay applied before the run.
+    /// * `tick` - A `Tick` that represents the tick passed to the business function.
+    /// * `retry_of` - An `Option<RunId>` that represents the id of the failed run the run retries, see `RetryRun`. It is `None` if the
+    ///   run is not a retry.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_job(
+        id: Uuid,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        business: JobBusiness,
+        scheduled_at: DateTime<Utc>,
+        delay: Duration,
+        tick: Tick,
+        retry_of: Option<RunId>,
+    ) {
+        let kill_switch = KillSwitch::default();
+        let output = RunOutput::default();
+        let in_flight = InFlightRun {
+            run_id: tick.run_id,
+            kill_switch: kill_switch.clone(),
+            output: output.clone(),
+        };
+        let Some((running, finished, outputs, hooks, options, declaration)) =
+            jobs.read().await.get(&id).map(|entry| {
+                (
+                    entry.running.clone(),
+                    entry.finished.clone(),
+                    entry.output.clone(),
+                    entry.hooks.clone(),
+                    entry.options.clone(),
+                    Self::declaration(entry),
+                )
+            })
+        else {
+            return;
+        };
+        loop {
+            let freed = finished.notified();
+            {
+                let mut running = Self::lock_running(&running);
+                if options
+                    .max_concurrent
+                    .is_none_or(|max| running.len() < max.get() as usize)
+                {
+                    running.push(in_flight);
+                    break;
+                }
+                if options.on_overlap == OverlapPolicy::Skip {
+                    events.emit_run(
+                        id,
+                        tick.run_id,
+                        EventKind::RunSkipped {
+                            reason: "the job has reached its limit of runs in flight".to_string(),
+                        },
+                    );
+                    return;
+                }
+            }
+            freed.await;
+        }
+        let idempotency_key = options
+            .idempotency_key
+            .as_deref()
+            .map(|key| {
+                let identity = JobIdentity {
+                    id: id.to_string(),
+                    name: options.name.clone(),
+                    ..JobIdentity::default()
+                };
+                template::render(key, tick, &identity)
+            })
+            .transpose();
+        let skip_reason = match &idempotency_key {
+            _ if options.on_overlap == OverlapPolicy::Skip && pool.is_saturated() => {
+                Some("every worker of the service is busy".to_string())
+            }
+            Err(e) => Some(format!("the idempotency key cannot be rendered: {e}")),
+            Ok(Some(key)) if !history.write().await.claim_key(id, key) => Some(format!(
+                "a run with the idempotency key `{key}` already succeeded or is in flight"
+            )),
+            Ok(_) => None,
+        };
+        if let Some(reason) = skip_reason {
+            Self::lock_running(&running).retain(|other| other.run_id != tick.run_id);
+            finished.notify_waiters();
+            events.emit_run(id, tick.run_id, EventKind::RunSkipped { reason });
+            return;
+        }
+        let run_kill_switch = kill_switch.clone();
+        let run_events = events.clone();
+        let run_output = output.clone();
+        let (started_at, duration, result) = pool
+            .run(options.priority, move || {
+                let started_at = Utc::now();
+                let started = Instant::now();
+                run_output.start(tick.run_id);
+                let mut outputs = Self::lock_output(&outputs);
+                if outputs.len() == KEPT_OUTPUTS {
+                    outputs.pop_front();
+                }
+                outputs.push_back(run_output.clone());
+                drop(outputs);
+                let timer = options.timeout.map(|timeout| {
+                    let kill_switch = run_kill_switch.clone();
+                    tokio::spawn(async move {
+                        sleep(timeout).await;
+                        kill_switch.kill();
+                    })
+                });
+                let long_run = options.notify_after.map(|running_for| {
+                    tokio::spawn(async move {
+                        sleep(running_for).await;
+                        run_events.emit_run(
+                            id,
+                            tick.run_id,
+                            EventKind::RunLongRunning { running_for },
+                        );
+                    })
+                });
+                let result = if chaos::fails_run() {
+                    Err("Failure injected by chaos mode".to_string())
+                } else {
+                    hooks.run_around(&business, tick, &run_kill_switch, &run_output)
+                };
+                if let Some(long_run) = long_run {
+                    long_run.abort();
+                }
+                let timed_out = timer.is_some_and(|timer| {
+                    let fired = timer.is_finished();
+                    timer.abort();
+                    fired
+                });
+                let result = match (result, options.timeout) {
+                    (Err(_), Some(timeout)) if timed_out => Err(format!(
+                        "Run timed out after {}",
+                        humantime::format_duration(timeout)
+                    )),
+                    (result, _) => result,
+                };
+                (started_at, started.elapsed(), result)
+            })
+            .await
+            .unwrap_or_else(|| (Utc::now(), Duration::ZERO, Err("Job panicked".to_string())));
+        output.finish();
+        Self::lock_running(&running).retain(|other| other.run_id != tick.run_id);
+        finished.notify_waiters();
+        let cost_center = match jobs.write().await.get_mut(&id) {
+            Some(entry) => {
+                entry.unacknowledged_failure = result.is_err();
+                entry.consecutive_failures = match result {
+                    Ok(()) => 0,
+                    Err(_) => entry.consecutive_failures + 1,
+                };
+                if !entry.paused
+                    && entry
+                        .options
+                        .disable_after
+                        .is_some_and(|limit| entry.consecutive_failures >= limit.get())
+                {
+                    entry.paused = true;
+                    events.emit(
+                        id,
+                        EventKind::JobDisabled {
+                            failures: entry.consecutive_failures,
+                        },
+                    );
+                }
+                entry.options.cost_center.clone()
+            }
+            None => None,
+        };
+        let (status, error) = match result {
+            Ok(()) => {
+                events.emit_run(id, tick.run_id, EventKind::RunSucceeded);
+                (RunStatus::Succeeded, None)
+            }
+            Err(message) => {
+                events.emit_run(
+                    id,
+                    tick.run_id,
+                    EventKind::RunFailed {
+                        message: message.clone(),
+                    },
+                );
+                (RunStatus::Failed, Some(message))
+            }
+        };
+        let record = RunRecord {
+            job_id: id.to_string(),
+            run_id: Some(tick.run_id),
+            scheduled_at: scheduled_at.timestamp() as u64,
+            started_at: started_at.timestamp() as u64,
+            delay,
+            duration,
+            status,
+            error,
+            cost_center,
+            idempotency_key: idempotency_key.unwrap_or_default(),
+            result: output.result(),
+            exit_code: output.exit_code(),
+            window_start: Some(tick.window_start.timestamp() as u64),
+            window_end: Some(tick.window_end.timestamp() as u64),
+            retry_of,
+            declaration: Some(declaration),
+        };
+        history.write().await.record(id, record.clone());
+        events.emit_run(id, tick.run_id, EventKind::RunRecorded { record });
+    }
+
+    /// Dispatches the notifications of jobs to their notifiers.
+    ///
+    /// This function listens for the `RunRecorded` and `RunLongRunning` events of the scheduler, and alerts every notifier of the job the
+    /// event is for, if the job is notified about it, see `JobOptions::notifies`. Every alert is sent on its own, so a slow notifier does
+    /// not hold the others back, and is recorded as a system run under `SYSTEM_RUNS`, whose `job_id` is `notify:<notifier>`. An alert for
+    /// a notifier missing from the configuration fails. The notifiers follow the reloaded configurations.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the alerts.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `notifiers` - A `watch::Receiver<BTreeMap<String, Notifier>>` that is used to receive the notifiers of the configuration.
+    async fn dispatch_notifications(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        mut notifiers: watch::Receiver<BTreeMap<String, Notifier>>,
+    ) {
+        let mut receiver = events.subscribe();
+        loop {
+            let (job_id, run_id, kind) = match receiver.recv().await {
+                Ok(Event {
+                    job_id,
+                    run_id,
+                    kind,
+                    ..
+                }) => (job_id, run_id, kind),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            let options = match Uuid::parse_str(&job_id) {
+                Ok(id) => jobs
+                    .read()
+                    .await
+                    .get(&id)
+                    .map(|entry| entry.options.clone()),
+                Err(_) => None,
+            };
+            let Some(options) = options.filter(|options| !options.notify.is_empty()) else {
+                continue;
+            };
+            let job = options.name.clone().unwrap_or(job_id);
+            let run = run_id
+                .map(|run_id| format!(" (run {run_id})"))
+                .unwrap_or_default();
+            let message = match kind {
+                EventKind::RunRecorded { record } => {
+                    let duration =
+                        humantime::format_duration(Duration::from_secs(record.duration.as_secs()));
+                    match record.status {
+                        RunStatus::Failed if options.notifies(NotifyEvent::Failure) => format!(
+                            "cronus: job `{job}`{run} failed after {duration}: {}",
+                            record.error.unwrap_or_default()
+                        ),
+                        RunStatus::Succeeded if options.notifies(NotifyEvent::Success) => {
+                            format!("cronus: job `{job}`{run} succeeded in {duration}")
+                        }
+                        _ => continue,
+                    }
+                }
+                EventKind::RunLongRunning { running_for } => format!(
+                    "cronus: job `{job}`{run} has been running for {}",
+                    humantime::format_duration(running_for)
+                ),
+                _ => continue,
+            };
+            let configured = notifiers.borrow_and_update().clone();
+            for name in options.notify {
+                let notifier = configured.get(&name).cloned();
+                let history = history.clone();
+                let message = message.clone();
+                tokio::spawn(async move {
+                    let started_at = Utc::now();
+                    let started = Instant::now();
+                    let result = match notifier {
+                        Some(notifier) => spawn_blocking(move || notifier.send(&message))
+                            .await
+                            .unwrap_or_else(|_| Err("Notifier panicked".to_string())),
+                        None => Err(format!("Notifier `{name}` is not configured")),
+                    };
+                    let (status, error) = match result {
+                        Ok(()) => (RunStatus::Succeeded, None),
+                        Err(message) => (RunStatus::Failed, Some(message)),
+                    };
+                    history.write().await.record(
+                        SYSTEM_RUNS,
+                        RunRecord {
+                            job_id: format!("notify:{name}"),
+                            run_id: Some(RunId::generate()),
+                            scheduled_at: started_at.timestamp() as u64,
+                            started_at: started_at.timestamp() as u64,
+                            delay: Duration::ZERO,
+                            duration: started.elapsed(),
+                            status,
+                            error,
+                            cost_center: None,
+                            idempotency_key: None,
+                            result: None,
+                            exit_code: None,
+                            window_start: None,
+                            window_end: None,
+                            retry_of: None,
+                            declaration: None,
+                        },
+                    );
+                });
+            }
+        }
+    }
+
+    /// Exports the runs of jobs as spans, and the metrics of the scheduler, to an OpenTelemetry collector.
+    ///
+    /// This function listens for the `RunRecorded` and `RunSkipped` events of the scheduler, gathering a span for every recorded run, see
+    /// `otel::run_span`, and accounting every run for the metrics. Every `interval` of the exporter, the spans gathered are exported, then
+    /// the metrics, sampled from the jobs. An export runs on its own, so a slow collector does not hold the events back, and an export that
+    /// fails is recorded as a system run under `SYSTEM_RUNS`, whose `job_id` is `otel:traces` or `otel:metrics`, its spans being dropped.
+    /// The exporter follows the reloaded configurations, and gathers no spans while none is configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the failed exports.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `otel` - A `watch::Receiver<Option<OtelConfig>>` that is used to receive the telemetry exporter of the configuration.
+    #[cfg(feature = "otel")]
+    async fn export_telemetry(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        mut otel: watch::Receiver<Option<OtelConfig>>,
+    ) {
+        let mut receiver = events.subscribe();
+        let mut config = otel.borrow_and_update().clone();
+        let mut next_export = tokio::time::Instant::now()
+            + config
+                .as_ref()
+                .map_or(Duration::ZERO, |config| config.interval);
+        let mut spans = Vec::new();
+        let mut runs = RunMetrics::default();
+        loop {
+            tokio::select! {
+                event = receiver.recv() => match event {
+                    Ok(Event { job_id, kind: EventKind::RunRecorded { record }, .. }) => {
+                        runs.record(&record);
+                        if config.is_some() {
+                            let name = match Uuid::parse_str(&job_id) {
+                                Ok(id) => jobs
+                                    .read()
+                                    .await
+                                    .get(&id)
+                                    .and_then(|entry| entry.options.name.clone()),
+                                Err(_) => None,
+                            };
+                            spans.push(otel::run_span(&record, name.as_deref()));
+                        }
+                    }
+                    Ok(Event { kind: EventKind::RunSkipped { .. }, .. }) => runs.skip(),
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                },
+                changed = otel.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    config = otel.borrow_and_update().clone();
+                    if config.is_none() {
+                        spans.clear();
+                    }
+                    next_export = tokio::time::Instant::now()
+                        + config.as_ref().map_or(Duration::ZERO, |config| config.interval);
+                }
+                _ = tokio::time::sleep_until(next_export), if config.is_some() => {
+                    let Some(config) = config.clone() else {
+                        continue;
+                    };
+                    next_export = tokio::time::Instant::now() + config.interval;
+                    let mut gauges = SchedulerGauges::default();
+                    for entry in jobs.read().await.values() {
+                        if entry.paused {
+                            gauges.paused_jobs += 1;
+                        } else {
+                            gauges.active_jobs += 1;
+                        }
+                        gauges.runs_in_flight += Self::lock_running(&entry.running).len() as u64;
+                    }
+                    let spans = std::mem::take(&mut spans);
+                    let runs = runs.clone();
+                    let history = history.clone();
+                    tokio::spawn(async move {
+                        let started_at = Utc::now();
+                        let started = Instant::now();
+                        let exported = spawn_blocking(move || {
+                            let traces = if spans.is_empty() {
+                                Ok(())
+                            } else {
+                                config.export_spans(spans)
+                            };
+                            [
+                                ("traces", traces),
+                                ("metrics", config.export_metrics(&gauges, &runs)),
+                            ]
+                        })
+                        .await
+                        .unwrap_or_else(|_| {
+                            let panicked = || Err("Exporter panicked".to_string());
+                            [("traces", panicked()), ("metrics", panicked())]
+                        });
+                        for (signal, result) in exported {
+                            let Err(error) = result else {
+                                continue;
+                            };
+                            history.write().await.record(
+                                SYSTEM_RUNS,
+                                RunRecord {
+                                    job_id: format!("otel:{signal}"),
+                                    run_id: Some(RunId::generate()),
+                                    scheduled_at: started_at.timestamp() as u64,
+                                    started_at: started_at.timestamp() as u64,
+                                    delay: Duration::ZERO,
+                                    duration: started.elapsed(),
+                                    status: RunStatus::Failed,
+                                    error: Some(error),
+                                    cost_center: None,
+                                    idempotency_key: None,
+                                    result: None,
+                                    exit_code: None,
+                                    window_start: None,
+                                    window_end: None,
+                                    retry_of: None,
+                                    declaration: None,
+                                },
+                            );
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    /// Dispatches the completions of jobs to the jobs that run after them.
+    ///
+    /// This function listens for the `RunSucceeded` events of the scheduler, and executes every job running after the job that succeeded,
+    /// whether it refers to that job by its id or by its name, as long as both jobs are in the same namespace. Completions missed because
+    /// the listener lagged behind are not replayed.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    async fn dispatch_chains(
+        scheduler: JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+    ) {
+        let mut receiver = events.subscribe();
+        loop {
+            let job_id = match receiver.recv().await {
+                Ok(Event {
+                    job_id,
+                    kind: EventKind::RunSucceeded,
+                    ..
+                }) => job_id,
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            let dependents: Vec<_> = {
+                let jobs = jobs.read().await;
+                let Some(finished) = Uuid::parse_str(&job_id).ok().and_then(|id| jobs.get(&id))
+                else {
+                    continue;
+                };
+                let name = finished.options.name.as_deref();
+                let namespace = finished
+                    .options
+                    .namespace
+                    .as_deref()
+                    .unwrap_or(DEFAULT_NAMESPACE);
+                jobs.iter()
+                    .filter(|(_, entry)| {
+                        entry
+                            .options
+                            .namespace
+                            .as_deref()
+                            .unwrap_or(DEFAULT_NAMESPACE)
+                            == namespace
+                            && entry
+                                .trigger
+                                .after()
+                                .is_some_and(|after| after == job_id || Some(after) == name)
+                    })
+                    .map(|(id, _)| *id)
+                    .collect()
+            };
+            for id in dependents {
+                tokio::spawn(Self::execute_job(
+                    id,
+                    scheduler.clone(),
+                    jobs.clone(),
+                    history.clone(),
+                    events.clone(),
+                    pool.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Expires a job whose schedule has passed.
+    ///
+    /// Depending on the expiry action the job is registered with, the job is either removed like a `DeleteJob` command would, or paused. Either way a
+    /// `JobExpired` event is emitted, from which the change is journaled and announced by the command loop.
+    /// Nothing happens if the job is gone or its `not_after` time has not passed, e.g. because the job was updated since the timer was started.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job to be expired.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if successful, or an error if not.
+    async fn expire_job(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        id: Uuid,
+    ) -> CronusResult<()> {
+        let expired = jobs.read().await.get(&id).and_then(|entry| {
+            entry
+                .options
+                .not_after
+                .filter(|not_after| *not_after <= Utc::now())
+                .map(|_| entry.options.on_expiry)
+        });
+        let Some(on_expiry) = expired else {
+            return Ok(());
+        };
+        match on_expiry {
+            ExpiryAction::Remove => {
+                Self::handle_cmd_delete_job(scheduler, jobs.clone(), history, id).await?;
+            }
+            ExpiryAction::Pause => {
+                if let Some(entry) = jobs.write().await.get_mut(&id) {
+                    entry.paused = true;
+                }
+            }
+        }
+        events.emit(id, EventKind::JobExpired { action: on_expiry });
+        Ok(())
+    }
+
+    /// Handles the `ListJobs` command.
+    ///
+    /// This function describes the jobs matching the filter, see `describe_jobs`, and sorts them.
+    /// It then returns a `CommandResponse::JobList` that contains the `JobInfo` objects from the offset, or a `CommandResponse::JobPage`
+    /// that contains at most as many of them as the limit, with the offset of the next page.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `offset` - A `usize` that represents the number of jobs skipped.
+    /// * `limit` - An `Option<usize>` that represents the maximum number of jobs listed, or `None` for every job.
+    /// * `filter` - A reference to the `JobFilter` the jobs listed match.
+    /// * `sort` - A `JobSort` that represents the order the jobs are listed in.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobList` or a `CommandResponse::JobPage` if successful, or an error if not.
+    async fn handle_cmd_list_job(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        offset: usize,
+        limit: Option<usize>,
+        filter: &JobFilter,
+        sort: JobSort,
+    ) -> CronusResult<CommandResponse> {
+        let mut job_list = Self::describe_jobs(jobs, history, filter).await?;
+        sort_jobs(&mut job_list, sort);
+        Ok(match limit {
+            Some(limit) => CommandResponse::JobPage(page_jobs(job_list, offset, limit)),
+            None => CommandResponse::JobList(job_list.into_iter().skip(offset).collect()),
+        })
+    }
+
+    /// Describes the jobs matching a filter.
+    ///
+    /// This function retrieves the jobs from the jobs map, along with the ticks of the jobs running on a cron schedule cached as the job
+    /// scheduler fires them, see `CronTicks`, so the jobs are described from memory without locking the job scheduler.
+    /// It creates a `JobInfo` object for each job, which includes the job's ID, cron schedule, last run time, next run time, and the job itself.
+    /// The estimated duration of the runs of the job is included as well, with whether the next run is estimated to overrun the tick after it,
+    /// and the result of its last run. The jobs not matching the filter are left out before they are described.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs. Jobs running after another job, or not scheduled, like the jobs mirrored by a follower, take their last run time from it, and have no next run time.
+    /// * `filter` - A reference to the `JobFilter` the jobs described match.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<Vec<JobInfo>>` - Returns a `CronusResult` that contains the `JobInfo` objects, in no particular order, if successful, or an error if not.
+    async fn describe_jobs(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        filter: &JobFilter,
+    ) -> CronusResult<Vec<JobInfo>> {
+        let mut job_list = Vec::new();
+        let jobs: Vec<_> = jobs
+            .read()
+            .await
+            .iter()
+            .filter(|(_, entry)| filter.matches(&entry.job, &entry.options))
+            .map(|(id, entry)| (*id, entry.clone()))
+            .collect();
+        let history = history.read().await;
+        for (
+            id,
+            JobEntry {
+                trigger,
+                job,
+                options,
+                paused,
+                unacknowledged_failure,
+                running,
+                ticks,
+                ..
+            },
+        ) in jobs
+        {
+            let (last_run, next_run) = match &trigger {
+                Trigger::Cron(cron) => {
+                    let cached = *Self::lock_ticks(&ticks);
+                    let (last_run, next_tick) = match cached {
+                        Some(ticks) => (ticks.last, ticks.next),
+                        None => (history.runs(&id).last().map(|run| run.started_at), None),
+                    };
+                    let displaced = match parse_schedule(cron) {
+                        Ok(schedule) if options.on_dst != DstPolicy::Skip => {
+                            match options.timezone.as_deref().map(parse_timezone) {
+                                Some(Ok(tz)) => {
+                                    DstRuns::new(schedule, tz, options.on_dst, Utc::now()).next()
+                                }
+                                _ => {
+                                    DstRuns::new(schedule, Local, options.on_dst, Utc::now()).next()
+                                }
+                            }
+                        }
+                        _ => None,
+                    };
+                    let displaced = displaced.map(|at| at.timestamp() as u64);
+                    (last_run, next_tick.into_iter().chain(displaced).min())
+                }
+                Trigger::Rrule(rule) => (
+                    history.runs(&id).last().map(|run| run.started_at),
+                    parse_rrule(rule)
+                        .ok()
+                        .and_then(|rule| following_occurrence(&rule, Utc::now()))
+                        .map(|occurrence| occurrence.timestamp() as u64),
+                ),
+                Trigger::Sun(sun) => (
+                    history.runs(&id).last().map(|run| run.started_at),
+                    sun.following(Utc::now())
+                        .map(|time| time.timestamp() as u64),
+                ),
+                Trigger::After(_) | Trigger::Event(_) | Trigger::Manual => {
+                    (history.runs(&id).last().map(|run| run.started_at), None)
+                }
+            };
+            let estimated_duration = history.estimated_duration(&id);
+            let overruns_next_tick = match (trigger.cron().map(parse_schedule), next_run) {
+                (Some(Ok(schedule)), Some(next_run)) => {
+                    let next_run = DateTime::from_timestamp(next_run as i64, 0).unwrap_or_default();
+                    let following = match options.timezone.as_deref().map(parse_timezone) {
+                        Some(Ok(tz)) => following_tick(&schedule, &tz, next_run),
+                        _ => following_tick(&schedule, &Local, next_run),
+                    };
+                    let estimated_end = estimated_duration
+                        .and_then(|estimate| chrono::Duration::from_std(estimate).ok())
+                        .map(|estimate| next_run + estimate);
+                    estimated_end
+                        .zip(following)
+                        .is_some_and(|(end, following)| end >= following)
+                }
+                _ => false,
+            };
+            let job = JobInfo {
+                id: id.to_string(),
+                trigger,
+                last_run,
+                next_run,
+                job,
+                options,
+                paused,
+                unacknowledged_failure,
+                estimated_duration,
+                overruns_next_tick,
+                last_result: history.runs(&id).last().and_then(|run| run.result.clone()),
+                runs_in_flight: Self::lock_running(&running).len(),
+                agent: None,
+            };
+            job_list.push(job);
+        }
+        Ok(job_list)
+    }
+
+    /// Handles the `GetJob` command.
+    ///
+    /// This function describes a single job like the `ListJobs` command does, with the result of its last run.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobDetail` if successful, or an error if not.
+    async fn handle_cmd_get_job(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        id: Uuid,
+    ) -> CronusResult<CommandResponse> {
+        let job = Self::describe_jobs(jobs, history, &JobFilter::default())
+            .await?
+            .into_iter()
+            .find(|job| job.id == id.to_string());
+        Ok(CommandResponse::JobDetail(job))
+    }
+
+    /// Handles the `DeleteJob` command.
+    ///
+    /// This function removes a job from the job scheduler, the jobs map and the run history.
+    /// It uses the job's ID to find and remove the job.
+    /// Jobs running after the deleted job stay registered, and do not run until a job they refer to by name is added again.
+    /// The runs of the deleted job in flight are killed, and its state is forgotten.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job to be deleted.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobDeleted` if successful, or an error if not.
+    async fn handle_cmd_delete_job(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        id: Uuid,
+    ) -> CronusResult<CommandResponse> {
+        let removed = Self::unregister_job(scheduler, jobs, id).await?;
+        if let Some(entry) = removed {
+            Self::forget_job(history, id, &entry).await;
+        }
+        Ok(CommandResponse::JobDeleted)
+    }
+
+    /// Unregisters a job, so it is not run anymore, leaving its runs in flight and its run history alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job to be unregistered.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<Option<JobEntry>>` - Returns a `CronusResult` that contains the entry of the job, or `None` if no job has the id, if successful, or an error if not.
+    async fn unregister_job(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        id: Uuid,
+    ) -> CronusResult<Option<JobEntry>> {
+        let removed = jobs.write().await.remove(&id);
+        if let Some(entry) = &removed {
+            entry
+                .recurrence
+                .iter()
+                .chain(&entry.expiry)
+                .for_each(|task| task.abort());
+        }
+        if removed
+            .as_ref()
+            .is_some_and(|entry| entry.trigger.cron().is_some())
+        {
+            scheduler.remove(&id).await?;
+        }
+        Ok(removed)
+    }
+
+    /// Forgets an unregistered job, killing its runs in flight and removing its run history and its state.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    /// * `entry` - A reference to the `JobEntry` the job had, see `unregister_job`.
+    async fn forget_job(history: Arc<RwLock<RunHistory>>, id: Uuid, entry: &JobEntry) {
+        Self::lock_running(&entry.running)
+            .iter()
+            .for_each(InFlightRun::kill);
+        history.write().await.remove(&id);
+        // A state that cannot be written is forgotten in memory, and written along with the next change.
+        _ = state::forget(&id.to_string());
+    }
+
+    /// Handles the `Batch` command.
+    ///
+    /// This function carries out the commands of the batch in order, each one as it would be on its own, so a command sees the changes
+    /// of the commands before it. Once a command fails, is refused or is not a command a batch can undo, see `Command::is_batchable`, the
+    /// changes of the commands before it are undone, see `undo_batch`, and the batch is aborted. The runs in flight of the jobs deleted
+    /// by the batch are only killed, and their run history only forgotten, once every command succeeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `config` - A reference to the `ServiceConfig` whose tokens and policy every command of the batch is checked against.
+    /// * `token` - An `Option<&str>` that represents the token the client presented, if any.
+    /// * `commands` - A vector of `Command` instances that represent the commands of the batch.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Batch` with the responses of the commands, or a `CommandResponse::BatchAborted` naming the failed command, if successful, or an error if the changes cannot be undone.
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_cmd_batch(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        config: &ServiceConfig,
+        token: Option<&str>,
+        commands: Vec<Command>,
+    ) -> CronusResult<CommandResponse> {
+        let mut changes = Vec::new();
+        let mut responses = Vec::new();
+        for (index, cmd) in commands.into_iter().enumerate() {
+            let res = Self::handle_batch_command(
+                scheduler,
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                pool.clone(),
+                config,
+                token,
+                cmd,
+                &mut changes,
+            )
+            .await;
+            match res {
+                Ok(res) => responses.push(res),
+                Err(e) => {
+                    Self::undo_batch(scheduler, jobs, history, events, pool, changes).await?;
+                    return Ok(CommandResponse::BatchAborted(index, e.to_string()));
+                }
+            }
+        }
+        for change in changes {
+            if let BatchChange::Deleted(id, entry) = change {
+                Self::forget_job(history.clone(), id, &entry).await;
+            }
+        }
+        Ok(CommandResponse::Batch(responses))
+    }
+
+    /// Carries out a command of a batch, recording the change it makes so it can be undone.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `config` - A reference to the `ServiceConfig` whose tokens and policy the command is checked against.
+    /// * `token` - An `Option<&str>` that represents the token the client presented, if any.
+    /// * `cmd` - A `Command` that represents the command.
+    /// * `changes` - A mutable reference to the changes carried out by the batch so far, the change of the command is pushed to.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains the response of the command if it succeeded, or an error describing why it failed or was refused.
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_batch_command(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        config: &ServiceConfig,
+        token: Option<&str>,
+        cmd: Command,
+        changes: &mut Vec<BatchChange>,
+    ) -> CronusResult<CommandResponse> {
+        if !cmd.is_batchable() {
+            let cmd = serde_json::to_value(&cmd)?;
+            let name = cmd["command"].as_str().unwrap_or_default();
+            return Err(format!(
+                "Command `{name}` cannot be batched, expected adding, updating, deleting, pausing or resuming a job"
+            )
+            .into());
+        }
+        authorize(&config.tokens, token, &cmd)?;
+        if let Command::AddJob { job, options, .. } | Command::UpdateJob { job, options, .. } = &cmd
+        {
+            config.policy.check(job, options)?;
+        }
+        Self::check_preflight(&cmd)?;
+        Self::check_quota(&config.quota, jobs.clone(), &cmd).await?;
+        let previous = {
+            let jobs = jobs.read().await;
+            let target = match &cmd {
+                Command::AddJob { options, .. } => Self::keyed_job(&jobs, options),
+                cmd => Self::changed_job(cmd),
+            };
+            target.and_then(|id| Some((id, jobs.get(&id)?.clone())))
+        };
+        let res = match cmd {
+            Command::AddJob {
+                trigger,
+                job,
+                options,
+                no_duplicates,
+                paused,
+                dry_run,
+            } => {
+                Self::handle_cmd_add_job(
+                    scheduler,
+                    jobs,
+                    history,
+                    events,
+                    pool,
+                    trigger,
+                    job,
+                    options,
+                    no_duplicates,
+                    paused,
+                    dry_run,
+                )
+                .await?
+            }
+            Command::UpdateJob {
+                id,
+                trigger,
+                job,
+                options,
+            } => {
+                Self::handle_cmd_update_job(
+                    scheduler,
+                    jobs,
+                    history,
+                    events,
+                    pool,
+                    Uuid::parse_str(&id)?,
+                    trigger,
+                    job,
+                    options,
+                )
+                .await?
+            }
+            Command::DeleteJob { id } => {
+                let id = Uuid::parse_str(&id)?;
+                let removed = Self::unregister_job(scheduler, jobs, id).await?;
+                if let Some(entry) = removed {
+                    changes.push(BatchChange::Deleted(id, entry));
+                }
+                return Ok(CommandResponse::JobDeleted);
+            }
+            Command::PauseJob { id } => {
+                Self::handle_cmd_pause_job(jobs, Uuid::parse_str(&id)?, true).await?
+            }
+            Command::ResumeJob { id } => {
+                Self::handle_cmd_pause_job(jobs, Uuid::parse_str(&id)?, false).await?
+            }
+            _ => unreachable!("the command is batchable"),
+        };
+        match (&res, previous) {
+            (CommandResponse::JobAdded(id) | CommandResponse::JobDuplicated(id, _), _) => {
+                changes.push(BatchChange::Added(Uuid::parse_str(id)?));
+            }
+            (CommandResponse::JobUpdated | CommandResponse::JobReplaced(_), Some((id, entry))) => {
+                changes.push(BatchChange::Updated(id, entry));
+            }
+            (CommandResponse::JobPaused | CommandResponse::JobResumed, Some((id, entry))) => {
+                changes.push(BatchChange::Paused(id, entry));
+            }
+            (CommandResponse::JobPaused | CommandResponse::JobResumed, None) => {}
+            (res, _) => {
+                let res = res.to_json();
+                let error = res["error"].as_str().map(str::to_string);
+                return Err(error.unwrap_or_else(|| res.to_string()).into());
+            }
+        }
+        Ok(res)
+    }
+
+    /// Undoes the changes of a batch, the latest first, so the jobs are left as they were before the batch.
+    ///
+    /// The jobs added by the batch are deleted, and the jobs updated or deleted by it are registered again with their ids, their former
+    /// declarations and their state, e.g. whether they are paused.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `changes` - A vector of `BatchChange` instances that represent the changes carried out by the batch, in order.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if successful, or an error if a job cannot be registered again.
+    async fn undo_batch(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        changes: Vec<BatchChange>,
+    ) -> CronusResult<()> {
+        for change in changes.into_iter().rev() {
+            match change {
+                BatchChange::Added(id) => {
+                    Self::handle_cmd_delete_job(scheduler, jobs.clone(), history.clone(), id)
+                        .await?;
+                }
+                BatchChange::Updated(id, entry) | BatchChange::Deleted(id, entry) => {
+                    Self::register_job(
+                        scheduler,
+                        jobs.clone(),
+                        history.clone(),
+                        events.clone(),
+                        pool.clone(),
+                        id,
+                        entry.trigger.clone(),
+                        entry.job.clone(),
+                        entry.options.clone(),
+                        entry.paused,
+                    )
+                    .await?;
+                    if let Some(current) = jobs.write().await.get_mut(&id) {
+                        *current = JobEntry {
+                            recurrence: current.recurrence.take(),
+                            expiry: current.expiry.take(),
+                            ..entry
+                        };
+                    }
+                }
+                BatchChange::Paused(id, entry) => {
+                    if let Some(current) = jobs.write().await.get_mut(&id) {
+                        current.paused = entry.paused;
+                        current.watched_since = entry.watched_since;
+                        current.consecutive_failures = entry.consecutive_failures;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the `JobHistory` command.
+    ///
+    /// This function retrieves the recorded runs of a job from the run history.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::RunList` if successful, or an error if not.
+    async fn handle_cmd_job_history(
+        history: Arc<RwLock<RunHistory>>,
+        id: Uuid,
+    ) -> CronusResult<CommandResponse> {
+        Ok(CommandResponse::RunList(history.read().await.runs(&id)))
+    }
+
+    /// Handles the `LintJobs` command.
+    ///
+    /// This function lints every registered job of the scope of the client, using the run history to compare schedules against the estimated duration of their runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::LintReport` if successful, or an error if not.
+    async fn handle_cmd_lint_jobs(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        scope: &Scope,
+    ) -> CronusResult<CommandResponse> {
+        let jobs = jobs.read().await;
+        let history = history.read().await;
+        let findings = jobs
+            .iter()
+            .filter(|(_, entry)| scope.contains(entry.options.namespace.as_deref()))
+            .flat_map(|(id, entry)| {
+                lint_job(
+                    &id.to_string(),
+                    &entry.trigger,
+                    &entry.job,
+                    &entry.options,
+                    history.estimated_duration(id),
+                )
+            })
+            .collect();
+        Ok(CommandResponse::LintReport(findings))
+    }
+
+    /// Handles the `AnalyzeSchedules` command.
+    ///
+    /// This function projects the schedules of all registered cron jobs and reports the times of day where many of them fire simultaneously.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs. The duration of hotspots is estimated from it.
+    /// * `window` - A `Duration` that represents how far ahead the schedules are projected.
+    /// * `threshold` - A `usize` that represents the number of distinct jobs firing together that makes a hotspot.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Hotspots` if successful, or an error if not.
+    async fn handle_cmd_analyze_schedules(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        scope: &Scope,
+        window: Duration,
+        threshold: usize,
+    ) -> CronusResult<CommandResponse> {
+        let history = history.read().await;
+        let analyzed: Vec<_> = jobs
+            .read()
+            .await
+            .iter()
+            .filter(|(_, entry)| scope.contains(entry.options.namespace.as_deref()))
+            .filter_map(|(id, entry)| {
+                Some(AnalyzedJob {
+                    job: id.to_string(),
+                    cron: entry.trigger.cron()?.to_string(),
+                    jitter: entry.options.jitter,
+                    estimated_duration: history.estimated_duration(id),
+                })
+            })
+            .collect();
+        Ok(CommandResponse::Hotspots(analyze_hotspots(
+            &analyzed, window, threshold,
+        )))
+    }
+
+    /// Handles the `Timeline` command.
+    ///
+    /// This function lays out the recorded runs of all registered jobs within the window before now, and the ticks of the cron jobs within
+    /// the window after now, assumed to last as long as the runs of their job are estimated to take.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `window` - A `Duration` that represents how far back and ahead the executions are laid out.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Timeline` if successful, or an error if not.
+    async fn handle_cmd_timeline(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        scope: &Scope,
+        window: Duration,
+    ) -> CronusResult<CommandResponse> {
+        let history = history.read().await;
+        let timelined: Vec<_> = jobs
+            .read()
+            .await
+            .iter()
+            .filter(|(_, entry)| scope.contains(entry.options.namespace.as_deref()))
+            .map(|(id, entry)| TimelinedJob {
+                job: id.to_string(),
+                label: entry.options.name.clone().unwrap_or_else(|| id.to_string()),
+                cron: entry.trigger.cron().map(String::from),
+                timezone: entry.options.timezone.clone(),
+                paused: entry.paused,
+                runs: history.runs(id),
+                estimated_duration: history.estimated_duration(id),
+            })
+            .collect();
+        Ok(CommandResponse::Timeline(build_timeline(
+            &timelined,
+            Utc::now(),
+            window,
+        )))
+    }
+
+    /// Handles the `AckFailure` command.
+    ///
+    /// This function acknowledges the last failure of a job, so a job that skips on failure is executed again on its next tick.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::FailureAcknowledged` if successful, or an error if not.
+    async fn handle_cmd_ack_failure(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        events: Arc<EventBus>,
+        id: Uuid,
+    ) -> CronusResult<CommandResponse> {
+        if let Some(entry) = jobs.write().await.get_mut(&id) {
+            if entry.unacknowledged_failure {
+                entry.unacknowledged_failure = false;
+                events.emit(id, EventKind::FailureAcknowledged);
+            }
+        }
+        Ok(CommandResponse::FailureAcknowledged)
+    }
+
+    /// Handles the `TriggerJob` command.
+    ///
+    /// This function runs a job right away in the background, like one of its ticks would, except that the run ignores
+    /// whether the job is paused, its schedule window, its unacknowledged failure and its jitter. The run is still subject to the concurrency limits.
+    /// A run of a job running on a cron schedule is for the last tick of its schedule, so triggering the job reprocesses the last window.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job to be run.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobTriggered` with the id of the run if successful, or an error if not.
+    async fn handle_cmd_trigger_job(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        id: Uuid,
+    ) -> CronusResult<CommandResponse> {
+        let now = Utc::now();
+        let run = match jobs.read().await.get(&id) {
+            Some(entry) => Some((
+                entry.business.clone(),
+                Self::tick_for(&id, entry, &*history.read().await, now),
+            )),
+            None => None,
+        };
+        let run_id = run.as_ref().map(|(_, tick)| tick.run_id);
+        if let Some((business, tick)) = run {
+            tokio::spawn(Self::run_job(
+                id,
+                jobs,
+                history,
+                events,
+                pool,
+                business,
+                now,
+                Duration::ZERO,
+                tick,
+                None,
+            ));
+        }
+        Ok(CommandResponse::JobTriggered(run_id))
+    }
+
+    /// Handles the `RetryRun` command.
+    ///
+    /// This function runs the job of a failed run again right away in the background, like `TriggerJob` does, except that the run is for
+    /// the tick of the failed run rather than the last tick of the schedule of the job, so the placeholders of the job render the same
+    /// values and its processes get the same `CRONUS_SCHEDULED_TIME`, `CRONUS_WINDOW_START` and `CRONUS_WINDOW_END` as the failed run.
+    /// The run is recorded with the id of the failed run as its `retry_of`. A run recorded before the windows of runs were is retried
+    /// for the time it was due. The job must still have the payload and the options the failed run ran with, see `declaration`, so the
+    /// retry runs the same invocation: a run of a job updated since is refused, as is a run recorded before declarations were.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `run_id` - A `RunId` that represents the ID of the failed run.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::RunRetried` with the id of the job
+    ///   and the id of the new run if successful, an `Error` if the run is not recorded, did not fail, is not the run of a registered
+    ///   job or ran another declaration of the job, or an error if not.
+    async fn handle_cmd_retry_run(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        run_id: RunId,
+    ) -> CronusResult<CommandResponse> {
+        let Some((id, run)) = history.read().await.find_run(run_id) else {
+            return Ok(CommandResponse::Error {
+                code: ErrorCode::NotFound,
+                message: format!("No run `{run_id}`"),
+            });
+        };
+        if id == SYSTEM_RUNS {
+            return Ok(CommandResponse::Error {
+                code: ErrorCode::Rejected,
+                message: format!(
+                    "Run `{run_id}` is a system run of `{}`, only the runs of jobs can be retried",
+                    run.job_id
+                ),
+            });
+        }
+        if run.status != RunStatus::Failed {
+            return Ok(CommandResponse::Error {
+                code: ErrorCode::Rejected,
+                message: format!("Run `{run_id}` succeeded, only a failed run can be retried"),
+            });
+        }
+        let Some((business, declaration)) = jobs
+            .read()
+            .await
+            .get(&id)
+            .map(|entry| (entry.business.clone(), Self::declaration(entry)))
+        else {
+            return Ok(CommandResponse::Error {
+                code: ErrorCode::NotFound,
+                message: format!("Job `{id}` of run `{run_id}` is not registered anymore"),
+            });
+        };
+        if run.declaration.as_ref() != Some(&declaration) {
+            return Ok(CommandResponse::Error {
+                code: ErrorCode::Rejected,
+                message: format!(
+                    "Job `{id}` was changed since run `{run_id}`, only a run of the job as it is now can be retried"
+                ),
+            });
+        }
+        let at = |timestamp: u64| DateTime::from_timestamp(timestamp as i64, 0);
+        let now = Utc::now();
+        let scheduled_time = run
+            .window_end
+            .or(Some(run.scheduled_at))
+            .and_then(at)
+            .unwrap_or(now);
+        let tick = Tick {
+            window_start: run.window_start.and_then(at).unwrap_or(scheduled_time),
+            ..Tick::at(scheduled_time)
+        };
+        tokio::spawn(Self::run_job(
+            id,
+            jobs,
+            history,
+            events,
+            pool,
+            business,
+            now,
+            Duration::ZERO,
+            tick,
+            Some(run_id),
+        ));
+        Ok(CommandResponse::RunRetried(id.to_string(), tick.run_id))
+    }
+
+    /// Handles the `PauseJob` and `ResumeJob` commands.
+    ///
+    /// This function suspends or resumes the executions of a job. Resuming a job also resumes a job paused once its schedule expired,
+    /// until the expiry timer or its next tick expires it again. A paused job is never overdue, and the window it is expected to succeed
+    /// within starts over once it is resumed. Resuming a job also re-arms its `disable_after` circuit breaker.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job.
+    /// * `paused` - A `bool` that represents whether the job is paused or resumed.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::JobPaused` or `CommandResponse::JobResumed` if successful, or an error if not.
+    async fn handle_cmd_pause_job(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        id: Uuid,
+        paused: bool,
+    ) -> CronusResult<CommandResponse> {
+        if let Some(entry) = jobs.write().await.get_mut(&id) {
+            if entry.paused && !paused {
+                entry.watched_since = Utc::now().timestamp() as u64;
+                entry.consecutive_failures = 0;
+            }
+            entry.paused = paused;
+        }
+        Ok(if paused {
+            CommandResponse::JobPaused
+        } else {
+            CommandResponse::JobResumed
+        })
+    }
+
+    /// Handles the `KillRun` command.
+    ///
+    /// This function flips the kill switch of every run of a job in flight, or of one of them. Commands are killed, and Rhai scripts and
+    /// workflows are aborted, so the runs end as failed with a `Run killed` error in the run history.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job whose runs are killed.
+    /// * `run_id` - An `Option<RunId>` that represents the ID of the run to be killed. It is `None` to kill every run of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::RunKilled` telling whether a run was in flight if successful, or an error if not.
+    async fn handle_cmd_kill_run(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        id: Uuid,
+        run_id: Option<RunId>,
+    ) -> CronusResult<CommandResponse> {
+        let running = jobs
+            .read()
+            .await
+            .get(&id)
+            .map(|entry| entry.running.clone());
+        let killed = running.is_some_and(|running| {
+            let mut killed = false;
+            for run in Self::lock_running(&running)
+                .iter()
+                .filter(|run| run_id.is_none_or(|run_id| run.run_id == run_id))
+            {
+                run.kill();
+                killed = true;
+            }
+            killed
+        });
+        Ok(CommandResponse::RunKilled(killed))
+    }
+
+    /// Handles the `TailOutput` command.
+    ///
+    /// This function reads the output of the run of a job started last, whether it is in flight or over, from a position on. A run given
+    /// by its id is read while it is in flight, even if another run of the job started after it, and once it is over as long as it is
+    /// one of the last `KEPT_OUTPUTS` runs started.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `id` - A `Uuid` that represents the ID of the job whose output is read.
+    /// * `from` - A `u64` that represents the position of the first line to be read.
+    /// * `run_id` - An `Option<RunId>` that represents the ID of the run whose output is read. It is `None` for the run started last.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::RunOutput` with the lines read, or none if the job or the run has not run, if successful, or an error if not.
+    async fn handle_cmd_tail_output(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        id: Uuid,
+        from: u64,
+        run_id: Option<RunId>,
+    ) -> CronusResult<CommandResponse> {
+        let output = jobs.read().await.get(&id).and_then(|entry| {
+            let outputs = Self::lock_output(&entry.output);
+            let Some(run_id) = run_id else {
+                return outputs.back().cloned();
+            };
+            Self::lock_running(&entry.running)
+                .iter()
+                .map(|run| &run.output)
+                .chain(outputs.iter())
+                .find(|output| output.run_id() == Some(run_id))
+                .cloned()
+        });
+        Ok(CommandResponse::RunOutput(
+            output.map(|output| output.read_from(from)),
+        ))
+    }
+
+    /// Handles the `EmitEvent` command.
+    ///
+    /// This function fires every job of the scope of the client listening on the topic of the event in the background, like one of their
+    /// ticks would, so a team only fires the listeners of its own namespace. An `EventReceived` event carrying the payload is emitted for
+    /// every fired job, so the payload can be found next to its runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `scope` - A reference to the `Scope` of the client, whose listeners are fired.
+    /// * `topic` - A string that represents the topic the event is emitted on.
+    /// * `payload` - An `Option<String>` that represents the payload of the event, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::EventEmitted` if successful, or an error if not.
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_cmd_emit_event(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        scope: &Scope,
+        topic: String,
+        payload: Option<String>,
+    ) -> CronusResult<CommandResponse> {
+        let listeners: Vec<_> = jobs
+            .read()
+            .await
+            .iter()
+            .filter(|(_, entry)| {
+                entry.trigger.event() == Some(topic.as_str())
+                    && scope.contains(entry.options.namespace.as_deref())
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &listeners {
+            events.emit(
+                *id,
+                EventKind::EventReceived {
+                    topic: topic.clone(),
+                    payload: payload.clone(),
+                },
+            );
+            tokio::spawn(Self::execute_job(
+                *id,
+                scheduler.clone(),
+                jobs.clone(),
+                history.clone(),
+                events.clone(),
+                pool.clone(),
+            ));
+        }
+        Ok(CommandResponse::EventEmitted(
+            listeners.iter().map(Uuid::to_string).collect(),
+        ))
+    }
+
+    /// Handles the `JobStats` command.
+    ///
+    /// This function reports the execution time accounted in the run history of a job, or of every registered job.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `id` - An `Option<Uuid>` that represents the ID of the job to report on, or `None` for every job.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Stats` if successful, or an error if not.
+    async fn handle_cmd_job_stats(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        scope: &Scope,
+        id: Option<Uuid>,
+    ) -> CronusResult<CommandResponse> {
+        let stats = Self::accounted_jobs(jobs, history, scope)
+            .await
+            .iter()
+            .filter(|job| id.is_none_or(|id| job.job_id == id.to_string()))
+            .map(job_stats)
+            .collect();
+        Ok(CommandResponse::Stats(stats))
+    }
+
+    /// Handles the `CostCenterStats` command.
+    ///
+    /// This function reports the execution time charged to every cost center in the run history, including the time of deleted jobs.
+    /// A client of one namespace is only reported the time its registered jobs charged to their cost centers, as a cost center may be
+    /// shared by several namespaces.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::CostCenterStats` if successful, or an error if not.
+    async fn handle_cmd_cost_center_stats(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        scope: &Scope,
+    ) -> CronusResult<CommandResponse> {
+        let history = history.read().await;
+        let usage = match scope {
+            Scope::All => history.cost_center_usage(),
+            Scope::Namespace(_) => {
+                let mut usage: BTreeMap<String, BTreeMap<_, Usage>> = BTreeMap::new();
+                for (id, entry) in jobs.read().await.iter() {
+                    let Some(cost_center) = entry.options.cost_center.as_ref() else {
+                        continue;
+                    };
+                    if !scope.contains(entry.options.namespace.as_deref()) {
+                        continue;
+                    }
+                    let daily = usage.entry(cost_center.clone()).or_default();
+                    for (day, day_usage) in history.daily_usage(id) {
+                        *daily.entry(day).or_default() += day_usage;
+                    }
+                }
+                usage
+            }
+        };
+        let stats = usage
+            .into_iter()
+            .map(|(cost_center, daily)| cost_center_stats(cost_center, &daily))
+            .collect();
+        Ok(CommandResponse::CostCenterStats(stats))
+    }
+
+    /// Handles the `UsageDigest` command.
+    ///
+    /// This function reports the registered jobs consuming the most machine time today and this month, in local time.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Digest` if successful, or an error if not.
+    async fn handle_cmd_usage_digest(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        scope: &Scope,
+    ) -> CronusResult<CommandResponse> {
+        let accounted = Self::accounted_jobs(jobs, history, scope).await;
+        Ok(CommandResponse::Digest(build_digest(
+            &accounted,
+            Local::now().date_naive(),
+        )))
+    }
+
+    /// Collects the execution time accounted in the run history of every registered job of the scope of a client.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<AccountedJob>` - Returns the accounted jobs, sorted by id.
+    async fn accounted_jobs(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        scope: &Scope,
+    ) -> Vec<AccountedJob> {
+        let jobs = jobs.read().await;
+        let history = history.read().await;
+        let mut accounted: Vec<_> = jobs
+            .iter()
+            .filter(|(_, entry)| scope.contains(entry.options.namespace.as_deref()))
+            .map(|(id, entry)| AccountedJob {
+                job_id: id.to_string(),
+                name: entry.options.name.clone(),
+                daily: history.daily_usage(id),
+                durations: history.runs(id).iter().map(|run| run.duration).collect(),
+                last_failure: history.last_failure(id),
+            })
+            .collect();
+        accounted.sort_by(|a, b| a.job_id.cmp(&b.job_id));
+        accounted
+    }
+
+    /// Handles the `CheckTimezones` command.
+    ///
+    /// This function checks the timezones the registered jobs are scheduled in against the timezone database embedded in the service,
+    /// and compares the embedded database with the one of the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::TimezoneReport` if successful, or an error if not.
+    async fn handle_cmd_check_timezones(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        scope: &Scope,
+    ) -> CronusResult<CommandResponse> {
+        let mut scheduled: Vec<_> = jobs
+            .read()
+            .await
+            .iter()
+            .filter(|(_, entry)| scope.contains(entry.options.namespace.as_deref()))
+            .filter_map(|(id, entry)| Some((id.to_string(), entry.options.timezone.clone()?)))
+            .collect();
+        scheduled.sort();
+        Ok(CommandResponse::TimezoneReport(check_timezones(
+            &scheduled,
+            system_tzdb_version(),
+        )))
+    }
+
+    /// Handles the `ListEvents` command.
+    ///
+    /// This function retrieves the most recent events emitted by the scheduler about the jobs of the scope of the client, see `scoped_jobs`.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::EventList` if successful, or an error if not.
+    async fn handle_cmd_list_events(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        events: Arc<EventBus>,
+        scope: &Scope,
+    ) -> CronusResult<CommandResponse> {
+        let scoped = Self::scoped_jobs(&*jobs.read().await, scope);
+        let mut recent = events.recent();
+        if let Some(scoped) = scoped {
+            recent.retain(|event| scoped.contains(&event.job_id));
+        }
+        Ok(CommandResponse::EventList(recent))
+    }
+
+    /// Handles the `ListAudit` command.
+    ///
+    /// This function reads the most recent management commands received by the service from its audit log, about the jobs of the scope
+    /// of the client, see `scoped_jobs`.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `audit` - An `AuditLog` that represents the audit log of the service.
+    /// * `id` - An `Option<String>` that represents the id of the job whose commands are listed, or `None` for every command.
+    /// * `limit` - A `usize` that represents the maximum number of commands listed.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::AuditLog` if successful, or an error if not.
+    async fn handle_cmd_list_audit(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        audit: AuditLog,
+        scope: &Scope,
+        id: Option<String>,
+        limit: usize,
+    ) -> CronusResult<CommandResponse> {
+        let scoped = Self::scoped_jobs(&*jobs.read().await, scope);
+        let entries = spawn_blocking(move || {
+            audit.read(
+                |entry| {
+                    let job_id = entry.job_id.as_ref();
+                    id.as_ref().is_none_or(|id| job_id == Some(id))
+                        && scoped
+                            .as_ref()
+                            .is_none_or(|scoped| job_id.is_some_and(|id| scoped.contains(id)))
+                },
+                limit,
+            )
+        })
+        .await??;
+        Ok(CommandResponse::AuditLog(entries))
+    }
+
+    /// Handles the `FollowEvents` command.
+    ///
+    /// This function retrieves the events emitted after a position about the jobs of the scope of the client, see `scoped_jobs`, for a
+    /// follower to apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `after` - A `u64` that represents the `seq` of the last event the follower read.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::EventFeed` if successful, or an error if not.
+    async fn handle_cmd_follow_events(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        events: Arc<EventBus>,
+        scope: &Scope,
+        after: u64,
+    ) -> CronusResult<CommandResponse> {
+        let scoped = Self::scoped_jobs(&*jobs.read().await, scope);
+        let mut feed = events.since(after);
+        if let Some(scoped) = scoped {
+            feed.events.retain(|event| scoped.contains(&event.job_id));
+        }
+        Ok(CommandResponse::EventFeed(feed))
+    }
+
+    /// Lists the ids of the registered jobs of the scope of a client, which the commands reporting on the whole service answer about.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - A reference to the registered jobs.
+    /// * `scope` - A reference to the `Scope` of the client.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<HashSet<String>>` - Returns the ids of the jobs of the namespace of the client, or `None` across every namespace, whose
+    ///   client is answered about every job, including the jobs since deleted and the runs of the service itself.
+    fn scoped_jobs(jobs: &HashMap<Uuid, JobEntry>, scope: &Scope) -> Option<HashSet<String>> {
+        match scope {
+            Scope::All => None,
+            Scope::Namespace(_) => Some(
+                jobs.iter()
+                    .filter(|(_, entry)| scope.contains(entry.options.namespace.as_deref()))
+                    .map(|(id, _)| id.to_string())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Handles the `Snapshot` command.
+    ///
+    /// This function describes the jobs, the run history and the state of the jobs of the service, for a follower to mirror or for
+    /// `cronus snapshot` to archive. The position of the last event is read first, so the events emitted while the snapshot is taken are
+    /// replayed by the follower rather than missed. A client of one namespace is only described the jobs of its namespace.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `scope` - A reference to the `Scope` of the client, whose jobs are reported on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Snapshot` if successful, or an error if not.
+    async fn handle_cmd_snapshot(
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        scope: &Scope,
+    ) -> CronusResult<CommandResponse> {
+        let seq = events.last_seq();
+        let mut jobs = Self::stored_jobs(&*jobs.read().await);
+        jobs.retain(|job| scope.contains(job.options.namespace.as_deref()));
+        let history = history.read().await;
+        let runs = jobs
+            .iter()
+            .map(|job| (job.id, history.runs(&job.id)))
+            .collect();
+        let mut state = state::export();
+        if let Scope::Namespace(_) = scope {
+            state.retain(|id, _| jobs.iter().any(|job| job.id.to_string() == *id));
+        }
+        Ok(CommandResponse::Snapshot(ServiceSnapshot {
+            seq,
+            jobs,
+            runs,
+            state,
+        }))
+    }
+
+    /// Handles the `Restore` command.
+    ///
+    /// This function registers the jobs of a snapshot with their ids and whether they are paused, records their runs and restores their
+    /// state, e.g. to recover a service on a new host. A snapshot is only restored into a service without jobs, and only if this version
+    /// of the service supports every one of its jobs, so nothing is restored otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `snapshot` - A `ServiceSnapshot` that represents the jobs, the run history and the state of the jobs to be restored.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Restored` if successful, a `CommandResponse::RestoreRejected` if the service has jobs already, a `CommandResponse::Unsupported` if a job cannot be run by this version of the service, or an error if a job cannot be registered or the state cannot be written.
+    async fn handle_cmd_restore(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        snapshot: ServiceSnapshot,
+    ) -> CronusResult<CommandResponse> {
+        let registered = jobs.read().await.len();
+        if registered > 0 {
+            return Ok(CommandResponse::RestoreRejected(format!(
+                "The service has {registered} jobs already, restore the snapshot into a service without jobs"
+            )));
+        }
+        for stored in &snapshot.jobs {
+            if let Err(e) = Self::check_supported(&stored.job, &stored.options) {
+                return Ok(CommandResponse::Unsupported(format!(
+                    "Job `{}`: {e}",
+                    stored.id
+                )));
+            }
+        }
+        let ids: Vec<_> = snapshot.jobs.iter().map(|stored| stored.id).collect();
+        Self::restore_jobs(
+            scheduler,
+            jobs,
+            history.clone(),
+            events,
+            pool,
+            snapshot.jobs,
+        )
+        .await?;
+        {
+            let mut history = history.write().await;
+            for (id, runs) in snapshot.runs.into_iter().filter(|(id, _)| ids.contains(id)) {
+                for run in runs {
+                    history.record(id, run);
+                }
+            }
+        }
+        state::import(snapshot.state)
+            .map_err(|e| format!("Cannot write the state of the jobs: {e}"))?;
+        Ok(CommandResponse::Restored(
+            ids.iter().map(Uuid::to_string).collect(),
+        ))
+    }
+
+    /// Handles the `Promote` command.
+    ///
+    /// This function stops following the primary of the service, and registers the mirrored jobs with their ids and whether they are
+    /// paused, so the service schedules them from now on, dispatches the completions of jobs and their notifications, exports their telemetry, and watches the
+    /// jobs expected to succeed within a window, like a primary.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of jobs.
+    /// * `events` - An `Arc<EventBus>` that is used to emit the events of jobs.
+    /// * `pool` - An `Arc<WorkerPool>` that runs the jobs.
+    /// * `notifiers` - A `watch::Receiver<BTreeMap<String, Notifier>>` that is used to receive the notifiers of the configuration.
+    /// * `otel` - A `watch::Receiver<Option<OtelConfig>>` that is used to receive the telemetry exporter of the configuration, with the `otel` feature.
+    /// * `follower` - An `Option<(String, JoinHandle<()>)>` that represents the name of the primary the service follows and the task mirroring it, or `None` if the service is a primary.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Promoted` if successful, or an error if a mirrored job cannot be registered.
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_cmd_promote(
+        scheduler: &JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        events: Arc<EventBus>,
+        pool: Arc<WorkerPool>,
+        notifiers: watch::Receiver<BTreeMap<String, Notifier>>,
+        #[cfg(feature = "otel")] otel: watch::Receiver<Option<OtelConfig>>,
+        follower: Option<(String, JoinHandle<()>)>,
+    ) -> CronusResult<CommandResponse> {
+        let Some((primary, task)) = follower else {
+            return Ok(CommandResponse::Promoted(None));
+        };
+        task.abort();
+        // The task may still be applying an event, so the mirrored jobs are only read once it is over.
+        _ = task.await;
+        let mirrored = {
+            let mut jobs = jobs.write().await;
+            let mirrored = Self::stored_jobs(&jobs);
+            jobs.clear();
+            mirrored
+        };
+        Self::restore_jobs(
+            scheduler,
+            jobs.clone(),
+            history.clone(),
+            events.clone(),
+            pool.clone(),
+            mirrored,
+        )
+        .await?;
+        tokio::spawn(Self::dispatch_chains(
+            scheduler.clone(),
+            jobs.clone(),
+            history.clone(),
+            events.clone(),
+            pool,
+        ));
+        tokio::spawn(Self::dispatch_notifications(
+            jobs.clone(),
+            history.clone(),
+            events.clone(),
+            notifiers,
+        ));
+        #[cfg(feature = "otel")]
+        tokio::spawn(Self::export_telemetry(
+            jobs.clone(),
+            history.clone(),
+            events.clone(),
+            otel,
+        ));
+        tokio::spawn(Self::watch_overdue_jobs(jobs, history, events));
+        Ok(CommandResponse::Promoted(Some(primary)))
+    }
+
+    /// Handles the `ReloadConfig` command.
+    ///
+    /// This function reads the configuration file of the service again and runs the reload hooks of the new configuration.
+    /// An invalid configuration file is rejected, and the service keeps its configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of the hooks.
+    /// * `config_file` - An `Option<&Path>` that represents the path of the configuration file of the service. It is `None` if the service has no configuration file, in which case there is nothing to reload.
+    /// * `config` - A mutable reference to the `ServiceConfig` of the service, replaced by the new configuration.
+    /// * `backup` - A reference to the `watch::Sender<Option<BackupConfig>>` the backup settings of the new configuration are passed to the backups with.
+    /// * `notifiers` - A reference to the `watch::Sender<BTreeMap<String, Notifier>>` the notifiers of the new configuration are passed to the notifications with.
+    /// * `throttle` - A reference to the `watch::Sender<ThrottleConfig>` the throttle of the new configuration is passed to the command server with.
+    /// * `otel` - A reference to the `watch::Sender<Option<OtelConfig>>` the telemetry exporter of the new configuration is passed to the exporter with, with the `otel` feature.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::ConfigReloaded` or a `CommandResponse::ConfigRejected` if successful, or an error if not.
+    async fn handle_cmd_reload_config(
+        history: Arc<RwLock<RunHistory>>,
+        config_file: Option<&Path>,
+        config: &mut ServiceConfig,
+        backup: &watch::Sender<Option<BackupConfig>>,
+        notifiers: &watch::Sender<BTreeMap<String, Notifier>>,
+        throttle: &watch::Sender<ThrottleConfig>,
+        #[cfg(feature = "otel")] otel: &watch::Sender<Option<OtelConfig>>,
+    ) -> CronusResult<CommandResponse> {
+        if let Some(config_file) = config_file {
+            match ServiceConfig::load(config_file) {
+                Ok(reloaded) => *config = reloaded,
+                Err(e) => return Ok(CommandResponse::ConfigRejected(e.to_string())),
+            }
+        }
+        backup.send_replace(config.backup.clone());
+        notifiers.send_replace(config.notifiers.clone());
+        throttle.send_replace(config.throttle);
+        #[cfg(feature = "otel")]
+        otel.send_replace(config.otel.clone());
+        let runs = Self::run_hooks(history, HookStage::Reload, config).await;
+        Ok(CommandResponse::ConfigReloaded(runs))
+    }
+
+    /// Handles the `StopService` command.
+    ///
+    /// This function shuts down the job scheduler, kills the runs in flight and, once they are over or once `STOP_TIMEOUT` has passed,
+    /// runs the stop hooks and returns a `CommandResponse::ServiceStopped`.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheduler` - A mutable reference to the `JobScheduler` that is used to manage jobs.
+    /// * `jobs` - An `Arc<RwLock<HashMap<Uuid, JobEntry>>>` that is used to store jobs.
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of the hooks.
+    /// * `config` - A reference to the `ServiceConfig` holding the stop hooks.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::ServiceStopped` if successful, or an error if not.
+    async fn handle_cmd_stop_service(
+        scheduler: &mut JobScheduler,
+        jobs: Arc<RwLock<HashMap<Uuid, JobEntry>>>,
+        history: Arc<RwLock<RunHistory>>,
+        config: &ServiceConfig,
+    ) -> CronusResult<CommandResponse> {
+        scheduler.shutdown().await?;
+        let running: Vec<_> = jobs
+            .read()
+            .await
+            .values()
+            .map(|entry| entry.running.clone())
+            .collect();
+        for running in &running {
+            Self::lock_running(running)
+                .iter()
+                .for_each(InFlightRun::kill);
+        }
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while Instant::now() < deadline
+            && running
+                .iter()
+                .any(|running| !Self::lock_running(running).is_empty())
+        {
+            sleep(STOP_POLL_INTERVAL).await;
+        }
+        Self::run_hooks(history, HookStage::Stop, config).await;
+        Ok(CommandResponse::ServiceStopped)
+    }
+
+    /// Runs the hooks of a stage of the lifecycle of the service, recording their runs as system runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `history` - An `Arc<RwLock<RunHistory>>` that is used to record the runs of the hooks, under `SYSTEM_RUNS`.
+    /// * `stage` - A `HookStage` that represents the stage whose hooks are run.
+    /// * `config` - A reference to the `ServiceConfig` holding the hooks.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<RunRecord>` - Returns the runs of the hooks.
+    async fn run_hooks(
+        history: Arc<RwLock<RunHistory>>,
+        stage: HookStage,
+        config: &ServiceConfig,
+    ) -> Vec<RunRecord> {
+        let runs = run_hooks(stage, config.hooks(stage)).await;
+        let mut history = history.write().await;
+        for run in &runs {
+            history.record(SYSTEM_RUNS, run.clone());
+        }
+        runs
+    }
+
+    /// Handles the `PingService` command.
+    ///
+    /// This function responds to a ping request to the service. It is used to check if the service is running.
     ///
     /// # Returns
     ///
@@ -305,4 +5100,219 @@ impl CronusScheduler {
     async fn handle_cmd_ping_service() -> CronusResult<CommandResponse> {
         Ok(CommandResponse::ServiceRunning)
     }
+
+    /// Handles a command this version of the service does not know.
+    ///
+    /// This function rejects the command, which was sent by a newer client, without stopping the service.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::Unsupported` if successful, or an error if not.
+    async fn handle_cmd_unsupported() -> CronusResult<CommandResponse> {
+        Ok(CommandResponse::Unsupported(
+            "Unsupported command, it may need a newer version of the service".to_string(),
+        ))
+    }
+
+    /// Locks the kill switches of the runs of a job in flight.
+    ///
+    /// # Arguments
+    ///
+    /// * `running` - A reference to the runs of the job in flight.
+    ///
+    /// # Returns
+    ///
+    /// * `MutexGuard<Vec<InFlightRun>>` - Returns the guard of the runs, even if a thread panicked while holding it.
+    fn lock_running(running: &Mutex<Vec<InFlightRun>>) -> MutexGuard<'_, Vec<InFlightRun>> {
+        running.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the cached ticks of a job running on a cron schedule.
+    ///
+    /// # Arguments
+    ///
+    /// * `ticks` - A reference to the cached ticks of the job.
+    ///
+    /// # Returns
+    ///
+    /// * `MutexGuard<Option<CronTicks>>` - Returns the guard of the ticks, even if a thread panicked while holding it.
+    fn lock_ticks(ticks: &Mutex<Option<CronTicks>>) -> MutexGuard<'_, Option<CronTicks>> {
+        ticks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the outputs of the runs of a job started last.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - A reference to the outputs of the runs of the job started last.
+    ///
+    /// # Returns
+    ///
+    /// * `MutexGuard<VecDeque<RunOutput>>` - Returns the guard of the outputs, even if a thread panicked while holding it.
+    fn lock_output(output: &Mutex<VecDeque<RunOutput>>) -> MutexGuard<'_, VecDeque<RunOutput>> {
+        output.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn command() -> Job {
+        Job::Command(PathBuf::from("/bin/true"), vec![], None)
+    }
+
+    fn registered(namespace: Option<&str>) -> (Uuid, HashMap<Uuid, JobEntry>) {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            namespace: namespace.map(str::to_string),
+            ..JobOptions::default()
+        };
+        let entry = JobEntry::new(id, Trigger::Manual, command(), options);
+        (id, HashMap::from([(id, entry)]))
+    }
+
+    fn add_job(namespace: Option<&str>) -> Command {
+        Command::AddJob {
+            trigger: Trigger::Manual,
+            job: command(),
+            options: JobOptions {
+                namespace: namespace.map(str::to_string),
+                ..JobOptions::default()
+            },
+            no_duplicates: false,
+            paused: false,
+            dry_run: false,
+        }
+    }
+
+    fn recorded_namespace(cmd: &Command) -> Option<&str> {
+        match cmd {
+            Command::AddJob { options, .. } | Command::UpdateJob { options, .. } => {
+                options.namespace.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn job_of_another_namespace_is_not_found() {
+        let (id, jobs) = registered(Some("web"));
+        let mut cmd = Command::GetJob { id: id.to_string() };
+        let scope = Scope::named("data").unwrap();
+        let res = CronusScheduler::scope_command(&mut cmd, &scope, &jobs, &RunHistory::default());
+        assert!(matches!(
+            res.map_err(|res| *res),
+            Err(CommandResponse::Error {
+                code: ErrorCode::NotFound,
+                ..
+            })
+        ));
+        let res = CronusScheduler::scope_command(
+            &mut cmd,
+            &Scope::named("web").unwrap(),
+            &jobs,
+            &RunHistory::default(),
+        );
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn job_added_without_namespace_is_recorded_in_the_scope() {
+        let history = RunHistory::default();
+        let mut cmd = add_job(None);
+        let scope = Scope::named("data").unwrap();
+        CronusScheduler::scope_command(&mut cmd, &scope, &HashMap::new(), &history).unwrap();
+        assert_eq!(recorded_namespace(&cmd), Some("data"));
+        let mut cmd = add_job(Some(DEFAULT_NAMESPACE));
+        CronusScheduler::scope_command(&mut cmd, &Scope::default(), &HashMap::new(), &history)
+            .unwrap();
+        assert_eq!(recorded_namespace(&cmd), None);
+    }
+
+    #[test]
+    fn job_declaring_another_namespace_is_forbidden() {
+        let mut cmd = add_job(Some("web"));
+        let scope = Scope::named("data").unwrap();
+        let res = CronusScheduler::scope_command(
+            &mut cmd,
+            &scope,
+            &HashMap::new(),
+            &RunHistory::default(),
+        );
+        assert!(matches!(
+            res.map_err(|res| *res),
+            Err(CommandResponse::Forbidden(_))
+        ));
+        let mut cmd = add_job(Some("bad/name"));
+        let res = CronusScheduler::scope_command(
+            &mut cmd,
+            &Scope::All,
+            &HashMap::new(),
+            &RunHistory::default(),
+        );
+        assert!(matches!(
+            res.map_err(|res| *res),
+            Err(CommandResponse::Error {
+                code: ErrorCode::Rejected,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn job_updated_across_every_namespace_keeps_its_namespace() {
+        let (id, jobs) = registered(Some("web"));
+        let mut cmd = Command::UpdateJob {
+            id: id.to_string(),
+            trigger: Trigger::Manual,
+            job: command(),
+            options: JobOptions::default(),
+        };
+        CronusScheduler::scope_command(&mut cmd, &Scope::All, &jobs, &RunHistory::default())
+            .unwrap();
+        assert_eq!(recorded_namespace(&cmd), Some("web"));
+    }
+
+    #[test]
+    fn list_and_batch_are_scoped() {
+        let (id, jobs) = registered(Some("web"));
+        let scope = Scope::named("data").unwrap();
+        let history = RunHistory::default();
+        let mut cmd = Command::ListJobs {
+            offset: 0,
+            limit: None,
+            filter: JobFilter::default(),
+            sort: JobSort::default(),
+        };
+        CronusScheduler::scope_command(&mut cmd, &scope, &jobs, &history).unwrap();
+        assert!(matches!(
+            &cmd,
+            Command::ListJobs { filter, .. } if filter.namespace.as_deref() == Some("data")
+        ));
+        let mut cmd = Command::Batch {
+            commands: vec![add_job(None), Command::DeleteJob { id: id.to_string() }],
+        };
+        let res = CronusScheduler::scope_command(&mut cmd, &scope, &jobs, &history);
+        assert!(matches!(
+            res.map_err(|res| *res),
+            Err(CommandResponse::Error {
+                code: ErrorCode::NotFound,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn scoped_jobs_are_the_jobs_of_the_namespace() {
+        let (id, mut jobs) = registered(None);
+        let (other, web) = registered(Some("web"));
+        jobs.extend(web);
+        let scoped = CronusScheduler::scoped_jobs(&jobs, &Scope::default()).unwrap();
+        assert!(scoped.contains(&id.to_string()));
+        assert!(!scoped.contains(&other.to_string()));
+        assert_eq!(CronusScheduler::scoped_jobs(&jobs, &Scope::All), None);
+    }
 }
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// What a masked secret is replaced with in the output and the errors of a run.
+pub const MASK: &str = "***";
+
+/// The `keyctl` operation searching a keyring for a key, see `keyctl(2)`.
+const KEYCTL_SEARCH: libc::c_long = 10;
+
+/// The `keyctl` operation reading the payload of a key.
+const KEYCTL_READ: libc::c_long = 11;
+
+/// The special id of the keyring of the user of the calling process.
+const KEY_SPEC_USER_KEYRING: libc::c_long = -4;
+
+/// `SecretSource` is an enumeration that represents where the value of a secret given to a job is read from, when the job runs.
+///
+/// Only the source is kept with the job, never the value, so a secret is neither written to the job store nor shown by `list`.
+///
+/// # Variants
+///
+/// * `File(PathBuf)` - Represents a secret read from a file, written `@<file>`, e.g. `@/run/secrets/db_password`. The trailing line
+///   ending of the file is not part of the secret.
+/// * `Keyring(String)` - Represents a secret read from a key of the Linux keyring of the user of the service, written `keyring:<entry>`,
+///   e.g. `keyring:db_password` for a key added with `keyctl add user db_password <value> @u`.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub enum SecretSource {
+    File(PathBuf),
+    Keyring(String),
+}
+
+impl SecretSource {
+    /// Reads the value of the secret.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, String>` - Returns the value, or an error message if the file or the key cannot be read, or is not UTF-8.
+    pub fn resolve(&self) -> Result<String, String> {
+        match self {
+            Self::File(file) => {
+                let value = std::fs::read_to_string(file)
+                    .map_err(|e| format!("Cannot read the secret file {}: {e}", file.display()))?;
+                Ok(value.trim_end_matches(['\r', '\n']).to_string())
+            }
+            Self::Keyring(entry) => read_key(entry)
+                .map_err(|e| format!("Cannot read the key `{entry}` of the keyring: {e}")),
+        }
+    }
+}
+
+impl FromStr for SecretSource {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        if let Some(file) = source.strip_prefix('@').filter(|file| !file.is_empty()) {
+            return Ok(Self::File(PathBuf::from(file)));
+        }
+        if let Some(entry) = source
+            .strip_prefix("keyring:")
+            .filter(|entry| !entry.is_empty())
+        {
+            return Ok(Self::Keyring(entry.to_string()));
+        }
+        Err(format!(
+            "Invalid secret source `{source}`, expected @<file> or keyring:<entry>"
+        ))
+    }
+}
+
+impl TryFrom<String> for SecretSource {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        source.parse()
+    }
+}
+
+impl From<SecretSource> for String {
+    fn from(source: SecretSource) -> Self {
+        source.to_string()
+    }
+}
+
+impl fmt::Display for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(file) => write!(f, "@{}", file.display()),
+            Self::Keyring(entry) => write!(f, "keyring:{entry}"),
+        }
+    }
+}
+
+/// Parses a secret given on the command line, as `NAME=@file` or `NAME=keyring:entry`.
+///
+/// # Arguments
+///
+/// * `secret` - A string that represents the secret.
+///
+/// # Returns
+///
+/// * `Result<(String, SecretSource), String>` - Returns the name of the environment variable the secret is given as and its source, or
+///   an error message if the secret is malformed.
+pub fn parse_secret(secret: &str) -> Result<(String, SecretSource), String> {
+    let (name, source) = secret.split_once('=').ok_or_else(|| {
+        format!("Invalid secret `{secret}`, expected NAME=@file or NAME=keyring:entry")
+    })?;
+    validate_name(name)?;
+    Ok((name.to_string(), source.parse()?))
+}
+
+/// Checks that the secrets of a job are given as valid environment variables.
+///
+/// # Arguments
+///
+/// * `secrets` - A reference to the secrets of the job, by the name of their environment variable.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if every name is valid, or an error message describing the first invalid one.
+pub fn validate_secrets(secrets: &BTreeMap<String, SecretSource>) -> Result<(), String> {
+    secrets.keys().try_for_each(|name| validate_name(name))
+}
+
+/// Resolves the secrets of a job for a run.
+///
+/// # Arguments
+///
+/// * `secrets` - A reference to the secrets of the job, by the name of their environment variable.
+///
+/// # Returns
+///
+/// * `Result<Vec<(String, String)>, String>` - Returns the names and values of the secrets, or an error message describing the first
+///   secret that cannot be read.
+pub fn resolve_secrets(
+    secrets: &BTreeMap<String, SecretSource>,
+) -> Result<Vec<(String, String)>, String> {
+    secrets
+        .iter()
+        .map(|(name, source)| {
+            source
+                .resolve()
+                .map(|value| (name.clone(), value))
+                .map_err(|e| format!("Secret `{name}`: {e}"))
+        })
+        .collect()
+}
+
+/// Replaces the values of secrets in a text, e.g. a line of output, with `MASK`.
+///
+/// # Arguments
+///
+/// * `text` - A string that represents the text.
+/// * `values` - A slice of strings that represent the values of the secrets. Empty values are never masked.
+///
+/// # Returns
+///
+/// * `String` - Returns the text, with every value of a secret masked.
+pub fn mask(text: &str, values: &[String]) -> String {
+    values
+        .iter()
+        .filter(|value| !value.is_empty())
+        .fold(text.to_string(), |text, value| {
+            text.replace(value.as_str(), MASK)
+        })
+}
+
+/// Checks that the name of a secret is a valid environment variable, e.g. `DB_PASSWORD`.
+///
+/// # Arguments
+///
+/// * `name` - A string that represents the name.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the name is made of ASCII letters, digits and underscores, and does not start with a
+///   digit, or an error message if not.
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid secret name `{name}`, expected letters, digits and underscores"
+        ))
+    }
+}
+
+/// Reads the payload of a `user` key of the keyring of the user of the service.
+///
+/// # Arguments
+///
+/// * `entry` - A string that represents the description of the key.
+///
+/// # Returns
+///
+/// * `io::Result<String>` - Returns the payload of the key, or an error if the key cannot be found or read, or is not UTF-8.
+fn read_key(entry: &str) -> io::Result<String> {
+    let description = CString::new(entry)?;
+    // SAFETY: the key type and the description are NUL terminated strings that outlive the call.
+    let serial = unsafe {
+        libc::syscall(
+            libc::SYS_keyctl,
+            KEYCTL_SEARCH,
+            KEY_SPEC_USER_KEYRING,
+            c"user".as_ptr(),
+            description.as_ptr(),
+            0,
+        )
+    };
+    if serial < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut payload = vec![0u8; 256];
+    loop {
+        // SAFETY: the buffer outlives the call, and the length passed is the one of the buffer.
+        let len = unsafe {
+            libc::syscall(
+                libc::SYS_keyctl,
+                KEYCTL_READ,
+                serial,
+                payload.as_mut_ptr(),
+                payload.len(),
+            )
+        };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // A payload larger than the buffer is not copied, but its length is returned, so it is read again with a buffer that fits.
+        if len as usize > payload.len() {
+            payload.resize(len as usize, 0);
+            continue;
+        }
+        payload.truncate(len as usize);
+        return String::from_utf8(payload)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the key is not UTF-8"));
+    }
+}
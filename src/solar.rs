@@ -0,0 +1,288 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The Julian date of the Unix epoch.
+const UNIX_EPOCH_JULIAN: f64 = 2_440_587.5;
+
+/// The Julian date of the J2000 epoch, noon of January 1st 2000 in UTC.
+const J2000_JULIAN: f64 = 2_451_545.0;
+
+/// The obliquity of the ecliptic, in degrees.
+const OBLIQUITY: f64 = 23.4397;
+
+/// The number of days searched for a time of the sun, beyond which it is deemed not to occur, e.g. a sunset during the polar day.
+const MAX_SEARCH_DAYS: u64 = 366;
+
+/// `SunEvent` is an enumeration that represents a time of the day given by the position of the sun.
+///
+/// # Variants
+///
+/// * `Dawn` - Represents the start of the civil twilight, when the center of the sun is 6° below the horizon in the morning.
+/// * `Sunrise` - Represents the time the upper edge of the sun appears on the horizon.
+/// * `Noon` - Represents the solar noon, when the sun is at its highest.
+/// * `Sunset` - Represents the time the upper edge of the sun disappears below the horizon.
+/// * `Dusk` - Represents the end of the civil twilight, when the center of the sun is 6° below the horizon in the evening.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SunEvent {
+    Dawn,
+    Sunrise,
+    Noon,
+    Sunset,
+    Dusk,
+}
+
+impl FromStr for SunEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dawn" => Ok(Self::Dawn),
+            "sunrise" => Ok(Self::Sunrise),
+            "noon" => Ok(Self::Noon),
+            "sunset" => Ok(Self::Sunset),
+            "dusk" => Ok(Self::Dusk),
+            _ => Err(format!(
+                "Invalid sun event `{s}`, expected `dawn`, `sunrise`, `noon`, `sunset` or `dusk`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SunEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dawn => write!(f, "dawn"),
+            Self::Sunrise => write!(f, "sunrise"),
+            Self::Noon => write!(f, "noon"),
+            Self::Sunset => write!(f, "sunset"),
+            Self::Dusk => write!(f, "dusk"),
+        }
+    }
+}
+
+/// `SunTime` is a structure that represents a time of the day relative to a time of the sun, written `<event>[+|-<duration>]`, e.g.
+/// `sunset+30m` or `sunrise-1h`.
+///
+/// # Fields
+///
+/// * `event` - A `SunEvent` that represents the time of the sun.
+/// * `offset` - A `Duration` that represents how long after, or before, the time of the sun the job runs.
+/// * `before` - A `bool` that represents whether the job runs `offset` before the time of the sun instead of after it.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(try_from = "String", into = "String")]
+pub struct SunTime {
+    pub event: SunEvent,
+    pub offset: Duration,
+    pub before: bool,
+}
+
+impl FromStr for SunTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(at) = s.find(['+', '-']) else {
+            return Ok(Self {
+                event: s.parse()?,
+                offset: Duration::ZERO,
+                before: false,
+            });
+        };
+        let (event, offset) = s.split_at(at);
+        Ok(Self {
+            event: event.parse()?,
+            offset: humantime::parse_duration(&offset[1..])
+                .map_err(|e| format!("Invalid offset `{}` of `{s}`: {e}", &offset[1..]))?,
+            before: offset.starts_with('-'),
+        })
+    }
+}
+
+impl TryFrom<String> for SunTime {
+    type Error = String;
+
+    fn try_from(time: String) -> Result<Self, Self::Error> {
+        time.parse()
+    }
+}
+
+impl From<SunTime> for String {
+    fn from(time: SunTime) -> Self {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for SunTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.offset.is_zero() {
+            return write!(f, "{}", self.event);
+        }
+        let sign = if self.before { '-' } else { '+' };
+        let offset = humantime::format_duration(self.offset).to_string();
+        write!(f, "{}{sign}{}", self.event, offset.replace(' ', ""))
+    }
+}
+
+/// `SunSchedule` is a structure that represents a job running every day at a time given by the position of the sun at a place, e.g.
+/// 30 minutes after sunset to switch the lights on.
+///
+/// # Fields
+///
+/// * `at` - A `SunTime` that represents the time of the day the job runs at.
+/// * `latitude` - A `f64` that represents the latitude of the place, in degrees north, from -90 to 90.
+/// * `longitude` - A `f64` that represents the longitude of the place, in degrees east, from -180 to 180.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub struct SunSchedule {
+    pub at: SunTime,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl fmt::Display for SunSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {},{}", self.at, self.latitude, self.longitude)
+    }
+}
+
+impl SunSchedule {
+    /// Checks that the place of the schedule is on Earth.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the latitude and the longitude are within their ranges, or an error message if not.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            return Err(format!(
+                "Invalid latitude {}, expected -90 to 90",
+                self.latitude
+            ));
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(format!(
+                "Invalid longitude {}, expected -180 to 180",
+                self.longitude
+            ));
+        }
+        Ok(())
+    }
+
+    /// Computes the time the job runs at on a day.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - A `NaiveDate` that represents the day, as the solar day of the place.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<DateTime<Utc>>` - Returns the time, or `None` if the time of the sun does not occur on that day, e.g. a sunrise during
+    ///   the polar night.
+    pub fn time_on(&self, date: NaiveDate) -> Option<DateTime<Utc>> {
+        let julian = solar_time(date, self.latitude, self.longitude, self.at.event)?;
+        let time = DateTime::from_timestamp(((julian - UNIX_EPOCH_JULIAN) * 86_400.0) as i64, 0)?;
+        let offset = chrono::Duration::from_std(self.at.offset).ok()?;
+        Some(if self.at.before {
+            time - offset
+        } else {
+            time + offset
+        })
+    }
+
+    /// Finds the first time the job runs at after a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - A `DateTime<Utc>` that represents the time.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<DateTime<Utc>>` - Returns the time, or `None` if the time of the sun does not occur within a year.
+    pub fn following(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        at.date_naive()
+            .pred_opt()?
+            .iter_days()
+            .take(MAX_SEARCH_DAYS as usize)
+            .filter_map(|date| self.time_on(date))
+            .find(|time| *time > at)
+    }
+
+    /// Finds the period of the schedule a time falls in.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - A `DateTime<Utc>` that represents the time to find the period of.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(DateTime<Utc>, DateTime<Utc>)>` - Returns the last two times the job runs at up to `at`, oldest first, or `None` if
+    ///   the time of the sun has not occurred twice within the year before.
+    pub fn period_at(&self, at: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut times = at
+            .date_naive()
+            .succ_opt()?
+            .iter_days()
+            .rev()
+            .take(MAX_SEARCH_DAYS as usize)
+            .filter_map(|date| self.time_on(date))
+            .filter(|time| *time <= at);
+        let end = times.next()?;
+        Some((times.next()?, end))
+    }
+
+    /// Returns the times the job runs at after a time, computed one day at a time as they are walked.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - A `DateTime<Utc>` that represents the time to start from.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = DateTime<Utc>>` - Returns the times, in order, ending if the time of the sun stops occurring.
+    pub fn occurrences(self, after: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> {
+        std::iter::successors(self.following(after), move |time| self.following(*time))
+    }
+}
+
+/// Computes a time of the sun with the sunrise equation, accurate to about a minute away from the polar circles.
+///
+/// # Arguments
+///
+/// * `date` - A `NaiveDate` that represents the solar day of the place.
+/// * `latitude` - A `f64` that represents the latitude of the place, in degrees north.
+/// * `longitude` - A `f64` that represents the longitude of the place, in degrees east.
+/// * `event` - A `SunEvent` that represents the time of the sun.
+///
+/// # Returns
+///
+/// * `Option<f64>` - Returns the time as a Julian date, or `None` if the sun does not reach the altitude of the event that day.
+fn solar_time(date: NaiveDate, latitude: f64, longitude: f64, event: SunEvent) -> Option<f64> {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+    let mean_solar_day = (date - epoch).num_days() as f64 - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_solar_day).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.0200 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic_longitude = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000_JULIAN + mean_solar_day + 0.0053 * m.sin()
+        - 0.0069 * (2.0 * ecliptic_longitude).sin();
+    let altitude: f64 = match event {
+        SunEvent::Noon => return Some(transit),
+        SunEvent::Sunrise | SunEvent::Sunset => -0.833,
+        SunEvent::Dawn | SunEvent::Dusk => -6.0,
+    };
+    let declination = (ecliptic_longitude.sin() * OBLIQUITY.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = (altitude.to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees() / 360.0;
+    match event {
+        SunEvent::Dawn | SunEvent::Sunrise => Some(transit - hour_angle),
+        _ => Some(transit + hour_angle),
+    }
+}
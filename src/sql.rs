@@ -0,0 +1,66 @@
+use serde_json::json;
+use sqlx::{AnyConnection, AssertSqlSafe, Connection};
+
+use crate::job::{JobIdentity, KillSwitch, Tick, RUN_KILLED};
+use crate::output::RunOutput;
+use crate::template;
+
+/// The schemes of the database URLs SQL jobs can connect to.
+pub const SUPPORTED_SCHEMES: [&str; 5] =
+    ["postgres:", "postgresql:", "mysql:", "mariadb:", "sqlite:"];
+
+/// Executes the statements of an SQL job against its database for a run, e.g. `VACUUM` or `DELETE FROM sessions WHERE ...`.
+///
+/// The statements are a template rendered for the tick of the run, see `template::render`, and may hold several statements separated
+/// by semicolons, executed in order on a connection of their own. The number of rows they affected is written to the output of the
+/// run, and recorded as its result. The statements are abandoned, and the connection closed, once the kill switch of the run is
+/// flipped.
+///
+/// # Arguments
+///
+/// * `url` - A string that represents the URL of the database, e.g. `postgres://cronus@db1/app` or `sqlite:///var/lib/app.db`.
+/// * `statement` - A string that represents the template of the statements.
+/// * `tick` - A `Tick` that represents the tick of the run.
+/// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+/// * `kill_switch` - A reference to the `KillSwitch` of the run.
+/// * `output` - A reference to the `RunOutput` of the run.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if every statement succeeded, or an error message describing why the run failed.
+pub fn run(
+    url: &str,
+    statement: &str,
+    tick: Tick,
+    identity: &JobIdentity,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+) -> Result<(), String> {
+    let statement = template::render(statement, tick, identity)?;
+    sqlx::any::install_default_drivers();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Cannot start the database client: {e}"))?;
+    let rows_affected = runtime.block_on(async {
+        let execute = async {
+            let mut connection = AnyConnection::connect(url)
+                .await
+                .map_err(|e| format!("Cannot connect to the database: {e}"))?;
+            // The statements are written by the owner of the job, like the command of a command job.
+            let done = sqlx::raw_sql(AssertSqlSafe(statement))
+                .execute(&mut connection)
+                .await
+                .map_err(|e| format!("Statement failed: {e}"))?;
+            _ = connection.close().await;
+            Ok::<_, String>(done.rows_affected())
+        };
+        tokio::select! {
+            rows_affected = execute => rows_affected,
+            _ = kill_switch.killed() => Err(RUN_KILLED.to_string()),
+        }
+    })?;
+    output.push(format!("{rows_affected} rows affected"));
+    output.set_result(json!({ "rows_affected": rows_affected }));
+    Ok(())
+}
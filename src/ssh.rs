@@ -0,0 +1,196 @@
+use std::io::{ErrorKind, Read};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use ssh2::{CheckResult, KnownHostFileKind, Session};
+
+use crate::job::{JobIdentity, KillSwitch, Tick, RUN_KILLED};
+use crate::output::RunOutput;
+use crate::template;
+
+/// How long connecting to a remote host, and every exchange with it but reading the output of the command, may take.
+const SSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a remote command with no new output checks whether its run has been killed.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The port SSH servers listen on by default.
+const SSH_PORT: u16 = 22;
+
+/// `LineBuffer` is a structure that gathers the output of a remote command into lines, as it is read in chunks.
+///
+/// # Fields
+///
+/// * `pending` - A vector of bytes that represents the start of a line, read before its end.
+#[derive(Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Reads the data available on a stream, writing every complete line to the output of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A stream of the channel of the command, in non-blocking mode.
+    /// * `output` - A reference to the `RunOutput` of the run.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, String>` - Returns whether any data was read, or an error message if the stream failed.
+    fn pump(&mut self, mut stream: impl Read, output: &RunOutput) -> Result<bool, String> {
+        let mut chunk = [0; 8192];
+        let read = match stream.read(&mut chunk) {
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => 0,
+            Err(e) => return Err(format!("Cannot read the output of the remote command: {e}")),
+        };
+        self.pending.extend_from_slice(&chunk[..read]);
+        while let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<_> = self.pending.drain(..=end).collect();
+            output.push(
+                String::from_utf8_lossy(&line[..end])
+                    .trim_end_matches('\r')
+                    .to_string(),
+            );
+        }
+        Ok(read > 0)
+    }
+
+    /// Writes the last line to the output of the run, if the command did not end it.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - A reference to the `RunOutput` of the run.
+    fn flush(&mut self, output: &RunOutput) {
+        if !self.pending.is_empty() {
+            output.push(String::from_utf8_lossy(&self.pending).into_owned());
+            self.pending.clear();
+        }
+    }
+}
+
+/// Runs a command on a remote host over SSH for a run of a job, and waits for it to exit.
+///
+/// The host must be listed in the `~/.ssh/known_hosts` file of the user of the service, so a host whose key changed is never trusted.
+/// The user is authenticated with a private key file, or else by the SSH agent of the service. The command is a template rendered for
+/// the tick of the run, see `template::render`, run by the login shell of the user. Its standard output and standard error are written
+/// to the output of the run, line by line, as they are read. Once the run is killed, the session is disconnected.
+///
+/// # Arguments
+///
+/// * `host` - A string that represents the remote host, with an optional port, e.g. `db1.internal:2222`.
+/// * `user` - A string that represents the user the command runs as on the remote host.
+/// * `key_path` - An `Option<&Path>` that represents the path of the private key of the user. It is `None` if the SSH agent is used.
+/// * `command` - A string that represents the command template.
+/// * `tick` - A `Tick` that represents the tick of the run.
+/// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+/// * `kill_switch` - A reference to the `KillSwitch` of the run.
+/// * `output` - A reference to the `RunOutput` of the run.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the command exited with status 0, or an error message describing why the run failed.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    host: &str,
+    user: &str,
+    key_path: Option<&Path>,
+    command: &str,
+    tick: Tick,
+    identity: &JobIdentity,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+) -> Result<(), String> {
+    let command = template::render(command, tick, identity)?;
+    let (name, port) = host
+        .rsplit_once(':')
+        .and_then(|(name, port)| Some((name, port.parse().ok()?)))
+        .unwrap_or((host, SSH_PORT));
+    let address = (name, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {name}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {name}"))?;
+    let tcp = TcpStream::connect_timeout(&address, SSH_TIMEOUT)
+        .map_err(|e| format!("Cannot connect to {host}: {e}"))?;
+    let failed = |e: ssh2::Error| format!("SSH session with {host} failed: {e}");
+    let mut session = Session::new().map_err(failed)?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(SSH_TIMEOUT.as_millis() as u32);
+    session.handshake().map_err(failed)?;
+    verify_host_key(&session, name, port)?;
+    match key_path {
+        Some(key_path) => session.userauth_pubkey_file(user, None, key_path, None),
+        None => session.userauth_agent(user),
+    }
+    .map_err(|e| format!("SSH authentication of {user} on {host} failed: {e}"))?;
+    let mut channel = session.channel_session().map_err(failed)?;
+    channel.exec(&command).map_err(failed)?;
+    session.set_blocking(false);
+    let mut stdout = LineBuffer::default();
+    let mut stderr = LineBuffer::default();
+    loop {
+        if kill_switch.is_killed() {
+            _ = session.disconnect(None, RUN_KILLED, None);
+            return Err(RUN_KILLED.to_string());
+        }
+        let read = stdout.pump(&mut channel, output)? | stderr.pump(channel.stderr(), output)?;
+        if channel.eof() && !read {
+            break;
+        }
+        if !read {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+    stdout.flush(output);
+    stderr.flush(output);
+    session.set_blocking(true);
+    channel.wait_close().map_err(failed)?;
+    let status = channel.exit_status().map_err(failed)?;
+    output.set_exit_code(status);
+    match status {
+        0 => Ok(()),
+        status => Err(format!("Remote command failed with exit status: {status}")),
+    }
+}
+
+/// Checks the key of a remote host against the `~/.ssh/known_hosts` file of the user of the service.
+///
+/// # Arguments
+///
+/// * `session` - A reference to the `Session` with the host, once its handshake is over.
+/// * `name` - A string that represents the name of the host.
+/// * `port` - A `u16` that represents the port of the host.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the host is known with the same key, or an error message if not.
+fn verify_host_key(session: &Session, name: &str, port: u16) -> Result<(), String> {
+    let file = std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".ssh/known_hosts"))
+        .ok_or("Cannot find the known hosts, HOME is not set")?;
+    let mut known_hosts = session
+        .known_hosts()
+        .map_err(|e| format!("Cannot read the known hosts: {e}"))?;
+    known_hosts
+        .read_file(&file, KnownHostFileKind::OpenSSH)
+        .map_err(|e| format!("Cannot read the known hosts from {}: {e}", file.display()))?;
+    let (key, _) = session
+        .host_key()
+        .ok_or_else(|| format!("{name} did not present a host key"))?;
+    match known_hosts.check_port(name, port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => Err(format!(
+            "The host key of {name} does not match the one in {}",
+            file.display()
+        )),
+        CheckResult::NotFound => Err(format!(
+            "The host key of {name} is unknown, add it to {} first, e.g. with ssh-keyscan",
+            file.display()
+        )),
+        CheckResult::Failure => Err(format!("Cannot check the host key of {name}")),
+    }
+}
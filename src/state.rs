@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+
+use serde_json::Value;
+
+/// The state of the jobs of the process, opened once when the service starts.
+static STATE: OnceLock<Mutex<JobState>> = OnceLock::new();
+
+/// `JobState` is a structure that keeps the key/value state of every job, so scripts can keep counters, watermarks or dedupe keys
+/// across runs.
+///
+/// The state is a JSON object holding the state of every job by its id, rewritten whenever a run has changed it, so it outlives the
+/// service. There is a single state per process: it is kept in memory only until the service opens its state file.
+///
+/// # Fields
+///
+/// * `file` - An `Option<PathBuf>` that represents the path of the state file. It is `None` if the state is only kept in memory.
+/// * `jobs` - A `BTreeMap<String, BTreeMap<String, Value>>` that represents the values of every job by key.
+/// * `changed` - A `bool` that represents whether the state has changed since it was last written.
+#[derive(Debug, Default)]
+struct JobState {
+    file: Option<PathBuf>,
+    jobs: BTreeMap<String, BTreeMap<String, Value>>,
+    changed: bool,
+}
+
+/// Opens the state kept in a file, reading the values it already holds.
+///
+/// The state can only be opened once: later calls are ignored.
+///
+/// # Arguments
+///
+/// * `file` - A `PathBuf` that represents the path of the state file, created on the first change if it does not exist.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns `Ok(())` if the state was opened, or an error if the file exists but cannot be read or parsed.
+pub fn open(file: PathBuf) -> io::Result<()> {
+    let jobs = match std::fs::read(&file) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+        Err(e) => return Err(e),
+    };
+    _ = STATE.set(Mutex::new(JobState {
+        file: Some(file),
+        jobs,
+        changed: false,
+    }));
+    Ok(())
+}
+
+/// Reads a value of the state of a job.
+///
+/// # Arguments
+///
+/// * `job` - A string that represents the id of the job.
+/// * `key` - A string that represents the key of the value.
+///
+/// # Returns
+///
+/// * `Option<Value>` - Returns the value, or `None` if the job has no value for the key.
+pub fn get(job: &str, key: &str) -> Option<Value> {
+    lock().jobs.get(job)?.get(key).cloned()
+}
+
+/// Sets a value of the state of a job, written to the state file by `persist`.
+///
+/// # Arguments
+///
+/// * `job` - A string that represents the id of the job.
+/// * `key` - A string that represents the key of the value.
+/// * `value` - A `Value` that represents the value.
+pub fn set(job: &str, key: &str, value: Value) {
+    let mut state = lock();
+    state
+        .jobs
+        .entry(job.to_string())
+        .or_default()
+        .insert(key.to_string(), value);
+    state.changed = true;
+}
+
+/// Removes a value of the state of a job, written to the state file by `persist`.
+///
+/// # Arguments
+///
+/// * `job` - A string that represents the id of the job.
+/// * `key` - A string that represents the key of the value.
+///
+/// # Returns
+///
+/// * `Option<Value>` - Returns the value removed, or `None` if the job had no value for the key.
+pub fn remove(job: &str, key: &str) -> Option<Value> {
+    let mut state = lock();
+    let values = state.jobs.get_mut(job)?;
+    let removed = values.remove(key)?;
+    if values.is_empty() {
+        state.jobs.remove(job);
+    }
+    state.changed = true;
+    Some(removed)
+}
+
+/// Forgets the state of a job, e.g. once it has been deleted, and writes the state file.
+///
+/// # Arguments
+///
+/// * `job` - A string that represents the id of the job.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns `Ok(())` if the state was written, or an error if not.
+pub fn forget(job: &str) -> io::Result<()> {
+    let mut state = lock();
+    if state.jobs.remove(job).is_some() {
+        state.changed = true;
+    }
+    write(&mut state)
+}
+
+/// Reads the state of every job, e.g. to take a snapshot of the service.
+///
+/// # Returns
+///
+/// * `BTreeMap<String, BTreeMap<String, Value>>` - Returns the values of every job by key, by the id of the job.
+pub fn export() -> BTreeMap<String, BTreeMap<String, Value>> {
+    lock().jobs.clone()
+}
+
+/// Merges the state of jobs into the state, e.g. restored from a snapshot of the service, and writes the state file.
+///
+/// # Arguments
+///
+/// * `jobs` - A `BTreeMap<String, BTreeMap<String, Value>>` that represents the values of every job by key, replacing the state of these jobs.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns `Ok(())` if the state was written, or an error if not.
+pub fn import(jobs: BTreeMap<String, BTreeMap<String, Value>>) -> io::Result<()> {
+    let mut state = lock();
+    if !jobs.is_empty() {
+        state.jobs.extend(jobs);
+        state.changed = true;
+    }
+    write(&mut state)
+}
+
+/// Writes the state file, if the state has changed since it was last written.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns `Ok(())` if the state is on disk, or only kept in memory, or an error if it cannot be written.
+pub fn persist() -> io::Result<()> {
+    write(&mut lock())
+}
+
+/// Writes the state file if the state has changed, to a temporary file first, renamed over the state file so it is never left half
+/// written.
+///
+/// # Arguments
+///
+/// * `state` - A mutable reference to the `JobState` to be written.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Returns `Ok(())` if the state is on disk, or only kept in memory, or an error if it cannot be written.
+fn write(state: &mut JobState) -> io::Result<()> {
+    let Some(file) = state.file.as_ref().filter(|_| state.changed) else {
+        return Ok(());
+    };
+    let mut temp = file.as_os_str().to_owned();
+    temp.push(".tmp");
+    let mut written = File::create(&temp)?;
+    written.write_all(&serde_json::to_vec_pretty(&state.jobs)?)?;
+    written.sync_all()?;
+    std::fs::rename(&temp, file)?;
+    state.changed = false;
+    Ok(())
+}
+
+/// Locks the state of the process.
+///
+/// # Returns
+///
+/// * `MutexGuard<JobState>` - Returns the guard of the state, kept in memory only if it has not been opened, even if a thread panicked
+///   while holding it.
+fn lock() -> MutexGuard<'static, JobState> {
+    STATE
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,318 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// The number of most recent days listed in the daily usage of a job.
+const DAILY_USAGE_DAYS: usize = 31;
+
+/// `Usage` is a structure that represents the machine time consumed by the runs of a job over a period.
+///
+/// # Fields
+///
+/// * `time` - A `Duration` that represents the cumulative execution time of the runs.
+/// * `runs` - A `u32` that represents the number of runs.
+/// * `failures` - A `u32` that represents the number of runs that failed.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub struct Usage {
+    #[serde(with = "humantime_serde")]
+    pub time: Duration,
+    pub runs: u32,
+    #[serde(default)]
+    pub failures: u32,
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.time += other.time;
+        self.runs += other.runs;
+        self.failures += other.failures;
+    }
+}
+
+/// `PeriodUsage` is a structure that represents the usage of a job over a calendar period.
+///
+/// # Fields
+///
+/// * `period` - A string that represents the period, formatted as `YYYY-MM-DD` for a day or `YYYY-MM` for a month.
+/// * `usage` - A `Usage` that represents the machine time consumed over the period.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct PeriodUsage {
+    pub period: String,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+/// `AccountedJob` is a structure that represents a job whose execution time is accounted for.
+///
+/// # Fields
+///
+/// * `job_id` - A string that represents the unique identifier of the job.
+/// * `name` - An `Option<String>` that represents the name of the job, if any.
+/// * `daily` - A `BTreeMap<NaiveDate, Usage>` that represents the usage of the job by local day.
+/// * `durations` - A vector of `Duration` instances that represent the durations of the runs of the job kept in the run history.
+/// * `last_failure` - An `Option<u64>` that represents the time the last failed run of the job started in Unix timestamp. It is `None` if no run of the job failed.
+#[derive(Debug, Clone)]
+pub struct AccountedJob {
+    pub job_id: String,
+    pub name: Option<String>,
+    pub daily: BTreeMap<NaiveDate, Usage>,
+    pub durations: Vec<Duration>,
+    pub last_failure: Option<u64>,
+}
+
+impl AccountedJob {
+    /// Returns the usage of the job over the month of a day.
+    ///
+    /// # Arguments
+    ///
+    /// * `day` - A `NaiveDate` that represents a day of the month.
+    ///
+    /// # Returns
+    ///
+    /// * `Usage` - Returns the usage of the job from the first day of the month up to the given day.
+    fn month_to_date(&self, day: NaiveDate) -> Usage {
+        let mut usage = Usage::default();
+        for (_, daily) in self.daily.range(day.with_day(1).unwrap_or(day)..=day) {
+            usage += *daily;
+        }
+        usage
+    }
+}
+
+/// `RunSummary` is a structure that represents how reliably and how fast a job runs, to spot flaky jobs.
+///
+/// # Fields
+///
+/// * `runs` - A `u32` that represents the number of runs of the job over the days its execution time is accounted for.
+/// * `failures` - A `u32` that represents the number of these runs that failed.
+/// * `success_rate` - An `Option<f64>` that represents the share of these runs that succeeded, from 0 to 1. It is `None` if the job has not run.
+/// * `mean_duration` - An `Option<Duration>` that represents the mean duration of these runs. It is `None` if the job has not run.
+/// * `p95_duration` - An `Option<Duration>` that represents the 95th percentile of the durations of the runs kept in the run history. It is `None` if the job has not run.
+/// * `last_failure` - An `Option<u64>` that represents the time the last failed run started in Unix timestamp. It is `None` if no run failed.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone)]
+pub struct RunSummary {
+    pub runs: u32,
+    pub failures: u32,
+    pub success_rate: Option<f64>,
+    #[serde(with = "humantime_serde")]
+    pub mean_duration: Option<Duration>,
+    #[serde(with = "humantime_serde")]
+    pub p95_duration: Option<Duration>,
+    pub last_failure: Option<u64>,
+}
+
+/// `JobStats` is a structure that represents the machine time consumed by a job, and how reliably it runs.
+///
+/// # Fields
+///
+/// * `job_id` - A string that represents the unique identifier of the job.
+/// * `name` - An `Option<String>` that represents the name of the job, if any.
+/// * `summary` - A `RunSummary` that represents the aggregated metrics of the runs of the job, flattened into the statistics.
+/// * `daily` - A vector of `PeriodUsage` instances that represent the usage of the job on its most recent days with runs, oldest first.
+/// * `monthly` - A vector of `PeriodUsage` instances that represent the usage of the job by month, oldest first.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct JobStats {
+    pub job_id: String,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub summary: RunSummary,
+    pub daily: Vec<PeriodUsage>,
+    pub monthly: Vec<PeriodUsage>,
+}
+
+/// `CostCenterStats` is a structure that represents the machine time charged to a cost center.
+///
+/// # Fields
+///
+/// * `cost_center` - A string that represents the cost center.
+/// * `daily` - A vector of `PeriodUsage` instances that represent the usage charged to the cost center on its most recent days with runs, oldest first.
+/// * `monthly` - A vector of `PeriodUsage` instances that represent the usage charged to the cost center by month, oldest first.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct CostCenterStats {
+    pub cost_center: String,
+    pub daily: Vec<PeriodUsage>,
+    pub monthly: Vec<PeriodUsage>,
+}
+
+/// `DigestEntry` is a structure that represents the usage of a single job in a digest report.
+///
+/// # Fields
+///
+/// * `job_id` - A string that represents the unique identifier of the job.
+/// * `name` - An `Option<String>` that represents the name of the job, if any.
+/// * `today` - A `Usage` that represents the machine time consumed by the job today.
+/// * `this_month` - A `Usage` that represents the machine time consumed by the job this month.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct DigestEntry {
+    pub job_id: String,
+    pub name: Option<String>,
+    pub today: Usage,
+    pub this_month: Usage,
+}
+
+/// `Digest` is a structure that represents a report of the jobs consuming the most machine time.
+///
+/// # Fields
+///
+/// * `day` - A string that represents the local day the report is made on, formatted as `YYYY-MM-DD`.
+/// * `today` - A `Usage` that represents the machine time consumed by all the jobs today.
+/// * `this_month` - A `Usage` that represents the machine time consumed by all the jobs this month.
+/// * `jobs` - A vector of `DigestEntry` instances that represent the jobs that ran this month, the most consuming first.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Digest {
+    pub day: String,
+    pub today: Usage,
+    pub this_month: Usage,
+    pub jobs: Vec<DigestEntry>,
+}
+
+/// Computes the usage statistics of a job.
+///
+/// # Arguments
+///
+/// * `job` - A reference to the `AccountedJob` to compute the statistics of.
+///
+/// # Returns
+///
+/// * `JobStats` - Returns the aggregated metrics of the runs of the job, and its usage by day and by month.
+pub fn job_stats(job: &AccountedJob) -> JobStats {
+    JobStats {
+        job_id: job.job_id.clone(),
+        name: job.name.clone(),
+        summary: run_summary(job),
+        daily: daily_periods(&job.daily),
+        monthly: monthly_periods(&job.daily),
+    }
+}
+
+/// Aggregates the metrics of the runs of a job.
+///
+/// The counts and the mean duration cover every accounted day, while the 95th percentile, by nearest rank, covers the runs kept in
+/// the run history.
+///
+/// # Arguments
+///
+/// * `job` - A reference to the `AccountedJob` to aggregate the runs of.
+///
+/// # Returns
+///
+/// * `RunSummary` - Returns the aggregated metrics of the runs of the job.
+fn run_summary(job: &AccountedJob) -> RunSummary {
+    let mut total = Usage::default();
+    for usage in job.daily.values() {
+        total += *usage;
+    }
+    let mut durations = job.durations.clone();
+    durations.sort();
+    let p95 = (durations.len() * 95).div_ceil(100).saturating_sub(1);
+    RunSummary {
+        runs: total.runs,
+        failures: total.failures,
+        success_rate: (total.runs > 0)
+            .then(|| f64::from(total.runs - total.failures) / f64::from(total.runs)),
+        mean_duration: (total.runs > 0).then(|| total.time / total.runs),
+        p95_duration: durations.get(p95).copied(),
+        last_failure: job.last_failure,
+    }
+}
+
+/// Computes the usage statistics of a cost center.
+///
+/// # Arguments
+///
+/// * `cost_center` - A string that represents the cost center.
+/// * `daily` - A reference to the usage charged to the cost center by local day.
+///
+/// # Returns
+///
+/// * `CostCenterStats` - Returns the usage charged to the cost center by day and by month.
+pub fn cost_center_stats(
+    cost_center: String,
+    daily: &BTreeMap<NaiveDate, Usage>,
+) -> CostCenterStats {
+    CostCenterStats {
+        cost_center,
+        daily: daily_periods(daily),
+        monthly: monthly_periods(daily),
+    }
+}
+
+/// Lists the most recent days of a usage by local day.
+///
+/// # Arguments
+///
+/// * `daily` - A reference to the usage by local day.
+///
+/// # Returns
+///
+/// * `Vec<PeriodUsage>` - Returns the usage on the last `DAILY_USAGE_DAYS` days with runs, oldest first.
+fn daily_periods(daily: &BTreeMap<NaiveDate, Usage>) -> Vec<PeriodUsage> {
+    daily
+        .iter()
+        .skip(daily.len().saturating_sub(DAILY_USAGE_DAYS))
+        .map(|(day, usage)| PeriodUsage {
+            period: day.format("%Y-%m-%d").to_string(),
+            usage: *usage,
+        })
+        .collect()
+}
+
+/// Sums a usage by local day into a usage by month.
+///
+/// # Arguments
+///
+/// * `daily` - A reference to the usage by local day.
+///
+/// # Returns
+///
+/// * `Vec<PeriodUsage>` - Returns the usage by month, oldest first.
+fn monthly_periods(daily: &BTreeMap<NaiveDate, Usage>) -> Vec<PeriodUsage> {
+    let mut monthly: BTreeMap<String, Usage> = BTreeMap::new();
+    for (day, usage) in daily {
+        *monthly.entry(day.format("%Y-%m").to_string()).or_default() += *usage;
+    }
+    monthly
+        .into_iter()
+        .map(|(period, usage)| PeriodUsage { period, usage })
+        .collect()
+}
+
+/// Builds the digest report of the machine time consumed by jobs.
+///
+/// # Arguments
+///
+/// * `jobs` - A slice of `AccountedJob` instances that represent the jobs to report on.
+/// * `today` - A `NaiveDate` that represents the local day the report is made on.
+///
+/// # Returns
+///
+/// * `Digest` - Returns the report, listing the jobs that ran this month by decreasing execution time.
+pub fn build_digest(jobs: &[AccountedJob], today: NaiveDate) -> Digest {
+    let mut entries: Vec<_> = jobs
+        .iter()
+        .map(|job| DigestEntry {
+            job_id: job.job_id.clone(),
+            name: job.name.clone(),
+            today: job.daily.get(&today).copied().unwrap_or_default(),
+            this_month: job.month_to_date(today),
+        })
+        .filter(|entry| entry.this_month.runs > 0)
+        .collect();
+    entries.sort_by_key(|entry| (Reverse(entry.this_month.time), entry.job_id.clone()));
+    let mut digest = Digest {
+        day: today.format("%Y-%m-%d").to_string(),
+        today: Usage::default(),
+        this_month: Usage::default(),
+        jobs: Vec::new(),
+    };
+    for entry in &entries {
+        digest.today += entry.today;
+        digest.this_month += entry.this_month;
+    }
+    digest.jobs = entries;
+    digest
+}
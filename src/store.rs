@@ -0,0 +1,615 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::crypto::StoreKey;
+use crate::job::{Job, JobOptions, Trigger};
+use crate::schedule::translate;
+use crate::CronusResult;
+
+/// The version of the schema of the job store written by this version of cronus.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// A migration upgrading a job store by one version of its schema, in place.
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// The migrations of the job store, in order: the migration at index `i` upgrades a store from schema version `i` to `i + 1`.
+///
+/// A migration is only ever appended, never changed once released, so a store written by any release can be upgraded step by step.
+const MIGRATIONS: &[Migration] = &[wrap_jobs];
+
+/// The number of entries the journal of a job store holds before it is compacted into the store file.
+pub const COMPACT_AFTER: usize = 1000;
+
+/// What the jobs of an encrypted store file are bound to, so they cannot be passed off as an entry of its journal.
+const STORE_CONTEXT: &str = "cronus-store";
+
+/// What the entries of the journal of an encrypted store are bound to.
+const JOURNAL_CONTEXT: &str = "cronus-journal";
+
+/// `StoredJob` is a structure that represents a job kept in the job store, registered again when the service starts.
+///
+/// # Fields
+///
+/// * `id` - A `Uuid` that represents the ID of the job, kept across restarts.
+/// * `trigger` - A `Trigger` that represents what makes the job run.
+/// * `job` - A `Job` that represents the job itself.
+/// * `options` - A `JobOptions` that represents the execution settings of the job.
+/// * `paused` - A `bool` that represents whether the executions of the job are suspended.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StoredJob {
+    pub id: Uuid,
+    pub trigger: Trigger,
+    pub job: Job,
+    #[serde(default)]
+    pub options: JobOptions,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl StoredJob {
+    /// Checks whether a job has the same schedule and payload as the stored job, so registering both would run the same work twice.
+    ///
+    /// Cron expressions are compared as the job scheduler runs them, so e.g. `0 3 * * *` and `0 0 3 * * *` are the same schedule.
+    ///
+    /// # Arguments
+    ///
+    /// * `trigger` - A reference to the `Trigger` of the job.
+    /// * `job` - A reference to the `Job` of the job.
+    /// * `options` - A reference to the `JobOptions` of the job, whose timezone is compared.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the job has the same trigger, payload and timezone as the stored job, and `false` if not.
+    pub fn duplicates(&self, trigger: &Trigger, job: &Job, options: &JobOptions) -> bool {
+        let same_trigger = match (self.trigger.cron(), trigger.cron()) {
+            (Some(stored), Some(cron)) => {
+                stored == cron
+                    || translate(stored).is_ok_and(|stored| translate(cron) == Ok(stored))
+            }
+            _ => self.trigger == *trigger,
+        };
+        same_trigger && self.job == *job && self.options.timezone == options.timezone
+    }
+}
+
+/// Finds a stored job a job would duplicate, see `StoredJob::duplicates`.
+///
+/// # Arguments
+///
+/// * `stored` - A slice of `StoredJob` instances that represent the registered jobs.
+/// * `trigger` - A reference to the `Trigger` of the job.
+/// * `job` - A reference to the `Job` of the job.
+/// * `options` - A reference to the `JobOptions` of the job.
+///
+/// # Returns
+///
+/// * `Option<&StoredJob>` - Returns the first stored job with the same schedule and payload, or `None` if there is none.
+pub fn find_duplicate<'a>(
+    stored: &'a [StoredJob],
+    trigger: &Trigger,
+    job: &Job,
+    options: &JobOptions,
+) -> Option<&'a StoredJob> {
+    stored
+        .iter()
+        .find(|stored| stored.duplicates(trigger, job, options))
+}
+
+/// `JournalEntry` is an enumeration that represents a change to the jobs of a job store, appended to its journal.
+///
+/// # Variants
+///
+/// * `Put(StoredJob)` - Represents a job added or changed. It contains the job as it is now.
+/// * `Delete { id }` - Represents a job deleted. It contains the id of the job.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum JournalEntry {
+    Put(StoredJob),
+    Delete { id: Uuid },
+}
+
+/// `JobStore` is a structure that keeps the jobs of the service in a file, so they survive restarts.
+///
+/// The store is a JSON document holding the version of its schema and the jobs. A store written by an older release is migrated when it
+/// is opened, after being backed up next to itself, so upgrading cronus never requires wiping it.
+///
+/// Changes are not written to the store file right away, but appended to a journal next to it, `<file>.wal`, one JSON entry per line,
+/// and flushed to disk before they are acknowledged. The journal is replayed over the store file when the store is opened, so an
+/// acknowledged change survives a crash at any point, and compacted into the store file once it holds `COMPACT_AFTER` entries.
+///
+/// With a key, the jobs of the store file and every entry of the journal are encrypted, as job definitions often hold tokens and
+/// connection strings, and a store file or an entry written in clear is refused, so it cannot be slipped in beside them. A store written
+/// in clear is encrypted once with `encrypt`, with its service stopped.
+///
+/// # Fields
+///
+/// * `file` - A `PathBuf` that represents the path of the store file.
+/// * `journal` - A `File` that represents the journal, opened for appending.
+/// * `entries` - A `usize` that represents the number of entries appended to the journal since it was last compacted.
+/// * `key` - An `Option<StoreKey>` that represents the key the store is encrypted with. It is `None` if the store is not encrypted.
+#[derive(Debug)]
+pub struct JobStore {
+    file: PathBuf,
+    journal: File,
+    entries: usize,
+    key: Option<StoreKey>,
+}
+
+impl JobStore {
+    /// Opens the store kept in a file, migrating it to the current schema if it was written by an older release.
+    ///
+    /// Before a store is migrated, it is copied to `<file>.v<version>.bak`, e.g. `jobs.json.v0.bak`. A store written by a newer release
+    /// is refused rather than downgraded. The journal is then replayed over the jobs of the store file, ignoring a last entry left half
+    /// written by a crash, which was never acknowledged, and compacted into the store file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - A `PathBuf` that represents the path of the store file, created on the first save if it does not exist.
+    /// * `key` - An `Option<StoreKey>` that represents the key the store is encrypted with. It is `None` if the store is not encrypted.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<(JobStore, Vec<StoredJob>)>` - Returns a `CronusResult` that contains the store and the jobs it holds on success, or an
+    ///   error if the store file or the journal cannot be read, decrypted, parsed or migrated, is written in clear while the store has a
+    ///   key, or the store file is written in a newer schema.
+    pub fn open(file: PathBuf, key: Option<StoreKey>) -> CronusResult<(Self, Vec<StoredJob>)> {
+        let jobs = load_jobs(&file, key.as_ref(), true)?;
+        let mut store = Self {
+            journal: OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(journal_file(&file))?,
+            file,
+            entries: 0,
+            key,
+        };
+        store.compact(&jobs)?;
+        Ok((store, jobs))
+    }
+
+    /// Appends a change to the journal, encrypted if the store has a key, and flushes it to disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - A reference to the `JournalEntry` that represents the change.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<bool>` - Returns a `CronusResult` that contains whether the journal holds `COMPACT_AFTER` entries and should be
+    ///   compacted if successful, or an error if the change cannot be written, in which case it must not be acknowledged.
+    pub fn record(&mut self, entry: &JournalEntry) -> CronusResult<bool> {
+        let mut line = serde_json::to_vec(entry)?;
+        if let Some(key) = &self.key {
+            line = key.seal(JOURNAL_CONTEXT, &line)?.into_bytes();
+        }
+        line.push(b'\n');
+        self.journal.write_all(&line)?;
+        self.journal.sync_data()?;
+        self.entries += 1;
+        Ok(self.entries >= COMPACT_AFTER)
+    }
+
+    /// Compacts the journal, writing the jobs to the store file with `write_jobs` and emptying the journal once they are on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `jobs` - A slice of `StoredJob` instances that represent the jobs of the store, with every change of the journal applied.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if successful, or an error if the store cannot be written.
+    pub fn compact(&mut self, jobs: &[StoredJob]) -> CronusResult<()> {
+        write_jobs(&self.file, jobs, self.key.as_ref())?;
+        self.journal.set_len(0)?;
+        self.journal.sync_all()?;
+        self.entries = 0;
+        Ok(())
+    }
+}
+
+/// Checks that a store can be opened, without changing it: its store file is read, decrypted, parsed and migrated in memory, and its
+/// journal is replayed over it, like `JobStore::open` does.
+///
+/// # Arguments
+///
+/// * `file` - A path to the store file.
+/// * `key` - An `Option<&StoreKey>` that represents the key the store is encrypted with. It is `None` if the store is not encrypted.
+///
+/// # Returns
+///
+/// * `CronusResult<usize>` - Returns a `CronusResult` that contains the number of jobs of the store on success, or the error opening the
+///   store would fail with.
+pub fn check(file: &Path, key: Option<&StoreKey>) -> CronusResult<usize> {
+    load_jobs(file, key, false).map(|jobs| jobs.len())
+}
+
+/// Encrypts a store written in clear with a key, once, with its service stopped: its store file, migrated to the current schema and
+/// with its journal replayed over it, is written encrypted, then its journal is emptied and the copies of the store file backed up
+/// before a migration, which hold its jobs in clear, are removed.
+///
+/// # Arguments
+///
+/// * `file` - A path to the store file.
+/// * `key` - A reference to the `StoreKey` the store is encrypted with from then on.
+///
+/// # Returns
+///
+/// * `CronusResult<usize>` - Returns a `CronusResult` that contains the number of jobs of the store on success, or an error if the store
+///   is encrypted already, or cannot be read or written.
+pub fn encrypt(file: &Path, key: &StoreKey) -> CronusResult<usize> {
+    let sealed = match std::fs::read(file) {
+        Ok(bytes) => serde_json::from_slice::<Value>(&bytes)?
+            .get("sealed")
+            .is_some(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => return Err(e.into()),
+    };
+    if sealed {
+        return Err(format!("The job store `{}` is encrypted already", file.display()).into());
+    }
+    let jobs = load_jobs(file, None, false)?;
+    write_jobs(file, &jobs, Some(key))?;
+    File::create(journal_file(file))?.sync_all()?;
+    for version in 0..SCHEMA_VERSION {
+        match std::fs::remove_file(backup_file(file, version)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(jobs.len())
+}
+
+/// Reads the jobs of a store, replaying its journal over its store file, see `JobStore::open`.
+///
+/// # Arguments
+///
+/// * `file` - A path to the store file.
+/// * `key` - An `Option<&StoreKey>` that represents the key the store is encrypted with. It is `None` if the store is not encrypted.
+/// * `back_up` - A `bool` that represents whether the store file is copied before it is migrated, as it is about to be rewritten.
+///
+/// # Returns
+///
+/// * `CronusResult<Vec<StoredJob>>` - Returns a `CronusResult` that contains the jobs, by id, on success, or an error if the store file
+///   cannot be read, or the journal is corrupted before its last entry.
+fn load_jobs(file: &Path, key: Option<&StoreKey>, back_up: bool) -> CronusResult<Vec<StoredJob>> {
+    let mut jobs: BTreeMap<Uuid, StoredJob> = read_jobs(file, key, back_up)?
+        .into_iter()
+        .map(|job| (job.id, job))
+        .collect();
+    let journal_file = journal_file(file);
+    let journal = match std::fs::read_to_string(&journal_file) {
+        Ok(journal) => journal,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut lines = journal.lines().peekable();
+    while let Some(line) = lines.next() {
+        match read_entry(line, key) {
+            Ok(JournalEntry::Put(job)) => {
+                jobs.insert(job.id, job);
+            }
+            Ok(JournalEntry::Delete { id }) => {
+                jobs.remove(&id);
+            }
+            Err(_) if lines.peek().is_none() => {}
+            Err(e) => {
+                return Err(
+                    format!("The journal `{}` is corrupted: {e}", journal_file.display()).into(),
+                )
+            }
+        }
+    }
+    Ok(jobs.into_values().collect())
+}
+
+/// Writes jobs to a store file in the current schema, replacing the file if it exists.
+///
+/// The store is written to a temporary file first, flushed to disk and renamed over the store file, so it is never left half written,
+/// and the rename is flushed to disk as well. With a key, the jobs are encrypted as the `sealed` field of the store, next to its
+/// schema version, in place of the `jobs` field.
+///
+/// # Arguments
+///
+/// * `file` - A path to the store file.
+/// * `jobs` - A slice of `StoredJob` instances that represent the jobs to be written.
+/// * `key` - An `Option<&StoreKey>` that represents the key the jobs are encrypted with. It is `None` if they are written in clear.
+///
+/// # Returns
+///
+/// * `CronusResult<()>` - Returns a `CronusResult` that contains `()` if the jobs are on disk, or an error if not.
+pub fn write_jobs(file: &Path, jobs: &[StoredJob], key: Option<&StoreKey>) -> CronusResult<()> {
+    let document = match key {
+        Some(key) => {
+            json!({ "schema": SCHEMA_VERSION, "sealed": key.seal(STORE_CONTEXT, &serde_json::to_vec(jobs)?)? })
+        }
+        None => json!({ "schema": SCHEMA_VERSION, "jobs": jobs }),
+    };
+    let mut temp = file.as_os_str().to_owned();
+    temp.push(".tmp");
+    let mut written = File::create(&temp)?;
+    written.write_all(&serde_json::to_vec_pretty(&document)?)?;
+    written.sync_all()?;
+    std::fs::rename(&temp, file)?;
+    if let Some(dir) = file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Reads the jobs of a store file, migrating them to the current schema if it was written by an older release.
+///
+/// # Arguments
+///
+/// * `file` - A path to the store file.
+/// * `key` - An `Option<&StoreKey>` that represents the key the jobs are encrypted with. It is `None` if the store is not encrypted.
+/// * `back_up` - A `bool` that represents whether the file is copied to `<file>.v<version>.bak` before it is migrated.
+///
+/// # Returns
+///
+/// * `CronusResult<Vec<StoredJob>>` - Returns a `CronusResult` that contains the jobs, none if the file does not exist, on success, or an
+///   error if the file cannot be read, decrypted, parsed or migrated, or is written in a newer schema.
+fn read_jobs(file: &Path, key: Option<&StoreKey>, back_up: bool) -> CronusResult<Vec<StoredJob>> {
+    let document = match std::fs::read(file) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let version = schema_version(&document)?;
+    if back_up && version < SCHEMA_VERSION {
+        std::fs::copy(file, backup_file(file, version))?;
+    }
+    parse_jobs(document, key, &file.display().to_string())
+}
+
+/// Reads the jobs of a job store document, migrating them to the current schema if it was written by an older release, e.g. the
+/// jobs of a snapshot archive.
+///
+/// # Arguments
+///
+/// * `document` - A `Value` that represents the store.
+/// * `key` - An `Option<&StoreKey>` that represents the key the jobs are encrypted with. It is `None` if the store is not encrypted.
+/// * `source` - A string that represents where the store was read from, in error messages, e.g. the path of the store file.
+///
+/// # Returns
+///
+/// * `CronusResult<Vec<StoredJob>>` - Returns a `CronusResult` that contains the jobs on success, or an error if the store cannot be
+///   decrypted, parsed or migrated, is written in clear while a key is given, or is written in a newer schema.
+pub fn parse_jobs(
+    mut document: Value,
+    key: Option<&StoreKey>,
+    source: &str,
+) -> CronusResult<Vec<StoredJob>> {
+    let version = schema_version(&document)?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "The job store `{source}` is written in schema version {version}, this version of cronus reads up to version {SCHEMA_VERSION}"
+        )
+        .into());
+    }
+    for (from, migrate) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migrate(&mut document).map_err(|e| {
+            format!("Cannot migrate the job store `{source}` from schema version {from}: {e}")
+        })?;
+        document["schema"] = json!(from as u64 + 1);
+    }
+    if let Some(sealed) = document.get("sealed").and_then(Value::as_str) {
+        let key = key.ok_or_else(|| {
+            format!("The job store `{source}` is encrypted, pass its key with --store-key")
+        })?;
+        let jobs = key
+            .open(STORE_CONTEXT, sealed)
+            .map_err(|e| format!("Cannot read the job store `{source}`: {e}"))?;
+        return Ok(serde_json::from_slice(&jobs)?);
+    }
+    if key.is_some() {
+        return Err(format!(
+            "The job store `{source}` is written in clear, encrypt it once with cronus encrypt-store"
+        )
+        .into());
+    }
+    Ok(serde_json::from_value(document["jobs"].take())?)
+}
+
+/// Reads an entry of the journal of a store, encrypted if the store has a key, or else written in clear.
+///
+/// # Arguments
+///
+/// * `line` - A string that represents the line of the entry.
+/// * `key` - An `Option<&StoreKey>` that represents the key the journal is encrypted with. It is `None` if the store is not encrypted.
+///
+/// # Returns
+///
+/// * `Result<JournalEntry, String>` - Returns the entry, or an error message if it cannot be decrypted or parsed, or is written in
+///   clear while the store has a key.
+fn read_entry(line: &str, key: Option<&StoreKey>) -> Result<JournalEntry, String> {
+    if line.starts_with('{') {
+        if key.is_some() {
+            return Err("The entry is written in clear, while the store is encrypted".to_string());
+        }
+        return serde_json::from_str(line).map_err(|e| e.to_string());
+    }
+    let entry = key
+        .ok_or("The entry is encrypted, pass the key of the store with --store-key")?
+        .open(JOURNAL_CONTEXT, line)?;
+    serde_json::from_slice(&entry).map_err(|e| e.to_string())
+}
+
+/// Returns the path of the journal of a store.
+///
+/// # Arguments
+///
+/// * `file` - A path to the store file.
+///
+/// # Returns
+///
+/// * `PathBuf` - Returns the path of the journal.
+pub fn journal_file(file: &Path) -> PathBuf {
+    let mut journal = file.as_os_str().to_owned();
+    journal.push(".wal");
+    PathBuf::from(journal)
+}
+
+/// Returns the version of the schema a store is written in.
+///
+/// # Arguments
+///
+/// * `document` - A reference to the `Value` that represents the store.
+///
+/// # Returns
+///
+/// * `Result<u64, String>` - Returns the version, `0` for a store written before the schema was versioned, or an error message if the
+///   version is malformed.
+fn schema_version(document: &Value) -> Result<u64, String> {
+    match document.get("schema") {
+        Some(version) => version.as_u64().ok_or_else(|| {
+            "The schema version of the job store must be a positive integer".to_string()
+        }),
+        None if document.is_array() => Ok(0),
+        None => Err("The job store is missing its schema version".to_string()),
+    }
+}
+
+/// Returns the path a store is backed up to before it is migrated.
+///
+/// # Arguments
+///
+/// * `file` - A path to the store file.
+/// * `version` - A `u64` that represents the schema version of the store being backed up.
+///
+/// # Returns
+///
+/// * `PathBuf` - Returns the path of the backup.
+fn backup_file(file: &Path, version: u64) -> PathBuf {
+    let mut backup = file.as_os_str().to_owned();
+    backup.push(format!(".v{version}.bak"));
+    PathBuf::from(backup)
+}
+
+/// Migrates a store from schema version 0, a bare array of jobs, to version 1, an object holding its schema version and the jobs.
+///
+/// # Arguments
+///
+/// * `document` - A mutable reference to the `Value` that represents the store.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the store was migrated, or an error message if it is not an array.
+fn wrap_jobs(document: &mut Value) -> Result<(), String> {
+    if !document.is_array() {
+        return Err("Expected an array of jobs".to_string());
+    }
+    *document = json!({ "jobs": document.take() });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cronus-{}-{name}", Uuid::new_v4()))
+    }
+
+    fn key() -> StoreKey {
+        let file = temp_file("key");
+        std::fs::write(&file, "22".repeat(32)).unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let key = StoreKey::load(&file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        key
+    }
+
+    fn stored_job() -> StoredJob {
+        StoredJob {
+            id: Uuid::new_v4(),
+            trigger: Trigger::Manual,
+            job: Job::Command(PathBuf::from("/bin/true"), vec!["s3cr3t".to_string()], None),
+            options: JobOptions::default(),
+            paused: false,
+        }
+    }
+
+    fn remove(file: &Path) {
+        for file in [file.to_path_buf(), journal_file(file)] {
+            _ = std::fs::remove_file(file);
+        }
+    }
+
+    #[test]
+    fn encrypted_store_and_journal_are_read_with_the_key() {
+        let file = temp_file("jobs.json");
+        let key = key();
+        let (job, other) = (stored_job(), stored_job());
+        let (mut store, jobs) = JobStore::open(file.clone(), Some(key.clone())).unwrap();
+        assert!(jobs.is_empty());
+        store.record(&JournalEntry::Put(job.clone())).unwrap();
+        store.record(&JournalEntry::Put(other.clone())).unwrap();
+        store
+            .record(&JournalEntry::Delete { id: other.id })
+            .unwrap();
+        let journal = std::fs::read_to_string(journal_file(&file)).unwrap();
+        assert!(!journal.contains("s3cr3t"));
+        assert_eq!(check(&file, Some(&key)).unwrap(), 1);
+        assert!(check(&file, None).is_err());
+        let (_, jobs) = JobStore::open(file.clone(), Some(key)).unwrap();
+        assert_eq!(jobs, vec![job]);
+        assert!(!std::fs::read_to_string(&file).unwrap().contains("s3cr3t"));
+        remove(&file);
+    }
+
+    #[test]
+    fn clear_store_is_refused_with_a_key() {
+        let file = temp_file("jobs.json");
+        write_jobs(&file, &[stored_job()], None).unwrap();
+        assert!(JobStore::open(file.clone(), Some(key())).is_err());
+        let document = json!({ "schema": SCHEMA_VERSION, "jobs": [] });
+        assert!(parse_jobs(document.clone(), Some(&key()), "jobs.json").is_err());
+        assert!(parse_jobs(document, None, "jobs.json").is_ok());
+        remove(&file);
+    }
+
+    #[test]
+    fn clear_journal_entry_is_refused_with_a_key() {
+        let line = serde_json::to_string(&JournalEntry::Put(stored_job())).unwrap();
+        assert!(read_entry(&line, None).is_ok());
+        assert!(read_entry(&line, Some(&key())).is_err());
+        let file = temp_file("jobs.json");
+        let key = key();
+        write_jobs(&file, &[], Some(&key)).unwrap();
+        let job = serde_json::to_string(&JournalEntry::Put(stored_job())).unwrap();
+        std::fs::write(journal_file(&file), format!("{line}\n{job}\n")).unwrap();
+        assert!(JobStore::open(file.clone(), Some(key)).is_err());
+        remove(&file);
+    }
+
+    #[test]
+    fn clear_store_is_encrypted_once() {
+        let file = temp_file("jobs.json");
+        let (job, other) = (stored_job(), stored_job());
+        std::fs::write(&file, serde_json::to_vec(&[&job]).unwrap()).unwrap();
+        let (mut store, _) = JobStore::open(file.clone(), None).unwrap();
+        let backup = backup_file(&file, 0);
+        assert!(backup.exists());
+        store.record(&JournalEntry::Put(other.clone())).unwrap();
+        let key = key();
+        assert_eq!(encrypt(&file, &key).unwrap(), 2);
+        assert!(!backup.exists());
+        assert!(std::fs::read_to_string(journal_file(&file))
+            .unwrap()
+            .is_empty());
+        assert!(!std::fs::read_to_string(&file).unwrap().contains("s3cr3t"));
+        assert!(encrypt(&file, &key).is_err());
+        let (_, jobs) = JobStore::open(file.clone(), Some(key)).unwrap();
+        assert_eq!(jobs.len(), 2);
+        remove(&file);
+    }
+}
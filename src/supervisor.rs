@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::task::{spawn_local, LocalSet};
+
+use crate::command::CommandResponse;
+use crate::scheduler::CronusScheduler;
+use crate::CronusResult;
+
+/// `SupervisedService` is a structure that represents a service run by the supervisor, declared like the options of `cronus run`.
+///
+/// # Fields
+///
+/// * `path` - A `PathBuf` that represents the command acceptance path of the service, `/tmp` by default.
+/// * `http_addr` - An `Option<SocketAddr>` that represents the address the HTTP API of the service listens on. It is `None` if the
+///   service serves no HTTP API.
+/// * `tcp_addr` - An `Option<SocketAddr>` that represents the address the command socket of the service also listens on over TCP. It
+///   is `None` if the service is only reached on its command acceptance path.
+/// * `health_addr` - An `Option<SocketAddr>` that represents the address the health probes of the service are served on. It is `None`
+///   if the service serves no health probes.
+/// * `max_parallel` - An `Option<NonZeroUsize>` that represents the number of workers running the jobs of the service. It is `None`
+///   for the default number of workers.
+/// * `config` - An `Option<PathBuf>` that represents the configuration file of the service, with its hooks, notifiers, policy and
+///   tokens. It is `None` if the service has no configuration file.
+/// * `store` - An `Option<PathBuf>` that represents the job store of the service. It is `None` if its jobs are only kept in memory.
+/// * `store_key` - An `Option<PathBuf>` that represents the key file the job store and the backups of the service are encrypted with.
+/// * `lease_window` - An `Option<Duration>` that represents the window of the lease on the job store shared with a service of another
+///   supervisor, e.g. `10s`. It is `None` if the job store is not shared.
+/// * `reconcile` - An `Option<PathBuf>` that represents the job file the jobs of the service are converged to, see `--reconcile`. It
+///   is `None` if the jobs of the service are only changed by commands.
+/// * `follow` - An `Option<String>` that represents the name of the service the service follows, on the same command acceptance path.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SupervisedService {
+    #[serde(default = "default_path")]
+    pub path: PathBuf,
+    #[serde(default)]
+    pub http_addr: Option<SocketAddr>,
+    #[serde(default)]
+    pub tcp_addr: Option<SocketAddr>,
+    #[serde(default)]
+    pub health_addr: Option<SocketAddr>,
+    #[serde(default)]
+    pub max_parallel: Option<NonZeroUsize>,
+    #[serde(default)]
+    pub config: Option<PathBuf>,
+    #[serde(default)]
+    pub store: Option<PathBuf>,
+    #[serde(default)]
+    pub store_key: Option<PathBuf>,
+    #[serde(default, with = "humantime_serde")]
+    pub lease_window: Option<Duration>,
+    #[serde(default)]
+    pub reconcile: Option<PathBuf>,
+    #[serde(default)]
+    pub follow: Option<String>,
+}
+
+/// Returns the command acceptance path of a service that declares none, the default of `cronus run`.
+fn default_path() -> PathBuf {
+    PathBuf::from("/tmp")
+}
+
+/// `SupervisorConfig` is a structure that represents the services run by the supervisor, read from its configuration file, e.g.
+/// `services.toml`:
+///
+/// ```toml
+/// [services.billing]
+/// store = "/var/lib/cronus/billing.json"
+/// config = "/etc/cronus/billing.toml"
+///
+/// [services.reports]
+/// store = "/var/lib/cronus/reports.json"
+/// http_addr = "127.0.0.1:8081"
+/// ```
+///
+/// # Fields
+///
+/// * `services` - A `BTreeMap<String, SupervisedService>` that represents every service by its name.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SupervisorConfig {
+    pub services: BTreeMap<String, SupervisedService>,
+}
+
+impl SupervisorConfig {
+    /// Loads the services run by the supervisor from a file.
+    ///
+    /// A configuration file with a `.toml` extension is a TOML document, any other configuration file is a JSON object, like the
+    /// configuration file of a service.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A path to the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<SupervisorConfig>` - Returns a `CronusResult` that contains the configuration on success, or an error if the
+    ///   file cannot be read or parsed, declares no service, or declares services sharing a job store, an HTTP address, a TCP address or a health
+    ///   address.
+    pub fn load(path: &Path) -> CronusResult<Self> {
+        let config: Self = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            serde_json::from_slice(&std::fs::read(path)?)?
+        };
+        if config.services.is_empty() {
+            return Err("The supervisor declares no service".into());
+        }
+        let mut stores = BTreeMap::new();
+        let mut http_addrs = BTreeMap::new();
+        let mut tcp_addrs = BTreeMap::new();
+        let mut health_addrs = BTreeMap::new();
+        for (name, service) in &config.services {
+            if let Some(other) = service
+                .store
+                .as_ref()
+                .and_then(|store| stores.insert(store, name))
+            {
+                return Err(
+                    format!("Services `{other}` and `{name}` share their job store").into(),
+                );
+            }
+            if let Some(other) = service
+                .http_addr
+                .and_then(|addr| http_addrs.insert(addr, name))
+            {
+                return Err(
+                    format!("Services `{other}` and `{name}` share their HTTP address").into(),
+                );
+            }
+            if let Some(other) = service
+                .tcp_addr
+                .and_then(|addr| tcp_addrs.insert(addr, name))
+            {
+                return Err(
+                    format!("Services `{other}` and `{name}` share their TCP address").into(),
+                );
+            }
+            if let Some(other) = service
+                .health_addr
+                .and_then(|addr| health_addrs.insert(addr, name))
+            {
+                return Err(
+                    format!("Services `{other}` and `{name}` share their health address").into(),
+                );
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Runs the services of the supervisor in the current process, sharing its runtime, until all of them have stopped.
+///
+/// Every service is started like `cronus run` starts it, with a command server of its own, so it is managed with `-n <name>` like a
+/// service running on its own, and stopped with `cronus stop -n <name>`. The services are independent: a service that stops or fails
+/// leaves the others running. As there is a single key/value state of the jobs per process, the state of the jobs of every service is
+/// kept in the state file of the first service, by name, job ids being unique across services.
+///
+/// # Arguments
+///
+/// * `config` - A `SupervisorConfig` that represents the services to be run.
+///
+/// # Returns
+///
+/// * `CronusResult<CommandResponse>` - Returns a `CronusResult` that contains a `CommandResponse::ServiceStopped` once every service
+///   has stopped, or an error if a service cannot be started, or naming every service that failed.
+pub async fn supervise(config: SupervisorConfig) -> CronusResult<CommandResponse> {
+    let mut schedulers = Vec::new();
+    for (name, service) in config.services {
+        let scheduler = CronusScheduler::new(
+            name.clone(),
+            service.path,
+            service.http_addr,
+            service.tcp_addr,
+            service.health_addr,
+            service.max_parallel,
+            service.config,
+            service.store,
+            service.store_key,
+            service.lease_window,
+            service.reconcile,
+            service.follow,
+            None,
+        )
+        .await
+        .map_err(|e| format!("Service `{name}` cannot start: {e}"))?;
+        schedulers.push((name, scheduler));
+    }
+    // The services are not `Send`, so they run on the tasks of a local set rather than on the workers of the runtime.
+    let failures = LocalSet::new()
+        .run_until(async move {
+            let services: Vec<_> = schedulers
+                .into_iter()
+                .map(|(name, scheduler)| (name, spawn_local(scheduler.run())))
+                .collect();
+            let mut failures = Vec::new();
+            for (name, service) in services {
+                match service.await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => failures.push(format!("Service `{name}` failed: {e}")),
+                    Err(e) => failures.push(format!("Service `{name}` failed: {e}")),
+                }
+            }
+            failures
+        })
+        .await;
+    if !failures.is_empty() {
+        return Err(failures.join(", ").into());
+    }
+    Ok(CommandResponse::ServiceStopped)
+}
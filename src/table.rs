@@ -0,0 +1,603 @@
+use std::io::IsTerminal;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde_json::Value;
+
+use crate::command::CommandResponse;
+use crate::doctor::{CheckStatus, Finding};
+use crate::history::{RunRecord, RunStatus};
+use crate::job::{JobInfo, Trigger};
+use crate::stats::{CostCenterStats, Digest, JobStats, Usage};
+
+/// The number of spaces between the columns of a table.
+const COLUMN_GAP: usize = 2;
+
+/// The escape sequences printing the warnings under a table in yellow on a terminal.
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// `OutputFormat` is an enumeration that represents how the CLI prints the response of the Cronus service.
+///
+/// # Variants
+///
+/// * `Json` - Represents printing the response as JSON, for scripts.
+/// * `Table` - Represents printing the jobs, runs or usage of the response as a table aligned for reading.
+/// * `Wide` - Represents printing the table with more columns, e.g. the full id and the type of every job.
+/// * `Quiet` - Represents printing only the id of every job, the start time of every run or the name of every failed check, one per
+///   line, to be piped to other commands.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Table,
+    Wide,
+    Quiet,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "table" => Ok(Self::Table),
+            "wide" => Ok(Self::Wide),
+            "quiet" => Ok(Self::Quiet),
+            _ => Err(format!(
+                "Invalid output format `{s}`, expected `json`, `table`, `wide` or `quiet`"
+            )),
+        }
+    }
+}
+
+/// Renders the response of the Cronus service in an output format.
+///
+/// # Arguments
+///
+/// * `response` - A reference to the `CommandResponse` of the service.
+/// * `format` - An `OutputFormat` that represents how the response is printed.
+/// * `now` - A `DateTime<Utc>` that represents the current time, the times of the tables are relative to.
+///
+/// # Returns
+///
+/// * `String` - Returns the rendered response. Responses holding no jobs, runs or usage, e.g. errors, are rendered as JSON whatever the
+///   format. The warnings of a response rendered as a table are printed under it, in yellow on a terminal, and left out of quiet output.
+pub fn render(response: &CommandResponse, format: OutputFormat, now: DateTime<Utc>) -> String {
+    let rendered = match response {
+        CommandResponse::Warned(inner, warnings) => {
+            render_rows(inner, format, now).map(|mut rendered| {
+                if format != OutputFormat::Quiet {
+                    let (color, reset) = match std::io::stdout().is_terminal() {
+                        true => (YELLOW, RESET),
+                        false => ("", ""),
+                    };
+                    for warning in warnings {
+                        rendered.push_str(&format!("\n{color}warning: {warning}{reset}"));
+                    }
+                }
+                rendered
+            })
+        }
+        response => render_rows(response, format, now),
+    };
+    rendered.unwrap_or_else(|| response.to_json_msg())
+}
+
+/// Renders the jobs, runs or usage of a response in an output format other than JSON.
+///
+/// # Arguments
+///
+/// * `response` - A reference to the `CommandResponse` of the service.
+/// * `format` - An `OutputFormat` that represents how the response is printed.
+/// * `now` - A `DateTime<Utc>` that represents the current time, the times of the tables are relative to.
+///
+/// # Returns
+///
+/// * `Option<String>` - Returns the rendered response, or `None` if it is printed as JSON, as the format is JSON or the response holds
+///   no jobs, runs or usage.
+fn render_rows(
+    response: &CommandResponse,
+    format: OutputFormat,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    let wide = format == OutputFormat::Wide;
+    match (response, format) {
+        (_, OutputFormat::Json) => None,
+        (CommandResponse::JobList(jobs), OutputFormat::Quiet) => Some(quiet(jobs, |job| &job.id)),
+        (CommandResponse::JobPage(page), OutputFormat::Quiet) => {
+            Some(quiet(&page.jobs, |job| &job.id))
+        }
+        (CommandResponse::JobDetail(Some(job)), OutputFormat::Quiet) => Some(job.id.clone()),
+        (CommandResponse::RunList(runs), OutputFormat::Quiet) => Some(
+            runs.iter()
+                .map(|run| run.started_at.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        (CommandResponse::Stats(stats), OutputFormat::Quiet) => {
+            Some(quiet(stats, |stats| &stats.job_id))
+        }
+        (CommandResponse::CostCenterStats(stats), OutputFormat::Quiet) => {
+            Some(quiet(stats, |stats| &stats.cost_center))
+        }
+        (CommandResponse::Digest(digest), OutputFormat::Quiet) => {
+            Some(quiet(&digest.jobs, |entry| &entry.job_id))
+        }
+        (CommandResponse::DoctorReport(findings), OutputFormat::Quiet) => Some(
+            findings
+                .iter()
+                .filter(|finding| finding.status != CheckStatus::Ok)
+                .map(|finding| finding.check.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        (CommandResponse::JobList(jobs), _) => Some(job_table(jobs, wide, now)),
+        (CommandResponse::JobPage(page), _) => {
+            let mut table = job_table(&page.jobs, wide, now);
+            let shown = page.jobs.len();
+            match page.next_offset {
+                Some(offset) => table.push_str(&format!(
+                    "\n{shown} of {} jobs, next page at --offset {offset}",
+                    page.total
+                )),
+                None => table.push_str(&format!("\n{shown} of {} jobs", page.total)),
+            }
+            Some(table)
+        }
+        (CommandResponse::JobDetail(Some(job)), _) => {
+            Some(job_table(std::slice::from_ref(job), wide, now))
+        }
+        (CommandResponse::RunList(runs), _) => Some(run_table(runs, wide, now)),
+        (CommandResponse::Stats(stats), _) => Some(stats_table(stats, wide, now)),
+        (CommandResponse::CostCenterStats(stats), _) => Some(cost_center_table(stats)),
+        (CommandResponse::Digest(digest), _) => Some(digest_table(digest, wide)),
+        (CommandResponse::DoctorReport(findings), _) => Some(doctor_table(findings)),
+        _ => None,
+    }
+}
+
+/// Lists a column of rows, one per line.
+///
+/// # Arguments
+///
+/// * `rows` - A slice of the rows.
+/// * `column` - A function returning the column of a row.
+///
+/// # Returns
+///
+/// * `String` - Returns the column of every row, one per line.
+fn quiet<T>(rows: &[T], column: impl Fn(&T) -> &String) -> String {
+    rows.iter()
+        .map(|row| column(row).as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders jobs as a table.
+///
+/// # Arguments
+///
+/// * `jobs` - A slice of `JobInfo` instances that represent the jobs.
+/// * `wide` - A `bool` that represents whether the full id, the type, the last run, the estimated duration, the labels and the
+///   description of the jobs are shown.
+/// * `now` - A `DateTime<Utc>` that represents the current time.
+///
+/// # Returns
+///
+/// * `String` - Returns the table, one job per line after the header.
+fn job_table(jobs: &[JobInfo], wide: bool, now: DateTime<Utc>) -> String {
+    let mut headers = vec!["ID", "NAME", "SCHEDULE", "NEXT RUN", "STATE"];
+    if wide {
+        headers.extend(["TYPE", "LAST RUN", "ESTIMATED", "LABELS", "DESCRIPTION"]);
+    }
+    let rows = jobs.iter().map(|job| {
+        let id = if wide { &job.id } else { short_id(&job.id) };
+        let mut row = vec![
+            id.to_string(),
+            job.options.name.clone().unwrap_or_default(),
+            schedule(&job.trigger),
+            job.next_run
+                .map(|at| relative_time(at, now))
+                .unwrap_or_default(),
+            state(job).to_string(),
+        ];
+        if wide {
+            row.extend([
+                job_type(job),
+                job.last_run
+                    .map(|at| relative_time(at, now))
+                    .unwrap_or_default(),
+                job.estimated_duration
+                    .map(format_duration)
+                    .unwrap_or_default(),
+                job.options
+                    .labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                job.options.description.clone().unwrap_or_default(),
+            ]);
+        }
+        row
+    });
+    align(&headers, rows)
+}
+
+/// Renders runs as a table.
+///
+/// # Arguments
+///
+/// * `runs` - A slice of `RunRecord` instances that represent the runs.
+/// * `wide` - A `bool` that represents whether the delay, the exit code, the cost center, the id of the runs and the id of the run they
+///   retry are shown, before their error.
+/// * `now` - A `DateTime<Utc>` that represents the current time.
+///
+/// # Returns
+///
+/// * `String` - Returns the table, one run per line after the header.
+fn run_table(runs: &[RunRecord], wide: bool, now: DateTime<Utc>) -> String {
+    let mut headers = vec!["STARTED", "STATUS", "DURATION"];
+    if wide {
+        headers.extend(["DELAY", "EXIT CODE", "COST CENTER", "RUN ID", "RETRY OF"]);
+    }
+    headers.push("ERROR");
+    let rows = runs.iter().map(|run| {
+        let status = match run.status {
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+        };
+        let mut row = vec![
+            relative_time(run.started_at, now),
+            status.to_string(),
+            format_duration(run.duration),
+        ];
+        if wide {
+            row.extend([
+                format_duration(run.delay),
+                run.exit_code
+                    .map(|code| code.to_string())
+                    .unwrap_or_default(),
+                run.cost_center.clone().unwrap_or_default(),
+                run.run_id.map(|id| id.to_string()).unwrap_or_default(),
+                run.retry_of.map(|id| id.to_string()).unwrap_or_default(),
+            ]);
+        }
+        row.push(run.error.clone().unwrap_or_default());
+        row
+    });
+    align(&headers, rows)
+}
+
+/// Renders the statistics of jobs as a table.
+///
+/// # Arguments
+///
+/// * `stats` - A slice of `JobStats` instances that represent the statistics of the jobs.
+/// * `wide` - A `bool` that represents whether the full id and the last failure of the jobs are shown.
+/// * `now` - A `DateTime<Utc>` that represents the current time.
+///
+/// # Returns
+///
+/// * `String` - Returns the table, one job per line after the header.
+fn stats_table(stats: &[JobStats], wide: bool, now: DateTime<Utc>) -> String {
+    let mut headers = vec!["ID", "NAME", "RUNS", "FAILURES", "SUCCESS", "MEAN", "P95"];
+    if wide {
+        headers.push("LAST FAILURE");
+    }
+    let rows = stats.iter().map(|stats| {
+        let summary = &stats.summary;
+        let id = if wide {
+            &stats.job_id
+        } else {
+            short_id(&stats.job_id)
+        };
+        let mut row = vec![
+            id.to_string(),
+            stats.name.clone().unwrap_or_default(),
+            summary.runs.to_string(),
+            summary.failures.to_string(),
+            summary
+                .success_rate
+                .map(|rate| format!("{:.1}%", rate * 100.0))
+                .unwrap_or_default(),
+            summary
+                .mean_duration
+                .map(format_duration)
+                .unwrap_or_default(),
+            summary
+                .p95_duration
+                .map(format_duration)
+                .unwrap_or_default(),
+        ];
+        if wide {
+            row.push(
+                summary
+                    .last_failure
+                    .map(|at| relative_time(at, now))
+                    .unwrap_or_default(),
+            );
+        }
+        row
+    });
+    align(&headers, rows)
+}
+
+/// Renders the machine time charged to cost centers as a table, with their latest day and month.
+///
+/// # Arguments
+///
+/// * `stats` - A slice of `CostCenterStats` instances that represent the usage of the cost centers.
+///
+/// # Returns
+///
+/// * `String` - Returns the table, one cost center per line after the header.
+fn cost_center_table(stats: &[CostCenterStats]) -> String {
+    let headers = ["COST CENTER", "DAY", "DAY TIME", "MONTH", "MONTH TIME"];
+    let rows = stats.iter().map(|stats| {
+        let day = stats.daily.last();
+        let month = stats.monthly.last();
+        vec![
+            stats.cost_center.clone(),
+            day.map(|day| day.period.clone()).unwrap_or_default(),
+            day.map(|day| usage(&day.usage)).unwrap_or_default(),
+            month.map(|month| month.period.clone()).unwrap_or_default(),
+            month.map(|month| usage(&month.usage)).unwrap_or_default(),
+        ]
+    });
+    align(&headers, rows)
+}
+
+/// Renders a digest report as a table, headed by the usage of all the jobs.
+///
+/// # Arguments
+///
+/// * `digest` - A reference to the `Digest` report.
+/// * `wide` - A `bool` that represents whether the full id of the jobs is shown.
+///
+/// # Returns
+///
+/// * `String` - Returns the table, one job per line after the header and a line for all the jobs.
+fn digest_table(digest: &Digest, wide: bool) -> String {
+    let headers = ["ID", "NAME", "TODAY", "THIS MONTH"];
+    let total = vec![
+        String::new(),
+        format!("all jobs on {}", digest.day),
+        usage(&digest.today),
+        usage(&digest.this_month),
+    ];
+    let rows = digest.jobs.iter().map(|entry| {
+        let id = if wide {
+            &entry.job_id
+        } else {
+            short_id(&entry.job_id)
+        };
+        vec![
+            id.to_string(),
+            entry.name.clone().unwrap_or_default(),
+            usage(&entry.today),
+            usage(&entry.this_month),
+        ]
+    });
+    align(&headers, std::iter::once(total).chain(rows))
+}
+
+/// Renders the findings of the doctor as a table.
+///
+/// # Arguments
+///
+/// * `findings` - A slice of `Finding` instances that represent the outcome of the checks.
+///
+/// # Returns
+///
+/// * `String` - Returns the table, one check per line after the header.
+fn doctor_table(findings: &[Finding]) -> String {
+    let headers = ["CHECK", "STATUS", "MESSAGE", "HINT"];
+    let rows = findings.iter().map(|finding| {
+        let status = match finding.status {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warning",
+            CheckStatus::Error => "error",
+        };
+        vec![
+            finding.check.clone(),
+            status.to_string(),
+            finding.message.clone(),
+            finding.hint.clone().unwrap_or_default(),
+        ]
+    });
+    align(&headers, rows)
+}
+
+/// Aligns the columns of a table, padding every cell but the last of a row to the width of its column.
+///
+/// # Arguments
+///
+/// * `headers` - A slice of strings that represent the header of every column.
+/// * `rows` - An iterator of the rows, each holding a cell for every column.
+///
+/// # Returns
+///
+/// * `String` - Returns the header line followed by a line per row.
+fn align(headers: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let lines: Vec<Vec<String>> = std::iter::once(headers.iter().map(|h| h.to_string()).collect())
+        .chain(rows)
+        .collect();
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|column| {
+            lines
+                .iter()
+                .filter_map(|line| line.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    lines
+        .iter()
+        .map(|line| {
+            let last = line.len().saturating_sub(1);
+            line.iter()
+                .enumerate()
+                .map(|(column, cell)| {
+                    if column == last {
+                        cell.clone()
+                    } else {
+                        format!("{cell:<width$}", width = widths[column] + COLUMN_GAP)
+                    }
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the start of a job id, enough to tell the jobs of a service apart.
+///
+/// # Arguments
+///
+/// * `id` - A string that represents the id of the job.
+///
+/// # Returns
+///
+/// * `&str` - Returns the first 8 characters of the id.
+pub fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+/// Describes what makes a job run.
+///
+/// # Arguments
+///
+/// * `trigger` - A reference to the `Trigger` of the job.
+///
+/// # Returns
+///
+/// * `String` - Returns the cron expression of the job, `after <job>`, `on <topic>`, the `RRULE` line of its recurrence, its time of the sun, e.g.
+///   `sunset+30m at 48.85,2.35`, or `manual`.
+pub fn schedule(trigger: &Trigger) -> String {
+    match trigger {
+        Trigger::Cron(cron) => cron.clone(),
+        Trigger::After(after) => format!("after {after}"),
+        Trigger::Event(topic) => format!("on {topic}"),
+        Trigger::Rrule(rule) => rule
+            .split_whitespace()
+            .find(|line| line.starts_with("RRULE:"))
+            .unwrap_or(rule)
+            .to_string(),
+        Trigger::Sun(sun) => sun.to_string(),
+        Trigger::Manual => "manual".to_string(),
+    }
+}
+
+/// Returns the type of a job, as it is written in job files, e.g. `command` or `http`.
+///
+/// # Arguments
+///
+/// * `job` - A reference to the `JobInfo` of the job.
+///
+/// # Returns
+///
+/// * `String` - Returns the type of the job.
+pub fn job_type(job: &JobInfo) -> String {
+    serde_json::to_value(&job.job)
+        .ok()
+        .and_then(|job| job.get("type").and_then(Value::as_str).map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Returns the state of a job, as of its last run.
+///
+/// # Arguments
+///
+/// * `job` - A reference to the `JobInfo` of the job.
+///
+/// # Returns
+///
+/// * `&str` - Returns `running` if runs of the job are in flight, `paused` if it is paused, `failed` if its last failure is not
+///   acknowledged, and `idle` otherwise.
+pub fn state(job: &JobInfo) -> &'static str {
+    if job.runs_in_flight > 0 {
+        "running"
+    } else if job.paused {
+        "paused"
+    } else if job.unacknowledged_failure {
+        "failed"
+    } else {
+        "idle"
+    }
+}
+
+/// Formats how far a time is from now.
+///
+/// # Arguments
+///
+/// * `timestamp` - A `u64` that represents the time in Unix timestamp.
+/// * `now` - A `DateTime<Utc>` that represents the current time.
+///
+/// # Returns
+///
+/// * `String` - Returns the distance to the time, to the second, e.g. `in 5m` or `1h 5m ago`.
+pub fn relative_time(timestamp: u64, now: DateTime<Utc>) -> String {
+    let offset = timestamp as i64 - now.timestamp();
+    let distance = humantime::format_duration(Duration::from_secs(offset.unsigned_abs()));
+    if offset >= 0 {
+        format!("in {distance}")
+    } else {
+        format!("{distance} ago")
+    }
+}
+
+/// Formats a time in the local timezone, with how far it is from now.
+///
+/// # Arguments
+///
+/// * `timestamp` - A `u64` that represents the time in Unix timestamp.
+/// * `now` - A `DateTime<Utc>` that represents the current time.
+///
+/// # Returns
+///
+/// * `String` - Returns the time, e.g. `10-14 09:30:00 (in 5m)` or `10-14 09:20:00 (5m ago)`.
+pub fn format_time(timestamp: u64, now: DateTime<Utc>) -> String {
+    let Some(at) = DateTime::from_timestamp(timestamp as i64, 0) else {
+        return String::new();
+    };
+    let at = at.with_timezone(&Local).format("%m-%d %H:%M:%S");
+    format!("{at} ({})", relative_time(timestamp, now))
+}
+
+/// Formats a duration to the millisecond, e.g. `1s 250ms`.
+///
+/// # Arguments
+///
+/// * `duration` - A `Duration` to format.
+///
+/// # Returns
+///
+/// * `String` - Returns the formatted duration.
+fn format_duration(duration: Duration) -> String {
+    let duration = Duration::from_millis(duration.as_millis() as u64);
+    humantime::format_duration(duration).to_string()
+}
+
+/// Formats the machine time consumed by runs, with their number, e.g. `1m 30s (12 runs)` or `250ms (1 run)`.
+///
+/// # Arguments
+///
+/// * `usage` - A reference to the `Usage` of the runs.
+///
+/// # Returns
+///
+/// * `String` - Returns the formatted usage.
+fn usage(usage: &Usage) -> String {
+    let plural = if usage.runs == 1 { "" } else { "s" };
+    format!(
+        "{} ({} run{plural})",
+        format_duration(usage.time),
+        usage.runs
+    )
+}
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, Utc};
+
+use crate::job::{JobIdentity, Tick};
+
+/// The format a time is rendered with when a placeholder names no format.
+const DEFAULT_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+
+/// `Placeholder` is an enumeration that represents a parsed `{{ ... }}` placeholder of a template.
+///
+/// # Variants
+///
+/// * `Time { variable, offsets, format }` - Represents a time. It contains a string that represents the time variable the placeholder
+///   starts from, `scheduled_time`, `window_start`, `window_end` or `now`, a vector of pairs of a sign and a `Duration` that represent the
+///   offsets added to or subtracted from the variable, in order, and a string that represents the `strftime` format the resulting time
+///   is rendered with.
+/// * `ScheduledTs` - Represents the time the run was scheduled for, in Unix timestamp.
+/// * `JobId` - Represents the id of the job.
+/// * `JobName` - Represents the name of the job, or its id if it has no name.
+/// * `RunId` - Represents the id of the run, unique to every run of the job.
+enum Placeholder<'a> {
+    Time {
+        variable: &'a str,
+        offsets: Vec<(bool, Duration)>,
+        format: &'a str,
+    },
+    ScheduledTs,
+    JobId,
+    JobName,
+    RunId,
+}
+
+impl<'a> Placeholder<'a> {
+    /// Parses the expression of a placeholder, e.g. `scheduled_time - 1d | %Y%m%d`, `date:%Y-%m-%d` or `job_name`.
+    ///
+    /// # Arguments
+    ///
+    /// * `expr` - A string that represents the expression between the `{{` and `}}` of the placeholder.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Placeholder, String>` - Returns the parsed placeholder, or an error message describing why the expression is invalid.
+    fn parse(expr: &'a str) -> Result<Self, String> {
+        match expr.trim() {
+            "scheduled_ts" => return Ok(Self::ScheduledTs),
+            "job_id" => return Ok(Self::JobId),
+            "job_name" => return Ok(Self::JobName),
+            "run_id" => return Ok(Self::RunId),
+            _ => {}
+        }
+        // `date:<format>` is a shorthand for `scheduled_time | <format>`.
+        if let Some(format) = expr.trim_start().strip_prefix("date:") {
+            return Ok(Self::Time {
+                variable: "scheduled_time",
+                offsets: Vec::new(),
+                format: Self::parse_format(format.trim())?,
+            });
+        }
+        let (time, format) = match expr.split_once('|') {
+            Some((time, format)) => (time, Self::parse_format(format.trim())?),
+            None => (expr, DEFAULT_FORMAT),
+        };
+        let time = time.trim();
+        let end = time.find(['+', '-']).unwrap_or(time.len());
+        let variable = time[..end].trim();
+        if !matches!(
+            variable,
+            "scheduled_time" | "window_start" | "window_end" | "now"
+        ) {
+            return Err(format!(
+                "Unknown variable `{variable}`, expected `scheduled_time`, `window_start`, `window_end`, `now`, `date:<format>`, `scheduled_ts`, `job_id`, `job_name` or `run_id`"
+            ));
+        }
+        let mut offsets = Vec::new();
+        let mut rest = &time[end..];
+        while let Some(sign) = rest.chars().next() {
+            rest = &rest[1..];
+            let end = rest.find(['+', '-']).unwrap_or(rest.len());
+            let duration = rest[..end].trim();
+            let duration = humantime::parse_duration(duration)
+                .map_err(|e| format!("Invalid duration `{duration}`: {e}"))?;
+            offsets.push((sign == '+', duration));
+            rest = &rest[end..];
+        }
+        Ok(Self::Time {
+            variable,
+            offsets,
+            format,
+        })
+    }
+
+    /// Checks the `strftime` format of a time placeholder.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - A string that represents the format.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<&str, String>` - Returns the format, or an error message if it is empty or invalid.
+    fn parse_format(format: &str) -> Result<&str, String> {
+        if format.is_empty() || StrftimeItems::new(format).any(|item| item == Item::Error) {
+            return Err(format!("Invalid time format `{format}`"));
+        }
+        Ok(format)
+    }
+
+    /// Renders the placeholder.
+    ///
+    /// # Arguments
+    ///
+    /// * `tick` - A `Tick` that represents the tick the job runs for.
+    /// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+    /// * `now` - A `DateTime<Utc>` that represents the current time.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, String>` - Returns the rendered placeholder, times being rendered in local time, or an error message if an
+    ///   offset overflows.
+    fn render(
+        &self,
+        tick: Tick,
+        identity: &JobIdentity,
+        now: DateTime<Utc>,
+    ) -> Result<String, String> {
+        let (variable, offsets, format) = match self {
+            Self::Time {
+                variable,
+                offsets,
+                format,
+            } => (*variable, offsets, *format),
+            Self::ScheduledTs => return Ok(tick.scheduled_time.timestamp().to_string()),
+            Self::JobId => return Ok(identity.id.clone()),
+            Self::JobName => {
+                return Ok(identity.name.clone().unwrap_or_else(|| identity.id.clone()))
+            }
+            Self::RunId => return Ok(tick.run_id.to_string()),
+        };
+        let mut time = match variable {
+            "now" => now,
+            "window_start" => tick.window_start,
+            "window_end" => tick.window_end,
+            _ => tick.scheduled_time,
+        };
+        for (add, duration) in offsets {
+            let offset = chrono::Duration::from_std(*duration).map_err(|e| e.to_string())?;
+            time = if *add {
+                time.checked_add_signed(offset)
+            } else {
+                time.checked_sub_signed(offset)
+            }
+            .ok_or("Time offset out of range")?;
+        }
+        Ok(time.with_timezone(&Local).format(format).to_string())
+    }
+}
+
+/// Renders a template, replacing its `{{ ... }}` placeholders with times and with the job and the run they are for.
+///
+/// A time placeholder names a time variable, `scheduled_time` for the tick the job runs for, `window_start` and `window_end` for the window
+/// of time the tick closes, or `now` for the time the job actually starts, followed by any number of `+ <duration>` or `- <duration>` offsets in humantime notation and an optional `| <format>` in `strftime`
+/// notation, e.g. `{{scheduled_time - 1d | %Y%m%d}}` for the partition of the day before. Times are rendered in local time, as RFC 3339
+/// when no format is given, and `{{date:<format>}}` is a shorthand for `{{scheduled_time | <format>}}`, e.g. `{{date:%Y-%m-%d}}`.
+/// `{{scheduled_ts}}` is the scheduled time in Unix timestamp, `{{job_id}}` and `{{job_name}}` the id and the name of the job, the name
+/// being its id if it has none, and `{{run_id}}` the id of the run. Text outside of placeholders is kept as is.
+///
+/// # Arguments
+///
+/// * `template` - A string that represents the template.
+/// * `tick` - A `Tick` that represents the tick the job runs for.
+/// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+///
+/// # Returns
+///
+/// * `Result<String, String>` - Returns the rendered template, or an error message describing the first invalid placeholder.
+pub fn render(template: &str, tick: Tick, identity: &JobIdentity) -> Result<String, String> {
+    let now = Utc::now();
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find("}}")
+            .ok_or(format!("Unclosed placeholder in `{template}`"))?;
+        let expr = &rest[start + 2..start + end];
+        let placeholder = Placeholder::parse(expr)
+            .map_err(|e| format!("Invalid placeholder `{{{{{expr}}}}}`: {e}"))?;
+        rendered.push_str(&placeholder.render(tick, identity, now)?);
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Checks that the placeholders of a template are valid.
+///
+/// # Arguments
+///
+/// * `template` - A string that represents the template.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the template can be rendered, or an error message describing the first invalid placeholder.
+pub fn validate(template: &str) -> Result<(), String> {
+    render(template, Tick::at(Utc::now()), &JobIdentity::default()).map(|_| ())
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::time::{Duration, Instant};
+
+use nng::options::{Options, RemAddr};
+use nng::{Message, SocketAddr};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::Caller;
+
+/// The number of clients whose requests are counted at once, beyond which the clients that have not sent a request for a while are
+/// forgotten.
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+/// How much larger than the largest request accepted a message the command socket still receives is, in bytes, so a request a little
+/// too large is answered with `Throttled` rather than dropped with the header the socket protocol adds to it.
+const RECV_HEADER_ALLOWANCE: usize = 1024;
+
+/// `ThrottleConfig` is a structure that represents how much the clients of the service may send over its command socket, declared as a
+/// `[throttle]` table of the configuration file:
+///
+/// ```toml
+/// [throttle]
+/// requests_per_second = 20
+/// burst = 50
+/// max_message_bytes = 1048576
+/// ```
+///
+/// # Fields
+///
+/// * `requests_per_second` - An `Option<NonZeroU32>` that represents how many requests a client may send per second in the long run. It
+///   is `None` if the requests are not rate limited.
+/// * `burst` - An `Option<NonZeroU32>` that represents how many requests a client may send at once, beyond its rate, after a pause. It
+///   is `None` for a burst of one second of requests.
+/// * `max_message_bytes` - An `Option<NonZeroUsize>` that represents the size of the largest request accepted, in bytes. It is `None` if
+///   the size of the requests is not limited.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub struct ThrottleConfig {
+    #[serde(default)]
+    pub requests_per_second: Option<NonZeroU32>,
+    #[serde(default)]
+    pub burst: Option<NonZeroU32>,
+    #[serde(default)]
+    pub max_message_bytes: Option<NonZeroUsize>,
+}
+
+impl ThrottleConfig {
+    /// Checks a request is not larger than the largest request accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - A `usize` that represents the size of the request in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the request is small enough, or an error message telling it is too large.
+    pub fn check_size(&self, len: usize) -> Result<(), String> {
+        match self.max_message_bytes.filter(|max| len > max.get()) {
+            Some(max) => Err(format!(
+                "Request of {len} bytes exceeds the maximum of {max} bytes"
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the size of the largest message the command socket receives, see `nng::options::RecvMaxSize`, so a message much larger
+    /// than the largest request accepted is dropped by the socket before it is buffered.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Returns the size in bytes, or `0` for messages of any size if the size of the requests is not limited.
+    pub fn recv_max_size(&self) -> usize {
+        self.max_message_bytes
+            .map_or(0, |max| max.get().saturating_add(RECV_HEADER_ALLOWANCE))
+    }
+}
+
+/// `Bucket` is a structure that represents the requests a client may still send at once, refilled at the rate of the throttle.
+///
+/// # Fields
+///
+/// * `tokens` - A `f64` that represents the number of requests the client may send at once.
+/// * `refilled` - An `Instant` that represents when the bucket was last refilled.
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// `Throttle` is a structure that represents the requests every client of the service may still send, see `ThrottleConfig`, shared by
+/// the contexts of the command server.
+#[derive(Default)]
+pub struct Throttle {
+    buckets: HashMap<String, Bucket>,
+}
+
+impl Throttle {
+    /// Admits a request of a client, unless the client has sent too many requests.
+    ///
+    /// Every client has a bucket of `burst` requests, refilled at `requests_per_second`, a request admitted taking a request out of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - A reference to the `ThrottleConfig` the request is checked against.
+    /// * `client` - A string that represents the client the request was received from, see `client_of`.
+    /// * `now` - An `Instant` that represents when the request was received.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Returns `Ok(())` if the request is admitted, or an error message telling why it is not, and when the
+    ///   client may send a request again.
+    pub fn admit(
+        &mut self,
+        config: &ThrottleConfig,
+        client: &str,
+        now: Instant,
+    ) -> Result<(), String> {
+        let Some(rate) = config.requests_per_second else {
+            return Ok(());
+        };
+        let burst = f64::from(config.burst.unwrap_or(rate).get());
+        let rate = f64::from(rate.get());
+        if self.buckets.len() >= MAX_TRACKED_CLIENTS {
+            // A client whose bucket would be full again is forgotten, as it would be admitted anyway.
+            self.buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.refilled).as_secs_f64() * rate < burst
+            });
+        }
+        let bucket = self.buckets.entry(client.to_string()).or_insert(Bucket {
+            tokens: burst,
+            refilled: now,
+        });
+        bucket.tokens =
+            (bucket.tokens + now.duration_since(bucket.refilled).as_secs_f64() * rate).min(burst);
+        bucket.refilled = now;
+        if bucket.tokens < 1.0 {
+            let retry_in =
+                Duration::from_millis(((1.0 - bucket.tokens) / rate * 1000.0).ceil() as u64);
+            return Err(format!(
+                "Client `{client}` exceeds {rate} requests per second, retry in {}",
+                humantime::format_duration(retry_in)
+            ));
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+/// Identifies the client a request was received from, as its requests are counted: by the IP address of its host over TCP, or else
+/// by its user, so the processes of a script are counted together.
+///
+/// # Arguments
+///
+/// * `msg` - A mutable reference to the `Message` the request was received in.
+///
+/// # Returns
+///
+/// * `String` - Returns the client, e.g. `10.0.0.7` or `uid:1000`, or `unknown` if the command socket does not report it.
+pub fn client_of(msg: &mut Message) -> String {
+    match msg.pipe().and_then(|pipe| pipe.get_opt::<RemAddr>().ok()) {
+        Some(SocketAddr::Inet(addr)) => addr.ip().to_string(),
+        Some(SocketAddr::Inet6(addr)) => addr.ip().to_string(),
+        _ => match Caller::of(msg) {
+            Caller { uid: Some(uid), .. } => format!("uid:{uid}"),
+            Caller { pid: Some(pid), .. } => format!("pid:{pid}"),
+            _ => "unknown".to_string(),
+        },
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::history::{RunRecord, RunStatus};
+use crate::projection::project_in;
+use crate::schedule::parse_schedule;
+use crate::tz::parse_timezone;
+
+/// The width of the column holding the labels of the lanes of a rendered timeline, in pixels.
+const LABEL_WIDTH: f64 = 240.0;
+
+/// The width of the chart of a rendered timeline, in pixels.
+const CHART_WIDTH: f64 = 960.0;
+
+/// The height of a lane of a rendered timeline, in pixels.
+const LANE_HEIGHT: f64 = 24.0;
+
+/// The height of the time axis of a rendered timeline, in pixels.
+const AXIS_HEIGHT: f64 = 32.0;
+
+/// The number of intervals the time axis of a rendered timeline is divided into.
+const AXIS_STEPS: i64 = 8;
+
+/// The narrowest bar of a rendered timeline, in pixels, so instant executions remain visible.
+const MIN_BAR_WIDTH: f64 = 2.0;
+
+/// `BarKind` is an enumeration that represents what a bar of a timeline stands for.
+///
+/// # Variants
+///
+/// * `Succeeded` - Represents a past execution that succeeded.
+/// * `Failed` - Represents a past execution that failed.
+/// * `Projected` - Represents an upcoming execution, lasting as long as the runs of the job are estimated to take.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum BarKind {
+    Succeeded,
+    Failed,
+    Projected,
+}
+
+/// `TimelineBar` is a structure that represents a single execution on a timeline.
+///
+/// # Fields
+///
+/// * `start` - A `u64` that represents the time the execution starts in Unix timestamp.
+/// * `end` - A `u64` that represents the time the execution ends in Unix timestamp.
+/// * `kind` - A `BarKind` that represents whether the execution is past or projected, and how it went.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct TimelineBar {
+    pub start: u64,
+    pub end: u64,
+    pub kind: BarKind,
+}
+
+/// `TimelineLane` is a structure that represents the executions of a job on a timeline.
+///
+/// # Fields
+///
+/// * `job` - A string that represents the id of the job.
+/// * `label` - A string that represents the name of the job, or its id if it has no name.
+/// * `bars` - A vector of `TimelineBar` instances that represent the executions of the job within the window, in chronological order.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct TimelineLane {
+    pub job: String,
+    pub label: String,
+    pub bars: Vec<TimelineBar>,
+}
+
+/// `Timeline` is a structure that represents the past and projected executions of jobs, as the data of a Gantt chart.
+///
+/// # Fields
+///
+/// * `from` - A `u64` that represents the start of the window in Unix timestamp.
+/// * `now` - A `u64` that represents the time the timeline was built at in Unix timestamp, separating past and projected executions.
+/// * `until` - A `u64` that represents the end of the window in Unix timestamp.
+/// * `lanes` - A vector of `TimelineLane` instances that represent the jobs, sorted by label.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Timeline {
+    pub from: u64,
+    pub now: u64,
+    pub until: u64,
+    pub lanes: Vec<TimelineLane>,
+}
+
+/// `TimelinedJob` is a structure that represents a job placed on a timeline.
+///
+/// # Fields
+///
+/// * `job` - A string that represents the id of the job.
+/// * `label` - A string that represents the name of the job, or its id if it has no name.
+/// * `cron` - An `Option<String>` that represents the cron schedule of the job. It is `None` for a job that is not run on a schedule, which has no projected executions.
+/// * `timezone` - An `Option<String>` that represents the timezone the schedule is evaluated in. It is `None` for local time.
+/// * `paused` - A `bool` that represents whether the job is paused, in which case it has no projected executions.
+/// * `runs` - A vector of `RunRecord` instances that represent the recorded runs of the job.
+/// * `estimated_duration` - An `Option<Duration>` that represents the estimated duration of the runs of the job, which projected executions are assumed to last.
+#[derive(Debug, Clone)]
+pub struct TimelinedJob {
+    pub job: String,
+    pub label: String,
+    pub cron: Option<String>,
+    pub timezone: Option<String>,
+    pub paused: bool,
+    pub runs: Vec<RunRecord>,
+    pub estimated_duration: Option<Duration>,
+}
+
+/// Builds the timeline of the executions of jobs around a time.
+///
+/// The window extends as far back as it extends ahead: past executions are taken from the recorded runs that started within the window,
+/// and projected executions from the ticks of the schedules up to the end of the window. Jobs without any execution within the window
+/// are left out. Jobs with an invalid cron expression or timezone only have their past executions, `lint` reports them.
+///
+/// # Arguments
+///
+/// * `jobs` - A slice of `TimelinedJob` instances that represent the jobs to be placed on the timeline.
+/// * `now` - A `DateTime<Utc>` that represents the time separating past and projected executions.
+/// * `window` - A `Duration` that represents how far back and how far ahead of `now` the timeline extends.
+///
+/// # Returns
+///
+/// * `Timeline` - Returns the timeline.
+pub fn build_timeline(jobs: &[TimelinedJob], now: DateTime<Utc>, window: Duration) -> Timeline {
+    let window = chrono::Duration::from_std(window).unwrap_or_else(|_| chrono::Duration::days(365));
+    let from = now - window;
+    let until = now + window;
+    let mut lanes: Vec<_> = jobs
+        .iter()
+        .map(|job| {
+            let mut bars: Vec<_> = job
+                .runs
+                .iter()
+                .filter(|run| {
+                    (from.timestamp()..=now.timestamp()).contains(&(run.started_at as i64))
+                })
+                .map(|run| TimelineBar {
+                    start: run.started_at,
+                    end: run.started_at + run.duration.as_secs(),
+                    kind: match run.status {
+                        RunStatus::Succeeded => BarKind::Succeeded,
+                        RunStatus::Failed => BarKind::Failed,
+                    },
+                })
+                .collect();
+            let estimated = job.estimated_duration.unwrap_or_default().as_secs();
+            bars.extend(
+                project_job(job, now, until)
+                    .into_iter()
+                    .map(|tick| TimelineBar {
+                        start: tick.timestamp() as u64,
+                        end: tick.timestamp() as u64 + estimated,
+                        kind: BarKind::Projected,
+                    }),
+            );
+            TimelineLane {
+                job: job.job.clone(),
+                label: job.label.clone(),
+                bars,
+            }
+        })
+        .filter(|lane| !lane.bars.is_empty())
+        .collect();
+    lanes.sort_by(|a, b| a.label.cmp(&b.label));
+    Timeline {
+        from: from.timestamp() as u64,
+        now: now.timestamp() as u64,
+        until: until.timestamp() as u64,
+        lanes,
+    }
+}
+
+/// Projects the upcoming executions of a job.
+///
+/// # Arguments
+///
+/// * `job` - A reference to the `TimelinedJob` to be projected.
+/// * `now` - A `DateTime<Utc>` that represents the start of the projection, exclusive.
+/// * `until` - A `DateTime<Utc>` that represents the end of the projection, inclusive.
+///
+/// # Returns
+///
+/// * `Vec<DateTime<Utc>>` - Returns the upcoming ticks of the job, or none if the job is paused, not run on a schedule, or invalid.
+fn project_job(job: &TimelinedJob, now: DateTime<Utc>, until: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    if job.paused {
+        return Vec::new();
+    }
+    let Some(Ok(schedule)) = job.cron.as_deref().map(parse_schedule) else {
+        return Vec::new();
+    };
+    match job.timezone.as_deref().map(parse_timezone) {
+        Some(Ok(tz)) => project_in(&schedule, &tz, now, until),
+        Some(Err(_)) => Vec::new(),
+        None => project_in(&schedule, &Local, now, until),
+    }
+}
+
+/// Renders a timeline as an SVG Gantt chart.
+///
+/// Every job is a lane, every execution a bar: green for succeeded runs, red for failed runs and grey for projected ones. The time axis
+/// is in local time, and a vertical line marks the time the timeline was built at.
+///
+/// # Arguments
+///
+/// * `timeline` - A reference to the `Timeline` to be rendered.
+///
+/// # Returns
+///
+/// * `String` - Returns the SVG document.
+pub fn render_svg(timeline: &Timeline) -> String {
+    let span = timeline.until.saturating_sub(timeline.from).max(1) as f64;
+    let x = |t: u64| {
+        LABEL_WIDTH + (t.saturating_sub(timeline.from) as f64 / span).min(1.0) * CHART_WIDTH
+    };
+    let width = LABEL_WIDTH + CHART_WIDTH;
+    let height = AXIS_HEIGHT + LANE_HEIGHT * timeline.lanes.len() as f64;
+    let mut svg = String::new();
+    _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="12">"#
+    );
+    let format = match span as i64 / AXIS_STEPS {
+        step if step < 60 => "%H:%M:%S",
+        step if step < 3 * 3600 => "%H:%M",
+        _ => "%m-%d %H:%M",
+    };
+    for step in 0..=AXIS_STEPS {
+        let t = timeline.from + (span as i64 * step / AXIS_STEPS) as u64;
+        let label = DateTime::from_timestamp(t as i64, 0)
+            .map(|t| t.with_timezone(&Local).format(format).to_string())
+            .unwrap_or_default();
+        let x = x(t);
+        _ = writeln!(
+            svg,
+            r##"<line x1="{x:.1}" y1="{AXIS_HEIGHT}" x2="{x:.1}" y2="{height}" stroke="#e0e0e0"/><text x="{x:.1}" y="20" text-anchor="middle">{label}</text>"##
+        );
+    }
+    for (i, lane) in timeline.lanes.iter().enumerate() {
+        let y = AXIS_HEIGHT + LANE_HEIGHT * i as f64;
+        _ = writeln!(
+            svg,
+            r#"<text x="4" y="{:.1}">{}</text>"#,
+            y + LANE_HEIGHT * 0.7,
+            escape(&lane.label)
+        );
+        for bar in &lane.bars {
+            let (color, opacity) = match bar.kind {
+                BarKind::Succeeded => ("#43a047", 1.0),
+                BarKind::Failed => ("#e53935", 1.0),
+                BarKind::Projected => ("#90a4ae", 0.6),
+            };
+            let start = x(bar.start);
+            let width = (x(bar.end) - start).max(MIN_BAR_WIDTH);
+            _ = writeln!(
+                svg,
+                r#"<rect x="{start:.1}" y="{:.1}" width="{width:.1}" height="{:.1}" fill="{color}" fill-opacity="{opacity}"><title>{} {:?} {}s</title></rect>"#,
+                y + 4.0,
+                LANE_HEIGHT - 8.0,
+                escape(&lane.label),
+                bar.kind,
+                bar.end - bar.start
+            );
+        }
+    }
+    let now = x(timeline.now);
+    _ = writeln!(
+        svg,
+        r##"<line x1="{now:.1}" y1="{AXIS_HEIGHT}" x2="{now:.1}" y2="{height}" stroke="#1e88e5" stroke-dasharray="4 2"/>"##
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Escapes a text for use in an SVG document.
+///
+/// # Arguments
+///
+/// * `text` - A string that represents the text to be escaped.
+///
+/// # Returns
+///
+/// * `String` - Returns the escaped text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
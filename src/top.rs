@@ -0,0 +1,401 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Utc};
+use ratatui::crossterm::event::{
+    self, Event as TermEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::Value;
+
+use crate::command::{CommandClient, CommandResponse};
+use crate::events::EventKind;
+use crate::job::JobInfo;
+use crate::table::{format_time, job_type, schedule, short_id, state};
+use crate::CronusResult;
+
+/// How often the dashboard polls the Cronus service for its jobs and its new events.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the dashboard waits for the Cronus service to answer, before giving up on it.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum number of failed runs listed by the dashboard.
+const MAX_FAILURES: usize = 100;
+
+/// `Failure` is a structure that represents a failed run listed by the dashboard.
+///
+/// # Fields
+///
+/// * `at` - A `u64` that represents the time the run failed in Unix timestamp.
+/// * `job_id` - A string that represents the unique identifier of the job.
+/// * `message` - A string that represents why the run failed.
+struct Failure {
+    at: u64,
+    job_id: String,
+    message: String,
+}
+
+/// `Dashboard` is a structure that represents the state of `cronus top`.
+///
+/// # Fields
+///
+/// * `service` - A string that represents the name of the Cronus service.
+/// * `jobs` - A vector of `JobInfo` instances that represent the jobs of the service, by next run time.
+/// * `failures` - A `VecDeque<Failure>` that represents the most recent failed runs, newest first.
+/// * `after` - A `u64` that represents the `seq` of the last event read from the service.
+/// * `table` - A `TableState` that represents the job selected in the job table.
+/// * `status` - A string that represents the outcome of the last action, shown in the footer.
+/// * `pending_delete` - An `Option<String>` that represents the id of the job waiting for its deletion to be confirmed.
+#[derive(Default)]
+struct Dashboard {
+    service: String,
+    jobs: Vec<JobInfo>,
+    failures: VecDeque<Failure>,
+    after: u64,
+    table: TableState,
+    status: String,
+    pending_delete: Option<String>,
+}
+
+/// Runs `cronus top`, a live dashboard of a Cronus service in the terminal, until it is quit with `q`.
+///
+/// The dashboard lists the jobs of the service with their next run, the runs in flight and the most recent failed runs, read from the
+/// event feed of the service, refreshed every `REFRESH_INTERVAL`. The selected job is paused or resumed with `p`, triggered with `t`, and
+/// deleted with `d` once confirmed with `y`.
+///
+/// # Arguments
+///
+/// * `cc` - A reference to the `CommandClient` connected to the Cronus service.
+/// * `service` - A string that represents the name of the Cronus service.
+///
+/// # Returns
+///
+/// * `CronusResult<()>` - Returns `Ok(())` once the dashboard is quit, or an error if the service cannot be reached or the terminal
+///   cannot be drawn to.
+pub fn run(cc: &CommandClient, service: &str) -> CronusResult<()> {
+    cc.set_timeout(COMMAND_TIMEOUT)?;
+    let mut dashboard = Dashboard {
+        service: service.to_string(),
+        ..Default::default()
+    };
+    // The service is reached once before the terminal is taken over, so an error is printed like for any other command.
+    dashboard.refresh(cc)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = dashboard.run(cc, &mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Dashboard {
+    /// Draws the dashboard and handles the keys pressed until it is quit.
+    ///
+    /// # Arguments
+    ///
+    /// * `cc` - A reference to the `CommandClient` connected to the Cronus service.
+    /// * `terminal` - A mutable reference to the `DefaultTerminal` the dashboard is drawn on.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns `Ok(())` once the dashboard is quit, or an error if not.
+    fn run(&mut self, cc: &CommandClient, terminal: &mut DefaultTerminal) -> CronusResult<()> {
+        let mut refreshed = Instant::now();
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(REFRESH_INTERVAL.saturating_sub(refreshed.elapsed()))? {
+                if let TermEvent::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !self.handle_key(cc, key)? {
+                        return Ok(());
+                    }
+                }
+            }
+            if refreshed.elapsed() >= REFRESH_INTERVAL {
+                self.refresh(cc)?;
+                refreshed = Instant::now();
+            }
+        }
+    }
+
+    /// Reads the jobs of the service, and the events emitted since the last refresh, keeping the same job selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `cc` - A reference to the `CommandClient` connected to the Cronus service.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<()>` - Returns `Ok(())` if the service answered, or an error if not.
+    fn refresh(&mut self, cc: &CommandClient) -> CronusResult<()> {
+        let selected = self.selected().map(|job| job.id.clone());
+        self.jobs = match cc.list_jobs()? {
+            CommandResponse::JobList(mut jobs) => {
+                jobs.sort_by_cached_key(|job| (job.next_run.is_none(), job.next_run, label(job)));
+                jobs
+            }
+            response => return Err(describe(&response).into()),
+        };
+        let feed = match cc.follow_events(self.after)? {
+            CommandResponse::EventFeed(feed) => feed,
+            response => return Err(describe(&response).into()),
+        };
+        // A position ahead of the service means it restarted, so its events are read again from the start.
+        if feed.missed && feed.events.is_empty() {
+            self.after = 0;
+        }
+        for event in feed.events {
+            self.after = self.after.max(event.seq);
+            if let EventKind::RunFailed { message } = event.kind {
+                self.failures.push_front(Failure {
+                    at: event.at,
+                    job_id: event.job_id,
+                    message,
+                });
+            }
+        }
+        self.failures.truncate(MAX_FAILURES);
+        let index = selected
+            .and_then(|id| self.jobs.iter().position(|job| job.id == id))
+            .or_else(|| (!self.jobs.is_empty()).then_some(0));
+        self.table.select(index);
+        Ok(())
+    }
+
+    /// Handles a key pressed on the dashboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `cc` - A reference to the `CommandClient` connected to the Cronus service.
+    /// * `key` - A `KeyEvent` that represents the key pressed.
+    ///
+    /// # Returns
+    ///
+    /// * `CronusResult<bool>` - Returns whether the dashboard keeps running, or an error if the service cannot be reached.
+    fn handle_key(&mut self, cc: &CommandClient, key: KeyEvent) -> CronusResult<bool> {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(false);
+        }
+        if let Some(id) = self.pending_delete.take() {
+            if key.code == KeyCode::Char('y') {
+                self.status = describe(&cc.delete_job(id)?);
+                self.refresh(cc)?;
+            } else {
+                self.status = "Deletion cancelled".to_string();
+            }
+            return Ok(true);
+        }
+        let selected = self
+            .selected()
+            .map(|job| (job.id.clone(), job.paused, label(job)));
+        let response = match (key.code, selected) {
+            (KeyCode::Char('q') | KeyCode::Esc, _) => return Ok(false),
+            (KeyCode::Up | KeyCode::Char('k'), _) => {
+                self.table.select_previous();
+                return Ok(true);
+            }
+            (KeyCode::Down | KeyCode::Char('j'), _) => {
+                if self
+                    .table
+                    .selected()
+                    .is_some_and(|index| index + 1 < self.jobs.len())
+                {
+                    self.table.select_next();
+                }
+                return Ok(true);
+            }
+            (KeyCode::Char('p'), Some((id, true, _))) => cc.resume_job(id)?,
+            (KeyCode::Char('p'), Some((id, false, _))) => cc.pause_job(id)?,
+            (KeyCode::Char('t'), Some((id, _, _))) => cc.trigger_job(id)?,
+            (KeyCode::Char('d'), Some((id, _, label))) => {
+                self.status = format!("Delete {label}? Press y to confirm");
+                self.pending_delete = Some(id);
+                return Ok(true);
+            }
+            _ => return Ok(true),
+        };
+        self.status = describe(&response);
+        self.refresh(cc)?;
+        Ok(true)
+    }
+
+    /// Returns the job selected in the job table.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&JobInfo>` - Returns the selected job, or `None` if the service has no jobs.
+    fn selected(&self) -> Option<&JobInfo> {
+        self.table.selected().and_then(|index| self.jobs.get(index))
+    }
+
+    /// Draws the dashboard: a summary of the service, the job table, the runs in flight, the recent failures and the keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - A mutable reference to the `Frame` of the terminal.
+    fn draw(&mut self, frame: &mut Frame) {
+        let now = Utc::now();
+        let [header, jobs, bottom, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(10),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [running, failures] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(bottom);
+        let in_flight: Vec<_> = self
+            .jobs
+            .iter()
+            .filter(|job| job.runs_in_flight > 0)
+            .collect();
+        let paused = self.jobs.iter().filter(|job| job.paused).count();
+        frame.render_widget(
+            Paragraph::new(format!(
+                "cronus top - {} - {} jobs, {paused} paused, {} runs in flight - {}",
+                self.service,
+                self.jobs.len(),
+                in_flight
+                    .iter()
+                    .map(|job| job.runs_in_flight)
+                    .sum::<usize>(),
+                now.with_timezone(&Local).format("%H:%M:%S"),
+            ))
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+            header,
+        );
+
+        let rows = self.jobs.iter().map(|job| {
+            let state = state(job);
+            let color = match state {
+                "running" => Color::Green,
+                "paused" => Color::Yellow,
+                "failed" => Color::Red,
+                _ => Color::Reset,
+            };
+            Row::new([
+                short_id(&job.id).to_string(),
+                job.options.name.clone().unwrap_or_default(),
+                schedule(&job.trigger),
+                job_type(job),
+                job.next_run
+                    .map(|at| format_time(at, now))
+                    .unwrap_or_default(),
+                job.last_run
+                    .map(|at| format_time(at, now))
+                    .unwrap_or_default(),
+                state.to_string(),
+            ])
+            .style(Style::new().fg(color))
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Length(10),
+                Constraint::Fill(3),
+                Constraint::Fill(3),
+                Constraint::Length(7),
+            ],
+        )
+        .header(
+            Row::new([
+                "ID", "NAME", "SCHEDULE", "TYPE", "NEXT RUN", "LAST RUN", "STATE",
+            ])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(" Jobs "));
+        frame.render_stateful_widget(table, jobs, &mut self.table);
+
+        let rows = in_flight.iter().map(|job| {
+            Row::new([
+                label(job),
+                job.runs_in_flight.to_string(),
+                job.last_run
+                    .map(|at| format_time(at, now))
+                    .unwrap_or_default(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(4),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["JOB", "RUNS", "SINCE"]).style(Style::new().add_modifier(Modifier::BOLD)))
+        .block(Block::bordered().title(" In flight "));
+        frame.render_widget(table, running);
+
+        let rows = self.failures.iter().map(|failure| {
+            let job = self
+                .jobs
+                .iter()
+                .find(|job| job.id == failure.job_id)
+                .map(label)
+                .unwrap_or_else(|| short_id(&failure.job_id).to_string());
+            Row::new([format_time(failure.at, now), job, failure.message.clone()])
+                .style(Style::new().fg(Color::Red))
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(24),
+                Constraint::Length(16),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["AT", "JOB", "ERROR"]).style(Style::new().add_modifier(Modifier::BOLD)))
+        .block(Block::bordered().title(" Recent failures "));
+        frame.render_widget(table, failures);
+
+        let keys = "q quit  up/down select  p pause/resume  t trigger  d delete";
+        let footer_text = if self.status.is_empty() {
+            keys.to_string()
+        } else {
+            format!("{keys}  |  {}", self.status)
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+}
+
+/// Returns how the dashboard refers to a job: by its name, or else by the start of its id.
+///
+/// # Arguments
+///
+/// * `job` - A reference to the `JobInfo` of the job.
+///
+/// # Returns
+///
+/// * `String` - Returns the label of the job.
+fn label(job: &JobInfo) -> String {
+    job.options
+        .name
+        .clone()
+        .unwrap_or_else(|| short_id(&job.id).to_string())
+}
+
+/// Describes the response of the service to an action of the dashboard.
+///
+/// # Arguments
+///
+/// * `response` - A reference to the `CommandResponse` of the service.
+///
+/// # Returns
+///
+/// * `String` - Returns the error of the response if it has one, and its message otherwise, e.g. `Job paused`.
+fn describe(response: &CommandResponse) -> String {
+    let message: Value = serde_json::from_str(&response.to_json_msg()).unwrap_or_default();
+    message
+        .get("error")
+        .or_else(|| message.get("message"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
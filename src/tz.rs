@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Offset, Utc};
+use chrono_tz::{OffsetName, Tz, IANA_TZDB_VERSION, TZ_VARIANTS};
+use serde::{Deserialize, Serialize};
+
+/// The directory the system timezone database is read from when `TZDIR` is not set.
+const SYSTEM_TZDIR: &str = "/usr/share/zoneinfo";
+
+/// `ZoneInfo` is a structure that represents a timezone of the timezone database.
+///
+/// # Fields
+///
+/// * `zone` - A string that represents the IANA name of the timezone, e.g. `Europe/Paris`.
+/// * `offset` - A string that represents the current offset of the timezone from UTC, formatted as `+HH:MM`.
+/// * `abbreviation` - A string that represents the current abbreviation of the timezone, e.g. `CEST`.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ZoneInfo {
+    pub zone: String,
+    pub offset: String,
+    pub abbreviation: String,
+}
+
+/// `ZoneCheck` is a structure that represents a timezone used by the schedules of jobs.
+///
+/// # Fields
+///
+/// * `zone` - A string that represents the name of the timezone.
+/// * `known` - A `bool` that represents whether the timezone is part of the timezone database of the service.
+/// * `jobs` - A vector of strings that represent the ids of the jobs scheduled in the timezone.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ZoneCheck {
+    pub zone: String,
+    pub known: bool,
+    pub jobs: Vec<String>,
+}
+
+/// `TzReport` is a structure that represents the state of the timezone database the schedules of jobs rely on.
+///
+/// # Fields
+///
+/// * `tzdb_version` - A string that represents the version of the IANA timezone database embedded in the service, e.g. `2025b`.
+/// * `system_version` - An `Option<String>` that represents the version of the timezone database of the system, if it can be found.
+/// * `outdated` - A `bool` that represents whether the system timezone database is newer than the embedded one, so DST rule changes
+///   it carries are not applied to the schedules until the service is rebuilt, as the system database is only read for its version.
+/// * `zones` - A vector of `ZoneCheck` instances that represent the timezones used by the schedules of jobs, sorted by name.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct TzReport {
+    pub tzdb_version: String,
+    pub system_version: Option<String>,
+    pub outdated: bool,
+    pub zones: Vec<ZoneCheck>,
+}
+
+/// Parses the name of a timezone.
+///
+/// # Arguments
+///
+/// * `zone` - A string that represents the IANA name of the timezone, e.g. `Europe/Paris`.
+///
+/// # Returns
+///
+/// * `Result<Tz, String>` - Returns the timezone, or an error message if the timezone is not part of the embedded timezone database.
+pub fn parse_timezone(zone: &str) -> Result<Tz, String> {
+    zone.parse()
+        .map_err(|_| format!("Unknown timezone `{zone}`, see `cronus tz list`"))
+}
+
+/// Lists the timezones of the embedded timezone database.
+///
+/// # Arguments
+///
+/// * `now` - A `DateTime<Utc>` that represents the time the offsets of the timezones are given for.
+///
+/// # Returns
+///
+/// * `Vec<ZoneInfo>` - Returns every timezone, sorted by name.
+pub fn list_zones(now: DateTime<Utc>) -> Vec<ZoneInfo> {
+    TZ_VARIANTS
+        .iter()
+        .map(|tz| {
+            let offset = *now.with_timezone(tz).offset();
+            ZoneInfo {
+                zone: tz.name().to_string(),
+                offset: offset.fix().to_string(),
+                abbreviation: offset.abbreviation().unwrap_or_default().to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Reads the version of the timezone database of the system.
+///
+/// The version is read from the `tzdata.zi` file of the directory named by `TZDIR`, or of `/usr/share/zoneinfo`.
+///
+/// # Returns
+///
+/// * `Option<String>` - Returns the version, e.g. `2025b`, or `None` if the system has no readable timezone database.
+pub fn system_tzdb_version() -> Option<String> {
+    let dir = std::env::var_os("TZDIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(SYSTEM_TZDIR));
+    let content = std::fs::read_to_string(dir.join("tzdata.zi")).ok()?;
+    content
+        .lines()
+        .next()?
+        .strip_prefix("# version ")
+        .map(|version| version.trim().to_string())
+}
+
+/// Checks the timezones used by the schedules of jobs against the timezone database.
+///
+/// # Arguments
+///
+/// * `scheduled` - A slice of pairs of the id of a job and the name of the timezone it is scheduled in.
+/// * `system_version` - An `Option<String>` that represents the version of the timezone database of the system, if any.
+///
+/// # Returns
+///
+/// * `TzReport` - Returns the report of the timezone database and of the timezones used.
+pub fn check_timezones(scheduled: &[(String, String)], system_version: Option<String>) -> TzReport {
+    let mut zones: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (job, zone) in scheduled {
+        zones.entry(zone).or_default().push(job.clone());
+    }
+    // IANA versions are a year followed by a letter, so they sort chronologically as strings.
+    let outdated = system_version
+        .as_deref()
+        .is_some_and(|version| version > IANA_TZDB_VERSION);
+    TzReport {
+        tzdb_version: IANA_TZDB_VERSION.to_string(),
+        system_version,
+        outdated,
+        zones: zones
+            .into_iter()
+            .map(|(zone, jobs)| ZoneCheck {
+                zone: zone.to_string(),
+                known: parse_timezone(zone).is_ok(),
+                jobs,
+            })
+            .collect(),
+    }
+}
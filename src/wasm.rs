@@ -0,0 +1,243 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use wasmtime::{
+    Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+    UpdateDeadline, Val, ValType,
+};
+
+use crate::job::{JobIdentity, KillSwitch, Tick, RUN_KILLED};
+use crate::limits::ResourceLimits;
+use crate::output::RunOutput;
+use crate::template;
+
+/// How often a running WebAssembly function checks whether its run has been killed.
+const EPOCH_INTERVAL: Duration = Duration::from_millis(20);
+
+/// `WasmRun` is a structure that represents the data of the store a WebAssembly function runs in.
+///
+/// # Fields
+///
+/// * `limits` - A `StoreLimits` that represents the memory the module may use.
+/// * `output` - A `RunOutput` that represents the output of the run, which the module writes its lines to.
+struct WasmRun {
+    limits: StoreLimits,
+    output: RunOutput,
+}
+
+/// Runs an exported function of a WebAssembly module for a run of a job.
+///
+/// The module is compiled and instantiated for every run, so a new version of the module is picked up by the next run. It can import
+/// `log(ptr, len)` from the `cronus` module, which writes the UTF-8 text found at `ptr` in its exported `memory` to the output of the
+/// run as a line. The arguments are templates rendered for the tick of the run, see `template::render`, then parsed according to the
+/// types of the parameters of the function, which may be `i32`, `i64`, `f32` or `f64`. The values the function returns become the result
+/// of the run, as a number, or an array if there are several of them.
+///
+/// The module may use the memory its sandbox allows, see `Sandbox::memory_limit`, and is trapped once the kill switch of the run is
+/// flipped, or once it has consumed its maximum number of operations as fuel.
+///
+/// # Arguments
+///
+/// * `module_path` - A reference to the path of the module, either a binary `.wasm` or a text `.wat` file.
+/// * `func` - A string that represents the name of the exported function.
+/// * `args` - A slice of strings that represent the argument templates of the function.
+/// * `tick` - A `Tick` that represents the tick of the run.
+/// * `identity` - A reference to the `JobIdentity` of the job the run is for.
+/// * `kill_switch` - A reference to the `KillSwitch` of the run.
+/// * `output` - A reference to the `RunOutput` of the run.
+/// * `limits` - A `ResourceLimits` that represents the limits applied to the module.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the function returned, or an error message describing why it failed.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    module_path: &Path,
+    func: &str,
+    args: &[String],
+    tick: Tick,
+    identity: &JobIdentity,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+    limits: ResourceLimits,
+) -> Result<(), String> {
+    let mut config = Config::new();
+    config
+        .consume_fuel(limits.max_operations.is_some())
+        .epoch_interruption(true);
+    let engine =
+        Engine::new(&config).map_err(|e| format!("Cannot create the WebAssembly engine: {e}"))?;
+    let module = Module::from_file(&engine, module_path).map_err(|e| {
+        format!(
+            "Cannot load the WebAssembly module {}: {e}",
+            module_path.display()
+        )
+    })?;
+    let mut store_limits = StoreLimitsBuilder::new();
+    if let Some(memory_limit) = limits.sandbox.memory_limit() {
+        store_limits = store_limits.memory_size(memory_limit);
+    }
+    let mut store = Store::new(
+        &engine,
+        WasmRun {
+            limits: store_limits.build(),
+            output: output.clone(),
+        },
+    );
+    store.limiter(|run| &mut run.limits);
+    if let Some(max_operations) = limits.max_operations {
+        store
+            .set_fuel(max_operations.get())
+            .map_err(|e| format!("Cannot limit the operations of the module: {e}"))?;
+    }
+    let epoch_kill_switch = kill_switch.clone();
+    store.epoch_deadline_callback(move |_| {
+        if epoch_kill_switch.is_killed() {
+            Err(wasmtime::Error::msg(RUN_KILLED))
+        } else {
+            Ok(UpdateDeadline::Continue(1))
+        }
+    });
+    let mut linker = Linker::new(&engine);
+    linker
+        .func_wrap("cronus", "log", log)
+        .map_err(|e| format!("Cannot link the WebAssembly module: {e}"))?;
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(|e| describe_error(&e, kill_switch))?;
+    let function = instance
+        .get_func(&mut store, func)
+        .ok_or_else(|| format!("The WebAssembly module does not export a function `{func}`"))?;
+    let ty = function.ty(&store);
+    if ty.params().len() != args.len() {
+        return Err(format!(
+            "Function `{func}` takes {} arguments, {} given",
+            ty.params().len(),
+            args.len()
+        ));
+    }
+    let params = ty
+        .params()
+        .zip(args)
+        .map(|(ty, arg)| parse_arg(&ty, &template::render(arg, tick, identity)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut results = vec![Val::I32(0); ty.results().len()];
+    let done = AtomicBool::new(false);
+    let called = thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::SeqCst) {
+                thread::sleep(EPOCH_INTERVAL);
+                engine.increment_epoch();
+            }
+        });
+        let called = function.call(&mut store, &params, &mut results);
+        done.store(true, Ordering::SeqCst);
+        called
+    });
+    called.map_err(|e| describe_error(&e, kill_switch))?;
+    let mut values: Vec<_> = results.iter().map(result_to_json).collect();
+    match values.len() {
+        0 => {}
+        1 => output.set_result(values.remove(0)),
+        _ => output.set_result(values.into()),
+    }
+    Ok(())
+}
+
+/// Writes a line to the output of a run, on behalf of the `cronus.log` import of a WebAssembly module.
+///
+/// # Arguments
+///
+/// * `caller` - A `Caller` that represents the instance of the module calling the import.
+/// * `ptr` - An `i32` that represents the offset of the text in the exported `memory` of the module.
+/// * `len` - An `i32` that represents the length of the text, in bytes.
+///
+/// # Returns
+///
+/// * `wasmtime::Result<()>` - Returns `Ok(())` if the line was written, or an error trapping the module if the text lies outside of its
+///   memory.
+fn log(mut caller: Caller<'_, WasmRun>, ptr: i32, len: i32) -> wasmtime::Result<()> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| wasmtime::Error::msg("The module must export its memory to log"))?;
+    let start = ptr as u32 as usize;
+    let text = memory
+        .data(&caller)
+        .get(start..start.saturating_add(len as u32 as usize))
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+        .ok_or_else(|| wasmtime::Error::msg("The text to log lies outside of the memory"))?;
+    caller.data().output.push(text);
+    Ok(())
+}
+
+/// Parses an argument of a WebAssembly function.
+///
+/// # Arguments
+///
+/// * `ty` - A reference to the `ValType` of the parameter.
+/// * `arg` - A string that represents the rendered argument.
+///
+/// # Returns
+///
+/// * `Result<Val, String>` - Returns the value of the argument, or an error message if it cannot be parsed as the type of the parameter.
+fn parse_arg(ty: &ValType, arg: &str) -> Result<Val, String> {
+    let parsed = match ty {
+        ValType::I32 => arg.parse().ok().map(Val::I32),
+        ValType::I64 => arg.parse().ok().map(Val::I64),
+        ValType::F32 => arg
+            .parse::<f32>()
+            .ok()
+            .map(|value| Val::F32(value.to_bits())),
+        ValType::F64 => arg
+            .parse::<f64>()
+            .ok()
+            .map(|value| Val::F64(value.to_bits())),
+        _ => {
+            return Err(format!(
+                "Parameters of type {ty} cannot be passed to WebAssembly functions"
+            ))
+        }
+    };
+    parsed.ok_or_else(|| format!("Invalid argument `{arg}`, expected a value of type {ty}"))
+}
+
+/// Converts a value returned by a WebAssembly function into JSON.
+///
+/// # Arguments
+///
+/// * `value` - A reference to the `Val` returned.
+///
+/// # Returns
+///
+/// * `serde_json::Value` - Returns the value as a JSON number, or `null` if it is not a number.
+fn result_to_json(value: &Val) -> serde_json::Value {
+    match value {
+        Val::I32(number) => (*number).into(),
+        Val::I64(number) => (*number).into(),
+        Val::F32(bits) => f32::from_bits(*bits).into(),
+        Val::F64(bits) => f64::from_bits(*bits).into(),
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// Describes why a WebAssembly module failed.
+///
+/// # Arguments
+///
+/// * `e` - A reference to the error the module failed with.
+/// * `kill_switch` - A reference to the `KillSwitch` of the run.
+///
+/// # Returns
+///
+/// * `String` - Returns the error message of the run.
+fn describe_error(e: &wasmtime::Error, kill_switch: &KillSwitch) -> String {
+    match e.downcast_ref::<Trap>() {
+        _ if kill_switch.is_killed() => RUN_KILLED.to_string(),
+        Some(Trap::OutOfFuel) => "Module exceeded its maximum number of operations".to_string(),
+        Some(trap) => trap.to_string(),
+        None => e.to_string(),
+    }
+}
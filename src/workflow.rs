@@ -0,0 +1,349 @@
+use std::collections::{HashMap, HashSet};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::{Condvar, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::credentials::RunAs;
+use crate::job::{Job, JobIdentity, JobOptions, KillSwitch, Tick, Trigger, RUN_KILLED};
+use crate::limits::ResourceLimits;
+use crate::manifest::JobSpec;
+use crate::output::RunOutput;
+use crate::CronusResult;
+
+/// `WorkflowStep` is a structure that represents a single step of a workflow.
+///
+/// # Fields
+///
+/// * `name` - A string that represents the name of the step, unique within the workflow.
+/// * `after` - A vector of strings that represent the names of the steps that must succeed before this step runs. Steps without dependencies start right away.
+/// * `job` - A `Job` that represents what the step runs.
+/// * `retries` - A `u32` that represents how many times a failed step is retried.
+/// * `retry_delay` - An `Option<Duration>` that represents how long to wait before retrying a failed step. It is `None` if the step is retried right away.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct WorkflowStep {
+    pub name: String,
+    #[serde(default)]
+    pub after: Vec<String>,
+    pub job: Job,
+    #[serde(default)]
+    pub retries: u32,
+    #[serde(default, with = "humantime_serde")]
+    pub retry_delay: Option<Duration>,
+}
+
+/// `Workflow` is a structure that represents a workflow as declared in a workflow file.
+///
+/// # Fields
+///
+/// * `name` - A string that represents the name of the workflow. It is the stable key `apply` manages the workflow by.
+/// * `trigger` - A `Trigger` that represents what makes the workflow run, declared as a `cron`, an `after` or an `event` key.
+/// * `options` - A `JobOptions` that represents the execution settings of the workflow.
+/// * `steps` - A vector of `WorkflowStep` instances that represent the steps of the workflow.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct Workflow {
+    pub name: String,
+    #[serde(flatten)]
+    pub trigger: Trigger,
+    #[serde(default)]
+    pub options: JobOptions,
+    pub steps: Vec<WorkflowStep>,
+}
+
+impl Workflow {
+    /// Converts the workflow into the job spec it is applied as.
+    ///
+    /// # Returns
+    ///
+    /// * `JobSpec` - Returns a job spec keyed by the name of the workflow, running the steps as a `Job::Workflow`.
+    pub fn into_spec(self) -> JobSpec {
+        JobSpec {
+            key: Some(self.name),
+            trigger: self.trigger,
+            job: Job::Workflow(self.steps),
+            options: self.options,
+        }
+    }
+}
+
+/// `StepStatus` is an enumeration that represents the outcome of a step of a workflow run.
+///
+/// # Variants
+///
+/// * `Succeeded` - Represents a step that finished successfully, possibly after retries.
+/// * `Failed` - Represents a step that failed on every attempt.
+/// * `Skipped` - Represents a step that did not run because one of its dependencies did not succeed.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+/// `StepOutcome` is a structure that represents the outcome of a step of a workflow run.
+///
+/// # Fields
+///
+/// * `step` - A string that represents the name of the step.
+/// * `status` - A `StepStatus` that represents how the step ended.
+/// * `attempts` - A `u32` that represents how many times the step was run.
+/// * `error` - An `Option<String>` that represents why the step did not succeed. It is `None` if the step succeeded.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StepOutcome {
+    pub step: String,
+    pub status: StepStatus,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// Loads the workflow declared in a YAML workflow file.
+///
+/// # Arguments
+///
+/// * `path` - A path to the workflow file.
+///
+/// # Returns
+///
+/// * `CronusResult<Workflow>` - Returns a `CronusResult` that contains the declared workflow on success, or an error if the file cannot be read or the steps do not form a valid workflow.
+pub fn load_workflow(path: &Path) -> CronusResult<Workflow> {
+    let content = std::fs::read(path)?;
+    let workflow: Workflow = serde_yaml::from_slice(&content)?;
+    validate_steps(&workflow.steps)?;
+    Ok(workflow)
+}
+
+/// Checks that steps form a valid workflow.
+///
+/// Step names must be unique, dependencies must name steps of the workflow, and the dependencies must not form a cycle.
+///
+/// # Arguments
+///
+/// * `steps` - A slice of `WorkflowStep` instances that represent the steps of the workflow.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if the steps are valid, or an error message describing the problem.
+pub fn validate_steps(steps: &[WorkflowStep]) -> Result<(), String> {
+    if steps.is_empty() {
+        return Err("Workflow has no steps".to_string());
+    }
+    let mut names = HashSet::new();
+    for step in steps {
+        if !names.insert(step.name.as_str()) {
+            return Err(format!("Step `{}` is declared more than once", step.name));
+        }
+    }
+    for step in steps {
+        if let Some(dependency) = step
+            .after
+            .iter()
+            .find(|name| !names.contains(name.as_str()))
+        {
+            return Err(format!(
+                "Step `{}` runs after unknown step `{dependency}`",
+                step.name
+            ));
+        }
+    }
+    let mut resolved = HashSet::new();
+    while resolved.len() < steps.len() {
+        let ready: Vec<_> = steps
+            .iter()
+            .filter(|step| !resolved.contains(step.name.as_str()))
+            .filter(|step| {
+                step.after
+                    .iter()
+                    .all(|name| resolved.contains(name.as_str()))
+            })
+            .map(|step| step.name.as_str())
+            .collect();
+        if ready.is_empty() {
+            let cycle: Vec<_> = steps
+                .iter()
+                .map(|step| step.name.as_str())
+                .filter(|name| !resolved.contains(name))
+                .collect();
+            return Err(format!(
+                "Steps {} depend on each other in a cycle",
+                cycle.join(", ")
+            ));
+        }
+        resolved.extend(ready);
+    }
+    Ok(())
+}
+
+/// Runs the steps of a workflow.
+///
+/// Every step runs on its own thread as soon as all of its dependencies have succeeded, so independent steps run concurrently and a step
+/// depending on several steps waits for all of them. A failed step is retried up to its number of retries, and the steps depending on
+/// a step that did not succeed are skipped. Once the run is killed, the running steps are aborted and the steps that have not
+/// started yet are skipped. The steps must have been validated with `validate_steps`.
+///
+/// # Arguments
+///
+/// * `steps` - A slice of `WorkflowStep` instances that represent the steps of the workflow.
+/// * `tick` - A `Tick` that represents the tick the workflow runs for, passed on to every step.
+/// * `identity` - A reference to the `JobIdentity` of the workflow job, passed on to every step.
+/// * `limits` - A `ResourceLimits` that represents the limits applied to the processes spawned by the steps.
+/// * `run_as` - A reference to the `RunAs` that represents the user and group the processes spawned by the steps run as.
+/// * `kill_switch` - A reference to the `KillSwitch` of the workflow run.
+/// * `output` - A reference to the `RunOutput` of the workflow run, which the steps write their lines to.
+///
+/// # Returns
+///
+/// * `Vec<StepOutcome>` - Returns the outcome of every step, in declaration order.
+pub fn run_steps(
+    steps: &[WorkflowStep],
+    tick: Tick,
+    identity: &JobIdentity,
+    limits: ResourceLimits,
+    run_as: &RunAs,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+) -> Vec<StepOutcome> {
+    let outcomes: Mutex<HashMap<String, StepOutcome>> = Mutex::new(HashMap::new());
+    let finished = Condvar::new();
+    thread::scope(|scope| {
+        for step in steps {
+            let outcomes = &outcomes;
+            let finished = &finished;
+            scope.spawn(move || {
+                let blocked_by = {
+                    let mut done = outcomes.lock().unwrap_or_else(PoisonError::into_inner);
+                    while !step.after.iter().all(|name| done.contains_key(name)) {
+                        done = finished.wait(done).unwrap_or_else(PoisonError::into_inner);
+                    }
+                    step.after
+                        .iter()
+                        .find(|name| done[name.as_str()].status != StepStatus::Succeeded)
+                        .cloned()
+                };
+                let outcome = match blocked_by {
+                    Some(name) => StepOutcome {
+                        step: step.name.clone(),
+                        status: StepStatus::Skipped,
+                        attempts: 0,
+                        error: Some(format!("Step `{name}` did not succeed")),
+                    },
+                    None if kill_switch.is_killed() => StepOutcome {
+                        step: step.name.clone(),
+                        status: StepStatus::Skipped,
+                        attempts: 0,
+                        error: Some(RUN_KILLED.to_string()),
+                    },
+                    None => run_step(step, tick, identity, limits, run_as, kill_switch, output),
+                };
+                outcomes
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(step.name.clone(), outcome);
+                finished.notify_all();
+            });
+        }
+    });
+    let mut outcomes = outcomes
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner);
+    steps
+        .iter()
+        .filter_map(|step| outcomes.remove(&step.name))
+        .collect()
+}
+
+/// Runs a single step of a workflow, retrying it while it fails and the workflow run has not been killed.
+///
+/// # Arguments
+///
+/// * `step` - A reference to the `WorkflowStep` to be run.
+/// * `tick` - A `Tick` that represents the tick the workflow runs for.
+/// * `identity` - A reference to the `JobIdentity` of the workflow job.
+/// * `limits` - A `ResourceLimits` that represents the limits applied to the processes spawned by the step.
+/// * `run_as` - A reference to the `RunAs` that represents the user and group the processes spawned by the step run as.
+/// * `kill_switch` - A reference to the `KillSwitch` of the workflow run.
+/// * `output` - A reference to the `RunOutput` of the workflow run.
+///
+/// # Returns
+///
+/// * `StepOutcome` - Returns the outcome of the last attempt.
+fn run_step(
+    step: &WorkflowStep,
+    tick: Tick,
+    identity: &JobIdentity,
+    limits: ResourceLimits,
+    run_as: &RunAs,
+    kill_switch: &KillSwitch,
+    output: &RunOutput,
+) -> StepOutcome {
+    let business = step
+        .job
+        .clone()
+        .to_business(identity.clone(), limits, run_as.clone());
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = catch_unwind(AssertUnwindSafe(|| business(tick, kill_switch, output)))
+            .unwrap_or_else(|_| Err("Step panicked".to_string()));
+        match result {
+            Ok(()) => {
+                return StepOutcome {
+                    step: step.name.clone(),
+                    status: StepStatus::Succeeded,
+                    attempts,
+                    error: None,
+                }
+            }
+            Err(message) if attempts > step.retries || kill_switch.is_killed() => {
+                return StepOutcome {
+                    step: step.name.clone(),
+                    status: StepStatus::Failed,
+                    attempts,
+                    error: Some(message),
+                }
+            }
+            Err(_) => {
+                if let Some(delay) = step.retry_delay {
+                    thread::sleep(delay);
+                }
+            }
+        }
+    }
+}
+
+/// Summarizes the outcomes of a workflow or a pipeline run into the aggregate status of the run.
+///
+/// # Arguments
+///
+/// * `outcomes` - A slice of `StepOutcome` instances that represent the outcomes of the steps.
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Returns `Ok(())` if every step succeeded, or an error message listing the steps that failed or were skipped.
+pub fn summarize(outcomes: &[StepOutcome]) -> Result<(), String> {
+    let unsuccessful: Vec<_> = outcomes
+        .iter()
+        .filter(|outcome| outcome.status != StepStatus::Succeeded)
+        .map(|outcome| match outcome.status {
+            StepStatus::Skipped => format!("{} (skipped)", outcome.step),
+            _ => format!(
+                "{} ({} after {} attempts)",
+                outcome.step,
+                outcome.error.as_deref().unwrap_or_default(),
+                outcome.attempts
+            ),
+        })
+        .collect();
+    if unsuccessful.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} of {} steps did not succeed: {}",
+        unsuccessful.len(),
+        outcomes.len(),
+        unsuccessful.join(", ")
+    ))
+}